@@ -0,0 +1,445 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use clap::Parser;
+use comfy_table::{presets::UTF8_FULL, Cell, ContentArrangement, Table};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::netns::{self, RunTable};
+
+/// Address the agent listens on by default; other hosts need --listen and --token
+pub const DEFAULT_AGENT_LISTEN: &str = "127.0.0.1:5380";
+/// Port used when an --agent address leaves it out
+const DEFAULT_AGENT_PORT: u16 = 5380;
+/// First line of every job request
+const JOB_MAGIC: &str = "rust-dns-benchmark-job 1";
+/// Largest job request an agent reads
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+/// Time a coordinator gets to send its whole request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+/// Time to connect to an agent
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// Longest a job may run before the agent stops it and moves on
+const JOB_TIMEOUT: Duration = Duration::from_secs(4 * 3600);
+/// Column prepended to merged CSV rows
+const VANTAGE_COLUMN: &str = "vantage";
+
+/// Options an agent refuses: they read or write files on the agent host,
+/// start another batch, or pick the host's network path or credentials.
+/// The agent writes its own CSV export.
+const REFUSED_OPTIONS: [&str; 17] = [
+	"--config", "--resolver-file", "--output", "--prometheus", "--influx", "--policy", "--raw-output",
+	"--checkpoint", "--save-resolvers", "--asn-db", "--warm-source tranco:N:FILE", "--netns", "--agent",
+	"--proxy", "--interface", "--source-ip", "--ripe-atlas-key",
+];
+
+//============================================
+/// Remote agent a coordinator sends the benchmark to (--agent).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentTarget {
+	/// Vantage point name for reports, e.g. home or vps
+	pub name: String,
+	/// host:port the agent listens on
+	pub addr: String,
+}
+
+/// Parse an --agent value: NAME=HOST[:PORT] or HOST[:PORT] (port 5380 by
+/// default; the name defaults to the address).
+pub fn parse_agent(value: &str) -> Result<AgentTarget, String> {
+	let (name, addr) = match value.split_once('=') {
+		Some((name, addr)) if !name.is_empty() => (Some(name), addr),
+		Some(_) => return Err(format!("agent name is empty in '{}'", value)),
+		None => (None, value),
+	};
+	if addr.is_empty() {
+		return Err(format!("expected NAME=HOST[:PORT] or HOST[:PORT], got '{}'", value));
+	}
+	// A bare IPv6 address or a name without a port gets the default port
+	let has_port = match addr.rsplit_once(':') {
+		Some((host, port)) => port.parse::<u16>().is_ok() && (!host.contains(':') || host.ends_with(']')),
+		None => false,
+	};
+	let addr = match (has_port, addr.contains(':') && !addr.starts_with('[')) {
+		(true, _) => addr.to_string(),
+		(false, true) => format!("[{}]:{}", addr, DEFAULT_AGENT_PORT),
+		(false, false) => format!("{}:{}", addr, DEFAULT_AGENT_PORT),
+	};
+	Ok(AgentTarget { name: name.unwrap_or(&addr).to_string(), addr })
+}
+
+//============================================
+/// Benchmark job a coordinator sends to an agent: a version line, an
+/// optional token, and the benchmark arguments one per line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct JobRequest {
+	version: String,
+	token: Option<String>,
+	args: Vec<String>,
+}
+
+impl JobRequest {
+	/// Request text, ending with a blank line.
+	fn encode(&self) -> String {
+		let mut text = format!("{}\nversion {}\n", JOB_MAGIC, self.version);
+		if let Some(token) = &self.token {
+			text += &format!("token {}\n", token);
+		}
+		for arg in &self.args {
+			text += &format!("arg {}\n", arg);
+		}
+		text + "\n"
+	}
+
+	/// Parse request lines (without the blank terminator line).
+	fn parse(lines: &[String]) -> Result<JobRequest> {
+		if lines.first().map(String::as_str) != Some(JOB_MAGIC) {
+			bail!("not a benchmark job request");
+		}
+		let mut request = JobRequest { version: String::new(), token: None, args: Vec::new() };
+		for line in &lines[1..] {
+			match line.split_once(' ') {
+				Some(("version", v)) => request.version = v.to_string(),
+				Some(("token", t)) => request.token = Some(t.to_string()),
+				Some(("arg", a)) => request.args.push(a.to_string()),
+				_ => bail!("unexpected request line '{}'", line),
+			}
+		}
+		Ok(request)
+	}
+}
+
+/// Check that the arguments describe a plain benchmark an agent may run:
+/// they must parse, name no subcommand, and use none of REFUSED_OPTIONS.
+pub fn check_job_args(args: &[String]) -> Result<()> {
+	let cli = crate::cli::Cli::try_parse_from(std::iter::once("rust-dns-benchmark".to_string()).chain(args.iter().cloned()))
+		.map_err(|e| anyhow!("{}", e.to_string().lines().next().unwrap_or_default()))?;
+	if cli.command.is_some() {
+		bail!("agents run benchmarks only, not subcommands");
+	}
+	let warm_file = matches!(&cli.warm_source, Some(crate::domains::WarmSource::Tranco { file: Some(_), .. }));
+	let refused = [
+		cli.config.is_some(), cli.resolver_file.is_some(), cli.output.is_some(), cli.prometheus.is_some(),
+		cli.influx.is_some(), cli.policy.is_some(), cli.raw_output.is_some(), cli.checkpoint.is_some(),
+		cli.save_resolvers.is_some(), cli.asn_db.is_some(), warm_file, !cli.netns.is_empty(), !cli.agents.is_empty(),
+		cli.proxy.is_some(), cli.interface.is_some(), cli.source_ip.is_some(), cli.ripe_atlas_key.is_some(),
+	];
+	if let Some(i) = refused.iter().position(|&r| r) {
+		bail!(
+			"{} cannot be sent to an agent, since it names a file, another batch, or the agent host's network or credentials",
+			REFUSED_OPTIONS[i],
+		);
+	}
+	Ok(())
+}
+
+//============================================
+/// Run as an agent: accept benchmark jobs on `listen` and answer each with
+/// the CSV export of the run. Jobs run one at a time, as a child process
+/// of this binary, so they never compete for the uplink. A non-loopback
+/// `listen` address needs a token, since a job makes this host send queries
+/// wherever its arguments point.
+pub async fn run_agent(listen: &str, token: Option<&str>) -> Result<()> {
+	let listener = TcpListener::bind(listen).await.map_err(|e| anyhow!("agent cannot listen on {}: {}", listen, e))?;
+	let local = listener.local_addr()?;
+	if token.is_none() && !local.ip().is_loopback() {
+		bail!("agent on {} would accept jobs from anyone who can reach it; set --token or listen on a loopback address", local);
+	}
+	crate::info!("Agent listening on {} (version {})", local, env!("CARGO_PKG_VERSION"));
+	loop {
+		let (stream, peer) = listener.accept().await?;
+		if let Err(e) = serve_job(stream, token).await {
+			crate::warn!("job from {}: {}", peer, e);
+		}
+	}
+}
+
+/// Read one job, run it, and send back `ok` and the CSV or `error` and a reason.
+async fn serve_job(stream: TcpStream, token: Option<&str>) -> Result<()> {
+	let peer = stream.peer_addr()?;
+	let (read_half, mut write_half) = stream.into_split();
+	let lines = tokio::time::timeout(REQUEST_TIMEOUT, read_request(read_half)).await
+		.map_err(|_| anyhow!("request not received within {}s", REQUEST_TIMEOUT.as_secs()))??;
+	let reply = match accept_job(&lines, token) {
+		Ok(args) => {
+			crate::info!("=== Job from {}: {} ===\n", peer, args.join(" "));
+			match run_job(&args).await {
+				Ok(csv) => format!("ok\n{}", csv),
+				Err(e) => format!("error {}\n", e),
+			}
+		}
+		Err(e) => {
+			crate::warn!("refused job from {}: {}", peer, e);
+			format!("error {}\n", e)
+		}
+	};
+	write_half.write_all(reply.as_bytes()).await?;
+	write_half.shutdown().await?;
+	Ok(())
+}
+
+/// Request lines up to the blank terminator line.
+async fn read_request(stream: tokio::net::tcp::OwnedReadHalf) -> Result<Vec<String>> {
+	let mut reader = BufReader::new(stream.take(MAX_REQUEST_BYTES));
+	let mut lines = Vec::new();
+	loop {
+		let mut line = String::new();
+		if reader.read_line(&mut line).await? == 0 {
+			bail!("request ended early");
+		}
+		let line = line.trim_end_matches(['\r', '\n']);
+		if line.is_empty() {
+			return Ok(lines);
+		}
+		lines.push(line.to_string());
+	}
+}
+
+/// Compare a presented token with the agent's in time that depends only on
+/// their lengths, so response timing does not reveal a matching prefix.
+fn tokens_match(presented: &str, expected: &str) -> bool {
+	let (a, b) = (presented.as_bytes(), expected.as_bytes());
+	let diff = a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y));
+	a.len() == b.len() && diff == 0
+}
+
+/// Validate a request against the agent's version and token; the job's arguments.
+fn accept_job(lines: &[String], token: Option<&str>) -> Result<Vec<String>> {
+	let request = JobRequest::parse(lines)?;
+	if let Some(token) = token {
+		if !tokens_match(request.token.as_deref().unwrap_or_default(), token) {
+			bail!("wrong or missing token");
+		}
+	}
+	if request.version != env!("CARGO_PKG_VERSION") {
+		bail!("agent runs version {}, coordinator {}", env!("CARGO_PKG_VERSION"), request.version);
+	}
+	check_job_args(&request.args)?;
+	Ok(request.args)
+}
+
+/// Run the benchmark as a child process and return its CSV export.
+///
+/// The export goes to a private temporary directory, removed afterwards; a
+/// job still running after JOB_TIMEOUT is killed.
+async fn run_job(args: &[String]) -> Result<String> {
+	let exe = std::env::current_exe()?;
+	let dir = tempfile::tempdir()?;
+	let csv_path = dir.path().join("results.csv");
+	let mut child = tokio::process::Command::new(&exe)
+		.args(args)
+		.arg("-o")
+		.arg(&csv_path)
+		.kill_on_drop(true)
+		.spawn()
+		.map_err(|e| anyhow!("failed to start the benchmark: {}", e))?;
+	let status = tokio::time::timeout(JOB_TIMEOUT, child.wait()).await
+		.map_err(|_| anyhow!("benchmark did not finish within {}s", JOB_TIMEOUT.as_secs()))??;
+	match std::fs::read_to_string(&csv_path) {
+		Ok(text) if status.success() => Ok(text),
+		_ => bail!("benchmark exited with {}", status),
+	}
+}
+
+//============================================
+/// Send a job to one agent and wait for its CSV export.
+async fn request_job(agent: &AgentTarget, request: &JobRequest) -> Result<String> {
+	let mut stream = tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&agent.addr)).await
+		.map_err(|_| anyhow!("no connection within {}s", CONNECT_TIMEOUT.as_secs()))??;
+	stream.write_all(request.encode().as_bytes()).await?;
+	// The run takes as long as the benchmark does; the agent closes when done
+	let mut reply = String::new();
+	stream.read_to_string(&mut reply).await?;
+	match reply.split_once('\n') {
+		Some(("ok", csv)) => Ok(csv.to_string()),
+		Some((status, _)) if status.starts_with("error ") => bail!("{}", &status["error ".len()..]),
+		_ => bail!("agent closed the connection without a result"),
+	}
+}
+
+/// Resolver identity across vantage exports: address, port, and transport
+type VantageKey = (String, String, String);
+
+/// Per-resolver score at each vantage point, fastest resolvers first.
+fn vantage_table(tables: &[RunTable]) -> Table {
+	// (ip, port, transport) -> (label, score per table)
+	let mut scores: HashMap<VantageKey, (String, Vec<Option<f64>>)> = HashMap::new();
+	let mut order = Vec::new();
+	for (t, RunTable { header, rows, .. }) in tables.iter().enumerate() {
+		let col = |name: &str| header.iter().position(|h| h == name);
+		let (Some(label_idx), Some(ip_idx), Some(score_idx)) = (col("resolver"), col("ip_address"), col("overall_score")) else { continue };
+		let field = |row: &Vec<String>, name: &str| col(name).map(|i| row[i].clone()).unwrap_or_default();
+		for row in rows {
+			let key = (row[ip_idx].clone(), field(row, "port"), field(row, "transport"));
+			let entry = scores.entry(key.clone()).or_insert_with(|| {
+				order.push(key);
+				(row[label_idx].clone(), vec![None; tables.len()])
+			});
+			entry.1[t] = row[score_idx].parse::<f64>().ok().filter(|s| s.is_finite());
+		}
+	}
+	let best = |key: &VantageKey| scores[key].1.iter().flatten().copied().fold(f64::INFINITY, f64::min);
+	order.sort_by(|a, b| best(a).partial_cmp(&best(b)).unwrap_or(std::cmp::Ordering::Equal));
+
+	let mut table = Table::new();
+	table.load_preset(UTF8_FULL);
+	table.set_content_arrangement(ContentArrangement::Dynamic);
+	let mut header = vec!["Resolver".to_string(), "IP Address".to_string(), "Transport".to_string()];
+	header.extend(tables.iter().map(|t| format!("{} score", t.label)));
+	header.push("Fastest from".to_string());
+	table.set_header(header);
+	for key in &order {
+		let (label, per_vantage) = &scores[key];
+		let (ip, _, transport) = key;
+		let mut cells = vec![Cell::new(label), Cell::new(ip), Cell::new(transport)];
+		cells.extend(per_vantage.iter().map(|s| Cell::new(s.map(|s| format!("{:.1}", s)).unwrap_or_else(|| "-".to_string()))));
+		let fastest = per_vantage.iter().enumerate()
+			.filter_map(|(i, s)| s.map(|s| (i, s)))
+			.min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal))
+			.map(|(i, _)| tables[i].label.clone())
+			.unwrap_or_else(|| "-".to_string());
+		cells.push(Cell::new(fastest));
+		table.add_row(cells);
+	}
+	table
+}
+
+//============================================
+/// Run the benchmark on every agent at once and merge the results.
+///
+/// Each agent gets this command line minus --agent, --agent-token, and
+/// --output, runs it from its own network, and sends back its CSV export.
+/// The report compares each resolver's score across vantage points; the
+/// merged CSV gets a leading `vantage` column.
+pub async fn run_coordinator(agents: &[AgentTarget], token: Option<&str>, output: Option<&str>) -> Result<()> {
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	let args = netns::strip_options(&args, &["--agent", "--agent-token", "-o", "--output"]);
+	check_job_args(&args)?;
+	if let Some(arg) = args.iter().find(|a| a.contains('\n')) {
+		bail!("argument {:?} contains a newline and cannot be sent to agents", arg);
+	}
+	let request = JobRequest { version: env!("CARGO_PKG_VERSION").to_string(), token: token.map(String::from), args };
+
+	crate::info!("Running the benchmark on {} agent(s): {}\n", agents.len(),
+		agents.iter().map(|a| format!("{} ({})", a.name, a.addr)).collect::<Vec<_>>().join(", "));
+	let jobs: Vec<_> = agents.iter().map(|agent| {
+		let (agent, request) = (agent.clone(), request.clone());
+		tokio::spawn(async move {
+			let result = request_job(&agent, &request).await;
+			match &result {
+				Ok(_) => crate::info!("Agent {} finished", agent.name),
+				Err(e) => crate::warn!("agent {} ({}): {}", agent.name, agent.addr, e),
+			}
+			result
+		})
+	}).collect();
+	let mut tables = Vec::new();
+	for (agent, job) in agents.iter().zip(jobs) {
+		if let Ok(csv) = job.await? {
+			match netns::parse_table(&agent.name, &csv) {
+				Ok(table) => tables.push(table),
+				Err(e) => crate::warn!("agent {}: unreadable results: {}", agent.name, e),
+			}
+		}
+	}
+	if tables.is_empty() {
+		bail!("no agent run completed");
+	}
+
+	println!("\nVantage point comparison");
+	println!("========================");
+	println!("{}", vantage_table(&tables));
+	netns::print_best(&tables);
+
+	if let Some(path) = output {
+		netns::write_merged(path, VANTAGE_COLUMN, &tables)?;
+		crate::info!("\nMerged results written to {}", path);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn strings(items: &[&str]) -> Vec<String> {
+		items.iter().map(|s| s.to_string()).collect()
+	}
+
+	#[test]
+	fn test_parse_agent() {
+		let named = parse_agent("home=192.168.1.5:6000").unwrap();
+		assert_eq!((named.name.as_str(), named.addr.as_str()), ("home", "192.168.1.5:6000"));
+		assert_eq!(parse_agent("vps.example.net").unwrap().addr, "vps.example.net:5380");
+		assert_eq!(parse_agent("vps.example.net").unwrap().name, "vps.example.net:5380");
+		assert_eq!(parse_agent("v6=2001:db8::5").unwrap().addr, "[2001:db8::5]:5380");
+		assert_eq!(parse_agent("[2001:db8::5]:6000").unwrap().addr, "[2001:db8::5]:6000");
+		assert!(parse_agent("=10.0.0.1").is_err());
+		assert!(parse_agent("office=").is_err());
+	}
+
+	#[test]
+	fn test_job_request_round_trip_and_checks() {
+		let request = JobRequest {
+			version: env!("CARGO_PKG_VERSION").to_string(),
+			token: Some("s3cret".to_string()),
+			args: strings(&["-r", "1.1.1.1", "--rounds", "2"]),
+		};
+		let text = request.encode();
+		let lines: Vec<String> = text.lines().take_while(|l| !l.is_empty()).map(String::from).collect();
+		assert_eq!(JobRequest::parse(&lines).unwrap(), request);
+		assert_eq!(accept_job(&lines, Some("s3cret")).unwrap(), request.args);
+		assert!(accept_job(&lines, Some("other")).is_err());
+		assert!(accept_job(&lines[..1], None).is_err());
+		assert!(tokens_match("s3cret", "s3cret"));
+		assert!(!tokens_match("s3cre", "s3cret"));
+		assert!(!tokens_match("", "s3cret"));
+
+		// Options naming files on the agent host are refused
+		let err = check_job_args(&strings(&["--prometheus", "/etc/passwd"])).unwrap_err();
+		assert!(err.to_string().starts_with("--prometheus"));
+		assert!(check_job_args(&strings(&["--warm-source", "tranco:100:/tmp/top.csv"])).is_err());
+		assert!(check_job_args(&strings(&["analyze", "/tmp"])).is_err());
+		// So are options that pick the agent's network path or credentials
+		for option in [["--proxy", "socks5://10.0.0.1:1080"], ["--source-ip", "10.0.0.2"], ["--ripe-atlas-key", "k"]] {
+			let err = check_job_args(&strings(&option)).unwrap_err();
+			assert!(err.to_string().starts_with(option[0]));
+		}
+		assert!(check_job_args(&strings(&["--warm-source", "tranco:100", "-l", "medium"])).is_ok());
+	}
+
+	#[test]
+	fn test_vantage_table_keeps_transports_apart() {
+		let csv = "resolver,ip_address,port,transport,overall_score\n\
+			Cloudflare,1.1.1.1,53,UDP,9.5\n\
+			Cloudflare,1.1.1.1,853,DoT,30.0\n";
+		let tables = vec![netns::parse_table("home", csv).unwrap(), netns::parse_table("vps", csv).unwrap()];
+		let text = vantage_table(&tables).to_string();
+		// One row per transport, fastest first, not one row per address
+		assert_eq!(text.matches("1.1.1.1").count(), 2);
+		let (udp, dot) = (text.find("UDP").unwrap(), text.find("DoT").unwrap());
+		assert!(udp < dot);
+		assert!(text.contains("30.0"));
+	}
+
+	#[tokio::test]
+	async fn test_request_job_reads_result() {
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let agent = AgentTarget { name: "lab".to_string(), addr: listener.local_addr().unwrap().to_string() };
+		tokio::spawn(async move {
+			for reply in ["ok\nresolver,ip_address,overall_score\nCloudflare,1.1.1.1,9.5\n", "error wrong or missing token\n"] {
+				let (stream, _) = listener.accept().await.unwrap();
+				let (read_half, mut write_half) = stream.into_split();
+				read_request(read_half).await.unwrap();
+				write_half.write_all(reply.as_bytes()).await.unwrap();
+			}
+		});
+		let request = JobRequest { version: "1".to_string(), token: None, args: strings(&["-r", "1.1.1.1"]) };
+		let csv = request_job(&agent, &request).await.unwrap();
+		let table = netns::parse_table(&agent.name, &csv).unwrap();
+		assert_eq!(table.rows, vec![strings(&["Cloudflare", "1.1.1.1", "9.5"])]);
+		let err = request_job(&agent, &request).await.unwrap_err();
+		assert_eq!(err.to_string(), "wrong or missing token");
+	}
+}
@@ -0,0 +1,413 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+
+/// Score increase (percent) between consecutive runs that counts as a regression
+const REGRESSION_PCT: f64 = 20.0;
+/// Minimum absolute score increase (ms) for a regression, to ignore noise on fast resolvers
+const REGRESSION_MIN_MS: f64 = 5.0;
+
+/// One resolver row from a historical CSV export.
+#[derive(Debug, Clone)]
+struct ExportRow {
+	label: String,
+	ip: String,
+	/// Resolver port; exports without the port column get the transport's default
+	port: String,
+	transport: String,
+	score: f64,
+	/// Run start from the `timestamp` column of an appended export
+	started_secs: Option<u64>,
+}
+
+/// Address, port, and transport: the same resolver across runs, as `diff` matches rows
+type RowKey<'a> = (&'a str, &'a str, &'a str);
+
+impl ExportRow {
+	fn key(&self) -> RowKey<'_> {
+		(&self.ip, &self.port, &self.transport)
+	}
+}
+
+/// Where a run's start time came from
+const TIME_FROM_MANIFEST: &str = "manifest start time";
+const TIME_FROM_COLUMN: &str = "timestamp column";
+const TIME_FROM_MTIME: &str = "file modification time";
+
+/// One historical benchmark run loaded from a CSV export.
+#[derive(Debug, Clone)]
+struct RunExport {
+	/// Run start in seconds since the Unix epoch: the manifest `started`
+	/// time or `timestamp` column, else the file modification time
+	timestamp_secs: u64,
+	/// Which of those `timestamp_secs` came from (a TIME_FROM_* value)
+	time_source: &'static str,
+	rows: Vec<ExportRow>,
+}
+
+/// Time-of-day bucket labels (UTC), indexed by `time_of_day_bucket`
+const TIME_OF_DAY_LABELS: [&str; 4] = [
+	"night (00-06 UTC)",
+	"morning (06-12 UTC)",
+	"afternoon (12-18 UTC)",
+	"evening (18-24 UTC)",
+];
+
+//============================================
+/// Time-of-day bucket index (UTC, 6-hour blocks) for a Unix timestamp.
+fn time_of_day_bucket(secs: u64) -> usize {
+	((secs % 86400) / 3600 / 6) as usize
+}
+
+//============================================
+/// Parse a CSV export into resolver rows.
+///
+/// Only the resolver, ip_address, and overall_score columns are required,
+/// so exports from older and newer versions both load. Rows with a
+/// non-finite score are skipped. The `timestamp` column of an appended
+/// export, when present, gives each row its run start.
+fn parse_export(csv_text: &str) -> Result<Vec<ExportRow>> {
+	// Skip the run manifest comment lines above the header
+	let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(csv_text.as_bytes());
+	let headers = reader.headers()?.clone();
+	let column = |name: &str| headers.iter().position(|h| h == name)
+		.ok_or_else(|| anyhow!("export is missing the '{}' column", name));
+	let label_idx = column("resolver")?;
+	let ip_idx = column("ip_address")?;
+	let score_idx = column("overall_score")?;
+	let optional = |name: &str| headers.iter().position(|h| h == name);
+	let (port_idx, transport_idx, timestamp_idx) = (optional("port"), optional("transport"), optional("timestamp"));
+
+	let mut rows = Vec::new();
+	for record in reader.records() {
+		let record = match record {
+			Ok(r) => r,
+			Err(_) => continue,
+		};
+		let score: f64 = match record.get(score_idx).and_then(|s| s.parse().ok()) {
+			Some(v) if f64::is_finite(v) => v,
+			_ => continue,
+		};
+		let field = |idx: Option<usize>| idx.and_then(|i| record.get(i)).unwrap_or("");
+		let transport = field(transport_idx);
+		let default_port = match transport {
+			"DoT" => "853",
+			"DoH" => "443",
+			_ => "53",
+		};
+		rows.push(ExportRow {
+			label: record.get(label_idx).unwrap_or("").to_string(),
+			ip: record.get(ip_idx).unwrap_or("").to_string(),
+			port: Some(field(port_idx)).filter(|p| !p.is_empty()).unwrap_or(default_port).to_string(),
+			transport: transport.to_string(),
+			score,
+			started_secs: timestamp_idx
+				.and_then(|i| record.get(i))
+				.and_then(crate::telemetry::parse_timestamp_iso),
+		});
+	}
+	Ok(rows)
+}
+
+/// Run start from the manifest's `# started:` comment line, if the export has one.
+fn manifest_started(csv_text: &str) -> Option<u64> {
+	csv_text.lines()
+		.take_while(|line| line.starts_with('#'))
+		.filter_map(|line| line.trim_start_matches('#').trim().strip_prefix("started:"))
+		.find_map(crate::telemetry::parse_timestamp_iso)
+}
+
+//============================================
+/// Load every CSV export in a directory, sorted oldest first. An appended
+/// export holds one run per `timestamp` value; exports older than the run
+/// manifest fall back to the file modification time.
+fn load_runs(dir: &str) -> Result<Vec<RunExport>> {
+	let entries = std::fs::read_dir(dir)
+		.map_err(|e| anyhow!("results directory '{}' not found: {}", dir, e))?;
+	let mut runs = Vec::new();
+	for entry in entries.flatten() {
+		let path = entry.path();
+		if path.extension().and_then(|e| e.to_str()) != Some("csv") {
+			continue;
+		}
+		let text = match std::fs::read_to_string(&path) {
+			Ok(t) => t,
+			Err(_) => continue,
+		};
+		// Files that are not benchmark exports are skipped quietly
+		let rows = match parse_export(&text) {
+			Ok(rows) if !rows.is_empty() => rows,
+			_ => continue,
+		};
+		let file_started = match manifest_started(&text) {
+			Some(secs) => (secs, TIME_FROM_MANIFEST),
+			None => (file_mtime_secs(&path), TIME_FROM_MTIME),
+		};
+		let mut by_start: BTreeMap<(u64, &'static str), Vec<ExportRow>> = BTreeMap::new();
+		for row in rows {
+			let started = row.started_secs.map(|s| (s, TIME_FROM_COLUMN)).unwrap_or(file_started);
+			by_start.entry(started).or_default().push(row);
+		}
+		runs.extend(by_start.into_iter()
+			.map(|((timestamp_secs, time_source), rows)| RunExport { timestamp_secs, time_source, rows }));
+	}
+	runs.sort_by_key(|r| r.timestamp_secs);
+	Ok(runs)
+}
+
+/// File modification time in seconds since the Unix epoch (0 if unavailable).
+fn file_mtime_secs(path: &Path) -> u64 {
+	std::fs::metadata(path)
+		.and_then(|m| m.modified())
+		.ok()
+		.and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+		.map(|d| d.as_secs())
+		.unwrap_or(0)
+}
+
+//============================================
+/// Best resolver (lowest mean score) for each time-of-day bucket.
+///
+/// Returns (bucket label, resolver label, mean score, run count) in time-of-day order.
+fn best_by_time_of_day(runs: &[RunExport]) -> Vec<(&'static str, String, f64, usize)> {
+	// bucket index -> resolver key -> (label, scores)
+	let mut buckets: BTreeMap<usize, HashMap<RowKey, (&str, Vec<f64>)>> = BTreeMap::new();
+	for run in runs {
+		let bucket = buckets.entry(time_of_day_bucket(run.timestamp_secs)).or_default();
+		for row in &run.rows {
+			bucket.entry(row.key())
+				.or_insert_with(|| (row.label.as_str(), Vec::new()))
+				.1.push(row.score);
+		}
+	}
+	let mut best = Vec::new();
+	for (bucket, resolvers) in buckets {
+		let winner = resolvers.values()
+			.filter_map(|(label, scores)| {
+				crate::stats::mean(scores).map(|m| (label.to_string(), m, scores.len()))
+			})
+			.min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+		if let Some((label, mean, count)) = winner {
+			best.push((TIME_OF_DAY_LABELS[bucket], label, mean, count));
+		}
+	}
+	best
+}
+
+//============================================
+/// Most stable resolver: lowest coefficient of variation of score across runs.
+///
+/// Only resolvers present in at least two runs are considered.
+/// Returns (label, mean score, stddev, run count).
+fn most_stable(runs: &[RunExport]) -> Option<(String, f64, f64, usize)> {
+	let mut history: HashMap<RowKey, (&str, Vec<f64>)> = HashMap::new();
+	for run in runs {
+		for row in &run.rows {
+			history.entry(row.key())
+				.or_insert_with(|| (row.label.as_str(), Vec::new()))
+				.1.push(row.score);
+		}
+	}
+	history.values()
+		.filter(|(_, scores)| scores.len() >= 2)
+		.filter_map(|(label, scores)| {
+			let m = crate::stats::mean(scores)?;
+			let sd = crate::stats::stddev(scores)?;
+			if m <= 0.0 {
+				return None;
+			}
+			Some((label.to_string(), m, sd, scores.len(), sd / m))
+		})
+		.min_by(|a, b| a.4.partial_cmp(&b.4).unwrap_or(std::cmp::Ordering::Equal))
+		.map(|(label, m, sd, n, _)| (label, m, sd, n))
+}
+
+//============================================
+/// Regressions between consecutive runs that share a resolver.
+///
+/// Returns (run timestamp, label, previous score, new score) in time order.
+fn regression_timeline(runs: &[RunExport]) -> Vec<(u64, String, f64, f64)> {
+	let mut last_score: HashMap<RowKey, f64> = HashMap::new();
+	let mut events = Vec::new();
+	for run in runs {
+		for row in &run.rows {
+			if let Some(&prev) = last_score.get(&row.key()) {
+				let delta = row.score - prev;
+				if prev > 0.0 && delta >= REGRESSION_MIN_MS && delta / prev * 100.0 >= REGRESSION_PCT {
+					events.push((run.timestamp_secs, row.label.clone(), prev, row.score));
+				}
+			}
+			last_score.insert(row.key(), row.score);
+		}
+	}
+	events
+}
+
+//============================================
+/// Run the read-only analysis over a directory of CSV exports.
+///
+/// Sends no DNS queries; prints aggregate insights to stdout.
+pub fn run_analyze(dir: &str) -> Result<()> {
+	let runs = load_runs(dir)?;
+	if runs.is_empty() {
+		anyhow::bail!("No benchmark CSV exports found in '{}'", dir);
+	}
+
+	let first = crate::telemetry::format_timestamp_iso(runs[0].timestamp_secs);
+	let last = crate::telemetry::format_timestamp_iso(runs[runs.len() - 1].timestamp_secs);
+	println!("Results Analysis");
+	println!("================");
+	println!("Directory: {}", dir);
+	let mut sources: Vec<&str> = Vec::new();
+	for run in &runs {
+		if !sources.contains(&run.time_source) {
+			sources.push(run.time_source);
+		}
+	}
+	println!("Runs:      {} ({} to {}, by {})", runs.len(), first, last, sources.join(" or "));
+
+	println!("\nBest resolver by time of day");
+	println!("----------------------------");
+	for (bucket, label, mean, count) in best_by_time_of_day(&runs) {
+		println!("  {:<24} {} (mean score {:.1} over {} run(s))", bucket, label, mean, count);
+	}
+
+	println!("\nMost stable resolver");
+	println!("--------------------");
+	match most_stable(&runs) {
+		Some((label, mean, sd, count)) => {
+			println!("  {} (mean score {:.1}, stddev {:.1} over {} runs)", label, mean, sd, count);
+		}
+		None => println!("  Not enough runs (a resolver must appear in at least 2 runs)"),
+	}
+
+	println!("\nRegression timeline");
+	println!("-------------------");
+	let regressions = regression_timeline(&runs);
+	if regressions.is_empty() {
+		println!("  No regressions (score increase >= {:.0}% and >= {:.0} ms between runs)",
+			REGRESSION_PCT, REGRESSION_MIN_MS);
+	}
+	for (secs, label, prev, new) in regressions {
+		println!("  {}  {}  {:.1} -> {:.1} (+{:.0}%)",
+			crate::telemetry::format_timestamp_iso(secs), label, prev, new,
+			(new - prev) / prev * 100.0);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn run(secs: u64, rows: &[(&str, f64)]) -> RunExport {
+		RunExport {
+			timestamp_secs: secs,
+			time_source: TIME_FROM_MANIFEST,
+			rows: rows.iter()
+				.map(|(label, score)| ExportRow {
+					label: label.to_string(),
+					ip: label.to_string(),
+					port: "53".to_string(),
+					transport: "UDP".to_string(),
+					score: *score,
+					started_secs: None,
+				})
+				.collect(),
+		}
+	}
+
+	#[test]
+	fn test_parse_export_skips_infinite_scores() {
+		let csv = "rank,resolver,ip_address,transport,overall_score\n\
+			1,Cloudflare,1.1.1.1,UDP,12.50\n\
+			2,Dead,192.0.2.1,UDP,inf\n";
+		let rows = parse_export(csv).unwrap();
+		assert_eq!(rows.len(), 1);
+		assert_eq!(rows[0].label, "Cloudflare");
+		assert!((rows[0].score - 12.5).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_load_runs_uses_manifest_start_times() {
+		let dir = std::env::temp_dir().join(format!("analyze_test_{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		// Manifest export, appended export with two runs, and an old export without either
+		std::fs::write(dir.join("manifest.csv"),
+			"# started: 2026-01-01T03:00:00Z\nresolver,ip_address,overall_score\na,1.1.1.1,10.0\n").unwrap();
+		std::fs::write(dir.join("appended.csv"),
+			"run_id,timestamp,network_label,resolver,ip_address,overall_score\n\
+			r1,2026-01-01T13:00:00Z,,a,1.1.1.1,11.0\n\
+			r2,2026-01-01T19:00:00Z,,a,1.1.1.1,12.0\n").unwrap();
+		std::fs::write(dir.join("old.csv"), "resolver,ip_address,overall_score\na,1.1.1.1,13.0\n").unwrap();
+		let runs = load_runs(dir.to_str().unwrap()).unwrap();
+		std::fs::remove_dir_all(&dir).unwrap();
+		let base = crate::telemetry::parse_timestamp_iso("2026-01-01T00:00:00Z").unwrap();
+		let times: Vec<u64> = runs.iter().map(|r| r.timestamp_secs).collect();
+		assert_eq!(&times[..3], &[base + 3 * 3600, base + 13 * 3600, base + 19 * 3600]);
+		let scores: Vec<f64> = runs.iter().map(|r| r.rows[0].score).collect();
+		assert_eq!(scores, vec![10.0, 11.0, 12.0, 13.0]);
+		let sources: Vec<&str> = runs.iter().map(|r| r.time_source).collect();
+		assert_eq!(sources, vec![TIME_FROM_MANIFEST, TIME_FROM_COLUMN, TIME_FROM_COLUMN, TIME_FROM_MTIME]);
+	}
+
+	#[test]
+	fn test_history_keeps_transports_of_one_address_apart() {
+		let csv = "resolver,ip_address,port,transport,overall_score
+			Cloudflare,1.1.1.1,53,UDP,10.0
+			Cloudflare,1.1.1.1,,DoT,40.0
+";
+		let first = parse_export(csv).unwrap();
+		assert_eq!(first[1].port, "853");
+		let mut second = first.clone();
+		second[1].score = 80.0;
+		let runs = vec![
+			RunExport { timestamp_secs: 0, time_source: TIME_FROM_MANIFEST, rows: first },
+			RunExport { timestamp_secs: 3600, time_source: TIME_FROM_MANIFEST, rows: second },
+		];
+		// Only the DoT row regressed; keyed by address alone it would compare 80 against 10
+		let events = regression_timeline(&runs);
+		assert_eq!(events.len(), 1);
+		assert!((events[0].2 - 40.0).abs() < 1e-9);
+		let (_, mean, _, n) = most_stable(&runs).unwrap();
+		assert_eq!(n, 2);
+		assert!((mean - 10.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_parse_export_requires_score_column() {
+		assert!(parse_export("rank,resolver,ip_address\n1,a,1.1.1.1\n").is_err());
+	}
+
+	#[test]
+	fn test_time_of_day_bucket() {
+		assert_eq!(TIME_OF_DAY_LABELS[time_of_day_bucket(3 * 3600)], "night (00-06 UTC)");
+		assert_eq!(TIME_OF_DAY_LABELS[time_of_day_bucket(86400 + 13 * 3600)], "afternoon (12-18 UTC)");
+		assert_eq!(time_of_day_bucket(86399), 3);
+	}
+
+	#[test]
+	fn test_most_stable_prefers_low_variation() {
+		let runs = vec![
+			run(0, &[("steady", 20.0), ("jumpy", 10.0)]),
+			run(3600, &[("steady", 21.0), ("jumpy", 30.0)]),
+		];
+		let (label, _, _, count) = most_stable(&runs).unwrap();
+		assert_eq!(label, "steady");
+		assert_eq!(count, 2);
+	}
+
+	#[test]
+	fn test_regression_timeline_flags_large_increase() {
+		let runs = vec![
+			run(0, &[("a", 20.0), ("b", 2.0)]),
+			run(3600, &[("a", 40.0), ("b", 4.0)]),
+		];
+		let events = regression_timeline(&runs);
+		// b doubled but by less than the absolute floor
+		assert_eq!(events.len(), 1);
+		assert_eq!(events[0].1, "a");
+	}
+}
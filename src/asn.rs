@@ -0,0 +1,143 @@
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::record::{AsnInfo, ResolverRecord};
+
+//============================================
+/// Offline IP-to-ASN table loaded from an iptoasn.com TSV file
+/// (ip2asn-v4.tsv, ip2asn-v6.tsv, ip2asn-combined.tsv, or ip2asn-v4-u32.tsv).
+///
+/// Each line is `range_start  range_end  AS_number  country_code  AS_description`.
+/// Ranges announced by nobody (AS 0) are skipped.
+#[derive(Debug, Default)]
+pub struct AsnDb {
+	/// (start, end, entry index), sorted by start
+	v4: Vec<(u32, u32, usize)>,
+	v6: Vec<(u128, u128, usize)>,
+	entries: Vec<AsnInfo>,
+}
+
+/// Range bound as a number: a dotted or colon address, or a decimal IPv4
+/// address as in the `-u32` variant of the file.
+fn parse_bound(field: &str) -> Option<IpAddr> {
+	field.parse::<IpAddr>().ok()
+		.or_else(|| field.parse::<u32>().ok().map(|n| IpAddr::V4(n.into())))
+}
+
+impl AsnDb {
+	/// Parse the TSV text. Fails on a malformed line, naming its number.
+	pub fn parse(text: &str) -> Result<AsnDb> {
+		let mut db = AsnDb::default();
+		for (n, line) in text.lines().enumerate() {
+			if line.trim().is_empty() || line.starts_with('#') {
+				continue;
+			}
+			let fields: Vec<&str> = line.split('\t').collect();
+			let parsed = match fields.as_slice() {
+				[start, end, asn, country, name, ..] => parse_bound(start)
+					.zip(parse_bound(end))
+					.zip(asn.parse::<u32>().ok())
+					.map(|((s, e), asn)| (s, e, asn, country, name)),
+				_ => None,
+			};
+			let (start, end, asn, country, name) = parsed
+				.ok_or_else(|| anyhow!("line {}: expected range_start, range_end, AS number, country, description", n + 1))?;
+			if asn == 0 {
+				continue;
+			}
+			let idx = db.entries.len();
+			match (start, end) {
+				(IpAddr::V4(s), IpAddr::V4(e)) => db.v4.push((s.into(), e.into(), idx)),
+				(IpAddr::V6(s), IpAddr::V6(e)) => db.v6.push((s.into(), e.into(), idx)),
+				_ => return Err(anyhow!("line {}: range mixes IPv4 and IPv6", n + 1)),
+			}
+			db.entries.push(AsnInfo {
+				asn,
+				country: if *country == "None" { String::new() } else { country.to_string() },
+				name: if *name == "Not routed" { String::new() } else { name.to_string() },
+			});
+		}
+		db.v4.sort_unstable();
+		db.v6.sort_unstable();
+		Ok(db)
+	}
+
+	/// Read and parse a TSV file.
+	pub fn load(path: &str) -> Result<AsnDb> {
+		let text = std::fs::read_to_string(path)
+			.with_context(|| format!("reading ASN database {} (expected an uncompressed iptoasn.com TSV)", path))?;
+		AsnDb::parse(&text).with_context(|| format!("parsing ASN database {}", path))
+	}
+
+	/// Number of announced ranges loaded.
+	pub fn len(&self) -> usize {
+		self.v4.len() + self.v6.len()
+	}
+
+	/// Whether no ranges were loaded.
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// AS and country for an address, if a range covers it.
+	pub fn lookup(&self, ip: IpAddr) -> Option<&AsnInfo> {
+		let idx = match ip {
+			IpAddr::V4(v4) => find_range(&self.v4, u32::from(v4)),
+			IpAddr::V6(v6) => find_range(&self.v6, u128::from(v6)),
+		}?;
+		self.entries.get(idx)
+	}
+}
+
+/// Entry index of the last range starting at or below `key`, if it also ends at or above it.
+fn find_range<T: Ord + Copy>(ranges: &[(T, T, usize)], key: T) -> Option<usize> {
+	let pos = ranges.partition_point(|&(start, _, _)| start <= key);
+	let &(_, end, idx) = ranges.get(pos.checked_sub(1)?)?;
+	(key <= end).then_some(idx)
+}
+
+//============================================
+/// Fill in the AS and country of every record found in the table.
+pub fn annotate(records: &mut [ResolverRecord], db: &AsnDb) {
+	let mut found = 0;
+	for r in records.iter_mut() {
+		r.asn = db.lookup(r.resolver.addr.ip()).cloned();
+		found += usize::from(r.asn.is_some());
+	}
+	crate::verbose!("ASN lookup: {} of {} resolvers found", found, records.len());
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SAMPLE: &str = "1.0.0.0\t1.0.0.255\t13335\tUS\tCLOUDFLARENET\n\
+		8.8.8.0\t8.8.8.255\t15169\tUS\tGOOGLE\n\
+		9.9.9.0\t9.9.9.255\t19281\tCH\tQUAD9-AS-1\n\
+		10.0.0.0\t10.255.255.255\t0\tNone\tNot routed\n\
+		2606:4700::\t2606:4700:ffff:ffff:ffff:ffff:ffff:ffff\t13335\tUS\tCLOUDFLARENET\n";
+
+	#[test]
+	fn test_lookup_ranges() {
+		let db = AsnDb::parse(SAMPLE).unwrap();
+		assert_eq!(db.len(), 4);
+		let quad9 = db.lookup("9.9.9.9".parse().unwrap()).unwrap();
+		assert_eq!((quad9.asn, quad9.country.as_str(), quad9.name.as_str()), (19281, "CH", "QUAD9-AS-1"));
+		assert_eq!(db.lookup("1.0.0.1".parse().unwrap()).unwrap().asn, 13335);
+		assert_eq!(db.lookup("2606:4700::1111".parse().unwrap()).unwrap().asn, 13335);
+		// Between ranges, and inside the unrouted range
+		assert!(db.lookup("8.8.9.1".parse().unwrap()).is_none());
+		assert!(db.lookup("10.1.2.3".parse().unwrap()).is_none());
+	}
+
+	#[test]
+	fn test_parse_u32_bounds_and_errors() {
+		// 134744064 = 8.8.8.0
+		let db = AsnDb::parse("134744064\t134744319\t15169\tUS\tGOOGLE\n").unwrap();
+		assert_eq!(db.lookup("8.8.8.8".parse().unwrap()).unwrap().name, "GOOGLE");
+		let err = AsnDb::parse("1.0.0.0\t1.0.0.255\n").unwrap_err();
+		assert!(err.to_string().starts_with("line 1:"));
+		assert!(AsnDb::parse("1.0.0.0\t2606:4700::\t13335\tUS\tCLOUDFLARENET\n").is_err());
+	}
+}
@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Result};
+use serde::Deserialize;
+
+use crate::record::{AtlasResult, ResolverRecord};
+use crate::transport::DnsTransport;
+
+/// RIPE Atlas REST API
+pub const ATLAS_API: &str = "https://atlas.ripe.net/api/v2";
+/// Probes asked for per measurement by default
+pub const DEFAULT_ATLAS_PROBES: u32 = 10;
+/// Warm-set domains measured per resolver
+const ATLAS_DOMAINS: usize = 3;
+/// Resolvers measured at most, best ranked first, to bound credit use
+const ATLAS_MAX_RESOLVERS: usize = 20;
+/// How long to wait for probe results before reporting what arrived
+const ATLAS_WAIT: Duration = Duration::from_secs(300);
+/// Time between result polls
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+/// Time limit for each API request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings for the RIPE Atlas comparison (--ripe-atlas-key and friends).
+#[derive(Debug, Clone)]
+pub struct AtlasOptions {
+	/// API key with permission to create measurements
+	pub key: String,
+	/// Probes per measurement
+	pub probes: u32,
+	/// ISO country code to pick probes from; worldwide when None
+	pub country: Option<String>,
+	/// API base URL (ATLAS_API outside tests)
+	pub api: String,
+}
+
+/// Response to a measurement request
+#[derive(Debug, Deserialize)]
+struct CreateResponse {
+	measurements: Vec<u64>,
+}
+
+/// Error body of a failed API call
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+	error: ErrorDetail,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorDetail {
+	#[serde(default)]
+	detail: String,
+	#[serde(default)]
+	title: String,
+}
+
+/// One probe's result of a DNS measurement; `result` is missing when the
+/// probe got no answer (the body then has an `error` object instead)
+#[derive(Debug, Deserialize)]
+struct ProbeResult {
+	prb_id: u64,
+	result: Option<DnsAnswer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DnsAnswer {
+	/// Response time (ms)
+	rt: Option<f64>,
+}
+
+//============================================
+/// Whether Atlas probes can reach the address: not loopback, private,
+/// shared (CGNAT), link-local, or documentation space.
+pub(crate) fn publicly_routable(ip: IpAddr) -> bool {
+	match ip {
+		IpAddr::V4(v4) => {
+			let shared = v4.octets()[0] == 100 && (v4.octets()[1] & 0xc0) == 64;
+			!(v4.is_loopback() || v4.is_private() || v4.is_link_local() || v4.is_unspecified()
+				|| v4.is_documentation() || v4.is_broadcast() || shared)
+		}
+		IpAddr::V6(v6) => {
+			let documentation = v6.segments()[0] == 0x2001 && v6.segments()[1] == 0xdb8;
+			!(v6.is_loopback() || v6.is_unspecified() || v6.is_unique_local() || v6.is_unicast_link_local() || documentation)
+		}
+	}
+}
+
+/// Request body for one-off UDP DNS measurements: one A query per
+/// (resolver, domain), all asking for the same number of probes from the
+/// same area or country. Atlas picks each measurement's probes on its own,
+/// so resolvers are measured from comparable places, not identical ones.
+fn measurement_request(targets: &[IpAddr], domains: &[String], options: &AtlasOptions) -> serde_json::Value {
+	let mut definitions = Vec::new();
+	for ip in targets {
+		for domain in domains {
+			definitions.push(serde_json::json!({
+				"type": "dns",
+				"af": if ip.is_ipv6() { 6 } else { 4 },
+				"target": ip.to_string(),
+				"description": format!("rust-dns-benchmark {} {}", ip, domain),
+				"query_class": "IN",
+				"query_type": "A",
+				"query_argument": domain,
+				"protocol": "UDP",
+				"use_probe_resolver": false,
+				"set_rd_bit": true,
+			}));
+		}
+	}
+	let (kind, value) = match &options.country {
+		Some(country) => ("country", country.to_uppercase()),
+		None => ("area", "WW".to_string()),
+	};
+	serde_json::json!({
+		"definitions": definitions,
+		"probes": [{ "requested": options.probes, "type": kind, "value": value }],
+		"is_oneoff": true,
+	})
+}
+
+/// Readable message from a failed API response body.
+fn api_error(status: reqwest::StatusCode, body: &str) -> anyhow::Error {
+	match serde_json::from_str::<ErrorResponse>(body) {
+		Ok(ErrorResponse { error }) if !error.detail.is_empty() => anyhow!("{} ({})", error.detail, status),
+		Ok(ErrorResponse { error }) => anyhow!("{} ({})", error.title, status),
+		Err(_) => anyhow!("HTTP {}", status),
+	}
+}
+
+/// Summarize the probe results of one resolver's measurements.
+fn summarize(measurement_ids: Vec<u64>, results: &[ProbeResult]) -> AtlasResult {
+	let mut times: Vec<f64> = results.iter()
+		.filter_map(|r| r.result.as_ref()?.rt)
+		.filter(|rt| rt.is_finite() && *rt >= 0.0)
+		.collect();
+	times.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	AtlasResult {
+		measurement_ids,
+		probes: results.iter().map(|r| r.prb_id).collect::<HashSet<_>>().len(),
+		answered: times.len(),
+		results: results.len(),
+		p50_ms: crate::stats::percentile(&times, 50.0),
+		p90_ms: crate::stats::percentile(&times, 90.0),
+	}
+}
+
+//============================================
+/// Measure the best-ranked public UDP resolvers from RIPE Atlas probes and
+/// attach the results to their records.
+///
+/// Submits one-off DNS measurements for the first warm-set domains, then
+/// polls until every probe has reported or ATLAS_WAIT runs out. Each probe
+/// result costs credits from the key's account.
+pub async fn run_atlas(records: &mut [ResolverRecord], categories: &BTreeMap<String, Vec<String>>, options: &AtlasOptions) -> Result<()> {
+	let mut targets: Vec<IpAddr> = Vec::new();
+	for r in records.iter().filter(|r| r.resolver.transport == DnsTransport::Udp) {
+		let ip = r.resolver.addr.ip();
+		if publicly_routable(ip) && !targets.contains(&ip) && targets.len() < ATLAS_MAX_RESOLVERS {
+			targets.push(ip);
+		}
+	}
+	let domains: Vec<String> = categories.get("cached").or_else(|| categories.values().next())
+		.map(|d| d.iter().take(ATLAS_DOMAINS).cloned().collect())
+		.unwrap_or_default();
+	if targets.is_empty() || domains.is_empty() {
+		crate::info!("RIPE Atlas: no public UDP resolvers to measure");
+		return Ok(());
+	}
+	crate::info!("RIPE Atlas: measuring {} resolvers x {} domains from {} probes ({})...",
+		targets.len(), domains.len(), options.probes, options.country.as_deref().unwrap_or("worldwide"));
+
+	let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+	let response = client.post(format!("{}/measurements/", options.api))
+		.header("Authorization", format!("Key {}", options.key))
+		.header(reqwest::header::CONTENT_TYPE, "application/json")
+		.body(measurement_request(&targets, &domains, options).to_string())
+		.send().await?;
+	let status = response.status();
+	let body = response.text().await?;
+	if !status.is_success() {
+		return Err(api_error(status, &body).context("creating measurements"));
+	}
+	let created: CreateResponse = serde_json::from_str(&body)?;
+	if created.measurements.len() != targets.len() * domains.len() {
+		bail!("asked for {} measurements, Atlas created {}", targets.len() * domains.len(), created.measurements.len());
+	}
+	crate::verbose!("RIPE Atlas measurements: {:?}", created.measurements);
+
+	// Poll until every measurement has a result from each probe
+	let deadline = Instant::now() + ATLAS_WAIT;
+	let mut results: HashMap<u64, Vec<ProbeResult>> = HashMap::new();
+	loop {
+		for id in &created.measurements {
+			if results.get(id).is_some_and(|r| r.len() >= options.probes as usize) {
+				continue;
+			}
+			let response = client.get(format!("{}/measurements/{}/results/?format=json", options.api, id))
+				.header("Authorization", format!("Key {}", options.key))
+				.send().await?;
+			let status = response.status();
+			let body = response.text().await?;
+			if !status.is_success() {
+				return Err(api_error(status, &body).context(format!("fetching results of measurement {}", id)));
+			}
+			results.insert(*id, serde_json::from_str(&body)?);
+		}
+		let complete = created.measurements.iter()
+			.all(|id| results.get(id).is_some_and(|r| r.len() >= options.probes as usize));
+		if complete || Instant::now() + POLL_INTERVAL > deadline {
+			break;
+		}
+		tokio::time::sleep(POLL_INTERVAL).await;
+	}
+
+	for (t, ip) in targets.iter().enumerate() {
+		let ids = created.measurements[t * domains.len()..(t + 1) * domains.len()].to_vec();
+		let probe_results: Vec<ProbeResult> = ids.iter().flat_map(|id| results.remove(id).unwrap_or_default()).collect();
+		let summary = summarize(ids, &probe_results);
+		for r in records.iter_mut().filter(|r| r.resolver.addr.ip() == *ip && r.resolver.transport == DnsTransport::Udp) {
+			r.atlas = Some(summary.clone());
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_measurement_request_and_reachability() {
+		assert!(publicly_routable("9.9.9.9".parse().unwrap()));
+		assert!(publicly_routable("2620:fe::fe".parse().unwrap()));
+		for ip in ["192.168.1.1", "100.100.100.100", "127.0.0.53", "fd00::53", "2001:db8::1"] {
+			assert!(!publicly_routable(ip.parse().unwrap()), "{}", ip);
+		}
+
+		let options = AtlasOptions { key: "k".to_string(), probes: 5, country: Some("de".to_string()), api: ATLAS_API.to_string() };
+		let targets: Vec<IpAddr> = vec!["1.1.1.1".parse().unwrap(), "2606:4700:4700::1111".parse().unwrap()];
+		let domains = vec!["example.com".to_string(), "example.org".to_string()];
+		let body = measurement_request(&targets, &domains, &options);
+		let definitions = body["definitions"].as_array().unwrap();
+		assert_eq!(definitions.len(), 4);
+		assert_eq!((definitions[1]["target"].as_str(), definitions[1]["query_argument"].as_str()), (Some("1.1.1.1"), Some("example.org")));
+		assert_eq!(definitions[2]["af"], 6);
+		assert_eq!(body["probes"][0], serde_json::json!({ "requested": 5, "type": "country", "value": "DE" }));
+		assert_eq!(body["is_oneoff"], true);
+	}
+
+	#[test]
+	fn test_summarize_probe_results() {
+		let body = r#"[
+			{"prb_id": 1, "result": {"rt": 12.5, "ANCOUNT": 1}},
+			{"prb_id": 2, "result": {"rt": 40.0}},
+			{"prb_id": 2, "error": {"timeout": 5000}},
+			{"prb_id": 3, "result": {"rt": 20.0}}
+		]"#;
+		let results: Vec<ProbeResult> = serde_json::from_str(body).unwrap();
+		let summary = summarize(vec![7, 8], &results);
+		assert_eq!((summary.probes, summary.answered, summary.results), (3, 3, 4));
+		assert_eq!((summary.p50_ms, summary.p90_ms), (Some(20.0), Some(40.0)));
+
+		let err = api_error(reqwest::StatusCode::FORBIDDEN, r#"{"error": {"status": 403, "title": "Forbidden", "detail": "Not enough credits"}}"#);
+		assert_eq!(err.to_string(), "Not enough credits (403 Forbidden)");
+	}
+}
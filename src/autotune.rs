@@ -0,0 +1,256 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Semaphore;
+
+use crate::dns::{build_query, parse_response};
+use crate::transport::{BenchmarkConfig, DnsTransport, QueryType, Resolver};
+
+/// Concurrency levels tried in ascending order
+const AUTOTUNE_LEVELS: [usize; 6] = [8, 16, 32, 64, 128, 256];
+/// Queries sent per in-flight slot at each level
+const AUTOTUNE_QUERIES_PER_SLOT: usize = 4;
+/// Serial queries used to measure the uncontended baseline latency
+const AUTOTUNE_BASELINE_QUERIES: usize = 5;
+/// p50 may grow by this factor over the baseline before measurements count as distorted
+const AUTOTUNE_MAX_INFLATION: f64 = 1.5;
+/// Absolute slack (ms) on the inflation limit so sub-millisecond baselines are not over-strict
+const AUTOTUNE_INFLATION_SLACK_MS: f64 = 5.0;
+/// Highest timeout fraction a sustainable level may show
+const AUTOTUNE_MAX_TIMEOUT_RATE: f64 = 0.02;
+/// Cheap, universally cached name used for every ramp query
+const AUTOTUNE_DOMAIN: &str = "google.com";
+
+/// Measurements for one concurrency level against the control resolver.
+#[derive(Debug, Clone)]
+pub struct LevelStats {
+	pub level: usize,
+	pub total: usize,
+	/// Local socket bind or send failures (e.g. EMFILE, ENOBUFS)
+	pub send_errors: usize,
+	pub timeouts: usize,
+	/// Kernel UDP receive-buffer drops during the level; None off Linux
+	pub rcvbuf_drops: Option<u64>,
+	/// Median latency of answered queries; None if nothing was answered
+	pub p50_ms: Option<f64>,
+}
+
+/// Outcome of a single ramp query.
+enum ProbeOutcome {
+	Answered(f64),
+	Timeout,
+	SendError,
+}
+
+//============================================
+/// Decide whether a level can be sustained without distorting measurements.
+///
+/// Requires no local send errors or receive-buffer drops, a timeout rate at
+/// or below AUTOTUNE_MAX_TIMEOUT_RATE, and a p50 within the inflation limit.
+fn level_is_sustainable(stats: &LevelStats, baseline_p50_ms: f64) -> bool {
+	if stats.send_errors > 0 || stats.rcvbuf_drops.unwrap_or(0) > 0 || stats.total == 0 {
+		return false;
+	}
+	if stats.timeouts as f64 / stats.total as f64 > AUTOTUNE_MAX_TIMEOUT_RATE {
+		return false;
+	}
+	let limit = baseline_p50_ms * AUTOTUNE_MAX_INFLATION + AUTOTUNE_INFLATION_SLACK_MS;
+	stats.p50_ms.is_some_and(|p50| p50 <= limit)
+}
+
+//============================================
+/// Pick the control resolver: a system UDP resolver if present, else the first UDP resolver.
+fn pick_control(resolvers: &[Resolver]) -> Option<SocketAddr> {
+	let udp = || resolvers.iter().filter(|r| r.transport == DnsTransport::Udp);
+	udp().find(|r| r.is_system).or_else(|| udp().next()).map(|r| r.addr)
+}
+
+//============================================
+/// Parse the UDP RcvbufErrors counter from /proc/net/snmp text.
+fn parse_rcvbuf_errors(snmp: &str) -> Option<u64> {
+	// Two "Udp:" lines: a header row of field names, then a row of values
+	let mut udp_lines = snmp.lines().filter(|l| l.starts_with("Udp:"));
+	let header = udp_lines.next()?;
+	let values = udp_lines.next()?;
+	let idx = header.split_whitespace().position(|f| f == "RcvbufErrors")?;
+	values.split_whitespace().nth(idx)?.parse().ok()
+}
+
+/// Current kernel UDP receive-buffer drop counter (Linux only).
+fn read_rcvbuf_errors() -> Option<u64> {
+	let text = std::fs::read_to_string("/proc/net/snmp").ok()?;
+	parse_rcvbuf_errors(&text)
+}
+
+//============================================
+/// Send one query to the control resolver on a dedicated socket.
+async fn probe_once(addr: SocketAddr, timeout: Duration, dnssec: bool) -> ProbeOutcome {
+	let socket = match crate::source::bind_udp(addr.is_ipv6()).await {
+		Ok(s) => s,
+		Err(_) => return ProbeOutcome::SendError,
+	};
+	let txid: u16 = rand::random();
+	let query = match build_query(AUTOTUNE_DOMAIN, QueryType::A, txid, dnssec) {
+		Ok(q) => q,
+		Err(_) => return ProbeOutcome::SendError,
+	};
+	let start = Instant::now();
+	if socket.send_to(&query, addr).await.is_err() {
+		return ProbeOutcome::SendError;
+	}
+	let mut buf = vec![0u8; 4096];
+	while start.elapsed() < timeout {
+		let remaining = timeout - start.elapsed();
+		match tokio::time::timeout(remaining, socket.recv_from(&mut buf)).await {
+			Ok(Ok((len, _src))) => {
+				if parse_response(&buf[..len], txid, AUTOTUNE_DOMAIN, QueryType::A).is_ok() {
+					return ProbeOutcome::Answered(start.elapsed().as_secs_f64() * 1000.0);
+				}
+			}
+			_ => break,
+		}
+	}
+	ProbeOutcome::Timeout
+}
+
+//============================================
+/// Run one concurrency level: `level` queries in flight, AUTOTUNE_QUERIES_PER_SLOT each.
+async fn measure_level(addr: SocketAddr, level: usize, timeout: Duration, dnssec: bool) -> LevelStats {
+	let drops_before = read_rcvbuf_errors();
+	let semaphore = Arc::new(Semaphore::new(level));
+	let total = level * AUTOTUNE_QUERIES_PER_SLOT;
+	let mut handles = Vec::with_capacity(total);
+	for _ in 0..total {
+		let sem = semaphore.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			probe_once(addr, timeout, dnssec).await
+		}));
+	}
+
+	let mut latencies = Vec::new();
+	let mut send_errors = 0;
+	let mut timeouts = 0;
+	for handle in handles {
+		match handle.await {
+			Ok(ProbeOutcome::Answered(ms)) => latencies.push(ms),
+			Ok(ProbeOutcome::Timeout) => timeouts += 1,
+			Ok(ProbeOutcome::SendError) | Err(_) => send_errors += 1,
+		}
+	}
+	let rcvbuf_drops = match (drops_before, read_rcvbuf_errors()) {
+		(Some(before), Some(after)) => Some(after.saturating_sub(before)),
+		_ => None,
+	};
+	latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	LevelStats {
+		level,
+		total,
+		send_errors,
+		timeouts,
+		rcvbuf_drops,
+		p50_ms: crate::stats::percentile(&latencies, 50.0),
+	}
+}
+
+//============================================
+/// Ramp concurrency against a control resolver and return the highest
+/// level the local host and network sustain without distorting latency.
+///
+/// Falls back to the configured `max_inflight` when there is no UDP
+/// control resolver or it does not answer the baseline queries.
+pub async fn run_autotune(resolvers: &[Resolver], config: &BenchmarkConfig) -> usize {
+	let fallback = config.max_inflight;
+	let control = match pick_control(resolvers) {
+		Some(addr) => addr,
+		None => {
+			crate::info!("Auto concurrency: no UDP control resolver, using {}", fallback);
+			return fallback;
+		}
+	};
+	crate::info!("Auto concurrency: ramping against control resolver {}...", control.ip());
+
+	// Uncontended baseline: serial queries
+	let mut baseline = Vec::new();
+	for _ in 0..AUTOTUNE_BASELINE_QUERIES {
+		if let ProbeOutcome::Answered(ms) = probe_once(control, config.timeout, config.dnssec).await {
+			baseline.push(ms);
+		}
+	}
+	baseline.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	let baseline_p50 = match crate::stats::percentile(&baseline, 50.0) {
+		Some(p) => p,
+		None => {
+			crate::info!("  Control resolver did not answer; using {}", fallback);
+			return fallback;
+		}
+	};
+	crate::info!("  baseline: p50 {:.1} ms", baseline_p50);
+
+	// Never ramp past what the open file limit allows
+	let fd_limit = crate::validate::open_file_limit();
+	let mut settled = AUTOTUNE_LEVELS[0];
+	for &level in AUTOTUNE_LEVELS.iter()
+		.filter(|&&l| fd_limit.is_none_or(|limit| (l as u64) + crate::validate::FD_RESERVE <= limit))
+	{
+		let stats = measure_level(control, level, config.timeout, config.dnssec).await;
+		let ok = level_is_sustainable(&stats, baseline_p50);
+		crate::info!("  {:>3} in flight: p50 {}, {} timeouts, {} send errors, {} drops -> {}",
+			level,
+			stats.p50_ms.map(|p| format!("{:.1} ms", p)).unwrap_or_else(|| "-".to_string()),
+			stats.timeouts, stats.send_errors,
+			stats.rcvbuf_drops.map(|d| d.to_string()).unwrap_or_else(|| "n/a".to_string()),
+			if ok { "ok" } else { "limit" });
+		config.telemetry.log_autotune_level(
+			level, stats.p50_ms.unwrap_or(0.0), stats.timeouts,
+			stats.send_errors, stats.rcvbuf_drops.unwrap_or(0), ok,
+		);
+		if !ok {
+			break;
+		}
+		settled = level;
+	}
+	crate::info!("  Settled at {} concurrent queries", settled);
+	settled
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn stats(p50_ms: f64, timeouts: usize, send_errors: usize) -> LevelStats {
+		LevelStats {
+			level: 32,
+			total: 128,
+			send_errors,
+			timeouts,
+			rcvbuf_drops: Some(0),
+			p50_ms: Some(p50_ms),
+		}
+	}
+
+	#[test]
+	fn test_level_sustainable_within_inflation() {
+		// limit = 10 * 1.5 + 5 = 20 ms
+		assert!(level_is_sustainable(&stats(19.0, 0, 0), 10.0));
+		assert!(!level_is_sustainable(&stats(21.0, 0, 0), 10.0));
+	}
+
+	#[test]
+	fn test_level_rejects_send_errors_and_timeouts() {
+		assert!(!level_is_sustainable(&stats(10.0, 0, 1), 10.0));
+		// 3 of 128 is above the 2% timeout limit
+		assert!(!level_is_sustainable(&stats(10.0, 3, 0), 10.0));
+		assert!(level_is_sustainable(&stats(10.0, 2, 0), 10.0));
+	}
+
+	#[test]
+	fn test_parse_rcvbuf_errors() {
+		let snmp = "Udp: InDatagrams NoPorts InErrors OutDatagrams RcvbufErrors SndbufErrors\n\
+			Udp: 1000 5 2 900 7 0\n\
+			UdpLite: InDatagrams NoPorts\n";
+		assert_eq!(parse_rcvbuf_errors(snmp), Some(7));
+		assert_eq!(parse_rcvbuf_errors("Tcp: a b\nTcp: 1 2\n"), None);
+	}
+}
@@ -8,17 +8,18 @@ use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpStream, UdpSocket};
 use tokio::sync::Semaphore;
 use rand::seq::SliceRandom;
-use rand::SeedableRng;
+use rand::{Rng, SeedableRng};
 use rand::rngs::StdRng;
-use anyhow::Result;
+use anyhow::{bail, Result};
 
-use hickory_proto::op::ResponseCode;
 use rustls::ClientConfig;
 use tokio_rustls::TlsConnector;
 
 use crate::transport::{
-	DnsTransport, Resolver, QueryType, QueryResult, BenchmarkConfig,
+	DnsTransport, DohMethod, DohOptions, Resolver, QueryType, QueryOutcome, QueryResult, BenchmarkConfig,
 };
+use crate::cli::{ConnectionMode, DohHttpVersion};
+use crate::proxy::{connect_via, ProxyConfig};
 
 /// Timeout for Phase 1 discovery reachability screen -- UDP (ms)
 pub const SCREEN_TIMEOUT_MS: u64 = 500;
@@ -34,14 +35,83 @@ use crate::dns::{
 	check_rebinding_protection, check_dnssec_validation,
 };
 use crate::stats::{
-	compute_set_stats, compute_uncertainty, detect_ties_on_records,
+	compute_set_stats, compute_uncertainty, detect_ties_significance,
 	rank_records,
 };
 
 use tokio::task::JoinHandle;
 
-/// Shared pool of reqwest clients for DoH, keyed by resolver URL
-type DohClientPool = HashMap<String, reqwest::Client>;
+/// How DoT and DoH queries connect: the run's --connection-mode,
+/// --doh-http, and --proxy. The default reuses connections over HTTP/2, direct.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionOptions {
+	pub mode: ConnectionMode,
+	pub doh_http: DohHttpVersion,
+	pub proxy: Option<ProxyConfig>,
+}
+
+impl From<&BenchmarkConfig> for ConnectionOptions {
+	fn from(config: &BenchmarkConfig) -> Self {
+		ConnectionOptions {
+			mode: config.connection_mode,
+			doh_http: config.doh_http,
+			proxy: config.proxy.clone(),
+		}
+	}
+}
+
+/// A DoT connection: TLS over TCP
+type DotStream = tokio_rustls::client::TlsStream<TcpStream>;
+
+/// Connections for the encrypted transports, shared by every query of a
+/// run: a reqwest client per DoH resolver URL and address, and idle DoT
+/// connections per resolver address. In per-query mode nothing carries
+/// over between queries.
+#[derive(Clone, Default)]
+pub struct ConnectionPool {
+	options: ConnectionOptions,
+	/// DoH clients keyed by resolver key (the URL) and benchmarked address
+	doh: HashMap<(String, SocketAddr), reqwest::Client>,
+	/// Shared by new DoT connections in reuse mode, so they can resume a session
+	dot_connector: Option<TlsConnector>,
+	/// Idle DoT connections, keyed by resolver address
+	dot_idle: Arc<std::sync::Mutex<HashMap<SocketAddr, Vec<DotStream>>>>,
+}
+
+impl ConnectionPool {
+	/// Client for a DoH resolver URL at `addr`: the shared one, or in
+	/// per-query mode a new one so the query opens its own connection. None
+	/// for an unknown resolver.
+	fn doh_client(&self, url: &str, addr: SocketAddr) -> Option<reqwest::Client> {
+		let client = self.doh.get(&(url.to_string(), addr))?;
+		match self.options.mode {
+			ConnectionMode::Reuse => Some(client.clone()),
+			ConnectionMode::PerQuery => Some(new_doh_client(url, addr, self.options.doh_http, self.options.proxy.as_ref())),
+		}
+	}
+
+	/// Connector for a new DoT connection; a fresh one (no session to
+	/// resume) in per-query mode.
+	fn dot_connector(&self) -> TlsConnector {
+		self.dot_connector.clone().unwrap_or_else(dot_connector)
+	}
+
+	/// An idle DoT connection to the resolver, if one is kept.
+	fn take_dot(&self, resolver: SocketAddr) -> Option<DotStream> {
+		let mut idle = self.dot_idle.lock().ok()?;
+		idle.get_mut(&resolver)?.pop()
+	}
+
+	/// Keep a DoT connection for the next query (reuse mode only).
+	fn keep_dot(&self, resolver: SocketAddr, stream: DotStream) {
+		if self.options.mode != ConnectionMode::Reuse {
+			return;
+		}
+		if let Ok(mut idle) = self.dot_idle.lock() {
+			idle.entry(resolver).or_default().push(stream);
+		}
+	}
+}
 
 /// Format a duration in seconds to a human-readable string like "2m 15s" or "8s".
 fn format_duration_secs(secs: u64) -> String {
@@ -89,7 +159,7 @@ pub fn spawn_progress_monitor(
 		loop {
 			tokio::time::sleep(Duration::from_millis(500)).await;
 			let done = completed.load(Ordering::Relaxed);
-			let pct = if total > 0 { done * 100 / total } else { 100 };
+			let pct = (done * 100).checked_div(total).unwrap_or(100);
 			let elapsed = start.elapsed().as_secs_f64();
 			// Calculate ETA with EMA smoothing
 			let eta_str = if done == 0 || elapsed < 0.001 {
@@ -119,7 +189,9 @@ pub fn spawn_progress_monitor(
 			};
 			// Pad to 80 chars to overwrite any longer previous line
 			let line = format!("  {}: {}/{} ({}%) -- {}", label, done, total, pct, eta_str);
-			eprint!("\r{:<80}", line);
+			if crate::logging::enabled(crate::logging::Verbosity::Normal) {
+				eprint!("\r{:<80}", line);
+			}
 		}
 	})
 }
@@ -134,253 +206,521 @@ pub fn stop_progress_monitor(
 	monitor.abort();
 	let elapsed_secs = start.elapsed().as_secs();
 	let time_str = format_duration_secs(elapsed_secs);
+	if !crate::logging::enabled(crate::logging::Verbosity::Normal) {
+		return;
+	}
 	// Clear entire line first to avoid leftover characters from longer progress text
 	eprint!("\r{:width$}\r", "", width = 80);
 	eprintln!("  {}: {}/{} (100%) -- done in {}", label, total, total, time_str);
 }
 
+//============================================
+/// Per-attempt receive windows for a UDP query with `retries` retransmissions.
+///
+/// Each window doubles the previous one (exponential backoff) and the windows
+/// sum to the overall timeout, so retries never extend the timeout penalty.
+/// With zero retries the single window is the full timeout.
+pub(crate) fn udp_attempt_windows(timeout: Duration, retries: u32) -> Vec<Duration> {
+	let attempts = retries.min(crate::transport::MAX_UDP_RETRIES) + 1;
+	// Sum of 1 + 2 + 4 + ... over all attempts
+	let units = (1u32 << attempts) - 1;
+	let base = timeout / units;
+	let mut windows: Vec<Duration> = (0..attempts).map(|i| base * (1u32 << i)).collect();
+	// Give integer-division leftovers to the last attempt
+	let used: Duration = windows.iter().sum();
+	if let Some(last) = windows.last_mut() {
+		*last += timeout.saturating_sub(used);
+	}
+	windows
+}
+
 /// Send a single DNS query over UDP and measure latency.
 ///
 /// Creates a dedicated socket per query to avoid response stealing between
-/// concurrent tasks sharing the same resolver socket.
+/// concurrent tasks sharing the same resolver socket. The socket is connected
+/// to the resolver, so an ICMP port or host unreachable ends the query at
+/// once with a network error instead of a timeout. With `retries` > 0 the
+/// same query is retransmitted when an attempt window expires; a response to
+/// any attempt is accepted and latency is measured from the first send.
+#[allow(clippy::too_many_arguments)]
 async fn send_udp_query(
 	resolver: std::net::SocketAddr,
 	query_bytes: &[u8],
 	timeout: Duration,
+	retries: u32,
+	grace: Duration,
 	txid: u16,
 	domain: &str,
 	query_type: QueryType,
 ) -> QueryResult {
 	let resolver_label = resolver.ip().to_string();
 
-	// Bind a dedicated socket for this query
-	let bind_addr = if resolver.is_ipv4() {
-		"0.0.0.0:0"
-	} else {
-		"[::]:0"
+	// Bind a dedicated socket for this query; connecting it makes the kernel
+	// report ICMP errors from the resolver on recv
+	let connected = async {
+		let socket = crate::source::bind_udp(resolver.is_ipv6()).await?;
+		socket.connect(resolver).await?;
+		Ok::<_, std::io::Error>(socket)
 	};
-	let socket = match UdpSocket::bind(bind_addr).await {
+	let socket = match connected.await {
 		Ok(s) => s,
-		Err(_) => {
+		Err(e) => {
 			return QueryResult {
 				resolver: resolver_label,
 				latency: timeout,
-				success: false,
-				timeout: true,
+				outcome: QueryOutcome::NetworkError(e.kind()),
+				ttl: None,
+				attempts: 1,
+				late_latency: None,
+				duplicates: 0,
 			};
 		}
 	};
 
-	// Send the query and start timing immediately around send+recv
+	// Start timing before the first send; retries add to the measured latency
 	let start = Instant::now();
-	if socket.send_to(query_bytes, resolver).await.is_err() {
-		return QueryResult {
-			resolver: resolver_label,
-			latency: timeout,
-			success: false,
-			timeout: true,
-		};
-	}
-
-	// Receive with timeout, retry recv on txid mismatch
+	let windows = udp_attempt_windows(timeout, retries);
+	let mut attempt_deadline = start;
 	// Use 4096-byte buffer to handle EDNS-extended responses
 	let mut buf = vec![0u8; 4096];
-	let max_retries = 3;
-	for _ in 0..max_retries {
-		let elapsed = start.elapsed();
-		if elapsed >= timeout {
-			break;
+	// Rejected replies, to tell a plain timeout from bad answers
+	let (mut parse_errors, mut mismatches_exhausted) = (false, false);
+
+	for (attempt, window) in windows.iter().enumerate() {
+		let attempts = attempt as u32 + 1;
+		if let Err(e) = socket.send(query_bytes).await {
+			return QueryResult {
+				resolver: resolver_label,
+				latency: timeout,
+				outcome: QueryOutcome::NetworkError(e.kind()),
+				ttl: None,
+				attempts,
+				late_latency: None,
+				duplicates: 0,
+			};
 		}
-		let remaining = timeout - elapsed;
-
-		match tokio::time::timeout(remaining, socket.recv_from(&mut buf)).await {
-			Ok(Ok((len, _src))) => {
-				let latency = start.elapsed();
-				match parse_response(&buf[..len], txid, domain, query_type) {
-					Ok(response) => {
-						let success =
-							response.rcode == ResponseCode::NoError;
-						return QueryResult {
-							resolver: resolver_label,
-							latency,
-							success,
-							timeout: false,
-						};
+		attempt_deadline += *window;
+
+		// Receive until this attempt's window closes, retry recv on txid mismatch
+		let max_mismatches = 3;
+		for mismatch in 1..=max_mismatches {
+			let now = Instant::now();
+			if now >= attempt_deadline {
+				break;
+			}
+			let remaining = attempt_deadline - now;
+
+			match tokio::time::timeout(remaining, recv_or_error(&socket, &mut buf)).await {
+				Ok(Ok((len, _src))) => {
+					#[cfg(any(test, feature = "fault-injection"))]
+					match crate::fault::next_fault() {
+						crate::fault::Fault::Drop => continue,
+						crate::fault::Fault::Delay(d) => tokio::time::sleep(d).await,
+						crate::fault::Fault::Corrupt => crate::fault::corrupt(&mut buf[..len], rand::random()),
+						crate::fault::Fault::None => {}
 					}
-					Err(_) => {
-						// txid mismatch or parse error, retry recv
-						continue;
+					let latency = start.elapsed();
+					match parse_response(&buf[..len], txid, domain, query_type) {
+						Ok(response) => {
+							return QueryResult {
+								resolver: resolver_label,
+								latency,
+								outcome: QueryOutcome::from_rcode(response.rcode),
+								ttl: response.min_ttl,
+								attempts,
+								late_latency: None,
+								duplicates: drain_duplicates(&socket, &mut buf, txid, domain, query_type),
+							};
+						}
+						Err(e) => {
+							// txid mismatch or parse error, retry recv
+							crate::verbose!("  {}: ignored response for {}: {}", resolver_label, domain, e);
+							match rejection(&buf[..len], txid) {
+								QueryOutcome::ParseError => parse_errors = true,
+								_ => mismatches_exhausted |= mismatch == max_mismatches,
+							}
+							continue;
+						}
 					}
 				}
-			}
-			_ => {
-				// Timeout or recv error
-				break;
+				Ok(Err(e)) => {
+					// Recv error (e.g. ICMP port unreachable): retransmitting will not help
+					return QueryResult {
+						resolver: resolver_label,
+						latency: start.elapsed(),
+						outcome: QueryOutcome::NetworkError(e.kind()),
+						ttl: None,
+						attempts,
+						late_latency: None,
+						duplicates: 0,
+					};
+				}
+				Err(_) => {
+					// Attempt window expired, move on to the next retransmission
+					break;
+				}
 			}
 		}
 	}
 
-	// Exhausted retries or timed out
+	// All attempts timed out; optionally wait out the grace period for a late answer
+	let latency = start.elapsed();
+	let mut late_latency = None;
+	let mut duplicates = 0;
+	if !grace.is_zero() {
+		let deadline = start + timeout + grace;
+		while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+			match tokio::time::timeout(remaining, socket.recv_from(&mut buf)).await {
+				Ok(Ok((len, _src))) => {
+					if parse_response(&buf[..len], txid, domain, query_type).is_ok() {
+						late_latency = Some(start.elapsed());
+						duplicates = drain_duplicates(&socket, &mut buf, txid, domain, query_type);
+						break;
+					}
+				}
+				_ => break,
+			}
+		}
+	}
 	QueryResult {
 		resolver: resolver_label,
-		latency: start.elapsed(),
-		success: false,
-		timeout: true,
+		latency,
+		outcome: match (mismatches_exhausted, parse_errors) {
+			(true, _) => QueryOutcome::TxidMismatchExhausted,
+			(false, true) => QueryOutcome::ParseError,
+			(false, false) => QueryOutcome::Timeout,
+		},
+		ttl: None,
+		attempts: windows.len() as u32,
+		late_latency,
+		duplicates,
 	}
 }
 
-/// Send a single DNS query over TLS (DoT, RFC 7858) and measure latency.
-///
-/// Creates a new TCP+TLS connection per query (no reuse) to measure
-/// cold-start latency including TLS handshake. Uses 2-byte length prefix
-/// per DNS-over-TCP convention.
-async fn send_dot_query(
-	resolver: std::net::SocketAddr,
-	hostname: &str,
-	query_bytes: &[u8],
-	timeout: Duration,
-	_txid: u16,
-	domain: &str,
-	query_type: QueryType,
-) -> QueryResult {
-	let resolver_label = resolver.ip().to_string();
-	let make_timeout_result = || QueryResult {
-		resolver: resolver_label.clone(),
-		latency: timeout,
-		success: false,
-		timeout: true,
-	};
+/// `recv_from` that also wakes on a socket error. Tokio wakes readers only
+/// on readable events, so an ICMP error queued on a connected UDP socket
+/// would otherwise go unseen until the next send.
+async fn recv_or_error(socket: &UdpSocket, buf: &mut [u8]) -> std::io::Result<(usize, SocketAddr)> {
+	use tokio::io::Interest;
+	loop {
+		let ready = socket.ready(Interest::READABLE | Interest::ERROR).await?;
+		if ready.is_error() {
+			if let Some(e) = socket.take_error()? {
+				return Err(e);
+			}
+			// Nothing pending after all: clear the readiness and wait again
+			let _ = socket.try_io(Interest::ERROR, || Err::<(), _>(std::io::ErrorKind::WouldBlock.into()));
+		}
+		if ready.is_readable() {
+			match socket.try_recv_from(buf) {
+				Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+				other => return other,
+			}
+		}
+	}
+}
+
+/// Outcome for a reply that failed to parse as the answer: a transaction
+/// ID mismatch, or a parse error when the ID matched (or was unreadable).
+fn rejection(bytes: &[u8], txid: u16) -> QueryOutcome {
+	match bytes {
+		[hi, lo, ..] if u16::from_be_bytes([*hi, *lo]) != txid => QueryOutcome::TxidMismatchExhausted,
+		_ => QueryOutcome::ParseError,
+	}
+}
+
+/// Answers to this query already queued on `socket` after the one just
+/// read. Reads only what has arrived, so it never delays the query.
+fn drain_duplicates(socket: &UdpSocket, buf: &mut [u8], txid: u16, domain: &str, query_type: QueryType) -> u32 {
+	let mut duplicates = 0;
+	while let Ok((len, _src)) = socket.try_recv_from(buf) {
+		if parse_response(&buf[..len], txid, domain, query_type).is_ok() {
+			duplicates += 1;
+		}
+	}
+	duplicates
+}
 
-	// Build TLS config with system root certificates
+/// TLS connector trusting the bundled web PKI roots, for DoT.
+fn dot_connector() -> TlsConnector {
 	let root_store = rustls::RootCertStore::from_iter(
 		webpki_roots::TLS_SERVER_ROOTS.iter().cloned()
 	);
 	let tls_config = ClientConfig::builder()
 		.with_root_certificates(root_store)
 		.with_no_client_auth();
-	let connector = TlsConnector::from(Arc::new(tls_config));
+	TlsConnector::from(Arc::new(tls_config))
+}
 
-	// Parse SNI hostname
-	let server_name = match rustls::pki_types::ServerName::try_from(hostname.to_string()) {
-		Ok(sn) => sn,
-		Err(_) => {
-			// Fall back to IP-based if hostname doesn't parse
-			match rustls::pki_types::ServerName::try_from(resolver.ip().to_string()) {
-				Ok(sn) => sn,
-				Err(_) => return make_timeout_result(),
-			}
-		}
-	};
+/// SNI name for a DoT resolver: its hostname, else its IP address.
+fn dot_server_name(resolver: SocketAddr, hostname: &str) -> Option<rustls::pki_types::ServerName<'static>> {
+	rustls::pki_types::ServerName::try_from(hostname.to_string())
+		.or_else(|_| rustls::pki_types::ServerName::try_from(resolver.ip().to_string()))
+		.ok()
+}
 
+/// Open a TCP+TLS connection to a DoT resolver, through the proxy if one
+/// is set. None on a timeout or any connection error.
+async fn dot_connect(
+	resolver: SocketAddr,
+	connector: &TlsConnector,
+	server_name: rustls::pki_types::ServerName<'static>,
+	timeout: Duration,
+	proxy: Option<&ProxyConfig>,
+) -> Result<DotStream, QueryOutcome> {
 	let start = Instant::now();
 
 	// TCP connect with timeout
-	let tcp_stream = match tokio::time::timeout(timeout, TcpStream::connect(resolver)).await {
-		Ok(Ok(s)) => s,
-		_ => return make_timeout_result(),
-	};
+	let tcp_stream = io_step(tokio::time::timeout(timeout, connect_via(proxy, resolver)).await)?;
 
 	// TLS handshake with remaining timeout
 	let remaining = timeout.saturating_sub(start.elapsed());
-	let mut tls_stream = match tokio::time::timeout(
-		remaining, connector.connect(server_name, tcp_stream),
-	).await {
-		Ok(Ok(s)) => s,
-		_ => return make_timeout_result(),
-	};
+	io_step(tokio::time::timeout(remaining, connector.connect(server_name, tcp_stream)).await)
+}
+
+/// Result of a timed I/O step: Timeout when the time ran out, else the
+/// I/O error's kind.
+fn io_step<T>(step: Result<std::io::Result<T>, tokio::time::error::Elapsed>) -> Result<T, QueryOutcome> {
+	match step {
+		Ok(Ok(value)) => Ok(value),
+		Ok(Err(e)) => Err(QueryOutcome::NetworkError(e.kind())),
+		Err(_) => Err(QueryOutcome::Timeout),
+	}
+}
+
+/// One DNS exchange over a new TCP+TLS connection. Fails with Timeout or
+/// the connection error.
+async fn dot_exchange(
+	resolver: SocketAddr,
+	connector: &TlsConnector,
+	server_name: rustls::pki_types::ServerName<'static>,
+	query_bytes: &[u8],
+	timeout: Duration,
+	proxy: Option<&ProxyConfig>,
+) -> Result<Vec<u8>, QueryOutcome> {
+	let start = Instant::now();
+	let mut tls_stream = dot_connect(resolver, connector, server_name, timeout, proxy).await?;
+	dot_stream_exchange(&mut tls_stream, query_bytes, timeout.saturating_sub(start.elapsed())).await
+}
+
+/// One DNS exchange on an open DoT connection, with the 2-byte length
+/// prefix of DNS over TCP. Fails on a timeout, a closed connection, or any
+/// other error; the connection is then unusable.
+async fn dot_stream_exchange(
+	tls_stream: &mut DotStream,
+	query_bytes: &[u8],
+	timeout: Duration,
+) -> Result<Vec<u8>, QueryOutcome> {
+	let start = Instant::now();
 
 	// Send DNS query with 2-byte TCP length prefix
 	let len_prefix = (query_bytes.len() as u16).to_be_bytes();
 	let remaining = timeout.saturating_sub(start.elapsed());
-	let send_result = tokio::time::timeout(remaining, async {
+	io_step(tokio::time::timeout(remaining, async {
 		tls_stream.write_all(&len_prefix).await?;
 		tls_stream.write_all(query_bytes).await?;
 		tls_stream.flush().await
-	}).await;
-	if send_result.is_err() || send_result.unwrap().is_err() {
-		return make_timeout_result();
-	}
+	}).await)?;
 
 	// Read 2-byte response length prefix
 	let remaining = timeout.saturating_sub(start.elapsed());
-	let resp_len = match tokio::time::timeout(remaining, async {
+	let resp_len = io_step(tokio::time::timeout(remaining, async {
 		let mut len_buf = [0u8; 2];
 		tls_stream.read_exact(&mut len_buf).await?;
 		Ok::<u16, std::io::Error>(u16::from_be_bytes(len_buf))
-	}).await {
-		Ok(Ok(len)) => len as usize,
-		_ => return make_timeout_result(),
-	};
+	}).await)? as usize;
 
 	// Read response body
 	let remaining = timeout.saturating_sub(start.elapsed());
-	let resp_bytes = match tokio::time::timeout(remaining, async {
+	io_step(tokio::time::timeout(remaining, async {
 		let mut buf = vec![0u8; resp_len];
 		tls_stream.read_exact(&mut buf).await?;
 		Ok::<Vec<u8>, std::io::Error>(buf)
-	}).await {
-		Ok(Ok(buf)) => buf,
-		_ => return make_timeout_result(),
+	}).await)
+}
+
+/// One DNS exchange on an idle pooled DoT connection if there is one,
+/// else on a new connection, which is then kept for later queries (reuse
+/// mode). A pooled connection the server has since closed is replaced by
+/// a new one within the same timeout.
+async fn dot_pooled_exchange(
+	resolver: SocketAddr,
+	connector: &TlsConnector,
+	server_name: rustls::pki_types::ServerName<'static>,
+	query_bytes: &[u8],
+	timeout: Duration,
+	connections: &ConnectionPool,
+) -> Result<Vec<u8>, QueryOutcome> {
+	let start = Instant::now();
+	if let Some(mut tls_stream) = connections.take_dot(resolver) {
+		if let Ok(reply) = dot_stream_exchange(&mut tls_stream, query_bytes, timeout).await {
+			connections.keep_dot(resolver, tls_stream);
+			return Ok(reply);
+		}
+	}
+	let proxy = connections.options.proxy.as_ref();
+	let mut tls_stream = dot_connect(resolver, connector, server_name, timeout.saturating_sub(start.elapsed()), proxy).await?;
+	let reply = dot_stream_exchange(&mut tls_stream, query_bytes, timeout.saturating_sub(start.elapsed())).await?;
+	connections.keep_dot(resolver, tls_stream);
+	Ok(reply)
+}
+
+/// Send a single DNS query over TLS (DoT, RFC 7858) and measure latency.
+///
+/// In reuse mode the query goes over a kept-alive connection when one is
+/// idle, so latency is the steady-state exchange; a query that has to
+/// connect pays the handshake. In per-query mode every query opens a new
+/// TCP+TLS connection with a full handshake. Uses 2-byte length prefix
+/// per DNS-over-TCP convention.
+#[allow(clippy::too_many_arguments)]
+async fn send_dot_query(
+	resolver: std::net::SocketAddr,
+	hostname: &str,
+	query_bytes: &[u8],
+	timeout: Duration,
+	_txid: u16,
+	domain: &str,
+	query_type: QueryType,
+	connections: &ConnectionPool,
+) -> QueryResult {
+	let resolver_label = resolver.ip().to_string();
+	let make_failed_result = |outcome: QueryOutcome| QueryResult {
+		resolver: resolver_label.clone(),
+		latency: timeout,
+		outcome,
+		ttl: None,
+		attempts: 1,
+		late_latency: None,
+		duplicates: 0,
 	};
 
+	// TLS setup stays outside the timed exchange
+	let connector = connections.dot_connector();
+	let server_name = match dot_server_name(resolver, hostname) {
+		Some(sn) => sn,
+		None => return make_failed_result(QueryOutcome::NetworkError(std::io::ErrorKind::InvalidInput)),
+	};
+
+	let start = Instant::now();
+	let resp_bytes = match dot_pooled_exchange(resolver, &connector, server_name, query_bytes, timeout, connections).await {
+		Ok(bytes) => bytes,
+		Err(outcome) => return make_failed_result(outcome),
+	};
 	let latency = start.elapsed();
 
 	// Parse the DNS response
 	match parse_response(&resp_bytes, _txid, domain, query_type) {
 		Ok(response) => {
-			let success = response.rcode == ResponseCode::NoError;
 			QueryResult {
 				resolver: resolver_label,
 				latency,
-				success,
-				timeout: false,
+				outcome: QueryOutcome::from_rcode(response.rcode),
+				ttl: response.min_ttl,
+				attempts: 1,
+				late_latency: None,
+				duplicates: 0,
+			}
+		}
+		Err(e) => {
+			crate::verbose!("  {} (DoT): unparseable response for {}: {}", resolver_label, domain, e);
+			make_failed_result(rejection(&resp_bytes, _txid))
+		}
+	}
+}
+
+/// One DNS exchange over HTTPS (RFC 8484): a POST body or GET `dns=`
+/// parameter, as `options` says. Fails with Timeout or the HTTP error.
+async fn doh_exchange(
+	url: &str,
+	options: &DohOptions,
+	query_bytes: &[u8],
+	timeout: Duration,
+	client: &reqwest::Client,
+) -> Result<Vec<u8>, QueryOutcome> {
+	let start = Instant::now();
+
+	// Build the request: POST body or GET dns= parameter (RFC 8484)
+	let has_header = |name: &str| options.headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(name));
+	let mut request = match options.method {
+		DohMethod::Post => {
+			let mut req = client.post(url).body(query_bytes.to_vec());
+			if !has_header("content-type") {
+				req = req.header("Content-Type", "application/dns-message");
+			}
+			req
+		}
+		DohMethod::Get => {
+			use base64::Engine;
+			let encoded = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(query_bytes);
+			client.get(url).query(&[("dns", encoded)])
+		}
+	};
+	if !has_header("accept") {
+		request = request.header("Accept", "application/dns-message");
+	}
+	// reqwest appends headers, so defaults above are skipped when overridden
+	for (name, value) in &options.headers {
+		request = request.header(name.as_str(), value.as_str());
+	}
+
+	let response = http_step(tokio::time::timeout(timeout, request.send()).await)?;
+
+	// Read response body
+	let remaining = timeout.saturating_sub(start.elapsed());
+	let body = http_step(tokio::time::timeout(remaining, response.bytes()).await)?;
+	Ok(body.to_vec())
+}
+
+/// Result of a timed HTTP step: Timeout when the time ran out, else the
+/// kind of the I/O error behind the failure (`Other` when there is none).
+fn http_step<T>(step: Result<reqwest::Result<T>, tokio::time::error::Elapsed>) -> Result<T, QueryOutcome> {
+	match step {
+		Ok(Ok(value)) => Ok(value),
+		Ok(Err(e)) if e.is_timeout() => Err(QueryOutcome::Timeout),
+		Ok(Err(e)) => {
+			let mut source = std::error::Error::source(&e);
+			while let Some(err) = source {
+				if let Some(io) = err.downcast_ref::<std::io::Error>() {
+					return Err(QueryOutcome::NetworkError(io.kind()));
+				}
+				source = err.source();
 			}
+			Err(QueryOutcome::NetworkError(std::io::ErrorKind::Other))
 		}
-		Err(_) => make_timeout_result(),
+		Err(_) => Err(QueryOutcome::Timeout),
 	}
 }
 
 /// Send a single DNS query over HTTPS (DoH, RFC 8484) and measure latency.
 ///
-/// Uses a shared reqwest::Client per resolver for HTTP/2 connection reuse,
-/// which reflects how DoH works in practice.
+/// In reuse mode the resolver's shared reqwest::Client keeps its HTTP/2
+/// connection open, which reflects how DoH works in practice; in per-query
+/// mode the caller passes a new client, so each query connects afresh.
+/// Method and extra headers come from the resolver's DohOptions.
 async fn send_doh_query(
 	url: &str,
+	options: &DohOptions,
 	query_bytes: &[u8],
 	timeout: Duration,
 	domain: &str,
 	query_type: QueryType,
 	client: &reqwest::Client,
 ) -> QueryResult {
-	let make_timeout_result = || QueryResult {
+	let make_failed_result = |outcome: QueryOutcome| QueryResult {
 		resolver: url.to_string(),
 		latency: timeout,
-		success: false,
-		timeout: true,
+		outcome,
+		ttl: None,
+		attempts: 1,
+		late_latency: None,
+		duplicates: 0,
 	};
 
 	let start = Instant::now();
-
-	// POST DNS query as application/dns-message (RFC 8484)
-	let response = match tokio::time::timeout(timeout, async {
-		client.post(url)
-			.header("Content-Type", "application/dns-message")
-			.header("Accept", "application/dns-message")
-			.body(query_bytes.to_vec())
-			.send()
-			.await
-	}).await {
-		Ok(Ok(r)) => r,
-		_ => return make_timeout_result(),
-	};
-
-	// Read response body
-	let remaining = timeout.saturating_sub(start.elapsed());
-	let resp_bytes = match tokio::time::timeout(remaining, response.bytes()).await {
-		Ok(Ok(b)) => b,
-		_ => return make_timeout_result(),
+	let resp_bytes = match doh_exchange(url, options, query_bytes, timeout, client).await {
+		Ok(bytes) => bytes,
+		Err(outcome) => return make_failed_result(outcome),
 	};
-
 	let latency = start.elapsed();
 
 	// Parse the DNS wire-format response
@@ -388,12 +728,14 @@ async fn send_doh_query(
 	match parse_response(&resp_bytes, 0, domain, query_type) {
 		Ok(response) => {
 			// Accept even if txid doesn't match (DoH handles correlation via HTTP)
-			let success = response.rcode == ResponseCode::NoError;
 			QueryResult {
 				resolver: url.to_string(),
 				latency,
-				success,
-				timeout: false,
+				outcome: QueryOutcome::from_rcode(response.rcode),
+				ttl: response.min_ttl,
+				attempts: 1,
+				late_latency: None,
+				duplicates: 0,
 			}
 		}
 		Err(_) => {
@@ -401,61 +743,165 @@ async fn send_doh_query(
 			if resp_bytes.len() >= 2 {
 				let resp_txid = u16::from_be_bytes([resp_bytes[0], resp_bytes[1]]);
 				if let Ok(response) = parse_response(&resp_bytes, resp_txid, domain, query_type) {
-					let success = response.rcode == ResponseCode::NoError;
 					return QueryResult {
 						resolver: url.to_string(),
 						latency,
-						success,
-						timeout: false,
+						outcome: QueryOutcome::from_rcode(response.rcode),
+						ttl: response.min_ttl,
+						attempts: 1,
+						late_latency: None,
+						duplicates: 0,
 					};
 				}
 			}
-			make_timeout_result()
+			crate::verbose!("  {} (DoH): unparseable response for {}", url, domain);
+			make_failed_result(QueryOutcome::ParseError)
 		}
 	}
 }
 
+/// Check EDNS padding (RFC 7830, RFC 8467) on an encrypted transport.
+///
+/// Sends a padded query over DoT or DoH; padding hides the query and
+/// answer length from an observer of the encrypted stream. Some(true)
+/// when the reply carries a Padding option, Some(false) when it does
+/// not, None for plain UDP resolvers or when nothing usable came back.
+pub async fn check_edns_padding(
+	resolver: &Resolver,
+	timeout: Duration,
+	connections: &ConnectionPool,
+) -> Option<bool> {
+	let query = crate::dns::build_padded_query("example.com", rand::random(), crate::dns::PADDING_QUERY_BLOCK).ok()?;
+	let reply = match &resolver.transport {
+		DnsTransport::Udp => return None,
+		DnsTransport::Dot { hostname } => {
+			let server_name = dot_server_name(resolver.addr, hostname)?;
+			dot_exchange(resolver.addr, &dot_connector(), server_name, &query, timeout, connections.options.proxy.as_ref()).await.ok()?
+		}
+		DnsTransport::Doh { url, options } => {
+			doh_exchange(url, options, &query, timeout, &connections.doh_client(url, resolver.addr)?).await.ok()?
+		}
+	};
+	crate::dns::has_padding(&reply)
+}
+
 /// Dispatch a query to the appropriate transport based on resolver address and transport.
+///
+/// `retries` applies to UDP only; DoT and DoH run over TCP, which
+/// retransmits lost segments itself.
 #[allow(clippy::too_many_arguments)]
 async fn dispatch_query(
 	addr: SocketAddr,
 	transport: &DnsTransport,
 	query_bytes: &[u8],
 	timeout: Duration,
+	retries: u32,
+	grace: Duration,
 	txid: u16,
 	domain: &str,
 	query_type: QueryType,
-	doh_clients: &DohClientPool,
+	connections: &ConnectionPool,
 ) -> QueryResult {
-	match transport {
+	let result = match transport {
 		DnsTransport::Udp => {
-			send_udp_query(addr, query_bytes, timeout, txid, domain, query_type).await
+			send_udp_query(addr, query_bytes, timeout, retries, grace, txid, domain, query_type).await
 		}
 		DnsTransport::Dot { hostname } => {
 			send_dot_query(
 				addr, hostname, query_bytes, timeout,
-				txid, domain, query_type,
+				txid, domain, query_type, connections,
 			).await
 		}
-		DnsTransport::Doh { url } => {
-			let client = doh_clients.get(url).expect("DoH client not found");
-			send_doh_query(url, query_bytes, timeout, domain, query_type, client).await
+		DnsTransport::Doh { url, options } => {
+			let client = connections.doh_client(url, addr).expect("DoH client not found");
+			send_doh_query(url, options, query_bytes, timeout, domain, query_type, &client).await
+		}
+	};
+	crate::debug!("  {} {} {} {:?}: {}", transport, result.resolver, domain, query_type, query_outcome(&result));
+	result
+}
+
+/// One-word outcome of a query with its latency, for -vv traces.
+fn query_outcome(result: &QueryResult) -> String {
+	let ms = result.latency.as_secs_f64() * 1000.0;
+	let outcome = match result.outcome {
+		QueryOutcome::Ok => format!("{:.1} ms", ms),
+		QueryOutcome::Rcode(_) => format!("{} in {:.1} ms", result.outcome, ms),
+		_ => format!("{} after {:.1} ms", result.outcome, ms),
+	};
+	if result.attempts > 1 {
+		format!("{} ({} sends)", outcome, result.attempts)
+	} else {
+		outcome
+	}
+}
+
+/// Send one A query for `domain` to a resolver over its configured transport.
+///
+/// Convenience wrapper for single-shot probes outside the round-based
+/// benchmark (e.g. monitor mode checks).
+pub async fn probe_query(
+	resolver: &Resolver,
+	domain: &str,
+	timeout: Duration,
+	dnssec: bool,
+	connections: &ConnectionPool,
+) -> QueryResult {
+	let txid: u16 = rand::random();
+	let query_bytes = match build_query(domain, QueryType::A, txid, dnssec) {
+		Ok(bytes) => bytes,
+		Err(_) => {
+			return QueryResult {
+				resolver: resolver.addr.ip().to_string(),
+				latency: Duration::ZERO,
+				outcome: QueryOutcome::NetworkError(std::io::ErrorKind::InvalidInput),
+				ttl: None,
+				attempts: 1,
+				late_latency: None,
+				duplicates: 0,
+			};
 		}
+	};
+	dispatch_query(
+		resolver.addr, &resolver.transport, &query_bytes, timeout, 0, Duration::ZERO,
+		txid, domain, QueryType::A, connections,
+	).await
+}
+
+/// Client for DoH queries to `url` over the given HTTP version, through
+/// the proxy if one is set. The URL's host is pinned to `addr`, the
+/// address the resolver was bootstrapped to, so reqwest does not look it
+/// up again through the system resolver and the address shown in the
+/// results is the server that was measured.
+fn new_doh_client(url: &str, addr: SocketAddr, version: DohHttpVersion, proxy: Option<&ProxyConfig>) -> reqwest::Client {
+	let mut builder = crate::source::apply_to_client(reqwest::Client::builder().use_rustls_tls());
+	if let Some(host) = reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(String::from)) {
+		builder = builder.resolve(&host, addr);
 	}
+	builder = match version {
+		DohHttpVersion::Http1 => builder.http1_only(),
+		DohHttpVersion::Http2 => builder.http2_prior_knowledge(),
+	};
+	if let Some(p) = proxy {
+		builder = builder.proxy(reqwest::Proxy::all(&p.url).expect("proxy URL was validated by parse_proxy"));
+	}
+	builder.build().expect("failed to build DoH HTTP client")
 }
 
-/// Build a DoH client pool with one reqwest::Client per DoH resolver URL.
-pub fn build_doh_client_pool(resolvers: &[Resolver]) -> DohClientPool {
-	let mut pool = HashMap::new();
+/// Build the connection pool: one reqwest::Client per DoH resolver URL and
+/// address, and in reuse mode a shared connector for the DoT resolvers.
+pub fn build_connection_pool(resolvers: &[Resolver], options: &ConnectionOptions) -> ConnectionPool {
+	let mut pool = ConnectionPool { options: options.clone(), ..Default::default() };
 	for r in resolvers {
-		if let DnsTransport::Doh { url } = &r.transport {
-			pool.entry(url.clone()).or_insert_with(|| {
-				reqwest::Client::builder()
-					.use_rustls_tls()
-					.http2_prior_knowledge()
-					.build()
-					.expect("failed to build DoH HTTP client")
-			});
+		match &r.transport {
+			DnsTransport::Doh { url, .. } => {
+				pool.doh.entry((r.key(), r.addr))
+					.or_insert_with(|| new_doh_client(url, r.addr, options.doh_http, options.proxy.as_ref()));
+			}
+			DnsTransport::Dot { .. } if options.mode == ConnectionMode::Reuse => {
+				pool.dot_connector.get_or_insert_with(dot_connector);
+			}
+			_ => {}
 		}
 	}
 	pool
@@ -471,6 +917,59 @@ struct QueryTask {
 	domain: String,
 	query_type: QueryType,
 	set_name: String,
+	/// Benchmark round this task was sent in (0-based)
+	round: u32,
+	/// Seed for this query's txid, spacing jitter, and cold-zone label, drawn
+	/// from the run RNG each round so a --seed run sends identical packets
+	seed: u64,
+}
+
+impl QueryTask {
+	/// Aggregation key of the resolver this task queries (see `resolver_key`).
+	fn key(&self) -> String {
+		crate::transport::resolver_key(self.resolver_addr, &self.resolver_transport)
+	}
+}
+
+/// Per-resolver fairness limits for the benchmark: an in-flight cap and an
+/// optional send-rate pacer, keyed by resolver IP (what a rate limiter sees).
+struct ResolverLimiter {
+	inflight: Arc<Semaphore>,
+	/// Earliest time the next query may be sent; None without a QPS limit
+	next_send: Option<std::sync::Mutex<Instant>>,
+	interval: Duration,
+}
+
+impl ResolverLimiter {
+	fn new(max_inflight: usize, qps: Option<u32>) -> Self {
+		ResolverLimiter {
+			inflight: Arc::new(Semaphore::new(max_inflight)),
+			next_send: qps.map(|_| std::sync::Mutex::new(Instant::now())),
+			interval: qps.map(|q| Duration::from_secs(1) / q).unwrap_or_default(),
+		}
+	}
+
+	/// Wait for this resolver's next send slot under the QPS limit.
+	async fn pace(&self) {
+		let slot = match &self.next_send {
+			Some(next) => match next.lock() {
+				Ok(mut next) => reserve_send_slot(&mut next, Instant::now(), self.interval),
+				Err(_) => return,
+			},
+			None => return,
+		};
+		tokio::time::sleep_until(slot.into()).await;
+	}
+}
+
+/// Reserve the next send slot at least `interval` after the previous one.
+///
+/// Returns when the caller may send and advances `next` past it. A slot is
+/// never in the past, so idle time does not build up a burst allowance.
+fn reserve_send_slot(next: &mut Instant, now: Instant, interval: Duration) -> Instant {
+	let slot = (*next).max(now);
+	*next = slot + interval;
+	slot
 }
 
 /// Run NXDOMAIN interception characterization for all resolvers.
@@ -484,10 +983,19 @@ pub async fn run_characterization(
 ) {
 	let timeout = config.timeout;
 
+	// A transparent port 53 proxy answers for every UDP resolver, so say so
+	// before any result is trusted
+	if crate::dns::check_port53_interception(timeout).await == Some(true) {
+		crate::info!("WARNING: port 53 traffic on this network is intercepted. A recursive query sent to");
+		crate::info!("  a.root-servers.net ({}) got a recursive reply, so UDP resolvers below are answered", crate::dns::INTERCEPT_PROBE_ADDR.ip());
+		crate::info!("  by the interceptor, not the resolver you asked. Use DoT or DoH resolvers to get past it.");
+		crate::info!();
+	}
+
 	// Phase 0: v2-style reachability pre-check
 	let char_timeout = Duration::from_millis(crate::transport::DEFAULT_CHAR_TIMEOUT_MS);
 	let char_attempts = crate::transport::DEFAULT_CHAR_ATTEMPTS;
-	println!("Reachability pre-check ({} resolvers, {} attempts, {} ms timeout)...",
+	crate::info!("Reachability pre-check ({} resolvers, {} attempts, {} ms timeout)...",
 		records.len(), char_attempts, char_timeout.as_millis());
 
 	let semaphore = std::sync::Arc::new(Semaphore::new(32));
@@ -511,6 +1019,7 @@ pub async fn run_characterization(
 			let mut any_fast = false;
 			let mut success_latencies: Vec<f64> = Vec::with_capacity(3);
 			let mut attempts_tried = 0u32;
+			let mut refused = None;
 			// Collect up to 3 successful latencies for median calculation
 			for _ in 0..attempts {
 				attempts_tried += 1;
@@ -521,18 +1030,22 @@ pub async fn run_characterization(
 					Ok(b) => b,
 					Err(_) => continue,
 				};
-				let result = send_udp_query(addr, &query_bytes, ct, txid, "google.com", crate::transport::QueryType::A).await;
-				if result.success {
+				let result = send_udp_query(addr, &query_bytes, ct, 0, Duration::ZERO, txid, "google.com", crate::transport::QueryType::A).await;
+				if result.outcome.is_ok() {
 					any_fast = true;
 					success_latencies.push(result.latency.as_secs_f64() * 1000.0);
 					// Stop once we have 3 successes
 					if success_latencies.len() >= 3 {
 						break;
 					}
+				} else if result.outcome.is_unreachable() && !any_fast {
+					// ICMP says nothing listens there; more attempts would only wait
+					refused = Some(result.outcome);
+					break;
 				}
 			}
 			done.fetch_add(1, Ordering::Relaxed);
-			(i, any_fast, success_latencies, attempts_tried)
+			(i, any_fast, success_latencies, attempts_tried, refused)
 		}));
 	}
 
@@ -540,10 +1053,12 @@ pub async fn run_characterization(
 	let mut reach_latency = vec![0.0f64; records.len()];
 	let mut reach_attempts_used = vec![0u32; records.len()];
 	let mut reach_successes = vec![0u32; records.len()];
+	let mut reach_refused = vec![None; records.len()];
 	for handle in reachability_handles {
 		match handle.await {
-			Ok((idx, is_reachable, mut success_latencies, attempts_tried)) => {
+			Ok((idx, is_reachable, mut success_latencies, attempts_tried, refused)) => {
 				reachable[idx] = is_reachable;
+				reach_refused[idx] = refused;
 				reach_successes[idx] = success_latencies.len() as u32;
 				// Compute median latency from successful attempts
 				if !success_latencies.is_empty() {
@@ -554,7 +1069,7 @@ pub async fn run_characterization(
 				reach_attempts_used[idx] = attempts_tried;
 			}
 			Err(e) => {
-				eprintln!("Warning: reachability check failed: {}", e);
+				crate::warn!("reachability check failed: {}", e);
 			}
 		}
 	}
@@ -564,20 +1079,23 @@ pub async fn run_characterization(
 	let before = records.len();
 	for (i, rec) in records.iter().enumerate() {
 		if !reachable[i] {
-			config.telemetry.log_sidelined(
-				&rec.resolver.addr.ip().to_string(), "reachability_precheck", 0,
-			);
+			let reason = match reach_refused[i] {
+				Some(outcome) => format!("reachability_precheck, unreachable, {}", outcome),
+				None => "reachability_precheck".to_string(),
+			};
+			config.telemetry.log_sidelined(&rec.resolver.addr.ip().to_string(), &reason, 0);
 			let class = rec.resolver.class;
 			if class != "public" {
-				println!("  {} {} ({}) -- sidelined (reachability precheck)",
-					class, rec.resolver.label, rec.resolver.addr.ip());
+				let detail = if reach_refused[i].is_some() { "unreachable" } else { "no answer" };
+				crate::info!("  {} {} ({}) -- sidelined (reachability precheck, {})",
+					class, rec.resolver.label, rec.resolver.addr.ip(), detail);
 			}
 		}
 	}
 	// Build reachability stats map before removing unreachable records
 	let reach_stats: HashMap<String, (f64, u32, u32)> = records.iter().enumerate()
 		.filter(|(i, _)| reachable[*i])
-		.map(|(i, r)| (r.resolver.addr.ip().to_string(), (reach_latency[i], reach_attempts_used[i], reach_successes[i])))
+		.map(|(i, r)| (r.resolver.key(), (reach_latency[i], reach_attempts_used[i], reach_successes[i])))
 		.collect();
 	let mut idx = 0;
 	records.retain(|_| {
@@ -586,11 +1104,12 @@ pub async fn run_characterization(
 		keep
 	});
 	let sidelined = before - records.len();
-	println!("  {} reachable, {} sidelined, {} total", records.len(), sidelined, before);
-	println!();
+	let unreachable = reach_refused.iter().filter(|r| r.is_some()).count();
+	crate::info!("  {} reachable, {} sidelined ({} unreachable), {} total", records.len(), sidelined, unreachable, before);
+	crate::info!();
 
 	// Phase 1: NXDOMAIN interception check
-	println!("Checking NXDOMAIN interception ({} resolvers)...", records.len());
+	crate::info!("Checking NXDOMAIN interception ({} resolvers)...", records.len());
 	let phase1_total = records.len();
 	let phase1_done = Arc::new(AtomicUsize::new(0));
 	let phase1_start = Instant::now();
@@ -614,26 +1133,24 @@ pub async fn run_characterization(
 		}));
 	}
 
-	let mut nxdomain_results = vec![false; records.len()];
-	let mut nxdomain_intercept_count = 0usize;
+	let mut nxdomain_results = vec![None; records.len()];
 	for handle in handles {
 		match handle.await {
 			Ok((idx, intercepts)) => {
 				nxdomain_results[idx] = intercepts;
-				if intercepts { nxdomain_intercept_count += 1; }
 			}
 			Err(e) => {
-				eprintln!("Warning: characterization task failed: {}", e);
+				crate::warn!("characterization task failed: {}", e);
 			}
 		}
 	}
 	stop_progress_monitor(monitor, "NXDOMAIN check", phase1_total, phase1_start);
-	println!("  {} intercept NXDOMAIN, {} OK",
-		nxdomain_intercept_count, records.len() - nxdomain_intercept_count);
-	println!();
+	let count = |verdict: Option<bool>| nxdomain_results.iter().filter(|r| **r == verdict).count();
+	crate::info!("  {} intercept NXDOMAIN, {} OK, {} inconclusive", count(Some(true)), count(Some(false)), count(None));
+	crate::info!();
 
 	// Phase 2: Check rebinding protection
-	println!("Checking DNS rebinding protection ({} resolvers)...", records.len());
+	crate::info!("Checking DNS rebinding protection ({} resolvers)...", records.len());
 	let phase2_total = records.len();
 	let phase2_done = Arc::new(AtomicUsize::new(0));
 	let phase2_start = Instant::now();
@@ -671,17 +1188,17 @@ pub async fn run_characterization(
 				}
 			}
 			Err(e) => {
-				eprintln!("Warning: rebinding check failed: {}", e);
+				crate::warn!("rebinding check failed: {}", e);
 			}
 		}
 	}
 	stop_progress_monitor(monitor, "Rebinding check", phase2_total, phase2_start);
-	println!("  {} protected, {} not protected, {} unknown",
+	crate::info!("  {} protected, {} not protected, {} unknown",
 		rebind_protected, rebind_not, rebind_unknown);
-	println!();
+	crate::info!();
 
 	// Phase 3: Check DNSSEC validation
-	println!("Checking DNSSEC validation ({} resolvers)...", records.len());
+	crate::info!("Checking DNSSEC validation ({} resolvers)...", records.len());
 	let phase3_total = records.len();
 	let phase3_done = Arc::new(AtomicUsize::new(0));
 	let phase3_start = Instant::now();
@@ -719,18 +1236,64 @@ pub async fn run_characterization(
 				}
 			}
 			Err(e) => {
-				eprintln!("Warning: DNSSEC validation check failed: {}", e);
+				crate::warn!("DNSSEC validation check failed: {}", e);
 			}
 		}
 	}
 	stop_progress_monitor(monitor, "DNSSEC check", phase3_total, phase3_start);
-	println!("  {} validate, {} do not validate, {} unknown",
+	crate::info!("  {} validate, {} do not validate, {} unknown",
 		dnssec_validates, dnssec_not, dnssec_unknown);
+	crate::info!();
+
+	// Phase 4: Known-record check (captive portal / hijack)
+	crate::info!("Checking known-record answers ({} resolvers)...", records.len());
+	let phase4_total = records.len();
+	let phase4_done = Arc::new(AtomicUsize::new(0));
+	let phase4_start = Instant::now();
+	let monitor = spawn_progress_monitor(
+		"Known-record check".to_string(), phase4_done.clone(), phase4_total, phase4_start,
+	);
+
+	let mut known_handles = Vec::new();
+	for (i, rec) in records.iter().enumerate() {
+		let addr = rec.resolver.addr;
+		let sem = semaphore.clone();
+		let tm = timeout;
+		let done = phase4_done.clone();
+
+		known_handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let correct = crate::dns::check_known_record(addr, tm).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(i, correct)
+		}));
+	}
+
+	let mut known_results: Vec<Option<bool>> = vec![None; records.len()];
+	for handle in known_handles {
+		match handle.await {
+			Ok((idx, correct)) => known_results[idx] = correct,
+			Err(e) => {
+				crate::warn!("known-record check failed: {}", e);
+			}
+		}
+	}
+	stop_progress_monitor(monitor, "Known-record check", phase4_total, phase4_start);
+	let known_wrong = known_results.iter().filter(|r| **r == Some(false)).count();
+	let known_ok = known_results.iter().filter(|r| **r == Some(true)).count();
+	crate::info!("  {} correct, {} wrong, {} unknown",
+		known_ok, known_wrong, records.len() - known_ok - known_wrong);
+	for (i, rec) in records.iter().enumerate() {
+		if known_results[i] == Some(false) {
+			crate::info!("  {} ({}) -- wrong answer for the root NS set (captive portal or hijack?)",
+				rec.resolver.label, rec.resolver.addr.ip());
+		}
+	}
 
 	// Build CharacterizationResult for each record and log telemetry
 	for (i, rec) in records.iter_mut().enumerate() {
 		let ip_str = rec.resolver.addr.ip().to_string();
-		let (lat, attempts_used, successes) = reach_stats.get(&ip_str).copied().unwrap_or((0.0, 0, 0));
+		let (lat, attempts_used, successes) = reach_stats.get(&rec.resolver.key()).copied().unwrap_or((0.0, 0, 0));
 		let intercepts = nxdomain_results[i];
 		let rebinding = rebind_results[i];
 		let dnssec = dnssec_results[i];
@@ -744,10 +1307,27 @@ pub async fn run_characterization(
 			intercepts_nxdomain: intercepts,
 			rebinding_protection: rebinding,
 			validates_dnssec: dnssec,
+			answers_known_record: known_results[i],
+			filtering: None,
+			edns_udp_size: None,
+			server_cookie: None,
+			preserves_case: None,
+			edns_buffer: None,
+			large_responses: None,
+			rd_zero: None,
+			https_rr: None,
+			pads_responses: None,
+			dns64: None,
+			ttl_rewrite: None,
+			coalescing: None,
 		});
 
 		// Log telemetry
-		let nxdomain_str = if intercepts { "intercepts" } else { "ok" };
+		let nxdomain_str = match intercepts {
+			Some(true) => "intercepts",
+			Some(false) => "ok",
+			None => "inconclusive",
+		};
 		let rebinding_str = match rebinding {
 			Some(true) => "protected",
 			Some(false) => "not_protected",
@@ -765,27 +1345,154 @@ pub async fn run_characterization(
 		);
 	}
 
-	println!();
+	crate::info!();
 }
 
-/// Run discovery prefilter to narrow a large resolver list to the best N.
+/// Run the probes only the `characterize` subcommand needs: ad/malware
+/// filtering, EDNS support, DNS cookies, 0x20 case handling, the EDNS
+/// buffer size probe, large responses, RD=0 (cache snooping) behavior,
+/// HTTPS records, EDNS padding on DoT and DoH, DNS64, TTL rewriting, and
+/// duplicate query coalescing.
 ///
-/// Phase 1: fast parallel screen with 1 query per resolver (500ms timeout).
-/// Phase 2: parallel warm-only benchmark on survivors, keep top N by p50.
-pub async fn run_discovery(
+/// Expects `run_characterization` to have run first; records without a
+/// characterization result are skipped.
+pub async fn run_capability_probes(
 	records: &mut [crate::record::ResolverRecord],
-	categories: &std::collections::BTreeMap<String, Vec<String>>,
 	config: &BenchmarkConfig,
-	doh_clients: &DohClientPool,
 ) {
-	println!("Discovery mode: screening {} resolvers...", records.len());
+	crate::info!("Checking filtering, EDNS support, cookies, 0x20, buffer sizes, large responses, RD=0, HTTPS records, padding, DNS64, TTL rewriting, and query coalescing ({} resolvers)...", records.len());
+	let resolvers: Vec<Resolver> = records.iter().map(|r| r.resolver.clone()).collect();
+	let connections = Arc::new(build_connection_pool(&resolvers, &ConnectionOptions::from(config)));
+	let total = records.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	// Authoritative TTLs are the same for every resolver; look them up once
+	let addrs: Vec<std::net::SocketAddr> = records.iter().map(|r| r.resolver.addr).collect();
+	let ttl_references = crate::dns::ttl_references(&addrs, config.timeout).await;
+	let monitor = spawn_progress_monitor("Capability probes".to_string(), done.clone(), total, start);
 
-	// Pick discovery domains from the first category with enough entries
-	let discovery_domains: &[String] = categories.values()
-		.find(|domains| domains.len() >= 5)
-		.or_else(|| categories.values().next())
-		.map(|v| v.as_slice())
-		.unwrap_or(&[]);
+	let semaphore = Arc::new(Semaphore::new(32));
+	let mut handles = Vec::new();
+	for (i, rec) in records.iter().enumerate() {
+		let addr = rec.resolver.addr;
+		let resolver = rec.resolver.clone();
+		let connections = connections.clone();
+		let cold_zone = config.cold_zone.clone();
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let filtering = crate::dns::check_filtering(addr, tm).await;
+			let edns = crate::dns::check_edns_support(addr, tm).await;
+			let cookie = crate::dns::check_cookie_support(addr, tm).await;
+			let case = crate::dns::check_case_preservation(addr, tm).await;
+			let buffer = crate::dns::check_edns_buffer(addr, tm).await;
+			let large = crate::dns::check_large_responses(addr, tm).await;
+			let rd_zero = crate::dns::check_rd_zero(addr, tm).await;
+			let https_rr = crate::dns::check_https_rr(addr, tm).await;
+			let padding = check_edns_padding(&resolver, tm, &connections).await;
+			let dns64 = crate::dns::check_dns64(addr, tm).await;
+			let ttl_rewrite = crate::dns::check_ttl_rewrite(addr, ttl_references, tm).await;
+			let coalescing = crate::dns::check_coalescing(addr, cold_zone.as_deref(), tm).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(i, filtering, edns, cookie, case, buffer, large, rd_zero, https_rr, padding, dns64, ttl_rewrite, coalescing)
+		}));
+	}
+	for handle in handles {
+		match handle.await {
+			Ok((idx, filtering, edns, cookie, case, buffer, large, rd_zero, https_rr, padding, dns64, ttl_rewrite, coalescing)) => {
+				if let Some(c) = records[idx].characterization.as_mut() {
+					c.filtering = filtering;
+					c.edns_udp_size = edns;
+					c.server_cookie = cookie;
+					c.preserves_case = case;
+					c.edns_buffer = buffer;
+					c.large_responses = large;
+					c.rd_zero = rd_zero;
+					c.https_rr = https_rr;
+					c.pads_responses = padding;
+					c.dns64 = dns64;
+					c.ttl_rewrite = ttl_rewrite;
+					c.coalescing = coalescing;
+				}
+			}
+			Err(e) => crate::warn!("capability probe failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Capability probes", total, start);
+	crate::info!();
+}
+
+/// Probe ad/malware filtering only, for a nonzero filtering penalty.
+///
+/// The benchmark pipeline skips the full capability probes; this fills in
+/// just `filtering` so the score adjustment has something to act on.
+pub async fn run_filtering_probe(
+	records: &mut [crate::record::ResolverRecord],
+	config: &BenchmarkConfig,
+) {
+	crate::info!("Checking ad/malware filtering ({} resolvers)...", records.len());
+	let total = records.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Filtering check".to_string(), done.clone(), total, start);
+
+	let semaphore = Arc::new(Semaphore::new(32));
+	let mut handles = Vec::new();
+	for (i, rec) in records.iter().enumerate() {
+		let addr = rec.resolver.addr;
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let filtering = crate::dns::check_filtering(addr, tm).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(i, filtering)
+		}));
+	}
+	for handle in handles {
+		match handle.await {
+			Ok((idx, filtering)) => {
+				if let Some(c) = records[idx].characterization.as_mut() {
+					c.filtering = filtering;
+				}
+			}
+			Err(e) => crate::warn!("filtering check failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Filtering check", total, start);
+	let filtering = records.iter()
+		.filter(|r| r.characterization.as_ref().is_some_and(|c| c.filtering == Some(true)))
+		.count();
+	crate::info!("  {} filter", filtering);
+	crate::info!();
+}
+
+/// Run discovery prefilter to narrow a large resolver list to the best N.
+///
+/// Runs in progressive waves, each sending more queries to fewer resolvers:
+/// Wave 1: 1 query per resolver (the reachability screen), keep best 4x N.
+/// Wave 2: a few queries per resolver, keep best 2x N by p50.
+/// Wave 3: the full warm (cached) set, keep best N by p50.
+/// Waves are skipped once the survivor count is already within budget.
+pub async fn run_discovery(
+	records: &mut [crate::record::ResolverRecord],
+	categories: &std::collections::BTreeMap<String, Vec<String>>,
+	config: &BenchmarkConfig,
+	connections: &ConnectionPool,
+) {
+	crate::info!("Discovery mode: screening {} resolvers...", records.len());
+
+	// Pick discovery domains from the first category with enough entries
+	let discovery_domains: &[String] = categories.iter()
+		.filter(|(name, _)| name.as_str() != crate::domains::NXDOMAIN_SET)
+		.map(|(_, domains)| domains)
+		.find(|domains| domains.len() >= 5)
+		.or_else(|| categories.values().next())
+		.map(|v| v.as_slice())
+		.unwrap_or(&[]);
 
 	// Phase 1: fast parallel reachability screen (1 query per resolver)
 	let screen_timeout_udp = Duration::from_millis(SCREEN_TIMEOUT_MS);
@@ -813,7 +1520,7 @@ pub async fn run_discovery(
 		// Clone only the fields needed for dispatch and result reporting
 		let addr = rec.resolver.addr;
 		let transport = rec.resolver.transport.clone();
-		let doh_clients = doh_clients.clone();
+		let connections = connections.clone();
 		let done = screen_done.clone();
 		let screen_timeout = match &rec.resolver.transport {
 			DnsTransport::Udp => screen_timeout_udp,
@@ -833,12 +1540,12 @@ pub async fn run_discovery(
 				}
 			};
 			let result = dispatch_query(
-				addr, &transport, &query_bytes, screen_timeout,
-				txid, &domain, QueryType::A, &doh_clients,
+				addr, &transport, &query_bytes, screen_timeout, 0, Duration::ZERO,
+				txid, &domain, QueryType::A, &connections,
 			).await;
 			let latency_ms = result.latency.as_secs_f64() * 1000.0;
 			done.fetch_add(1, Ordering::Relaxed);
-			(i, result.success, result.timeout, latency_ms)
+			(i, result.outcome.is_ok(), result.outcome.no_answer(), latency_ms)
 		}));
 	}
 
@@ -876,7 +1583,7 @@ pub async fn run_discovery(
 					});
 					// Print private/system resolver failures to stdout
 					if class != "public" {
-						println!("  {} {} ({}) -- {}",
+						crate::info!("  {} {} ({}) -- {}",
 							class, label, ip_str, reason);
 					}
 					if was_timeout { timed_out += 1; } else { failed_fast += 1; }
@@ -890,18 +1597,916 @@ pub async fn run_discovery(
 	stop_progress_monitor(monitor, "Screening", screen_total, screen_start);
 
 	let unreachable = screen_total - passed_count;
-	println!(
+	crate::info!(
 		"  Screen: {}/{} resolvers reachable ({} unreachable, dropped)",
 		passed_count, screen_total, unreachable,
 	);
 	if timed_out > 0 || failed_fast > 0 || panicked > 0 {
-		println!(
+		crate::info!(
 			"  Failures: {} timed out, {} connect failed, {} panicked",
 			timed_out, failed_fast, panicked,
 		);
 	}
 
-	config.telemetry.log_pipeline("discovery_reachable", passed_count);
+	config.telemetry.log_pipeline("discovery_reachable", passed_count);
+
+	// Progressive tightening: each wave sends more queries to fewer resolvers
+	let top_n = crate::transport::DEFAULT_DISCOVERY_TOP_N;
+	let mut survivors: Vec<usize> = (0..records.len())
+		.filter(|&i| records[i].discovery.as_ref().is_some_and(|d| d.passed))
+		.collect();
+
+	// Wave 1: cut to 4x N using the single screening query latency
+	let screen_latency: HashMap<usize, Option<f64>> = survivors.iter()
+		.map(|&i| (i, records[i].discovery.as_ref().and_then(|d| d.latency_ms)))
+		.collect();
+	cut_discovery_wave(records, &mut survivors, &screen_latency, 4 * top_n, 1, config);
+
+	// Wave 2: a handful of queries per resolver, cut to 2x N
+	let wave2_count = crate::transport::DEFAULT_DISCOVERY_WAVE2_QUERIES;
+	let wave2_domains: Vec<String> = discovery_domains.iter()
+		.cycle()
+		.take(wave2_count)
+		.cloned()
+		.collect();
+	if survivors.len() > 2 * top_n && !wave2_domains.is_empty() {
+		let p50s = run_discovery_wave(
+			records, &survivors, &wave2_domains, 2, discovery_concurrency, connections,
+		).await;
+		cut_discovery_wave(records, &mut survivors, &p50s, 2 * top_n, 2, config);
+	}
+
+	// Wave 3: the full warm (cached) set, cut to N
+	let warm_domains: Vec<String> = categories.get("cached")
+		.cloned()
+		.unwrap_or_else(|| discovery_domains.to_vec());
+	if survivors.len() > top_n && !warm_domains.is_empty() {
+		let p50s = run_discovery_wave(
+			records, &survivors, &warm_domains, 3, discovery_concurrency, connections,
+		).await;
+		cut_discovery_wave(records, &mut survivors, &p50s, top_n, 3, config);
+	}
+
+	crate::info!("  Discovery kept {} of {} resolvers", survivors.len(), screen_total);
+}
+
+/// Run one progressive discovery wave over the surviving record indices.
+///
+/// Each surviving resolver is sent every domain in `domains` (sequentially,
+/// resolvers in parallel). Returns the p50 latency of successful queries per
+/// record index; None means the resolver answered nothing in this wave.
+async fn run_discovery_wave(
+	records: &[crate::record::ResolverRecord],
+	survivors: &[usize],
+	domains: &[String],
+	wave: u32,
+	concurrency: usize,
+	connections: &ConnectionPool,
+) -> HashMap<usize, Option<f64>> {
+	let label = format!("Wave {}", wave);
+	crate::info!("  {}: {} resolvers x {} queries", label, survivors.len(), domains.len());
+
+	let semaphore = Arc::new(Semaphore::new(concurrency));
+	let total = survivors.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor(label.clone(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for &i in survivors {
+		let sem = semaphore.clone();
+		let addr = records[i].resolver.addr;
+		let transport = records[i].resolver.transport.clone();
+		let connections = connections.clone();
+		let domains = domains.to_vec();
+		let done = done.clone();
+		let wave_timeout = match transport {
+			DnsTransport::Udp => Duration::from_millis(SCREEN_TIMEOUT_MS),
+			_ => Duration::from_millis(SCREEN_TLS_TIMEOUT_MS),
+		};
+
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let mut latencies: Vec<f64> = Vec::with_capacity(domains.len());
+			for domain in &domains {
+				let txid: u16 = rand::random();
+				let query_bytes = match build_query(domain, QueryType::A, txid, false) {
+					Ok(b) => b,
+					Err(_) => continue,
+				};
+				let result = dispatch_query(
+					addr, &transport, &query_bytes, wave_timeout, 0, Duration::ZERO,
+					txid, domain, QueryType::A, &connections,
+				).await;
+				if result.outcome.is_ok() {
+					latencies.push(result.latency.as_secs_f64() * 1000.0);
+				}
+			}
+			done.fetch_add(1, Ordering::Relaxed);
+			latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+			(i, crate::stats::percentile(&latencies, 50.0))
+		}));
+	}
+
+	let mut p50s = HashMap::new();
+	for handle in handles {
+		if let Ok((idx, p50)) = handle.await {
+			p50s.insert(idx, p50);
+		}
+	}
+	stop_progress_monitor(monitor, &label, total, start);
+	p50s
+}
+
+/// Keep the best `keep` survivors by wave latency and mark the rest as cut.
+///
+/// System resolvers are never cut by rank since they are pinned in results.
+/// Survivors keep their discovery latency updated to the latest wave p50.
+fn cut_discovery_wave(
+	records: &mut [crate::record::ResolverRecord],
+	survivors: &mut Vec<usize>,
+	latencies: &HashMap<usize, Option<f64>>,
+	keep: usize,
+	wave: u32,
+	config: &BenchmarkConfig,
+) {
+	let latency_of = |i: &usize| -> f64 {
+		latencies.get(i).copied().flatten().filter(|l| l.is_finite()).unwrap_or(f64::INFINITY)
+	};
+	survivors.sort_by(|a, b| {
+		latency_of(a).partial_cmp(&latency_of(b)).unwrap_or(std::cmp::Ordering::Equal)
+	});
+
+	let before = survivors.len();
+	let reason = format!("wave{}_cut", wave);
+	let mut kept: Vec<usize> = Vec::with_capacity(keep.min(before));
+	for (pos, &i) in survivors.iter().enumerate() {
+		let latency = latency_of(&i);
+		// Telemetry latency field is numeric; report 0 when nothing answered
+		let logged_ms = if latency.is_finite() { latency } else { 0.0 };
+		let rec = &mut records[i];
+		if (pos < keep && latency.is_finite()) || rec.resolver.is_system {
+			if let Some(ref mut d) = rec.discovery {
+				if latency.is_finite() {
+					d.latency_ms = Some(latency);
+				}
+			}
+			kept.push(i);
+			continue;
+		}
+		rec.discovery = Some(crate::record::DiscoveryResult {
+			passed: false,
+			latency_ms: if latency.is_finite() { Some(latency) } else { None },
+			reason: reason.clone(),
+		});
+		config.telemetry.log_discovery(
+			&rec.resolver.addr.ip().to_string(), &rec.resolver.label,
+			rec.resolver.class, false, &reason, logged_ms,
+		);
+	}
+	*survivors = kept;
+	if survivors.len() < before {
+		crate::info!("  Wave {}: kept {} of {} resolvers", wave, survivors.len(), before);
+	}
+	config.telemetry.log_pipeline(&format!("discovery_wave{}", wave), survivors.len());
+}
+
+/// Query all three identity sources on one resolver.
+async fn probe_identity(addr: std::net::SocketAddr, timeout: Duration) -> crate::record::IdentityResult {
+	crate::record::IdentityResult {
+		nsid: crate::dns::query_nsid(addr, timeout).await,
+		id_server: crate::dns::query_chaos_txt(addr, "id.server", timeout).await,
+		hostname_bind: crate::dns::query_chaos_txt(addr, "hostname.bind", timeout).await,
+	}
+}
+
+//============================================
+/// Ask each resolver which server answered: EDNS NSID plus CHAOS TXT
+/// `id.server` and `hostname.bind`.
+///
+/// Diagnostic only. Writes IdentityResult onto every record; resolvers that
+/// reveal nothing get an empty result.
+pub async fn run_identity_probe(
+	records: &mut [crate::record::ResolverRecord],
+	config: &BenchmarkConfig,
+) {
+	crate::info!("Identity probe: NSID and CHAOS TXT ({} resolvers)...", records.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = records.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Identity probe".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for (i, rec) in records.iter().enumerate() {
+		let addr = rec.resolver.addr;
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let identity = probe_identity(addr, tm).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(i, identity)
+		}));
+	}
+	for handle in handles {
+		match handle.await {
+			Ok((idx, identity)) => records[idx].identity = Some(identity),
+			Err(e) => crate::warn!("identity probe task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Identity probe", total, start);
+	crate::info!();
+}
+
+/// Number of TCP connects per resolver in the path probe
+const PATH_PROBE_ATTEMPTS: usize = 3;
+
+/// Probe the network path to each benchmarked resolver with ICMP pings and
+/// TCP connects.
+///
+/// Echo replies and the TCP handshake are answered by the remote kernel, so
+/// their time is close to one network round trip. Subtracting it from the
+/// DNS p50 (cached set when available) leaves the DNS overhead: resolver
+/// software and upstream work. Ping is preferred; TCP connect stands in when
+/// ICMP is unavailable or dropped. Writes PathProbeResult onto each record
+/// that has a benchmark result.
+pub async fn run_path_probe(
+	records: &mut [crate::record::ResolverRecord],
+	config: &BenchmarkConfig,
+) {
+	crate::info!("Path probe: ICMP ping and TCP connect timing ({} resolvers)...", records.len());
+	if !crate::ping::icmp_available() {
+		crate::info!("  ICMP unavailable (needs net.ipv4.ping_group_range or CAP_NET_RAW); using TCP connect times");
+	}
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = records.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Path probe".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for (i, rec) in records.iter().enumerate() {
+		let addr = rec.resolver.addr;
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let mut connect_ms: Vec<f64> = Vec::with_capacity(PATH_PROBE_ATTEMPTS);
+			for _ in 0..PATH_PROBE_ATTEMPTS {
+				let t0 = Instant::now();
+				if let Ok(Ok(_stream)) = tokio::time::timeout(tm, crate::source::connect_tcp(addr)).await {
+					connect_ms.push(t0.elapsed().as_secs_f64() * 1000.0);
+				}
+			}
+			let ping_ms = crate::ping::ping_rtt(addr.ip(), PATH_PROBE_ATTEMPTS, tm).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			connect_ms.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+			(i, ping_ms, crate::stats::percentile(&connect_ms, 50.0))
+		}));
+	}
+
+	for handle in handles {
+		let (idx, ping_ms, tcp_connect_ms) = match handle.await {
+			Ok(r) => r,
+			Err(e) => {
+				crate::warn!("path probe task failed: {}", e);
+				continue;
+			}
+		};
+		let rec = &mut records[idx];
+		// Only the cached set compares with a round trip: other sets add upstream recursion time
+		let Some(dns_p50_ms) = rec.benchmark.as_ref()
+			.and_then(|bm| bm.categories.get("cached"))
+			.map(|s| s.p50_ms) else { continue };
+		let dns_overhead_ms = ping_ms.or(tcp_connect_ms).map(|rtt| (dns_p50_ms - rtt).max(0.0));
+		rec.path_probe = Some(crate::record::PathProbeResult {
+			ping_ms,
+			tcp_connect_ms,
+			dns_p50_ms,
+			dns_overhead_ms,
+		});
+	}
+	stop_progress_monitor(monitor, "Path probe", total, start);
+}
+
+/// Highest hop limit tried by the traceroute
+const TRACEROUTE_MAX_HOPS: u8 = 30;
+
+/// Trace the network path length to each resolver (--traceroute).
+///
+/// Annotates records with the hop count and last-hop round trip; when two
+/// resolvers are equally fast, the one fewer hops away has less path to go
+/// wrong. Needs a raw ICMP socket; without one the probe is skipped.
+pub async fn run_traceroute(
+	records: &mut [crate::record::ResolverRecord],
+	config: &BenchmarkConfig,
+) {
+	if !crate::ping::raw_icmp_available() {
+		crate::warn!("traceroute needs a raw ICMP socket (root or CAP_NET_RAW); skipping");
+		return;
+	}
+	crate::info!("Traceroute: up to {} hops ({} resolvers)...", TRACEROUTE_MAX_HOPS, records.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = records.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Traceroute".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for (i, rec) in records.iter().enumerate() {
+		let ip = rec.resolver.addr.ip();
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let trace = crate::ping::trace_path(ip, TRACEROUTE_MAX_HOPS, tm).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(i, trace)
+		}));
+	}
+	for handle in handles {
+		match handle.await {
+			Ok((idx, trace)) => records[idx].traceroute = trace,
+			Err(e) => crate::warn!("traceroute task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Traceroute", total, start);
+}
+
+/// Identical queries per resolver in the loss probe burst
+pub const LOSS_PROBE_BURST: u32 = 20;
+/// Gap between sends within a loss probe burst (avoids self-inflicted rate limiting)
+const LOSS_PROBE_SPACING_MS: u64 = 10;
+/// Cheap, universally cached name used for every loss probe query
+const LOSS_PROBE_DOMAIN: &str = "google.com";
+
+/// Send a burst of identical A queries from one UDP socket and count answers.
+///
+/// Each query gets its own transaction ID so responses can be matched.
+/// Sends are staggered by LOSS_PROBE_SPACING_MS; responses that arrive
+/// during the send phase wait in the socket buffer. Any parseable response
+/// counts as answered, regardless of rcode.
+async fn send_udp_burst(addr: SocketAddr, count: u32, timeout: Duration, dnssec: bool) -> u32 {
+	let socket = match crate::source::bind_udp(addr.is_ipv6()).await {
+		Ok(s) => s,
+		Err(_) => return 0,
+	};
+
+	// Send phase: distinct txids for otherwise identical queries
+	let mut pending: std::collections::HashSet<u16> = std::collections::HashSet::new();
+	while pending.len() < count as usize {
+		pending.insert(rand::random());
+	}
+	for (i, &txid) in pending.iter().enumerate() {
+		if i > 0 {
+			tokio::time::sleep(Duration::from_millis(LOSS_PROBE_SPACING_MS)).await;
+		}
+		if let Ok(query) = build_query(LOSS_PROBE_DOMAIN, QueryType::A, txid, dnssec) {
+			let _ = socket.send_to(&query, addr).await;
+		}
+	}
+
+	// Receive phase: one full timeout after the last send
+	let deadline = Instant::now() + timeout;
+	let mut answered = 0;
+	let mut buf = vec![0u8; 4096];
+	while !pending.is_empty() {
+		let now = Instant::now();
+		if now >= deadline {
+			break;
+		}
+		let len = match tokio::time::timeout(deadline - now, socket.recv_from(&mut buf)).await {
+			Ok(Ok((len, _src))) => len,
+			_ => break,
+		};
+		if len < 2 {
+			continue;
+		}
+		let txid = u16::from_be_bytes([buf[0], buf[1]]);
+		if pending.contains(&txid)
+			&& parse_response(&buf[..len], txid, LOSS_PROBE_DOMAIN, QueryType::A).is_ok()
+		{
+			pending.remove(&txid);
+			answered += 1;
+		}
+	}
+	answered
+}
+
+/// Estimate packet loss per UDP resolver with a burst of identical queries.
+///
+/// The benchmark score folds lost packets into its timeout penalty; this
+/// probe reports the unanswered fraction on its own. DoT and DoH resolvers
+/// are skipped because TCP retransmission hides loss. Writes LossProbeResult
+/// onto each UDP record that has a benchmark result.
+pub async fn run_loss_probe(
+	records: &mut [crate::record::ResolverRecord],
+	config: &BenchmarkConfig,
+) {
+	let targets: Vec<usize> = records.iter().enumerate()
+		.filter(|(_, r)| r.benchmark.is_some() && r.resolver.transport == DnsTransport::Udp)
+		.map(|(i, _)| i)
+		.collect();
+	crate::info!("Loss probe: {} queries per resolver ({} UDP resolvers)...",
+		LOSS_PROBE_BURST, targets.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = targets.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Loss probe".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for idx in targets {
+		let addr = records[idx].resolver.addr;
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let dnssec = config.dnssec;
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let answered = send_udp_burst(addr, LOSS_PROBE_BURST, tm, dnssec).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(idx, answered)
+		}));
+	}
+
+	for handle in handles {
+		let (idx, answered) = match handle.await {
+			Ok(r) => r,
+			Err(e) => {
+				crate::warn!("loss probe task failed: {}", e);
+				continue;
+			}
+		};
+		let sent = LOSS_PROBE_BURST;
+		records[idx].loss_probe = Some(crate::record::LossProbeResult {
+			sent,
+			answered,
+			loss_pct: (sent - answered) as f64 / sent as f64 * 100.0,
+		});
+	}
+	stop_progress_monitor(monitor, "Loss probe", total, start);
+}
+
+/// Setup times of one TCP+TLS connection
+struct TlsConnectTiming {
+	connect_ms: f64,
+	handshake_ms: f64,
+	version: Option<rustls::ProtocolVersion>,
+	resumed: bool,
+}
+
+/// ALPN protocols a DoT or DoH client offers. None for UDP.
+pub fn tls_alpn(transport: &DnsTransport) -> Option<Vec<Vec<u8>>> {
+	match transport {
+		DnsTransport::Udp => None,
+		DnsTransport::Dot { .. } => Some(vec![b"dot".to_vec()]),
+		DnsTransport::Doh { .. } => Some(vec![b"h2".to_vec(), b"http/1.1".to_vec()]),
+	}
+}
+
+/// SNI name for a DoT or DoH resolver: the DoT hostname or the DoH URL
+/// host, else its IP address. None for UDP.
+pub fn tls_server_name(resolver: &Resolver) -> Option<rustls::pki_types::ServerName<'static>> {
+	match &resolver.transport {
+		DnsTransport::Udp => None,
+		DnsTransport::Dot { hostname } => dot_server_name(resolver.addr, hostname),
+		DnsTransport::Doh { url, .. } => {
+			let host = reqwest::Url::parse(url).ok()
+				.and_then(|u| u.host_str().map(String::from))
+				.unwrap_or_default();
+			dot_server_name(resolver.addr, &host)
+		}
+	}
+}
+
+/// Client TLS config for the setup probe: web PKI roots, the transport's
+/// ALPN protocols, and early data enabled so 0-RTT support shows. None for UDP.
+fn tls_probe_config(transport: &DnsTransport) -> Option<ClientConfig> {
+	let alpn = tls_alpn(transport)?;
+	let root_store = rustls::RootCertStore::from_iter(
+		webpki_roots::TLS_SERVER_ROOTS.iter().cloned()
+	);
+	let mut tls_config = ClientConfig::builder()
+		.with_root_certificates(root_store)
+		.with_no_client_auth();
+	tls_config.alpn_protocols = alpn;
+	tls_config.enable_early_data = true;
+	Some(tls_config)
+}
+
+/// Protocol version as reported, e.g. "TLSv1.3".
+fn tls_version_text(version: Option<rustls::ProtocolVersion>) -> String {
+	match version {
+		Some(rustls::ProtocolVersion::TLSv1_3) => "TLSv1.3".to_string(),
+		Some(rustls::ProtocolVersion::TLSv1_2) => "TLSv1.2".to_string(),
+		Some(other) => format!("{:?}", other),
+		None => "unknown".to_string(),
+	}
+}
+
+/// Open one TCP+TLS connection, timing the TCP connect and the TLS
+/// handshake separately. Then writes `first_send` (if any) and reads once,
+/// so session tickets the server sends after the handshake reach the
+/// connector's session cache before the next connection.
+async fn timed_tls_connect(
+	resolver: SocketAddr,
+	connector: &TlsConnector,
+	server_name: rustls::pki_types::ServerName<'static>,
+	first_send: &[u8],
+	timeout: Duration,
+	proxy: Option<&ProxyConfig>,
+) -> Option<TlsConnectTiming> {
+	let start = Instant::now();
+	let tcp_stream = tokio::time::timeout(timeout, connect_via(proxy, resolver)).await.ok()?.ok()?;
+	let connect_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+	let handshake_start = Instant::now();
+	let remaining = timeout.saturating_sub(start.elapsed());
+	let mut tls_stream = tokio::time::timeout(
+		remaining, connector.connect(server_name, tcp_stream),
+	).await.ok()?.ok()?;
+	let handshake_ms = handshake_start.elapsed().as_secs_f64() * 1000.0;
+	let (_, conn) = tls_stream.get_ref();
+	let version = conn.protocol_version();
+	let resumed = conn.handshake_kind() == Some(rustls::HandshakeKind::Resumed);
+
+	// Best effort: a failed read only means the next connection may not resume
+	let remaining = timeout.saturating_sub(start.elapsed());
+	let _ = tokio::time::timeout(remaining, async {
+		if !first_send.is_empty() {
+			tls_stream.write_all(first_send).await?;
+			tls_stream.flush().await?;
+		}
+		let mut buf = [0u8; 512];
+		tls_stream.read(&mut buf).await
+	}).await;
+	Some(TlsConnectTiming { connect_ms, handshake_ms, version, resumed })
+}
+
+/// Time a fresh and a resumed TLS connection to one DoT or DoH resolver.
+/// None for UDP or when the fresh connection fails.
+async fn probe_tls_setup(
+	resolver: &Resolver,
+	timeout: Duration,
+	proxy: Option<&ProxyConfig>,
+) -> Option<crate::record::TlsSetupResult> {
+	let tls_config = Arc::new(tls_probe_config(&resolver.transport)?);
+	let server_name = tls_server_name(resolver)?;
+	let first_send = match &resolver.transport {
+		DnsTransport::Dot { .. } => {
+			// A DoT server answers nothing until it gets a query
+			let query = crate::dns::build_query("example.com", QueryType::A, rand::random(), false).ok()?;
+			let mut framed = (query.len() as u16).to_be_bytes().to_vec();
+			framed.extend_from_slice(&query);
+			framed
+		}
+		// The HTTP/2 server speaks first with its SETTINGS frame
+		_ => Vec::new(),
+	};
+	let connector = TlsConnector::from(tls_config.clone());
+	let fresh = timed_tls_connect(resolver.addr, &connector, server_name.clone(), &first_send, timeout, proxy).await?;
+	let second = timed_tls_connect(resolver.addr, &connector, server_name.clone(), &first_send, timeout, proxy).await;
+	// A new connection from the same config takes the cached ticket and
+	// offers early data only if that ticket allows it
+	let early_data = rustls::ClientConnection::new(tls_config, server_name)
+		.map(|mut c| c.early_data().is_some())
+		.unwrap_or(false);
+	Some(crate::record::TlsSetupResult {
+		version: tls_version_text(fresh.version),
+		connect_ms: fresh.connect_ms,
+		handshake_ms: fresh.handshake_ms,
+		resumed_handshake_ms: second.as_ref().map(|s| s.handshake_ms),
+		resumed: second.is_some_and(|s| s.resumed),
+		early_data,
+	})
+}
+
+/// Measure TLS connection setup to each DoT and DoH resolver.
+///
+/// Each resolver gets a fresh connection and then a second one offering
+/// the first one's session, so the report separates a full handshake from
+/// a resumed one. A client that reconnects often pays this on top of each
+/// query, which the benchmark itself only measures with
+/// `--connection-mode per-query`. Writes TlsSetupResult onto each encrypted
+/// record that has a benchmark result.
+pub async fn run_tls_probe(
+	records: &mut [crate::record::ResolverRecord],
+	config: &BenchmarkConfig,
+) {
+	let targets: Vec<usize> = records.iter().enumerate()
+		.filter(|(_, r)| r.benchmark.is_some() && r.resolver.transport != DnsTransport::Udp)
+		.map(|(i, _)| i)
+		.collect();
+	if targets.is_empty() {
+		return;
+	}
+	crate::info!("TLS setup probe: fresh and resumed connection ({} DoT/DoH resolvers)...", targets.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = targets.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("TLS setup probe".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for idx in targets {
+		let resolver = records[idx].resolver.clone();
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let proxy = config.proxy.clone();
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let setup = probe_tls_setup(&resolver, tm, proxy.as_ref()).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(idx, setup)
+		}));
+	}
+
+	for handle in handles {
+		match handle.await {
+			Ok((idx, setup)) => records[idx].tls_setup = setup,
+			Err(e) => crate::warn!("TLS setup probe task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "TLS setup probe", total, start);
+}
+
+/// Timed queries per HTTP version in the DoH version comparison
+pub const DOH_HTTP_PROBE_QUERIES: usize = 10;
+/// Name queried by the DoH version comparison; cached almost everywhere
+const DOH_HTTP_PROBE_DOMAIN: &str = "google.com";
+
+/// Time DOH_HTTP_PROBE_QUERIES sequential queries to a DoH resolver over
+/// one HTTP version, on one kept-alive connection. A first query opens
+/// the connection and is not counted, so the handshake does not skew the
+/// comparison. Sent queries that got no answer count as failed.
+async fn probe_doh_http(
+	url: &str,
+	addr: SocketAddr,
+	options: &DohOptions,
+	version: DohHttpVersion,
+	timeout: Duration,
+	proxy: Option<&ProxyConfig>,
+) -> crate::record::DohHttpResult {
+	let client = new_doh_client(url, addr, version, proxy);
+	let query = |txid: u16| build_query(DOH_HTTP_PROBE_DOMAIN, QueryType::A, txid, false);
+	if let Ok(bytes) = query(rand::random()) {
+		let _ = doh_exchange(url, options, &bytes, timeout, &client).await;
+	}
+	let mut latencies = Vec::new();
+	for _ in 0..DOH_HTTP_PROBE_QUERIES {
+		let Ok(bytes) = query(rand::random()) else { continue };
+		let result = send_doh_query(url, options, &bytes, timeout, DOH_HTTP_PROBE_DOMAIN, QueryType::A, &client).await;
+		if !result.outcome.no_answer() {
+			latencies.push(result.latency.as_secs_f64() * 1000.0);
+		}
+	}
+	latencies.sort_by(|a, b| a.total_cmp(b));
+	crate::record::DohHttpResult {
+		version,
+		answered: latencies.len(),
+		p50_ms: crate::stats::percentile(&latencies, 50.0),
+	}
+}
+
+/// Compare HTTP versions for each DoH resolver (--doh-http-compare).
+///
+/// Each version gets its own client and connection, timed the same way,
+/// so the medians differ only by the HTTP layer and the server's handling
+/// of it. Writes one DohHttpResult per version onto each DoH record that
+/// has a benchmark result. The versions run one after another per
+/// resolver so they do not compete for the same path.
+pub async fn run_doh_http_probe(
+	records: &mut [crate::record::ResolverRecord],
+	config: &BenchmarkConfig,
+) {
+	let targets: Vec<usize> = records.iter().enumerate()
+		.filter(|(_, r)| r.benchmark.is_some() && matches!(r.resolver.transport, DnsTransport::Doh { .. }))
+		.map(|(i, _)| i)
+		.collect();
+	if targets.is_empty() {
+		crate::info!("DoH HTTP comparison: no DoH resolvers to compare");
+		return;
+	}
+	crate::info!("DoH HTTP comparison: {} queries per version ({} DoH resolvers)...",
+		DOH_HTTP_PROBE_QUERIES, targets.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = targets.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("DoH HTTP comparison".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for idx in targets {
+		let transport = records[idx].resolver.transport.clone();
+		let addr = records[idx].resolver.addr;
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let proxy = config.proxy.clone();
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let mut results = Vec::new();
+			if let DnsTransport::Doh { url, options } = &transport {
+				for version in DohHttpVersion::ALL {
+					results.push(probe_doh_http(url, addr, options, version, tm, proxy.as_ref()).await);
+				}
+			}
+			done.fetch_add(1, Ordering::Relaxed);
+			(idx, results)
+		}));
+	}
+
+	for handle in handles {
+		match handle.await {
+			Ok((idx, results)) => records[idx].doh_http = results,
+			Err(e) => crate::warn!("DoH HTTP comparison task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "DoH HTTP comparison", total, start);
+}
+
+/// Gap between the two warm-probe queries; long enough for a decrementing TTL to drop
+const WARM_PROBE_GAP_MS: u64 = 1100;
+
+/// Warm-probe outcome for one domain at one resolver
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WarmClass {
+	Cached,
+	Miss,
+	Undetermined,
+}
+
+//============================================
+/// Classify a warm-set domain from two answer TTLs taken WARM_PROBE_GAP_MS apart.
+///
+/// `reference` is the highest TTL any resolver returned for the domain,
+/// taken as the fresh authoritative TTL. A first answer below it was served
+/// from cache. A first answer at the reference followed by a lower second
+/// answer means the resolver decrements TTLs and had just fetched the name.
+fn classify_warm(first: Option<u32>, second: Option<u32>, reference: u32) -> WarmClass {
+	match (first, second) {
+		(Some(t1), _) if t1 < reference => WarmClass::Cached,
+		(Some(t1), Some(t2)) if t2 < t1 => WarmClass::Miss,
+		_ => WarmClass::Undetermined,
+	}
+}
+
+/// Pre-probe each resolver's warm set to find which domains are really cached.
+///
+/// Every domain is queried twice per resolver, WARM_PROBE_GAP_MS apart, and
+/// classified with `classify_warm`. Domains that were fetched fresh are
+/// excluded from that resolver's warm ("cached") set in `run_benchmark`, so
+/// warm stats cover only lookups the resolver already had cached.
+/// Writes WarmProbeResult onto each record.
+pub async fn run_warm_probe(
+	records: &mut [crate::record::ResolverRecord],
+	domains: &[String],
+	config: &BenchmarkConfig,
+	connections: &ConnectionPool,
+) {
+	crate::info!("Warm probe: {} domains x 2 queries per resolver ({} resolvers)...",
+		domains.len(), records.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = records.len() * domains.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Warm probe".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::with_capacity(total);
+	for (ri, rec) in records.iter().enumerate() {
+		for (di, domain) in domains.iter().enumerate() {
+			let resolver = rec.resolver.clone();
+			let domain = domain.clone();
+			let sem = semaphore.clone();
+			let connections = connections.clone();
+			let done = done.clone();
+			let timeout = config.timeout;
+			let dnssec = config.dnssec;
+			handles.push(tokio::spawn(async move {
+				// Hold a permit only while a query is in flight, not across the gap
+				let first = {
+					let _permit = sem.acquire().await.unwrap();
+					probe_query(&resolver, &domain, timeout, dnssec, &connections).await
+				};
+				tokio::time::sleep(Duration::from_millis(WARM_PROBE_GAP_MS)).await;
+				let second = {
+					let _permit = sem.acquire().await.unwrap();
+					probe_query(&resolver, &domain, timeout, dnssec, &connections).await
+				};
+				done.fetch_add(1, Ordering::Relaxed);
+				let ttl = |r: &QueryResult| if r.outcome.is_ok() { r.ttl } else { None };
+				(ri, di, ttl(&first), ttl(&second))
+			}));
+		}
+	}
+
+	let mut ttls: Vec<Vec<(Option<u32>, Option<u32>)>> = vec![vec![(None, None); domains.len()]; records.len()];
+	for handle in handles {
+		match handle.await {
+			Ok((ri, di, t1, t2)) => ttls[ri][di] = (t1, t2),
+			Err(e) => crate::warn!("warm probe task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Warm probe", total, start);
+
+	// Reference TTL per domain: the highest TTL seen from any resolver
+	let reference: Vec<u32> = (0..domains.len())
+		.map(|di| ttls.iter()
+			.flat_map(|row| [row[di].0, row[di].1])
+			.flatten()
+			.max()
+			.unwrap_or(0))
+		.collect();
+
+	let mut excluded_total = 0;
+	for (rec, row) in records.iter_mut().zip(&ttls) {
+		let mut result = crate::record::WarmProbeResult::default();
+		for (di, &(t1, t2)) in row.iter().enumerate() {
+			let domain = domains[di].clone();
+			match classify_warm(t1, t2, reference[di]) {
+				WarmClass::Cached => result.cached.push(domain),
+				WarmClass::Miss => result.excluded.push(domain),
+				WarmClass::Undetermined => result.undetermined.push(domain),
+			}
+		}
+		excluded_total += result.excluded.len();
+		rec.warm_probe = Some(result);
+	}
+	crate::info!("  Excluded {} of {} resolver/domain pairs from the warm set (not cached before probing)",
+		excluded_total, total);
+}
+
+/// Prime every resolver's cache with the warm-set domains (--prime-cache).
+///
+/// Sends one unmeasured query per domain and query type to every resolver,
+/// so the warm set measures cache-hit latency rather than relying on the
+/// domains being popular enough to already be cached. Results are discarded.
+pub async fn run_cache_prime(
+	records: &[crate::record::ResolverRecord],
+	domains: &[String],
+	config: &BenchmarkConfig,
+	connections: &ConnectionPool,
+) {
+	let mut query_types = if config.query_aaaa {
+		vec![QueryType::A, QueryType::AAAA]
+	} else {
+		vec![QueryType::A]
+	};
+	if config.query_https {
+		query_types.push(QueryType::HTTPS);
+	}
+	let total = records.len() * domains.len() * query_types.len();
+	crate::info!("Priming cache: {} unmeasured queries ({} domains x {} resolvers)...",
+		total, domains.len(), records.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Cache prime".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::with_capacity(total);
+	for rec in records {
+		for domain in domains {
+			for &qt in &query_types {
+				let addr = rec.resolver.addr;
+				let transport = rec.resolver.transport.clone();
+				let domain = domain.clone();
+				let sem = semaphore.clone();
+				let connections = connections.clone();
+				let done = done.clone();
+				let timeout = config.timeout;
+				let retries = config.retries;
+				let dnssec = config.dnssec;
+				handles.push(tokio::spawn(async move {
+					let _permit = sem.acquire().await.unwrap();
+					let txid: u16 = rand::random();
+					let answered = match build_query(&domain, qt, txid, dnssec) {
+						Ok(query_bytes) => dispatch_query(
+							addr, &transport, &query_bytes, timeout, retries, Duration::ZERO,
+							txid, &domain, qt, &connections,
+						).await.outcome.is_ok(),
+						Err(_) => false,
+					};
+					done.fetch_add(1, Ordering::Relaxed);
+					answered
+				}));
+			}
+		}
+	}
+
+	let mut answered = 0;
+	for handle in handles {
+		if let Ok(true) = handle.await {
+			answered += 1;
+		}
+	}
+	stop_progress_monitor(monitor, "Cache prime", total, start);
+	crate::info!("  {} of {} priming queries answered", answered, total);
 }
 
 /// Run a lightweight qualification pass to select finalists for medium mode.
@@ -912,9 +2517,9 @@ pub async fn run_qualification(
 	records: &mut [crate::record::ResolverRecord],
 	categories: &std::collections::BTreeMap<String, Vec<String>>,
 	config: &BenchmarkConfig,
-	doh_clients: &DohClientPool,
+	connections: &ConnectionPool,
 ) {
-	println!("Qualification pass: scoring {} resolvers...", records.len());
+	crate::info!("Qualification pass: scoring {} resolvers...", records.len());
 
 	// Build a small domain set: up to 3 cached, 5 uncached, 2 from other categories
 	let mut qual_domains: Vec<String> = Vec::new();
@@ -925,7 +2530,8 @@ pub async fn run_qualification(
 		qual_domains.extend(uncached.iter().take(5).cloned());
 	}
 	for (name, domains) in categories {
-		if name != "cached" && name != "uncached" && qual_domains.len() < 12 {
+		if name != "cached" && name != "uncached" && name != crate::domains::NXDOMAIN_SET
+			&& qual_domains.len() < 12 {
 			qual_domains.extend(domains.iter().take(2).cloned());
 		}
 	}
@@ -933,10 +2539,11 @@ pub async fn run_qualification(
 		qual_domains.push("google.com".to_string());
 	}
 
-	println!("  Using {} qualification domains", qual_domains.len());
+	crate::info!("  Using {} qualification domains", qual_domains.len());
 
 	let semaphore = std::sync::Arc::new(Semaphore::new(config.max_inflight));
 	let timeout = config.timeout;
+	let retries = config.retries;
 
 	let qual_total = records.len() * qual_domains.len();
 	let qual_done = Arc::new(AtomicUsize::new(0));
@@ -950,11 +2557,12 @@ pub async fn run_qualification(
 	for rec in records.iter() {
 		for domain in &qual_domains {
 			let sem = semaphore.clone();
+			let key = rec.resolver.key();
 			let addr = rec.resolver.addr;
 			let transport = rec.resolver.transport.clone();
 			let dnssec = config.dnssec;
 			let domain_clone = domain.clone();
-			let doh_clients = doh_clients.clone();
+			let connections = connections.clone();
 			let done = qual_done.clone();
 
 			handles.push(tokio::spawn(async move {
@@ -966,57 +2574,61 @@ pub async fn run_qualification(
 					Ok(b) => b,
 					Err(_) => {
 						done.fetch_add(1, Ordering::Relaxed);
-						return (addr.ip(), None, true);
+						return (key, None, true);
 					}
 				};
 				let result = dispatch_query(
-					addr, &transport, &query_bytes, timeout,
-					txid, &domain_clone, QueryType::A, &doh_clients,
+					addr, &transport, &query_bytes, timeout, retries, Duration::ZERO,
+					txid, &domain_clone, QueryType::A, &connections,
 				).await;
 				done.fetch_add(1, Ordering::Relaxed);
-				if result.success {
+				if result.outcome.is_ok() {
 					let latency_ms = result.latency.as_secs_f64() * 1000.0;
-					(addr.ip(), Some(latency_ms), false)
+					(key, Some(latency_ms), false)
 				} else {
-					(addr.ip(), None, result.timeout)
+					(key, None, result.outcome.no_answer())
 				}
 			}));
 		}
 	}
 
-	// Collect results per resolver IP
-	let mut resolver_data: HashMap<std::net::IpAddr, (Vec<f64>, usize, usize)> = HashMap::new();
+	// Collect results per resolver key
+	let mut resolver_data: HashMap<String, (Vec<f64>, usize, usize, usize)> = HashMap::new();
 	for handle in handles {
-		if let Ok((ip, latency, is_timeout)) = handle.await {
-			let entry = resolver_data.entry(ip).or_insert_with(|| (Vec::new(), 0, 0));
+		if let Ok((key, latency, is_timeout)) = handle.await {
+			let entry = resolver_data.entry(key).or_insert_with(|| (Vec::new(), 0, 0, 0));
 			entry.1 += 1;
-			if let Some(lat) = latency {
-				entry.0.push(lat);
-			}
-			if is_timeout {
-				entry.2 += 1;
+			match latency {
+				Some(lat) => entry.0.push(lat),
+				None if is_timeout => entry.2 += 1,
+				None => entry.3 += 1,
 			}
 		}
 	}
 	stop_progress_monitor(monitor, "Qualifying", qual_total, qual_start);
 
 	// Score each resolver: lower is better
-	// Score = p50 + 0.5*(p95-p50) + timeout_penalty*timeout_rate
-	// Same formula family as the benchmark phase for consistency
+	// Score = p50 + tail_weight*(p95-p50) + timeout_scale*timeout_penalty*timeout_rate
+	//         + failure_penalty*failure_rate
+	// Same formula and weights as the benchmark phase for consistency
 	let timeout_penalty_ms = config.timeout.as_millis() as f64;
-	let mut scored: Vec<(std::net::IpAddr, f64, f64, f64, f64)> = resolver_data.iter()
-		.map(|(ip, (latencies, total, timeouts))| {
-			let timeout_rate = *timeouts as f64 / *total as f64;
+	let weights = config.score_weights;
+	let mut scored: Vec<(String, f64, f64, f64, f64)> = resolver_data.into_iter()
+		.map(|(key, (latencies, total, timeouts, failures))| {
+			let timeout_rate = timeouts as f64 / total as f64;
+			let failure_rate = failures as f64 / total as f64;
 			if latencies.is_empty() {
-				return (*ip, f64::INFINITY, 0.0, 0.0, timeout_rate);
+				return (key, f64::INFINITY, 0.0, 0.0, timeout_rate);
 			}
-			let mut sorted = latencies.clone();
+			let mut sorted = latencies;
 			sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 			// Use percentile helper; fall back to simple index for tiny samples
 			let p50 = crate::stats::percentile(&sorted, 50.0).unwrap_or(sorted[sorted.len() / 2]);
 			let p95 = crate::stats::percentile(&sorted, 95.0).unwrap_or(sorted[sorted.len() - 1]);
-			let score = p50 + 0.5 * (p95 - p50) + (timeout_rate * timeout_penalty_ms);
-			(*ip, score, p50, p95, timeout_rate)
+			let score = p50 + weights.tail_weight * (p95 - p50)
+				+ weights.timeout_scale * timeout_rate * timeout_penalty_ms
+				+ weights.failure_penalty_ms * failure_rate;
+			(key, score, p50, p95, timeout_rate)
 		})
 		.collect();
 
@@ -1030,29 +2642,29 @@ pub async fn run_qualification(
 		.count()
 		.min(budget);
 
-	// Build score lookup by IP for writing onto records
-	let score_map: HashMap<std::net::IpAddr, (f64, f64, f64, f64, bool)> = scored.iter()
+	// Build score lookup by resolver key for writing onto records
+	let score_map: HashMap<&str, (f64, f64, f64, f64, bool)> = scored.iter()
 		.enumerate()
-		.map(|(i, (ip, score, p50, p95, timeout_rate))| {
+		.map(|(i, (key, score, p50, p95, timeout_rate))| {
 			let promoted = score.is_finite() && i < promote_count;
-			(*ip, (*score, *p50, *p95, *timeout_rate, promoted))
+			(key.as_str(), (*score, *p50, *p95, *timeout_rate, promoted))
 		})
 		.collect();
 
 	// Log all candidates and write QualificationResult onto each record
 	let total_candidates = records.len();
-	for (i, (ip, score, p50, p95, timeout_rate)) in scored.iter().enumerate() {
+	for (i, (key, score, p50, p95, timeout_rate)) in scored.iter().enumerate() {
 		let promoted = score.is_finite() && i < promote_count;
 		// Find the record for telemetry logging
-		if let Some(rec) = records.iter().find(|r| r.resolver.addr.ip() == *ip) {
+		if let Some(rec) = records.iter().find(|r| r.resolver.key() == *key) {
 			config.telemetry.log_qualification(
-				&ip.to_string(), &rec.resolver.label, rec.resolver.class,
+				key, &rec.resolver.label, rec.resolver.class,
 				*score, promoted, *p50, *p95, *timeout_rate,
 			);
 			if rec.resolver.class != "public" {
 				let status = if promoted { "promoted" } else { "not promoted" };
-				println!("  {} {} ({}) -- score {:.1}, p50 {:.1} ms, {} (rank {}/{})",
-					rec.resolver.class, rec.resolver.label, ip, score, p50, status,
+				crate::info!("  {} {} ({}) -- score {:.1}, p50 {:.1} ms, {} (rank {}/{})",
+					rec.resolver.class, rec.resolver.label, key, score, p50, status,
 					i + 1, scored.len());
 			}
 		}
@@ -1060,8 +2672,7 @@ pub async fn run_qualification(
 
 	// Write QualificationResult on each record
 	for rec in records.iter_mut() {
-		let ip = rec.resolver.addr.ip();
-		if let Some((score, p50, p95, timeout_rate, promoted)) = score_map.get(&ip) {
+		if let Some((score, p50, p95, timeout_rate, promoted)) = score_map.get(rec.resolver.key().as_str()) {
 			rec.qualification = Some(crate::record::QualificationResult {
 				score: *score,
 				promoted: *promoted,
@@ -1075,7 +2686,7 @@ pub async fn run_qualification(
 	let promoted_count = records.iter()
 		.filter(|r| r.qualification.as_ref().is_some_and(|q| q.promoted))
 		.count();
-	println!("  Promoted {} finalists from {} candidates (budget {})",
+	crate::info!("  Promoted {} finalists from {} candidates (budget {})",
 		promoted_count, total_candidates, budget);
 }
 
@@ -1088,13 +2699,13 @@ pub async fn run_staged_benchmark(
 	records: &mut Vec<crate::record::ResolverRecord>,
 	categories: &std::collections::BTreeMap<String, Vec<String>>,
 	config: &BenchmarkConfig,
-	doh_clients: &DohClientPool,
+	connections: &ConnectionPool,
 ) -> Result<()> {
 	let purge_ratio = crate::transport::DEFAULT_SLOW_PURGE_RATIO;
 	let finalist_min = crate::transport::DEFAULT_SLOW_FINALIST_MIN;
 	let total_rounds = config.rounds;
 
-	println!("Staged elimination: {} resolvers, {} total rounds, purge {:.0}% per stage",
+	crate::info!("Staged elimination: {} resolvers, {} total rounds, purge {:.0}% per stage",
 		records.len(), total_rounds, purge_ratio * 100.0);
 
 	let mut round_offset = 0u32;
@@ -1105,13 +2716,16 @@ pub async fn run_staged_benchmark(
 		let mut block_config = config.clone();
 		block_config.rounds = block_rounds;
 
-		println!("  Stage: {} resolvers, rounds {}-{}",
+		crate::info!("  Stage: {} resolvers, rounds {}-{}",
 			records.len(),
 			round_offset + 1,
 			round_offset + block_rounds);
 
 		// Run benchmark on current records (writes BenchmarkResult in place)
-		run_benchmark(records, categories, &block_config, doh_clients).await?;
+		run_benchmark(records, categories, &block_config, connections).await?;
+		if interrupted() {
+			return Ok(());
+		}
 
 		round_offset += block_rounds;
 
@@ -1122,7 +2736,7 @@ pub async fn run_staged_benchmark(
 			let keep_count = target_count.max(finalist_min);
 			let before = records.len();
 			records.truncate(keep_count);
-			println!("  Purged: {} -> {} resolvers (removed weaker half)",
+			crate::info!("  Purged: {} -> {} resolvers (removed weaker half)",
 				before, records.len());
 			// Clear benchmark results for next round (will be recomputed)
 			for rec in records.iter_mut() {
@@ -1137,36 +2751,254 @@ pub async fn run_staged_benchmark(
 	let mut final_config = config.clone();
 	final_config.rounds = 2.min(total_rounds.saturating_sub(round_offset));
 	if final_config.rounds > 0 {
-		run_benchmark(records, categories, &final_config, doh_clients).await?;
+		run_benchmark(records, categories, &final_config, connections).await?;
 	}
 
 	Ok(())
 }
 
+/// Per-resolver timeout under --adaptive-timeout: the p99 of its answers
+/// so far times ADAPTIVE_TIMEOUT_FACTOR, kept between ADAPTIVE_TIMEOUT_FLOOR_MS
+/// and the configured timeout. None with too few answers to trust a p99.
+pub fn adaptive_timeout(latencies_ms: &[f64], configured: Duration) -> Option<Duration> {
+	if latencies_ms.len() < crate::transport::ADAPTIVE_TIMEOUT_MIN_SAMPLES {
+		return None;
+	}
+	let mut sorted = latencies_ms.to_vec();
+	sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	let p99 = crate::stats::percentile(&sorted, 99.0)?;
+	let floor = Duration::from_millis(crate::transport::ADAPTIVE_TIMEOUT_FLOOR_MS);
+	let tightened = Duration::from_secs_f64(p99 * crate::transport::ADAPTIVE_TIMEOUT_FACTOR / 1000.0);
+	Some(tightened.clamp(floor.min(configured), configured))
+}
+
+/// Adaptive timeout per resolver key from every answer in `results`; resolvers
+/// with too few answers keep the configured timeout and are left out.
+fn adaptive_timeouts(results: &[(QueryTask, QueryResult)], configured: Duration) -> HashMap<String, Duration> {
+	let mut answered: HashMap<String, Vec<f64>> = HashMap::new();
+	for (task, result) in results.iter().filter(|(t, r)| answered_as_expected(t, r)) {
+		answered.entry(task.key()).or_default().push(result.latency.as_secs_f64() * 1000.0);
+	}
+	answered.into_iter()
+		.filter_map(|(key, latencies)| Some((key, adaptive_timeout(&latencies, configured)?)))
+		.collect()
+}
+
+/// Set by the first Ctrl-C once the handler is installed
+static INTERRUPTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Whether the benchmark was interrupted by Ctrl-C.
+pub fn interrupted() -> bool {
+	INTERRUPTED.load(Ordering::Relaxed)
+}
+
+/// Catch Ctrl-C for the rest of the run: the first one stops benchmark
+/// pipelines from sending so the run can score and report what finished;
+/// a second one exits at once.
+pub fn install_interrupt_handler() {
+	tokio::spawn(async {
+		if tokio::signal::ctrl_c().await.is_err() {
+			return;
+		}
+		INTERRUPTED.store(true, Ordering::Relaxed);
+		crate::warn!("\nInterrupted: waiting for queries in flight, then reporting partial results (Ctrl-C again to quit)");
+		if tokio::signal::ctrl_c().await.is_ok() {
+			std::process::exit(130);
+		}
+	});
+}
+
+/// Query settings shared by every resolver pipeline in a round.
+struct QueryContext {
+	/// Global in-flight cap across all resolvers
+	semaphore: Arc<Semaphore>,
+	retries: u32,
+	late_grace: Duration,
+	spacing: Duration,
+	dnssec: bool,
+	cold_zone: Option<String>,
+	connections: ConnectionPool,
+	udp_pool: Option<Arc<crate::udp_pool::UdpSocketPool>>,
+	/// Queries finished this round, for the progress monitor
+	progress: Arc<AtomicUsize>,
+}
+
+/// Whether a query got the answer its set expects: NOERROR, or NXDOMAIN
+/// in the NXDOMAIN set.
+fn answered_as_expected(task: &QueryTask, result: &QueryResult) -> bool {
+	if task.set_name == crate::domains::NXDOMAIN_SET {
+		result.outcome.is_nxdomain()
+	} else {
+		result.outcome.is_ok()
+	}
+}
+
+/// A sent query: the task, its wall-clock and monotonic send times, and the result
+type SentQuery = (QueryTask, std::time::SystemTime, Instant, QueryResult);
+
+/// Run one resolver's queries for a round in its own worker.
+///
+/// Queries start in queue order, each once a per-resolver in-flight slot
+/// and (with a QPS limit) send slot are free, so every resolver keeps its
+/// own steady pace. A slow resolver only fills its own window while its
+/// timeouts run out; other resolvers' queues do not wait behind it.
+///
+/// If the first DEFAULT_ABANDON_TIMEOUTS queries to finish all time out, or
+/// one fails as unreachable before any answer, the rest of the queue is not
+/// sent; neither is it after Ctrl-C. Returns
+/// the outcomes in queue order and whether the pipeline was abandoned.
+async fn run_resolver_pipeline(
+	queue: Vec<QueryTask>,
+	round: u32,
+	timeout: Duration,
+	limiter: Arc<ResolverLimiter>,
+	ctx: Arc<QueryContext>,
+) -> (Vec<SentQuery>, bool) {
+	// Timeouts so far, until the first response of any kind resets the watch
+	let responded = Arc::new(std::sync::atomic::AtomicBool::new(false));
+	let timeouts = Arc::new(AtomicUsize::new(0));
+	let unreachable = Arc::new(std::sync::atomic::AtomicBool::new(false));
+	let dead = || !responded.load(Ordering::Relaxed)
+		&& (unreachable.load(Ordering::Relaxed) || timeouts.load(Ordering::Relaxed) >= crate::transport::DEFAULT_ABANDON_TIMEOUTS);
+	let queued = queue.len();
+	let mut sent = 0;
+	let mut abandoned = false;
+	let mut in_flight = tokio::task::JoinSet::new();
+	for (seq, mut task) in queue.into_iter().enumerate() {
+		task.round = round;
+		let resolver_permit = limiter.inflight.clone().acquire_owned().await.unwrap();
+		// Checked after the wait: the window frees up as timeouts come in
+		if dead() {
+			abandoned = true;
+			break;
+		}
+		if interrupted() {
+			break;
+		}
+		limiter.pace().await;
+		let ctx = ctx.clone();
+		let responded = responded.clone();
+		let timeouts = timeouts.clone();
+		let unreachable = unreachable.clone();
+		in_flight.spawn(async move {
+			let _resolver_permit = resolver_permit;
+			let _permit = ctx.semaphore.acquire().await.unwrap();
+			let outcome = run_query(task, timeout, &ctx).await;
+			if outcome.3.outcome.is_unreachable() {
+				unreachable.store(true, Ordering::Relaxed);
+			}
+			if outcome.3.outcome.no_answer() {
+				timeouts.fetch_add(1, Ordering::Relaxed);
+			} else {
+				responded.store(true, Ordering::Relaxed);
+			}
+			ctx.progress.fetch_add(1, Ordering::Relaxed);
+			(seq, outcome)
+		});
+		sent += 1;
+	}
+	// Unsent queries count as done for the round's progress line
+	ctx.progress.fetch_add(queued - sent, Ordering::Relaxed);
+	let mut outcomes = in_flight.join_all().await;
+	outcomes.sort_by_key(|(seq, _)| *seq);
+	// A late answer from the last window means the resolver is alive after all
+	let abandoned = abandoned && !responded.load(Ordering::Relaxed);
+	(outcomes.into_iter().map(|(_, outcome)| outcome).collect(), abandoned)
+}
+
+/// Send one benchmark query over its resolver's transport.
+async fn run_query(mut task: QueryTask, timeout: Duration, ctx: &QueryContext) -> SentQuery {
+	let mut rng = StdRng::seed_from_u64(task.seed);
+	// Guaranteed cache miss: a name no one has asked for before
+	if let (Some(zone), true) = (&ctx.cold_zone, task.set_name == crate::domains::COLD_SET) {
+		task.domain = crate::domains::cold_name(zone, crate::domains::cold_nonce(), &mut rng);
+	}
+
+	// Inter-query spacing delay with random jitter (0-50% of spacing)
+	if !ctx.spacing.is_zero() {
+		let jitter_ms = rng.gen_range(0..=ctx.spacing.as_millis() as u64 / 2);
+		tokio::time::sleep(ctx.spacing + std::time::Duration::from_millis(jitter_ms)).await;
+	}
+
+	// Transaction ID
+	let txid: u16 = rng.gen();
+	// Send time for the raw export: wall clock plus monotonic
+	let sent_wall = std::time::SystemTime::now();
+	let sent_mono = Instant::now();
+
+	// Shared-socket path: the pool allocates its own collision-free txid
+	if let (Some(pool), DnsTransport::Udp) = (&ctx.udp_pool, &task.resolver_transport) {
+		let result = pool.query(
+			task.resolver_addr, &task.domain, task.query_type, ctx.dnssec, timeout, ctx.retries, ctx.late_grace, &mut rng,
+		).await;
+		crate::debug!("  UDP (pool) {} {} {:?}: {}", result.resolver, task.domain, task.query_type, query_outcome(&result));
+		return (task, sent_wall, sent_mono, result);
+	}
+
+	// Build the DNS query
+	let query_bytes = match build_query(&task.domain, task.query_type, txid, ctx.dnssec) {
+		Ok(bytes) => bytes,
+		Err(_) => {
+			let result = QueryResult {
+				resolver: task.resolver_addr.ip().to_string(),
+				latency: Duration::ZERO,
+				outcome: QueryOutcome::NetworkError(std::io::ErrorKind::InvalidInput),
+				ttl: None,
+				attempts: 1,
+				late_latency: None,
+				duplicates: 0,
+			};
+			return (task, sent_wall, sent_mono, result);
+		}
+	};
+
+	// Send query via appropriate transport
+	let result = dispatch_query(
+		task.resolver_addr, &task.resolver_transport, &query_bytes,
+		timeout, ctx.retries, ctx.late_grace, txid, &task.domain, task.query_type,
+		&ctx.connections,
+	).await;
+	(task, sent_wall, sent_mono, result)
+}
+
 /// Run the full benchmark across all resolvers and domains.
 ///
-/// Executes multiple rounds of queries, shuffling the order each round.
+/// Executes multiple rounds of queries. Each round runs one pipeline per
+/// resolver over its own shuffled queue (see `run_resolver_pipeline`).
 /// Returns scored and ranked resolver results.
 #[allow(clippy::too_many_arguments)]
 pub async fn run_benchmark(
 	records: &mut [crate::record::ResolverRecord],
 	categories: &std::collections::BTreeMap<String, Vec<String>>,
 	config: &BenchmarkConfig,
-	doh_clients: &DohClientPool,
+	connections: &ConnectionPool,
 ) -> Result<()> {
 	// Determine which query types to use
-	let query_types = if config.query_aaaa {
+	let mut query_types = if config.query_aaaa {
 		vec![QueryType::A, QueryType::AAAA]
 	} else {
 		vec![QueryType::A]
 	};
+	if config.query_https {
+		query_types.push(QueryType::HTTPS);
+	}
 
-	// Build the list of all query tasks from all categories
-	let mut tasks: Vec<QueryTask> = Vec::new();
+	// Build each resolver's query tasks from all categories, keyed by
+	// resolver key and paced by the IP's limiter
+	let mut pipelines: Vec<(String, std::net::IpAddr, Vec<QueryTask>)> = Vec::new();
 	for rec in records.iter() {
 		let resolver = &rec.resolver;
+		let mut tasks: Vec<QueryTask> = Vec::new();
 		for (category_name, domains) in categories {
+			// Warm set: skip domains the pre-probe found were not already cached,
+			// unless that would leave the set empty
+			let warm_excluded = rec.warm_probe.as_ref()
+				.filter(|w| category_name == "cached" && w.excluded.len() < domains.len())
+				.map(|w| &w.excluded);
 			for domain in domains {
+				if warm_excluded.is_some_and(|ex| ex.contains(domain)) {
+					continue;
+				}
 				for &qt in &query_types {
 					tasks.push(QueryTask {
 						resolver_addr: resolver.addr,
@@ -1174,43 +3006,116 @@ pub async fn run_benchmark(
 						domain: domain.clone(),
 						query_type: qt,
 						set_name: category_name.clone(),
+						round: 0,
+						seed: 0,
 					});
 				}
 			}
 		}
+		pipelines.push((resolver.key(), resolver.addr.ip(), tasks));
 	}
 
-	let total_queries = tasks.len() * config.rounds as usize;
-	println!("  {} queries across {} resolvers, {} rounds",
+	let total_queries = pipelines.iter().map(|(_, _, tasks)| tasks.len()).sum::<usize>() * config.rounds as usize;
+	crate::info!("  {} queries across {} resolvers, {} rounds",
 		total_queries, records.len(), config.rounds);
 
 	// Collect all results across rounds
 	let mut all_results: Vec<(QueryTask, QueryResult)> = Vec::new();
 	let semaphore = std::sync::Arc::new(Semaphore::new(config.max_inflight));
+	let limiters: HashMap<std::net::IpAddr, Arc<ResolverLimiter>> = records.iter()
+		.map(|r| r.resolver.addr.ip())
+		.collect::<std::collections::HashSet<_>>()
+		.into_iter()
+		.map(|ip| (ip, Arc::new(ResolverLimiter::new(config.per_resolver_inflight, config.per_resolver_qps))))
+		.collect();
 
-	// Create a seeded RNG for reproducible shuffling
-	let mut rng = match config.seed {
-		Some(seed) => StdRng::seed_from_u64(seed),
-		None => StdRng::from_entropy(),
-	};
+	// NXDOMAIN set latencies per resolver: (first round, repeat rounds)
+	let mut nx_latencies: HashMap<String, (Vec<f64>, Vec<f64>)> = HashMap::new();
+
+	// Best identity per resolver key after each round (--identity-probe)
+	let mut identity_history: HashMap<String, Vec<Option<String>>> = HashMap::new();
 
-	// Track sidelined resolvers (by IP string)
+	// Tightened per-resolver timeouts (--adaptive-timeout); others use config.timeout
+	let mut resolver_timeouts: HashMap<String, Duration> = HashMap::new();
+
+	// Track sidelined resolvers (by resolver key)
 	let mut sidelined: std::collections::HashSet<String> = std::collections::HashSet::new();
+	// Of those, the ones abandoned mid-round as unresponsive
+	let mut unresponsive: std::collections::HashSet<String> = std::collections::HashSet::new();
+	// Of those, the ones the network reported unreachable
+	let mut unreachable: std::collections::HashSet<String> = std::collections::HashSet::new();
 	// Build config map for sidelining messages and metadata lookups
 	let sideline_config_map: HashMap<String, &Resolver> = records.iter()
-		.map(|r| (r.resolver.addr.ip().to_string(), &r.resolver))
+		.map(|r| (r.resolver.key(), &r.resolver))
 		.collect();
 
-	for round in 0..config.rounds {
+	// --resume: restore the rounds saved in the checkpoint and start after them
+	let mut first_round = 0;
+	if let (true, Some(path)) = (config.resume, &config.checkpoint) {
+		if let Some(saved) = crate::checkpoint::Checkpoint::read_for_resume(path, &config.checkpoint_fingerprint)? {
+			if saved.rounds_done > config.rounds {
+				bail!("checkpoint {} already has {} rounds but --rounds is {}", path, saved.rounds_done, config.rounds);
+			}
+			first_round = saved.rounds_done;
+			let query_count = saved.queries.len();
+			for q in saved.queries {
+				let Some(resolver) = sideline_config_map.get(&q.result.resolver) else { continue };
+				if q.set_name == crate::domains::NXDOMAIN_SET && q.result.outcome.is_nxdomain() {
+					let ms = q.result.latency.as_secs_f64() * 1000.0;
+					let entry = nx_latencies.entry(q.result.resolver.clone()).or_default();
+					if q.round == 0 { entry.0.push(ms); } else { entry.1.push(ms); }
+				}
+				let task = QueryTask {
+					resolver_addr: resolver.addr,
+					resolver_transport: resolver.transport.clone(),
+					domain: q.domain,
+					query_type: q.query_type,
+					set_name: q.set_name,
+					round: q.round,
+					seed: 0,
+				};
+				all_results.push((task, q.result));
+			}
+			sidelined.extend(saved.sidelined);
+			unresponsive.extend(saved.unresponsive);
+			unreachable.extend(saved.unreachable);
+			if config.adaptive_timeout {
+				resolver_timeouts = adaptive_timeouts(&all_results, config.timeout);
+			}
+			crate::info!("  Resumed {} of {} rounds ({} queries, {} resolvers sidelined) from {}",
+				first_round, config.rounds, query_count, sidelined.len(), path);
+		} else {
+			crate::info!("  No checkpoint at {} yet; starting from round 1", path);
+		}
+	}
+
+	// Create a seeded RNG for reproducible shuffling; a resumed run offsets
+	// the seed so its rounds do not repeat the first rounds' order
+	let mut rng = match config.seed {
+		Some(seed) => StdRng::seed_from_u64(seed.wrapping_add(first_round as u64)),
+		None => StdRng::from_entropy(),
+	};
+
+	for round in first_round..config.rounds {
+		// Ctrl-C: score the rounds already run
+		if interrupted() {
+			break;
+		}
 		let round_start = std::time::Instant::now();
-		// Filter out sidelined resolvers for this round
-		let mut round_tasks = tasks.clone();
-		if !sidelined.is_empty() {
-			round_tasks.retain(|t| !sidelined.contains(&t.resolver_addr.ip().to_string()));
+		// Sidelined resolvers get no pipeline; each other resolver's queue is
+		// shuffled on its own, interleaving its sets and domains
+		let mut round_pipelines: Vec<(String, std::net::IpAddr, Vec<QueryTask>)> = pipelines.iter()
+			.filter(|(key, _, _)| !sidelined.contains(key))
+			.cloned()
+			.collect();
+		for (_, _, queue) in &mut round_pipelines {
+			queue.shuffle(&mut rng);
+			for task in queue.iter_mut() {
+				task.seed = rng.gen();
+			}
 		}
-		round_tasks.shuffle(&mut rng);
 
-		let round_total = round_tasks.len();
+		let round_total = round_pipelines.iter().map(|(_, _, queue)| queue.len()).sum();
 		let completed_count = Arc::new(AtomicUsize::new(0));
 
 		// Progress monitor with ETA
@@ -1219,67 +3124,65 @@ pub async fn run_benchmark(
 			round_label.clone(), completed_count.clone(), round_total, round_start,
 		);
 
-		// Spawn all query tasks for this round
+		// One worker per resolver for this round
+		let ctx = Arc::new(QueryContext {
+			semaphore: semaphore.clone(),
+			retries: config.retries,
+			late_grace: config.late_grace,
+			spacing: config.inter_query_spacing,
+			dnssec: config.dnssec,
+			cold_zone: config.cold_zone.clone(),
+			connections: connections.clone(),
+			udp_pool: config.udp_pool.clone(),
+			progress: completed_count.clone(),
+		});
 		let mut handles = Vec::new();
-		for task in round_tasks {
-			let sem = semaphore.clone();
-			let timeout = config.timeout;
-			let spacing = config.inter_query_spacing;
-			let dnssec = config.dnssec;
-			let doh_clients = doh_clients.clone();
-			let progress = completed_count.clone();
-
-			handles.push(tokio::spawn(async move {
-				// Acquire semaphore permit for concurrency control
-				let _permit = sem.acquire().await.unwrap();
-
-				// Inter-query spacing delay with random jitter (0-50% of spacing)
-				if !spacing.is_zero() {
-					let jitter_ms = rand::random::<u64>() % (spacing.as_millis() as u64 / 2 + 1);
-					tokio::time::sleep(spacing + std::time::Duration::from_millis(jitter_ms)).await;
-				}
-
-				// Generate a random transaction ID
-				let txid: u16 = rand::random();
-
-				// Build the DNS query
-				let query_bytes = match build_query(
-					&task.domain, task.query_type, txid, dnssec,
-				) {
-					Ok(bytes) => bytes,
-					Err(_) => {
-						return (task.clone(), QueryResult {
-							resolver: task.resolver_addr.ip().to_string(),
-							latency: Duration::ZERO,
-							success: false,
-							timeout: false,
-						});
-					}
-				};
-
-				// Send query via appropriate transport
-				let result = dispatch_query(
-					task.resolver_addr, &task.resolver_transport, &query_bytes,
-					timeout, txid, &task.domain, task.query_type,
-					&doh_clients,
-				).await;
-
-				// Increment progress counter
-				progress.fetch_add(1, Ordering::Relaxed);
-
-				(task, result)
-			}));
+		for (key, ip, queue) in round_pipelines {
+			let limiter = limiters[&ip].clone();
+			let timeout = resolver_timeouts.get(&key).copied().unwrap_or(config.timeout);
+			handles.push(tokio::spawn(run_resolver_pipeline(queue, round, timeout, limiter, ctx.clone())));
 		}
 
-		// Collect results from all tasks in this round
+		// Collect results from every pipeline in this round
 		for handle in handles {
-			match handle.await {
-				Ok((task, result)) => {
-					all_results.push((task, result));
-				}
+			let (outcomes, abandoned) = match handle.await {
+				Ok(result) => result,
 				Err(e) => {
-					eprintln!("Warning: task failed: {}", e);
+					crate::warn!("resolver pipeline failed: {}", e);
+					continue;
+				}
+			};
+			// Dead resolver: its first queries all timed out, or the network
+			// reported it unreachable; skip it from now on
+			if let (true, Some((task, _, _, _))) = (abandoned, outcomes.first()) {
+				let key = task.key();
+				let label = sideline_config_map.get(&key).map(|r| r.label.clone()).unwrap_or_else(|| key.clone());
+				let refused = outcomes.iter().map(|(_, _, _, r)| r.outcome).find(|o| o.is_unreachable());
+				let reason = match refused {
+					Some(outcome) => format!("unreachable, {}", outcome),
+					None => format!("unresponsive, first {} queries timed out", outcomes.len()),
+				};
+				crate::info!("  Abandoned {} ({}) -- {}", label, key, reason);
+				config.telemetry.log_sidelined(&key, &reason, round + 1);
+				sidelined.insert(key.clone());
+				if refused.is_some() {
+					unreachable.insert(key.clone());
+				}
+				unresponsive.insert(key);
+			}
+			for (task, sent_wall, sent_mono, result) in outcomes {
+				// In the NXDOMAIN set an NXDOMAIN answer is the expected outcome
+				if task.set_name == crate::domains::NXDOMAIN_SET && result.outcome.is_nxdomain() {
+					let ms = result.latency.as_secs_f64() * 1000.0;
+					let entry = nx_latencies.entry(task.key()).or_default();
+					if round == 0 { entry.0.push(ms); } else { entry.1.push(ms); }
 				}
+				config.raw_log.log_query(
+					sent_wall, sent_mono, round + 1, &result.resolver,
+					&task.resolver_transport.to_string(), &task.set_name, &task.domain,
+					&format!("{:?}", task.query_type), &result,
+				);
+				all_results.push((task, result));
 			}
 		}
 
@@ -1288,10 +3191,7 @@ pub async fn run_benchmark(
 
 		// Log round completion to telemetry
 		let round_failures = all_results.iter()
-			.filter(|(t, r)| {
-				let ip = t.resolver_addr.ip().to_string();
-				!sidelined.contains(&ip) && !r.success
-			})
+			.filter(|(t, r)| !sidelined.contains(&t.key()) && !answered_as_expected(t, r))
 			.count();
 		config.telemetry.log_round_complete(round + 1, round_total, round_failures);
 
@@ -1299,18 +3199,18 @@ pub async fn run_benchmark(
 		{
 			let mut round_stats: HashMap<String, (usize, usize, usize, Vec<f64>)> = HashMap::new();
 			for (task, result) in &all_results {
-				let ip = task.resolver_addr.ip().to_string();
-				if sidelined.contains(&ip) { continue; }
+				let key = task.key();
+				if sidelined.contains(&key) { continue; }
 				// Only count results from the current round
-				let entry = round_stats.entry(ip).or_insert((0, 0, 0, Vec::new()));
+				let entry = round_stats.entry(key).or_insert((0, 0, 0, Vec::new()));
 				entry.0 += 1; // queries
-				if result.success {
+				if answered_as_expected(task, result) {
 					entry.1 += 1; // successes
 					entry.3.push(result.latency.as_secs_f64() * 1000.0);
 				}
-				if result.timeout { entry.2 += 1; } // timeouts
+				if result.outcome.no_answer() { entry.2 += 1; } // timeouts
 			}
-			for (ip, (queries, successes, timeouts, latencies)) in &round_stats {
+			for (key, (queries, successes, timeouts, latencies)) in &round_stats {
 				let (p50, mean, stddev) = if latencies.is_empty() {
 					(0.0, 0.0, 0.0)
 				} else {
@@ -1322,37 +3222,74 @@ pub async fn run_benchmark(
 					(p50, mean, var.sqrt())
 				};
 				config.telemetry.log_round_resolver(
-					round + 1, ip, *queries, *successes, *timeouts,
+					round + 1, key, *queries, *successes, *timeouts,
 					p50, mean, stddev,
 				);
 			}
 		}
 
+		// Anycast PoP tracking: ask which site answered, between rounds so the
+		// probe never competes with timed queries
+		if config.identity_rounds {
+			let addrs: HashMap<String, std::net::SocketAddr> = records.iter()
+				.map(|r| (r.resolver.key(), r.resolver.addr))
+				.filter(|(key, _)| !sidelined.contains(key))
+				.collect();
+			let mut handles = Vec::new();
+			for (key, addr) in addrs {
+				let sem = semaphore.clone();
+				let tm = config.timeout;
+				handles.push(tokio::spawn(async move {
+					let _permit = sem.acquire().await.unwrap();
+					(key, probe_identity(addr, tm).await)
+				}));
+			}
+			for handle in handles {
+				if let Ok((key, identity)) = handle.await {
+					identity_history.entry(key).or_default().push(identity.best().map(str::to_string));
+				}
+			}
+		}
+
+		// Adaptive timeout: re-derive each resolver's timeout from all its answers so far
+		if config.adaptive_timeout && round < config.rounds - 1 {
+			resolver_timeouts = adaptive_timeouts(&all_results, config.timeout);
+			if let (Some(min), Some(max)) = (resolver_timeouts.values().min(), resolver_timeouts.values().max()) {
+				let range = if min == max {
+					format!("{} ms", min.as_millis())
+				} else {
+					format!("{}-{} ms", min.as_millis(), max.as_millis())
+				};
+				crate::info!("  Adaptive timeout: {} of {} resolvers at {} (configured {} ms)",
+					resolver_timeouts.len(), pipelines.len(), range, config.timeout.as_millis());
+			}
+		}
+
 		// Mid-benchmark sidelining: check for slow/dead resolvers after each round
 		if round < config.rounds - 1 {
 			let mut per_resolver: HashMap<String, (usize, usize, Vec<f64>)> = HashMap::new();
 			for (task, result) in &all_results {
-				let ip = task.resolver_addr.ip().to_string();
-				if sidelined.contains(&ip) {
+				let key = task.key();
+				if sidelined.contains(&key) {
 					continue;
 				}
-				let entry = per_resolver.entry(ip).or_insert((0, 0, Vec::new()));
+				let entry = per_resolver.entry(key).or_insert((0, 0, Vec::new()));
 				entry.0 += 1; // total
-				if result.timeout { entry.1 += 1; } // timeouts
-				if result.success {
+				if result.outcome.no_answer() { entry.1 += 1; } // timeouts
+				if answered_as_expected(task, result) {
 					entry.2.push(result.latency.as_secs_f64() * 1000.0);
 				}
 			}
-			for (ip, (total, timeouts, latencies)) in &per_resolver {
+			for (key, (total, timeouts, latencies)) in &per_resolver {
 				let timeout_rate = *timeouts as f64 / *total as f64;
 				// Sideline if >80% timeouts
 				if timeout_rate > 0.8 {
-					let label = sideline_config_map.get(ip)
-						.map(|r| r.label.clone()).unwrap_or_else(|| ip.clone());
+					let label = sideline_config_map.get(key)
+						.map(|r| r.label.clone()).unwrap_or_else(|| key.clone());
 					let reason = format!("{:.0}% timeouts", timeout_rate * 100.0);
-					println!("  Sidelined {} ({}) -- {}", label, ip, reason);
-					config.telemetry.log_sidelined(ip, &reason, round + 1);
-					sidelined.insert(ip.clone());
+					crate::info!("  Sidelined {} ({}) -- {}", label, key, reason);
+					config.telemetry.log_sidelined(key, &reason, round + 1);
+					sidelined.insert(key.clone());
 					continue;
 				}
 				// Sideline if p50 exceeds threshold
@@ -1361,97 +3298,245 @@ pub async fn run_benchmark(
 					sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
 					let p50 = sorted[sorted.len() / 2];
 					if p50 > crate::transport::DEFAULT_SIDELINE_MS {
-						let label = sideline_config_map.get(ip)
-							.map(|r| r.label.clone()).unwrap_or_else(|| ip.clone());
+						let label = sideline_config_map.get(key)
+							.map(|r| r.label.clone()).unwrap_or_else(|| key.clone());
 						let reason = format!("p50 {:.0} ms > {} ms threshold", p50, crate::transport::DEFAULT_SIDELINE_MS as u64);
-						println!("  Sidelined {} ({}) -- {}", label, ip, reason);
-						config.telemetry.log_sidelined(ip, &reason, round + 1);
-						sidelined.insert(ip.clone());
+						crate::info!("  Sidelined {} ({}) -- {}", label, key, reason);
+						config.telemetry.log_sidelined(key, &reason, round + 1);
+						sidelined.insert(key.clone());
 					}
 				}
 			}
 		}
+
+		// Save the completed round; a round cut short by Ctrl-C is not saved
+		if let (Some(path), false) = (&config.checkpoint, interrupted()) {
+			let checkpoint = crate::checkpoint::Checkpoint {
+				fingerprint: config.checkpoint_fingerprint.clone(),
+				resolvers: records.iter().map(|r| r.resolver.key()).collect(),
+				rounds_done: round + 1,
+				sidelined: sidelined.iter().cloned().collect(),
+				unresponsive: unresponsive.iter().cloned().collect(),
+				unreachable: unreachable.iter().cloned().collect(),
+				queries: all_results.iter()
+					.map(|(task, result)| crate::checkpoint::SavedQuery {
+						round: task.round,
+						set_name: task.set_name.clone(),
+						domain: task.domain.clone(),
+						query_type: task.query_type,
+						result: QueryResult { resolver: task.key(), ..result.clone() },
+					})
+					.collect(),
+			};
+			if let Err(e) = checkpoint.write(path) {
+				crate::warn!("checkpoint not saved: {:#}", e);
+			}
+		}
 	}
 
+	config.raw_log.flush();
+	// Answers the shared sockets caught after their query finished (--udp-pool)
+	let stray_responses = config.udp_pool.as_ref().map(|pool| pool.take_stray_responses()).unwrap_or_default();
+
 	// Aggregate results per resolver
 	let timeout_penalty_ms = config.timeout.as_millis() as f64;
 	let mut resolver_data: HashMap<String, ResolverAggregation> = HashMap::new();
 
+	// Reference TTL per (domain, query type): the highest TTL any resolver returned.
+	// A response below the reference has a decremented TTL and looks cached.
+	let mut reference_ttl: HashMap<(&str, QueryType), u32> = HashMap::new();
+	for (task, result) in &all_results {
+		if let (true, Some(ttl)) = (result.outcome.is_ok(), result.ttl) {
+			let entry = reference_ttl.entry((task.domain.as_str(), task.query_type)).or_insert(0);
+			*entry = (*entry).max(ttl);
+		}
+	}
+
 	for (task, result) in &all_results {
 		let entry = resolver_data
-			.entry(result.resolver.clone())
+			.entry(task.key())
 			.or_default();
 		let latency_ms = result.latency.as_secs_f64() * 1000.0;
 
+		// TTL-based cache-hit accounting
+		if let (true, Some(ttl)) = (result.outcome.is_ok(), result.ttl) {
+			entry.ttl_samples += 1;
+			let reference = reference_ttl.get(&(task.domain.as_str(), task.query_type)).copied();
+			if reference.is_some_and(|r| ttl < r) {
+				entry.cache_hits += 1;
+			}
+		}
+
+		// Late arrivals: timeouts whose answer came during the grace period
+		if result.outcome.no_answer() && task.set_name != crate::domains::NXDOMAIN_SET {
+			entry.scored_timeouts += 1;
+			if let Some(late) = result.late_latency {
+				entry.late_latencies.push(late.as_secs_f64() * 1000.0);
+			}
+		}
+		entry.duplicates += result.duplicates as usize;
+
 		// Aggregate into the appropriate category bucket
 		let cat = entry.categories
 			.entry(task.set_name.clone())
 			.or_default();
-		if result.success {
+		if cat.rounds.is_empty() {
+			cat.rounds.resize(config.rounds as usize, crate::record::RoundAggregate::default());
+		}
+		let round_agg = &mut cat.rounds[task.round as usize];
+		if answered_as_expected(task, result) {
 			cat.latencies.push(latency_ms);
 			cat.success += 1;
+			round_agg.latencies_ms.push(latency_ms);
+			round_agg.success += 1;
+			if result.attempts > 1 {
+				cat.retried += 1;
+				round_agg.retried += 1;
+			}
 		}
 		cat.total += 1;
-		if result.timeout {
+		round_agg.total += 1;
+		if result.outcome.no_answer() {
 			cat.timeout += 1;
+			round_agg.timeout += 1;
+			cat.failures.record(&result.outcome);
+			round_agg.failures.record(&result.outcome);
+		}
+		// Error answers by code; NXDOMAIN is the expected answer in its own set
+		let expected_nx = task.set_name == crate::domains::NXDOMAIN_SET && result.outcome.is_nxdomain();
+		if let (Some(rcode), false) = (result.outcome.rcode(), expected_nx) {
+			cat.rcodes.record(rcode);
+			round_agg.rcodes.record(rcode);
+		}
+
+		// Scored sets again, split by query type
+		if task.set_name != crate::domains::NXDOMAIN_SET {
+			let by_type = entry.query_types.entry(format!("{:?}", task.query_type)).or_default();
+			by_type.total += 1;
+			if result.outcome.is_ok() {
+				by_type.latencies.push(latency_ms);
+				by_type.success += 1;
+			}
+			if result.outcome.no_answer() {
+				by_type.timeout += 1;
+				by_type.failures.record(&result.outcome);
+			}
+			if let Some(rcode) = result.outcome.rcode() {
+				by_type.rcodes.record(rcode);
+			}
 		}
 	}
 
 	// Write BenchmarkResult onto each existing record
-	// Build IP-to-record-index map
-	let ip_to_idx: HashMap<String, usize> = records.iter().enumerate()
-		.map(|(i, r)| (r.resolver.addr.ip().to_string(), i))
+	// Build key-to-record-index map
+	let key_to_idx: HashMap<String, usize> = records.iter().enumerate()
+		.map(|(i, r)| (r.resolver.key(), i))
 		.collect();
 
-	// Track all latencies per record for uncertainty computation
-	let mut all_latencies: Vec<Vec<f64>> = vec![Vec::new(); records.len()];
+	// Staged elimination calls this once per block, so extend the history
+	for (key, history) in identity_history {
+		if let Some(&idx) = key_to_idx.get(&key) {
+			records[idx].identity_rounds.extend(history);
+		}
+	}
 
-	for (resolver_ip, agg) in &resolver_data {
-		let idx = match ip_to_idx.get(resolver_ip) {
+	for (resolver_key, agg) in &resolver_data {
+		let idx = match key_to_idx.get(resolver_key) {
 			Some(&i) => i,
 			None => continue,
 		};
+		// The shared UDP sockets only ever carry plain UDP queries
+		let strays = match records[idx].resolver.transport {
+			DnsTransport::Udp => stray_responses.get(&records[idx].resolver.addr).copied().unwrap_or_default(),
+			_ => Default::default(),
+		};
 
-		// Compute per-category stats
-		let mut cat_stats: std::collections::BTreeMap<String, crate::stats::SetStats> = std::collections::BTreeMap::new();
-		for (cat_name, cat_agg) in &agg.categories {
+		// Compute per-category and per-query-type stats
+		let set_stats = |cat_agg: &CategoryAgg| {
 			let stats = compute_set_stats(
 				&cat_agg.latencies, cat_agg.success,
-				cat_agg.timeout, cat_agg.total, timeout_penalty_ms,
+				cat_agg.timeout, cat_agg.total, timeout_penalty_ms, &config.score_weights,
 			);
-			cat_stats.insert(cat_name.clone(), stats);
-		}
-
-		// Overall score: average of all categories that have data
-		let scored_categories: Vec<f64> = cat_stats.values()
-			.filter(|s| s.total_count > 0)
-			.map(|s| s.score)
+			crate::stats::SetStats { rcodes: cat_agg.rcodes, failures: cat_agg.failures, ..stats }
+		};
+		let cat_stats: std::collections::BTreeMap<String, crate::stats::SetStats> = agg.categories.iter()
+			.map(|(name, cat_agg)| (name.clone(), set_stats(cat_agg)))
+			.collect();
+		let query_types = agg.query_types.iter()
+			.map(|(name, type_agg)| (name.clone(), set_stats(type_agg)))
 			.collect();
-		let overall_score = if scored_categories.is_empty() {
-			f64::INFINITY
+
+		// Overall score: weighted mean of the sets that have data (the
+		// NXDOMAIN set is reported separately and not scored)
+		let overall_score = crate::stats::rescore(&cat_stats, &query_types, timeout_penalty_ms, &config.score_weights)
+			+ config.score_weights.characterization_adjustment(records[idx].characterization.as_ref());
+
+		// Total success rate across all scored categories
+		let scored_aggs = || agg.categories.iter()
+			.filter(|(name, _)| name.as_str() != crate::domains::NXDOMAIN_SET)
+			.map(|(_, c)| c);
+		let total: usize = scored_aggs().map(|c| c.total).sum();
+		let total_success: usize = scored_aggs().map(|c| c.success).sum();
+		let total_retried: usize = scored_aggs().map(|c| c.retried).sum();
+		let (success_rate, retry_success_rate) = if total > 0 {
+			(
+				(total_success as f64 / total as f64) * 100.0,
+				(total_retried as f64 / total as f64) * 100.0,
+			)
 		} else {
-			scored_categories.iter().sum::<f64>() / scored_categories.len() as f64
+			(0.0, 0.0)
 		};
 
-		// Total success rate across all categories
-		let total: usize = agg.categories.values().map(|c| c.total).sum();
-		let total_success: usize = agg.categories.values().map(|c| c.success).sum();
-		let success_rate = if total > 0 {
-			(total_success as f64 / total as f64) * 100.0
+		// Share of answers with a decremented TTL (None without TTL samples)
+		let cache_hit_rate = if agg.ttl_samples > 0 {
+			Some((agg.cache_hits as f64 / agg.ttl_samples as f64) * 100.0)
 		} else {
-			0.0
+			None
 		};
 
-		// Combine all latencies for uncertainty computation
-		for cat_agg in agg.categories.values() {
-			all_latencies[idx].extend(&cat_agg.latencies);
-		}
+		// Negative-cache speedup: first-round NXDOMAIN p50 minus repeat-round p50
+		let negative_cache_speedup_ms = nx_latencies.get(resolver_key).and_then(|(first, repeat)| {
+			let mut first = first.clone();
+			let mut repeat = repeat.clone();
+			first.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+			repeat.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+			let first_p50 = crate::stats::percentile(&first, 50.0)?;
+			let repeat_p50 = crate::stats::percentile(&repeat, 50.0)?;
+			Some(first_p50 - repeat_p50)
+		});
+
+		let stability_cv = crate::stats::overall_stability(&cat_stats);
+
+		let mut late_sorted = agg.late_latencies.clone();
+		late_sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+		let late_p50_ms = crate::stats::percentile(&late_sorted, 50.0);
+
+		// Keep the per-round raw data for library consumers
+		let raw = crate::record::RawAggregates {
+			sets: agg.categories.iter()
+				.map(|(name, cat_agg)| (name.clone(), cat_agg.rounds.clone()))
+				.collect(),
+		};
 
 		// Write benchmark result onto existing record (preserves characterization etc.)
 		records[idx].benchmark = Some(crate::record::BenchmarkResult {
 			categories: cat_stats,
+			query_types,
 			overall_score,
 			success_rate,
+			first_attempt_rate: success_rate - retry_success_rate,
+			retry_success_rate,
+			cache_hit_rate,
+			negative_cache_speedup_ms,
+			stability_cv,
+			scored_timeouts: agg.scored_timeouts,
+			late_arrivals: agg.late_latencies.len(),
+			late_p50_ms,
+			duplicate_responses: agg.duplicates + strays.duplicate,
+			after_grace_responses: strays.late,
+			unresponsive: unresponsive.contains(resolver_key),
+			unreachable: unreachable.contains(resolver_key),
+			raw,
 			rank: 0,
 			tie_group: None,
 		});
@@ -1459,22 +3544,25 @@ pub async fn run_benchmark(
 
 	// Rank records by sort mode
 	rank_records(records, &config.sort_mode);
+	detect_ties(records);
 
-	// Build uncertainty map for tie detection
-	let uncertainty_map: HashMap<String, f64> = records.iter().enumerate()
-		.map(|(i, rec)| {
-			(rec.resolver.label.clone(), compute_uncertainty(&all_latencies[i]))
-		})
-		.collect();
+	Ok(())
+}
 
-	let uncertainties: Vec<f64> = records.iter()
-		.map(|rec| {
-			uncertainty_map.get(&rec.resolver.label).copied().unwrap_or(0.0)
-		})
+/// Set tie groups for records in their current order: a significance test
+/// between neighbors, uncertainty overlap for small samples. Call again
+/// after reordering, with the old groups cleared.
+pub fn detect_ties(records: &mut [crate::record::ResolverRecord]) {
+	// Scored-set latencies in ranked order (raw aggregates travel with each record)
+	let latencies: Vec<Vec<f64>> = records.iter()
+		.map(|rec| rec.benchmark.as_ref()
+			.map(|bm| bm.raw.scored_latencies_ms())
+			.unwrap_or_default())
 		.collect();
-	detect_ties_on_records(records, &uncertainties);
-
-	Ok(())
+	let uncertainties: Vec<f64> = latencies.iter()
+		.map(|l| compute_uncertainty(l))
+		.collect();
+	detect_ties_significance(records, &latencies, &uncertainties);
 }
 
 /// Per-category aggregation of query results
@@ -1482,12 +3570,611 @@ pub async fn run_benchmark(
 struct CategoryAgg {
 	latencies: Vec<f64>,
 	success: usize,
+	/// Successes that needed at least one UDP retransmission
+	retried: usize,
 	total: usize,
 	timeout: usize,
+	rcodes: crate::stats::RcodeCounts,
+	failures: crate::stats::FailureCounts,
+	/// Same counters split by round, indexed from round 1 at position 0
+	rounds: Vec<crate::record::RoundAggregate>,
 }
 
 /// Intermediate aggregation of query results for a single resolver
 #[derive(Default)]
 struct ResolverAggregation {
 	categories: std::collections::BTreeMap<String, CategoryAgg>,
+	/// Successful responses that carried an answer TTL
+	ttl_samples: usize,
+	/// Responses whose TTL was below the run's reference TTL for that name
+	cache_hits: usize,
+	/// Scored-set timeouts, and arrival times (ms) of those answered late
+	scored_timeouts: usize,
+	late_latencies: Vec<f64>,
+	/// Extra copies of answers seen by the queries themselves
+	duplicates: usize,
+	/// Scored-set counters keyed by query type ("A", "AAAA"); rounds unused
+	query_types: std::collections::BTreeMap<String, CategoryAgg>,
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_udp_attempt_windows_no_retries() {
+		let windows = udp_attempt_windows(Duration::from_millis(2000), 0);
+		assert_eq!(windows, vec![Duration::from_millis(2000)]);
+	}
+
+	#[test]
+	fn test_udp_attempt_windows_backoff_sums_to_timeout() {
+		let timeout = Duration::from_millis(2000);
+		let windows = udp_attempt_windows(timeout, 2);
+		assert_eq!(windows.len(), 3);
+		// Each window at least doubles the previous one
+		assert!(windows[1] >= windows[0] * 2);
+		assert!(windows[2] >= windows[1] * 2);
+		assert_eq!(windows.iter().sum::<Duration>(), timeout);
+	}
+
+	#[test]
+	fn test_classify_warm() {
+		// Decremented first answer: already cached
+		assert_eq!(classify_warm(Some(250), Some(249), 300), WarmClass::Cached);
+		// Full TTL, then decremented: fetched fresh by the probe
+		assert_eq!(classify_warm(Some(300), Some(299), 300), WarmClass::Miss);
+		// TTLs that never decrement cannot be classified
+		assert_eq!(classify_warm(Some(300), Some(300), 300), WarmClass::Undetermined);
+		assert_eq!(classify_warm(None, Some(250), 300), WarmClass::Undetermined);
+	}
+
+	#[test]
+	fn test_reserve_send_slot_paces_and_does_not_bank_idle_time() {
+		let t0 = Instant::now();
+		let interval = Duration::from_millis(100);
+		let mut next = t0;
+		assert_eq!(reserve_send_slot(&mut next, t0, interval), t0);
+		assert_eq!(reserve_send_slot(&mut next, t0, interval), t0 + interval);
+		// After a long idle gap the slot is "now", not a backlog of old slots
+		let later = t0 + Duration::from_secs(5);
+		assert_eq!(reserve_send_slot(&mut next, later, interval), later);
+		assert_eq!(next, later + interval);
+	}
+
+	/// Local responder that echoes each query back as an empty NOERROR answer.
+	async fn spawn_echo_resolver() -> SocketAddr {
+		let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let addr = socket.local_addr().unwrap();
+		tokio::spawn(async move {
+			let mut buf = vec![0u8; 512];
+			while let Ok((len, src)) = socket.recv_from(&mut buf).await {
+				// Set QR (response) and keep RCODE 0
+				buf[2] |= 0x80;
+				let _ = socket.send_to(&buf[..len], src).await;
+			}
+		});
+		addr
+	}
+
+	// One test owns the process-wide fault config so cases cannot interleave
+	#[tokio::test]
+	async fn test_udp_query_under_injected_faults() {
+		use crate::fault::{set_faults, FaultConfig};
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		let addr = spawn_echo_resolver().await;
+		let timeout = Duration::from_millis(300);
+		let query = |retries| async move {
+			let txid: u16 = rand::random();
+			let bytes = build_query("example.com", QueryType::A, txid, false).unwrap();
+			send_udp_query(addr, &bytes, timeout, retries, Duration::ZERO, txid, "example.com", QueryType::A).await
+		};
+
+		set_faults(FaultConfig::NONE);
+		let clean = query(0).await;
+		assert_eq!(clean.outcome, QueryOutcome::Ok);
+
+		// Every response dropped: timeout after all attempts
+		set_faults(FaultConfig { drop_rate: 1.0, ..FaultConfig::NONE });
+		let dropped = query(1).await;
+		assert_eq!(dropped.outcome, QueryOutcome::Timeout);
+		assert_eq!(dropped.attempts, 2);
+
+		// Delayed responses still succeed, with the delay in the latency
+		set_faults(FaultConfig { delay_rate: 1.0, delay: Duration::from_millis(60), ..FaultConfig::NONE });
+		let delayed = query(0).await;
+		assert!(delayed.outcome.is_ok());
+		assert!(delayed.latency >= Duration::from_millis(60));
+
+		set_faults(FaultConfig::NONE);
+	}
+
+	#[test]
+	fn test_adaptive_timeout() {
+		let configured = Duration::from_millis(2000);
+		// 19 answers are too few to trust a p99
+		assert_eq!(adaptive_timeout(&[10.0; 19], configured), None);
+		let mut latencies = vec![20.0; 99];
+		latencies.push(60.0);
+		// p99 interpolates between 20 and 60: 20.4 ms, times 3, raised to the floor
+		assert_eq!(adaptive_timeout(&latencies, configured), Some(Duration::from_millis(100)));
+		latencies.extend([200.0; 5]);
+		assert_eq!(adaptive_timeout(&latencies, configured), Some(Duration::from_millis(600)));
+		// Never above the configured timeout
+		assert_eq!(adaptive_timeout(&[900.0; 30], configured), Some(configured));
+	}
+
+	#[tokio::test]
+	async fn test_resolver_pipeline_keeps_order_and_window() {
+		use crate::fault::{set_faults, FaultConfig};
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		let addr = spawn_echo_resolver().await;
+		// Every answer 50 ms late; a window of one makes the queries serial
+		set_faults(FaultConfig { delay_rate: 1.0, delay: Duration::from_millis(50), ..FaultConfig::NONE });
+		let queue: Vec<QueryTask> = ["a.example", "b.example", "c.example", "d.example"].iter()
+			.map(|domain| QueryTask {
+				resolver_addr: addr,
+				resolver_transport: DnsTransport::Udp,
+				domain: domain.to_string(),
+				query_type: QueryType::A,
+				set_name: "cached".to_string(),
+				round: 0,
+				seed: 0,
+			})
+			.collect();
+		let ctx = Arc::new(QueryContext {
+			semaphore: Arc::new(Semaphore::new(16)),
+			retries: 0,
+			late_grace: Duration::ZERO,
+			spacing: Duration::ZERO,
+			dnssec: false,
+			cold_zone: None,
+			connections: ConnectionPool::default(),
+			udp_pool: None,
+			progress: Arc::new(AtomicUsize::new(0)),
+		});
+		let start = Instant::now();
+		let (outcomes, abandoned) = run_resolver_pipeline(queue, 2, Duration::from_millis(500), Arc::new(ResolverLimiter::new(1, None)), ctx.clone()).await;
+		assert!(!abandoned);
+		set_faults(FaultConfig::NONE);
+		assert!(start.elapsed() >= Duration::from_millis(200));
+		let domains: Vec<&str> = outcomes.iter().map(|(t, _, _, _)| t.domain.as_str()).collect();
+		assert_eq!(domains, ["a.example", "b.example", "c.example", "d.example"]);
+		assert!(outcomes.iter().all(|(t, _, _, r)| t.round == 2 && r.outcome.is_ok()));
+		assert_eq!(ctx.progress.load(Ordering::Relaxed), 4);
+	}
+
+	#[tokio::test]
+	async fn test_resolver_pipeline_abandons_dead_resolver() {
+		use crate::fault::{set_faults, FaultConfig};
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		let addr = spawn_echo_resolver().await;
+		set_faults(FaultConfig { drop_rate: 1.0, ..FaultConfig::NONE });
+		let queue: Vec<QueryTask> = (0..40)
+			.map(|i| QueryTask {
+				resolver_addr: addr,
+				resolver_transport: DnsTransport::Udp,
+				domain: format!("host{}.example", i),
+				query_type: QueryType::A,
+				set_name: "cached".to_string(),
+				round: 0,
+				seed: i,
+			})
+			.collect();
+		let ctx = Arc::new(QueryContext {
+			semaphore: Arc::new(Semaphore::new(16)),
+			retries: 0,
+			late_grace: Duration::ZERO,
+			spacing: Duration::ZERO,
+			dnssec: false,
+			cold_zone: None,
+			connections: ConnectionPool::default(),
+			udp_pool: None,
+			progress: Arc::new(AtomicUsize::new(0)),
+		});
+		let limiter = Arc::new(ResolverLimiter::new(crate::transport::DEFAULT_PER_RESOLVER_INFLIGHT, None));
+		let (outcomes, abandoned) = run_resolver_pipeline(queue, 0, Duration::from_millis(50), limiter, ctx.clone()).await;
+		set_faults(FaultConfig::NONE);
+		assert!(abandoned);
+		// The first window timed out; at most one more window went out before the check
+		assert!(outcomes.len() < 2 * crate::transport::DEFAULT_PER_RESOLVER_INFLIGHT + 1);
+		assert!(outcomes.iter().all(|(_, _, _, r)| r.outcome.no_answer()));
+		// Unsent queries still complete the progress count
+		assert_eq!(ctx.progress.load(Ordering::Relaxed), 40);
+	}
+
+	#[tokio::test]
+	async fn test_closed_port_is_unreachable_not_timeout() {
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		// A port nothing listens on: loopback answers with ICMP port unreachable
+		let addr = UdpSocket::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+		let timeout = Duration::from_secs(2);
+		let txid: u16 = rand::random();
+		let bytes = build_query("example.com", QueryType::A, txid, false).unwrap();
+		let result = send_udp_query(addr, &bytes, timeout, 2, Duration::ZERO, txid, "example.com", QueryType::A).await;
+		assert!(result.outcome.is_unreachable(), "{}", result.outcome);
+		assert!(result.latency < timeout);
+		assert_eq!(result.attempts, 1);
+
+		let queue: Vec<QueryTask> = (0..40)
+			.map(|i| QueryTask {
+				resolver_addr: addr,
+				resolver_transport: DnsTransport::Udp,
+				domain: format!("host{}.example", i),
+				query_type: QueryType::A,
+				set_name: "cached".to_string(),
+				round: 0,
+				seed: i,
+			})
+			.collect();
+		let ctx = Arc::new(QueryContext {
+			semaphore: Arc::new(Semaphore::new(16)),
+			retries: 0,
+			late_grace: Duration::ZERO,
+			spacing: Duration::ZERO,
+			dnssec: false,
+			cold_zone: None,
+			connections: ConnectionPool::default(),
+			udp_pool: None,
+			progress: Arc::new(AtomicUsize::new(0)),
+		});
+		let limiter = Arc::new(ResolverLimiter::new(1, None));
+		let (outcomes, abandoned) = run_resolver_pipeline(queue, 0, timeout, limiter, ctx).await;
+		// One in flight at a time: the first refusal stops the queue
+		assert!(abandoned);
+		assert_eq!(outcomes.len(), 1);
+	}
+
+	/// Small single-round UDP config for tests that drive the pipeline directly.
+	fn test_config() -> BenchmarkConfig {
+		BenchmarkConfig {
+			rounds: 1,
+			timeout: Duration::from_millis(500),
+			retries: 0,
+			late_grace: Duration::ZERO,
+			max_inflight: 16,
+			per_resolver_inflight: 1,
+			per_resolver_qps: None,
+			inter_query_spacing: Duration::ZERO,
+			query_aaaa: false,
+			query_https: false,
+			connection_mode: crate::cli::ConnectionMode::Reuse,
+			doh_http: crate::cli::DohHttpVersion::Http2,
+			proxy: None,
+			seed: Some(1),
+			cold_zone: None,
+			adaptive_timeout: false,
+			dnssec: false,
+			checkpoint: None,
+			resume: false,
+			checkpoint_fingerprint: String::new(),
+			discover: false,
+			level: crate::cli::BenchLevel::Quick,
+			max_resolver_ms: 1000.0,
+			sort_mode: crate::stats::SortMode::Score,
+			score_weights: crate::stats::ScoreWeights::DEFAULT,
+			profile: None,
+			identity_rounds: false,
+			telemetry: crate::telemetry::TelemetryLog::new(false),
+			udp_pool: None,
+			raw_log: crate::telemetry::RawQueryLog::disabled(),
+		}
+	}
+
+	#[test]
+	fn test_cut_discovery_wave_tightens_and_pins_system() {
+		let config = test_config();
+		let mut records: Vec<crate::record::ResolverRecord> = (0..8u16)
+			.map(|i| {
+				let addr = SocketAddr::from(([192, 0, 2, 1], 5300 + i));
+				let mut rec = crate::record::ResolverRecord::new(Resolver::new(addr, DnsTransport::Udp));
+				rec.discovery = Some(crate::record::DiscoveryResult {
+					passed: true,
+					latency_ms: None,
+					reason: "reachable".to_string(),
+				});
+				rec
+			})
+			.collect();
+		// Slowest of the answering resolvers, but a system resolver
+		records[6].resolver.is_system = true;
+		let latencies: HashMap<usize, Option<f64>> = HashMap::from([
+			(0, Some(40.0)), (1, Some(10.0)), (2, Some(30.0)), (3, Some(f64::NAN)),
+			(4, Some(20.0)), (5, None), (6, Some(90.0)), (7, Some(50.0)),
+		]);
+		let mut survivors: Vec<usize> = (0..8).collect();
+		let top_n = 1;
+
+		// Wave 1: 4x top_n fastest finite latencies plus the pinned system resolver
+		cut_discovery_wave(&mut records, &mut survivors, &latencies, 4 * top_n, 1, &config);
+		assert_eq!(survivors, vec![1, 4, 2, 0, 6]);
+		assert_eq!(records[1].discovery.as_ref().unwrap().latency_ms, Some(10.0));
+		for i in [3, 5, 7] {
+			let d = records[i].discovery.as_ref().unwrap();
+			assert!(!d.passed);
+			assert_eq!(d.reason, "wave1_cut");
+		}
+		// Non-finite latencies never survive and are not reported as numbers
+		assert_eq!(records[3].discovery.as_ref().unwrap().latency_ms, None);
+		assert_eq!(records[5].discovery.as_ref().unwrap().latency_ms, None);
+		assert_eq!(records[7].discovery.as_ref().unwrap().latency_ms, Some(50.0));
+
+		// Wave 2: 2x top_n, the system resolver still kept
+		cut_discovery_wave(&mut records, &mut survivors, &latencies, 2 * top_n, 2, &config);
+		assert_eq!(survivors, vec![1, 4, 6]);
+		for i in [0, 2] {
+			let d = records[i].discovery.as_ref().unwrap();
+			assert_eq!((d.passed, d.reason.as_str()), (false, "wave2_cut"));
+		}
+		assert!(records[6].discovery.as_ref().unwrap().passed);
+	}
+
+	#[tokio::test]
+	async fn test_same_ip_different_ports_kept_apart() {
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		let live = spawn_echo_resolver().await;
+		let closed = UdpSocket::bind("127.0.0.1:0").await.unwrap().local_addr().unwrap();
+		let mut records: Vec<crate::record::ResolverRecord> = [live, closed].into_iter()
+			.map(|addr| crate::record::ResolverRecord::new(Resolver::new(addr, DnsTransport::Udp)))
+			.collect();
+		let categories = std::collections::BTreeMap::from([
+			("cached".to_string(), vec!["example.com".to_string(), "example.org".to_string()]),
+		]);
+		let config = test_config();
+		run_benchmark(&mut records, &categories, &config, &ConnectionPool::default()).await.unwrap();
+		// Both on 127.0.0.1: each keeps its own stats instead of merging
+		let live_bm = records[0].benchmark.as_ref().unwrap();
+		let closed_bm = records[1].benchmark.as_ref().unwrap();
+		assert_eq!((live_bm.success_rate, live_bm.unreachable), (100.0, false));
+		assert_eq!((closed_bm.success_rate, closed_bm.unreachable), (0.0, true));
+	}
+
+	#[tokio::test]
+	async fn test_resume_restores_saved_resolver_set() {
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		crate::fault::set_faults(crate::fault::FaultConfig::NONE);
+		let input: Vec<Resolver> = vec![
+			Resolver::new(spawn_echo_resolver().await, DnsTransport::Udp),
+			Resolver::new(spawn_echo_resolver().await, DnsTransport::Udp),
+		];
+		let categories = std::collections::BTreeMap::from([
+			("cached".to_string(), vec!["example.com".to_string(), "example.org".to_string()]),
+		]);
+		let path = std::env::temp_dir().join(format!("dns_benchmark_resume_{}.csv", std::process::id()));
+		let mut config = BenchmarkConfig {
+			checkpoint: Some(path.to_str().unwrap().to_string()),
+			..test_config()
+		};
+		config.checkpoint_fingerprint = crate::checkpoint::fingerprint(&input, &categories, &config);
+
+		// First run: discovery kept only the first resolver
+		let mut records = vec![crate::record::ResolverRecord::new(input[0].clone())];
+		run_benchmark(&mut records, &categories, &config, &ConnectionPool::default()).await.unwrap();
+
+		// Second run: discovery would now keep the second one, but the
+		// input list fingerprint still matches and the saved set is restored
+		let resumed = crate::checkpoint::Checkpoint::read_for_resume(
+			config.checkpoint.as_deref().unwrap(), &config.checkpoint_fingerprint,
+		).unwrap().unwrap();
+		assert_eq!(resumed.resolvers, vec![input[0].key()]);
+		let mut records: Vec<crate::record::ResolverRecord> = input.iter()
+			.filter(|r| resumed.resolvers.contains(&r.key()))
+			.map(|r| crate::record::ResolverRecord::new(r.clone()))
+			.collect();
+		let config = BenchmarkConfig { rounds: 2, resume: true, ..config };
+		run_benchmark(&mut records, &categories, &config, &ConnectionPool::default()).await.unwrap();
+		let stats = &records[0].benchmark.as_ref().unwrap().categories["cached"];
+		assert_eq!((stats.success_count, stats.total_count), (4, 4));
+
+		// A different input list is still refused
+		let other = crate::checkpoint::fingerprint(&input[1..], &categories, &config);
+		assert!(crate::checkpoint::Checkpoint::read_for_resume(config.checkpoint.as_deref().unwrap(), &other).is_err());
+		std::fs::remove_file(&path).unwrap();
+	}
+
+	#[tokio::test]
+	async fn test_nxdomain_set_aggregation() {
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		crate::fault::set_faults(crate::fault::FaultConfig::NONE);
+		// Answers nx.example names with NXDOMAIN, slowly the first time a
+		// name is asked and at once after that, as a negative cache would
+		let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let addr = socket.local_addr().unwrap();
+		tokio::spawn(async move {
+			let mut seen = std::collections::HashSet::new();
+			let mut buf = vec![0u8; 512];
+			while let Ok((len, src)) = socket.recv_from(&mut buf).await {
+				let qname = buf[12..len].to_vec();
+				buf[2] |= 0x80;
+				if qname.windows(2).any(|w| w == b"nx") {
+					buf[3] = (buf[3] & 0xf0) | 3;
+					if seen.insert(qname) {
+						tokio::time::sleep(Duration::from_millis(40)).await;
+					}
+				}
+				let _ = socket.send_to(&buf[..len], src).await;
+			}
+		});
+		let mut records = vec![crate::record::ResolverRecord::new(Resolver::new(addr, DnsTransport::Udp))];
+		let categories = std::collections::BTreeMap::from([
+			("cached".to_string(), vec!["example.com".to_string(), "example.org".to_string()]),
+			(crate::domains::NXDOMAIN_SET.to_string(), vec!["a.nx.example".to_string(), "b.nx.example".to_string()]),
+		]);
+		let config = BenchmarkConfig { rounds: 2, ..test_config() };
+		run_benchmark(&mut records, &categories, &config, &ConnectionPool::default()).await.unwrap();
+		let bm = records[0].benchmark.as_ref().unwrap();
+
+		// NXDOMAIN is the expected answer in its own set
+		let nx = &bm.categories[crate::domains::NXDOMAIN_SET];
+		assert_eq!((nx.success_count, nx.total_count), (4, 4));
+		// The set stays out of the score, the success rate, and the query type stats
+		let mut scored = bm.categories.clone();
+		scored.remove(crate::domains::NXDOMAIN_SET);
+		let timeout_penalty_ms = config.timeout.as_millis() as f64;
+		let expected = crate::stats::rescore(&scored, &bm.query_types, timeout_penalty_ms, &config.score_weights);
+		assert_eq!(bm.overall_score, expected);
+		assert_eq!(bm.success_rate, 100.0);
+		assert_eq!(bm.query_types["A"].total_count, 4);
+		// First-round p50 (uncached, 40 ms) minus repeat-round p50 (cached)
+		let speedup = bm.negative_cache_speedup_ms.unwrap();
+		assert!((30.0..60.0).contains(&speedup), "speedup {}", speedup);
+	}
+
+	#[tokio::test]
+	async fn test_query_seed_fixes_cold_name() {
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		let addr = spawn_echo_resolver().await;
+		let ctx = QueryContext {
+			semaphore: Arc::new(Semaphore::new(1)),
+			retries: 0,
+			late_grace: Duration::ZERO,
+			spacing: Duration::ZERO,
+			dnssec: false,
+			cold_zone: Some("cold.example.com".to_string()),
+			connections: ConnectionPool::default(),
+			udp_pool: None,
+			progress: Arc::new(AtomicUsize::new(0)),
+		};
+		let task = |seed: u64| QueryTask {
+			resolver_addr: addr,
+			resolver_transport: DnsTransport::Udp,
+			domain: "placeholder.example".to_string(),
+			query_type: QueryType::A,
+			set_name: crate::domains::COLD_SET.to_string(),
+			round: 0,
+			seed,
+		};
+		let timeout = Duration::from_millis(500);
+		let (first, _, _, _) = run_query(task(42), timeout, &ctx).await;
+		let (again, _, _, _) = run_query(task(42), timeout, &ctx).await;
+		let (other, _, _, _) = run_query(task(43), timeout, &ctx).await;
+		assert!(first.domain.ends_with(".cold.example.com"));
+		assert_eq!(first.domain, again.domain);
+		assert_ne!(first.domain, other.domain);
+	}
+
+	#[tokio::test]
+	async fn test_udp_query_records_late_arrival() {
+		use crate::fault::{set_faults, FaultConfig};
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		set_faults(FaultConfig::NONE);
+		// Responder that answers 150 ms after each query
+		let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let addr = socket.local_addr().unwrap();
+		tokio::spawn(async move {
+			let mut buf = vec![0u8; 512];
+			while let Ok((len, src)) = socket.recv_from(&mut buf).await {
+				buf[2] |= 0x80;
+				tokio::time::sleep(Duration::from_millis(150)).await;
+				let _ = socket.send_to(&buf[..len], src).await;
+			}
+		});
+		let timeout = Duration::from_millis(50);
+		let query = |grace| async move {
+			let txid: u16 = rand::random();
+			let bytes = build_query("example.com", QueryType::A, txid, false).unwrap();
+			send_udp_query(addr, &bytes, timeout, 0, grace, txid, "example.com", QueryType::A).await
+		};
+
+		let without = query(Duration::ZERO).await;
+		assert!(without.outcome.no_answer() && without.late_latency.is_none());
+
+		let with = query(Duration::from_millis(1000)).await;
+		assert_eq!(with.outcome, QueryOutcome::Timeout);
+		let late = with.late_latency.expect("late answer recorded");
+		assert!(late >= Duration::from_millis(150) && late < Duration::from_millis(1050));
+	}
+	#[test]
+	fn test_tls_probe_config_per_transport() {
+		// main() installs the provider; both ring and aws-lc-rs are linked
+		let _ = rustls::crypto::ring::default_provider().install_default();
+		assert!(tls_probe_config(&DnsTransport::Udp).is_none());
+		let dot = tls_probe_config(&DnsTransport::Dot { hostname: "dns.quad9.net".to_string() }).unwrap();
+		assert_eq!(dot.alpn_protocols, vec![b"dot".to_vec()]);
+		assert!(dot.enable_early_data);
+		let doh = tls_probe_config(&DnsTransport::Doh {
+			url: "https://1.1.1.1/dns-query".to_string(),
+			options: Default::default(),
+		}).unwrap();
+		assert_eq!(doh.alpn_protocols[0], b"h2".to_vec());
+		assert_eq!(tls_version_text(Some(rustls::ProtocolVersion::TLSv1_3)), "TLSv1.3");
+		assert_eq!(tls_version_text(None), "unknown");
+	}
+
+	#[test]
+	fn test_connection_pool_modes() {
+		let _ = rustls::crypto::ring::default_provider().install_default();
+		let url = "https://1.1.1.1/dns-query".to_string();
+		let resolvers = [
+			Resolver::new("1.1.1.1:443".parse().unwrap(), DnsTransport::Doh { url: url.clone(), options: Default::default() }),
+			Resolver::new("9.9.9.9:853".parse().unwrap(), DnsTransport::Dot { hostname: "dns.quad9.net".to_string() }),
+		];
+		let reuse = build_connection_pool(&resolvers, &ConnectionOptions::default());
+		let addr = resolvers[0].addr;
+		assert!(reuse.doh_client(&url, addr).is_some() && reuse.doh_client("https://unknown/dns-query", addr).is_none());
+		// The same URL at another address is another resolver
+		assert!(reuse.doh_client(&url, "1.0.0.1:443".parse().unwrap()).is_none());
+		assert!(reuse.dot_connector.is_some());
+		// Per-query mode keeps no DoT connector to resume sessions with
+		let per_query = build_connection_pool(&resolvers, &ConnectionOptions { mode: ConnectionMode::PerQuery, ..Default::default() });
+		assert!(per_query.doh_client(&url, addr).is_some());
+		assert!(per_query.dot_connector.is_none());
+		assert!(per_query.take_dot(resolvers[1].addr).is_none());
+	}
+
+	#[tokio::test]
+	async fn test_doh_http_probe_forces_version() {
+		use tokio::net::TcpListener;
+		// Plain-HTTP/1.1 DoH server that echoes each query back as a response
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let addr = listener.local_addr().unwrap();
+		// A name nothing resolves: the query only lands if the client is pinned to addr
+		let url = format!("http://doh.invalid:{}/dns-query", addr.port());
+		tokio::spawn(async move {
+			while let Ok((mut stream, _)) = listener.accept().await {
+				tokio::spawn(async move {
+					let mut buf = Vec::new();
+					let mut chunk = [0u8; 1024];
+					loop {
+						let Some(end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else {
+							match stream.read(&mut chunk).await {
+								Ok(0) | Err(_) => return,
+								Ok(n) => { buf.extend_from_slice(&chunk[..n]); continue }
+							}
+						};
+						let head = String::from_utf8_lossy(&buf[..end]).to_ascii_lowercase();
+						// An HTTP/2 connection preface is not HTTP/1.1
+						if head.starts_with("pri ") {
+							return;
+						}
+						let length: usize = head.lines()
+							.find_map(|l| l.strip_prefix("content-length:"))
+							.and_then(|v| v.trim().parse().ok())
+							.unwrap_or(0);
+						while buf.len() < end + 4 + length {
+							match stream.read(&mut chunk).await {
+								Ok(0) | Err(_) => return,
+								Ok(n) => buf.extend_from_slice(&chunk[..n]),
+							}
+						}
+						let mut body: Vec<u8> = buf.drain(..end + 4 + length).skip(end + 4).collect();
+						body[2] |= 0x80;
+						let reply = format!("HTTP/1.1 200 OK\r\ncontent-type: application/dns-message\r\ncontent-length: {}\r\n\r\n", body.len());
+						if stream.write_all(&[reply.into_bytes(), body].concat()).await.is_err() {
+							return;
+						}
+					}
+				});
+			}
+		});
+
+		let options = DohOptions::default();
+		let timeout = Duration::from_millis(1000);
+		let http1 = probe_doh_http(&url, addr, &options, DohHttpVersion::Http1, timeout, None).await;
+		assert_eq!(http1.answered, DOH_HTTP_PROBE_QUERIES);
+		assert!(http1.p50_ms.is_some());
+		// Forcing HTTP/2 against an HTTP/1.1-only server gets no answers
+		let http2 = probe_doh_http(&url, addr, &options, DohHttpVersion::Http2, timeout, None).await;
+		assert_eq!((http2.answered, http2.p50_ms), (0, None));
+	}
 }
@@ -0,0 +1,216 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use base64::Engine;
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::WebPkiServerVerifier;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, SignatureScheme};
+use tokio::sync::Semaphore;
+use tokio_rustls::TlsConnector;
+
+use crate::bench::{spawn_progress_monitor, stop_progress_monitor, tls_alpn, tls_server_name};
+use crate::proxy::{connect_via, ProxyConfig};
+use crate::record::{CertPinResult, ResolverRecord};
+use crate::transport::{DnsTransport, Resolver};
+
+//============================================
+/// Server certificate verifier that records the leaf certificate and the
+/// web PKI verdict on it, then accepts the certificate either way, so a
+/// failed name check still yields an SPKI to report. Handshake signatures
+/// are still checked, so the server must hold the certificate's key.
+#[derive(Debug)]
+struct RecordingVerifier {
+	inner: Arc<WebPkiServerVerifier>,
+	seen: Mutex<Option<(CertificateDer<'static>, Option<String>)>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+	fn verify_server_cert(
+		&self,
+		end_entity: &CertificateDer<'_>,
+		intermediates: &[CertificateDer<'_>],
+		server_name: &ServerName<'_>,
+		ocsp_response: &[u8],
+		now: UnixTime,
+	) -> Result<ServerCertVerified, rustls::Error> {
+		let error = self.inner
+			.verify_server_cert(end_entity, intermediates, server_name, ocsp_response, now)
+			.err()
+			.map(|e| e.to_string());
+		*self.seen.lock().unwrap() = Some((end_entity.clone().into_owned(), error));
+		Ok(ServerCertVerified::assertion())
+	}
+
+	fn verify_tls12_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		self.inner.verify_tls12_signature(message, cert, dss)
+	}
+
+	fn verify_tls13_signature(
+		&self,
+		message: &[u8],
+		cert: &CertificateDer<'_>,
+		dss: &DigitallySignedStruct,
+	) -> Result<HandshakeSignatureValid, rustls::Error> {
+		self.inner.verify_tls13_signature(message, cert, dss)
+	}
+
+	fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+		self.inner.supported_verify_schemes()
+	}
+}
+
+//============================================
+/// SHA-256 of a certificate's SubjectPublicKeyInfo, base64: the form
+/// `spki=` tags take. None when the certificate does not parse.
+pub fn spki_pin(cert: &CertificateDer<'_>) -> Option<String> {
+	let parsed = webpki::EndEntityCert::try_from(cert).ok()?;
+	let digest = ring::digest::digest(&ring::digest::SHA256, parsed.subject_public_key_info().as_ref());
+	Some(base64::engine::general_purpose::STANDARD.encode(digest.as_ref()))
+}
+
+/// Compare a presented certificate's SPKI pin and web PKI verdict against
+/// the resolver's `spki=` and `tls_name=` tags.
+fn compare_pins(resolver: &Resolver, spki: String, verify_error: Option<String>) -> CertPinResult {
+	let spki_match = (!resolver.spki_pins.is_empty()).then(|| resolver.spki_pins.contains(&spki));
+	let (name_match, name_error) = match resolver.tls_name {
+		Some(_) => (Some(verify_error.is_none()), verify_error),
+		None => (None, None),
+	};
+	CertPinResult { spki, spki_match, name_match, name_error }
+}
+
+/// Complete one TLS handshake with a DoT or DoH resolver and check the
+/// certificate it presents. SNI is the `tls_name=` name when given, else
+/// the name the benchmark uses. None when the connection or handshake fails.
+async fn check_cert_pins(
+	resolver: &Resolver,
+	timeout: Duration,
+	proxy: Option<&ProxyConfig>,
+) -> Option<CertPinResult> {
+	let server_name = match &resolver.tls_name {
+		Some(name) => ServerName::try_from(name.clone()).ok()?,
+		None => tls_server_name(resolver)?,
+	};
+	let root_store = rustls::RootCertStore::from_iter(
+		webpki_roots::TLS_SERVER_ROOTS.iter().cloned()
+	);
+	let inner = WebPkiServerVerifier::builder(Arc::new(root_store)).build().ok()?;
+	let verifier = Arc::new(RecordingVerifier { inner, seen: Mutex::new(None) });
+	let mut tls_config = ClientConfig::builder()
+		.dangerous()
+		.with_custom_certificate_verifier(verifier.clone())
+		.with_no_client_auth();
+	tls_config.alpn_protocols = tls_alpn(&resolver.transport)?;
+
+	let start = Instant::now();
+	let tcp_stream = tokio::time::timeout(timeout, connect_via(proxy, resolver.addr)).await.ok()?.ok()?;
+	let remaining = timeout.saturating_sub(start.elapsed());
+	let connector = TlsConnector::from(Arc::new(tls_config));
+	// Only a completed handshake proves the server holds the certificate's key
+	tokio::time::timeout(remaining, connector.connect(server_name, tcp_stream)).await.ok()?.ok()?;
+
+	let (cert, verify_error) = verifier.seen.lock().unwrap().take()?;
+	Some(compare_pins(resolver, spki_pin(&cert)?, verify_error))
+}
+
+/// Check the certificate of each DoT and DoH resolver that carries `spki=`
+/// or `tls_name=` tags in the resolver file.
+///
+/// A pin mismatch means something other than the expected server finished
+/// the handshake: a middlebox or captive portal intercepting encrypted DNS,
+/// or a provider key rotation the resolver file has not caught up with.
+/// Writes CertPinResult onto each checked record; a failed connection
+/// leaves it None.
+pub async fn run_cert_pin_check(records: &mut [ResolverRecord], config: &crate::transport::BenchmarkConfig) {
+	let targets: Vec<usize> = records.iter().enumerate()
+		.filter(|(_, r)| r.resolver.transport != DnsTransport::Udp
+			&& (!r.resolver.spki_pins.is_empty() || r.resolver.tls_name.is_some()))
+		.map(|(i, _)| i)
+		.collect();
+	if targets.is_empty() {
+		return;
+	}
+	crate::info!("Certificate pin check ({} DoT/DoH resolvers)...", targets.len());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let total = targets.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Certificate pin check".to_string(), done.clone(), total, start);
+
+	let mut handles = Vec::new();
+	for &idx in &targets {
+		let resolver = records[idx].resolver.clone();
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let proxy = config.proxy.clone();
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let result = check_cert_pins(&resolver, tm, proxy.as_ref()).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(idx, result)
+		}));
+	}
+	for handle in handles {
+		match handle.await {
+			Ok((idx, result)) => records[idx].cert_pin = result,
+			Err(e) => crate::warn!("certificate pin check task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Certificate pin check", total, start);
+	for &idx in &targets {
+		let r = &records[idx];
+		if r.cert_pin.as_ref().is_some_and(|c| !c.matches()) {
+			crate::warn!(
+				"{} ({}): presented certificate does not match its pins; possible interception of encrypted DNS",
+				r.resolver.label, r.resolver.key(),
+			);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn dot_resolver(pins: &[&str], tls_name: Option<&str>) -> Resolver {
+		let transport = DnsTransport::Dot { hostname: "dns.example.net".to_string() };
+		let mut r = Resolver::new("192.0.2.1:853".parse().unwrap(), transport);
+		r.spki_pins = pins.iter().map(|p| p.to_string()).collect();
+		r.tls_name = tls_name.map(String::from);
+		r
+	}
+
+	#[test]
+	fn test_compare_pins() {
+		let pin = "jQJTbIh0grw0/1TkHSumWb+Fs0Ggogr621gT3PvPKG0=";
+		let other = "AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA=";
+		let c = compare_pins(&dot_resolver(&[other, pin], None), pin.to_string(), Some("bad".to_string()));
+		assert_eq!(c.spki_match, Some(true));
+		// No tls_name tag: the web PKI verdict is not a pin and is not reported
+		assert_eq!((c.name_match, c.name_error.as_deref()), (None, None));
+		assert!(c.matches());
+
+		let c = compare_pins(&dot_resolver(&[other], Some("dns.example.net")), pin.to_string(), None);
+		assert_eq!((c.spki_match, c.name_match), (Some(false), Some(true)));
+		assert!(!c.matches());
+
+		let error = "invalid peer certificate: NotValidForName".to_string();
+		let c = compare_pins(&dot_resolver(&[], Some("dns.example.net")), pin.to_string(), Some(error.clone()));
+		assert_eq!((c.spki_match, c.name_match), (None, Some(false)));
+		assert_eq!(c.name_error, Some(error));
+		assert!(!c.matches());
+	}
+
+	#[test]
+	fn test_spki_pin_rejects_garbage() {
+		assert_eq!(spki_pin(&CertificateDer::from(vec![0x30, 0x03, 0x02, 0x01, 0x01])), None);
+	}
+}
@@ -0,0 +1,162 @@
+/// Plot width in characters
+pub const CHART_WIDTH: usize = 60;
+/// Rows per ECDF curve; each row holds eight block levels
+pub const ECDF_HEIGHT: usize = 4;
+/// Vertical eighth blocks, empty to full
+const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+/// Smallest latency the axis shows (ms); loopback answers go below it
+const MIN_AXIS_MS: f64 = 0.01;
+
+//============================================
+/// Log-scale mapping from latency (ms) to a column, shared by every
+/// resolver in a chart so their shapes line up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogScale {
+	lo: f64,
+	hi: f64,
+	width: usize,
+}
+
+impl LogScale {
+	/// Scale spanning every sample; None when there are no samples.
+	pub fn fit(samples: &[Vec<f64>], width: usize) -> Option<LogScale> {
+		let all = samples.iter().flatten().copied().filter(|v| v.is_finite());
+		let (lo, hi) = all.fold((f64::INFINITY, 0.0f64), |(lo, hi), v| (lo.min(v), hi.max(v)));
+		if hi <= 0.0 || width < 2 {
+			return None;
+		}
+		let lo = lo.max(MIN_AXIS_MS);
+		// A single value still gets a readable span around it
+		let (lo, hi) = if hi < lo * 1.5 { (lo / 1.5, hi * 1.5) } else { (lo, hi) };
+		Some(LogScale { lo: lo.log10(), hi: hi.log10(), width })
+	}
+
+	/// Column (0..width) for a latency; values outside the range clamp to the edges.
+	pub fn column(&self, ms: f64) -> usize {
+		let x = (ms.max(MIN_AXIS_MS).log10() - self.lo) / (self.hi - self.lo);
+		((x * (self.width - 1) as f64).round().max(0.0) as usize).min(self.width - 1)
+	}
+
+	/// Latency at the right edge of a column.
+	fn upper_edge(&self, col: usize) -> f64 {
+		10f64.powf(self.lo + (col as f64 + 0.5) / (self.width - 1) as f64 * (self.hi - self.lo))
+	}
+
+	/// Axis line with tick marks, and the tick labels beneath it. Ticks
+	/// are at 1, 2, and 5 times powers of ten, skipping labels that would
+	/// overlap.
+	pub fn axis(&self) -> (String, String) {
+		let mut line: Vec<char> = vec!['─'; self.width];
+		let mut labels: Vec<char> = vec![' '; self.width];
+		let mut next_free = 0;
+		let (lo_ms, hi_ms) = (10f64.powf(self.lo), 10f64.powf(self.hi));
+		for decade in (lo_ms.log10().floor() as i32)..=(hi_ms.log10().ceil() as i32) {
+			for step in [1.0, 2.0, 5.0] {
+				let tick = step * 10f64.powi(decade);
+				if tick < lo_ms || tick > hi_ms {
+					continue;
+				}
+				let col = self.column(tick);
+				let text = format_tick(tick);
+				if col < next_free {
+					continue;
+				}
+				line[col] = '┬';
+				labels.resize(labels.len().max(col + text.len()), ' ');
+				for (i, ch) in text.chars().enumerate() {
+					labels[col + i] = ch;
+				}
+				next_free = col + text.len() + 1;
+			}
+		}
+		(line.into_iter().collect(), labels.into_iter().collect::<String>().trim_end().to_string())
+	}
+}
+
+/// Tick label without needless decimals: 0.05, 0.5, 2, 500, 1k.
+fn format_tick(ms: f64) -> String {
+	match ms {
+		ms if ms >= 1000.0 => format!("{}k", ms / 1000.0),
+		ms if ms >= 1.0 => format!("{}", ms),
+		ms => format!("{}", (ms * 1000.0).round() / 1000.0),
+	}
+}
+
+//============================================
+/// One box-plot row: whiskers from p5 to p95, a box from p25 to p75, and
+/// the median as a full block. `sorted` must be sorted ascending.
+pub fn box_row(sorted: &[f64], scale: &LogScale) -> Option<String> {
+	let p = |q: f64| crate::stats::percentile(sorted, q).map(|v| scale.column(v));
+	let (p5, p25, p50, p75, p95) = (p(5.0)?, p(25.0)?, p(50.0)?, p(75.0)?, p(95.0)?);
+	let mut row = vec![' '; scale.width];
+	for cell in row.iter_mut().take(p95 + 1).skip(p5) {
+		*cell = '─';
+	}
+	row[p5] = '├';
+	row[p95] = '┤';
+	for cell in row.iter_mut().take(p75 + 1).skip(p25) {
+		*cell = '░';
+	}
+	row[p50] = '█';
+	Some(row.into_iter().collect())
+}
+
+/// ECDF curve as `height` rows, top first: each column is filled up to
+/// the fraction of samples at or below that column's latency.
+pub fn ecdf_rows(sorted: &[f64], scale: &LogScale, height: usize) -> Option<Vec<String>> {
+	if sorted.is_empty() || height == 0 {
+		return None;
+	}
+	let levels: Vec<usize> = (0..scale.width)
+		.map(|col| {
+			let edge = scale.upper_edge(col);
+			let below = sorted.partition_point(|&v| v <= edge);
+			(below * height * 8 + sorted.len() / 2) / sorted.len()
+		})
+		.collect();
+	let rows = (0..height).rev()
+		.map(|row| levels.iter().map(|&level| BLOCKS[level.saturating_sub(row * 8).min(8)]).collect())
+		.collect();
+	Some(rows)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_scale_and_axis() {
+		let scale = LogScale::fit(&[vec![1.0, 10.0], vec![100.0]], 21).unwrap();
+		assert_eq!((scale.column(1.0), scale.column(10.0), scale.column(100.0)), (0, 10, 20));
+		// Out-of-range values clamp to the edges
+		assert_eq!((scale.column(0.001), scale.column(5000.0)), (0, 20));
+		let (line, labels) = scale.axis();
+		assert_eq!(line.chars().count(), 21);
+		assert_eq!(line.chars().next(), Some('┬'));
+		assert!(labels.starts_with("1 ") && labels.ends_with("100"), "{}", labels);
+		assert!(LogScale::fit(&[vec![]], 20).is_none());
+		assert_eq!(format_tick(0.05), "0.05");
+		assert_eq!(format_tick(2000.0), "2k");
+	}
+
+	#[test]
+	fn test_box_and_ecdf_shapes() {
+		let scale = LogScale::fit(&[vec![1.0, 100.0]], 21).unwrap();
+		let sorted: Vec<f64> = (1..=100).map(|v| v as f64).collect();
+		let row: Vec<char> = box_row(&sorted, &scale).unwrap().chars().collect();
+		assert_eq!(row.len(), 21);
+		assert_eq!(row[scale.column(50.0)], '█');
+		assert_eq!(row[scale.column(5.0)], '├');
+		assert_eq!(row[scale.column(95.0)], '┤');
+		assert_eq!(row[0], ' ');
+
+		// Half the samples at 1 ms, half at 100 ms: the curve is half full until the end
+		let sorted = [1.0, 1.0, 100.0, 100.0];
+		let rows = ecdf_rows(&sorted, &scale, 2).unwrap();
+		assert_eq!(rows.len(), 2);
+		let (top, bottom): (Vec<char>, Vec<char>) = (rows[0].chars().collect(), rows[1].chars().collect());
+		assert_eq!((bottom[0], top[0]), ('█', ' '));
+		assert_eq!((bottom[20], top[20]), ('█', '█'));
+		assert!(ecdf_rows(&[], &scale, 2).is_none());
+	}
+}
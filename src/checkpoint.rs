@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Context, Result};
+
+use crate::manifest::{domain_list_text, fnv1a_hex, resolver_list_text};
+use crate::transport::{BenchmarkConfig, QueryOutcome, QueryResult, QueryType, Resolver};
+
+/// Columns of the query rows, one row per completed benchmark query
+const HEADER: [&str; 11] = [
+	"round", "resolver", "set", "domain", "qtype", "latency_ms",
+	"outcome", "ttl", "attempts", "late_ms", "duplicates",
+];
+
+/// One completed benchmark query
+#[derive(Debug, Clone)]
+pub struct SavedQuery {
+	/// Benchmark round (0-based)
+	pub round: u32,
+	pub set_name: String,
+	pub domain: String,
+	pub query_type: QueryType,
+	/// Scored result; `result.resolver` is the resolver key (`Resolver::key`)
+	pub result: QueryResult,
+}
+
+//============================================
+/// Benchmark rounds completed so far (--checkpoint), saved after every
+/// round so a long run can continue with --resume after a crash or Ctrl-C.
+///
+/// The file is CSV: `# key: value` comment lines for the run state, then
+/// a header and one row per query. Only whole rounds are saved; a round
+/// cut short is run again on resume.
+#[derive(Debug, Clone, Default)]
+pub struct Checkpoint {
+	/// Hash of the inputs that must match to resume (see `fingerprint`)
+	pub fingerprint: String,
+	/// Keys of the resolvers that reached the benchmark, restored on
+	/// resume instead of running discovery and qualification again
+	pub resolvers: Vec<String>,
+	/// Rounds completed; resuming starts at this round
+	pub rounds_done: u32,
+	/// Resolver keys sidelined so far
+	pub sidelined: Vec<String>,
+	/// Of those, the ones abandoned as unresponsive
+	pub unresponsive: Vec<String>,
+	/// Of those, the ones the network reported unreachable
+	pub unreachable: Vec<String>,
+	pub queries: Vec<SavedQuery>,
+}
+
+/// Hash (64-bit FNV-1a, hex) of what a resumed run must share with the
+/// saved one: resolvers, domain sets, and the settings that change what
+/// each query measures. The round count is left out so a finished run can
+/// be extended with a higher --rounds. Taken over the input resolver list,
+/// before discovery and qualification cut it down by measured latency.
+pub fn fingerprint(resolvers: &[Resolver], categories: &BTreeMap<String, Vec<String>>, config: &BenchmarkConfig) -> String {
+	let text = format!(
+		"{}\n{}\naaaa={} https={} connection={} doh_http={} proxy={} dnssec={} retries={} timeout_ms={} cold_zone={}",
+		resolver_list_text(resolvers),
+		domain_list_text(categories),
+		config.query_aaaa,
+		config.query_https,
+		config.connection_mode,
+		config.doh_http.as_str(),
+		config.proxy.as_ref().map(|p| p.display()).unwrap_or_default(),
+		config.dnssec,
+		config.retries,
+		config.timeout.as_millis(),
+		config.cold_zone.as_deref().unwrap_or_default(),
+	);
+	fnv1a_hex(&text)
+}
+
+/// Milliseconds with microsecond precision, as written to the file.
+fn format_ms(duration: Duration) -> String {
+	format!("{:.3}", duration.as_secs_f64() * 1000.0)
+}
+
+fn parse_ms(field: &str) -> Option<Duration> {
+	field.parse::<f64>().ok().filter(|ms| ms.is_finite() && *ms >= 0.0).map(|ms| Duration::from_secs_f64(ms / 1000.0))
+}
+
+//============================================
+impl Checkpoint {
+	/// Write to `path` through a temporary file, so a crash mid-write
+	/// leaves the previous checkpoint intact.
+	pub fn write(&self, path: &str) -> Result<()> {
+		let tmp = format!("{}.tmp", path);
+		let mut text = String::from("# rust-dns-benchmark checkpoint\n");
+		text += &format!("# fingerprint: {}\n", self.fingerprint);
+		text += &format!("# rounds_done: {}\n", self.rounds_done);
+		text += &format!("# resolvers: {}\n", self.resolvers.join(" "));
+		text += &format!("# sidelined: {}\n", self.sidelined.join(" "));
+		text += &format!("# unresponsive: {}\n", self.unresponsive.join(" "));
+		text += &format!("# unreachable: {}\n", self.unreachable.join(" "));
+		let mut writer = csv::Writer::from_writer(text.into_bytes());
+		writer.write_record(HEADER)?;
+		for q in &self.queries {
+			let r = &q.result;
+			writer.write_record([
+				q.round.to_string(),
+				r.resolver.clone(),
+				q.set_name.clone(),
+				q.domain.clone(),
+				format!("{:?}", q.query_type),
+				format_ms(r.latency),
+				r.outcome.to_string(),
+				r.ttl.map(|t| t.to_string()).unwrap_or_default(),
+				r.attempts.to_string(),
+				r.late_latency.map(format_ms).unwrap_or_default(),
+				r.duplicates.to_string(),
+			])?;
+		}
+		let bytes = writer.into_inner().map_err(|e| anyhow!("{}", e))?;
+		std::fs::write(&tmp, bytes).with_context(|| format!("writing checkpoint {}", tmp))?;
+		std::fs::rename(&tmp, path).with_context(|| format!("replacing checkpoint {}", path))?;
+		Ok(())
+	}
+
+	/// Parse checkpoint text. Fails on a malformed row, naming its line.
+	pub fn parse(text: &str) -> Result<Checkpoint> {
+		let mut checkpoint = Checkpoint::default();
+		for line in text.lines().take_while(|l| l.starts_with('#')) {
+			let Some((key, value)) = line.trim_start_matches('#').split_once(':') else { continue };
+			let list = || value.split_whitespace().map(String::from).collect::<Vec<_>>();
+			match key.trim() {
+				"fingerprint" => checkpoint.fingerprint = value.trim().to_string(),
+				"rounds_done" => checkpoint.rounds_done = value.trim().parse()
+					.map_err(|_| anyhow!("rounds_done: expected a number, got '{}'", value.trim()))?,
+				"resolvers" => checkpoint.resolvers = list(),
+				"sidelined" => checkpoint.sidelined = list(),
+				"unresponsive" => checkpoint.unresponsive = list(),
+				"unreachable" => checkpoint.unreachable = list(),
+				_ => {}
+			}
+		}
+		if checkpoint.fingerprint.is_empty() {
+			bail!("no fingerprint line; not a benchmark checkpoint");
+		}
+
+		let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(text.as_bytes());
+		for row in reader.records() {
+			let row = row?;
+			let line = row.position().map(|p| p.line()).unwrap_or_default();
+			let fields: Vec<&str> = row.iter().collect();
+			let parsed = match fields.as_slice() {
+				[round, resolver, set, domain, qtype, latency, outcome, ttl, attempts, late, duplicates] => {
+					let query_type = match *qtype {
+						"A" => Some(QueryType::A),
+						"AAAA" => Some(QueryType::AAAA),
+						"HTTPS" => Some(QueryType::HTTPS),
+						_ => None,
+					};
+					let optional = |f: &str| if f.is_empty() { Some(None) } else { f.parse::<u32>().ok().map(Some) };
+					let late_latency = if late.is_empty() { Some(None) } else { parse_ms(late).map(Some) };
+					(|| Some(SavedQuery {
+						round: round.parse().ok()?,
+						set_name: set.to_string(),
+						domain: domain.to_string(),
+						query_type: query_type?,
+						result: QueryResult {
+							resolver: (!resolver.is_empty()).then(|| resolver.to_string())?,
+							latency: parse_ms(latency)?,
+							outcome: QueryOutcome::parse(outcome)?,
+							ttl: optional(ttl)?,
+							attempts: attempts.parse().ok()?,
+							late_latency: late_latency?,
+							duplicates: duplicates.parse().ok()?,
+						},
+					}))()
+				}
+				_ => None,
+			};
+			let query = parsed.ok_or_else(|| anyhow!("line {}: expected {}", line, HEADER.join(",")))?;
+			if query.round >= checkpoint.rounds_done {
+				bail!("line {}: round {} was not completed (rounds_done {})", line, query.round, checkpoint.rounds_done);
+			}
+			checkpoint.queries.push(query);
+		}
+		Ok(checkpoint)
+	}
+
+	/// Read and parse a checkpoint file.
+	pub fn read(path: &str) -> Result<Checkpoint> {
+		let text = std::fs::read_to_string(path).with_context(|| format!("reading checkpoint {}", path))?;
+		Checkpoint::parse(&text).with_context(|| format!("parsing checkpoint {}", path))
+	}
+
+	/// Read the checkpoint at `path` for --resume. None when there is no
+	/// checkpoint yet; fails when it was saved for other inputs.
+	pub fn read_for_resume(path: &str, fingerprint: &str) -> Result<Option<Checkpoint>> {
+		if !std::path::Path::new(path).exists() {
+			return Ok(None);
+		}
+		let saved = Checkpoint::read(path)?;
+		if saved.fingerprint != fingerprint {
+			bail!("checkpoint {} was saved by a run with different resolvers, domain sets, or query settings; \
+				rerun with the original options or drop --resume", path);
+		}
+		Ok(Some(saved))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hickory_proto::op::ResponseCode;
+
+	fn query(round: u32, resolver: &str, success: bool, late_ms: Option<u64>) -> SavedQuery {
+		SavedQuery {
+			round,
+			set_name: "cached".to_string(),
+			domain: "example.com".to_string(),
+			query_type: QueryType::AAAA,
+			result: QueryResult {
+				resolver: resolver.to_string(),
+				latency: Duration::from_micros(12_345),
+				outcome: if success { QueryOutcome::Rcode(ResponseCode::NXDomain) } else { QueryOutcome::TxidMismatchExhausted },
+				ttl: success.then_some(300),
+				attempts: if success { 1 } else { 2 },
+				late_latency: late_ms.map(Duration::from_millis),
+				duplicates: u32::from(!success),
+			},
+		}
+	}
+
+	#[test]
+	fn test_write_read_round_trip() {
+		let checkpoint = Checkpoint {
+			fingerprint: "0123456789abcdef".to_string(),
+			resolvers: vec!["192.0.2.1:5353".to_string(), "tls://[2001:db8::53]:853".to_string()],
+			rounds_done: 2,
+			sidelined: vec!["192.0.2.9:53".to_string(), "tls://[2001:db8::53]:853".to_string()],
+			unresponsive: vec!["192.0.2.9:53".to_string()],
+			unreachable: vec!["192.0.2.9:53".to_string()],
+			queries: vec![query(0, "192.0.2.1:5353", true, None), query(1, "tls://[2001:db8::53]:853", false, Some(2300))],
+		};
+		let path = std::env::temp_dir().join(format!("dns_benchmark_checkpoint_{}.csv", std::process::id()));
+		let path = path.to_str().unwrap();
+		checkpoint.write(path).unwrap();
+		let loaded = Checkpoint::read(path).unwrap();
+		std::fs::remove_file(path).unwrap();
+
+		assert_eq!(loaded.fingerprint, checkpoint.fingerprint);
+		assert_eq!(loaded.rounds_done, 2);
+		assert_eq!(loaded.resolvers, checkpoint.resolvers);
+		assert_eq!(loaded.sidelined, checkpoint.sidelined);
+		assert_eq!(loaded.unresponsive, checkpoint.unresponsive);
+		assert_eq!(loaded.unreachable, checkpoint.unreachable);
+		assert_eq!(loaded.queries.len(), 2);
+		let (first, second) = (&loaded.queries[0].result, &loaded.queries[1]);
+		assert_eq!((first.latency, first.ttl, first.late_latency), (Duration::from_micros(12_345), Some(300), None));
+		assert_eq!(first.outcome, QueryOutcome::Rcode(ResponseCode::NXDomain));
+		assert_eq!((second.round, second.query_type, second.result.resolver.as_str()), (1, QueryType::AAAA, "tls://[2001:db8::53]:853"));
+		assert_eq!((second.result.outcome, second.result.attempts, second.result.duplicates), (QueryOutcome::TxidMismatchExhausted, 2, 1));
+		assert_eq!(second.result.late_latency, Some(Duration::from_millis(2300)));
+	}
+
+	#[test]
+	fn test_parse_rejects_bad_rows() {
+		let header = format!("# fingerprint: ab\n# rounds_done: 1\n{}\n", HEADER.join(","));
+		assert!(Checkpoint::parse(&format!("{}0,192.0.2.1,cached,example.com,A,10.0,ok,,1,,0\n", header)).is_ok());
+		let err = Checkpoint::parse(&format!("{}0,192.0.2.1,cached,example.com,MX,10.0,ok,,1,,0\n", header)).unwrap_err();
+		assert!(err.to_string().starts_with("line 4:"));
+		assert!(Checkpoint::parse(&format!("{}0,192.0.2.1,cached,example.com,A,10.0,success,,1,,0\n", header)).is_err());
+		// A row from a round past rounds_done
+		assert!(Checkpoint::parse(&format!("{}1,192.0.2.1,cached,example.com,A,10.0,ok,,1,,0\n", header)).is_err());
+		assert!(Checkpoint::parse(&format!("{}0,,cached,example.com,A,10.0,ok,,1,,0\n", header)).is_err());
+		assert!(Checkpoint::parse(&HEADER.join(",")).is_err());
+	}
+}
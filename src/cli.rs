@@ -1,4 +1,5 @@
 use clap::Parser;
+use clap::Subcommand;
 use clap::ValueEnum;
 
 /// Benchmark level controlling coverage scope and confidence depth
@@ -25,11 +26,185 @@ impl std::fmt::Display for BenchLevel {
 	}
 }
 
+/// Connection handling for DoT and DoH queries (--connection-mode)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ConnectionMode {
+	/// Keep connections open between queries (steady-state latency)
+	#[default]
+	Reuse,
+	/// Open a new connection with a full TLS handshake for every query (worst case)
+	PerQuery,
+}
+
+impl std::fmt::Display for ConnectionMode {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			ConnectionMode::Reuse => write!(f, "reuse"),
+			ConnectionMode::PerQuery => write!(f, "per-query"),
+		}
+	}
+}
+
+/// HTTP version of DoH queries (--doh-http)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DohHttpVersion {
+	/// HTTP/1.1 over TLS
+	#[value(name = "1.1")]
+	Http1,
+	/// HTTP/2, assumed without negotiation
+	#[default]
+	#[value(name = "2")]
+	Http2,
+}
+
+impl DohHttpVersion {
+	/// Every version the DoH comparison measures, oldest first
+	pub const ALL: [DohHttpVersion; 2] = [DohHttpVersion::Http1, DohHttpVersion::Http2];
+
+	/// Value as given to --doh-http ("1.1" or "2")
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			DohHttpVersion::Http1 => "1.1",
+			DohHttpVersion::Http2 => "2",
+		}
+	}
+}
+
+impl std::fmt::Display for DohHttpVersion {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "HTTP/{}", self.as_str())
+	}
+}
+
+/// Format for results written to stdout (--stdout)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+	/// Same columns as the -o CSV
+	Csv,
+	/// Array of objects, one per resolver, keyed by the CSV column names
+	Json,
+}
+
+/// Latency distribution chart drawn by --chart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ChartKind {
+	/// One box plot row per resolver (p5, p25, median, p75, p95)
+	Box,
+	/// Cumulative distribution curve per resolver
+	Ecdf,
+}
+
+/// Workload the ranking is tuned for (--profile)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Profile {
+	/// Desktop and phone browsing: popular, cached names dominate
+	Browsing,
+	/// Games: latency spikes and lost lookups matter most
+	Gaming,
+	/// Servers and crawlers: uncached names and AAAA lookups
+	Server,
+}
+
+impl std::fmt::Display for Profile {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Profile::Browsing => write!(f, "browsing"),
+			Profile::Gaming => write!(f, "gaming"),
+			Profile::Server => write!(f, "server"),
+		}
+	}
+}
+
+/// Stub resolver configuration written by --emit-config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EmitFormat {
+	/// nameserver lines for /etc/resolv.conf
+	#[value(name = "resolv.conf")]
+	ResolvConf,
+	/// systemd-resolved drop-in (DNS=, DNSOverTLS=)
+	Systemd,
+	/// Unbound forward-zone
+	Unbound,
+	/// dnsmasq server= lines
+	Dnsmasq,
+}
+
+impl std::fmt::Display for EmitFormat {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			EmitFormat::ResolvConf => write!(f, "resolv.conf"),
+			EmitFormat::Systemd => write!(f, "systemd-resolved"),
+			EmitFormat::Unbound => write!(f, "Unbound"),
+			EmitFormat::Dnsmasq => write!(f, "dnsmasq"),
+		}
+	}
+}
+
+/// Concurrency setting: a fixed number of in-flight queries or auto-tuned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Concurrency {
+	Fixed(usize),
+	/// Ramp up against a control resolver before the benchmark
+	Auto,
+}
+
+/// Parse a --concurrency value: "auto" or a positive integer.
+fn parse_concurrency(value: &str) -> Result<Concurrency, String> {
+	if value.eq_ignore_ascii_case("auto") {
+		return Ok(Concurrency::Auto);
+	}
+	match value.parse::<usize>() {
+		Ok(n) if n > 0 => Ok(Concurrency::Fixed(n)),
+		_ => Err(format!("expected 'auto' or a positive integer, got '{}'", value)),
+	}
+}
+
 /// DNS resolver benchmark tool
 #[derive(Parser, Debug)]
 #[command(name = "dns-benchmark")]
 #[command(about = "Benchmark DNS resolver performance over UDP, DoT, and DoH")]
 pub struct Cli {
+	/// Optional subcommand; without one, a benchmark is run
+	#[command(subcommand)]
+	pub command: Option<Command>,
+
+	/// TOML file with benchmark settings; command-line flags override its values
+	#[arg(long = "config")]
+	pub config: Option<String>,
+
+	/// Print a configuration snippet listing the top resolvers
+	#[arg(long = "emit-config", value_enum, conflicts_with = "stdout")]
+	pub emit_config: Option<EmitFormat>,
+
+	/// Number of resolvers in the --emit-config snippet
+	#[arg(long = "emit-top", default_value_t = crate::emit::DEFAULT_EMIT_TOP,
+		value_parser = clap::value_parser!(u32).range(1..))]
+	pub emit_top: u32,
+
+	/// Exit with code 9 when the best resolver's score exceeds this many ms
+	#[arg(long = "fail-if-slower-than", value_name = "MS")]
+	pub fail_if_slower_than: Option<f64>,
+
+	/// Exit with code 10 when a system resolver intercepts NXDOMAIN
+	#[arg(long = "fail-on-interception")]
+	pub fail_on_interception: bool,
+
+	/// Write results to stdout in --format instead of printing the table and reports
+	#[arg(long = "stdout")]
+	pub stdout: bool,
+
+	/// Format for --stdout results
+	#[arg(long = "format", value_enum, default_value = "csv", requires = "stdout")]
+	pub format: OutputFormat,
+
+	/// Print only results (tables and reports); hide progress, notes, and warnings
+	#[arg(short = 'q', long = "quiet", global = true, conflicts_with = "verbose")]
+	pub quiet: bool,
+
+	/// Show response parse errors and per-resolver detail; -vv adds one line per query
+	#[arg(short = 'v', long = "verbose", global = true, action = clap::ArgAction::Count)]
+	pub verbose: u8,
+
 	/// DNS resolver address (repeatable, e.g. 1.1.1.1 or 1.1.1.1:53)
 	#[arg(short = 'r', long = "resolver")]
 	pub resolvers: Vec<String>,
@@ -38,14 +213,131 @@ pub struct Cli {
 	#[arg(short = 'f', long = "resolver-file")]
 	pub resolver_file: Option<String>,
 
+	/// Keep the systemd-resolved stub (127.0.0.53) as its own entry next to its upstream servers
+	#[arg(long = "include-stub")]
+	pub include_stub: bool,
+
+	/// Also benchmark the default gateway (home router) as a resolver
+	#[arg(long = "gateway-resolver")]
+	pub gateway_resolver: bool,
+
+	/// Also benchmark the other-family address of each built-in provider (1.1.1.1 adds 2606:4700:4700::1111)
+	#[arg(long = "dual-stack", conflicts_with_all = ["ipv4_only", "ipv6_only"])]
+	pub dual_stack: bool,
+
+	/// Benchmark IPv4 resolvers only
+	#[arg(long = "ipv4-only", conflicts_with = "ipv6_only")]
+	pub ipv4_only: bool,
+
+	/// Benchmark IPv6 resolvers only
+	#[arg(long = "ipv6-only")]
+	pub ipv6_only: bool,
+
+	/// Drop resolvers that intercept NXDOMAIN before the benchmark instead of only flagging them
+	#[arg(long = "drop-intercepting")]
+	pub drop_intercepting: bool,
+
+	/// Remove public resolvers that look like abused open resolvers (home routers, compromised devices)
+	#[arg(long = "exclude-suspect-open")]
+	pub exclude_suspect_open: bool,
+
+	/// Tighten each resolver's timeout to 3x its observed p99 after every round
+	#[arg(long = "adaptive-timeout")]
+	pub adaptive_timeout: bool,
+
 	/// Number of benchmark rounds (overrides level default)
 	#[arg(short = 'n', long = "rounds")]
 	pub rounds: Option<u32>,
 
+	/// Seed for the benchmark query order (default: random, recorded in the results manifest)
+	#[arg(long = "seed")]
+	pub seed: Option<u64>,
+
+	/// Sample the warm (cached) set from a top list instead of the built-in names: tranco:N or tranco:N:FILE
+	#[arg(long = "warm-source", value_parser = crate::domains::parse_warm_source)]
+	pub warm_source: Option<crate::domains::WarmSource>,
+
+	/// Query a fresh random label under this zone for every uncached-set query (needs a wildcard record you control)
+	#[arg(long = "cold-zone", value_parser = crate::domains::parse_cold_zone)]
+	pub cold_zone: Option<String>,
+
+	/// Also query HTTPS (type 65) records for every benchmark domain
+	#[arg(long = "https-rr")]
+	pub https_rr: bool,
+
+	/// DoT and DoH connections: reuse (kept alive between queries) or per-query (new connection each query)
+	#[arg(long = "connection-mode", default_value_t = ConnectionMode::Reuse)]
+	pub connection_mode: ConnectionMode,
+
+	/// HTTP version for DoH queries: 1.1 or 2
+	#[arg(long = "doh-http", default_value = "2")]
+	pub doh_http: DohHttpVersion,
+
+	/// Send DoT and DoH connections through a proxy: socks5://[user:pass@]host[:port] or http://host[:port]
+	#[arg(long = "proxy", value_parser = crate::proxy::parse_proxy)]
+	pub proxy: Option<crate::proxy::ProxyConfig>,
+
+	/// Send every query from this local address (multihomed hosts: pick the uplink)
+	#[arg(long = "source-ip", value_name = "IP")]
+	pub source_ip: Option<std::net::IpAddr>,
+
+	/// Send every query out this network interface, e.g. eth0 (Linux, may need root)
+	#[arg(long = "interface", value_name = "NAME")]
+	pub interface: Option<String>,
+
+	/// Resolve resolver hostnames (dns.quad9.net, tls://dns.quad9.net) through this DNS server instead of the system resolver
+	#[arg(long = "bootstrap", value_name = "IP")]
+	pub bootstrap: Option<std::net::IpAddr>,
+
+	/// RIPE Atlas API key: also measure the public UDP resolvers from Atlas probes (costs credits)
+	#[arg(long = "ripe-atlas-key", value_name = "KEY")]
+	pub ripe_atlas_key: Option<String>,
+
+	/// RIPE Atlas probes per measurement
+	#[arg(long = "ripe-atlas-probes", default_value_t = crate::atlas::DEFAULT_ATLAS_PROBES,
+		value_parser = clap::value_parser!(u32).range(1..=1000))]
+	pub ripe_atlas_probes: u32,
+
+	/// Pick RIPE Atlas probes from this country (ISO code, e.g. DE) instead of worldwide
+	#[arg(long = "ripe-atlas-country", value_name = "CC")]
+	pub ripe_atlas_country: Option<String>,
+
 	/// Output CSV file path
 	#[arg(short = 'o', long = "output")]
 	pub output: Option<String>,
 
+	/// Append to the --output CSV, with run_id, timestamp, and network_label columns, instead of overwriting it
+	#[arg(long = "append", requires = "output")]
+	pub append: bool,
+
+	/// Name of the network this run measures, e.g. home-wifi or office, recorded in every output (default: the Wi-Fi SSID or interface)
+	#[arg(long = "label", alias = "network-label", value_name = "LABEL")]
+	pub label: Option<String>,
+
+	/// Write results as a Prometheus textfile (for the node_exporter textfile collector)
+	#[arg(long = "prometheus")]
+	pub prometheus: Option<String>,
+
+	/// Write results as InfluxDB line protocol: an http(s) write URL, or a file to append to
+	#[arg(long = "influx")]
+	pub influx: Option<String>,
+
+	/// Write the recommendation (primary, secondary, fallback) as a JSON policy document
+	#[arg(long = "policy")]
+	pub policy: Option<String>,
+
+	/// Write every benchmark query with wall-clock and monotonic timestamps (JSONL)
+	#[arg(long = "raw-output")]
+	pub raw_output: Option<String>,
+
+	/// Save completed benchmark rounds to FILE after each round, for --resume
+	#[arg(long = "checkpoint", value_name = "FILE")]
+	pub checkpoint: Option<String>,
+
+	/// Continue from the rounds saved in the --checkpoint file instead of starting over
+	#[arg(long = "resume")]
+	pub resume: bool,
+
 	/// Save surviving resolver list to file (one per line)
 	#[arg(long = "save-resolvers")]
 	pub save_resolvers: Option<String>,
@@ -54,7 +346,251 @@ pub struct Cli {
 	#[arg(short = 'l', long = "level", default_value = "quick")]
 	pub level: BenchLevel,
 
+	/// Ranking: score, stability, name, or a set name (e.g. cached) to rank by its p50
+	#[arg(long = "rank-by", default_value = crate::transport::DEFAULT_SORT)]
+	pub rank_by: String,
+
+	/// Score for a workload: browsing weights cached names, gaming the latency tail, server uncached names and AAAA
+	#[arg(long = "profile", value_enum)]
+	pub profile: Option<Profile>,
+
+	/// Concurrent in-flight queries: a number, or "auto" to ramp up while watching for local drops
+	#[arg(long = "concurrency", value_parser = parse_concurrency)]
+	pub concurrency: Option<Concurrency>,
+
+	/// After a UDP timeout keep listening this many ms and record when late answers arrive (0 = off)
+	#[arg(long = "late-grace-ms", default_value_t = 0)]
+	pub late_grace_ms: u64,
+
+	/// Benchmark queries in flight to any one resolver IP
+	#[arg(long = "per-resolver-inflight", default_value_t = crate::transport::DEFAULT_PER_RESOLVER_INFLIGHT,
+		value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+	pub per_resolver_inflight: usize,
+
+	/// Maximum benchmark queries per second to any one resolver IP (default: unlimited)
+	#[arg(long = "per-resolver-qps", value_parser = clap::value_parser!(u32).range(1..))]
+	pub per_resolver_qps: Option<u32>,
+
+	/// UDP retransmissions per query within the timeout, with exponential backoff (0-5)
+	#[arg(long = "retries", default_value_t = crate::transport::DEFAULT_UDP_RETRIES,
+		value_parser = clap::value_parser!(u32).range(0..=crate::transport::MAX_UDP_RETRIES as i64))]
+	pub retries: u32,
+
+	/// Send UDP benchmark queries over a small pool of shared sockets instead of one socket per query
+	#[arg(long = "udp-pool")]
+	pub udp_pool: bool,
+
+	/// Ping each resolver and time TCP connects to split latency into network round trip vs DNS overhead
+	#[arg(long = "path-probe")]
+	pub path_probe: bool,
+
+	/// Ask each resolver for its server identity (EDNS NSID, CHAOS id.server and hostname.bind)
+	#[arg(long = "identity-probe")]
+	pub identity_probe: bool,
+
+	/// Resolve a few fixed-address names on every resolver and flag answers that deviate from the consensus
+	#[arg(long = "consistency-check")]
+	pub consistency_check: bool,
+
+	/// Trace the hop count and last-hop round trip to each resolver (needs root or CAP_NET_RAW)
+	#[arg(long = "traceroute")]
+	pub traceroute: bool,
+
+	/// Send a burst of identical queries to each UDP resolver to estimate packet loss
+	#[arg(long = "loss-probe")]
+	pub loss_probe: bool,
+
+	/// Time each DoH resolver over HTTP/1.1 and HTTP/2 and report the median per version
+	#[arg(long = "doh-http-compare")]
+	pub doh_http_compare: bool,
+
+	/// Rank providers after the results table, pooling stats over each provider's addresses
+	#[arg(long = "by-provider")]
+	pub by_provider: bool,
+
+	/// Offline IP-to-ASN table (iptoasn.com TSV) for an AS and country column per resolver
+	#[arg(long = "asn-db", value_name = "FILE")]
+	pub asn_db: Option<String>,
+
+	/// Print an ASCII latency histogram per resolver after the results table
+	#[arg(long = "histogram")]
+	pub histogram: bool,
+
+	/// Draw per-resolver latency box plots or ECDF curves after the results table
+	#[arg(long = "chart", value_enum)]
+	pub chart: Option<ChartKind>,
+
+	/// Send one unmeasured query for every warm-set domain to every resolver before timing
+	#[arg(long = "prime-cache")]
+	pub prime_cache: bool,
+
+	/// Fraction of UDP responses to drop (fault injection)
+	#[cfg(feature = "fault-injection")]
+	#[arg(long = "inject-drop", hide = true, default_value_t = 0.0, value_parser = crate::fault::parse_rate)]
+	pub inject_drop: f64,
+
+	/// Fraction of UDP responses to delay by --inject-delay-ms (fault injection)
+	#[cfg(feature = "fault-injection")]
+	#[arg(long = "inject-delay", hide = true, default_value_t = 0.0, value_parser = crate::fault::parse_rate)]
+	pub inject_delay: f64,
+
+	/// Delay for --inject-delay in milliseconds (fault injection)
+	#[cfg(feature = "fault-injection")]
+	#[arg(long = "inject-delay-ms", hide = true, default_value_t = 200)]
+	pub inject_delay_ms: u64,
+
+	/// Fraction of UDP responses to corrupt one byte of (fault injection)
+	#[cfg(feature = "fault-injection")]
+	#[arg(long = "inject-corrupt", hide = true, default_value_t = 0.0, value_parser = crate::fault::parse_rate)]
+	pub inject_corrupt: f64,
+
+	/// Run the benchmark in each named Linux network namespace in turn and merge results (repeatable, needs root)
+	#[arg(long = "netns")]
+	pub netns: Vec<String>,
+
+	/// Run the benchmark on a remote `agent` (NAME=HOST[:PORT], repeatable) and compare resolvers per vantage point
+	#[arg(long = "agent", value_parser = crate::agent::parse_agent, conflicts_with = "netns")]
+	pub agents: Vec<crate::agent::AgentTarget>,
+
+	/// Token the --agent hosts were started with
+	#[arg(long = "agent-token", requires = "agents")]
+	pub agent_token: Option<String>,
+
 	/// Print config summary and exit without running benchmark
 	#[arg(long = "no-test")]
 	pub no_test: bool,
 }
+
+/// Subcommands that replace the one-shot benchmark
+#[derive(Subcommand, Debug)]
+pub enum Command {
+	/// Analyze CSV exports in a directory without sending any queries
+	Analyze {
+		/// Directory containing CSV exports written with -o
+		dir: String,
+	},
+	/// Compare two saved results (CSV from -o, or --stdout --format json): rank changes, score deltas, new failures
+	Diff {
+		/// Earlier result file
+		before: String,
+		/// Later result file
+		after: String,
+	},
+	/// Generate a Grafana dashboard for the metrics written by --prometheus
+	GrafanaDashboard {
+		/// Output file (default: stdout)
+		#[arg(short = 'o', long = "output")]
+		output: Option<String>,
+	},
+	/// Build domain sets for --config from your own browsing
+	Domains {
+		#[command(subcommand)]
+		action: DomainsCommand,
+	},
+	/// Check resolvers periodically and report latency and availability
+	Monitor {
+		/// Seconds between checks
+		#[arg(long = "interval", default_value_t = crate::monitor::DEFAULT_MONITOR_INTERVAL_SECS,
+			value_parser = clap::value_parser!(u64).range(1..))]
+		interval: u64,
+
+		/// Cron expression (UTC) for check times instead of a fixed interval, e.g. "0 */6 * * *"
+		#[arg(long = "schedule", conflicts_with = "interval")]
+		schedule: Option<String>,
+
+		/// Print a report every N checks
+		#[arg(long = "report-every", default_value_t = crate::monitor::DEFAULT_MONITOR_REPORT_EVERY,
+			value_parser = clap::value_parser!(u32).range(1..))]
+		report_every: u32,
+
+		/// Stop after N checks (default: run until Ctrl-C)
+		#[arg(long = "count")]
+		count: Option<u32>,
+
+		/// Consecutive failed checks before a resolver counts as down
+		#[arg(long = "down-after", default_value_t = crate::monitor::DEFAULT_DOWN_AFTER,
+			value_parser = clap::value_parser!(u32).range(1..))]
+		down_after: u32,
+
+		/// POST a JSON alert here when a system resolver goes down, gets slow, or recovers
+		#[arg(long = "notify-webhook")]
+		notify_webhook: Option<String>,
+
+		/// Report-period p50 in ms above which --notify-webhook alerts
+		#[arg(long = "notify-p50-ms", default_value_t = crate::monitor::DEFAULT_NOTIFY_P50_MS, requires = "notify_webhook")]
+		notify_p50_ms: f64,
+	},
+	/// Run the characterization probes only (no latency benchmark) and print a capability matrix
+	Characterize {
+		/// Also write the matrix as CSV
+		#[arg(short = 'o', long = "output")]
+		output: Option<String>,
+	},
+	/// Compare a local caching resolver (cold and warm) against going direct to its upstreams
+	LocalCache {
+		/// Local caching resolver to test (dnsmasq, unbound, Pi-hole)
+		#[arg(long = "local", default_value = crate::localcache::DEFAULT_LOCAL_CACHE)]
+		local: String,
+
+		/// Upstream resolver to compare against (repeatable; default: the system's non-local resolvers)
+		#[arg(long = "upstream")]
+		upstream: Vec<String>,
+
+		/// Warm-cache queries per name after the first (cold) query
+		#[arg(long = "repeats", default_value_t = crate::localcache::DEFAULT_LOCAL_CACHE_REPEATS,
+			value_parser = clap::value_parser!(u32).range(1..))]
+		repeats: u32,
+	},
+	/// Listen for benchmark jobs from a coordinator run with --agent, and send back the results
+	Agent {
+		/// Address and port to listen on
+		#[arg(long = "listen", default_value = crate::agent::DEFAULT_AGENT_LISTEN)]
+		listen: String,
+
+		/// Only run jobs that present this token (--agent-token on the coordinator)
+		#[arg(long = "token")]
+		token: Option<String>,
+	},
+	/// Ramp query rate against one resolver to find its capacity and latency knee
+	Stress {
+		/// Resolver to load (an address, tls://, or https:// URL); only test resolvers you operate
+		#[arg(long = "resolver")]
+		resolver: String,
+
+		/// Total test length, e.g. 30s or 2m, split evenly over the ramp steps
+		#[arg(long = "duration", default_value = "30s", value_parser = crate::stress::parse_duration)]
+		duration: std::time::Duration,
+
+		/// Peak offered rate in queries per second
+		#[arg(long = "target-qps", default_value_t = crate::stress::DEFAULT_STRESS_TARGET_QPS,
+			value_parser = clap::value_parser!(u32).range(1..=crate::stress::STRESS_MAX_TARGET_QPS as i64))]
+		target_qps: u32,
+
+		/// Allow a resolver that is not on a loopback or private address (only one you operate)
+		#[arg(long = "allow-public")]
+		allow_public: bool,
+	},
+}
+
+/// Actions of the `domains` subcommand.
+#[derive(Subcommand, Debug)]
+pub enum DomainsCommand {
+	/// Rank the names in a Firefox/Chrome history file or a pcap of DNS traffic and print a [domains] table
+	Import {
+		/// places.sqlite (Firefox), History (Chrome), or a .pcap/.pcapng capture
+		source: String,
+
+		/// Domain set to write; "cached" replaces the warm-cache set
+		#[arg(long = "set", default_value = crate::import::DEFAULT_IMPORT_SET)]
+		set: String,
+
+		/// Number of names to keep, most visited or queried first
+		#[arg(long = "top", default_value_t = crate::import::DEFAULT_IMPORT_TOP,
+			value_parser = clap::value_parser!(u32).range(1..))]
+		top: u32,
+
+		/// Output file (default: stdout)
+		#[arg(short = 'o', long = "output")]
+		output: Option<String>,
+	},
+}
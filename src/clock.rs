@@ -0,0 +1,78 @@
+use std::time::{Duration, SystemTime};
+
+/// Host clock synchronization status, recorded with raw exports so query
+/// timestamps can be trusted (or discounted) when correlating with external
+/// events.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClockStatus {
+	/// Whether the kernel considers the clock NTP-synchronized; None if unknown
+	pub synchronized: Option<bool>,
+	/// Current NTP offset estimate in microseconds
+	pub offset_us: Option<i64>,
+	/// Maximum clock error in microseconds
+	pub max_error_us: Option<i64>,
+	/// Estimated clock error in microseconds
+	pub est_error_us: Option<i64>,
+	/// Where the status came from ("adjtimex" or "unavailable")
+	pub source: &'static str,
+}
+
+//============================================
+/// Read clock sync status from the kernel (Linux adjtimex, read-only).
+///
+/// Works with any NTP daemon (chrony, ntpd, systemd-timesyncd) because it
+/// reads the kernel discipline state they all feed.
+#[cfg(target_os = "linux")]
+pub fn read_clock_status() -> ClockStatus {
+	// SAFETY: timex is plain data; modes = 0 (from zeroed) makes adjtimex read-only
+	let mut tx: libc::timex = unsafe { std::mem::zeroed() };
+	let state = unsafe { libc::adjtimex(&mut tx) };
+	if state < 0 {
+		return ClockStatus { source: "unavailable", ..Default::default() };
+	}
+	let unsync = state == libc::TIME_ERROR || tx.status & libc::STA_UNSYNC != 0;
+	// Offset is in nanoseconds when STA_NANO is set, else microseconds
+	let offset_us = if tx.status & libc::STA_NANO != 0 {
+		tx.offset as i64 / 1000
+	} else {
+		tx.offset as i64
+	};
+	ClockStatus {
+		synchronized: Some(!unsync),
+		offset_us: Some(offset_us),
+		max_error_us: Some(tx.maxerror as i64),
+		est_error_us: Some(tx.esterror as i64),
+		source: "adjtimex",
+	}
+}
+
+/// Clock sync status is not available off Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn read_clock_status() -> ClockStatus {
+	ClockStatus { source: "unavailable", ..Default::default() }
+}
+
+//============================================
+/// Time since the Unix epoch; zero if the clock is before 1970.
+pub fn since_epoch(t: SystemTime) -> Duration {
+	t.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default()
+}
+
+/// Format a time as ISO 8601 UTC with milliseconds.
+pub fn format_iso_ms(t: SystemTime) -> String {
+	let d = since_epoch(t);
+	let secs = crate::telemetry::format_timestamp_iso(d.as_secs());
+	// Insert milliseconds before the trailing "Z"
+	format!("{}.{:03}Z", secs.trim_end_matches('Z'), d.subsec_millis())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_format_iso_ms() {
+		let t = SystemTime::UNIX_EPOCH + Duration::from_millis(86_400_000 + 1_234);
+		assert_eq!(format_iso_ms(t), "1970-01-02T00:00:01.234Z");
+	}
+}
@@ -0,0 +1,334 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::Deserialize;
+
+use crate::cli::{BenchLevel, ChartKind, Cli, Concurrency, ConnectionMode, DohHttpVersion, Profile};
+use crate::stats::ScoreWeights;
+
+/// A --concurrency value in the file: a number or "auto".
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum ConcurrencyValue {
+	Count(usize),
+	Text(String),
+}
+
+/// The [scoring] table: set score weights (see `crate::stats::set_score`)
+/// and set weights in the overall score (see `crate::stats::rescore`).
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ScoringSection {
+	/// Multiplier on the tail spread (p95 - p50); default 0.5
+	pub tail_weight: Option<f64>,
+	/// Multiplier on the full-timeout penalty per timed-out query; default 1.0
+	pub timeout_scale: Option<f64>,
+	/// Penalty (ms) per answered query with an error rcode; default 1000
+	pub failure_penalty_ms: Option<f64>,
+	/// Overall score penalty (ms) for NXDOMAIN interception; default 0
+	pub interception_penalty_ms: Option<f64>,
+	/// Overall score bonus (ms) for DNSSEC validation; default 0
+	pub dnssec_bonus_ms: Option<f64>,
+	/// Overall score penalty (ms) for ad/malware filtering; default 0
+	pub filtering_penalty_ms: Option<f64>,
+	/// Weight of the warm (cached) set; default 1, or the --profile's
+	pub warm_weight: Option<f64>,
+	/// Weight of the cold (uncached) set; default 1, or the --profile's
+	pub cold_weight: Option<f64>,
+	/// Weight of the AAAA query-type score; default 0, or the --profile's
+	pub aaaa_weight: Option<f64>,
+}
+
+/// Benchmark setup read from a TOML file (--config).
+///
+/// Top-level keys mirror the long CLI flags with dashes as underscores
+/// (`rank_by`, `late_grace_ms`, ...). Resolvers use the -r syntax, so the
+/// transport comes from the address (`tls://`, `https://`, `sdns://`).
+/// `[domains]` maps set names to domain lists, replacing a built-in set
+/// of the same name or adding a new one. Unknown keys are an error so a
+/// typo never silently falls back to a default.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+	pub level: Option<String>,
+	pub rounds: Option<u32>,
+	pub seed: Option<u64>,
+	pub warm_source: Option<String>,
+	pub cold_zone: Option<String>,
+	pub https_rr: Option<bool>,
+	pub connection_mode: Option<String>,
+	pub doh_http: Option<String>,
+	pub proxy: Option<String>,
+	pub source_ip: Option<std::net::IpAddr>,
+	pub interface: Option<String>,
+	pub bootstrap: Option<std::net::IpAddr>,
+	pub ripe_atlas_key: Option<String>,
+	pub ripe_atlas_probes: Option<u32>,
+	pub ripe_atlas_country: Option<String>,
+	#[serde(default)]
+	pub resolvers: Vec<String>,
+	pub resolver_file: Option<String>,
+	pub include_stub: Option<bool>,
+	pub gateway_resolver: Option<bool>,
+	pub dual_stack: Option<bool>,
+	pub ipv4_only: Option<bool>,
+	pub ipv6_only: Option<bool>,
+	pub drop_intercepting: Option<bool>,
+	pub exclude_suspect_open: Option<bool>,
+	pub adaptive_timeout: Option<bool>,
+	pub rank_by: Option<String>,
+	pub concurrency: Option<ConcurrencyValue>,
+	pub retries: Option<u32>,
+	pub late_grace_ms: Option<u64>,
+	pub per_resolver_inflight: Option<usize>,
+	pub per_resolver_qps: Option<u32>,
+	pub udp_pool: Option<bool>,
+	pub path_probe: Option<bool>,
+	pub traceroute: Option<bool>,
+	pub loss_probe: Option<bool>,
+	pub doh_http_compare: Option<bool>,
+	pub identity_probe: Option<bool>,
+	pub consistency_check: Option<bool>,
+	pub by_provider: Option<bool>,
+	pub asn_db: Option<String>,
+	pub histogram: Option<bool>,
+	pub chart: Option<String>,
+	pub profile: Option<String>,
+	pub append: Option<bool>,
+	#[serde(alias = "network_label")]
+	pub label: Option<String>,
+	pub fail_if_slower_than: Option<f64>,
+	pub fail_on_interception: Option<bool>,
+	pub prime_cache: Option<bool>,
+	pub output: Option<String>,
+	pub prometheus: Option<String>,
+	pub influx: Option<String>,
+	pub policy: Option<String>,
+	pub raw_output: Option<String>,
+	pub checkpoint: Option<String>,
+	pub resume: Option<bool>,
+	pub save_resolvers: Option<String>,
+	#[serde(default)]
+	pub scoring: ScoringSection,
+	#[serde(default)]
+	pub domains: BTreeMap<String, Vec<String>>,
+}
+
+//============================================
+impl FileConfig {
+	/// Parse TOML text and check value ranges clap would otherwise enforce.
+	pub fn parse(text: &str) -> Result<Self> {
+		let file: FileConfig = toml::from_str(text).map_err(|e| anyhow!("{}", e))?;
+		if file.retries.is_some_and(|r| r > crate::transport::MAX_UDP_RETRIES) {
+			bail!("retries must be 0-{}", crate::transport::MAX_UDP_RETRIES);
+		}
+		if file.per_resolver_inflight == Some(0) || file.per_resolver_qps == Some(0) {
+			bail!("per_resolver_inflight and per_resolver_qps must be at least 1");
+		}
+		let scoring = &file.scoring;
+		for (key, value) in [
+			("tail_weight", scoring.tail_weight),
+			("timeout_scale", scoring.timeout_scale),
+			("failure_penalty_ms", scoring.failure_penalty_ms),
+			("interception_penalty_ms", scoring.interception_penalty_ms),
+			("dnssec_bonus_ms", scoring.dnssec_bonus_ms),
+			("filtering_penalty_ms", scoring.filtering_penalty_ms),
+			("warm_weight", scoring.warm_weight),
+			("cold_weight", scoring.cold_weight),
+			("aaaa_weight", scoring.aaaa_weight),
+		] {
+			if value.is_some_and(|v| !v.is_finite() || v < 0.0) {
+				bail!("scoring.{} must be a non-negative number", key);
+			}
+		}
+		if let Some((name, _)) = file.domains.iter().find(|(_, list)| list.is_empty()) {
+			bail!("domain set '{}' is empty", name);
+		}
+		Ok(file)
+	}
+
+	/// Score weights, with `base` (ScoreWeights::DEFAULT or a --profile's)
+	/// for keys the file leaves out.
+	pub fn score_weights(&self, base: ScoreWeights) -> ScoreWeights {
+		ScoreWeights {
+			tail_weight: self.scoring.tail_weight.unwrap_or(base.tail_weight),
+			timeout_scale: self.scoring.timeout_scale.unwrap_or(base.timeout_scale),
+			failure_penalty_ms: self.scoring.failure_penalty_ms.unwrap_or(base.failure_penalty_ms),
+			interception_penalty_ms: self.scoring.interception_penalty_ms.unwrap_or(base.interception_penalty_ms),
+			dnssec_bonus_ms: self.scoring.dnssec_bonus_ms.unwrap_or(base.dnssec_bonus_ms),
+			filtering_penalty_ms: self.scoring.filtering_penalty_ms.unwrap_or(base.filtering_penalty_ms),
+			warm_weight: self.scoring.warm_weight.unwrap_or(base.warm_weight),
+			cold_weight: self.scoring.cold_weight.unwrap_or(base.cold_weight),
+			aaaa_weight: self.scoring.aaaa_weight.unwrap_or(base.aaaa_weight),
+		}
+	}
+
+	/// Replace or add query domain sets from `[domains]`.
+	pub fn apply_domain_sets(&self, categories: &mut BTreeMap<String, Vec<String>>) {
+		for (name, list) in &self.domains {
+			categories.insert(name.clone(), list.clone());
+		}
+	}
+}
+
+/// Read and parse a --config file.
+pub fn load(path: &str) -> Result<FileConfig> {
+	let text = std::fs::read_to_string(path)
+		.map_err(|e| anyhow!("config file {}: {}", path, e))?;
+	FileConfig::parse(&text).map_err(|e| anyhow!("config file {}: {}", path, e))
+}
+
+//============================================
+/// Fill CLI fields from the file wherever the flag was not given on the
+/// command line, so flags always override file values.
+pub fn apply_to_cli(file: &FileConfig, cli: &mut Cli, matches: &ArgMatches) -> Result<()> {
+	let from_file = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+
+	if let (true, Some(level)) = (from_file("level"), &file.level) {
+		cli.level = BenchLevel::from_str(level, true)
+			.map_err(|_| anyhow!("unknown level '{}'; use quick, medium, slow, or exhaustive", level))?;
+	}
+	if let (true, Some(mode)) = (from_file("connection_mode"), &file.connection_mode) {
+		cli.connection_mode = ConnectionMode::from_str(mode, true)
+			.map_err(|_| anyhow!("unknown connection_mode '{}'; use reuse or per-query", mode))?;
+	}
+	if let (true, Some(version)) = (from_file("doh_http"), &file.doh_http) {
+		cli.doh_http = DohHttpVersion::from_str(version, true)
+			.map_err(|_| anyhow!("unknown doh_http '{}'; use \"1.1\" or \"2\"", version))?;
+	}
+	if let (true, Some(name)) = (from_file("profile"), &file.profile) {
+		cli.profile = Some(Profile::from_str(name, true)
+			.map_err(|_| anyhow!("unknown profile '{}'; use browsing, gaming, or server", name))?);
+	}
+	if let (true, Some(kind)) = (from_file("chart"), &file.chart) {
+		cli.chart = Some(ChartKind::from_str(kind, true)
+			.map_err(|_| anyhow!("unknown chart '{}'; use box or ecdf", kind))?);
+	}
+	if let (true, Some(proxy)) = (from_file("proxy"), &file.proxy) {
+		cli.proxy = Some(crate::proxy::parse_proxy(proxy).map_err(|e| anyhow!("proxy: {}", e))?);
+	}
+	if let (true, Some(value)) = (from_file("concurrency"), &file.concurrency) {
+		cli.concurrency = Some(match value {
+			ConcurrencyValue::Count(n) if *n > 0 => Concurrency::Fixed(*n),
+			ConcurrencyValue::Text(s) if s.eq_ignore_ascii_case("auto") => Concurrency::Auto,
+			_ => bail!("concurrency must be \"auto\" or a positive integer"),
+		});
+	}
+	if let (true, Some(source)) = (from_file("warm_source"), &file.warm_source) {
+		cli.warm_source = Some(crate::domains::parse_warm_source(source).map_err(|e| anyhow!("warm_source: {}", e))?);
+	}
+	if let (true, Some(zone)) = (from_file("cold_zone"), &file.cold_zone) {
+		cli.cold_zone = Some(crate::domains::parse_cold_zone(zone).map_err(|e| anyhow!("cold_zone: {}", e))?);
+	}
+	if from_file("resolvers") && !file.resolvers.is_empty() {
+		cli.resolvers = file.resolvers.clone();
+	}
+
+	// Plain values: copy when the file sets them and the flag is absent
+	macro_rules! fill {
+		($($field:ident),* $(,)?) => {$(
+			if let (true, Some(value)) = (from_file(stringify!($field)), file.$field.clone()) {
+				cli.$field = value.into();
+			}
+		)*};
+	}
+	fill!(rounds, seed, https_rr, source_ip, interface, bootstrap, ripe_atlas_key, ripe_atlas_probes, ripe_atlas_country, resolver_file, include_stub, gateway_resolver, dual_stack, ipv4_only, ipv6_only, drop_intercepting, exclude_suspect_open, adaptive_timeout, rank_by, retries, late_grace_ms,
+		per_resolver_inflight, per_resolver_qps, udp_pool, path_probe, traceroute, loss_probe, doh_http_compare, identity_probe, consistency_check, by_provider, asn_db, histogram, prime_cache, append, label,
+		fail_if_slower_than, fail_on_interception, output, prometheus, influx, policy, raw_output, checkpoint, resume, save_resolvers);
+	if (cli.ipv4_only && cli.ipv6_only) || (cli.dual_stack && (cli.ipv4_only || cli.ipv6_only)) {
+		bail!("dual_stack, ipv4_only, and ipv6_only cannot be combined");
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::{CommandFactory, FromArgMatches};
+
+	const SAMPLE: &str = r#"
+level = "medium"
+rounds = 4
+resolvers = ["1.1.1.1", "tls://dns.quad9.net/9.9.9.9"]
+concurrency = "auto"
+retries = 1
+histogram = true
+output = "results.csv"
+warm_source = "tranco:500"
+connection_mode = "per-query"
+doh_http = "1.1"
+profile = "server"
+
+[scoring]
+tail_weight = 0.25
+interception_penalty_ms = 200.0
+
+[domains]
+cached = ["example.com"]
+internal = ["intranet.example"]
+"#;
+
+	fn cli_with(args: &[&str], file: &FileConfig) -> Cli {
+		let matches = Cli::command().get_matches_from(std::iter::once("dns-benchmark").chain(args.iter().copied()));
+		let mut cli = Cli::from_arg_matches(&matches).unwrap();
+		apply_to_cli(file, &mut cli, &matches).unwrap();
+		cli
+	}
+
+	#[test]
+	fn test_file_values_fill_unset_flags() {
+		let file = FileConfig::parse(SAMPLE).unwrap();
+		let cli = cli_with(&[], &file);
+		assert_eq!(cli.level, BenchLevel::Medium);
+		assert_eq!(cli.rounds, Some(4));
+		assert_eq!(cli.resolvers.len(), 2);
+		assert_eq!(cli.concurrency, Some(Concurrency::Auto));
+		assert_eq!(cli.retries, 1);
+		assert!(cli.histogram);
+		assert_eq!(cli.output.as_deref(), Some("results.csv"));
+		assert_eq!(cli.warm_source, Some(crate::domains::WarmSource::Tranco { pool: 500, file: None }));
+		assert_eq!(cli.connection_mode, crate::cli::ConnectionMode::PerQuery);
+		assert_eq!(cli.doh_http, DohHttpVersion::Http1);
+		assert_eq!(file.score_weights(ScoreWeights::DEFAULT), ScoreWeights { tail_weight: 0.25, interception_penalty_ms: 200.0, ..ScoreWeights::DEFAULT });
+		// File keys override the profile's weights; the rest keep the profile's
+		assert_eq!(cli.profile, Some(crate::cli::Profile::Server));
+		let server = file.score_weights(ScoreWeights::for_profile(cli.profile));
+		assert_eq!((server.tail_weight, server.cold_weight, server.aaaa_weight), (0.25, 3.0, 1.0));
+	}
+
+	#[test]
+	fn test_cli_flags_override_file() {
+		let file = FileConfig::parse(SAMPLE).unwrap();
+		let cli = cli_with(&["-l", "quick", "-r", "8.8.8.8", "--retries", "0", "-o", "cli.csv"], &file);
+		assert_eq!(cli.level, BenchLevel::Quick);
+		assert_eq!(cli.resolvers, vec!["8.8.8.8".to_string()]);
+		assert_eq!(cli.retries, 0);
+		assert_eq!(cli.output.as_deref(), Some("cli.csv"));
+		// Untouched keys still come from the file
+		assert_eq!(cli.rounds, Some(4));
+	}
+
+	#[test]
+	fn test_domain_sets_replace_and_add() {
+		let file = FileConfig::parse(SAMPLE).unwrap();
+		let mut categories = BTreeMap::from([
+			("cached".to_string(), vec!["google.com".to_string()]),
+			("tld".to_string(), vec!["nic.io".to_string()]),
+		]);
+		file.apply_domain_sets(&mut categories);
+		assert_eq!(categories["cached"], vec!["example.com".to_string()]);
+		assert_eq!(categories["tld"], vec!["nic.io".to_string()]);
+		assert!(categories.contains_key("internal"));
+	}
+
+	#[test]
+	fn test_parse_rejects_unknown_keys_and_bad_values() {
+		assert!(FileConfig::parse("rouns = 3").is_err());
+		assert!(FileConfig::parse("retries = 9").is_err());
+		assert!(FileConfig::parse("[scoring]\ntail_weight = -1.0").is_err());
+		assert!(FileConfig::parse("[scoring]\ndnssec_bonus_ms = -5.0").is_err());
+		assert!(FileConfig::parse("[domains]\nempty = []").is_err());
+	}
+}
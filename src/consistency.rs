@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use tokio::sync::Semaphore;
+
+use crate::bench::{spawn_progress_monitor, stop_progress_monitor};
+use crate::record::{ConsistencyResult, Deviation, ResolverRecord};
+use crate::transport::BenchmarkConfig;
+
+/// Names whose A records are the same from everywhere: root server and
+/// public-resolver service names served from fixed anycast addresses.
+/// CDN-hosted names are avoided because their answers vary by location.
+pub const CONSISTENCY_DOMAINS: [&str; 4] = [
+	"a.root-servers.net",
+	"one.one.one.one",
+	"dns.google",
+	"dns.quad9.net",
+];
+/// Resolvers that must return the same answer before it counts as consensus
+const MIN_CONSENSUS_VOTES: usize = 2;
+
+//============================================
+/// The answer returned by the most resolvers, if at least
+/// MIN_CONSENSUS_VOTES agree and no other answer ties it.
+///
+/// Answers are compared as sorted address sets. Empty answers never form a
+/// consensus.
+pub fn consensus(answers: &[Vec<Ipv4Addr>]) -> Option<Vec<Ipv4Addr>> {
+	let mut votes: Vec<(&Vec<Ipv4Addr>, usize)> = Vec::new();
+	for answer in answers.iter().filter(|a| !a.is_empty()) {
+		match votes.iter_mut().find(|(set, _)| *set == answer) {
+			Some((_, count)) => *count += 1,
+			None => votes.push((answer, 1)),
+		}
+	}
+	votes.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+	let (best, count) = *votes.first()?;
+	let tied = votes.get(1).is_some_and(|(_, runner_up)| *runner_up == count);
+	(count >= MIN_CONSENSUS_VOTES && !tied).then(|| best.clone())
+}
+
+/// Whether an answer deviates from consensus: it shares no address with it.
+///
+/// Partial overlap is accepted so a resolver that returns a subset of a
+/// rotating record set is not flagged.
+pub fn deviates(answer: &[Ipv4Addr], consensus: &[Ipv4Addr]) -> bool {
+	!answer.iter().any(|ip| consensus.contains(ip))
+}
+
+//============================================
+/// Resolve CONSISTENCY_DOMAINS on every resolver and flag answers that
+/// deviate from the consensus of all resolvers.
+///
+/// Writes a ConsistencyResult onto each record that answered at least one
+/// name. A deviating resolver is one that answered with addresses the
+/// others do not return (or an empty answer): the mark of a hijacking
+/// captive portal or an ISP resolver that rewrites answers. Queries go over
+/// UDP to the resolver address, like the characterization probes.
+pub async fn run_consistency_check(records: &mut [ResolverRecord], config: &BenchmarkConfig) {
+	crate::info!("Answer consistency check: {} names ({} resolvers)...",
+		CONSISTENCY_DOMAINS.len(), records.len());
+	let total = records.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Consistency".to_string(), done.clone(), total, start);
+
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let mut handles = Vec::new();
+	for (i, rec) in records.iter().enumerate() {
+		let addr = rec.resolver.addr;
+		let sem = semaphore.clone();
+		let tm = config.timeout;
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let mut answers = Vec::new();
+			for domain in CONSISTENCY_DOMAINS {
+				answers.push(crate::dns::resolve_a(addr, domain, tm).await);
+			}
+			done.fetch_add(1, Ordering::Relaxed);
+			(i, answers)
+		}));
+	}
+	// answers[record][domain]; None where the resolver did not answer
+	let mut answers: HashMap<usize, Vec<Option<Vec<Ipv4Addr>>>> = HashMap::new();
+	for handle in handles {
+		match handle.await {
+			Ok((idx, result)) => { answers.insert(idx, result); }
+			Err(e) => crate::warn!("consistency check task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Consistency", total, start);
+
+	let consensus_sets: Vec<Option<Vec<Ipv4Addr>>> = (0..CONSISTENCY_DOMAINS.len())
+		.map(|d| {
+			let votes: Vec<Vec<Ipv4Addr>> = answers.values()
+				.filter_map(|a| a[d].clone())
+				.collect();
+			consensus(&votes)
+		})
+		.collect();
+
+	for (idx, result) in answers {
+		let mut checked = 0;
+		let mut deviations = Vec::new();
+		for (d, answer) in result.into_iter().enumerate() {
+			let (Some(answer), Some(expected)) = (answer, &consensus_sets[d]) else { continue };
+			checked += 1;
+			if deviates(&answer, expected) {
+				deviations.push(Deviation {
+					domain: CONSISTENCY_DOMAINS[d].to_string(),
+					answer,
+					consensus: expected.clone(),
+				});
+			}
+		}
+		if checked > 0 {
+			records[idx].consistency = Some(ConsistencyResult { checked, deviations });
+		}
+	}
+	crate::info!();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ips(list: &[[u8; 4]]) -> Vec<Ipv4Addr> {
+		list.iter().map(|&o| Ipv4Addr::from(o)).collect()
+	}
+
+	#[test]
+	fn test_consensus_majority_and_minimum_votes() {
+		let good = ips(&[[1, 1, 1, 1], [1, 0, 0, 1]]);
+		let hijack = ips(&[[10, 0, 0, 1]]);
+		let answers = vec![good.clone(), hijack.clone(), good.clone(), Vec::new()];
+		assert_eq!(consensus(&answers), Some(good.clone()));
+		// One resolver alone is not a consensus
+		assert_eq!(consensus(std::slice::from_ref(&good)), None);
+		// A split vote has no consensus either
+		assert_eq!(consensus(&[good.clone(), hijack.clone(), good, hijack]), None);
+	}
+
+	#[test]
+	fn test_deviates_requires_no_overlap() {
+		let expected = ips(&[[8, 8, 8, 8], [8, 8, 4, 4]]);
+		assert!(!deviates(&ips(&[[8, 8, 4, 4]]), &expected));
+		assert!(deviates(&ips(&[[192, 168, 1, 1]]), &expected));
+		assert!(deviates(&[], &expected));
+	}
+}
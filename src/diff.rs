@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use comfy_table::{presets::UTF8_FULL, Cell, Color, ContentArrangement, Table};
+
+/// Success rate (percent) below which a resolver counts as failing
+const FAILING_SUCCESS_PCT: f64 = 50.0;
+/// Manifest entries that differ between any two runs without making scores
+/// incomparable (the seed is random unless --seed is given; a different
+/// network is usually the point of the comparison)
+const RUN_ONLY_KEYS: [&str; 9] = ["started", "finished", "build", "command_line", "interrupted", "seed", "label", "ssid", "network_interface"];
+
+/// One resolver row of a saved result file.
+#[derive(Debug, Clone, PartialEq)]
+struct DiffRow {
+	label: String,
+	ip: String,
+	/// Resolver port; files saved before the port column get the
+	/// transport's default
+	port: String,
+	transport: String,
+	rank: Option<u32>,
+	score: Option<f64>,
+	/// Percent of queries answered
+	success_pct: Option<f64>,
+	unresponsive: bool,
+}
+
+impl DiffRow {
+	/// Same resolver across runs: address, port, and transport
+	fn key(&self) -> (String, String, String) {
+		(self.ip.clone(), self.port.clone(), self.transport.clone())
+	}
+
+	fn failing(&self) -> bool {
+		self.unresponsive || self.score.is_none() || self.success_pct.is_some_and(|p| p < FAILING_SUCCESS_PCT)
+	}
+}
+
+/// A saved run: run manifest entries and resolver rows.
+#[derive(Debug, Clone, Default)]
+struct SavedRun {
+	manifest: BTreeMap<String, String>,
+	rows: Vec<DiffRow>,
+}
+
+//============================================
+/// Build a row from (column, value) cells; None without resolver and ip_address.
+fn row_from_cells(cells: &BTreeMap<String, String>) -> Option<DiffRow> {
+	let get = |name: &str| cells.get(name).map(String::as_str).unwrap_or_default();
+	let number = |name: &str| get(name).parse::<f64>().ok().filter(|v| v.is_finite());
+	if get("resolver").is_empty() || get("ip_address").is_empty() {
+		return None;
+	}
+	let default_port = match get("transport") {
+		"DoT" => "853",
+		"DoH" => "443",
+		_ => "53",
+	};
+	Some(DiffRow {
+		label: get("resolver").to_string(),
+		ip: get("ip_address").to_string(),
+		port: Some(get("port")).filter(|p| !p.is_empty()).unwrap_or(default_port).to_string(),
+		transport: get("transport").to_string(),
+		rank: get("rank").parse().ok(),
+		score: number("overall_score"),
+		success_pct: number("success_rate"),
+		unresponsive: get("unresponsive") == "true",
+	})
+}
+
+/// Parse a CSV export (-o, or --stdout --format csv) with its `# key: value`
+/// manifest lines.
+fn parse_csv_run(text: &str) -> Result<SavedRun> {
+	let mut run = SavedRun::default();
+	for line in text.lines().take_while(|l| l.starts_with('#')) {
+		if let Some((key, value)) = line.trim_start_matches('#').split_once(':') {
+			run.manifest.insert(key.trim().to_string(), value.trim().to_string());
+		}
+	}
+	let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(text.as_bytes());
+	let header = reader.headers()?.clone();
+	for record in reader.records() {
+		let cells = header.iter().zip(record?.iter()).map(|(h, v)| (h.to_string(), v.to_string())).collect();
+		run.rows.extend(row_from_cells(&cells));
+	}
+	Ok(run)
+}
+
+/// Parse `--stdout --format json` output: {"manifest": {...}, "results": [...]}.
+fn parse_json_run(text: &str) -> Result<SavedRun> {
+	let value: serde_json::Value = serde_json::from_str(text)?;
+	let text_of = |v: &serde_json::Value| match v {
+		serde_json::Value::String(s) => s.clone(),
+		serde_json::Value::Null => String::new(),
+		other => other.to_string(),
+	};
+	let mut run = SavedRun::default();
+	if let Some(manifest) = value["manifest"].as_object() {
+		run.manifest = manifest.iter().map(|(k, v)| (k.clone(), text_of(v))).collect();
+	}
+	let results = value["results"].as_array().ok_or_else(|| anyhow!("no \"results\" array"))?;
+	for result in results {
+		let Some(object) = result.as_object() else { continue };
+		let cells = object.iter().map(|(k, v)| (k.clone(), text_of(v))).collect();
+		run.rows.extend(row_from_cells(&cells));
+	}
+	Ok(run)
+}
+
+/// Load a result file, JSON or CSV by content.
+fn load_run(path: &str) -> Result<SavedRun> {
+	let text = std::fs::read_to_string(path).with_context(|| format!("reading {}", path))?;
+	let run = if text.trim_start().starts_with('{') { parse_json_run(&text) } else { parse_csv_run(&text) };
+	let run = run.with_context(|| format!("parsing {}", path))?;
+	if run.rows.is_empty() {
+		bail!("{} has no resolver rows; expected a CSV export (-o) or --stdout --format json output", path);
+	}
+	Ok(run)
+}
+
+//============================================
+/// Settings recorded in both manifests with different values: (key, before, after).
+fn setting_changes(before: &SavedRun, after: &SavedRun) -> Vec<(String, String, String)> {
+	let keys: std::collections::BTreeSet<&String> = before.manifest.keys().chain(after.manifest.keys()).collect();
+	keys.into_iter()
+		.filter(|k| !RUN_ONLY_KEYS.contains(&k.as_str()))
+		.filter_map(|k| {
+			let (a, b) = (before.manifest.get(k)?, after.manifest.get(k)?);
+			(a != b).then(|| (k.clone(), a.clone(), b.clone()))
+		})
+		.collect()
+}
+
+/// Resolvers in both runs, in the second run's rank order, as (before, after).
+fn matched_rows<'a>(before: &'a SavedRun, after: &'a SavedRun) -> Vec<(&'a DiffRow, &'a DiffRow)> {
+	let mut pairs: Vec<(&DiffRow, &DiffRow)> = after.rows.iter()
+		.filter_map(|b| before.rows.iter().find(|a| a.key() == b.key()).map(|a| (a, b)))
+		.collect();
+	pairs.sort_by_key(|(_, b)| b.rank.unwrap_or(u32::MAX));
+	pairs
+}
+
+/// Rank change as text: "3 -> 1 (+2)", positive when the resolver moved up.
+fn rank_change(before: Option<u32>, after: Option<u32>) -> String {
+	match (before, after) {
+		(Some(a), Some(b)) if a == b => format!("{}", b),
+		(Some(a), Some(b)) => format!("{} -> {} ({:+})", a, b, a as i64 - b as i64),
+		(a, b) => format!("{} -> {}", a.map(|r| r.to_string()).unwrap_or("-".into()), b.map(|r| r.to_string()).unwrap_or("-".into())),
+	}
+}
+
+//============================================
+/// Compare two saved runs and print setting changes, rank changes, score
+/// deltas, newly failing resolvers, and resolvers in only one run.
+///
+/// Sends no queries. Files are CSV exports (-o) or `--stdout --format json`
+/// output; resolvers are matched by address and transport.
+pub fn run_diff(before_path: &str, after_path: &str) -> Result<()> {
+	let before = load_run(before_path)?;
+	let after = load_run(after_path)?;
+	let when = |run: &SavedRun| {
+		let started = run.manifest.get("started").cloned().unwrap_or_else(|| "unknown time".to_string());
+		match run.manifest.get("label").filter(|l| !l.is_empty()) {
+			Some(label) => format!("{}, network {}", started, label),
+			None => started,
+		}
+	};
+	println!("Benchmark diff");
+	println!("==============");
+	println!("  Before: {} ({})", before_path, when(&before));
+	println!("  After:  {} ({})", after_path, when(&after));
+
+	let changes = setting_changes(&before, &after);
+	if !changes.is_empty() {
+		println!("\nSettings that differ (scores may not be comparable):");
+		for (key, a, b) in &changes {
+			println!("  {:<20} {} -> {}", key, a, b);
+		}
+	}
+
+	let pairs = matched_rows(&before, &after);
+	let mut table = Table::new();
+	table.load_preset(UTF8_FULL);
+	table.set_content_arrangement(ContentArrangement::Dynamic);
+	table.set_header(vec!["Resolver", "IP Address", "Transport", "Rank", "Score before", "Score after", "Delta"]);
+	for (a, b) in &pairs {
+		let score = |row: &DiffRow| row.score.map(|s| format!("{:.1}", s)).unwrap_or_else(|| "-".to_string());
+		let delta = match (a.score, b.score) {
+			(Some(x), Some(y)) if x > 0.0 => {
+				let color = if y < x { Color::Green } else if y > x { Color::Red } else { Color::Reset };
+				Cell::new(format!("{:+.1} ({:+.0}%)", y - x, (y - x) / x * 100.0)).fg(color)
+			}
+			(Some(x), Some(y)) => Cell::new(format!("{:+.1}", y - x)),
+			_ => Cell::new("-"),
+		};
+		table.add_row(vec![
+			Cell::new(&b.label), Cell::new(&b.ip), Cell::new(&b.transport), Cell::new(rank_change(a.rank, b.rank)),
+			Cell::new(score(a)), Cell::new(score(b)), delta,
+		]);
+	}
+	println!();
+	if pairs.is_empty() {
+		println!("No resolver appears in both runs.");
+	} else {
+		println!("{}", table);
+		println!("  Lower scores are better; rank change is positive when a resolver moved up.");
+	}
+
+	let newly_failing: Vec<&DiffRow> = pairs.iter().filter(|(a, b)| b.failing() && !a.failing()).map(|(_, b)| *b).collect();
+	if !newly_failing.is_empty() {
+		println!("\nNewly failing:");
+		for row in newly_failing {
+			let detail = match (row.unresponsive, row.success_pct) {
+				(true, _) => "unresponsive".to_string(),
+				(false, Some(pct)) => format!("{:.1}% answered", pct),
+				(false, None) => "no score".to_string(),
+			};
+			println!("  {} ({}, {}): {}", row.label, row.ip, row.transport, detail);
+		}
+	}
+	let recovered = pairs.iter().filter(|(a, b)| a.failing() && !b.failing()).count();
+	if recovered > 0 {
+		println!("\n{} resolver(s) failing before are answering again.", recovered);
+	}
+	for (title, only, other) in [("Only in before", &before, &after), ("Only in after", &after, &before)] {
+		let missing: Vec<&DiffRow> = only.rows.iter().filter(|r| !other.rows.iter().any(|o| o.key() == r.key())).collect();
+		if !missing.is_empty() {
+			println!("\n{}:", title);
+			for row in missing {
+				println!("  {} ({}, {})", row.label, row.ip, row.transport);
+			}
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const CSV_RUN: &str = "# started: 2026-01-01T00:00:00Z\n# level: quick\n\
+		rank,resolver,ip_address,transport,overall_score,success_rate,unresponsive\n\
+		1,Cloudflare,1.1.1.1,UDP,10.0,100.0,false\n\
+		2,Quad9,9.9.9.9,UDP,20.0,100.0,false\n\
+		3,OldISP,192.0.2.53,UDP,40.0,99.0,false\n";
+
+	#[test]
+	fn test_parse_both_formats() {
+		let csv = parse_csv_run(CSV_RUN).unwrap();
+		assert_eq!(csv.manifest["level"], "quick");
+		assert_eq!(csv.rows.len(), 3);
+		assert_eq!((csv.rows[1].rank, csv.rows[1].score), (Some(2), Some(20.0)));
+
+		let json = r#"{"manifest":{"level":"medium"},"results":[
+			{"rank":1,"resolver":"Quad9","ip_address":"9.9.9.9","transport":"UDP","overall_score":15.5,"success_rate":100.0,"unresponsive":false},
+			{"rank":2,"resolver":"Cloudflare","ip_address":"1.1.1.1","transport":"UDP","overall_score":null,"success_rate":20.0,"unresponsive":true},
+			{"rank":3,"resolver":"OldISP","ip_address":"192.0.2.53","port":5353,"transport":"UDP","overall_score":30.0,"success_rate":100.0,"unresponsive":false}
+		]}"#;
+		let json = parse_json_run(json).unwrap();
+		assert_eq!(json.rows[0].label, "Quad9");
+		assert!(json.rows[1].failing() && !json.rows[0].failing());
+		assert_eq!(setting_changes(&csv, &json), vec![("level".to_string(), "quick".to_string(), "medium".to_string())]);
+
+		// Matched in the second run's order; OldISP on port 53 is only in the
+		// first (files without a port column get the default port)
+		assert_eq!((csv.rows[2].port.as_str(), json.rows[2].port.as_str()), ("53", "5353"));
+		let pairs = matched_rows(&csv, &json);
+		assert_eq!(pairs.iter().map(|(a, b)| (a.rank, b.rank)).collect::<Vec<_>>(), vec![(Some(2), Some(1)), (Some(1), Some(2))]);
+	}
+
+	#[test]
+	fn test_rank_change() {
+		assert_eq!(rank_change(Some(3), Some(1)), "3 -> 1 (+2)");
+		assert_eq!(rank_change(Some(1), Some(4)), "1 -> 4 (-3)");
+		assert_eq!(rank_change(Some(2), Some(2)), "2");
+		assert_eq!(rank_change(None, Some(2)), "- -> 2");
+	}
+}
@@ -3,7 +3,7 @@ use std::time::Duration;
 use anyhow::{anyhow, Result};
 use hickory_proto::op::{Message, MessageType, Query, ResponseCode};
 use hickory_proto::rr::{Name, RecordType};
-use tokio::net::UdpSocket;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
 use crate::transport::QueryType;
 
@@ -13,6 +13,17 @@ pub struct DnsResponse {
 	pub rcode: ResponseCode,
 	/// True if the answer section contains A records
 	pub has_a_records: bool,
+	/// Minimum TTL across answer records; None if the answer section is empty
+	pub min_ttl: Option<u32>,
+}
+
+/// Record type asked for by a benchmark query type.
+fn record_type(query_type: QueryType) -> RecordType {
+	match query_type {
+		QueryType::A => RecordType::A,
+		QueryType::AAAA => RecordType::AAAA,
+		QueryType::HTTPS => RecordType::HTTPS,
+	}
 }
 
 /// Build a DNS query message for the given domain and query type.
@@ -28,15 +39,10 @@ pub fn build_query(
 	let name = Name::from_ascii(domain)
 		.map_err(|e| anyhow!("invalid domain name '{}': {}", domain, e))?;
 
-	let record_type = match query_type {
-		QueryType::A => RecordType::A,
-		QueryType::AAAA => RecordType::AAAA,
-	};
-
 	let mut message = Message::new();
 	message.set_id(txid);
 	message.set_recursion_desired(true);
-	message.add_query(Query::query(name, record_type));
+	message.add_query(Query::query(name, record_type(query_type)));
 
 	// Set DNSSEC OK bit via EDNS when requested
 	if dnssec {
@@ -79,13 +85,36 @@ pub fn parse_response(
 	// Check if any answer records are A records
 	let has_a_records = message.answers().iter()
 		.any(|r| r.record_type() == RecordType::A);
+	// Minimum answer TTL: a cached answer shows a TTL decremented from the original
+	let min_ttl = message.answers().iter().map(|r| r.ttl()).min();
 
 	Ok(DnsResponse {
 		rcode,
 		has_a_records,
+		min_ttl,
 	})
 }
 
+/// Whether a response's question matches the query: same name (ignoring
+/// case and a trailing dot) and record type.
+///
+/// Used where several queries share a socket and the txid alone does not
+/// identify the answer. Responses without a question section pass, since
+/// some error responses omit it.
+pub fn response_matches_question(bytes: &[u8], domain: &str, query_type: QueryType) -> bool {
+	let message = match Message::from_vec(bytes) {
+		Ok(m) => m,
+		Err(_) => return false,
+	};
+	let question = match message.queries().first() {
+		Some(q) => q,
+		None => return true,
+	};
+	let name = question.name().to_ascii();
+	question.query_type() == record_type(query_type)
+		&& name.trim_end_matches('.').eq_ignore_ascii_case(domain.trim_end_matches('.'))
+}
+
 /// Build a DNS PTR query for reverse DNS lookups.
 pub fn build_ptr_query(ptr_domain: &str, txid: u16) -> Result<Vec<u8>> {
 	let name = Name::from_ascii(ptr_domain)
@@ -149,8 +178,7 @@ pub async fn check_rebinding_protection(
 			Err(_) => continue,
 		};
 
-		let bind_addr = if resolver_addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
-		let socket = match UdpSocket::bind(bind_addr).await {
+		let socket = match crate::source::bind_udp(resolver_addr.is_ipv6()).await {
 			Ok(s) => s,
 			Err(_) => continue,
 		};
@@ -237,8 +265,7 @@ pub async fn check_dnssec_validation(
 		Err(_) => return None,
 	};
 
-	let bind_addr = if resolver_addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" };
-	let socket = match UdpSocket::bind(bind_addr).await {
+	let socket = match crate::source::bind_udp(resolver_addr.is_ipv6()).await {
 		Ok(s) => s,
 		Err(_) => return None,
 	};
@@ -272,75 +299,949 @@ pub async fn check_dnssec_validation(
 
 /// Check whether a resolver intercepts NXDOMAIN responses.
 ///
-/// Queries a known-nonexistent domain (.invalid TLD per RFC 2606).
-/// If the resolver returns NoError with A records, it is intercepting.
-/// Returns true if the resolver intercepts NXDOMAIN, false if honest.
+/// Queries every known-nonexistent domain (.invalid TLD per RFC 2606) at
+/// once; a resolver that returns NoError with A records for one is
+/// intercepting. Returns the majority verdict of the probes sent: Some(true)
+/// if most were intercepted, Some(false) if most got an honest reply, and
+/// None (inconclusive) when neither side has a majority, for example when
+/// the replies conflict or most probes got no answer.
 pub async fn check_nxdomain_interception(
 	resolver_addr: std::net::SocketAddr,
 	timeout: Duration,
 	nxdomain_domains: &[String],
-) -> bool {
-	// Query each NXDOMAIN probe domain; if any returns NoError with A records,
-	// the resolver is intercepting
+) -> Option<bool> {
+	let mut in_flight = tokio::task::JoinSet::new();
 	for probe_domain in nxdomain_domains {
-		if check_single_nxdomain(resolver_addr, timeout, probe_domain).await {
-			return true;
-		}
+		let probe_domain = probe_domain.clone();
+		in_flight.spawn(async move { check_single_nxdomain(resolver_addr, timeout, &probe_domain).await });
+	}
+	nxdomain_consensus(&in_flight.join_all().await)
+}
+
+/// Majority verdict of NXDOMAIN probes (Some(true) intercepted, Some(false)
+/// honest, None no answer): a verdict needs more than half of all probes.
+pub fn nxdomain_consensus(votes: &[Option<bool>]) -> Option<bool> {
+	let intercepted = votes.iter().filter(|v| **v == Some(true)).count();
+	let honest = votes.iter().filter(|v| **v == Some(false)).count();
+	match (intercepted * 2 > votes.len(), honest * 2 > votes.len()) {
+		(true, _) => Some(true),
+		(_, true) => Some(false),
+		_ => None,
 	}
-	false
 }
 
 /// Check a single NXDOMAIN probe domain against a resolver.
 ///
-/// Returns true if the resolver intercepts (returns NoError + A records).
+/// Returns Some(true) if the resolver intercepts (returns NoError + A
+/// records), Some(false) for any other reply, and None without a reply.
 async fn check_single_nxdomain(
 	resolver_addr: std::net::SocketAddr,
 	timeout: Duration,
 	probe_domain: &str,
-) -> bool {
+) -> Option<bool> {
 	let txid: u16 = rand::random();
+	let query_bytes = build_query(probe_domain, QueryType::A, txid, false).ok()?;
 
-	let query_bytes = match build_query(probe_domain, QueryType::A, txid, false) {
-		Ok(bytes) => bytes,
-		Err(_) => return false,
+	// Bind a dedicated socket and send the query
+	let socket = crate::source::bind_udp(resolver_addr.is_ipv6()).await.ok()?;
+	socket.send_to(&query_bytes, resolver_addr).await.ok()?;
+
+	// Receive response with timeout
+	let mut buf = vec![0u8; 512];
+	let (len, _src) = tokio::time::timeout(timeout, socket.recv_from(&mut buf)).await.ok()?.ok()?;
+	let response = parse_response(&buf[..len], txid, probe_domain, QueryType::A).ok()?;
+	// Intercepting: NoError with A records for a nonexistent domain
+	Some(response.rcode == ResponseCode::NoError && response.has_a_records)
+}
+
+/// Real, publicly resolvable names that filtering resolvers block:
+/// an ad network and Cloudflare's malware test name
+pub const FILTER_PROBE_DOMAINS: [&str; 2] = ["doubleclick.net", "malware.testcategory.com"];
+/// UDP payload size advertised in the EDNS probe (DNS Flag Day 2020 value)
+const EDNS_PROBE_PAYLOAD: u16 = 1232;
+
+/// Advertised EDNS buffer sizes in the fragmentation probe, smallest first:
+/// classic DNS, the Flag Day 2020 default, an Ethernet-MTU payload, and the
+/// traditional EDNS default that needs IP fragmentation
+pub const EDNS_BUFFER_SIZES: [u16; 4] = [512, 1232, 1452, 4096];
+/// Query whose signed answer is well over 1232 bytes: the root DNSKEY set
+const EDNS_BUFFER_PROBE_NAME: &str = ".";
+
+/// Send one query message over a fresh UDP socket and parse the matching reply.
+async fn udp_exchange(
+	resolver_addr: std::net::SocketAddr,
+	message: &Message,
+	timeout: Duration,
+) -> Option<Message> {
+	udp_exchange_sized(resolver_addr, message, timeout).await.map(|(reply, _)| reply)
+}
+
+/// Like `udp_exchange`, also returning the reply size in bytes.
+async fn udp_exchange_sized(
+	resolver_addr: std::net::SocketAddr,
+	message: &Message,
+	timeout: Duration,
+) -> Option<(Message, usize)> {
+	let query_bytes = message.to_vec().ok()?;
+	let socket = crate::source::bind_udp(resolver_addr.is_ipv6()).await.ok()?;
+	socket.send_to(&query_bytes, resolver_addr).await.ok()?;
+	let mut buf = vec![0u8; 65535];
+	let (len, _) = tokio::time::timeout(timeout, socket.recv_from(&mut buf)).await.ok()?.ok()?;
+	let reply = Message::from_vec(&buf[..len]).ok()?;
+	(reply.id() == message.id()).then_some((reply, len))
+}
+
+/// Send one query message over plain TCP (2-byte length prefix) and parse the reply.
+async fn tcp_exchange(
+	resolver_addr: std::net::SocketAddr,
+	message: &Message,
+	timeout: Duration,
+) -> Option<Message> {
+	tcp_exchange_sized(resolver_addr, message, timeout).await.map(|(reply, _)| reply)
+}
+
+/// Like `tcp_exchange`, also returning the reply size in bytes.
+async fn tcp_exchange_sized(
+	resolver_addr: std::net::SocketAddr,
+	message: &Message,
+	timeout: Duration,
+) -> Option<(Message, usize)> {
+	let query_bytes = message.to_vec().ok()?;
+	let exchange = async {
+		let mut stream = crate::source::connect_tcp(resolver_addr).await?;
+		stream.write_all(&(query_bytes.len() as u16).to_be_bytes()).await?;
+		stream.write_all(&query_bytes).await?;
+		let mut len_buf = [0u8; 2];
+		stream.read_exact(&mut len_buf).await?;
+		let mut buf = vec![0u8; u16::from_be_bytes(len_buf) as usize];
+		stream.read_exact(&mut buf).await?;
+		Ok::<Vec<u8>, std::io::Error>(buf)
 	};
+	let buf = tokio::time::timeout(timeout, exchange).await.ok()?.ok()?;
+	let reply = Message::from_vec(&buf).ok()?;
+	(reply.id() == message.id()).then_some((reply, buf.len()))
+}
 
-	// Bind a dedicated socket
-	let bind_addr = if resolver_addr.is_ipv4() {
-		"0.0.0.0:0"
-	} else {
-		"[::]:0"
+//============================================
+/// Check EDNS(0) support (RFC 6891).
+///
+/// Sends an A query carrying an OPT record. Returns the UDP payload size the
+/// resolver advertises in its reply, Some(0) when it answered without an OPT
+/// record (no EDNS), or None when there was no usable answer.
+pub async fn check_edns_support(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<u16> {
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii("example.com").ok()?, RecordType::A));
+	let mut edns = hickory_proto::op::Edns::new();
+	edns.set_max_payload(EDNS_PROBE_PAYLOAD);
+	message.set_edns(edns);
+
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	// FORMERR means the resolver rejected the OPT record outright
+	if reply.response_code() == ResponseCode::FormErr {
+		return Some(0);
+	}
+	Some(reply.extensions().as_ref().map(|e| e.max_payload()).unwrap_or(0))
+}
+
+/// Root DNSKEY query with the DO bit, advertising `payload` bytes over UDP.
+fn edns_buffer_query(payload: u16) -> Option<Message> {
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii(EDNS_BUFFER_PROBE_NAME).ok()?, RecordType::DNSKEY));
+	let mut edns = hickory_proto::op::Edns::new();
+	edns.set_max_payload(payload);
+	edns.set_dnssec_ok(true);
+	message.set_edns(edns);
+	Some(message)
+}
+
+/// Classify a reply to the large query at one buffer size.
+fn buffer_outcome(reply: Option<(Message, usize)>) -> crate::record::BufferOutcome {
+	use crate::record::BufferOutcome;
+	match reply {
+		Some((message, _)) if message.truncated() => BufferOutcome::Truncated,
+		Some((_, bytes)) => BufferOutcome::Complete(bytes),
+		None => BufferOutcome::NoAnswer,
+	}
+}
+
+//============================================
+/// Probe the resolver's effective UDP payload limit and TC/TCP fallback.
+///
+/// Sends the signed root DNSKEY query once per EDNS_BUFFER_SIZES entry,
+/// then once over TCP. None when the resolver answered none of them.
+pub async fn check_edns_buffer(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<crate::record::EdnsBufferResult> {
+	let mut sizes = Vec::new();
+	for payload in EDNS_BUFFER_SIZES {
+		let message = edns_buffer_query(payload)?;
+		let reply = udp_exchange_sized(resolver_addr, &message, timeout).await;
+		sizes.push((payload, buffer_outcome(reply)));
+	}
+	let tcp_ok = tcp_exchange(resolver_addr, &edns_buffer_query(EDNS_BUFFER_SIZES[0])?, timeout).await
+		.is_some_and(|reply| !reply.truncated() && reply.response_code() == ResponseCode::NoError);
+	let result = crate::record::EdnsBufferResult { sizes, tcp_ok };
+	let answered = result.tcp_ok || result.sizes.iter().any(|(_, o)| *o != crate::record::BufferOutcome::NoAnswer);
+	answered.then_some(result)
+}
+
+/// Large answers asked for by the large response probe, as (label, name,
+/// type): the signed root DNSKEY set and a domain with dozens of TXT
+/// verification records, both well over 1232 bytes. ANY is left out:
+/// resolvers may answer it with a single synthetic record (RFC 8482).
+pub const LARGE_RESPONSE_PROBES: [(&str, &str, RecordType); 2] = [
+	("dnskey", EDNS_BUFFER_PROBE_NAME, RecordType::DNSKEY),
+	("txt", "microsoft.com", RecordType::TXT),
+];
+
+/// Recursive query for `name`/`record_type` with the DO bit, advertising
+/// the Flag Day 2020 buffer size.
+fn large_response_query(name: &str, record_type: RecordType) -> Option<Message> {
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii(name).ok()?, record_type));
+	let mut edns = hickory_proto::op::Edns::new();
+	edns.set_max_payload(EDNS_PROBE_PAYLOAD);
+	edns.set_dnssec_ok(true);
+	message.set_edns(edns);
+	Some(message)
+}
+
+/// Whether a reply carries a complete, non-empty answer.
+fn is_full_answer(reply: &Message) -> bool {
+	!reply.truncated() && reply.response_code() == ResponseCode::NoError && !reply.answers().is_empty()
+}
+
+/// Classify a large query from its UDP reply and the size of the full TCP
+/// answer, None when TCP was not tried or gave no full answer.
+fn classify_large_response(udp: Option<(&Message, usize)>, tcp_bytes: Option<usize>) -> crate::record::LargeResponseOutcome {
+	use crate::record::LargeResponseOutcome;
+	match (udp, tcp_bytes) {
+		(Some((reply, bytes)), _) if is_full_answer(reply) => LargeResponseOutcome::Udp(bytes),
+		(Some((reply, _)), Some(bytes)) if reply.truncated() => LargeResponseOutcome::TcpFallback(bytes),
+		(Some((reply, _)), None) if reply.truncated() => LargeResponseOutcome::NoTcp,
+		(None, Some(_)) => LargeResponseOutcome::UdpLost,
+		_ => LargeResponseOutcome::Failed,
+	}
+}
+
+//============================================
+/// Probe how the resolver hands over large answers.
+///
+/// Asks for each LARGE_RESPONSE_PROBES entry over UDP, and over TCP when the
+/// UDP reply was truncated or never came. None when the resolver answered
+/// none of them over either transport.
+pub async fn check_large_responses(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<crate::record::LargeResponseResult> {
+	let mut probes = Vec::new();
+	let mut answered = false;
+	for (label, name, record_type) in LARGE_RESPONSE_PROBES {
+		let udp = udp_exchange_sized(resolver_addr, &large_response_query(name, record_type)?, timeout).await;
+		let needs_tcp = udp.as_ref().is_none_or(|(reply, _)| reply.truncated());
+		let tcp = if needs_tcp {
+			tcp_exchange_sized(resolver_addr, &large_response_query(name, record_type)?, timeout).await
+		} else {
+			None
+		};
+		answered |= udp.is_some() || tcp.is_some();
+		let tcp_bytes = tcp.filter(|(reply, _)| is_full_answer(reply)).map(|(_, bytes)| bytes);
+		probes.push((label, classify_large_response(udp.as_ref().map(|(m, n)| (m, *n)), tcp_bytes)));
+	}
+	answered.then_some(crate::record::LargeResponseResult { probes })
+}
+
+/// Resolve the A records of `domain` on one resolver over UDP.
+///
+/// Returns the addresses sorted, an empty list for NXDOMAIN or an answer
+/// without A records, and None when there was no answer or the resolver
+/// failed (SERVFAIL, REFUSED).
+pub async fn resolve_a(
+	resolver_addr: std::net::SocketAddr,
+	domain: &str,
+	timeout: Duration,
+) -> Option<Vec<std::net::Ipv4Addr>> {
+	use hickory_proto::rr::RData;
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii(domain).ok()?, RecordType::A));
+
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	match reply.response_code() {
+		ResponseCode::NoError | ResponseCode::NXDomain => {}
+		_ => return None,
+	}
+	let mut addrs: Vec<std::net::Ipv4Addr> = reply.answers().iter()
+		.filter_map(|r| match r.data() {
+			RData::A(a) => Some(a.0),
+			_ => None,
+		})
+		.collect();
+	addrs.sort();
+	addrs.dedup();
+	Some(addrs)
+}
+
+/// Domain queried by the 0x20 probe; long enough for plenty of case bits
+const CASE_PROBE_DOMAIN: &str = "www.example.com";
+
+/// Randomize the letter case of `domain`, one coin flip per letter.
+///
+/// At least one letter is uppercased, so a resolver that lowercases names
+/// can never pass by luck.
+fn randomize_case(domain: &str, mut flip: impl FnMut() -> bool) -> String {
+	let mut mixed: String = domain.chars()
+		.map(|c| if flip() { c.to_ascii_uppercase() } else { c.to_ascii_lowercase() })
+		.collect();
+	if !mixed.chars().any(|c| c.is_ascii_uppercase()) {
+		if let Some(i) = mixed.find(|c: char| c.is_ascii_alphabetic()) {
+			mixed.replace_range(i..i + 1, &mixed[i..i + 1].to_ascii_uppercase());
+		}
+	}
+	mixed
+}
+
+//============================================
+/// Check 0x20 case handling: does the reply echo a mixed-case QNAME exactly?
+///
+/// Resolvers that implement 0x20 anti-spoofing (draft-vixie-dnsext-dns0x20)
+/// must keep the case intact end to end. Some(false) when the question comes
+/// back with different case, None when there was no usable answer.
+pub async fn check_case_preservation(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<bool> {
+	// Fully qualified, so it compares equal to the name decoded from the reply
+	let name = Name::from_ascii(format!("{}.", randomize_case(CASE_PROBE_DOMAIN, rand::random))).ok()?;
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(name.clone(), RecordType::A));
+
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	let echoed = reply.queries().first()?.name();
+	Some(echoed.eq_case(&name))
+}
+
+/// Whether a COOKIE option from the server echoes our client cookie and
+/// adds a server cookie (8-32 bytes, RFC 7873 section 4).
+fn has_server_cookie(client: &[u8], option: &[u8]) -> bool {
+	option.len() >= client.len() + 8
+		&& option.len() <= client.len() + 32
+		&& option.starts_with(client)
+}
+
+//============================================
+/// Check DNS Cookie support (RFC 7873).
+///
+/// Sends an A query carrying a random 8-byte client cookie. Some(true) when
+/// the reply returns it with a server cookie, Some(false) when the reply
+/// has none, None when there was no usable answer. Cookies let a resolver
+/// and client reject off-path spoofed packets.
+pub async fn check_cookie_support(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<bool> {
+	use hickory_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+	let client: [u8; 8] = rand::random();
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii("example.com").ok()?, RecordType::A));
+	let mut edns = hickory_proto::op::Edns::new();
+	edns.set_max_payload(EDNS_PROBE_PAYLOAD);
+	edns.options_mut().insert(EdnsOption::Unknown(u16::from(EdnsCode::Cookie), client.to_vec()));
+	message.set_edns(edns);
+
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	let option = reply.extensions().as_ref()
+		.and_then(|e| e.options().get(EdnsCode::Cookie))
+		.and_then(|o| Vec::<u8>::try_from(o).ok());
+	Some(option.is_some_and(|bytes| has_server_cookie(&client, &bytes)))
+}
+
+/// Address of a.root-servers.net: an authoritative-only server that never
+/// answers recursive queries, used to detect port 53 interception
+pub const INTERCEPT_PROBE_ADDR: std::net::SocketAddr = std::net::SocketAddr::new(
+	std::net::IpAddr::V4(std::net::Ipv4Addr::new(198, 41, 0, 4)), 53,
+);
+
+/// Whether every NS target is one of the 13 root servers `a`-`m.root-servers.net`.
+fn is_root_ns_set(targets: &[String]) -> bool {
+	!targets.is_empty() && targets.iter().all(|t| {
+		let t = t.to_ascii_lowercase();
+		let label = t.strip_suffix(".root-servers.net.").unwrap_or("");
+		label.len() == 1 && ("a"..="m").contains(&label)
+	})
+}
+
+//============================================
+/// Check that a resolver returns a record whose content is known in advance:
+/// the root zone NS set (`a` through `m.root-servers.net`).
+///
+/// Captive portals and hijacking middleboxes answer with their own data or
+/// fail the query; a genuine recursive resolver always has this set.
+/// Some(false) on any other answer or rcode, None when there was no reply.
+pub async fn check_known_record(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<bool> {
+	use hickory_proto::rr::RData;
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::root(), RecordType::NS));
+
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	if reply.response_code() != ResponseCode::NoError {
+		return Some(false);
+	}
+	let targets: Vec<String> = reply.answers().iter()
+		.filter_map(|r| match r.data() {
+			RData::NS(ns) => Some(ns.0.to_ascii()),
+			_ => None,
+		})
+		.collect();
+	Some(is_root_ns_set(&targets))
+}
+
+/// Check whether something on the path intercepts port 53 traffic.
+///
+/// Sends a recursive query for `example.com` to a.root-servers.net, which
+/// only ever returns a referral. A reply that offers recursion or carries
+/// an answer came from a transparent proxy. Some(false) for a genuine
+/// referral, None when nothing answered (port 53 may be blocked).
+pub async fn check_port53_interception(timeout: Duration) -> Option<bool> {
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii("example.com.").ok()?, RecordType::A));
+
+	let reply = udp_exchange(INTERCEPT_PROBE_ADDR, &message, timeout).await?;
+	Some(reply.recursion_available() || !reply.answers().is_empty())
+}
+
+/// Whether a reply looks like a blocklist answer rather than a real one.
+///
+/// Filtering resolvers answer NXDOMAIN or REFUSED, an empty NoError, or a
+/// sinkhole address (0.0.0.0 or loopback).
+fn is_blocked_reply(message: &Message) -> bool {
+	match message.response_code() {
+		ResponseCode::NXDomain | ResponseCode::Refused => return true,
+		ResponseCode::NoError => {}
+		_ => return false,
+	}
+	let addrs: Vec<std::net::Ipv4Addr> = message.answers().iter()
+		.filter(|r| r.record_type() == RecordType::A)
+		.filter_map(|r| r.data().to_string().parse().ok())
+		.collect();
+	addrs.is_empty() || addrs.iter().all(|ip| ip.is_unspecified() || ip.is_loopback())
+}
+
+/// Check whether a resolver filters ad or malware domains.
+///
+/// Queries each of FILTER_PROBE_DOMAINS; any blocklist-style answer (see
+/// `is_blocked_reply`) means the resolver filters. None when no probe was
+/// answered.
+pub async fn check_filtering(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<bool> {
+	let mut answered = false;
+	for domain in FILTER_PROBE_DOMAINS {
+		let mut message = Message::new();
+		message.set_id(rand::random());
+		message.set_recursion_desired(true);
+		message.add_query(Query::query(Name::from_ascii(domain).ok()?, RecordType::A));
+		if let Some(reply) = udp_exchange(resolver_addr, &message, timeout).await {
+			if is_blocked_reply(&reply) {
+				return Some(true);
+			}
+			answered = true;
+		}
+	}
+	answered.then_some(false)
+}
+
+//============================================
+/// Name likely in every busy resolver's cache, for the RD=0 probe
+const RD_ZERO_CACHED_DOMAIN: &str = "www.google.com";
+/// Wildcard zone that answers any label under it with the embedded
+/// address; a fresh label here cannot be cached anywhere
+const RD_ZERO_WILDCARD_ZONE: &str = "1.1.1.1.sslip.io";
+
+/// Classify RD=0 replies for a popular name and for a fresh, never-cached name.
+///
+/// An answer for the fresh name can only come from recursion. Otherwise an
+/// answer for the popular name came from the cache.
+fn classify_rd_zero(popular: Option<&Message>, fresh: Option<&Message>) -> Option<crate::record::RdZeroBehavior> {
+	use crate::record::RdZeroBehavior;
+	let answered = |m: &Message| m.response_code() == ResponseCode::NoError
+		&& m.answers().iter().any(|r| r.record_type() == RecordType::A);
+	if fresh.is_some_and(answered) {
+		return Some(RdZeroBehavior::Recurses);
+	}
+	let popular = popular?;
+	Some(match popular.response_code() {
+		_ if answered(popular) => RdZeroBehavior::FromCache,
+		ResponseCode::Refused | ResponseCode::ServFail => RdZeroBehavior::Refused,
+		_ => RdZeroBehavior::NoAnswer,
+	})
+}
+
+/// Check how a resolver handles queries with recursion-desired cleared.
+///
+/// Asks for a popular name and for a random label under a wildcard zone,
+/// both with RD=0. None when neither query got a reply.
+pub async fn check_rd_zero(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<crate::record::RdZeroBehavior> {
+	let ask = |domain: String| async move {
+		let mut message = Message::new();
+		message.set_id(rand::random());
+		message.set_recursion_desired(false);
+		message.add_query(Query::query(Name::from_ascii(domain).ok()?, RecordType::A));
+		udp_exchange(resolver_addr, &message, timeout).await
 	};
-	let socket = match UdpSocket::bind(bind_addr).await {
-		Ok(s) => s,
-		Err(_) => return false,
+	let popular = ask(RD_ZERO_CACHED_DOMAIN.to_string()).await;
+	let fresh = ask(format!("dnsb-{:016x}.{}", rand::random::<u64>(), RD_ZERO_WILDCARD_ZONE)).await;
+	classify_rd_zero(popular.as_ref(), fresh.as_ref())
+}
+
+//============================================
+/// Block size padded queries are rounded up to (RFC 8467 section 4.1)
+pub const PADDING_QUERY_BLOCK: usize = 128;
+
+/// Build an A query whose EDNS Padding option (RFC 7830) rounds the whole
+/// message up to a multiple of `block` bytes.
+///
+/// RFC 8467 servers pad a response only when the query was padded, so the
+/// padding probe must send one.
+pub fn build_padded_query(domain: &str, txid: u16, block: usize) -> Result<Vec<u8>> {
+	use hickory_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+	let name = Name::from_ascii(domain)
+		.map_err(|e| anyhow!("invalid domain name '{}': {}", domain, e))?;
+	let padded = |len: usize| {
+		let mut message = Message::new();
+		message.set_id(txid);
+		message.set_recursion_desired(true);
+		message.add_query(Query::query(name.clone(), RecordType::A));
+		let mut edns = hickory_proto::op::Edns::new();
+		edns.set_max_payload(EDNS_PROBE_PAYLOAD);
+		edns.options_mut().insert(EdnsOption::Unknown(u16::from(EdnsCode::Padding), vec![0; len]));
+		message.set_edns(edns);
+		message.to_vec().map_err(|e| anyhow!("failed to serialize DNS query: {}", e))
 	};
+	// An empty option still adds its 4-byte header; fill up to the next block
+	let unpadded = padded(0)?.len();
+	padded((block - unpadded % block) % block)
+}
 
-	// Send query
-	if socket.send_to(&query_bytes, resolver_addr).await.is_err() {
-		return false;
+/// Whether a response carries an EDNS Padding option; None when it does
+/// not parse.
+pub fn has_padding(bytes: &[u8]) -> Option<bool> {
+	use hickory_proto::rr::rdata::opt::EdnsCode;
+	let message = Message::from_vec(bytes).ok()?;
+	Some(message.extensions().as_ref().is_some_and(|e| e.options().get(EdnsCode::Padding).is_some()))
+}
+
+//============================================
+/// Name with only A records, 192.0.0.170 and 192.0.0.171 (RFC 7050)
+pub const DNS64_PROBE_DOMAIN: &str = "ipv4only.arpa";
+/// The A records of DNS64_PROBE_DOMAIN
+const DNS64_WELL_KNOWN_V4: [[u8; 4]; 2] = [[192, 0, 0, 170], [192, 0, 0, 171]];
+
+/// NAT64 prefix and length of an address that embeds `v4` at one of the
+/// RFC 6052 positions. Bits 64-71 are zero in every format but /96.
+fn nat64_prefix(addr: std::net::Ipv6Addr, v4: [u8; 4]) -> Option<(std::net::Ipv6Addr, u8)> {
+	let bytes = addr.octets();
+	// Byte offsets of the four IPv4 bytes for each prefix length
+	const LAYOUTS: [(u8, [usize; 4]); 6] = [
+		(96, [12, 13, 14, 15]),
+		(64, [9, 10, 11, 12]),
+		(56, [7, 9, 10, 11]),
+		(48, [6, 7, 9, 10]),
+		(40, [5, 6, 7, 9]),
+		(32, [4, 5, 6, 7]),
+	];
+	LAYOUTS.iter()
+		.find(|(len, offsets)| offsets.iter().zip(v4).all(|(&i, b)| bytes[i] == b) && (*len == 96 || bytes[8] == 0))
+		.map(|&(len, _)| {
+			let mut prefix = [0u8; 16];
+			prefix[..len as usize / 8].copy_from_slice(&bytes[..len as usize / 8]);
+			(std::net::Ipv6Addr::from(prefix), len)
+		})
+}
+
+/// Classify a reply to the AAAA query for DNS64_PROBE_DOMAIN; None for an
+/// error reply, which says nothing either way.
+fn classify_dns64(reply: &Message) -> Option<crate::record::Dns64Behavior> {
+	use crate::record::Dns64Behavior;
+	use hickory_proto::rr::RData;
+	if reply.response_code() != ResponseCode::NoError {
+		return None;
 	}
+	let synthesized = reply.answers().iter()
+		.filter_map(|r| match r.data() {
+			RData::AAAA(aaaa) => Some(aaaa.0),
+			_ => None,
+		})
+		.find_map(|addr| DNS64_WELL_KNOWN_V4.iter().find_map(|&v4| nat64_prefix(addr, v4)));
+	Some(match synthesized {
+		Some((prefix, len)) => Dns64Behavior::Synthesizes(prefix, len),
+		None => Dns64Behavior::Off,
+	})
+}
 
-	// Receive response with timeout
-	let mut buf = vec![0u8; 512];
-	match tokio::time::timeout(timeout, socket.recv_from(&mut buf)).await {
-		Ok(Ok((len, _src))) => {
-			match parse_response(&buf[..len], txid, probe_domain, QueryType::A) {
-				Ok(response) => {
-					// Intercepting: NoError with A records for a nonexistent domain
-					response.rcode == ResponseCode::NoError && response.has_a_records
-				}
-				Err(_) => false,
-			}
+/// Check whether a resolver performs DNS64: asks for the AAAA record of
+/// an IPv4-only name and looks for the IPv4 address inside the answer.
+/// None when there was no reply or an error reply.
+pub async fn check_dns64(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<crate::record::Dns64Behavior> {
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii(DNS64_PROBE_DOMAIN).ok()?, RecordType::AAAA));
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	classify_dns64(&reply)
+}
+
+//============================================
+/// Name with a short authoritative TTL (the NTP pool rotates its addresses
+/// every couple of minutes)
+pub const TTL_PROBE_SHORT: &str = "pool.ntp.org";
+/// Name with a very long authoritative TTL (3600000 s, about 41 days)
+pub const TTL_PROBE_LONG: &str = "a.root-servers.net";
+/// A long-name TTL returned at or below this (7 days) is a cap, not cache age
+pub const TTL_CAP_LIMIT: u32 = 604_800;
+/// Zone levels walked up looking for a name's NS records
+const MAX_ZONE_WALK: usize = 4;
+
+/// Smallest TTL of the A records in a reply.
+fn a_record_ttl(reply: &Message) -> Option<u32> {
+	use hickory_proto::rr::RData;
+	reply.answers().iter()
+		.filter(|r| matches!(r.data(), RData::A(_)))
+		.map(|r| r.ttl())
+		.min()
+}
+
+/// A query for `name` with recursion desired or not; the reply when it came.
+async fn query_type(addr: std::net::SocketAddr, name: &Name, record_type: RecordType, recurse: bool, timeout: Duration) -> Option<Message> {
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(recurse);
+	message.add_query(Query::query(name.clone(), record_type));
+	udp_exchange(addr, &message, timeout).await
+}
+
+/// TTL of a name's A records as its zone's name server publishes it.
+///
+/// Looks up the zone's NS records through `resolver_addr` (walking up from
+/// the name to its apex), resolves the first name server, and asks it
+/// directly without recursion. None when any step gets no answer.
+pub async fn authoritative_ttl(resolver_addr: std::net::SocketAddr, name: &str, timeout: Duration) -> Option<u32> {
+	use hickory_proto::rr::RData;
+	let name = Name::from_ascii(name).ok()?;
+	let mut zone = name.clone();
+	let mut server = None;
+	for _ in 0..MAX_ZONE_WALK {
+		let reply = query_type(resolver_addr, &zone, RecordType::NS, true, timeout).await?;
+		server = reply.answers().iter().find_map(|r| match r.data() {
+			RData::NS(ns) => Some(ns.0.clone()),
+			_ => None,
+		});
+		if server.is_some() || zone.num_labels() <= 1 {
+			break;
+		}
+		zone = zone.base_name();
+	}
+	let reply = query_type(resolver_addr, &server?, RecordType::A, true, timeout).await?;
+	let server_ip = reply.answers().iter().find_map(|r| match r.data() {
+		RData::A(a) => Some(std::net::IpAddr::V4(a.0)),
+		_ => None,
+	})?;
+	let reply = query_type(std::net::SocketAddr::new(server_ip, 53), &name, RecordType::A, false, timeout).await?;
+	a_record_ttl(&reply)
+}
+
+/// Authoritative TTLs of TTL_PROBE_SHORT and TTL_PROBE_LONG, looked up
+/// through the first of `resolvers` that gets each one.
+pub async fn ttl_references(resolvers: &[std::net::SocketAddr], timeout: Duration) -> (Option<u32>, Option<u32>) {
+	let mut found = (None, None);
+	for addr in resolvers.iter().take(3) {
+		if found.0.is_none() {
+			found.0 = authoritative_ttl(*addr, TTL_PROBE_SHORT, timeout).await;
+		}
+		if found.1.is_none() {
+			found.1 = authoritative_ttl(*addr, TTL_PROBE_LONG, timeout).await;
+		}
+		if found.0.is_some() && found.1.is_some() {
+			break;
 		}
-		_ => false,
+	}
+	found
+}
+
+/// Compare the TTLs a resolver returns for the probe names against the
+/// authoritative ones. None when neither name got an answer.
+pub async fn check_ttl_rewrite(
+	resolver_addr: std::net::SocketAddr,
+	references: (Option<u32>, Option<u32>),
+	timeout: Duration,
+) -> Option<crate::record::TtlRewriteResult> {
+	use crate::record::{TtlCheck, TtlRewriteResult};
+	let mut result = TtlRewriteResult::default();
+	for (name, authoritative, slot) in [(TTL_PROBE_SHORT, references.0, &mut result.short), (TTL_PROBE_LONG, references.1, &mut result.long)] {
+		let Some(authoritative) = authoritative else { continue };
+		let reply = query_type(resolver_addr, &Name::from_ascii(name).ok()?, RecordType::A, true, timeout).await;
+		*slot = reply.as_ref().and_then(a_record_ttl).map(|returned| TtlCheck { authoritative, returned });
+	}
+	(result.short.is_some() || result.long.is_some()).then_some(result)
+}
+
+//============================================
+/// Identical queries sent at once by the coalescing probe
+pub const COALESCE_QUERIES: usize = 8;
+/// Answers this close together (ms) count as coalesced even when the
+/// median answer time is short
+pub const COALESCE_SPREAD_MS: f64 = 2.0;
+/// Zone the coalescing probe draws its fresh name from without --cold-zone;
+/// a random .com label is NXDOMAIN, so the resolver has to ask the .com servers
+const COALESCE_DEFAULT_ZONE: &str = "com";
+
+/// Send COALESCE_QUERIES identical A queries for a fresh name at once and
+/// time each answer, to see whether the resolver coalesces duplicates.
+///
+/// The name is drawn under `cold_zone` (or COALESCE_DEFAULT_ZONE) so no
+/// cache holds it. Each copy has its own socket and message ID, as from
+/// separate clients. None when no copy was answered.
+pub async fn check_coalescing(
+	resolver_addr: std::net::SocketAddr,
+	cold_zone: Option<&str>,
+	timeout: Duration,
+) -> Option<crate::record::CoalescingResult> {
+	let name = crate::domains::cold_name(
+		cold_zone.unwrap_or(COALESCE_DEFAULT_ZONE), crate::domains::cold_nonce(), &mut rand::thread_rng(),
+	);
+	let name = Name::from_ascii(&name).ok()?;
+	let mut in_flight = tokio::task::JoinSet::new();
+	for _ in 0..COALESCE_QUERIES {
+		let mut message = Message::new();
+		message.set_id(rand::random());
+		message.set_recursion_desired(true);
+		message.add_query(Query::query(name.clone(), RecordType::A));
+		in_flight.spawn(async move {
+			let start = std::time::Instant::now();
+			udp_exchange(resolver_addr, &message, timeout).await
+				.map(|_| start.elapsed().as_secs_f64() * 1000.0)
+		});
+	}
+	let mut latencies_ms: Vec<f64> = in_flight.join_all().await.into_iter().flatten().collect();
+	latencies_ms.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	(!latencies_ms.is_empty()).then_some(crate::record::CoalescingResult { sent: COALESCE_QUERIES, latencies_ms })
+}
+
+//============================================
+/// Name whose HTTPS record carries an ECH config (Cloudflare's ECH test host)
+pub const HTTPS_RR_PROBE_DOMAIN: &str = "crypto.cloudflare.com";
+
+/// Classify a reply to the HTTPS query for HTTPS_RR_PROBE_DOMAIN.
+fn classify_https_rr(reply: &Message) -> crate::record::HttpsRrBehavior {
+	use crate::record::HttpsRrBehavior;
+	use hickory_proto::rr::rdata::svcb::SvcParamKey;
+	use hickory_proto::rr::RData;
+	let records: Vec<_> = reply.answers().iter()
+		.filter_map(|r| match r.data() {
+			RData::HTTPS(https) => Some(https),
+			_ => None,
+		})
+		.collect();
+	if records.is_empty() {
+		HttpsRrBehavior::Stripped
+	} else if records.iter().any(|h| h.svc_params().iter().any(|(key, _)| *key == SvcParamKey::EchConfigList)) {
+		HttpsRrBehavior::Intact
+	} else {
+		HttpsRrBehavior::NoEch
 	}
 }
 
+/// Check whether a resolver passes HTTPS (type 65) records through intact.
+///
+/// Asks for the HTTPS record of a name known to publish one with an `ech`
+/// parameter. Browsers need that parameter for Encrypted Client Hello, and
+/// some filtering resolvers drop the record to block ECH. None when there
+/// was no reply.
+pub async fn check_https_rr(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<crate::record::HttpsRrBehavior> {
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii(HTTPS_RR_PROBE_DOMAIN).ok()?, RecordType::HTTPS));
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	Some(classify_https_rr(&reply))
+}
+
+//============================================
+/// Render an identifier payload: text when it is printable ASCII, else hex.
+fn identity_text(bytes: &[u8]) -> Option<String> {
+	if bytes.is_empty() {
+		return None;
+	}
+	if bytes.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+		return Some(String::from_utf8_lossy(bytes).trim().to_string());
+	}
+	Some(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Ask for the resolver's name server identifier (EDNS NSID, RFC 5001).
+///
+/// Anycast operators put the answering site or host here. None when the
+/// resolver did not answer or returned no NSID option.
+pub async fn query_nsid(
+	resolver_addr: std::net::SocketAddr,
+	timeout: Duration,
+) -> Option<String> {
+	use hickory_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.set_recursion_desired(true);
+	message.add_query(Query::query(Name::from_ascii("example.com").ok()?, RecordType::A));
+	let mut edns = hickory_proto::op::Edns::new();
+	edns.set_max_payload(EDNS_PROBE_PAYLOAD);
+	edns.options_mut().insert(EdnsOption::Unknown(u16::from(EdnsCode::NSID), Vec::new()));
+	message.set_edns(edns);
+
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	let option = reply.extensions().as_ref()?.options().get(EdnsCode::NSID)?;
+	identity_text(&Vec::<u8>::try_from(option).ok()?)
+}
+
+/// Query a CHAOS-class TXT identity name (`id.server` or `hostname.bind`).
+///
+/// Returns the first TXT string of the answer; None on no answer, REFUSED,
+/// or an empty answer.
+pub async fn query_chaos_txt(
+	resolver_addr: std::net::SocketAddr,
+	name: &str,
+	timeout: Duration,
+) -> Option<String> {
+	use hickory_proto::rr::{DNSClass, RData};
+	let mut query = Query::query(Name::from_ascii(name).ok()?, RecordType::TXT);
+	query.set_query_class(DNSClass::CH);
+	let mut message = Message::new();
+	message.set_id(rand::random());
+	message.add_query(query);
+
+	let reply = udp_exchange(resolver_addr, &message, timeout).await?;
+	if reply.response_code() != ResponseCode::NoError {
+		return None;
+	}
+	reply.answers().iter().find_map(|r| match r.data() {
+		RData::TXT(txt) => txt.txt_data().first().and_then(|s| identity_text(s)),
+		_ => None,
+	})
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn test_nxdomain_consensus() {
+		let (t, f) = (Some(true), Some(false));
+		assert_eq!(nxdomain_consensus(&[t, t, f]), Some(true));
+		assert_eq!(nxdomain_consensus(&[f, None, f]), Some(false));
+		// One dropped reply no longer decides the verdict
+		assert_eq!(nxdomain_consensus(&[t, f, f, f, None]), Some(false));
+		assert_eq!(nxdomain_consensus(&[t, t, f, f]), None);
+		assert_eq!(nxdomain_consensus(&[None, None, f]), None);
+		assert_eq!(nxdomain_consensus(&[]), None);
+	}
+
+	#[test]
+	fn test_identity_text() {
+		assert_eq!(identity_text(b"fra08"), Some("fra08".to_string()));
+		assert_eq!(identity_text(&[0x01, 0xab]), Some("01ab".to_string()));
+		assert_eq!(identity_text(b""), None);
+	}
+
+	#[test]
+	fn test_is_root_ns_set() {
+		let set = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+		assert!(is_root_ns_set(&set(&["a.root-servers.net.", "M.ROOT-SERVERS.NET."])));
+		assert!(!is_root_ns_set(&set(&["a.root-servers.net.", "n.root-servers.net."])));
+		assert!(!is_root_ns_set(&set(&["ns1.captive.example."])));
+		assert!(!is_root_ns_set(&[]));
+	}
+
+	#[test]
+	fn test_randomize_case() {
+		let mut toggle = false;
+		let mixed = randomize_case("example.com", || { toggle = !toggle; toggle });
+		assert_eq!(mixed, "ExAmPlE.CoM");
+		assert!(mixed.eq_ignore_ascii_case("example.com"));
+		// All-lowercase rolls still get one uppercase letter
+		assert_eq!(randomize_case("example.com", || false), "Example.com");
+
+		// Case survives a wire round trip and is compared exactly
+		let sent = Name::from_ascii(format!("{}.", mixed)).unwrap();
+		let mut message = Message::new();
+		message.add_query(Query::query(sent.clone(), RecordType::A));
+		let decoded = Message::from_vec(&message.to_vec().unwrap()).unwrap();
+		assert!(decoded.queries()[0].name().eq_case(&sent));
+		assert!(!decoded.queries()[0].name().eq_case(&Name::from_ascii("example.com.").unwrap()));
+	}
+
+	#[test]
+	fn test_has_server_cookie() {
+		let client = [1u8, 2, 3, 4, 5, 6, 7, 8];
+		let mut option = client.to_vec();
+		// Client cookie echoed alone: no server cookie
+		assert!(!has_server_cookie(&client, &option));
+		option.extend_from_slice(&[9u8; 16]);
+		assert!(has_server_cookie(&client, &option));
+		// A different client cookie means the reply is not for us
+		assert!(!has_server_cookie(&[0u8; 8], &option));
+		option.extend_from_slice(&[9u8; 32]);
+		assert!(!has_server_cookie(&client, &option));
+	}
+
+	#[test]
+	fn test_is_blocked_reply() {
+		use hickory_proto::rr::{rdata::A, RData, Record};
+		let reply = |rcode: ResponseCode, ip: Option<[u8; 4]>| {
+			let mut m = Message::new();
+			m.set_message_type(MessageType::Response);
+			m.set_response_code(rcode);
+			if let Some(ip) = ip {
+				m.add_answer(Record::from_rdata(Name::from_ascii("doubleclick.net.").unwrap(), 60, RData::A(A::from(std::net::Ipv4Addr::from(ip)))));
+			}
+			m
+		};
+		assert!(is_blocked_reply(&reply(ResponseCode::NXDomain, None)));
+		assert!(is_blocked_reply(&reply(ResponseCode::NoError, None)));
+		assert!(is_blocked_reply(&reply(ResponseCode::NoError, Some([0, 0, 0, 0]))));
+		assert!(!is_blocked_reply(&reply(ResponseCode::NoError, Some([142, 250, 1, 1]))));
+		assert!(!is_blocked_reply(&reply(ResponseCode::ServFail, None)));
+	}
+
 	#[test]
 	fn test_build_a_query() {
 		let result = build_query("example.com", QueryType::A, 1234, false);
@@ -378,6 +1279,14 @@ mod tests {
 		assert!(message.extensions().is_some(), "EDNS extension should be present");
 	}
 
+	#[test]
+	fn test_response_matches_question() {
+		let bytes = build_query("Example.COM", QueryType::A, 1, false).unwrap();
+		assert!(response_matches_question(&bytes, "example.com.", QueryType::A));
+		assert!(!response_matches_question(&bytes, "example.com", QueryType::AAAA));
+		assert!(!response_matches_question(&bytes, "example.org", QueryType::A));
+	}
+
 	#[test]
 	fn test_parse_valid_response() {
 		// Build a query, then turn it into a response
@@ -391,6 +1300,24 @@ mod tests {
 		let dns_resp = result.unwrap();
 		assert_eq!(dns_resp.rcode, ResponseCode::NoError);
 		assert!(!dns_resp.has_a_records);
+		assert_eq!(dns_resp.min_ttl, None);
+	}
+
+	#[test]
+	fn test_parse_response_min_ttl() {
+		use hickory_proto::rr::{Record, RData};
+		use hickory_proto::rr::rdata::A;
+		let query_bytes = build_query("example.com", QueryType::A, 4242, false).unwrap();
+		let mut response = Message::from_vec(&query_bytes).unwrap();
+		response.set_message_type(MessageType::Response);
+		let name = Name::from_ascii("example.com.").unwrap();
+		response.add_answer(Record::from_rdata(name.clone(), 300, RData::A(A::new(1, 2, 3, 4))));
+		response.add_answer(Record::from_rdata(name, 120, RData::A(A::new(5, 6, 7, 8))));
+		let response_bytes = response.to_vec().unwrap();
+
+		let dns_resp = parse_response(&response_bytes, 4242, "example.com", QueryType::A).unwrap();
+		assert!(dns_resp.has_a_records);
+		assert_eq!(dns_resp.min_ttl, Some(120));
 	}
 
 	#[test]
@@ -413,4 +1340,126 @@ mod tests {
 		let result = parse_response(&bytes, 0, "example.com", QueryType::A);
 		assert!(result.is_err());
 	}
+
+	#[test]
+	fn test_classify_large_response() {
+		use crate::record::LargeResponseOutcome;
+		use hickory_proto::rr::{rdata::TXT, RData, Record};
+		let mut full = Message::new();
+		full.add_answer(Record::from_rdata(Name::from_ascii("microsoft.com.").unwrap(), 300, RData::TXT(TXT::new(vec!["v=spf1 -all".to_string()]))));
+		let mut truncated = Message::new();
+		truncated.set_truncated(true);
+		let mut servfail = Message::new();
+		servfail.set_response_code(ResponseCode::ServFail);
+		assert_eq!(classify_large_response(Some((&full, 1180)), None), LargeResponseOutcome::Udp(1180));
+		assert_eq!(classify_large_response(Some((&truncated, 40)), Some(3042)), LargeResponseOutcome::TcpFallback(3042));
+		assert_eq!(classify_large_response(Some((&truncated, 40)), None), LargeResponseOutcome::NoTcp);
+		assert_eq!(classify_large_response(None, Some(3042)), LargeResponseOutcome::UdpLost);
+		assert_eq!(classify_large_response(None, None), LargeResponseOutcome::Failed);
+		assert_eq!(classify_large_response(Some((&servfail, 40)), None), LargeResponseOutcome::Failed);
+		// An empty NOERROR answer is no answer
+		assert_eq!(classify_large_response(Some((&Message::new(), 40)), None), LargeResponseOutcome::Failed);
+	}
+
+	#[test]
+	fn test_classify_rd_zero() {
+		use crate::record::RdZeroBehavior;
+		use hickory_proto::rr::{rdata::A, RData, Record};
+		let reply = |rcode: ResponseCode, answer: bool| {
+			let mut m = Message::new();
+			m.set_response_code(rcode);
+			if answer {
+				m.add_answer(Record::from_rdata(Name::from_ascii("www.google.com.").unwrap(), 60, RData::A(A::new(142, 250, 1, 1))));
+			}
+			m
+		};
+		let (cached, empty, refused) = (reply(ResponseCode::NoError, true), reply(ResponseCode::NoError, false), reply(ResponseCode::Refused, false));
+		assert_eq!(classify_rd_zero(Some(&cached), Some(&cached)), Some(RdZeroBehavior::Recurses));
+		assert_eq!(classify_rd_zero(Some(&cached), Some(&empty)), Some(RdZeroBehavior::FromCache));
+		assert_eq!(classify_rd_zero(Some(&empty), None), Some(RdZeroBehavior::NoAnswer));
+		assert_eq!(classify_rd_zero(Some(&refused), Some(&refused)), Some(RdZeroBehavior::Refused));
+		assert_eq!(classify_rd_zero(None, None), None);
+	}
+
+	#[test]
+	fn test_padded_query_fills_block() {
+		for domain in ["a.io", "example.com", "a-much-longer-name.subdomain.example.org"] {
+			let bytes = build_padded_query(domain, 42, PADDING_QUERY_BLOCK).unwrap();
+			assert_eq!(bytes.len() % PADDING_QUERY_BLOCK, 0, "{}", domain);
+			assert_eq!(has_padding(&bytes), Some(true));
+		}
+		assert_eq!(has_padding(&build_query("example.com", QueryType::A, 1, true).unwrap()), Some(false));
+		assert_eq!(has_padding(&[0, 1]), None);
+	}
+
+	#[test]
+	fn test_classify_https_rr() {
+		use crate::record::HttpsRrBehavior;
+		use hickory_proto::rr::rdata::svcb::{EchConfigList, SvcParamKey, SvcParamValue, SVCB};
+		use hickory_proto::rr::rdata::HTTPS;
+		use hickory_proto::rr::{RData, Record};
+		let reply = |params: Option<Vec<(SvcParamKey, SvcParamValue)>>| {
+			let mut m = Message::new();
+			if let Some(params) = params {
+				let rdata = RData::HTTPS(HTTPS(SVCB::new(1, Name::root(), params)));
+				m.add_answer(Record::from_rdata(Name::from_ascii("crypto.cloudflare.com.").unwrap(), 300, rdata));
+			}
+			m
+		};
+		let ech = (SvcParamKey::EchConfigList, SvcParamValue::EchConfigList(EchConfigList(vec![0, 1, 2])));
+		assert_eq!(classify_https_rr(&reply(Some(vec![ech]))), HttpsRrBehavior::Intact);
+		assert_eq!(classify_https_rr(&reply(Some(Vec::new()))), HttpsRrBehavior::NoEch);
+		assert_eq!(classify_https_rr(&reply(None)), HttpsRrBehavior::Stripped);
+		// Benchmark queries ask for type 65
+		let bytes = build_query("example.com", QueryType::HTTPS, 7, false).unwrap();
+		assert!(response_matches_question(&bytes, "example.com", QueryType::HTTPS));
+	}
+
+	#[test]
+	fn test_ttl_rewrite_verdicts() {
+		use crate::record::{TtlCheck, TtlRewriteResult};
+		let check = |authoritative, returned| Some(TtlCheck { authoritative, returned });
+		let clean = TtlRewriteResult { short: check(130, 95), long: check(3_600_000, 3_541_000) };
+		assert_eq!(clean.csv_text(), "none");
+		let clamped = TtlRewriteResult { short: check(130, 300), long: check(3_600_000, 86_400) };
+		assert!(clamped.raises_min() && clamped.caps_max() && !clamped.extends_max());
+		assert_eq!(clamped.csv_text(), "raises_min;caps_max");
+		let extended = TtlRewriteResult { short: None, long: check(86_400, 172_800) };
+		assert_eq!(extended.csv_text(), "extends_max");
+
+		let mut reply = Message::new();
+		let name = Name::from_ascii("pool.ntp.org.").unwrap();
+		for (ttl, ip) in [(130, 1), (90, 2)] {
+			reply.add_answer(hickory_proto::rr::Record::from_rdata(name.clone(), ttl,
+				hickory_proto::rr::RData::A(hickory_proto::rr::rdata::A::new(192, 0, 2, ip))));
+		}
+		assert_eq!(a_record_ttl(&reply), Some(90));
+		assert_eq!(a_record_ttl(&Message::new()), None);
+	}
+
+	#[test]
+	fn test_classify_dns64() {
+		use crate::record::Dns64Behavior;
+		use hickory_proto::rr::rdata::AAAA;
+		use hickory_proto::rr::{RData, Record};
+		let reply = |addrs: &[&str]| {
+			let mut m = Message::new();
+			for addr in addrs {
+				let rdata = RData::AAAA(AAAA(addr.parse().unwrap()));
+				m.add_answer(Record::from_rdata(Name::from_ascii("ipv4only.arpa.").unwrap(), 300, rdata));
+			}
+			m
+		};
+		let well_known = Dns64Behavior::Synthesizes("64:ff9b::".parse().unwrap(), 96);
+		assert_eq!(classify_dns64(&reply(&["64:ff9b::c000:aa"])), Some(well_known));
+		assert_eq!(well_known.csv_text(), "64:ff9b::/96");
+		// 192.0.0.171 under a network-specific /64 (IPv4 after the zero byte 8)
+		let nsp = classify_dns64(&reply(&["2001:db8:1:2:c0:0:ab00:0"]));
+		assert_eq!(nsp, Some(Dns64Behavior::Synthesizes("2001:db8:1:2::".parse().unwrap(), 64)));
+		assert_eq!(classify_dns64(&reply(&[])), Some(Dns64Behavior::Off));
+		assert_eq!(classify_dns64(&reply(&["2001:db8::1"])), Some(Dns64Behavior::Off));
+		let mut servfail = reply(&[]);
+		servfail.set_response_code(ResponseCode::ServFail);
+		assert_eq!(classify_dns64(&servfail), None);
+	}
 }
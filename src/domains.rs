@@ -1,4 +1,19 @@
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use anyhow::{anyhow, bail, Result};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Category name for the NXDOMAIN latency set (negative-cache timing).
+/// Queries in this set succeed when the resolver answers NXDOMAIN, and the
+/// set is reported on its own without contributing to the overall score.
+pub const NXDOMAIN_SET: &str = "nxdomain";
+
+/// Current Tranco list ID, as plain text
+const TRANCO_ID_URL: &str = "https://tranco-list.eu/top-1m-id";
+/// Tranco list download, `{id}` and `{n}` filled in; plain `rank,domain` CSV
+const TRANCO_DOWNLOAD_URL: &str = "https://tranco-list.eu/download/{id}/{n}";
 
 /// Default query domains CSV, embedded at compile time.
 const DEFAULT_QUERY_DOMAINS_CSV: &str = include_str!("../query_domains.csv");
@@ -53,6 +68,109 @@ pub fn default_nxdomain_domains() -> Vec<String> {
 	].into_iter().map(String::from).collect()
 }
 
+//============================================
+/// Where the warm (cached) set comes from (--warm-source).
+#[derive(Debug, Clone, PartialEq)]
+pub enum WarmSource {
+	/// Sample from the top `pool` names of the Tranco list, downloaded, or
+	/// read from a local `rank,domain` CSV copy when `file` is set
+	Tranco { pool: usize, file: Option<String> },
+}
+
+/// Parse `tranco:N` or `tranco:N:FILE`.
+pub fn parse_warm_source(value: &str) -> Result<WarmSource, String> {
+	let mut parts = value.splitn(3, ':');
+	let kind = parts.next().unwrap_or_default();
+	let pool = parts.next().and_then(|n| n.parse::<usize>().ok()).filter(|&n| n > 0);
+	match (kind, pool) {
+		("tranco", Some(pool)) => Ok(WarmSource::Tranco { pool, file: parts.next().map(String::from) }),
+		_ => Err(format!("expected tranco:N or tranco:N:FILE (N = top-list size to sample from), got '{}'", value)),
+	}
+}
+
+/// Domains from a Tranco `rank,domain` CSV, in rank order, up to `pool`.
+fn parse_tranco_csv(text: &str, pool: usize) -> Vec<String> {
+	text.lines()
+		.filter_map(|line| line.trim().split_once(','))
+		.filter(|(rank, _)| rank.parse::<u64>().is_ok())
+		.filter_map(|(_, domain)| crate::import::keep_name(domain))
+		.take(pool)
+		.collect()
+}
+
+/// Pick `count` names from `pool`, reproducibly for a given seed, and keep
+/// them in list (rank) order.
+pub fn sample_domains(pool: &[String], count: usize, seed: u64) -> Vec<String> {
+	let mut rng = StdRng::seed_from_u64(seed);
+	let mut picked: Vec<usize> = rand::seq::index::sample(&mut rng, pool.len(), count.min(pool.len())).into_vec();
+	picked.sort_unstable();
+	picked.into_iter().map(|i| pool[i].clone()).collect()
+}
+
+/// Load the name pool for a warm source, downloading the current Tranco
+/// list when no local copy is given.
+pub async fn load_warm_pool(source: &WarmSource) -> Result<Vec<String>> {
+	let WarmSource::Tranco { pool, file } = source;
+	let text = match file {
+		Some(path) => std::fs::read_to_string(path).map_err(|e| anyhow!("Tranco list {}: {}", path, e))?,
+		None => {
+			let id = reqwest::get(TRANCO_ID_URL).await
+				.and_then(|r| r.error_for_status())
+				.map_err(|e| anyhow!("Failed to look up the current Tranco list: {}", e))?
+				.text().await?;
+			let url = TRANCO_DOWNLOAD_URL.replace("{id}", id.trim()).replace("{n}", &pool.to_string());
+			crate::info!("Downloading the top {} of Tranco list {}...", pool, id.trim());
+			reqwest::get(&url).await
+				.and_then(|r| r.error_for_status())
+				.map_err(|e| anyhow!("Failed to download the Tranco list: {}", e))?
+				.text().await?
+		}
+	};
+	let names = parse_tranco_csv(&text, *pool);
+	if names.is_empty() {
+		bail!("the Tranco list has no usable domains");
+	}
+	Ok(names)
+}
+
+//============================================
+/// Set whose names --cold-zone replaces with fresh random labels
+pub const COLD_SET: &str = "uncached";
+/// Random characters in a --cold-zone label
+const COLD_LABEL_CHARS: usize = 16;
+/// Per-run value mixed into every --cold-zone label (see set_cold_nonce)
+static COLD_NONCE: AtomicU64 = AtomicU64::new(0);
+
+/// Set the per-run nonce for --cold-zone labels. The run start time is
+/// used, so a fixed --seed still draws names no earlier run has sent.
+pub fn set_cold_nonce(nonce: u64) {
+	COLD_NONCE.store(nonce, Ordering::Relaxed);
+}
+
+/// Current per-run nonce for --cold-zone labels.
+pub fn cold_nonce() -> u64 {
+	COLD_NONCE.load(Ordering::Relaxed)
+}
+
+/// Parse a --cold-zone value; a leading `*.` or `$RANDOM.` is accepted and dropped.
+pub fn parse_cold_zone(value: &str) -> Result<String, String> {
+	let zone = value.trim_start_matches("*.").trim_start_matches("$RANDOM.");
+	crate::import::keep_name(zone)
+		.ok_or_else(|| format!("expected a zone you control such as cold.example.com, got '{}'", value))
+}
+
+/// A name under `zone` with a random label no resolver can have cached,
+/// e.g. `dnsb-k3x9q2m7a0c4w8e1.cold.example.com`, drawn from `rng` mixed
+/// with the run's `nonce`.
+pub fn cold_name(zone: &str, nonce: u64, rng: &mut impl Rng) -> String {
+	const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+	let mut mixed = StdRng::seed_from_u64(rng.gen::<u64>() ^ nonce);
+	let label: String = (0..COLD_LABEL_CHARS)
+		.map(|_| ALPHABET[mixed.gen_range(0..ALPHABET.len())] as char)
+		.collect();
+	format!("dnsb-{}.{}", label, zone)
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
@@ -124,4 +242,38 @@ mod tests {
 		assert!(categories.contains_key("dnssec"), "missing 'dnssec' category");
 		assert!(categories["dnssec"].len() >= 10, "expected at least 10 DNSSEC domains");
 	}
+
+	#[test]
+	fn test_warm_source_parse_and_sampling() {
+		assert_eq!(parse_warm_source("tranco:1000"), Ok(WarmSource::Tranco { pool: 1000, file: None }));
+		assert_eq!(parse_warm_source("tranco:50:/tmp/top-1m.csv"),
+			Ok(WarmSource::Tranco { pool: 50, file: Some("/tmp/top-1m.csv".to_string()) }));
+		assert!(parse_warm_source("tranco:0").is_err());
+		assert!(parse_warm_source("alexa:100").is_err());
+
+		let pool = parse_tranco_csv("1,google.com\r\n2,localhost\n3,Example.ORG\n4,a.example.net\n5,b.example.net\n", 3);
+		assert_eq!(pool, vec!["google.com", "example.org", "a.example.net"]);
+		// Same seed, same sample, kept in rank order
+		let sample = sample_domains(&pool, 2, 7);
+		assert_eq!(sample, sample_domains(&pool, 2, 7));
+		assert_eq!(sample.len(), 2);
+		assert!(pool.iter().position(|d| *d == sample[0]) < pool.iter().position(|d| *d == sample[1]));
+		assert_eq!(sample_domains(&pool, 10, 7).len(), 3);
+	}
+
+	#[test]
+	fn test_cold_zone_names() {
+		assert_eq!(parse_cold_zone("*.Cold.Example.com."), Ok("cold.example.com".to_string()));
+		assert_eq!(parse_cold_zone("$RANDOM.cold.example.com"), Ok("cold.example.com".to_string()));
+		assert!(parse_cold_zone("localhost").is_err());
+		let mut rng = StdRng::seed_from_u64(7);
+		let a = cold_name("cold.example.com", 1, &mut rng);
+		assert!(a.starts_with("dnsb-") && a.ends_with(".cold.example.com"));
+		assert_eq!(a.len(), "dnsb-".len() + COLD_LABEL_CHARS + ".cold.example.com".len());
+		assert_ne!(a, cold_name("cold.example.com", 1, &mut rng));
+		// Same seed and nonce, same names
+		assert_eq!(a, cold_name("cold.example.com", 1, &mut StdRng::seed_from_u64(7)));
+		// Same seed in another run: fresh names
+		assert_ne!(a, cold_name("cold.example.com", 2, &mut StdRng::seed_from_u64(7)));
+	}
 }
@@ -0,0 +1,187 @@
+use std::net::SocketAddr;
+
+use crate::cli::EmitFormat;
+use crate::record::ResolverRecord;
+use crate::transport::DnsTransport;
+
+/// Default number of resolvers in an emitted configuration
+pub const DEFAULT_EMIT_TOP: u32 = 3;
+/// glibc reads at most this many nameserver lines from resolv.conf (MAXNS)
+const RESOLV_CONF_MAX_NAMESERVERS: usize = 3;
+
+//============================================
+/// Whether a format can express a resolver's transport and port.
+///
+/// resolv.conf has no port or TLS syntax; dnsmasq forwards plain DNS only.
+/// systemd-resolved and Unbound also speak DoT. No format here does DoH.
+fn supports(format: EmitFormat, addr: &SocketAddr, transport: &DnsTransport) -> bool {
+	match (format, transport) {
+		(EmitFormat::ResolvConf, DnsTransport::Udp) => addr.port() == 53,
+		(EmitFormat::Dnsmasq, DnsTransport::Udp) => true,
+		(EmitFormat::Systemd | EmitFormat::Unbound, DnsTransport::Udp | DnsTransport::Dot { .. }) => true,
+		_ => false,
+	}
+}
+
+/// Resolvers to list: the best-scoring policy-eligible ones the format can
+/// express, all with the same transport as the best of them.
+///
+/// Mixing plain and TLS upstreams would either downgrade the TLS ones
+/// (systemd-resolved's opportunistic mode) or need separate forward zones
+/// (Unbound), so one transport is used throughout. Loopback resolvers are
+/// skipped: pointing the stub at itself would loop.
+pub fn pick_resolvers(results: &[ResolverRecord], format: EmitFormat, top: usize) -> Vec<&ResolverRecord> {
+	let candidates: Vec<&ResolverRecord> = crate::policy::eligible_by_score(results).into_iter()
+		.filter(|r| !r.resolver.addr.ip().is_loopback())
+		.filter(|r| supports(format, &r.resolver.addr, &r.resolver.transport))
+		.collect();
+	let Some(best) = candidates.first() else { return Vec::new() };
+	let is_tls = |r: &ResolverRecord| matches!(r.resolver.transport, DnsTransport::Dot { .. });
+	let best_tls = is_tls(best);
+	let limit = match format {
+		EmitFormat::ResolvConf => top.min(RESOLV_CONF_MAX_NAMESERVERS),
+		_ => top,
+	};
+	candidates.into_iter()
+		.filter(|r| is_tls(r) == best_tls)
+		.take(limit)
+		.collect()
+}
+
+/// Address text with the port only when it is not the transport default.
+fn host_port(addr: &SocketAddr, default_port: u16, bracket_v6: bool) -> String {
+	match (addr.port() == default_port, addr) {
+		(true, _) => addr.ip().to_string(),
+		(false, SocketAddr::V6(v6)) if bracket_v6 => format!("[{}]:{}", v6.ip(), v6.port()),
+		(false, _) => format!("{}:{}", addr.ip(), addr.port()),
+	}
+}
+
+//============================================
+/// Render the configuration snippet for the picked resolvers.
+pub fn render(format: EmitFormat, picks: &[&ResolverRecord]) -> String {
+	let mut lines: Vec<String> = Vec::new();
+	let tls = picks.iter().any(|r| matches!(r.resolver.transport, DnsTransport::Dot { .. }));
+	match format {
+		EmitFormat::ResolvConf => {
+			lines.push("# /etc/resolv.conf".to_string());
+			for r in picks {
+				lines.push(format!("nameserver {}", r.resolver.addr.ip()));
+			}
+		}
+		EmitFormat::Systemd => {
+			lines.push("# /etc/systemd/resolved.conf.d/benchmark.conf".to_string());
+			lines.push("[Resolve]".to_string());
+			let servers: Vec<String> = picks.iter()
+				.map(|r| match &r.resolver.transport {
+					DnsTransport::Dot { hostname } => format!("{}#{}", host_port(&r.resolver.addr, 853, true), hostname),
+					_ => host_port(&r.resolver.addr, 53, true),
+				})
+				.collect();
+			lines.push(format!("DNS={}", servers.join(" ")));
+			if tls {
+				lines.push("DNSOverTLS=yes".to_string());
+			}
+		}
+		EmitFormat::Unbound => {
+			lines.push("# /etc/unbound/unbound.conf.d/forward.conf".to_string());
+			lines.push("forward-zone:".to_string());
+			lines.push("    name: \".\"".to_string());
+			if tls {
+				lines.push("    forward-tls-upstream: yes".to_string());
+			}
+			for r in picks {
+				let addr = &r.resolver.addr;
+				let entry = match &r.resolver.transport {
+					DnsTransport::Dot { hostname } => format!("{}@{}#{}", addr.ip(), addr.port(), hostname),
+					_ => format!("{}@{}", addr.ip(), addr.port()),
+				};
+				lines.push(format!("    forward-addr: {}", entry));
+			}
+			if tls {
+				lines.push("# Also set tls-cert-bundle in the server: section".to_string());
+			}
+		}
+		EmitFormat::Dnsmasq => {
+			lines.push("# /etc/dnsmasq.d/benchmark.conf".to_string());
+			lines.push("no-resolv".to_string());
+			for r in picks {
+				let addr = &r.resolver.addr;
+				match addr.port() {
+					53 => lines.push(format!("server={}", addr.ip())),
+					port => lines.push(format!("server={}#{}", addr.ip(), port)),
+				}
+			}
+		}
+	}
+	lines.join("\n")
+}
+
+/// Print a ready-to-use configuration for the top resolvers (--emit-config).
+pub fn print_emitted_config(results: &[ResolverRecord], format: EmitFormat, top: usize) {
+	let picks = pick_resolvers(results, format, top);
+	println!("\nSuggested {} configuration", format);
+	println!("{}", "-".repeat(format!("Suggested {} configuration", format).len()));
+	if picks.is_empty() {
+		println!("  No eligible resolver can be written in this format (DoH-only results, or all excluded).");
+		return;
+	}
+	println!("{}", render(format, &picks));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::transport::Resolver;
+
+	fn record(addr: &str, transport: DnsTransport, score: f64) -> ResolverRecord {
+		let mut rec = ResolverRecord::new(Resolver::new(addr.parse().unwrap(), transport));
+		rec.benchmark = Some(crate::record::BenchmarkResult {
+			overall_score: score,
+			success_rate: 100.0,
+			first_attempt_rate: 100.0,
+			..Default::default()
+		});
+		rec
+	}
+
+	fn dot(hostname: &str) -> DnsTransport {
+		DnsTransport::Dot { hostname: hostname.to_string() }
+	}
+
+	#[test]
+	fn test_pick_follows_best_transport() {
+		let records = vec![
+			record("9.9.9.9:853", dot("dns.quad9.net"), 8.0),
+			record("1.1.1.1:53", DnsTransport::Udp, 5.0),
+			record("127.0.0.1:53", DnsTransport::Udp, 0.5),
+			record("1.0.0.1:853", dot("cloudflare-dns.com"), 9.0),
+		];
+		// Best is UDP 1.1.1.1; loopback is skipped, DoT cannot mix in
+		let picks = pick_resolvers(&records, EmitFormat::Systemd, 3);
+		assert_eq!(picks.len(), 1);
+		assert_eq!(render(EmitFormat::Systemd, &picks), "# /etc/systemd/resolved.conf.d/benchmark.conf\n[Resolve]\nDNS=1.1.1.1");
+
+		let tls_only = vec![records[0].clone(), records[3].clone()];
+		let picks = pick_resolvers(&tls_only, EmitFormat::Unbound, 3);
+		let text = render(EmitFormat::Unbound, &picks);
+		assert!(text.contains("forward-tls-upstream: yes"));
+		assert!(text.contains("forward-addr: 9.9.9.9@853#dns.quad9.net\n    forward-addr: 1.0.0.1@853#cloudflare-dns.com"));
+		// resolv.conf and dnsmasq have no TLS syntax
+		assert!(pick_resolvers(&tls_only, EmitFormat::ResolvConf, 3).is_empty());
+	}
+
+	#[test]
+	fn test_render_plain_formats() {
+		let records = vec![
+			record("8.8.8.8:53", DnsTransport::Udp, 4.0),
+			record("192.168.1.1:5353", DnsTransport::Udp, 2.0),
+		];
+		let picks = pick_resolvers(&records, EmitFormat::Dnsmasq, 3);
+		assert_eq!(render(EmitFormat::Dnsmasq, &picks),
+			"# /etc/dnsmasq.d/benchmark.conf\nno-resolv\nserver=192.168.1.1#5353\nserver=8.8.8.8");
+		// resolv.conf cannot name a port, so only port 53 servers qualify
+		let picks = pick_resolvers(&records, EmitFormat::ResolvConf, 3);
+		assert_eq!(render(EmitFormat::ResolvConf, &picks), "# /etc/resolv.conf\nnameserver 8.8.8.8");
+	}
+}
@@ -0,0 +1,146 @@
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Fractions (0.0-1.0) of received responses to drop, delay, or corrupt.
+///
+/// Response fault injection for robustness testing, compiled into test
+/// builds and, with the `fault-injection` feature, into the binary. The UDP
+/// transport asks `next_fault` for every datagram it receives, so stats,
+/// retries, and tie detection can be checked under loss, delay, and
+/// corruption without a misbehaving network.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FaultConfig {
+	pub drop_rate: f64,
+	pub delay_rate: f64,
+	pub delay: Duration,
+	pub corrupt_rate: f64,
+}
+
+impl FaultConfig {
+	/// No faults.
+	pub const NONE: FaultConfig = FaultConfig {
+		drop_rate: 0.0,
+		delay_rate: 0.0,
+		delay: Duration::ZERO,
+		corrupt_rate: 0.0,
+	};
+
+	/// Whether any fault can fire.
+	pub fn is_active(&self) -> bool {
+		self.drop_rate > 0.0 || self.delay_rate > 0.0 || self.corrupt_rate > 0.0
+	}
+}
+
+/// What to do with one received response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fault {
+	None,
+	/// Discard the datagram as if it never arrived
+	Drop,
+	/// Hold the datagram for this long before handling it
+	Delay(Duration),
+	/// Flip the bits of one byte before parsing
+	Corrupt,
+}
+
+static FAULTS: RwLock<FaultConfig> = RwLock::new(FaultConfig::NONE);
+
+/// Held by tests that send UDP through the fault hooks, so one test's fault
+/// config never leaks into another running in parallel
+#[cfg(test)]
+pub(crate) static TEST_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+//============================================
+/// Set the process-wide fault configuration.
+pub fn set_faults(config: FaultConfig) {
+	if let Ok(mut guard) = FAULTS.write() {
+		*guard = config;
+	}
+}
+
+/// Current process-wide fault configuration.
+pub fn faults() -> FaultConfig {
+	FAULTS.read().map(|g| *g).unwrap_or(FaultConfig::NONE)
+}
+
+//============================================
+/// Pick the fault for one response from a uniform roll in [0, 1).
+///
+/// The rates partition the roll in order drop, delay, corrupt, so their sum
+/// is the total fault rate (capped at 1.0).
+pub fn pick_fault(config: &FaultConfig, roll: f64) -> Fault {
+	let mut threshold = config.drop_rate;
+	if roll < threshold {
+		return Fault::Drop;
+	}
+	threshold += config.delay_rate;
+	if roll < threshold {
+		return Fault::Delay(config.delay);
+	}
+	threshold += config.corrupt_rate;
+	if roll < threshold {
+		return Fault::Corrupt;
+	}
+	Fault::None
+}
+
+/// Roll the fault for the next received response.
+pub fn next_fault() -> Fault {
+	let config = faults();
+	if !config.is_active() {
+		return Fault::None;
+	}
+	pick_fault(&config, rand::random::<f64>())
+}
+
+/// Flip every bit of the byte at `pos` (wrapped to the buffer length).
+pub fn corrupt(bytes: &mut [u8], pos: usize) {
+	if bytes.is_empty() {
+		return;
+	}
+	let idx = pos % bytes.len();
+	bytes[idx] ^= 0xFF;
+}
+
+//============================================
+/// Parse a fault rate flag value: a fraction between 0.0 and 1.0.
+pub fn parse_rate(value: &str) -> Result<f64, String> {
+	match value.parse::<f64>() {
+		Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+		_ => Err(format!("expected a fraction between 0.0 and 1.0, got '{}'", value)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_pick_fault_partitions_roll() {
+		let config = FaultConfig {
+			drop_rate: 0.1,
+			delay_rate: 0.2,
+			delay: Duration::from_millis(50),
+			corrupt_rate: 0.3,
+		};
+		assert_eq!(pick_fault(&config, 0.05), Fault::Drop);
+		assert_eq!(pick_fault(&config, 0.25), Fault::Delay(Duration::from_millis(50)));
+		assert_eq!(pick_fault(&config, 0.55), Fault::Corrupt);
+		assert_eq!(pick_fault(&config, 0.65), Fault::None);
+		assert_eq!(pick_fault(&FaultConfig::NONE, 0.0), Fault::None);
+	}
+
+	#[test]
+	fn test_corrupt_flips_one_byte() {
+		let mut bytes = [0x12, 0x34, 0x00];
+		corrupt(&mut bytes, 4);
+		assert_eq!(bytes, [0x12, 0xCB, 0x00]);
+	}
+
+	#[test]
+	fn test_parse_rate() {
+		assert_eq!(parse_rate("0.25"), Ok(0.25));
+		assert!(parse_rate("1.5").is_err());
+		assert!(parse_rate("x").is_err());
+	}
+}
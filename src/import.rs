@@ -0,0 +1,330 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, bail, Result};
+use hickory_proto::op::{Message, MessageType};
+
+/// Default number of names kept by `domains import`
+pub const DEFAULT_IMPORT_TOP: u32 = 20;
+/// Default set written by `domains import`: the warm (cached) set
+pub const DEFAULT_IMPORT_SET: &str = "cached";
+
+/// Suffixes of names that only resolve on the local network or in reverse
+/// zones; benchmarking them against public resolvers measures nothing
+const SKIPPED_SUFFIXES: [&str; 7] = [".local", ".lan", ".home", ".home.arpa", ".internal", ".in-addr.arpa", ".ip6.arpa"];
+
+/// pcap link types this importer can strip down to the IP header
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+
+//============================================
+/// Normalize a name and decide whether it belongs in a benchmark set.
+///
+/// Lowercases and strips the trailing dot. Rejects single-label names, IP
+/// literals, localhost, and local or reverse-zone names.
+pub fn keep_name(name: &str) -> Option<String> {
+	let name = name.trim_end_matches('.').to_ascii_lowercase();
+	if !name.contains('.') || name.parse::<std::net::IpAddr>().is_ok() || name.ends_with("localhost") {
+		return None;
+	}
+	if SKIPPED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+		return None;
+	}
+	let valid = name.split('.').all(|label| !label.is_empty() && label.len() <= 63
+		&& label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_'));
+	valid.then_some(name)
+}
+
+/// Host name of an http(s) URL; None for other schemes.
+pub fn host_from_url(url: &str) -> Option<&str> {
+	let rest = url.strip_prefix("https://").or_else(|| url.strip_prefix("http://"))?;
+	let authority = rest.split(['/', '?', '#']).next()?;
+	let host_port = authority.rsplit('@').next()?;
+	// Bracketed IPv6 literals are never kept, so only strip a :port
+	host_port.split(':').next().filter(|h| !h.is_empty())
+}
+
+/// The most frequent names, ties broken alphabetically.
+pub fn top_domains(counts: &BTreeMap<String, usize>, top: usize) -> Vec<String> {
+	let mut ranked: Vec<(&String, &usize)> = counts.iter().collect();
+	ranked.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+	ranked.into_iter().take(top).map(|(name, _)| name.clone()).collect()
+}
+
+//============================================
+/// Visit counts per host from a Firefox (`places.sqlite`) or Chrome/Chromium
+/// (`History`) database, read with the sqlite3 command-line tool.
+///
+/// The database is copied first: browsers keep it locked while running.
+/// Its `-wal` and `-shm` files come along, since a running browser keeps
+/// recent visits in the write-ahead log until it checkpoints. The copies
+/// live in a private temporary directory that is removed on return.
+fn history_counts(path: &str) -> Result<BTreeMap<String, usize>> {
+	let dir = tempfile::tempdir()?;
+	let copy = dir.path().join("history.sqlite");
+	let sidecar = |base: &std::path::Path, suffix: &str| {
+		let mut name = base.as_os_str().to_owned();
+		name.push(suffix);
+		std::path::PathBuf::from(name)
+	};
+	std::fs::copy(path, &copy).map_err(|e| anyhow!("cannot copy {}: {}", path, e))?;
+	for suffix in ["-wal", "-shm"] {
+		let from = sidecar(std::path::Path::new(path), suffix);
+		if from.exists() {
+			std::fs::copy(&from, sidecar(&copy, suffix)).map_err(|e| anyhow!("cannot copy {}: {}", from.display(), e))?;
+		}
+	}
+	let query = |sql: &str| {
+		let output = std::process::Command::new("sqlite3")
+			.args(["-readonly", "-noheader", "-separator", "\t"])
+			.arg(&copy)
+			.arg(sql)
+			.output();
+		match output {
+			Ok(out) if out.status.success() => Ok(String::from_utf8_lossy(&out.stdout).into_owned()),
+			Ok(out) => Err(anyhow!("sqlite3 failed: {}", String::from_utf8_lossy(&out.stderr).trim())),
+			Err(e) => Err(anyhow!("cannot run sqlite3 (install the sqlite3 command-line tool): {}", e)),
+		}
+	};
+	let tables = query("SELECT name FROM sqlite_master WHERE type = 'table'");
+	let table = match tables {
+		Ok(names) if names.lines().any(|t| t == "moz_places") => Ok("moz_places"),
+		Ok(names) if names.lines().any(|t| t == "urls") => Ok("urls"),
+		Ok(_) => Err(anyhow!("{} is not a Firefox or Chrome history database", path)),
+		Err(e) => Err(e),
+	};
+	let rows = table.and_then(|t| query(&format!("SELECT url, visit_count FROM {} WHERE visit_count > 0", t)));
+
+	let mut counts = BTreeMap::new();
+	for line in rows?.lines() {
+		let Some((url, visits)) = line.rsplit_once('\t') else { continue };
+		let Some(host) = host_from_url(url).and_then(keep_name) else { continue };
+		*counts.entry(host).or_insert(0) += visits.parse::<usize>().unwrap_or(1);
+	}
+	Ok(counts)
+}
+
+//============================================
+/// UDP payload of a port-53 query inside a link-layer frame, if any.
+fn dns_payload(linktype: u32, frame: &[u8]) -> Option<&[u8]> {
+	let ip = match linktype {
+		LINKTYPE_ETHERNET => {
+			// Skip up to two 802.1Q VLAN tags
+			let mut offset = 12;
+			let mut ethertype = u16::from_be_bytes([*frame.get(offset)?, *frame.get(offset + 1)?]);
+			while ethertype == 0x8100 && offset < 20 {
+				offset += 4;
+				ethertype = u16::from_be_bytes([*frame.get(offset)?, *frame.get(offset + 1)?]);
+			}
+			frame.get(offset + 2..)?
+		}
+		LINKTYPE_LINUX_SLL => frame.get(16..)?,
+		LINKTYPE_NULL => frame.get(4..)?,
+		LINKTYPE_RAW => frame,
+		_ => return None,
+	};
+	let udp = match ip.first()? >> 4 {
+		4 => {
+			let header_len = (ip[0] & 0x0f) as usize * 4;
+			// Protocol 17 (UDP), not a later fragment
+			let fragment_offset = u16::from_be_bytes([*ip.get(6)?, *ip.get(7)?]) & 0x1fff;
+			if *ip.get(9)? != 17 || fragment_offset != 0 {
+				return None;
+			}
+			ip.get(header_len..)?
+		}
+		// Next header 17 directly after the fixed header; extension headers are skipped over
+		6 if *ip.get(6)? == 17 => ip.get(40..)?,
+		_ => return None,
+	};
+	let dst_port = u16::from_be_bytes([*udp.get(2)?, *udp.get(3)?]);
+	if dst_port != 53 {
+		return None;
+	}
+	udp.get(8..)
+}
+
+/// Link-layer frames from a pcap or pcapng capture, with their link type.
+fn capture_frames(data: &[u8]) -> Result<Vec<(u32, &[u8])>> {
+	let magic = data.get(..4).unwrap_or_default();
+	let mut frames = Vec::new();
+	match magic {
+		// Classic pcap, microsecond or nanosecond timestamps, either byte order
+		[0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1] | [0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d] => {
+			let little = magic[0] == 0xd4 || magic[0] == 0x4d;
+			let read_u32 = |at: usize| -> Option<u32> {
+				let bytes: [u8; 4] = data.get(at..at + 4)?.try_into().ok()?;
+				Some(if little { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+			};
+			let linktype = read_u32(20).ok_or_else(|| anyhow!("truncated pcap header"))?;
+			let mut offset = 24;
+			while let Some(captured) = read_u32(offset + 8) {
+				let start = offset + 16;
+				let Some(frame) = data.get(start..start + captured as usize) else { break };
+				frames.push((linktype, frame));
+				offset = start + captured as usize;
+			}
+		}
+		// pcapng: section header, interface descriptions, enhanced/simple packet blocks
+		[0x0a, 0x0d, 0x0d, 0x0a] => {
+			let mut little = true;
+			let mut linktypes: Vec<u32> = Vec::new();
+			let mut offset = 0;
+			while offset + 12 <= data.len() {
+				let read_u32 = |at: usize, little: bool| -> Option<u32> {
+					let bytes: [u8; 4] = data.get(at..at + 4)?.try_into().ok()?;
+					Some(if little { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+				};
+				if data[offset..offset + 4] == [0x0a, 0x0d, 0x0d, 0x0a] {
+					// Byte-order magic decides how the rest of the section is read
+					little = data.get(offset + 8..offset + 12) == Some(&[0x4d, 0x3c, 0x2b, 0x1a]);
+					linktypes.clear();
+				}
+				let block_type = read_u32(offset, little).unwrap_or(0);
+				let block_len = read_u32(offset + 4, little).unwrap_or(0) as usize;
+				if block_len < 12 || offset + block_len > data.len() {
+					break;
+				}
+				let body = &data[offset + 8..offset + block_len - 4];
+				match block_type {
+					1 if body.len() >= 2 => {
+						let bytes = [body[0], body[1]];
+						let linktype = if little { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) };
+						linktypes.push(linktype as u32);
+					}
+					6 if body.len() >= 20 => {
+						let interface = read_u32(offset + 8, little).unwrap_or(0) as usize;
+						let captured = read_u32(offset + 20, little).unwrap_or(0) as usize;
+						if let (Some(&linktype), Some(frame)) = (linktypes.get(interface), body.get(20..20 + captured)) {
+							frames.push((linktype, frame));
+						}
+					}
+					3 if body.len() >= 4 => {
+						if let Some(&linktype) = linktypes.first() {
+							frames.push((linktype, &body[4..]));
+						}
+					}
+					_ => {}
+				}
+				offset += block_len;
+			}
+		}
+		_ => bail!("not a browser history database, pcap, or pcapng capture"),
+	}
+	Ok(frames)
+}
+
+/// Query counts per name from the UDP port-53 queries in a capture.
+pub fn capture_counts(data: &[u8]) -> Result<BTreeMap<String, usize>> {
+	let mut counts = BTreeMap::new();
+	for (linktype, frame) in capture_frames(data)? {
+		let Some(payload) = dns_payload(linktype, frame) else { continue };
+		let Ok(message) = Message::from_vec(payload) else { continue };
+		if message.message_type() != MessageType::Query {
+			continue;
+		}
+		for query in message.queries() {
+			if let Some(name) = keep_name(&query.name().to_ascii()) {
+				*counts.entry(name).or_insert(0) += 1;
+			}
+		}
+	}
+	Ok(counts)
+}
+
+//============================================
+/// Render a `[domains]` table for a --config file.
+pub fn render_toml(set: &str, domains: &[String]) -> String {
+	let mut out = format!("[domains]\n{} = [\n", set);
+	for name in domains {
+		out.push_str(&format!("\t\"{}\",\n", name));
+	}
+	out.push_str("]\n");
+	out
+}
+
+/// Build a personalized domain set from browser history or a capture.
+///
+/// The source type is detected from its first bytes: an SQLite database is
+/// read as Firefox or Chrome history (hosts weighted by visit count), a
+/// pcap or pcapng file as captured DNS traffic (names weighted by query
+/// count). The `top` names are written as a `[domains]` table to `output`,
+/// or printed when None.
+pub fn run_import(source: &str, set: &str, top: usize, output: Option<&str>) -> Result<()> {
+	let data = std::fs::read(source).map_err(|e| anyhow!("{}: {}", source, e))?;
+	let (counts, kind) = if data.starts_with(b"SQLite format 3\0") {
+		(history_counts(source)?, "history entries")
+	} else {
+		(capture_counts(&data).map_err(|e| anyhow!("{}: {}", source, e))?, "DNS queries")
+	};
+	if counts.is_empty() {
+		bail!("no usable domain names in {} (no {} found)", source, kind);
+	}
+	let domains = top_domains(&counts, top);
+	crate::info!("Imported {} distinct names from {} in {}; keeping the top {}",
+		counts.len(), kind, source, domains.len());
+	let toml = render_toml(set, &domains);
+	match output {
+		Some(path) => {
+			std::fs::write(path, toml)?;
+			crate::info!("Domain set '{}' written to {}", set, path);
+		}
+		None => print!("{}", toml),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_keep_name_and_url_hosts() {
+		assert_eq!(keep_name("WWW.Example.COM."), Some("www.example.com".to_string()));
+		assert_eq!(keep_name("printer.local"), None);
+		assert_eq!(keep_name("4.3.2.1.in-addr.arpa"), None);
+		assert_eq!(keep_name("wpad"), None);
+		assert_eq!(keep_name("10.0.0.1"), None);
+		assert_eq!(host_from_url("https://user@news.example.org:8443/a?b#c"), Some("news.example.org"));
+		assert_eq!(host_from_url("about:config"), None);
+	}
+
+	#[test]
+	fn test_top_domains_order() {
+		let counts = BTreeMap::from([("b.com".to_string(), 3), ("a.com".to_string(), 3), ("c.com".to_string(), 9)]);
+		assert_eq!(top_domains(&counts, 2), vec!["c.com".to_string(), "a.com".to_string()]);
+	}
+
+	/// Classic little-endian pcap with one Ethernet/IPv4/UDP frame carrying `dns`.
+	fn pcap_with_query(dns: &[u8]) -> Vec<u8> {
+		let mut udp = vec![0xc0, 0x00, 0x00, 53];
+		udp.extend_from_slice(&((8 + dns.len()) as u16).to_be_bytes());
+		udp.extend_from_slice(&[0, 0]);
+		udp.extend_from_slice(dns);
+		let mut ip = vec![0x45, 0, 0, 0, 0, 0, 0, 0, 64, 17, 0, 0, 192, 168, 1, 2, 192, 168, 1, 1];
+		ip[2..4].copy_from_slice(&((20 + udp.len()) as u16).to_be_bytes());
+		ip.extend_from_slice(&udp);
+		let mut frame = vec![0u8; 12];
+		frame.extend_from_slice(&[0x08, 0x00]);
+		frame.extend_from_slice(&ip);
+		let mut pcap = vec![0xd4, 0xc3, 0xb2, 0xa1, 2, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 0, 0, 1, 0, 0, 0];
+		pcap.extend_from_slice(&[0; 8]);
+		pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+		pcap.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+		pcap.extend_from_slice(&frame);
+		pcap
+	}
+
+	#[test]
+	fn test_capture_counts_reads_queries() {
+		use hickory_proto::op::Query;
+		use hickory_proto::rr::{Name, RecordType};
+		let mut message = Message::new();
+		message.add_query(Query::query(Name::from_ascii("Mail.Example.net.").unwrap(), RecordType::A));
+		let pcap = pcap_with_query(&message.to_vec().unwrap());
+		let counts = capture_counts(&pcap).unwrap();
+		assert_eq!(counts, BTreeMap::from([("mail.example.net".to_string(), 1)]));
+		assert!(capture_counts(b"not a capture").is_err());
+	}
+}
@@ -0,0 +1,50 @@
+//! DNS resolver benchmark library.
+//!
+//! The `rust-dns-benchmark` binary is a thin CLI over these modules.
+//! Embedders can run the same pipeline stages (`bench`) and read per-resolver
+//! results, including raw per-round aggregates, from `record::ResolverRecord`.
+
+pub mod agent;
+pub mod analyze;
+pub mod asn;
+pub mod atlas;
+pub mod autotune;
+pub mod bench;
+pub mod certpin;
+pub mod checkpoint;
+pub mod chart;
+pub mod cli;
+pub mod clock;
+pub mod config_file;
+pub mod consistency;
+pub mod diff;
+pub mod dns;
+pub mod domains;
+pub mod emit;
+#[cfg(any(test, feature = "fault-injection"))]
+pub mod fault;
+pub mod import;
+pub mod localcache;
+pub mod logging;
+pub mod manifest;
+pub mod metrics;
+pub mod misuse;
+pub mod monitor;
+pub mod netns;
+pub mod network;
+pub mod output;
+pub mod ping;
+pub mod proxy;
+pub mod policy;
+pub mod rdns;
+pub mod record;
+pub mod resolver;
+pub mod schedule;
+pub mod source;
+pub mod stamp;
+pub mod stats;
+pub mod stress;
+pub mod telemetry;
+pub mod transport;
+pub mod udp_pool;
+pub mod validate;
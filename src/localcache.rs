@@ -0,0 +1,182 @@
+use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+
+use crate::transport::{Resolver, DEFAULT_DNSSEC, DEFAULT_TIMEOUT_MS};
+
+/// Default local caching resolver (dnsmasq, unbound, Pi-hole)
+pub const DEFAULT_LOCAL_CACHE: &str = "127.0.0.1";
+/// Default warm-cache queries per name after the cold query
+pub const DEFAULT_LOCAL_CACHE_REPEATS: u32 = 3;
+/// Names taken from the uncached set; each one is a fresh cache miss
+const LOCAL_CACHE_NAMES: usize = 20;
+/// Upstream used when none is given and the system has no non-local resolver
+const FALLBACK_UPSTREAM: &str = "1.1.1.1";
+
+/// Latency summary for one leg of the comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LegStats {
+	pub label: String,
+	pub sent: usize,
+	pub answered: usize,
+	pub p50_ms: Option<f64>,
+	pub p95_ms: Option<f64>,
+}
+
+//============================================
+/// Summarize latency samples; None marks a query that got no answer.
+pub fn summarize(label: &str, samples: &[Option<f64>]) -> LegStats {
+	let mut latencies: Vec<f64> = samples.iter().flatten().copied().collect();
+	latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	LegStats {
+		label: label.to_string(),
+		sent: samples.len(),
+		answered: latencies.len(),
+		p50_ms: crate::stats::percentile(&latencies, 50.0),
+		p95_ms: crate::stats::percentile(&latencies, 95.0),
+	}
+}
+
+/// What the local cache buys, from p50s: (hit saving, miss cost) in ms.
+///
+/// The hit saving is how much faster a warm local answer is than the
+/// fastest direct upstream; the miss cost is how much slower a cold local
+/// answer is than going direct. Either is None when a leg never answered.
+pub fn cache_effect(cold: &LegStats, warm: &LegStats, direct: &[LegStats]) -> (Option<f64>, Option<f64>) {
+	let best_direct = direct.iter()
+		.filter_map(|leg| leg.p50_ms)
+		.min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	let saving = best_direct.zip(warm.p50_ms).map(|(d, w)| d - w);
+	let miss_cost = best_direct.zip(cold.p50_ms).map(|(d, c)| c - d);
+	(saving, miss_cost)
+}
+
+//============================================
+/// Upstreams to compare against when --upstream is not given: the system
+/// resolvers that are not on this host (systemd-resolved's stub expands to
+/// its upstreams), or FALLBACK_UPSTREAM.
+fn default_upstreams(local: &Resolver) -> Result<Vec<Resolver>> {
+	let upstreams: Vec<Resolver> = crate::resolver::system_resolvers(false).into_iter()
+		.filter(|r| !r.addr.ip().is_loopback() && r.addr != local.addr)
+		.collect();
+	if !upstreams.is_empty() {
+		return Ok(upstreams);
+	}
+	Ok(vec![crate::resolver::parse_resolver(FALLBACK_UPSTREAM)?])
+}
+
+/// Latency in ms of one A query, or None without an answer.
+async fn timed_query(
+	resolver: &Resolver,
+	domain: &str,
+	connections: &crate::bench::ConnectionPool,
+) -> Option<f64> {
+	let timeout = Duration::from_millis(DEFAULT_TIMEOUT_MS);
+	let result = crate::bench::probe_query(resolver, domain, timeout, DEFAULT_DNSSEC, connections).await;
+	result.outcome.is_ok().then_some(result.latency.as_secs_f64() * 1000.0)
+}
+
+//============================================
+/// Print the per-leg table and the cache verdict.
+fn print_local_cache_report(cold: &LegStats, warm: &LegStats, direct: &[LegStats]) {
+	let fmt_ms = |v: Option<f64>| v.map(|ms| format!("{:.1} ms", ms)).unwrap_or_else(|| "-".to_string());
+	println!("\nLocal cache vs direct upstream");
+	println!("  {:<28} {:>9} {:>10} {:>10}", "Path", "Answered", "p50", "p95");
+	for leg in std::iter::once(cold).chain(std::iter::once(warm)).chain(direct) {
+		println!("  {:<28} {:>4}/{:<4} {:>10} {:>10}",
+			leg.label, leg.answered, leg.sent, fmt_ms(leg.p50_ms), fmt_ms(leg.p95_ms));
+	}
+	let (saving, miss_cost) = cache_effect(cold, warm, direct);
+	match saving {
+		Some(ms) if ms > 0.0 => println!("  Cache hit: {:.1} ms faster than the fastest direct upstream", ms),
+		Some(ms) => println!("  Cache hit: {:.1} ms slower than the fastest direct upstream; the local cache is not helping", -ms),
+		None => println!("  Cache hit: no comparison (a leg never answered)"),
+	}
+	match miss_cost {
+		Some(ms) if ms > 0.0 => println!("  Cache miss: {:.1} ms extra over going direct", ms),
+		Some(_) => println!("  Cache miss: no extra cost over going direct"),
+		None => println!("  Cache miss: no comparison (a leg never answered)"),
+	}
+}
+
+//============================================
+/// Benchmark a local caching resolver cold and warm against direct upstreams.
+///
+/// Each of LOCAL_CACHE_NAMES uncached-set names is sent to the local
+/// resolver once (a cold cache miss, forwarded upstream), then `repeats`
+/// more times (warm cache hits). The same names then go `repeats` times
+/// to each direct upstream; the local resolver's forwarding has just
+/// warmed them there, so the direct leg measures upstream cache hits plus
+/// the network path. Names run one at a time so the cold query for a name
+/// always precedes its warm queries.
+pub async fn run_local_cache(local: &Resolver, upstreams: &[Resolver], repeats: u32) -> Result<()> {
+	let mut domains = crate::domains::load_default_query_domains()
+		.remove("uncached")
+		.unwrap_or_default();
+	if domains.is_empty() {
+		bail!("the built-in uncached domain set is empty");
+	}
+	domains.truncate(LOCAL_CACHE_NAMES);
+	let upstreams = if upstreams.is_empty() { default_upstreams(local)? } else { upstreams.to_vec() };
+	let mut all = vec![local.clone()];
+	all.extend(upstreams.iter().cloned());
+	let connections = Arc::new(crate::bench::build_connection_pool(&all, &Default::default()));
+
+	crate::info!("Local cache benchmark: {} vs {} upstream(s), {} names, {} warm repeats",
+		local.label, upstreams.len(), domains.len(), repeats);
+
+	let mut cold = Vec::new();
+	let mut warm = Vec::new();
+	let mut direct: Vec<Vec<Option<f64>>> = vec![Vec::new(); upstreams.len()];
+	for (i, domain) in domains.iter().enumerate() {
+		cold.push(timed_query(local, domain, &connections).await);
+		for _ in 0..repeats {
+			warm.push(timed_query(local, domain, &connections).await);
+		}
+		for (u, upstream) in upstreams.iter().enumerate() {
+			for _ in 0..repeats {
+				direct[u].push(timed_query(upstream, domain, &connections).await);
+			}
+		}
+		if crate::logging::enabled(crate::logging::Verbosity::Normal) {
+			eprint!("\r  {}/{} names", i + 1, domains.len());
+			let _ = std::io::stderr().flush();
+		}
+	}
+	crate::info!();
+
+	let cold = summarize(&format!("Local {} (cold)", local.addr.ip()), &cold);
+	let warm = summarize(&format!("Local {} (warm)", local.addr.ip()), &warm);
+	let direct: Vec<LegStats> = upstreams.iter().zip(&direct)
+		.map(|(u, samples)| summarize(&format!("Direct {}", u.label), samples))
+		.collect();
+	if cold.answered == 0 && warm.answered == 0 {
+		bail!("no answers from {}; is a local caching resolver running?", local.addr);
+	}
+	print_local_cache_report(&cold, &warm, &direct);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_summarize_counts_unanswered() {
+		let leg = summarize("warm", &[Some(1.0), None, Some(3.0), Some(2.0)]);
+		assert_eq!((leg.sent, leg.answered), (4, 3));
+		assert_eq!(leg.p50_ms, Some(2.0));
+		assert_eq!(summarize("dead", &[None, None]).p50_ms, None);
+	}
+
+	#[test]
+	fn test_cache_effect_against_fastest_direct() {
+		let cold = summarize("cold", &[Some(40.0)]);
+		let warm = summarize("warm", &[Some(0.5)]);
+		let direct = vec![summarize("a", &[Some(20.0)]), summarize("b", &[Some(12.0)]), summarize("c", &[None])];
+		assert_eq!(cache_effect(&cold, &warm, &direct), (Some(11.5), Some(28.0)));
+		assert_eq!(cache_effect(&cold, &warm, &direct[2..]), (None, None));
+	}
+}
@@ -0,0 +1,96 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// How much console output to show (-q, -v, -vv).
+///
+/// Results (tables and reports on stdout) use println! and are never
+/// filtered. Everything else goes to stderr through the macros below so
+/// stdout stays clean for piping: `info!` for progress and run chrome,
+/// `warn!` for warnings (both hidden by -q), `verbose!` for per-resolver
+/// detail and response parse errors (-v), and `debug!` for one line per
+/// query (-vv).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+	Quiet,
+	Normal,
+	Verbose,
+	Debug,
+}
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+impl Verbosity {
+	/// Level from the -q flag and the number of -v flags.
+	pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+		match (quiet, verbose) {
+			(true, _) => Verbosity::Quiet,
+			(false, 0) => Verbosity::Normal,
+			(false, 1) => Verbosity::Verbose,
+			(false, _) => Verbosity::Debug,
+		}
+	}
+}
+
+/// Set the process-wide output level.
+pub fn set_verbosity(level: Verbosity) {
+	VERBOSITY.store(level as u8, Ordering::Relaxed);
+}
+
+/// Whether messages at `level` are shown.
+pub fn enabled(level: Verbosity) -> bool {
+	VERBOSITY.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Progress and run chrome on stderr (hidden by -q).
+#[macro_export]
+macro_rules! info {
+	($($arg:tt)*) => {
+		if $crate::logging::enabled($crate::logging::Verbosity::Normal) {
+			eprintln!($($arg)*);
+		}
+	};
+}
+
+/// A "Warning: " line on stderr (hidden by -q).
+#[macro_export]
+macro_rules! warn {
+	($($arg:tt)*) => {
+		if $crate::logging::enabled($crate::logging::Verbosity::Normal) {
+			eprintln!("Warning: {}", format_args!($($arg)*));
+		}
+	};
+}
+
+/// Detail on stderr, shown with -v.
+#[macro_export]
+macro_rules! verbose {
+	($($arg:tt)*) => {
+		if $crate::logging::enabled($crate::logging::Verbosity::Verbose) {
+			eprintln!($($arg)*);
+		}
+	};
+}
+
+/// Per-query trace on stderr, shown with -vv.
+#[macro_export]
+macro_rules! debug {
+	($($arg:tt)*) => {
+		if $crate::logging::enabled($crate::logging::Verbosity::Debug) {
+			eprintln!($($arg)*);
+		}
+	};
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_verbosity_from_flags() {
+		assert_eq!(Verbosity::from_flags(false, 0), Verbosity::Normal);
+		assert_eq!(Verbosity::from_flags(false, 1), Verbosity::Verbose);
+		assert_eq!(Verbosity::from_flags(false, 3), Verbosity::Debug);
+		// -q wins over -v
+		assert_eq!(Verbosity::from_flags(true, 2), Verbosity::Quiet);
+		assert!(Verbosity::Debug > Verbosity::Normal);
+	}
+}
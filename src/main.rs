@@ -1,24 +1,15 @@
-mod bench;
-mod cli;
-mod dns;
-mod domains;
-mod output;
-mod rdns;
-mod record;
-mod resolver;
-mod stats;
-mod telemetry;
-mod transport;
-
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use std::process::ExitCode;
 use std::time::Duration;
 
-use crate::cli::{BenchLevel, Cli};
-use crate::transport::{BenchmarkConfig, DEFAULT_TIMEOUT_MS, DEFAULT_CONCURRENCY,
+use rust_dns_benchmark::{agent, analyze, autotune, bench, certpin, clock, config_file, domains, logging, metrics, misuse, monitor, netns, output,
+	network, policy, rdns, record, resolver, source, stats, stress, telemetry, udp_pool, validate};
+use rust_dns_benchmark::cli::{BenchLevel, Cli, Command, Concurrency, DomainsCommand, Profile};
+use rust_dns_benchmark::{info, warn};
+use rust_dns_benchmark::transport::{BenchmarkConfig, DnsTransport, DEFAULT_TIMEOUT_MS, DEFAULT_CONCURRENCY,
 	DEFAULT_SPACING_MS, DEFAULT_MAX_RESOLVER_MS,
 	DEFAULT_QUERY_AAAA, DEFAULT_DNSSEC, DEFAULT_INCLUDE_SYSTEM_RESOLVERS,
-	DEFAULT_SORT, DEFAULT_QUICK_ROUNDS, DEFAULT_MEDIUM_ROUNDS,
+	DEFAULT_QUICK_ROUNDS, DEFAULT_MEDIUM_ROUNDS,
 	DEFAULT_SLOW_ROUNDS, DEFAULT_EXHAUSTIVE_ROUNDS};
 
 /// GRC-compatible exit codes for automation and scripting.
@@ -27,8 +18,16 @@ use crate::transport::{BenchmarkConfig, DEFAULT_TIMEOUT_MS, DEFAULT_CONCURRENCY,
 /// 3 = too many resolvers, 4 = no resolvers to test,
 /// 5 = no connectivity, 6 = lost connectivity during test,
 /// 7 = log file creation failure, 8 = log file write failure.
+/// 9 = best resolver slower than --fail-if-slower-than,
+/// 10 = a system resolver intercepts NXDOMAIN (--fail-on-interception).
 fn error_to_exit_code(msg: &str) -> u8 {
-	if msg.contains("No such file") || msg.contains("not found") {
+	if msg.contains("interrupted by Ctrl-C") {
+		130
+	} else if msg.contains("--fail-if-slower-than") {
+		9
+	} else if msg.contains("--fail-on-interception") {
+		10
+	} else if msg.contains("No such file") || msg.contains("not found") {
 		1
 	} else if msg.contains("no IPs") || msg.contains("No IPs") {
 		2
@@ -49,6 +48,22 @@ fn error_to_exit_code(msg: &str) -> u8 {
 	}
 }
 
+/// Current time in seconds since the Unix epoch.
+fn unix_now_secs() -> u64 {
+	std::time::SystemTime::now()
+		.duration_since(std::time::SystemTime::UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or(0)
+}
+
+/// Current time in nanoseconds since the Unix epoch, truncated to 64 bits.
+fn unix_now_nanos() -> u64 {
+	std::time::SystemTime::now()
+		.duration_since(std::time::SystemTime::UNIX_EPOCH)
+		.map(|d| d.as_nanos() as u64)
+		.unwrap_or(0)
+}
+
 #[tokio::main]
 async fn main() -> ExitCode {
 	// Install the rustls ring crypto provider before any TLS connections
@@ -66,9 +81,75 @@ async fn main() -> ExitCode {
 }
 
 async fn run() -> anyhow::Result<()> {
-	let cli = Cli::parse();
+	let matches = Cli::command().get_matches();
+	let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+	// Settings file fills in whatever the command line left unset
+	let file_config = match &cli.config {
+		Some(path) => config_file::load(path)?,
+		None => config_file::FileConfig::default(),
+	};
+	config_file::apply_to_cli(&file_config, &mut cli, &matches)?;
+	logging::set_verbosity(logging::Verbosity::from_flags(cli.quiet, cli.verbose));
 	let level = cli.level;
 
+	// Read-only subcommands run without loading resolvers or sending queries
+	if let Some(Command::Analyze { dir }) = &cli.command {
+		return analyze::run_analyze(dir);
+	}
+	if let Some(Command::Diff { before, after }) = &cli.command {
+		return rust_dns_benchmark::diff::run_diff(before, after);
+	}
+	if let Some(Command::GrafanaDashboard { output }) = &cli.command {
+		return metrics::write_grafana_dashboard(output.as_deref());
+	}
+	if let Some(Command::Domains { action: DomainsCommand::Import { source, set, top, output } }) = &cli.command {
+		return rust_dns_benchmark::import::run_import(source, set, *top as usize, output.as_deref());
+	}
+	if let Some(Command::Agent { listen, token }) = &cli.command {
+		return agent::run_agent(listen, token.as_deref()).await;
+	}
+	// Remote agents run the benchmark from their own networks
+	if !cli.agents.is_empty() {
+		if cli.command.is_some() {
+			anyhow::bail!("--agent only applies to benchmark runs");
+		}
+		return agent::run_coordinator(&cli.agents, cli.agent_token.as_deref(), cli.output.as_deref()).await;
+	}
+
+	// Source address and interface for every query socket; a --netns batch
+	// checks them inside each namespace instead, where the interface lives
+	if (cli.source_ip.is_some() || cli.interface.is_some()) && cli.netns.is_empty() {
+		let binding = source::SourceBinding { ip: cli.source_ip, interface: cli.interface.clone() };
+		source::check(&binding).await?;
+		source::set_source(binding);
+	}
+	// Network name for every output; detected after the binding, which picks the interface
+	network::set_network(network::detect(cli.label.as_deref()));
+	// Server for resolver hostnames; parsing any resolver below may use it
+	resolver::set_bootstrap(cli.bootstrap);
+	resolver::set_bootstrap_family(resolver::BootstrapFamily::from_filter(cli.ipv4_only, cli.ipv6_only, cli.dual_stack));
+
+	// Stress mode targets a single resolver named on the subcommand
+	if let Some(Command::Stress { resolver, duration, target_qps, allow_public }) = &cli.command {
+		let resolver = resolver::parse_resolver(resolver)?;
+		return stress::run_stress(&resolver, *duration, *target_qps, *allow_public).await;
+	}
+	if let Some(Command::LocalCache { local, upstream, repeats }) = &cli.command {
+		let local = resolver::parse_resolver(local)?;
+		let upstreams = upstream.iter()
+			.map(|u| resolver::parse_resolver(u))
+			.collect::<anyhow::Result<Vec<_>>>()?;
+		return rust_dns_benchmark::localcache::run_local_cache(&local, &upstreams, *repeats).await;
+	}
+
+	// Network namespace batch: re-run this command inside each namespace
+	if !cli.netns.is_empty() {
+		if cli.command.is_some() {
+			anyhow::bail!("--netns only applies to benchmark runs");
+		}
+		return netns::run_batch(&cli.netns, cli.output.as_deref());
+	}
+
 	// Collect resolvers from all sources
 	let mut resolvers = Vec::new();
 	let user_specified = !cli.resolvers.is_empty() || cli.resolver_file.is_some();
@@ -88,22 +169,22 @@ async fn run() -> anyhow::Result<()> {
 	if needs_global {
 		match resolver::download_exhaustive_csv().await {
 			Ok(csv_resolvers) if !csv_resolvers.is_empty() => {
-				println!("{} mode: loaded {} resolvers from public-dns.info CSV", level, csv_resolvers.len());
+				info!("{} mode: loaded {} resolvers from public-dns.info CSV", level, csv_resolvers.len());
 				resolvers.extend(csv_resolvers);
 			}
 			Ok(_) | Err(_) => {
 				// Fallback to local scan_global.txt or download plain text list
-				println!("CSV download failed or empty, falling back to local resolver list");
+				info!("CSV download failed or empty, falling back to local resolver list");
 				let mut global_list = resolver::scan_global_resolvers();
 				if global_list.is_empty() {
-					println!("Downloading global nameserver list from public-dns.info...");
+					info!("Downloading global nameserver list from public-dns.info...");
 					let global_path = resolver::download_global_list().await?;
 					global_list = resolver::read_resolver_file(&global_path)?;
 				}
 				if global_list.is_empty() {
 					anyhow::bail!("Global scan list is empty after download. Cannot run {} mode.", level);
 				}
-				println!("{} mode: loading {} global public resolvers from fallback", level, global_list.len());
+				info!("{} mode: loading {} global public resolvers from fallback", level, global_list.len());
 				resolvers.extend(global_list);
 			}
 		}
@@ -119,21 +200,75 @@ async fn run() -> anyhow::Result<()> {
 
 	// System resolvers (compile-time default: always included)
 	if DEFAULT_INCLUDE_SYSTEM_RESOLVERS {
-		let mut sys = resolver::system_resolvers();
+		let mut sys = resolver::system_resolvers(cli.include_stub);
 		// Deduplicate: skip system resolvers already in the list
-		sys.retain(|s| !resolvers.iter().any(|r| r.addr.ip() == s.addr.ip()));
+		sys.retain(|s| !resolvers.iter().any(|r| r.key() == s.key()));
 		resolvers.extend(sys);
 	}
 
-	// Deduplicate all resolvers by IP address, keeping first occurrence
-	let mut seen_ips = std::collections::HashSet::new();
-	resolvers.retain(|r| seen_ips.insert(r.addr.ip()));
+	// Home router as a resolver candidate
+	if cli.gateway_resolver {
+		match resolver::gateway_resolver() {
+			Some(gw) if resolvers.iter().any(|r| r.key() == gw.key()) => {
+				info!("Gateway resolver: {} is already in the list", gw.addr.ip());
+			}
+			Some(gw) => {
+				info!("Gateway resolver: adding {}", gw.addr.ip());
+				resolvers.push(gw);
+			}
+			None => warn!("--gateway-resolver: no IPv4 default gateway found"),
+		}
+	}
+
+	// Drop unusable addresses and repeats from the combined sources
+	let cleanup = resolver::clean_resolver_list(&mut resolvers);
+	resolver::print_list_cleanup(&cleanup, user_specified);
+
+	// Address family: pair each provider across families, or keep one family
+	if cli.dual_stack {
+		let builtin: Vec<_> = resolver::default_resolvers().into_iter()
+			.chain(resolver::default_ipv6_resolvers())
+			.collect();
+		let added = resolver::add_dual_stack_partners(&mut resolvers, &builtin);
+		info!("Dual-stack: added {} partner address(es) from the built-in lists", added);
+	}
+	if cli.ipv4_only || cli.ipv6_only {
+		let before = resolvers.len();
+		resolvers.retain(|r| r.addr.is_ipv6() == cli.ipv6_only);
+		info!("{}: kept {} of {} resolvers",
+			if cli.ipv6_only { "--ipv6-only" } else { "--ipv4-only" }, resolvers.len(), before);
+	}
+	// A source address only reaches resolvers of its own family
+	if let Some(ip) = cli.source_ip {
+		let before = resolvers.len();
+		resolvers.retain(|r| r.addr.is_ipv6() == ip.is_ipv6());
+		if resolvers.len() < before {
+			info!("--source-ip {}: kept {} of {} resolvers (IPv{} only)", ip, resolvers.len(), before, if ip.is_ipv6() { 6 } else { 4 });
+		}
+	}
 
 	// Bail early if no resolvers to test
 	if resolvers.is_empty() {
 		anyhow::bail!("No resolvers to test. Provide resolvers via -r, -f, or system defaults.");
 	}
 
+	// Monitor mode replaces the one-shot benchmark pipeline
+	if let Some(Command::Monitor { interval, schedule, report_every, count, down_after, notify_webhook, notify_p50_ms }) = &cli.command {
+		let settings = monitor::MonitorSettings {
+			interval: Duration::from_secs(*interval),
+			schedule: schedule.as_deref().map(rust_dns_benchmark::schedule::CronSchedule::parse).transpose()?,
+			report_every: *report_every,
+			count: *count,
+			down_after: *down_after,
+			influx: cli.influx.clone(),
+			notify: notify_webhook.as_ref().map(|url| monitor::NotifySettings {
+				url: url.clone(),
+				p50_threshold_ms: *notify_p50_ms,
+			}),
+		};
+		return monitor::run_monitor(&resolvers, &settings).await;
+	}
+
 	// Load query domain categories from built-in defaults
 	let mut categories = domains::load_default_query_domains();
 
@@ -142,11 +277,42 @@ async fn run() -> anyhow::Result<()> {
 		categories.remove("dnssec");
 	}
 
-	// Load NXDOMAIN test domains (used for characterization, not benchmarking)
+	// Load NXDOMAIN test domains (characterization probes and the NXDOMAIN latency set)
 	let nxdomain_domains = domains::default_nxdomain_domains();
-
-	// Sort mode (compile-time default)
-	let sort_mode = stats::parse_sort_mode(DEFAULT_SORT);
+	categories.insert(domains::NXDOMAIN_SET.to_string(), nxdomain_domains.clone());
+	file_config.apply_domain_sets(&mut categories);
+
+	// Chosen up front so --warm-source sampling repeats with the query order
+	let seed = cli.seed.unwrap_or_else(rand::random);
+	if let Some(source) = &cli.warm_source {
+		let pool = domains::load_warm_pool(source).await?;
+		let count = categories.get("cached").map_or(0, |set| set.len());
+		let warm = domains::sample_domains(&pool, count, seed);
+		info!("Warm set: {} names sampled from the top {} of the Tranco list", warm.len(), pool.len());
+		categories.insert("cached".to_string(), warm);
+	}
+	// The listed names stand in for the summary and qualification pass; the
+	// benchmark draws a new label for every query, mixed with a per-run nonce
+	// so a fixed --seed does not resend an earlier run's names. The listed
+	// names follow the seed alone, so a --resume fingerprint still matches.
+	if let Some(zone) = &cli.cold_zone {
+		domains::set_cold_nonce(unix_now_nanos());
+		let count = categories.get(domains::COLD_SET).map_or(0, |set| set.len());
+		let mut rng: rand::rngs::StdRng = rand::SeedableRng::seed_from_u64(seed);
+		categories.insert(domains::COLD_SET.to_string(), (0..count).map(|_| domains::cold_name(zone, 0, &mut rng)).collect());
+		info!("Cold set: {} random names under {} per resolver and round", count, zone);
+	}
+	let asn_db = cli.asn_db.as_deref().map(rust_dns_benchmark::asn::AsnDb::load).transpose()?;
+
+	// Sort mode from --rank-by (defaults to DEFAULT_SORT)
+	let sort_mode = stats::parse_sort_mode(&cli.rank_by);
+	if let stats::SortMode::Category(name) = &sort_mode {
+		if !categories.contains_key(name) {
+			let sets: Vec<&str> = categories.keys().map(|k| k.as_str()).collect();
+			anyhow::bail!("Unknown --rank-by '{}'; use score, stability, name, or one of: {}",
+				name, sets.join(", "));
+		}
+	}
 
 	// Determine rounds: user override via --rounds, or level default
 	let default_rounds = match level {
@@ -160,21 +326,112 @@ async fn run() -> anyhow::Result<()> {
 	// Auto-enable discovery when resolver list is large (>20)
 	let discover = needs_global || resolvers.len() > 20;
 
-	let config = BenchmarkConfig {
+	// Fixed --concurrency overrides the default; "auto" is tuned after --no-test
+	let max_inflight = match cli.concurrency {
+		Some(Concurrency::Fixed(n)) => n,
+		Some(Concurrency::Auto) | None => DEFAULT_CONCURRENCY,
+	};
+	let mut config = BenchmarkConfig {
 		rounds,
 		timeout: Duration::from_millis(DEFAULT_TIMEOUT_MS),
-		max_inflight: DEFAULT_CONCURRENCY,
+		retries: cli.retries,
+		late_grace: Duration::from_millis(cli.late_grace_ms),
+		max_inflight,
+		per_resolver_inflight: cli.per_resolver_inflight,
+		per_resolver_qps: cli.per_resolver_qps,
 		inter_query_spacing: Duration::from_millis(DEFAULT_SPACING_MS),
-		query_aaaa: DEFAULT_QUERY_AAAA,
-		seed: None,
+		// The server profile scores AAAA lookups, so it always sends them
+		query_aaaa: DEFAULT_QUERY_AAAA || cli.profile == Some(Profile::Server),
+		query_https: cli.https_rr,
+		connection_mode: cli.connection_mode,
+		doh_http: cli.doh_http,
+		proxy: cli.proxy.clone(),
+		// A random seed is still recorded so the run can be repeated
+		seed: Some(seed),
+		cold_zone: cli.cold_zone.clone(),
+		adaptive_timeout: cli.adaptive_timeout,
 		dnssec: DEFAULT_DNSSEC,
+		checkpoint: cli.checkpoint.clone(),
+		resume: cli.resume,
+		checkpoint_fingerprint: String::new(),
 		discover,
 		level,
 		max_resolver_ms: DEFAULT_MAX_RESOLVER_MS,
 		sort_mode,
+		score_weights: file_config.score_weights(stats::ScoreWeights::for_profile(cli.profile)),
+		profile: cli.profile,
+		identity_rounds: cli.identity_probe,
 		telemetry: telemetry::TelemetryLog::new(true),
+		udp_pool: None,
+		raw_log: telemetry::RawQueryLog::disabled(),
 	};
 
+	// Taken over the input list: discovery below keeps a latency-dependent subset
+	if config.checkpoint.is_some() {
+		config.checkpoint_fingerprint = rust_dns_benchmark::checkpoint::fingerprint(&resolvers, &categories, &config);
+	}
+
+	// Fail fast on flag combinations that would misbehave at runtime
+	validate::validate_config(&config, validate::open_file_limit())?;
+	for warning in validate::config_warnings(&config, resolvers.len()) {
+		info!("Note: {}", warning);
+	}
+
+	// Characterize mode: probes only, then a capability matrix
+	if let Some(Command::Characterize { output }) = &cli.command {
+		let started_secs = unix_now_secs();
+		let mut records: Vec<record::ResolverRecord> = resolvers.iter().cloned()
+			.map(record::ResolverRecord::new)
+			.collect();
+		bench::run_characterization(&mut records, &config, &nxdomain_domains).await;
+		bench::run_capability_probes(&mut records, &config).await;
+		bench::run_identity_probe(&mut records, &config).await;
+		rust_dns_benchmark::consistency::run_consistency_check(&mut records, &config).await;
+		// Unreachable resolvers were dropped by characterization; list them anyway
+		for r in &resolvers {
+			if !records.iter().any(|rec| rec.resolver.addr == r.addr) {
+				let mut rec = record::ResolverRecord::new(r.clone());
+				rec.characterization = Some(record::CharacterizationResult {
+					reachable: false,
+					attempts_used: rust_dns_benchmark::transport::DEFAULT_CHAR_ATTEMPTS,
+					successes: 0,
+					latency_ms: None,
+					intercepts_nxdomain: None,
+					rebinding_protection: None,
+					validates_dnssec: None,
+					answers_known_record: None,
+					filtering: None,
+					edns_udp_size: None,
+					server_cookie: None,
+					preserves_case: None,
+					edns_buffer: None,
+					large_responses: None,
+					rd_zero: None,
+					https_rr: None,
+					pads_responses: None,
+					dns64: None,
+					ttl_rewrite: None,
+					coalescing: None,
+				});
+				records.push(rec);
+			}
+		}
+		if cli.stdout {
+			// Machine-readable matrix only; stdout carries nothing else
+			let manifest = rust_dns_benchmark::manifest::RunManifest::build(
+				&config, &resolvers, &categories, &std::env::args().collect::<Vec<_>>(), started_secs, unix_now_secs(),
+			);
+			output::write_capability_stdout(&records, cli.format, &manifest)?;
+		} else {
+			output::print_capability_matrix(&records);
+			output::print_consistency(&records);
+		}
+		if let Some(path) = output {
+			output::write_capability_csv(path, &records)?;
+		}
+		return Ok(());
+	}
+
 	// Log config to telemetry
 	config.telemetry.log_config(rounds, DEFAULT_SPACING_MS, &level.to_string(), resolvers.len());
 
@@ -187,35 +444,96 @@ async fn run() -> anyhow::Result<()> {
 
 	// Early exit if --no-test was requested
 	if cli.no_test {
-		println!("--no-test: exiting without running benchmark.");
+		info!("--no-test: exiting without running benchmark.");
 		return Ok(());
 	}
 
-	// Build DoH client pool for any DoH resolvers
-	let doh_clients = bench::build_doh_client_pool(&resolvers);
+	// Developer fault injection (fault-injection feature builds only)
+	#[cfg(feature = "fault-injection")]
+	{
+		let faults = rust_dns_benchmark::fault::FaultConfig {
+			drop_rate: cli.inject_drop,
+			delay_rate: cli.inject_delay,
+			delay: Duration::from_millis(cli.inject_delay_ms),
+			corrupt_rate: cli.inject_corrupt,
+		};
+		if faults.is_active() {
+			info!("WARNING: fault injection active (drop {}, delay {} x {} ms, corrupt {}); results are not real",
+				faults.drop_rate, faults.delay_rate, cli.inject_delay_ms, faults.corrupt_rate);
+			rust_dns_benchmark::fault::set_faults(faults);
+		}
+	}
+
+	// Host clock status goes into telemetry so timestamps can be trusted or discounted
+	let clock_status = clock::read_clock_status();
+	config.telemetry.log_clock(&clock_status);
+	if let Some(path) = &cli.raw_output {
+		config.raw_log = telemetry::RawQueryLog::create(path, &clock_status)
+			.map_err(|e| anyhow::anyhow!("Failed to create log file {}: {}", path, e))?;
+		if clock_status.synchronized == Some(false) {
+			info!("Note: host clock is not NTP-synchronized; raw export wall times may drift");
+		}
+	}
+
+	if cli.udp_pool {
+		let pool = udp_pool::UdpSocketPool::bind(udp_pool::DEFAULT_UDP_POOL_SOCKETS).await
+			.map_err(|e| anyhow::anyhow!("Failed to bind UDP socket pool: {}", e))?;
+		info!("UDP socket pool: {} shared sockets for benchmark queries", pool.socket_count());
+		config.udp_pool = Some(std::sync::Arc::new(pool));
+	}
+
+	// Ramp concurrency against a control resolver before measuring anything
+	if cli.concurrency == Some(Concurrency::Auto) {
+		config.max_inflight = autotune::run_autotune(&resolvers, &config).await;
+		info!();
+	}
+
+	// A SOCKS or HTTP proxy carries TCP only
+	if let Some(proxy) = &config.proxy {
+		let direct = resolvers.iter().filter(|r| r.transport == DnsTransport::Udp).count();
+		if direct > 0 {
+			warn!("--proxy {} carries DoT and DoH only; {} UDP resolvers are queried directly", proxy.display(), direct);
+		}
+	}
+
+	// Connection pool shared by every DoT and DoH query of the run
+	let connections = bench::build_connection_pool(&resolvers, &bench::ConnectionOptions::from(&config));
 
 	// Wrap all resolvers into records -- single creation point for the entire pipeline
-	let mut records: Vec<record::ResolverRecord> = resolvers.into_iter()
+	// The input list stays for the results manifest
+	let mut records: Vec<record::ResolverRecord> = resolvers.iter().cloned()
 		.map(record::ResolverRecord::new)
 		.collect();
 
 	// Track phase timings for summary
 	let mut phase_timings: Vec<output::PhaseTimingEntry> = Vec::new();
 	let pipeline_start = std::time::Instant::now();
+	let run_started_secs = unix_now_secs();
+
+	// --resume: benchmark the resolvers the checkpoint saved; screening the
+	// list again would keep a different latency-dependent set
+	let resumed = match (&config.checkpoint, config.resume) {
+		(Some(path), true) => rust_dns_benchmark::checkpoint::Checkpoint::read_for_resume(path, &config.checkpoint_fingerprint)?,
+		_ => None,
+	};
+	if let Some(saved) = &resumed {
+		records.retain(|r| saved.resolvers.contains(&r.resolver.key()));
+		info!("Resuming with the {} resolvers saved in the checkpoint; skipping discovery and qualification", records.len());
+	}
 
 	// Discovery: reachability screen for large resolver lists
-	if config.discover {
+	if config.discover && resumed.is_none() {
 		let phase_start = std::time::Instant::now();
 		let before = records.len();
 		bench::run_discovery(
-			&mut records, &categories, &config, &doh_clients,
+			&mut records, &categories, &config, &connections,
 		).await;
 		// Retain only records that passed discovery (missing result = failure)
 		records.retain(|r| r.discovery.as_ref().is_some_and(|d| d.passed));
 		let discovery_elapsed = phase_start.elapsed();
 		config.telemetry.log_phase("discovery", discovery_elapsed.as_secs(), before, records.len());
 		phase_timings.push(("Discovery", discovery_elapsed, Some((before, records.len()))));
-		println!();
+		info!();
 	}
 
 	let post_discovery_count = records.len();
@@ -225,20 +543,60 @@ async fn run() -> anyhow::Result<()> {
 	let char_phase_start = std::time::Instant::now();
 	let char_before = records.len();
 	rdns::resolve_ptr_names(&mut records, config.timeout).await;
+	if let Some(db) = &asn_db {
+		rust_dns_benchmark::asn::annotate(&mut records, db);
+	}
+	// Before any filter: an intercepted resolver may fail every later probe
+	certpin::run_cert_pin_check(&mut records, &config).await;
 	bench::run_characterization(&mut records, &config, &nxdomain_domains).await;
+	if config.score_weights.filtering_penalty_ms > 0.0 {
+		bench::run_filtering_probe(&mut records, &config).await;
+	}
 	let char_elapsed = char_phase_start.elapsed();
 	config.telemetry.log_phase("characterization", char_elapsed.as_secs(), char_before, records.len());
 	phase_timings.push(("Characterization", char_elapsed, Some((char_before, records.len()))));
 
+	// Checked now: a filter below may drop the intercepting resolver from the results
+	let intercepting_system: Vec<String> = records.iter()
+		.filter(|r| r.resolver.is_system && r.intercepts_nxdomain())
+		.map(|r| r.resolver.label.clone())
+		.collect();
+	if cli.drop_intercepting {
+		let before_count = records.len();
+		records.retain(|r| !r.intercepts_nxdomain());
+		let dropped = before_count - records.len();
+		if dropped > 0 {
+			info!("Dropped {} resolver(s) that intercept NXDOMAIN", dropped);
+		}
+		if records.is_empty() {
+			anyhow::bail!("No resolvers left to benchmark: every resolver intercepts NXDOMAIN (--drop-intercepting)");
+		}
+	}
+	// Large imported lists are full of home routers answering the whole internet
+	if config.discover || cli.exclude_suspect_open {
+		misuse::run_misuse_probe(&mut records, &config).await;
+		let suspects = records.iter().filter(|r| r.misuse.as_ref().is_some_and(|m| m.suspect())).count();
+		if cli.exclude_suspect_open {
+			records.retain(|r| !r.misuse.as_ref().is_some_and(|m| m.suspect()));
+			if suspects > 0 {
+				info!("Dropped {} suspected abused open resolver(s)", suspects);
+			}
+			if records.is_empty() {
+				anyhow::bail!("No resolvers left to benchmark: every resolver looks like an abused open resolver (--exclude-suspect-open)");
+			}
+		} else if suspects > 0 {
+			info!("Note: {} resolver(s) look like home routers or compromised devices; --exclude-suspect-open leaves them out", suspects);
+		}
+	}
 	let post_char_count = records.len();
 	config.telemetry.log_pipeline("after_characterization", post_char_count);
 
 	// Medium mode: run qualification pass and promote finalists
-	if level == BenchLevel::Medium {
+	if level == BenchLevel::Medium && resumed.is_none() {
 		let qual_start = std::time::Instant::now();
 		let qual_before = records.len();
 		bench::run_qualification(
-			&mut records, &categories, &config, &doh_clients,
+			&mut records, &categories, &config, &connections,
 		).await;
 		// Retain only promoted records (missing qualification = not promoted)
 		records.retain(|r| r.qualification.as_ref().is_some_and(|q| q.promoted));
@@ -246,16 +604,39 @@ async fn run() -> anyhow::Result<()> {
 		config.telemetry.log_pipeline("after_qualification", records.len());
 	}
 
-	// Run benchmark (writes BenchmarkResult onto existing records in place)
-	println!("Running benchmark...");
+	// Warm set: either prime every cache explicitly, or classify which
+	// domains each resolver already had cached (priming would make that moot)
+	if let Some(warm_domains) = categories.get("cached") {
+		let warm_start = std::time::Instant::now();
+		if cli.prime_cache {
+			bench::run_cache_prime(&records, warm_domains, &config, &connections).await;
+			phase_timings.push(("Cache prime", warm_start.elapsed(), None));
+		} else {
+			bench::run_warm_probe(&mut records, warm_domains, &config, &connections).await;
+			phase_timings.push(("Warm probe", warm_start.elapsed(), None));
+		}
+		info!();
+	}
+
+	if let Some(saved) = &resumed {
+		let lost = saved.resolvers.len().saturating_sub(records.len());
+		if lost > 0 {
+			warn!("{} checkpointed resolver(s) failed this run's reachability or filter checks; their saved rounds are dropped", lost);
+		}
+	}
+
+	// Run benchmark (writes BenchmarkResult onto existing records in place);
+	// from here on Ctrl-C ends the run early with partial results
+	bench::install_interrupt_handler();
+	info!("Running benchmark...");
 	let bench_start = std::time::Instant::now();
 	if level == BenchLevel::Slow {
 		bench::run_staged_benchmark(
-			&mut records, &categories, &config, &doh_clients,
+			&mut records, &categories, &config, &connections,
 		).await?;
 	} else {
 		bench::run_benchmark(
-			&mut records, &categories, &config, &doh_clients,
+			&mut records, &categories, &config, &connections,
 		).await?;
 	}
 	phase_timings.push(("Benchmark", bench_start.elapsed(), None));
@@ -267,7 +648,7 @@ async fn run() -> anyhow::Result<()> {
 	});
 	let low_success_count = before_count - records.len();
 	if low_success_count > 0 {
-		println!(
+		info!(
 			"Filtered {} resolver(s) with success rate < 50%",
 			low_success_count,
 		);
@@ -286,7 +667,7 @@ async fn run() -> anyhow::Result<()> {
 		});
 		let filtered_count = before_count - records.len();
 		if filtered_count > 0 {
-			println!(
+			info!(
 				"Filtered {} resolver(s) with {} p50 > {} ms",
 				filtered_count, cat_name, config.max_resolver_ms as u64,
 			);
@@ -301,13 +682,14 @@ async fn run() -> anyhow::Result<()> {
 	pinned.append(&mut rest);
 	records = pinned;
 
-	// Re-rank after filtering and pinning
+	// Re-rank after filtering and pinning; ties are between new neighbors
 	for (i, r) in records.iter_mut().enumerate() {
 		if let Some(ref mut bm) = r.benchmark {
 			bm.rank = i + 1;
 			bm.tie_group = None;
 		}
 	}
+	bench::detect_ties(&mut records);
 
 	// Log final records to telemetry with full per-category breakdown
 	for r in &records {
@@ -340,13 +722,105 @@ async fn run() -> anyhow::Result<()> {
 	let total_elapsed = pipeline_start.elapsed();
 	output::print_phase_timing(&phase_timings, total_elapsed);
 
+	// Optional network diagnostics (after ranking so they never affect the
+	// score); an interrupted run goes straight to its partial results
+	let interrupted = bench::interrupted();
+	if cli.path_probe && !interrupted {
+		if categories.contains_key("cached") {
+			bench::run_path_probe(&mut records, &config).await;
+		} else {
+			warn!("--path-probe compares against the cached domain set, which this run did not query; skipped");
+		}
+	}
+	if cli.traceroute && !interrupted {
+		bench::run_traceroute(&mut records, &config).await;
+	}
+	if cli.loss_probe && !interrupted {
+		bench::run_loss_probe(&mut records, &config).await;
+	}
+	// Encrypted transports pay connection setup on top of query latency
+	if records.iter().any(|r| r.resolver.transport != DnsTransport::Udp) && !interrupted {
+		bench::run_tls_probe(&mut records, &config).await;
+	}
+	if cli.doh_http_compare && !interrupted {
+		bench::run_doh_http_probe(&mut records, &config).await;
+	}
+	if cli.identity_probe && !interrupted {
+		bench::run_identity_probe(&mut records, &config).await;
+	}
+	if cli.consistency_check && !interrupted {
+		rust_dns_benchmark::consistency::run_consistency_check(&mut records, &config).await;
+	}
+	if let (Some(key), false) = (&cli.ripe_atlas_key, interrupted) {
+		let options = rust_dns_benchmark::atlas::AtlasOptions {
+			key: key.clone(),
+			probes: cli.ripe_atlas_probes,
+			country: cli.ripe_atlas_country.clone(),
+			api: rust_dns_benchmark::atlas::ATLAS_API.to_string(),
+		};
+		// Atlas is a comparison on top of the local results; its failure is not the run's
+		if let Err(e) = rust_dns_benchmark::atlas::run_atlas(&mut records, &categories, &options).await {
+			warn!("RIPE Atlas: {:#}", e);
+		}
+	}
+
+	let manifest = rust_dns_benchmark::manifest::RunManifest::build(
+		&config, &resolvers, &categories, &std::env::args().collect::<Vec<_>>(), run_started_secs, unix_now_secs(),
+	);
+
 	// Print results table and conclusions
-	output::print_results_table(&records);
-	output::print_conclusions(&records);
+	if cli.stdout {
+		// Machine-readable results only; stdout carries nothing else
+		output::write_results_stdout(&records, cli.format, &manifest)?;
+	} else {
+		output::print_results_table(&records);
+		if cli.by_provider {
+			output::print_provider_ranking(&records, config.timeout.as_millis() as f64, &config.score_weights);
+		}
+		// Quiet runs keep only the table and the reports asked for by flag
+		if logging::enabled(logging::Verbosity::Normal) {
+			output::print_recommendation(&records);
+			output::print_conclusions(&records);
+			output::print_significance(&records);
+			output::print_score_sensitivity(&records, config.timeout.as_millis() as f64, &config.score_weights);
+			output::print_query_types(&records);
+			output::print_late_arrivals(&records);
+			output::print_round_drift(&records);
+			output::print_tls_setup(&records);
+			output::print_cert_pins(&records);
+			output::print_unexpected_nxdomain(&records);
+		}
+		output::print_path_probe(&records);
+		output::print_doh_http(&records);
+		output::print_atlas(&records);
+		output::print_identity(&records);
+		output::print_consistency(&records);
+		if cli.histogram {
+			output::print_histograms(&records);
+		}
+		if let Some(kind) = cli.chart {
+			output::print_charts(&records, kind);
+		}
+		if let Some(format) = cli.emit_config {
+			rust_dns_benchmark::emit::print_emitted_config(&records, format, cli.emit_top as usize);
+		}
+	}
 
 	// Write CSV if requested
-	if let Some(path) = &cli.output {
-		output::write_csv(path, &records)?;
+	if let (Some(path), true) = (&cli.output, cli.append) {
+		let run = output::RunLabel::new(run_started_secs, &network::network().label);
+		output::append_csv(path, &records, &run)?;
+	} else if let Some(path) = &cli.output {
+		output::write_csv(path, &records, &manifest)?;
+	}
+	if let Some(path) = &cli.policy {
+		policy::write_policy(path, &records)?;
+	}
+	if let Some(path) = &cli.prometheus {
+		metrics::write_prometheus(path, &records)?;
+	}
+	if let Some(target) = &cli.influx {
+		metrics::write_influx(target, &metrics::render_influx(&records, unix_now_secs())).await?;
 	}
 
 	// Save resolver list if requested
@@ -354,5 +828,27 @@ async fn run() -> anyhow::Result<()> {
 		output::write_resolver_list(path, &records)?;
 	}
 
+	// Partial results are written; scripts still see the run did not finish
+	if interrupted {
+		anyhow::bail!("interrupted by Ctrl-C; results are partial");
+	}
+
+	// Scripting gates: fail after every output is written
+	if cli.fail_on_interception && !intercepting_system.is_empty() {
+		anyhow::bail!("--fail-on-interception: system resolver(s) intercept NXDOMAIN: {}",
+			intercepting_system.join(", "));
+	}
+	if let Some(limit) = cli.fail_if_slower_than {
+		let best = records.iter()
+			.filter_map(|r| r.benchmark.as_ref().map(|bm| (r.resolver.label.as_str(), bm.overall_score)))
+			.min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+		match best {
+			Some((label, score)) if score > limit => anyhow::bail!(
+				"--fail-if-slower-than: best resolver {} scored {:.1} ms, above {} ms", label, score, limit),
+			None => anyhow::bail!("--fail-if-slower-than: no resolver has a benchmark score"),
+			Some(_) => {}
+		}
+	}
+
 	Ok(())
 }
@@ -0,0 +1,309 @@
+use std::fmt::Write as _;
+
+use anyhow::Result;
+
+use crate::record::ResolverRecord;
+use crate::telemetry::json_escape;
+
+/// Prefix shared by every exported metric name
+pub const METRIC_PREFIX: &str = "dns_benchmark";
+
+/// One exported gauge: name (without prefix), help text, and Grafana unit.
+pub struct MetricDef {
+	pub name: &'static str,
+	pub help: &'static str,
+	/// Grafana field unit id used by the generated dashboard
+	pub unit: &'static str,
+	/// Carries a `set` label (one series per domain set)
+	pub per_set: bool,
+}
+
+/// Every gauge written by `write_prometheus`; the Grafana dashboard is built
+/// from this same table so panel queries always match exported names.
+pub const METRICS: &[MetricDef] = &[
+	MetricDef { name: "score_ms", help: "Overall score (lower is better)", unit: "ms", per_set: false },
+	MetricDef { name: "rank", help: "Rank in the final results (1 is best)", unit: "none", per_set: false },
+	MetricDef { name: "success_ratio", help: "Fraction of scored queries answered", unit: "percentunit", per_set: false },
+	MetricDef { name: "stability_cv", help: "Mean coefficient of variation across scored sets", unit: "percentunit", per_set: false },
+	MetricDef { name: "set_p50_ms", help: "Median latency per domain set", unit: "ms", per_set: true },
+	MetricDef { name: "set_p95_ms", help: "95th percentile latency per domain set", unit: "ms", per_set: true },
+	MetricDef { name: "set_timeout_ratio", help: "Fraction of queries that timed out per domain set", unit: "percentunit", per_set: true },
+];
+
+/// Full metric name with the shared prefix.
+pub fn metric_name(def: &MetricDef) -> String {
+	format!("{}_{}", METRIC_PREFIX, def.name)
+}
+
+/// Escape a Prometheus label value (backslash, quote, newline).
+fn label_escape(s: &str) -> String {
+	s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+//============================================
+/// Render benchmark results in the Prometheus text exposition format.
+///
+/// Every series carries `resolver` (IP), `label`, and `transport` labels,
+/// plus `network` when the run has a network label.
+pub fn render_prometheus(records: &[ResolverRecord], timestamp_secs: u64) -> String {
+	let network = crate::network::network().label;
+	let mut out = String::new();
+	for def in METRICS {
+		let name = metric_name(def);
+		let _ = writeln!(out, "# HELP {} {}", name, def.help);
+		let _ = writeln!(out, "# TYPE {} gauge", name);
+		for r in records {
+			let bm = match &r.benchmark {
+				Some(bm) => bm,
+				None => continue,
+			};
+			let mut labels = format!("resolver=\"{}\",label=\"{}\",transport=\"{}\"",
+				r.resolver.addr.ip(), label_escape(&r.resolver.label), r.resolver.transport);
+			if !network.is_empty() {
+				labels += &format!(",network=\"{}\"", label_escape(&network));
+			}
+			if def.per_set {
+				for (set, stats) in &bm.categories {
+					let value = match def.name {
+						"set_p50_ms" => stats.p50_ms,
+						"set_p95_ms" => stats.p95_ms,
+						_ if stats.total_count == 0 => continue,
+						_ => stats.timeout_count as f64 / stats.total_count as f64,
+					};
+					let _ = writeln!(out, "{}{{{},set=\"{}\"}} {}", name, labels, label_escape(set), value);
+				}
+				continue;
+			}
+			let value = match def.name {
+				"score_ms" => bm.overall_score,
+				"rank" => bm.rank as f64,
+				"success_ratio" => bm.success_rate / 100.0,
+				_ => match bm.stability_cv {
+					Some(cv) => cv,
+					None => continue,
+				},
+			};
+			let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+		}
+	}
+	let name = format!("{}_last_run_timestamp_seconds", METRIC_PREFIX);
+	let _ = writeln!(out, "# HELP {} Unix time the benchmark finished", name);
+	let _ = writeln!(out, "# TYPE {} gauge", name);
+	let _ = writeln!(out, "{} {}", name, timestamp_secs);
+	out
+}
+
+//============================================
+/// Write results as a Prometheus textfile (node_exporter textfile collector).
+///
+/// Written to a temporary file and renamed so the collector never reads a
+/// partial file.
+pub fn write_prometheus(path: &str, records: &[ResolverRecord]) -> Result<()> {
+	let now = std::time::SystemTime::now()
+		.duration_since(std::time::SystemTime::UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or(0);
+	let tmp = format!("{}.tmp", path);
+	std::fs::write(&tmp, render_prometheus(records, now))?;
+	std::fs::rename(&tmp, path)?;
+	crate::info!("Prometheus metrics written to {}", path);
+	Ok(())
+}
+
+//============================================
+/// Escape an InfluxDB line protocol tag key or value (comma, equals, space).
+pub(crate) fn influx_escape(s: &str) -> String {
+	s.replace('\\', "\\\\").replace(',', "\\,").replace('=', "\\=").replace(' ', "\\ ")
+}
+
+/// Tag set identifying a resolver: `resolver` (IP), `label`, and `transport`,
+/// plus `network` when the run has a network label (empty tags are invalid).
+pub(crate) fn influx_tags(resolver: &crate::transport::Resolver) -> String {
+	let mut tags = format!("resolver={},label={},transport={}",
+		influx_escape(&resolver.addr.ip().to_string()), influx_escape(&resolver.label),
+		influx_escape(&resolver.transport.to_string()));
+	let network = crate::network::network().label;
+	if !network.is_empty() {
+		tags += &format!(",network={}", influx_escape(&network));
+	}
+	tags
+}
+
+/// Render benchmark results as InfluxDB line protocol (nanosecond timestamps).
+///
+/// One `dns_benchmark` point per resolver (score, rank, success ratio,
+/// stability) and one `dns_benchmark_set` point per resolver and domain set
+/// (p50, p95, timeout ratio), using the same values as the Prometheus gauges.
+pub fn render_influx(records: &[ResolverRecord], timestamp_secs: u64) -> String {
+	let ts = timestamp_secs as u128 * 1_000_000_000;
+	let mut out = String::new();
+	for r in records {
+		let bm = match &r.benchmark {
+			Some(bm) => bm,
+			None => continue,
+		};
+		let tags = influx_tags(&r.resolver);
+		let mut fields = vec![
+			format!("rank={}i", bm.rank),
+			format!("success_ratio={}", bm.success_rate / 100.0),
+		];
+		// Line protocol has no infinity; dead resolvers omit the score
+		if bm.overall_score.is_finite() {
+			fields.insert(0, format!("score_ms={}", bm.overall_score));
+		}
+		if let Some(cv) = bm.stability_cv {
+			fields.push(format!("stability_cv={}", cv));
+		}
+		let _ = writeln!(out, "{},{} {} {}", METRIC_PREFIX, tags, fields.join(","), ts);
+		for (set, stats) in &bm.categories {
+			if stats.total_count == 0 {
+				continue;
+			}
+			let _ = writeln!(out, "{}_set,{},set={} p50_ms={},p95_ms={},timeout_ratio={} {}",
+				METRIC_PREFIX, tags, influx_escape(set), stats.p50_ms, stats.p95_ms,
+				stats.timeout_count as f64 / stats.total_count as f64, ts);
+		}
+	}
+	out
+}
+
+/// Send line protocol to InfluxDB or append it to a file.
+///
+/// An http:// or https:// target is the full write URL, e.g.
+/// `http://localhost:8086/api/v2/write?org=home&bucket=dns` (v2) or
+/// `http://localhost:8086/write?db=dns` (v1); the token in INFLUX_TOKEN,
+/// if set, is sent as `Authorization: Token ...`. Any other target is a
+/// file path that each run appends to, for Telegraf's file input or
+/// `influx write`.
+pub async fn write_influx(target: &str, lines: &str) -> Result<()> {
+	if lines.is_empty() {
+		return Ok(());
+	}
+	if target.starts_with("http://") || target.starts_with("https://") {
+		let mut request = reqwest::Client::new().post(target).body(lines.to_string());
+		if let Ok(token) = std::env::var("INFLUX_TOKEN") {
+			request = request.header("Authorization", format!("Token {}", token));
+		}
+		let response = request.send().await
+			.map_err(|e| anyhow::anyhow!("InfluxDB write to {} failed: {}", target, e))?;
+		if !response.status().is_success() {
+			let status = response.status();
+			let body = response.text().await.unwrap_or_default();
+			anyhow::bail!("InfluxDB write to {} failed: {} {}", target, status, body.trim());
+		}
+		crate::info!("InfluxDB points written to {}", target);
+		return Ok(());
+	}
+	let mut file = std::fs::OpenOptions::new().create(true).append(true).open(target)?;
+	std::io::Write::write_all(&mut file, lines.as_bytes())?;
+	crate::info!("InfluxDB line protocol appended to {}", target);
+	Ok(())
+}
+
+//============================================
+/// Build one Grafana timeseries panel for a metric.
+fn grafana_panel(id: usize, def: &MetricDef) -> String {
+	let name = metric_name(def);
+	let (expr, legend) = if def.per_set {
+		(format!("{}{{label=~\\\"$resolver\\\",set=~\\\"$set\\\"}}", name), "{{label}} {{set}}")
+	} else {
+		(format!("{}{{label=~\\\"$resolver\\\"}}", name), "{{label}}")
+	};
+	// Two panels per row, 12 columns wide and 8 rows tall
+	let x = (id % 2) * 12;
+	let y = (id / 2) * 8;
+	format!(
+		r#"{{"id":{},"type":"timeseries","title":"{}","description":"{}","datasource":{{"type":"prometheus","uid":"${{DS_PROMETHEUS}}"}},"gridPos":{{"h":8,"w":12,"x":{},"y":{}}},"fieldConfig":{{"defaults":{{"unit":"{}"}},"overrides":[]}},"targets":[{{"refId":"A","expr":"{}","legendFormat":"{}"}}]}}"#,
+		id + 1, json_escape(def.help), json_escape(&name), x, y, def.unit, expr, legend,
+	)
+}
+
+/// Build a dashboard template variable populated from a metric label.
+fn grafana_variable(var: &str, metric: &str, label: &str) -> String {
+	format!(
+		r#"{{"name":"{var}","label":"{var}","type":"query","datasource":{{"type":"prometheus","uid":"${{DS_PROMETHEUS}}"}},"query":"label_values({metric}, {label})","refresh":2,"multi":true,"includeAll":true,"current":{{"text":"All","value":"$__all"}}}}"#,
+	)
+}
+
+//============================================
+/// Generate a Grafana dashboard (JSON) for the metrics from `write_prometheus`.
+///
+/// The Prometheus datasource is an import-time input (`DS_PROMETHEUS`), so
+/// Grafana asks for it when the file is imported.
+pub fn grafana_dashboard_json() -> String {
+	let panels: Vec<String> = METRICS.iter().enumerate()
+		.map(|(i, def)| grafana_panel(i, def))
+		.collect();
+	let score = format!("{}_score_ms", METRIC_PREFIX);
+	let set_p50 = format!("{}_set_p50_ms", METRIC_PREFIX);
+	let variables = [
+		grafana_variable("resolver", &score, "label"),
+		grafana_variable("set", &set_p50, "set"),
+	];
+	format!(
+		r#"{{"__inputs":[{{"name":"DS_PROMETHEUS","label":"Prometheus","type":"datasource","pluginId":"prometheus","pluginName":"Prometheus"}}],"title":"DNS resolver benchmark","uid":"dns-benchmark","tags":["dns","{prefix}"],"schemaVersion":39,"time":{{"from":"now-7d","to":"now"}},"refresh":"5m","templating":{{"list":[{vars}]}},"panels":[{panels}]}}"#,
+		prefix = METRIC_PREFIX,
+		vars = variables.join(","),
+		panels = panels.join(","),
+	) + "\n"
+}
+
+/// Write the Grafana dashboard to a file, or to stdout when `path` is None.
+pub fn write_grafana_dashboard(path: Option<&str>) -> Result<()> {
+	let json = grafana_dashboard_json();
+	match path {
+		Some(p) => {
+			std::fs::write(p, json)?;
+			crate::info!("Grafana dashboard written to {}", p);
+		}
+		None => print!("{}", json),
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_dashboard_queries_every_metric() {
+		let json = grafana_dashboard_json();
+		for def in METRICS {
+			assert!(json.contains(&format!("\"expr\":\"{}{{", metric_name(def))), "{}", def.name);
+		}
+		assert!(json.contains("label_values(dns_benchmark_score_ms, label)"));
+	}
+
+	#[test]
+	fn test_dashboard_is_valid_json() {
+		let value: serde_json::Value = serde_json::from_str(&grafana_dashboard_json()).unwrap();
+		assert_eq!(value["panels"].as_array().unwrap().len(), METRICS.len());
+		assert_eq!(value["templating"]["list"].as_array().unwrap().len(), 2);
+	}
+
+	#[test]
+	fn test_influx_escape_and_lines() {
+		assert_eq!(influx_escape("Cloudflare DoH,1=a"), r"Cloudflare\ DoH\,1\=a");
+		let mut rec = ResolverRecord::new(crate::transport::Resolver::new("1.1.1.1:53".parse().unwrap(), crate::transport::DnsTransport::Udp));
+		rec.resolver.label = "Cloudflare".to_string();
+		rec.benchmark = Some(crate::record::BenchmarkResult {
+			categories: std::collections::BTreeMap::from([("cached".to_string(), crate::stats::SetStats {
+				p50_ms: 4.0, p95_ms: 9.0, timeout_count: 1, total_count: 4, ..Default::default()
+			})]),
+			overall_score: 6.5,
+			success_rate: 75.0,
+			first_attempt_rate: 75.0,
+			scored_timeouts: 1,
+			rank: 1,
+			..Default::default()
+		});
+		assert_eq!(render_influx(&[rec], 2),
+			"dns_benchmark,resolver=1.1.1.1,label=Cloudflare,transport=UDP score_ms=6.5,rank=1i,success_ratio=0.75 2000000000\n\
+			dns_benchmark_set,resolver=1.1.1.1,label=Cloudflare,transport=UDP,set=cached p50_ms=4,p95_ms=9,timeout_ratio=0.25 2000000000\n");
+	}
+
+	#[test]
+	fn test_label_escape() {
+		assert_eq!(label_escape(r#"a"b\c"#), r#"a\"b\\c"#);
+	}
+}
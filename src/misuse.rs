@@ -0,0 +1,183 @@
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::Semaphore;
+
+use crate::bench::{spawn_progress_monitor, stop_progress_monitor};
+use crate::record::{MisuseResult, ResolverRecord};
+use crate::transport::{BenchmarkConfig, DnsTransport};
+
+/// Signals needed before a resolver counts as a suspect
+pub const MISUSE_MIN_SIGNALS: usize = 2;
+/// Timed queries per resolver for the latency-variance signal
+const MISUSE_LATENCY_QUERIES: usize = 5;
+/// Pause between the timed queries, so one burst is not measured as jitter
+const MISUSE_QUERY_GAP_MS: u64 = 100;
+/// Coefficient of variation above which latency counts as erratic
+const MISUSE_MAX_CV: f64 = 0.5;
+/// Name queried for the latency signal; cached on any working resolver
+const MISUSE_PROBE_DOMAIN: &str = "www.google.com";
+
+/// PTR name labels typical of consumer access networks
+const RESIDENTIAL_PTR_TOKENS: [&str; 22] = [
+	"adsl", "bb", "broadband", "cable", "client", "cpe", "cust", "customer", "dhcp", "dip", "dsl", "dyn",
+	"dynamic", "ftth", "home", "mobile", "pool", "ppp", "pppoe", "res", "user", "vdsl",
+];
+
+//============================================
+/// Whether a PTR name looks like a consumer access line: a telltale label
+/// (dsl, dynamic, pool, ...) or the address itself spelled out in the name.
+pub fn residential_ptr(ptr: &str, ip: IpAddr) -> bool {
+	let ptr = ptr.to_ascii_lowercase();
+	let tokens: Vec<&str> = ptr.split(['.', '-', '_']).collect();
+	let labelled = tokens.iter()
+		.any(|t| RESIDENTIAL_PTR_TOKENS.contains(&t.trim_end_matches(|c: char| c.is_ascii_digit())));
+	let spelled_out = match ip {
+		IpAddr::V4(v4) => {
+			let o = v4.octets();
+			let forward = format!("{}-{}-{}-{}", o[0], o[1], o[2], o[3]);
+			let reverse = format!("{}-{}-{}-{}", o[3], o[2], o[1], o[0]);
+			let dashed = ptr.replace('.', "-");
+			dashed.contains(&forward) || dashed.contains(&reverse)
+		}
+		IpAddr::V6(_) => false,
+	};
+	labelled || spelled_out
+}
+
+/// Whether a version.bind answer discloses a software version, which
+/// managed public services hide and stock router firmware does not.
+pub fn discloses_version(text: &str) -> bool {
+	text.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Erratic-latency signal text from the timed queries, if any. Latencies
+/// are in ms; `lost` counts queries that got no answer.
+fn latency_signal(latencies: &[f64], lost: usize) -> Option<String> {
+	if lost * 2 >= MISUSE_LATENCY_QUERIES {
+		return Some(format!("lost {} of {} queries", lost, MISUSE_LATENCY_QUERIES));
+	}
+	let mean = latencies.iter().sum::<f64>() / latencies.len() as f64;
+	let variance = latencies.iter().map(|l| (l - mean).powi(2)).sum::<f64>() / latencies.len() as f64;
+	let cv = if mean > 0.0 { variance.sqrt() / mean } else { 0.0 };
+	(cv > MISUSE_MAX_CV).then(|| format!("erratic latency (CV {:.2})", cv))
+}
+
+//============================================
+/// Collect the misuse signals for one resolver.
+async fn probe_signals(rec: ResolverRecord, config: BenchmarkConfig, connections: crate::bench::ConnectionPool) -> MisuseResult {
+	let addr = rec.resolver.addr;
+	let mut signals = Vec::new();
+	if rec.resolver.ptr_name.as_deref().is_some_and(|p| residential_ptr(p, addr.ip())) {
+		signals.push(format!("residential PTR {}", rec.resolver.ptr_name.as_deref().unwrap_or_default()));
+	}
+	if let Some(version) = crate::dns::query_chaos_txt(addr, "version.bind", config.timeout).await {
+		if discloses_version(&version) {
+			signals.push(format!("version.bind: {}", version));
+		}
+	}
+	if crate::dns::check_edns_support(addr, config.timeout).await == Some(0) {
+		signals.push("no EDNS".to_string());
+	}
+	let mut latencies = Vec::new();
+	for i in 0..MISUSE_LATENCY_QUERIES {
+		if i > 0 {
+			tokio::time::sleep(Duration::from_millis(MISUSE_QUERY_GAP_MS)).await;
+		}
+		let result = crate::bench::probe_query(&rec.resolver, MISUSE_PROBE_DOMAIN, config.timeout, false, &connections).await;
+		if result.outcome.is_ok() {
+			latencies.push(result.latency.as_secs_f64() * 1000.0);
+		}
+	}
+	signals.extend(latency_signal(&latencies, MISUSE_LATENCY_QUERIES - latencies.len()));
+	MisuseResult { signals }
+}
+
+/// Probe public UDP resolvers for signs of an abused open resolver: a
+/// residential PTR name, a disclosed software version, no EDNS, and
+/// erratic latency or loss.
+///
+/// Meant for large imported lists, which are full of home routers and
+/// compromised CPE devices answering the whole internet; benchmarking them
+/// adds load to equipment whose owners never agreed to serve it. A resolver
+/// with `MISUSE_MIN_SIGNALS` or more signals is a suspect. System, private,
+/// DoT, and DoH resolvers are not probed.
+pub async fn run_misuse_probe(records: &mut [ResolverRecord], config: &BenchmarkConfig) {
+	let targets: Vec<usize> = records.iter().enumerate()
+		.filter(|(_, r)| r.resolver.class == "public" && matches!(r.resolver.transport, DnsTransport::Udp))
+		.map(|(i, _)| i)
+		.collect();
+	crate::info!("Open-resolver misuse check ({} public UDP resolvers)...", targets.len());
+	let total = targets.len();
+	let done = Arc::new(AtomicUsize::new(0));
+	let start = Instant::now();
+	let monitor = spawn_progress_monitor("Misuse check".to_string(), done.clone(), total, start);
+
+	let connections = crate::bench::build_connection_pool(&[], &Default::default());
+	let semaphore = Arc::new(Semaphore::new(config.max_inflight));
+	let mut handles = Vec::new();
+	for &i in &targets {
+		let rec = records[i].clone();
+		let config = config.clone();
+		let connections = connections.clone();
+		let sem = semaphore.clone();
+		let done = done.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = sem.acquire().await.unwrap();
+			let result = probe_signals(rec, config, connections).await;
+			done.fetch_add(1, Ordering::Relaxed);
+			(i, result)
+		}));
+	}
+	for handle in handles {
+		match handle.await {
+			Ok((idx, result)) => records[idx].misuse = Some(result),
+			Err(e) => crate::warn!("misuse check task failed: {}", e),
+		}
+	}
+	stop_progress_monitor(monitor, "Misuse check", total, start);
+	let suspects: Vec<&ResolverRecord> = records.iter()
+		.filter(|r| r.misuse.as_ref().is_some_and(|m| m.suspect()))
+		.collect();
+	crate::info!("  {} suspect, {} look like managed services", suspects.len(), total - suspects.len());
+	for r in &suspects {
+		let signals = r.misuse.as_ref().map(|m| m.signals.join(", ")).unwrap_or_default();
+		crate::info!("  {} ({}) -- {}", r.resolver.label, r.resolver.addr.ip(), signals);
+	}
+	crate::info!();
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_residential_ptr() {
+		let ip: IpAddr = "203.0.113.45".parse().unwrap();
+		assert!(residential_ptr("45-113-0-203.dyn.example.net", ip));
+		assert!(residential_ptr("host-203-0-113-45.example.net", ip));
+		assert!(residential_ptr("dsl17.isp.example", ip));
+		assert!(!residential_ptr("dns.google", ip));
+		assert!(!residential_ptr("resolver1.opendns.com", ip));
+	}
+
+	#[test]
+	fn test_version_and_latency_signals() {
+		assert!(discloses_version("dnsmasq-2.45"));
+		assert!(discloses_version("9.11.4-P2-RedHat"));
+		assert!(!discloses_version("none of your business"));
+		assert_eq!(latency_signal(&[10.0, 11.0, 10.5, 9.8, 10.2], 0), None);
+		assert_eq!(latency_signal(&[10.0, 80.0, 12.0, 150.0, 11.0], 0).as_deref(), Some("erratic latency (CV 1.06)"));
+		assert_eq!(latency_signal(&[10.0, 11.0], 3).as_deref(), Some("lost 3 of 5 queries"));
+	}
+
+	#[test]
+	fn test_suspect_needs_two_signals() {
+		let one = MisuseResult { signals: vec!["no EDNS".to_string()] };
+		assert!(!one.suspect());
+		let two = MisuseResult { signals: vec!["no EDNS".to_string(), "version.bind: dnsmasq-2.45".to_string()] };
+		assert!(two.suspect());
+	}
+}
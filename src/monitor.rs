@@ -0,0 +1,493 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use tokio::sync::Semaphore;
+
+use crate::transport::{Resolver, DEFAULT_CONCURRENCY, DEFAULT_DNSSEC, DEFAULT_TIMEOUT_MS};
+
+/// Seconds between monitor checks
+pub const DEFAULT_MONITOR_INTERVAL_SECS: u64 = 60;
+/// Checks between periodic reports
+pub const DEFAULT_MONITOR_REPORT_EVERY: u32 = 10;
+/// Consecutive failed checks before a resolver counts as down; shorter
+/// failure streaks are tolerated as transient loss
+pub const DEFAULT_DOWN_AFTER: u32 = 2;
+/// Queries per resolver at each --schedule time, so each run yields a p50
+/// rather than a single sample
+pub const SCHEDULED_QUERIES_PER_RUN: u32 = 5;
+/// Report-period p50 (ms) above which --notify-webhook sends a slow alert
+pub const DEFAULT_NOTIFY_P50_MS: f64 = 200.0;
+
+/// Monitor mode settings from the CLI.
+#[derive(Debug, Clone)]
+pub struct MonitorSettings {
+	pub interval: Duration,
+	/// Cron schedule replacing the fixed interval (--schedule)
+	pub schedule: Option<crate::schedule::CronSchedule>,
+	pub report_every: u32,
+	/// Stop after this many checks; None runs until Ctrl-C
+	pub count: Option<u32>,
+	pub down_after: u32,
+	/// InfluxDB write URL or line protocol file for each report (--influx)
+	pub influx: Option<String>,
+	/// Webhook alerts for the system resolvers (--notify-webhook)
+	pub notify: Option<NotifySettings>,
+}
+
+/// Where and when to send regression alerts.
+#[derive(Debug, Clone)]
+pub struct NotifySettings {
+	/// URL that receives a JSON POST per alert (Slack incoming webhooks work as is)
+	pub url: String,
+	/// Report-period p50 above which a resolver counts as slow
+	pub p50_threshold_ms: f64,
+}
+
+/// Alert state of a watched resolver; a change of state sends one alert.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertState {
+	Ok,
+	Slow,
+	Down,
+}
+
+impl AlertState {
+	/// State after a check (`period_p50` None) or a report (`period_p50` Some).
+	///
+	/// Down follows the tracker's incidents, so it is noticed at the check
+	/// that opens one. Slow is only judged at reports, over the period p50.
+	pub fn next(self, down: bool, period_p50: Option<f64>, threshold_ms: f64) -> AlertState {
+		if down {
+			return AlertState::Down;
+		}
+		match (self, period_p50) {
+			(_, Some(p50)) if p50 > threshold_ms => AlertState::Slow,
+			(_, Some(_)) | (AlertState::Down, None) => AlertState::Ok,
+			(state, None) => state,
+		}
+	}
+}
+
+/// A period during which a resolver was down.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Incident {
+	/// Time of the first failed check in the streak (seconds since the Unix epoch)
+	pub start_secs: u64,
+	/// Time of the first successful check afterwards; None while ongoing
+	pub end_secs: Option<u64>,
+}
+
+/// Availability tracking for one resolver over the observation window.
+#[derive(Debug, Clone, Default)]
+pub struct UptimeTracker {
+	pub checks: u32,
+	/// Checks that fell inside an incident (including the streak that opened it)
+	pub down_checks: u32,
+	pub incidents: Vec<Incident>,
+	fail_streak: u32,
+	streak_start_secs: u64,
+	/// Successful latencies since the last report
+	period_latencies: Vec<f64>,
+	/// Successful latencies over the whole run, by UTC hour of day
+	hourly_latencies: std::collections::BTreeMap<u64, Vec<f64>>,
+}
+
+//============================================
+impl UptimeTracker {
+	/// Record one check result at `now_secs`.
+	fn record(&mut self, now_secs: u64, success: bool, latency_ms: f64, down_after: u32) {
+		self.checks += 1;
+		if success {
+			self.period_latencies.push(latency_ms);
+			self.hourly_latencies.entry((now_secs % 86400) / 3600).or_default().push(latency_ms);
+			self.fail_streak = 0;
+			if let Some(open) = self.incidents.last_mut().filter(|i| i.end_secs.is_none()) {
+				open.end_secs = Some(now_secs);
+			}
+			return;
+		}
+		if self.fail_streak == 0 {
+			self.streak_start_secs = now_secs;
+		}
+		self.fail_streak += 1;
+		if self.is_down() {
+			self.down_checks += 1;
+		} else if self.fail_streak >= down_after {
+			// The tolerated streak becomes downtime retroactively
+			self.down_checks += self.fail_streak;
+			self.incidents.push(Incident { start_secs: self.streak_start_secs, end_secs: None });
+		}
+	}
+
+	/// True while an incident is open.
+	pub fn is_down(&self) -> bool {
+		self.incidents.last().is_some_and(|i| i.end_secs.is_none())
+	}
+
+	/// Percent of checks outside incidents; 100 before the first check.
+	pub fn availability_pct(&self) -> f64 {
+		if self.checks == 0 {
+			return 100.0;
+		}
+		(self.checks - self.down_checks) as f64 / self.checks as f64 * 100.0
+	}
+
+	/// p50 of successful latencies since the last call, then reset the period.
+	fn take_period_p50(&mut self) -> Option<f64> {
+		let mut latencies = std::mem::take(&mut self.period_latencies);
+		latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+		crate::stats::percentile(&latencies, 50.0)
+	}
+
+	/// p50 of successful latencies per UTC hour of day, for hours with data.
+	pub fn hourly_p50(&self) -> Vec<(u64, f64)> {
+		self.hourly_latencies.iter()
+			.filter_map(|(&hour, latencies)| {
+				let mut sorted = latencies.clone();
+				sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+				crate::stats::percentile(&sorted, 50.0).map(|p50| (hour, p50))
+			})
+			.collect()
+	}
+}
+
+/// Current time in seconds since the Unix epoch.
+fn now_secs() -> u64 {
+	SystemTime::now()
+		.duration_since(SystemTime::UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or(0)
+}
+
+//============================================
+/// Print a periodic report: latency for the period plus availability so far.
+fn print_report(resolvers: &[Resolver], trackers: &[UptimeTracker], period_p50: &[Option<f64>], check: u32, window_start: u64, now: u64) {
+	println!("\nMonitor report after {} checks ({} to {})", check,
+		crate::telemetry::format_timestamp_iso(window_start),
+		crate::telemetry::format_timestamp_iso(now));
+	println!("  {:<28} {:>10} {:>8} {:>10} {:>7}", "Resolver", "p50", "Avail %", "Incidents", "Status");
+	for ((r, t), p50) in resolvers.iter().zip(trackers).zip(period_p50) {
+		let p50_str = p50
+			.map(|p| format!("{:.1} ms", p))
+			.unwrap_or_else(|| "-".to_string());
+		let status = if t.is_down() { "DOWN" } else { "up" };
+		println!("  {:<28} {:>10} {:>8.2} {:>10} {:>7}",
+			r.label, p50_str, t.availability_pct(), t.incidents.len(), status);
+	}
+	for (r, t) in resolvers.iter().zip(trackers.iter()) {
+		for incident in &t.incidents {
+			let end = incident.end_secs
+				.map(crate::telemetry::format_timestamp_iso)
+				.unwrap_or_else(|| "ongoing".to_string());
+			println!("  Incident: {} down {} to {}", r.label,
+				crate::telemetry::format_timestamp_iso(incident.start_secs), end);
+		}
+	}
+}
+
+/// Print each resolver's p50 by UTC hour of day over the whole run.
+///
+/// Only prints when checks landed in at least two different hours.
+fn print_diurnal(resolvers: &[Resolver], trackers: &[UptimeTracker]) {
+	let hourly: Vec<Vec<(u64, f64)>> = trackers.iter().map(UptimeTracker::hourly_p50).collect();
+	if hourly.iter().all(|h| h.len() < 2) {
+		return;
+	}
+	println!("\nTime of day (UTC hour: p50)");
+	for (r, hours) in resolvers.iter().zip(&hourly) {
+		let cells: Vec<String> = hours.iter()
+			.map(|(hour, p50)| format!("{:02}h {:.1}", hour, p50))
+			.collect();
+		let slowest = hours.iter().max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+		let fastest = hours.iter().min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+		let spread = match (slowest, fastest) {
+			(Some(slow), Some(fast)) if hours.len() >= 2 => {
+				format!("  (slowest {:02}h, +{:.1} ms over {:02}h)", slow.0, slow.1 - fast.1, fast.0)
+			}
+			_ => String::new(),
+		};
+		println!("  {:<28} {}{}", r.label, cells.join(" | "), spread);
+	}
+}
+
+/// One `dns_benchmark_monitor` line protocol point per resolver for a report.
+fn render_influx_report(resolvers: &[Resolver], trackers: &[UptimeTracker], period_p50: &[Option<f64>], now: u64) -> String {
+	let ts = now as u128 * 1_000_000_000;
+	resolvers.iter().zip(trackers).zip(period_p50)
+		.map(|((r, t), p50)| {
+			let mut fields = vec![
+				format!("availability_pct={}", t.availability_pct()),
+				format!("incidents={}i", t.incidents.len()),
+				format!("down={}", t.is_down()),
+			];
+			if let Some(p50) = p50 {
+				fields.insert(0, format!("p50_ms={}", p50));
+			}
+			format!("{}_monitor,{} {} {}\n", crate::metrics::METRIC_PREFIX,
+				crate::metrics::influx_tags(r), fields.join(","), ts)
+		})
+		.collect()
+}
+
+//============================================
+/// JSON body of one alert: a Slack-style `text` line plus structured fields.
+fn alert_json(resolver: &Resolver, state: AlertState, p50_ms: Option<f64>, threshold_ms: f64, now: u64) -> String {
+	use crate::telemetry::json_escape;
+	let who = format!("DNS resolver {} ({})", resolver.label, resolver.addr.ip());
+	let (event, text) = match state {
+		AlertState::Down => ("down", format!("{} is down: it stopped answering", who)),
+		AlertState::Slow => ("slow", format!("{} is slow: p50 {:.1} ms is over the {:.0} ms threshold",
+			who, p50_ms.unwrap_or(0.0), threshold_ms)),
+		AlertState::Ok => ("recovered", format!("{} recovered", who)),
+	};
+	let p50 = p50_ms.map(|ms| format!("{:.1}", ms)).unwrap_or_else(|| "null".to_string());
+	format!(
+		r#"{{"text":"{}","event":"{}","resolver":"{}","label":"{}","p50_ms":{},"threshold_ms":{},"time":"{}"}}"#,
+		json_escape(&text), event, resolver.addr.ip(), json_escape(&resolver.label), p50, threshold_ms,
+		crate::telemetry::format_timestamp_iso(now),
+	)
+}
+
+/// Move each watched resolver to its next alert state and POST an alert for
+/// every change; a failed POST only warns so monitoring keeps running.
+async fn update_alerts(
+	resolvers: &[Resolver],
+	trackers: &[UptimeTracker],
+	states: &mut [(usize, AlertState)],
+	period_p50: Option<&[Option<f64>]>,
+	notify: &NotifySettings,
+) {
+	let now = now_secs();
+	for (idx, state) in states.iter_mut() {
+		let p50 = period_p50.and_then(|p| p[*idx]);
+		let next = state.next(trackers[*idx].is_down(), p50, notify.p50_threshold_ms);
+		if next == *state {
+			continue;
+		}
+		*state = next;
+		let body = alert_json(&resolvers[*idx], next, p50, notify.p50_threshold_ms, now);
+		crate::info!("Alert: {} is now {:?}", resolvers[*idx].label, next);
+		let sent = reqwest::Client::new().post(&notify.url)
+			.header("Content-Type", "application/json")
+			.body(body)
+			.send().await
+			.and_then(|r| r.error_for_status());
+		if let Err(e) = sent {
+			crate::warn!("webhook {} failed: {}", notify.url, e);
+		}
+	}
+}
+
+/// Print a report and, with --influx, export it; an export failure only warns
+/// so monitoring keeps running. Returns the period p50 of each resolver.
+async fn report(resolvers: &[Resolver], trackers: &mut [UptimeTracker], check: u32, window_start: u64, settings: &MonitorSettings) -> Vec<Option<f64>> {
+	let now = now_secs();
+	let period_p50: Vec<Option<f64>> = trackers.iter_mut().map(UptimeTracker::take_period_p50).collect();
+	print_report(resolvers, trackers, &period_p50, check, window_start, now);
+	if let Some(target) = &settings.influx {
+		let lines = render_influx_report(resolvers, trackers, &period_p50, now);
+		if let Err(e) = crate::metrics::write_influx(target, &lines).await {
+			crate::warn!("{}", e);
+		}
+	}
+	period_p50
+}
+
+//============================================
+/// Wait for the next check: the next --schedule time, or the next interval tick.
+///
+/// Returns false on Ctrl-C or when the schedule never matches again.
+/// `ctrl_c` is created once for the whole run, so a Ctrl-C pressed during a
+/// check is not lost while the next wait has not started yet.
+async fn wait_for_check(
+	ticker: &mut tokio::time::Interval,
+	schedule: Option<&crate::schedule::CronSchedule>,
+	ctrl_c: std::pin::Pin<&mut impl std::future::Future<Output = std::io::Result<()>>>,
+) -> bool {
+	let wait = async {
+		match schedule {
+			Some(s) => match s.next_after(now_secs()) {
+				Some(next) => {
+					tokio::time::sleep(Duration::from_secs(next.saturating_sub(now_secs()))).await;
+					true
+				}
+				None => false,
+			},
+			None => {
+				ticker.tick().await;
+				true
+			}
+		}
+	};
+	tokio::select! {
+		more = wait => more,
+		_ = ctrl_c => false,
+	}
+}
+
+//============================================
+/// Run monitor mode: check every resolver each interval and report periodically.
+///
+/// Each check is one A query for a cached domain (rotating through the set).
+/// With a schedule, checks run at the scheduled times instead and send
+/// SCHEDULED_QUERIES_PER_RUN queries to each resolver. Runs until `count`
+/// checks are done or Ctrl-C, then prints a final report with latency by
+/// hour of day.
+pub async fn run_monitor(resolvers: &[Resolver], settings: &MonitorSettings) -> Result<()> {
+	let domains = crate::domains::load_default_query_domains()
+		.remove("cached")
+		.filter(|d| !d.is_empty())
+		.unwrap_or_else(|| vec!["google.com".to_string()]);
+	let connections = crate::bench::build_connection_pool(resolvers, &Default::default());
+	let timeout = Duration::from_millis(DEFAULT_TIMEOUT_MS);
+	let semaphore = Arc::new(Semaphore::new(DEFAULT_CONCURRENCY));
+	let mut trackers = vec![UptimeTracker::default(); resolvers.len()];
+
+	let (cadence, queries_per_check) = match &settings.schedule {
+		Some(_) => ("on schedule (UTC)".to_string(), SCHEDULED_QUERIES_PER_RUN),
+		None => (format!("every {} s", settings.interval.as_secs()), 1),
+	};
+	crate::info!("Monitoring {} resolvers {} (report every {} checks, down after {} failed checks)",
+		resolvers.len(), cadence, settings.report_every, settings.down_after);
+	if let Some(next) = settings.schedule.as_ref().and_then(|s| s.next_after(now_secs())) {
+		crate::info!("First scheduled check at {}", crate::telemetry::format_timestamp_iso(next));
+	}
+	crate::info!("Press Ctrl-C to stop.");
+
+	// Alerts watch the system resolvers, or every resolver when none is listed
+	let mut alert_states: Vec<(usize, AlertState)> = resolvers.iter().enumerate()
+		.filter(|(_, r)| r.is_system)
+		.map(|(i, _)| (i, AlertState::Ok))
+		.collect();
+	if alert_states.is_empty() {
+		alert_states = (0..resolvers.len()).map(|i| (i, AlertState::Ok)).collect();
+	}
+	if let Some(notify) = &settings.notify {
+		crate::info!("Alerting {} on {} resolver(s): down, or report p50 over {} ms",
+			notify.url, alert_states.len(), notify.p50_threshold_ms);
+	}
+
+	let window_start = now_secs();
+	let mut ticker = tokio::time::interval(settings.interval);
+	let mut check: u32 = 0;
+	let mut queries: usize = 0;
+	let ctrl_c = tokio::signal::ctrl_c();
+	tokio::pin!(ctrl_c);
+	while wait_for_check(&mut ticker, settings.schedule.as_ref(), ctrl_c.as_mut()).await {
+		check += 1;
+		for _ in 0..queries_per_check {
+			let domain = domains[queries % domains.len()].clone();
+			queries += 1;
+
+			let mut handles = Vec::with_capacity(resolvers.len());
+			for r in resolvers {
+				let sem = semaphore.clone();
+				let resolver = r.clone();
+				let domain = domain.clone();
+				let connections = connections.clone();
+				handles.push(tokio::spawn(async move {
+					let _permit = sem.acquire().await.unwrap();
+					crate::bench::probe_query(&resolver, &domain, timeout, DEFAULT_DNSSEC, &connections).await
+				}));
+			}
+			let checked_at = now_secs();
+			for (tracker, handle) in trackers.iter_mut().zip(handles) {
+				let (success, latency_ms) = match handle.await {
+					Ok(result) => (result.outcome.is_ok(), result.latency.as_secs_f64() * 1000.0),
+					Err(_) => (false, 0.0),
+				};
+				tracker.record(checked_at, success, latency_ms, settings.down_after);
+			}
+		}
+		if let Some(notify) = &settings.notify {
+			update_alerts(resolvers, &trackers, &mut alert_states, None, notify).await;
+		}
+
+		if settings.count.is_some_and(|n| check >= n) {
+			break;
+		}
+		if check.is_multiple_of(settings.report_every) {
+			let period_p50 = report(resolvers, &mut trackers, check, window_start, settings).await;
+			if let Some(notify) = &settings.notify {
+				update_alerts(resolvers, &trackers, &mut alert_states, Some(&period_p50), notify).await;
+			}
+		}
+	}
+
+	report(resolvers, &mut trackers, check, window_start, settings).await;
+	print_diurnal(resolvers, &trackers);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_single_failure_is_tolerated() {
+		let mut t = UptimeTracker::default();
+		t.record(0, true, 10.0, 2);
+		t.record(60, false, 0.0, 2);
+		t.record(120, true, 10.0, 2);
+		assert!(t.incidents.is_empty());
+		assert_eq!(t.availability_pct(), 100.0);
+	}
+
+	#[test]
+	fn test_incident_opens_at_streak_start_and_closes() {
+		let mut t = UptimeTracker::default();
+		t.record(0, true, 10.0, 2);
+		t.record(60, false, 0.0, 2);
+		t.record(120, false, 0.0, 2);
+		assert!(t.is_down());
+		t.record(180, false, 0.0, 2);
+		t.record(240, true, 10.0, 2);
+		assert!(!t.is_down());
+		assert_eq!(t.incidents, vec![Incident { start_secs: 60, end_secs: Some(240) }]);
+		// 3 of 5 checks were inside the incident
+		assert!((t.availability_pct() - 40.0).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_period_p50_resets() {
+		let mut t = UptimeTracker::default();
+		for ms in [10.0, 20.0, 30.0] {
+			t.record(0, true, ms, 2);
+		}
+		assert_eq!(t.take_period_p50(), Some(20.0));
+		assert_eq!(t.take_period_p50(), None);
+	}
+
+	#[test]
+	fn test_alert_state_transitions() {
+		let state = AlertState::Ok.next(false, Some(250.0), 200.0);
+		assert_eq!(state, AlertState::Slow);
+		// Checks between reports keep a slow state
+		assert_eq!(state.next(false, None, 200.0), AlertState::Slow);
+		let state = state.next(true, None, 200.0);
+		assert_eq!(state, AlertState::Down);
+		// The first answering check ends the outage
+		assert_eq!(state.next(false, None, 200.0), AlertState::Ok);
+		assert_eq!(AlertState::Slow.next(false, Some(20.0), 200.0), AlertState::Ok);
+	}
+
+	#[test]
+	fn test_alert_json() {
+		let mut r = Resolver::new("192.168.1.1:53".parse().unwrap(), crate::transport::DnsTransport::Udp);
+		r.label = "Router".to_string();
+		let body = alert_json(&r, AlertState::Slow, Some(312.44), 200.0, 0);
+		assert_eq!(body, r#"{"text":"DNS resolver Router (192.168.1.1) is slow: p50 312.4 ms is over the 200 ms threshold","event":"slow","resolver":"192.168.1.1","label":"Router","p50_ms":312.4,"threshold_ms":200,"time":"1970-01-01T00:00:00Z"}"#);
+	}
+
+	#[test]
+	fn test_hourly_p50_by_utc_hour() {
+		let mut t = UptimeTracker::default();
+		// 00:10 and 06:00, 06:30 UTC on day 0
+		t.record(600, true, 10.0, 2);
+		t.record(6 * 3600, true, 30.0, 2);
+		t.record(6 * 3600 + 1800, true, 50.0, 2);
+		t.record(6 * 3600 + 1900, false, 0.0, 2);
+		assert_eq!(t.hourly_p50(), vec![(0, 10.0), (6, 30.0)]);
+	}
+}
@@ -0,0 +1,215 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use anyhow::{anyhow, bail, Result};
+
+/// Directory where `ip netns add` creates named namespaces
+const NETNS_DIR: &str = "/var/run/netns";
+/// Column prepended to merged CSV rows
+const NAMESPACE_COLUMN: &str = "namespace";
+
+/// CSV export from one run of a batch (a namespace, or a remote agent).
+pub(crate) struct RunTable {
+	/// Namespace or agent name, written to the leading column
+	pub label: String,
+	pub header: Vec<String>,
+	pub rows: Vec<Vec<String>>,
+}
+
+//============================================
+/// `args` minus the given options and their values, in both the
+/// `--name value` and `--name=value` forms (and `-ovalue` for short ones).
+pub(crate) fn strip_options(args: &[String], options: &[&str]) -> Vec<String> {
+	let mut out = Vec::new();
+	let mut skip_value = false;
+	for arg in args {
+		if skip_value {
+			skip_value = false;
+			continue;
+		}
+		let attached = options.iter().any(|opt| match opt.strip_prefix("--") {
+			Some(_) => arg.starts_with(&format!("{}=", opt)),
+			None => arg.starts_with(opt) && arg.len() > opt.len(),
+		});
+		if options.contains(&arg.as_str()) {
+			skip_value = true;
+		} else if !attached {
+			out.push(arg.clone());
+		}
+	}
+	out
+}
+
+/// Command-line arguments for one child run: the parent's arguments minus
+/// --netns and --output, which the batch driver sets itself.
+fn child_args(args: &[String]) -> Vec<String> {
+	strip_options(args, &["--netns", "-o", "--output"])
+}
+
+//============================================
+/// Merge per-run CSV tables into one with a leading `label_column`.
+///
+/// Headers are unioned in first-seen order, since runs can see
+/// different domain sets succeed and so export different set columns.
+pub(crate) fn merge_tables(label_column: &str, tables: &[RunTable]) -> (Vec<String>, Vec<Vec<String>>) {
+	let mut header: Vec<String> = vec![label_column.to_string()];
+	for table in tables {
+		for col in &table.header {
+			if !header.contains(col) {
+				header.push(col.clone());
+			}
+		}
+	}
+	let mut rows = Vec::new();
+	for table in tables {
+		for row in &table.rows {
+			let mut merged = vec![String::new(); header.len()];
+			merged[0] = table.label.clone();
+			for (col, value) in table.header.iter().zip(row) {
+				if let Some(idx) = header.iter().position(|h| h == col) {
+					merged[idx] = value.clone();
+				}
+			}
+			rows.push(merged);
+		}
+	}
+	(header, rows)
+}
+
+/// Parse one run's CSV export.
+pub(crate) fn parse_table(label: &str, text: &str) -> Result<RunTable> {
+	// Skip the run manifest comment lines above the header
+	let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(text.as_bytes());
+	let header = reader.headers()?.iter().map(String::from).collect();
+	let mut rows = Vec::new();
+	for record in reader.records() {
+		rows.push(record?.iter().map(String::from).collect());
+	}
+	Ok(RunTable { label: label.to_string(), header, rows })
+}
+
+/// Read one namespace's CSV export.
+fn read_table(namespace: &str, path: &Path) -> Result<RunTable> {
+	let text = std::fs::read_to_string(path)?;
+	parse_table(namespace, &text)
+}
+
+/// Print the best score of each run (not rank 1, which may be a pinned
+/// system resolver).
+pub(crate) fn print_best(tables: &[RunTable]) {
+	for RunTable { label, header, rows } in tables {
+		let col = |name: &str| header.iter().position(|h| h == name);
+		let (label_idx, score_idx) = match (col("resolver"), col("overall_score")) {
+			(Some(l), Some(s)) => (l, s),
+			_ => continue,
+		};
+		let best = rows.iter()
+			.filter_map(|r| r[score_idx].parse::<f64>().ok().map(|score| (&r[label_idx], score)))
+			.min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+		match best {
+			Some((resolver, score)) => println!("  {:<16} best: {} (score {:.1})", label, resolver, score),
+			None => println!("  {:<16} no results", label),
+		}
+	}
+}
+
+/// Write merged tables as one CSV with a leading `label_column`.
+pub(crate) fn write_merged(path: &str, label_column: &str, tables: &[RunTable]) -> Result<()> {
+	let (header, rows) = merge_tables(label_column, tables);
+	let mut writer = csv::Writer::from_path(path)?;
+	writer.write_record(&header)?;
+	for row in rows {
+		writer.write_record(&row)?;
+	}
+	writer.flush()?;
+	Ok(())
+}
+
+//============================================
+/// Run the benchmark once inside each named network namespace and merge results.
+///
+/// Each run is a child process started with `ip netns exec`, so sockets,
+/// routes, and /etc/netns/NAME/resolv.conf all come from that namespace.
+/// Namespaces run one after another so they do not compete for bandwidth.
+/// The merged CSV gets a leading `namespace` column. Requires root.
+pub fn run_batch(namespaces: &[String], output: Option<&str>) -> Result<()> {
+	if !cfg!(target_os = "linux") {
+		bail!("--netns requires Linux network namespaces");
+	}
+	for ns in namespaces {
+		if !Path::new(NETNS_DIR).join(ns).exists() {
+			bail!("network namespace '{}' not found in {}", ns, NETNS_DIR);
+		}
+	}
+	let exe = std::env::current_exe()?;
+	let args: Vec<String> = std::env::args().skip(1).collect();
+	let args = child_args(&args);
+	let tmp_dir = std::env::temp_dir();
+
+	let mut tables = Vec::new();
+	for (i, ns) in namespaces.iter().enumerate() {
+		crate::info!("=== Network namespace {} ({}/{}) ===\n", ns, i + 1, namespaces.len());
+		let csv_path: PathBuf = tmp_dir.join(format!("dns_benchmark_netns_{}_{}.csv", std::process::id(), ns));
+		let status = Command::new("ip")
+			.args(["netns", "exec", ns])
+			.arg(&exe)
+			.args(&args)
+			.arg("-o")
+			.arg(&csv_path)
+			.status()
+			.map_err(|e| anyhow!("failed to run `ip netns exec {}`: {}", ns, e))?;
+		if !status.success() {
+			crate::warn!("benchmark in namespace {} exited with {}", ns, status);
+			continue;
+		}
+		let table = read_table(ns, &csv_path)?;
+		let _ = std::fs::remove_file(&csv_path);
+		tables.push(table);
+		crate::info!();
+	}
+	if tables.is_empty() {
+		bail!("no namespace run completed");
+	}
+
+	println!("Namespace comparison");
+	println!("====================");
+	print_best(&tables);
+
+	if let Some(path) = output {
+		write_merged(path, NAMESPACE_COLUMN, &tables)?;
+		crate::info!("\nMerged results written to {}", path);
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn strings(items: &[&str]) -> Vec<String> {
+		items.iter().map(|s| s.to_string()).collect()
+	}
+
+	#[test]
+	fn test_child_args_strip_netns_and_output() {
+		let args = strings(&["-l", "medium", "--netns", "vpn1", "--netns=vpn2", "-o", "out.csv", "-oo.csv", "--loss-probe"]);
+		assert_eq!(child_args(&args), strings(&["-l", "medium", "--loss-probe"]));
+	}
+
+	#[test]
+	fn test_merge_tables_unions_headers() {
+		let table = |ns: &str, header: &[&str], row: &[&str]| RunTable {
+			label: ns.to_string(),
+			header: strings(header),
+			rows: vec![strings(row)],
+		};
+		let tables = vec![
+			table("a", &["rank", "cached_p50_ms"], &["1", "5.0"]),
+			table("b", &["rank", "tld_p50_ms"], &["1", "9.0"]),
+		];
+		let (header, rows) = merge_tables(NAMESPACE_COLUMN, &tables);
+		assert_eq!(header, strings(&["namespace", "rank", "cached_p50_ms", "tld_p50_ms"]));
+		assert_eq!(rows[0], strings(&["a", "1", "5.0", ""]));
+		assert_eq!(rows[1], strings(&["b", "1", "", "9.0"]));
+	}
+}
@@ -0,0 +1,172 @@
+use std::sync::RwLock;
+
+/// The network a run was measured on, recorded in every output so results
+/// collected from several networks can be told apart once aggregated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkContext {
+	/// --label, else the Wi-Fi SSID, else the interface name; may be empty
+	pub label: String,
+	/// Wi-Fi network of the outgoing interface, when it is wireless
+	pub ssid: Option<String>,
+	/// Interface queries leave through: --interface, else the default route's
+	pub interface: Option<String>,
+}
+
+static NETWORK: RwLock<NetworkContext> = RwLock::new(NetworkContext { label: String::new(), ssid: None, interface: None });
+
+//============================================
+/// Set the process-wide network context.
+pub fn set_network(context: NetworkContext) {
+	if let Ok(mut guard) = NETWORK.write() {
+		*guard = context;
+	}
+}
+
+/// Current process-wide network context.
+pub fn network() -> NetworkContext {
+	NETWORK.read().map(|g| g.clone()).unwrap_or_default()
+}
+
+/// Network context for this host: the outgoing interface (the bound one,
+/// or the default route's) and its Wi-Fi SSID, labeled with `label` when
+/// given. Detection is best effort; whatever cannot be found is None.
+pub fn detect(label: Option<&str>) -> NetworkContext {
+	let interface = crate::source::source().interface.or_else(default_interface);
+	let ssid = interface.as_deref().and_then(wifi_ssid);
+	let label = label.map(str::to_string)
+		.or_else(|| ssid.clone())
+		.or_else(|| interface.clone())
+		.unwrap_or_default();
+	NetworkContext { label, ssid, interface }
+}
+
+//============================================
+/// Interface of the IPv4 default route.
+fn default_interface() -> Option<String> {
+	#[cfg(target_os = "linux")]
+	{
+		parse_route_interface(&std::fs::read_to_string("/proc/net/route").ok()?)
+	}
+	#[cfg(target_os = "macos")]
+	{
+		parse_colon_value(&crate::resolver::command_output("route", &["-n", "get", "default"])?, "interface")
+	}
+	#[cfg(windows)]
+	{
+		let command = "Get-NetRoute -DestinationPrefix 0.0.0.0/0 | Sort-Object RouteMetric | Select-Object -First 1 -ExpandProperty InterfaceAlias";
+		let output = crate::resolver::command_output("powershell", &["-NoProfile", "-NonInteractive", "-Command", command])?;
+		output.lines().map(str::trim).find(|l| !l.is_empty()).map(str::to_string)
+	}
+	#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+	{
+		None
+	}
+}
+
+/// SSID the interface is associated with; None for wired interfaces or
+/// when the platform tools are missing.
+fn wifi_ssid(interface: &str) -> Option<String> {
+	#[cfg(target_os = "linux")]
+	{
+		let iwgetid = crate::resolver::command_output("iwgetid", &["-r", interface])
+			.map(|out| out.trim().to_string())
+			.filter(|s| !s.is_empty());
+		iwgetid.or_else(|| {
+			let out = crate::resolver::command_output("nmcli", &["-t", "-f", "active,ssid", "dev", "wifi", "list", "ifname", interface, "--rescan", "no"])?;
+			parse_nmcli_active(&out)
+		})
+	}
+	#[cfg(target_os = "macos")]
+	{
+		let out = crate::resolver::command_output("networksetup", &["-getairportnetwork", interface])?;
+		parse_colon_value(&out, "Current Wi-Fi Network")
+	}
+	#[cfg(windows)]
+	{
+		let out = crate::resolver::command_output("netsh", &["wlan", "show", "interfaces"])?;
+		parse_netsh_ssid(&out, interface)
+	}
+	#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+	{
+		let _ = interface;
+		None
+	}
+}
+
+/// Interface of the lowest-metric default route in /proc/net/route.
+#[cfg(any(target_os = "linux", test))]
+fn parse_route_interface(text: &str) -> Option<String> {
+	text.lines().skip(1)
+		.filter_map(|line| {
+			let fields: Vec<&str> = line.split_whitespace().collect();
+			(fields.len() >= 7 && fields[1] == "00000000")
+				.then(|| Some((fields[6].parse::<u32>().ok()?, fields[0].to_string())))
+				.flatten()
+		})
+		.min_by_key(|(metric, _)| *metric)
+		.map(|(_, name)| name)
+}
+
+/// SSID of the `yes:` line of `nmcli -t -f active,ssid dev wifi list`.
+#[cfg(any(target_os = "linux", test))]
+fn parse_nmcli_active(text: &str) -> Option<String> {
+	text.lines()
+		.filter_map(|line| line.strip_prefix("yes:"))
+		// nmcli terse output escapes colons in values
+		.map(|ssid| ssid.replace("\\:", ":"))
+		.find(|ssid| !ssid.is_empty())
+}
+
+/// Value after `key:` on any line, e.g. `interface: en0` from macOS
+/// `route -n get default` or `Current Wi-Fi Network: home` from networksetup.
+#[cfg(any(target_os = "macos", test))]
+fn parse_colon_value(text: &str, key: &str) -> Option<String> {
+	text.lines()
+		.filter_map(|line| line.trim().strip_prefix(key)?.strip_prefix(':'))
+		.map(|value| value.trim().to_string())
+		.find(|value| !value.is_empty())
+}
+
+/// SSID of the named interface from `netsh wlan show interfaces`, whose
+/// blocks start with a `Name` line and carry `SSID` (not `BSSID`) lines.
+#[cfg(any(windows, test))]
+fn parse_netsh_ssid(text: &str, interface: &str) -> Option<String> {
+	let mut in_interface = false;
+	for line in text.lines() {
+		let Some((key, value)) = line.split_once(':') else { continue };
+		let (key, value) = (key.trim(), value.trim());
+		match key {
+			"Name" => in_interface = value.eq_ignore_ascii_case(interface),
+			"SSID" if in_interface && !value.is_empty() => return Some(value.to_string()),
+			_ => {}
+		}
+	}
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_parse_interface_and_ssid() {
+		let route = "Iface\tDestination\tGateway\tFlags\tRefCnt\tUse\tMetric\tMask\n\
+			wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\n\
+			eth0\t00000000\t0100000A\t0003\t0\t0\t100\t00000000\n\
+			eth0\t0000000A\t00000000\t0001\t0\t0\t100\t00FFFFFF\n";
+		assert_eq!(parse_route_interface(route).as_deref(), Some("eth0"));
+		assert_eq!(parse_route_interface("Iface\tDestination\n"), None);
+
+		assert_eq!(parse_nmcli_active("no:Neighbor\nyes:Cafe\\: Guest\n").as_deref(), Some("Cafe: Guest"));
+		assert_eq!(parse_nmcli_active("no:Neighbor\n"), None);
+		assert_eq!(parse_colon_value("   route to: default\n  interface: en0\n", "interface").as_deref(), Some("en0"));
+		assert_eq!(parse_colon_value("Current Wi-Fi Network: home-wifi\n", "Current Wi-Fi Network").as_deref(), Some("home-wifi"));
+		assert_eq!(parse_colon_value("You are not associated with an AirPort network.\n", "Current Wi-Fi Network"), None);
+
+		let netsh = "    Name                   : Wi-Fi 2\n    SSID                   : Other\n\n\
+			    Name                   : Wi-Fi\n    State                  : connected\n\
+			    SSID                   : office\n    BSSID                  : aa:bb:cc:dd:ee:ff\n";
+		assert_eq!(parse_netsh_ssid(netsh, "Wi-Fi").as_deref(), Some("office"));
+		assert_eq!(parse_netsh_ssid(netsh, "Ethernet"), None);
+	}
+}
@@ -5,7 +5,7 @@ use anyhow::Result;
 use std::io::Write;
 
 use crate::record::ResolverRecord;
-use crate::transport::{BenchmarkConfig, Resolver};
+use crate::transport::{BenchmarkConfig, DnsTransport, Resolver};
 
 /// Phase timing entry: (name, duration, optional before/after resolver counts)
 pub type PhaseTimingEntry = (&'static str, std::time::Duration, Option<(usize, usize)>);
@@ -44,36 +44,36 @@ pub fn print_config_summary(
 	config: &BenchmarkConfig,
 ) {
 	// Section 1: Resolvers under test
-	println!("DNS Benchmark");
-	println!("=============");
-	println!("Resolvers under test: {}", resolvers.len());
+	crate::info!("DNS Benchmark");
+	crate::info!("=============");
+	crate::info!("Resolvers under test: {}", resolvers.len());
 	if resolvers.len() <= 20 {
 		for r in resolvers {
-			println!("  - {} ({})", r.label, r.addr);
+			crate::info!("  - {} ({})", r.label, r.addr);
 		}
 	} else {
 		// Show first 5 and last 2 to keep output manageable
 		for r in resolvers.iter().take(5) {
-			println!("  - {} ({})", r.label, r.addr);
+			crate::info!("  - {} ({})", r.label, r.addr);
 		}
-		println!("  ... ({} more)", resolvers.len() - 7);
+		crate::info!("  ... ({} more)", resolvers.len() - 7);
 		for r in resolvers.iter().skip(resolvers.len() - 2) {
-			println!("  - {} ({})", r.label, r.addr);
+			crate::info!("  - {} ({})", r.label, r.addr);
 		}
 	}
 
 	// Section 2: Query domains (what we send to each resolver)
-	println!();
+	crate::info!();
 	let total_domains: usize = categories.values().map(|v| v.len()).sum();
-	println!("Query domains ({} total, sent to each resolver):", total_domains);
+	crate::info!("Query domains ({} total, sent to each resolver):", total_domains);
 	for (category, domains) in categories {
 		// Show a few example domains for context
 		let examples: Vec<&str> = domains.iter().take(3).map(|s| s.as_str()).collect();
 		let example_str = examples.join(", ");
 		if domains.len() > 3 {
-			println!("  {:<12} {:>3}  ({}, ...)", category, domains.len(), example_str);
+			crate::info!("  {:<12} {:>3}  ({}, ...)", category, domains.len(), example_str);
 		} else {
-			println!("  {:<12} {:>3}  ({})", category, domains.len(), example_str);
+			crate::info!("  {:<12} {:>3}  ({})", category, domains.len(), example_str);
 		}
 	}
 
@@ -84,47 +84,102 @@ pub fn print_config_summary(
 		crate::stats::SortMode::Score => "overall score".to_string(),
 		crate::stats::SortMode::Category(name) => format!("{} p50", name),
 		crate::stats::SortMode::Name => "name".to_string(),
+		crate::stats::SortMode::Stability => "stability (latency CV)".to_string(),
 	};
 
-	println!();
-	println!("Build:              {}", env!("BUILD_TIMESTAMP"));
-	println!();
-	println!("Options:");
-	println!("  Level:            {}", config.level);
-	println!("  Query AAAA:       {}", aaaa_label);
-	println!("  DNSSEC (DO):      {}", dnssec_label);
-	println!("  Sort by:          {}", sort_label);
-	println!("  Pin system:       yes");
+	crate::info!();
+	crate::info!("Build:              {}", env!("BUILD_TIMESTAMP"));
+	crate::info!();
+	crate::info!("Options:");
+	crate::info!("  Level:            {}", config.level);
+	crate::info!("  Query AAAA:       {}", aaaa_label);
+	if config.query_https {
+		crate::info!("  Query HTTPS:      yes");
+	}
+	if config.connection_mode == crate::cli::ConnectionMode::PerQuery {
+		crate::info!("  DoT/DoH conns:    new per query");
+	}
+	if config.doh_http != crate::cli::DohHttpVersion::Http2 {
+		crate::info!("  DoH version:      {}", config.doh_http);
+	}
+	if let Some(proxy) = &config.proxy {
+		crate::info!("  Proxy (DoT/DoH):  {}", proxy.display());
+	}
+	let network = crate::network::network();
+	if !network.label.is_empty() {
+		// SSID and interface, unless one of them is the label already
+		let detail: Vec<String> = [("SSID ", network.ssid), ("via ", network.interface)].into_iter()
+			.filter_map(|(prefix, value)| value.filter(|v| *v != network.label).map(|v| format!("{}{}", prefix, v)))
+			.collect();
+		if detail.is_empty() {
+			crate::info!("  Network:          {}", network.label);
+		} else {
+			crate::info!("  Network:          {} ({})", network.label, detail.join(", "));
+		}
+	}
+	let source = crate::source::source();
+	if let Some(ip) = source.ip {
+		crate::info!("  Source address:   {}", ip);
+	}
+	if let Some(name) = &source.interface {
+		crate::info!("  Interface:        {}", name);
+	}
+	crate::info!("  DNSSEC (DO):      {}", dnssec_label);
+	crate::info!("  Sort by:          {}", sort_label);
+	crate::info!("  Pin system:       yes");
 	if let Some(seed) = config.seed {
-		println!("  Seed:             {}", seed);
+		crate::info!("  Seed:             {}", seed);
 	}
 
 	if config.discover {
-		println!();
-		println!("Discovery phase:");
-		println!("  UDP timeout:      {} ms", crate::bench::SCREEN_TIMEOUT_MS);
-		println!("  TLS timeout:      {} ms", crate::bench::SCREEN_TLS_TIMEOUT_MS);
-		println!("  Concurrency:      {}", crate::bench::DISCOVERY_CONCURRENCY.max(config.max_inflight));
+		crate::info!();
+		crate::info!("Discovery phase:");
+		crate::info!("  UDP timeout:      {} ms", crate::bench::SCREEN_TIMEOUT_MS);
+		crate::info!("  TLS timeout:      {} ms", crate::bench::SCREEN_TLS_TIMEOUT_MS);
+		crate::info!("  Concurrency:      {}", crate::bench::DISCOVERY_CONCURRENCY.max(config.max_inflight));
+		crate::info!("  Waves:            1 query -> {}, {} queries -> {}, warm set -> {}",
+			4 * crate::transport::DEFAULT_DISCOVERY_TOP_N,
+			crate::transport::DEFAULT_DISCOVERY_WAVE2_QUERIES,
+			2 * crate::transport::DEFAULT_DISCOVERY_TOP_N,
+			crate::transport::DEFAULT_DISCOVERY_TOP_N);
 	}
 
-	println!();
-	println!("Characterization phase:");
-	println!("  Timeout:          {} ms", crate::transport::DEFAULT_CHAR_TIMEOUT_MS);
-	println!("  Attempts:         {}", crate::transport::DEFAULT_CHAR_ATTEMPTS);
+	crate::info!();
+	crate::info!("Characterization phase:");
+	crate::info!("  Timeout:          {} ms", crate::transport::DEFAULT_CHAR_TIMEOUT_MS);
+	crate::info!("  Attempts:         {}", crate::transport::DEFAULT_CHAR_ATTEMPTS);
 
 	if config.level == crate::cli::BenchLevel::Medium {
-		println!();
-		println!("Qualification phase:");
-		println!("  Budget:           {}", crate::transport::DEFAULT_MEDIUM_BUDGET);
+		crate::info!();
+		crate::info!("Qualification phase:");
+		crate::info!("  Budget:           {}", crate::transport::DEFAULT_MEDIUM_BUDGET);
 	}
 
-	println!();
-	println!("Benchmark phase:");
-	println!("  Rounds:           {}", config.rounds);
-	println!("  Timeout:          {} ms", config.timeout.as_millis());
-	println!("  Concurrency:      {}", config.max_inflight);
-	println!("  Spacing:          {} ms", config.inter_query_spacing.as_millis());
-	println!();
+	crate::info!();
+	crate::info!("Benchmark phase:");
+	crate::info!("  Rounds:           {}", config.rounds);
+	crate::info!("  Timeout:          {} ms", config.timeout.as_millis());
+	crate::info!("  UDP retries:      {}", config.retries);
+	crate::info!("  Concurrency:      {}", config.max_inflight);
+	crate::info!("  Per resolver:     {} in flight, {}", config.per_resolver_inflight,
+		config.per_resolver_qps.map(|q| format!("{} qps", q)).unwrap_or_else(|| "no qps limit".to_string()));
+	crate::info!("  Spacing:          {} ms", config.inter_query_spacing.as_millis());
+	if let Some(profile) = config.profile {
+		crate::info!("  Profile:          {}", profile);
+	}
+	if config.score_weights != crate::stats::ScoreWeights::DEFAULT {
+		let w = &config.score_weights;
+		crate::info!("  Score weights:    tail {}, timeout x{}, failure {} ms", w.tail_weight, w.timeout_scale, w.failure_penalty_ms);
+		if (w.warm_weight, w.cold_weight, w.aaaa_weight) != (1.0, 1.0, 0.0) {
+			crate::info!("  Set weights:      cached x{}, {} x{}, AAAA x{}, others x1",
+				w.warm_weight, crate::domains::COLD_SET, w.cold_weight, w.aaaa_weight);
+		}
+		if w.interception_penalty_ms + w.dnssec_bonus_ms + w.filtering_penalty_ms > 0.0 {
+			crate::info!("  Score adjust:     +{} ms intercepting, -{} ms DNSSEC, +{} ms filtering",
+				w.interception_penalty_ms, w.dnssec_bonus_ms, w.filtering_penalty_ms);
+		}
+	}
+	crate::info!();
 }
 
 /// Collect the ordered list of category names present in results.
@@ -151,11 +206,32 @@ pub fn print_results_table(results: &[ResolverRecord]) {
 	// Check if any resolvers use non-UDP transport
 	let has_mixed_transport = results.iter()
 		.any(|r| r.resolver.transport.to_string() != "UDP");
+	// Show the port column only when some resolver is off its transport's default port
+	let has_custom_port = results.iter()
+		.any(|r| r.resolver.addr.port() != crate::transport::default_port(&r.resolver.transport));
+	// Show the retried column only when some answer needed a UDP retransmission
+	let has_retries = results.iter()
+		.filter_map(|r| r.benchmark.as_ref())
+		.any(|bm| bm.retry_success_rate > 0.0);
+	// Show the errors column only when some resolver answered with an error
+	// code or failed for a cause other than a timeout
+	let has_errors = results.iter()
+		.filter_map(|r| r.benchmark.as_ref())
+		.any(|bm| bm.categories.values().any(|s| s.rcodes.total() + s.failures.total() > 0));
+	let has_loss_probe = results.iter().any(|r| r.loss_probe.is_some());
+	let has_asn = results.iter().any(|r| r.asn.is_some());
+	let has_traceroute = results.iter().any(|r| r.traceroute.is_some());
 
 	// Build header dynamically
 	let mut header: Vec<String> = vec![
 		"Rank".to_string(), "Resolver".to_string(), "IP Address".to_string(),
 	];
+	if has_custom_port {
+		header.push("Port".to_string());
+	}
+	if has_asn {
+		header.push("Network".to_string());
+	}
 	if has_mixed_transport {
 		header.push("Proto".to_string());
 	}
@@ -164,7 +240,21 @@ pub fn print_results_table(results: &[ResolverRecord]) {
 	for cat in &category_names {
 		header.push(format!("{} p50", cat));
 	}
+	header.push("CV %".to_string());
 	header.push("Success %".to_string());
+	if has_errors {
+		header.push("Errors".to_string());
+	}
+	if has_retries {
+		header.push("Retried %".to_string());
+	}
+	if has_loss_probe {
+		header.push("Loss %".to_string());
+	}
+	if has_traceroute {
+		header.push("Hops".to_string());
+	}
+	header.push("Cache %".to_string());
 	header.push("NXDOMAIN".to_string());
 	header.push("DNSSEC".to_string());
 	header.push("Rebind".to_string());
@@ -200,21 +290,31 @@ pub fn print_results_table(results: &[ResolverRecord]) {
 		// NXDOMAIN status with color
 		let nxdomain_cell = if r.intercepts_nxdomain() {
 			Cell::new("Intercepts").fg(Color::Red)
+		} else if r.nxdomain_inconclusive() {
+			Cell::new("Inconclusive").fg(Color::Yellow)
+		} else if r.possibly_censoring() {
+			Cell::new("Censors?").fg(Color::Yellow)
 		} else {
 			Cell::new("OK").fg(Color::Green)
 		};
 
-		// Build label with optional system marker and PTR name
+		// Build label with optional system marker and hostname: the one the
+		// address was resolved from, else the PTR name
 		let mut label = r.resolver.label.clone();
-		if let Some(ref ptr) = r.resolver.ptr_name {
-			// Only show PTR if it differs from the label
-			if ptr != &r.resolver.label {
-				label = format!("{} ({})", label, ptr);
+		if let Some(name) = r.resolver.hostname.as_ref().or(r.resolver.ptr_name.as_ref()) {
+			// Only show the name if it differs from the label
+			if name != &r.resolver.label {
+				label = format!("{} ({})", label, name);
 			}
 		}
 		if r.resolver.is_system {
 			label = format!("{} [sys]", label);
 		}
+		if bm.unreachable {
+			label = format!("{} [unreachable]", label);
+		} else if bm.unresponsive {
+			label = format!("{} [unresponsive]", label);
+		}
 
 		// Build row with colored cells
 		let mut row: Vec<Cell> = vec![
@@ -222,6 +322,15 @@ pub fn print_results_table(results: &[ResolverRecord]) {
 			Cell::new(label),
 			Cell::new(r.resolver.addr.ip().to_string()),
 		];
+		if has_custom_port {
+			row.push(Cell::new(r.resolver.addr.port()));
+		}
+		if has_asn {
+			row.push(match &r.asn {
+				Some(asn) => Cell::new(asn.display()),
+				None => Cell::new("-").fg(Color::DarkGrey),
+			});
+		}
 		if has_mixed_transport {
 			row.push(Cell::new(r.resolver.transport.to_string()));
 		}
@@ -240,10 +349,67 @@ pub fn print_results_table(results: &[ResolverRecord]) {
 			}
 		}
 
+		// Latency consistency: mean coefficient of variation across sets
+		let cv_cell = match bm.stability_cv {
+			Some(cv) => Cell::new(format!("{:.0}%", cv * 100.0)),
+			None => Cell::new("-").fg(Color::DarkGrey),
+		};
+		row.push(cv_cell);
+
 		// Success rate with color
 		let success_text = format!("{:.1}%", bm.success_rate);
 		row.push(Cell::new(&success_text).fg(success_color(bm.success_rate)));
 
+		// Error answers by response code and failures by cause, over every set
+		if has_errors {
+			let mut rcodes = crate::stats::RcodeCounts::default();
+			let mut failures = crate::stats::FailureCounts::default();
+			for set in bm.categories.values() {
+				rcodes.add(&set.rcodes);
+				failures.add(&set.failures);
+			}
+			let summary = [rcodes.summary(), failures.summary()].into_iter()
+				.filter(|s| !s.is_empty())
+				.collect::<Vec<_>>()
+				.join(", ");
+			row.push(if !summary.is_empty() {
+				Cell::new(summary).fg(Color::Yellow)
+			} else {
+				Cell::new("-").fg(Color::DarkGrey)
+			});
+		}
+
+		// Share of answers that arrived only after a retransmission (packet loss)
+		if has_retries {
+			row.push(Cell::new(format!("{:.1}%", bm.retry_success_rate)));
+		}
+
+		// Estimated packet loss from the burst probe (UDP only)
+		if has_loss_probe {
+			let loss_cell = match &r.loss_probe {
+				Some(lp) if lp.loss_pct > 0.0 => Cell::new(format!("{:.0}%", lp.loss_pct)).fg(Color::Yellow),
+				Some(lp) => Cell::new(format!("{:.0}%", lp.loss_pct)),
+				None => Cell::new("-").fg(Color::DarkGrey),
+			};
+			row.push(loss_cell);
+		}
+
+		// Path length; ">N" when the resolver itself did not answer the trace
+		if has_traceroute {
+			let hops_cell = match &r.traceroute {
+				Some(t) => Cell::new(format!("{}{} ({:.1} ms)", if t.reached { "" } else { ">" }, t.hops, t.last_hop_rtt_ms)),
+				None => Cell::new("-").fg(Color::DarkGrey),
+			};
+			row.push(hops_cell);
+		}
+
+		// Measured cache-hit rate from TTL inspection
+		let cache_cell = match bm.cache_hit_rate {
+			Some(pct) => Cell::new(format!("{:.0}%", pct)),
+			None => Cell::new("-").fg(Color::DarkGrey),
+		};
+		row.push(cache_cell);
+
 		row.push(nxdomain_cell);
 
 		// DNSSEC cell with color
@@ -265,8 +431,14 @@ pub fn print_results_table(results: &[ResolverRecord]) {
 		table.add_row(row);
 	}
 
-	println!("\nBenchmark Results");
-	println!("=================\n");
+	if crate::bench::interrupted() {
+		println!("\nBenchmark Results -- INCOMPLETE");
+		println!("================================\n");
+		println!("Interrupted by Ctrl-C: scores cover only the queries finished before it.\n");
+	} else {
+		println!("\nBenchmark Results");
+		println!("=================\n");
+	}
 	println!("{table}");
 
 	if has_ties {
@@ -288,16 +460,16 @@ pub fn print_pipeline_summary(
 	post_char: usize,
 	final_count: usize,
 ) {
-	println!("\nResolver Pipeline");
-	println!("-----------------");
-	println!("  Started:              {}", initial);
+	crate::info!("\nResolver Pipeline");
+	crate::info!("-----------------");
+	crate::info!("  Started:              {}", initial);
 	if post_discovery != initial {
-		println!("  After discovery:      {}", post_discovery);
+		crate::info!("  After discovery:      {}", post_discovery);
 	}
 	if post_char != post_discovery {
-		println!("  After characterization: {}", post_char);
+		crate::info!("  After characterization: {}", post_char);
 	}
-	println!("  Final results:        {}", final_count);
+	crate::info!("  Final results:        {}", final_count);
 }
 
 /// Print a compact phase-by-phase timing breakdown.
@@ -305,8 +477,8 @@ pub fn print_phase_timing(
 	phases: &[PhaseTimingEntry],
 	total: std::time::Duration,
 ) {
-	println!("\nPhase Timing");
-	println!("------------");
+	crate::info!("\nPhase Timing");
+	crate::info!("------------");
 	for (name, dur, counts) in phases {
 		let secs = dur.as_secs();
 		let time_str = if secs >= 60 {
@@ -319,7 +491,7 @@ pub fn print_phase_timing(
 			Some((before, after)) => format!("  ({} -> {} resolvers)", before, after),
 			None => String::new(),
 		};
-		println!("  {:<20} {:>8}{}", name, time_str, count_str);
+		crate::info!("  {:<20} {:>8}{}", name, time_str, count_str);
 	}
 	let total_secs = total.as_secs();
 	let total_str = if total_secs >= 60 {
@@ -327,7 +499,84 @@ pub fn print_phase_timing(
 	} else {
 		format!("{}s", total_secs)
 	};
-	println!("  {:<20} {:>8}", "Total", total_str);
+	crate::info!("  {:<20} {:>8}", "Total", total_str);
+}
+
+/// Warm-set p50 in ms (the "cached" category), if benchmarked.
+fn warm_p50(record: &ResolverRecord) -> Option<f64> {
+	record.benchmark.as_ref()?.categories.get("cached").map(|c| c.p50_ms)
+}
+
+/// Join phrases as "a", "a and b", or "a, b, and c".
+fn join_phrases(phrases: &[String]) -> String {
+	match phrases {
+		[] => String::new(),
+		[one] => one.clone(),
+		[first, second] => format!("{} and {}", first, second),
+		[rest @ .., last] => format!("{}, and {}", rest.join(", "), last),
+	}
+}
+
+/// Why a resolver was picked: warm latency, then characterization results.
+///
+/// `fastest_warm` is the lowest warm p50 among all results, so the pick can
+/// be called the fastest when it is.
+fn pick_reasons(record: &ResolverRecord, fastest_warm: Option<f64>) -> Vec<String> {
+	let mut reasons = Vec::new();
+	match (warm_p50(record), fastest_warm) {
+		(Some(p50), Some(best)) if p50 <= best => reasons.push(format!("has the fastest warm p50 ({:.1} ms)", p50)),
+		(Some(p50), _) => reasons.push(format!("has a warm p50 of {:.1} ms", p50)),
+		(None, _) => {}
+	}
+	if record.characterization.as_ref().and_then(|c| c.intercepts_nxdomain) == Some(false) {
+		reasons.push("does not intercept NXDOMAIN".to_string());
+	}
+	match record.validates_dnssec() {
+		Some(true) => reasons.push("validates DNSSEC".to_string()),
+		Some(false) => reasons.push("does not validate DNSSEC".to_string()),
+		None => {}
+	}
+	if let Some(bm) = &record.benchmark {
+		if bm.success_rate < 100.0 {
+			reasons.push(format!("answered {:.1}% of queries", bm.success_rate));
+		}
+	}
+	reasons
+}
+
+/// Print the recommended primary and secondary resolver with a short
+/// explanation of why they were picked.
+///
+/// Uses the recommendation policy's eligibility and order (best overall
+/// score, excluding interception, wrong answers, and low success), so the
+/// summary matches --policy output.
+pub fn print_recommendation(results: &[ResolverRecord]) {
+	let eligible = crate::policy::eligible_by_score(results);
+	let Some(primary) = eligible.first() else { return };
+	let fastest_warm = results.iter()
+		.filter_map(warm_p50)
+		.min_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	let score = |r: &ResolverRecord| r.benchmark.as_ref().map(|bm| bm.overall_score).unwrap_or(f64::INFINITY);
+
+	println!("\nRecommendation");
+	println!("--------------");
+	println!("  Primary:   {} ({}, {}), score {:.1}",
+		primary.resolver.label, primary.resolver.addr.ip(), primary.resolver.transport, score(primary));
+	if let Some(secondary) = eligible.get(1) {
+		println!("  Secondary: {} ({}, {}), score {:.1}",
+			secondary.resolver.label, secondary.resolver.addr.ip(), secondary.resolver.transport, score(secondary));
+	}
+	let mut sentences = vec![format!("{} has the best overall score", primary.resolver.label)];
+	let reasons = pick_reasons(primary, fastest_warm);
+	if !reasons.is_empty() {
+		sentences[0] = format!("{}, {}", sentences[0], join_phrases(&reasons));
+	}
+	if let Some(secondary) = eligible.get(1) {
+		let mut phrases = vec![format!("is {:.1} points behind", score(secondary) - score(primary))];
+		phrases.extend(pick_reasons(secondary, fastest_warm));
+		sentences.push(format!("{} {}", secondary.resolver.label, join_phrases(&phrases)));
+	}
+	println!("  {}.", sentences.join(". "));
 }
 
 /// Print heuristic conclusions about the benchmark results.
@@ -389,6 +638,20 @@ pub fn print_conclusions(results: &[ResolverRecord]) {
 				r.resolver.label,
 			);
 		}
+		// Warn about answers that do not match a known record
+		if r.answers_known_record() == Some(false) {
+			println!(
+				"  Warning: {} returned the wrong root NS set; its answers may be rewritten (captive portal or hijack).",
+				r.resolver.label,
+			);
+		}
+		// Warn about NXDOMAIN answers for domains that exist
+		if let Some((count, pct)) = r.unexpected_nxdomain().filter(|_| r.possibly_censoring()) {
+			println!(
+				"  Warning: {} answered NXDOMAIN for {} real domain queries ({:.1}%); it may be filtering or censoring.",
+				r.resolver.label, count, pct,
+			);
+		}
 		// Warn about missing DNSSEC validation
 		if r.validates_dnssec() == Some(false) {
 			println!(
@@ -412,66 +675,868 @@ pub fn print_conclusions(results: &[ResolverRecord]) {
 		}
 	}
 
-	// IPv4 vs IPv6 comparison for same-provider pairs
-	let first_cat_name = result_category_names(results).into_iter().next();
-	let mut pairs_printed = false;
+	print_dual_stack(results);
+}
+
+/// One dual-stack pair: pair key, IPv4 p50, IPv6 p50.
+type StackPair = (String, f64, f64);
+
+/// Print IPv4 vs IPv6 latency for each provider that was benchmarked on both.
+///
+/// Entries pair up by label with the "-v6" marker removed ("Google" with
+/// "Google-v6") and group by provider (the label up to the first dash).
+/// Deltas use the first set's p50; positive means IPv6 is slower.
+fn print_dual_stack(results: &[ResolverRecord]) {
+	let cat = match result_category_names(results).into_iter().next() {
+		Some(c) => c,
+		None => return,
+	};
+	let p50 = |r: &ResolverRecord| r.benchmark.as_ref()
+		.and_then(|bm| bm.categories.get(&cat))
+		.map(|s| s.p50_ms);
+	// Providers in result order, each with its pairs
+	let mut providers: Vec<(&str, Vec<StackPair>)> = Vec::new();
+	for v4 in results.iter().filter(|r| r.resolver.addr.is_ipv4()) {
+		let key = crate::resolver::dual_stack_key(&v4.resolver.label);
+		let v6 = results.iter().find(|r| {
+			r.resolver.addr.is_ipv6() && crate::resolver::dual_stack_key(&r.resolver.label) == key
+		});
+		let (Some(v4_ms), Some(v6_ms)) = (p50(v4), v6.and_then(p50)) else { continue };
+		let provider = crate::resolver::provider_name(&v4.resolver.label);
+		match providers.iter_mut().find(|(name, _)| *name == provider) {
+			Some((_, pairs)) => pairs.push((key, v4_ms, v6_ms)),
+			None => providers.push((provider, vec![(key, v4_ms, v6_ms)])),
+		}
+	}
+	if providers.is_empty() {
+		return;
+	}
+	println!("\nIPv4 vs IPv6 Comparison ({} p50)", cat);
+	println!("----------------------");
+	let mut v6_faster = 0;
+	for (provider, pairs) in &providers {
+		let mut deltas: Vec<f64> = pairs.iter().map(|(_, v4, v6)| v6 - v4).collect();
+		deltas.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+		let median = crate::stats::percentile(&deltas, 50.0).unwrap_or(0.0);
+		if median < 0.0 {
+			v6_faster += 1;
+		}
+		println!("  {} (median IPv6 delta {:+.1} ms)", provider, median);
+		for (key, v4, v6) in pairs {
+			let pct = if *v4 > 0.0 { (v6 - v4) / v4 * 100.0 } else { 0.0 };
+			let direction = if v6 > v4 { "slower" } else { "faster" };
+			println!("    {:<28} IPv4 {:>7.1} ms  IPv6 {:>7.1} ms  {:+.1} ms ({:.0}% {})",
+				key, v4, v6, v6 - v4, pct.abs(), direction);
+		}
+	}
+	println!("  IPv6 faster for {} of {} providers.", v6_faster, providers.len());
+}
+
+/// Resolvers from the top of the results compared pairwise for significance
+const SIGNIFICANCE_TOP_N: usize = 5;
+
+/// Print pairwise Mann-Whitney U tests between the top displayed resolvers.
+///
+/// Shows which latency differences are statistically significant and which
+/// are within noise. The p-values are Holm-corrected across all pairs, since
+/// up to 10 tests are run at once. Skipped when fewer than two resolvers
+/// have results.
+pub fn print_significance(results: &[ResolverRecord]) {
+	let top: Vec<(&ResolverRecord, Vec<f64>)> = results.iter()
+		.filter_map(|r| r.benchmark.as_ref().map(|bm| (r, bm.raw.scored_latencies_ms())))
+		.take(SIGNIFICANCE_TOP_N)
+		.collect();
+	if top.len() < 2 {
+		return;
+	}
+	let pairs: Vec<(usize, usize)> = (0..top.len())
+		.flat_map(|i| ((i + 1)..top.len()).map(move |j| (i, j)))
+		.collect();
+	let p_values: Vec<Option<f64>> = pairs.iter()
+		.map(|&(i, j)| crate::stats::mann_whitney_u(&top[i].1, &top[j].1).map(|t| t.p_value))
+		.collect();
+	let adjusted = crate::stats::holm_adjust(&p_values);
+	println!("\nRanking Significance (latency only; Mann-Whitney U, Holm-corrected over {} pairs, alpha {})",
+		pairs.len(), crate::stats::SIGNIFICANCE_ALPHA);
+	println!("---------------------------------------------------------------------------------------------");
+	for (&(i, j), p) in pairs.iter().zip(adjusted) {
+		let verdict = match p {
+			Some(p) if p < crate::stats::SIGNIFICANCE_ALPHA => format!("p={:.4}  significant", p),
+			Some(p) => format!("p={:.4}  not significant", p),
+			None => "too few samples".to_string(),
+		};
+		println!("  #{} {:<24} vs #{} {:<24} {}",
+			i + 1, top[i].0.resolver.label, j + 1, top[j].0.resolver.label, verdict);
+	}
+	println!("  Scores also weigh timeouts and failures, which this test does not cover.");
+}
+
+/// Print how the top of the ranking changes under perturbed scoring weights.
+///
+/// States whether the winner holds under every scenario, so users can tell
+/// a clear-cut recommendation from an artifact of the score formula.
+/// Skipped when fewer than two resolvers have results.
+pub fn print_score_sensitivity(results: &[ResolverRecord], timeout_penalty_ms: f64, weights: &crate::stats::ScoreWeights) {
+	if results.iter().filter(|r| r.benchmark.is_some()).count() < 2 {
+		return;
+	}
+	let scenarios = crate::stats::score_sensitivity(results, timeout_penalty_ms, weights);
+	let baseline = match scenarios.first() {
+		Some(b) => b,
+		None => return,
+	};
+	println!("\nScore Sensitivity (top {} under perturbed weights)", crate::stats::SENSITIVITY_TOP_N);
+	println!("---------------------------------------------------");
+	let mut flips = Vec::new();
+	for s in &scenarios {
+		let verdict = if s.top == baseline.top {
+			"same"
+		} else if s.top.first() == baseline.top.first() {
+			"order changed"
+		} else {
+			flips.push(s.name);
+			"winner changed"
+		};
+		println!("  {:<22} {:<52} {}", s.name, s.top.join(" > "), verdict);
+	}
+	let winner = baseline.top.first().map(String::as_str).unwrap_or("-");
+	if flips.is_empty() {
+		println!("  Winner {} is robust to these scoring choices.", winner);
+	} else {
+		println!("  Winner {} changes under: {}; the pick depends on how tail latency and timeouts are weighed.",
+			winner, flips.join(", "));
+	}
+}
+
+/// Print the providers ranked by score over all their addresses (--by-provider).
+///
+/// Each provider's per-set latencies and counts are pooled across its
+/// addresses and scored as one resolver, so a provider is not ranked by
+/// its single luckiest IP.
+pub fn print_provider_ranking(results: &[ResolverRecord], timeout_penalty_ms: f64, weights: &crate::stats::ScoreWeights) {
+	let providers = crate::stats::provider_stats(results, timeout_penalty_ms, weights);
+	if providers.is_empty() {
+		return;
+	}
+	let mut table = Table::new();
+	table.load_preset(UTF8_FULL);
+	table.set_content_arrangement(ContentArrangement::Dynamic);
+	table.set_header(vec!["Rank", "Provider", "Addresses", "Score", "p50", "Success %"]);
+	for (i, p) in providers.iter().enumerate() {
+		table.add_row(vec![
+			Cell::new(i + 1),
+			Cell::new(&p.name).add_attribute(Attribute::Bold),
+			Cell::new(p.members.len()),
+			Cell::new(format!("{:.1}", p.score)).fg(latency_color(p.score)),
+			Cell::new(format!("{:.1} ms", p.p50_ms)).fg(latency_color(p.p50_ms)),
+			Cell::new(format!("{:.1}%", p.success_rate)).fg(success_color(p.success_rate)),
+		]);
+	}
+	println!("\nProvider Ranking (all addresses pooled)");
+	println!("{table}");
+}
+
+/// Print the path probe diagnostic: network round trip vs DNS overhead.
+///
+/// Only prints when at least one record has a path probe result.
+pub fn print_path_probe(results: &[ResolverRecord]) {
+	let probed: Vec<&ResolverRecord> = results.iter()
+		.filter(|r| r.path_probe.is_some())
+		.collect();
+	if probed.is_empty() {
+		return;
+	}
+	println!("\nPath Probe (diagnostic, not scored)");
+	println!("-----------------------------------");
+	println!("  {:<28} {:>10} {:>10} {:>10} {:>12}", "Resolver", "DNS p50", "Ping", "TCP conn", "DNS overhead");
+	for r in probed {
+		let pp = match &r.path_probe { Some(pp) => pp, None => continue };
+		let ping_str = pp.ping_ms
+			.map(|ms| format!("{:.1} ms", ms))
+			.unwrap_or_else(|| "-".to_string());
+		let tcp_str = pp.tcp_connect_ms
+			.map(|ms| format!("{:.1} ms", ms))
+			.unwrap_or_else(|| "refused".to_string());
+		let overhead_str = pp.dns_overhead_ms
+			.map(|ms| format!("{:.1} ms", ms))
+			.unwrap_or_else(|| "-".to_string());
+		println!("  {:<28} {:>10} {:>10} {:>10} {:>12}",
+			r.resolver.label, format!("{:.1} ms", pp.dns_p50_ms), ping_str, tcp_str, overhead_str);
+	}
+	println!("  Ping (else TCP connect) time is the network round trip; DNS overhead is the rest:");
+	println!("  a large overhead points at the resolver, a large round trip at distance.");
+}
+
+/// Print TLS connection setup for DoT and DoH resolvers, apart from query latency.
+///
+/// Only prints when the TLS setup probe ran. A resumed handshake saves a
+/// round trip or more on every reconnect; 0-RTT lets the first query ride
+/// along with the handshake.
+pub fn print_tls_setup(results: &[ResolverRecord]) {
+	let probed: Vec<&ResolverRecord> = results.iter()
+		.filter(|r| r.tls_setup.is_some())
+		.collect();
+	if probed.is_empty() {
+		return;
+	}
+	println!("\nTLS Setup (diagnostic, not scored)");
+	println!("----------------------------------");
+	println!("  {:<28} {:>8} {:>10} {:>10} {:>10} {:>10} {:>6}",
+		"Resolver", "Version", "TCP conn", "Handshake", "Resumed", "Resumption", "0-RTT");
+	for r in probed {
+		let ts = match &r.tls_setup { Some(ts) => ts, None => continue };
+		let resumed_str = ts.resumed_handshake_ms
+			.map(|ms| format!("{:.1} ms", ms))
+			.unwrap_or_else(|| "failed".to_string());
+		println!("  {:<28} {:>8} {:>10} {:>10} {:>10} {:>10} {:>6}",
+			r.resolver.label, ts.version, format!("{:.1} ms", ts.connect_ms), format!("{:.1} ms", ts.handshake_ms),
+			resumed_str, if ts.resumed { "yes" } else { "no" }, if ts.early_data { "yes" } else { "no" });
+	}
+	println!("  Handshake follows the TCP connect; Resumed is a second connection offering the first's");
+	println!("  session. A client that reconnects often pays this cost on top of each query.");
+}
+
+/// Print the certificate pin check for DoT and DoH resolvers with `spki=`
+/// or `tls_name=` tags.
+///
+/// Only prints when a checked resolver exists. A mismatch means the
+/// certificate came from somewhere other than the expected server.
+pub fn print_cert_pins(results: &[ResolverRecord]) {
+	let checked: Vec<&ResolverRecord> = results.iter()
+		.filter(|r| r.resolver.transport != DnsTransport::Udp
+			&& (!r.resolver.spki_pins.is_empty() || r.resolver.tls_name.is_some()))
+		.collect();
+	if checked.is_empty() {
+		return;
+	}
+	let verdict = |m: Option<bool>| match m {
+		Some(true) => "match",
+		Some(false) => "MISMATCH",
+		None => "-",
+	};
+	println!("\nCertificate Pins (diagnostic, not scored)");
+	println!("-----------------------------------------");
+	println!("  {:<28} {:<46} {:>8} {:>8}", "Resolver", "Presented SPKI (sha256)", "SPKI", "Name");
+	for r in checked {
+		match &r.cert_pin {
+			Some(c) => {
+				println!("  {:<28} {:<46} {:>8} {:>8}", r.resolver.label, c.spki, verdict(c.spki_match), verdict(c.name_match));
+				if let Some(error) = &c.name_error {
+					println!("  {:<28} {}", "", error);
+				}
+			}
+			None => println!("  {:<28} connection failed", r.resolver.label),
+		}
+	}
+	println!("  A mismatch means another server finished the handshake: an on-path device intercepting");
+	println!("  encrypted DNS, or a key rotation the resolver file has not caught up with.");
+}
+
+/// Print the median DoH latency per HTTP version (--doh-http-compare).
+///
+/// Only prints when the comparison ran. A version with some unanswered
+/// queries shows how many of them were answered.
+pub fn print_doh_http(results: &[ResolverRecord]) {
+	let probed: Vec<&ResolverRecord> = results.iter()
+		.filter(|r| !r.doh_http.is_empty())
+		.collect();
+	if probed.is_empty() {
+		return;
+	}
+	println!("\nDoH HTTP Versions (diagnostic, not scored)");
+	println!("------------------------------------------");
+	print!("  {:<28}", "Resolver");
+	for version in crate::cli::DohHttpVersion::ALL {
+		print!(" {:>16}", version.to_string());
+	}
+	println!();
+	for r in probed {
+		print!("  {:<28}", r.resolver.label);
+		for version in crate::cli::DohHttpVersion::ALL {
+			let cell = match r.doh_http.iter().find(|d| d.version == version) {
+				Some(d) => match d.p50_ms {
+					Some(ms) if d.answered == crate::bench::DOH_HTTP_PROBE_QUERIES => format!("{:.1} ms", ms),
+					Some(ms) => format!("{:.1} ms ({}/{})", ms, d.answered, crate::bench::DOH_HTTP_PROBE_QUERIES),
+					None => "failed".to_string(),
+				},
+				None => "-".to_string(),
+			};
+			print!(" {:>16}", cell);
+		}
+		println!();
+	}
+	println!("  Median of {} queries per version over one kept-alive connection, after a warm-up query.",
+		crate::bench::DOH_HTTP_PROBE_QUERIES);
+	println!("  HTTP/3 is not measured: this build has no QUIC support.");
+}
+
+/// Print latency from RIPE Atlas probes next to the local cached-set p50.
+///
+/// Only prints when --ripe-atlas-key was given and some measurement was made.
+pub fn print_atlas(results: &[ResolverRecord]) {
+	let measured: Vec<&ResolverRecord> = results.iter().filter(|r| r.atlas.is_some()).collect();
+	if measured.is_empty() {
+		return;
+	}
+	let ms = |v: Option<f64>| v.map(|ms| format!("{:.1} ms", ms)).unwrap_or_else(|| "-".to_string());
+	println!("\nRIPE Atlas (global probes, not scored)");
+	println!("--------------------------------------");
+	println!("  {:<28} {:>10} {:>10} {:>10} {:>7} {:>9}", "Resolver", "local p50", "Atlas p50", "Atlas p90", "Probes", "Answered");
+	for r in measured {
+		let Some(atlas) = &r.atlas else { continue };
+		let local = r.benchmark.as_ref().and_then(|bm| bm.categories.get("cached")).map(|s| s.p50_ms);
+		println!("  {:<28} {:>10} {:>10} {:>10} {:>7} {:>9}", r.resolver.label, ms(local), ms(atlas.p50_ms), ms(atlas.p90_ms),
+			atlas.probes, format!("{}/{}", atlas.answered, atlas.results));
+	}
+	println!("  Atlas times are the probes' own round trips to the resolver, for the first warm-set domains.");
+	println!("  Measurement IDs are in the CSV atlas_measurements column (atlas.ripe.net/measurements/ID).");
+}
+
+/// Print the server identity each resolver reported, next to its latency.
+///
+/// Only prints when the identity probe ran. Resolvers sharing an anycast
+/// address but answering from different sites show different identifiers.
+pub fn print_identity(results: &[ResolverRecord]) {
+	let probed: Vec<&ResolverRecord> = results.iter().filter(|r| r.identity.is_some()).collect();
+	if probed.is_empty() {
+		return;
+	}
+	println!("\nServer Identity (diagnostic, not scored)");
+	println!("----------------------------------------");
+	println!("  {:<28} {:>10}  {:<24} {:<24} hostname.bind", "Resolver", "p50", "NSID", "id.server");
+	let dash = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+	for r in probed {
+		let id = match &r.identity { Some(id) => id, None => continue };
+		let p50_str = r.benchmark.as_ref()
+			.and_then(|bm| bm.categories.get("cached").or_else(|| bm.categories.values().next()))
+			.map(|cs| format!("{:.1} ms", cs.p50_ms))
+			.unwrap_or_else(|| "-".to_string());
+		println!("  {:<28} {:>10}  {:<24} {:<24} {}",
+			r.resolver.label, p50_str, dash(&id.nsid), dash(&id.id_server), dash(&id.hostname_bind));
+	}
+	println!("  Identifiers name the site or host that answered; for anycast resolvers this is the PoP.");
+
+	// Sites seen round by round; a change mid-run often explains bimodal latency
+	let flapping: Vec<&ResolverRecord> = results.iter().filter(|r| r.pop_changes() > 0).collect();
+	if flapping.is_empty() {
+		return;
+	}
+	println!("\n  PoP changes during the benchmark (answering site by round):");
+	for r in flapping {
+		let runs: Vec<String> = r.identity_runs().iter()
+			.map(|(id, rounds)| format!("{} x{}", id, rounds))
+			.collect();
+		println!("    {:<28} {} change(s): {}", r.resolver.label, r.pop_changes(), runs.join(" -> "));
+	}
+	println!("  Latency from different sites mixes into one distribution; expect a wide or bimodal spread.");
+}
+
+/// Print resolvers whose answers deviate from the other resolvers.
+///
+/// Only prints when the consistency check ran.
+pub fn print_consistency(results: &[ResolverRecord]) {
+	let checked: Vec<&ResolverRecord> = results.iter().filter(|r| r.consistency.is_some()).collect();
+	if checked.is_empty() {
+		return;
+	}
+	println!("\nAnswer Consistency (diagnostic, not scored)");
+	println!("-------------------------------------------");
+	let fmt_ips = |ips: &[std::net::Ipv4Addr]| if ips.is_empty() {
+		"(no address)".to_string()
+	} else {
+		ips.iter().map(|ip| ip.to_string()).collect::<Vec<_>>().join(" ")
+	};
+	let mut flagged = 0;
+	for r in &checked {
+		let c = match &r.consistency { Some(c) => c, None => continue };
+		for d in &c.deviations {
+			if flagged == 0 {
+				println!("  {:<28} {:<22} {:<32} Consensus", "Resolver", "Name", "Answer");
+			}
+			flagged += 1;
+			println!("  {:<28} {:<22} {:<32} {}",
+				r.resolver.label, d.domain, fmt_ips(&d.answer), fmt_ips(&d.consensus));
+		}
+	}
+	if flagged == 0 {
+		println!("  All {} resolvers agree on {}.", checked.len(), crate::consistency::CONSISTENCY_DOMAINS.join(", "));
+	} else {
+		println!("  These names have fixed addresses; a different answer means the resolver");
+		println!("  or the network path rewrites DNS (captive portal, ISP redirect, or hijack).");
+	}
+}
+
+/// Query types split out in the A vs AAAA report and CSV columns
+const QUERY_TYPES: [&str; 3] = ["A", "AAAA", "HTTPS"];
+
+/// Print scored-set latency and success split by query type, to show
+/// resolvers that are fast for A but slow or broken for AAAA.
+///
+/// Only prints when AAAA queries were sent.
+pub fn print_query_types(results: &[ResolverRecord]) {
+	let split: Vec<(&ResolverRecord, &crate::stats::SetStats, &crate::stats::SetStats)> = results.iter()
+		.filter_map(|r| {
+			let bm = r.benchmark.as_ref()?;
+			Some((r, bm.query_types.get("A")?, bm.query_types.get("AAAA")?))
+		})
+		.collect();
+	if split.is_empty() {
+		return;
+	}
+	let success_pct = |s: &crate::stats::SetStats| if s.total_count > 0 {
+		format!("{:.1}%", s.success_count as f64 * 100.0 / s.total_count as f64)
+	} else {
+		"-".to_string()
+	};
+	let p50 = |s: &crate::stats::SetStats| if s.success_count > 0 {
+		format!("{:.1} ms", s.p50_ms)
+	} else {
+		"-".to_string()
+	};
+	println!("\nA vs AAAA");
+	println!("---------");
+	println!("  {:<28} {:>10} {:>10} {:>10} {:>9} {:>9}",
+		"Resolver", "A p50", "AAAA p50", "Diff", "A ok", "AAAA ok");
+	for (r, a, aaaa) in split {
+		let diff = if a.success_count > 0 && aaaa.success_count > 0 {
+			format!("{:+.1} ms", aaaa.p50_ms - a.p50_ms)
+		} else {
+			"-".to_string()
+		};
+		println!("  {:<28} {:>10} {:>10} {:>10} {:>9} {:>9}",
+			r.resolver.label, p50(a), p50(aaaa), diff, success_pct(a), success_pct(aaaa));
+	}
+	println!("  Scored sets only. A resolver much slower or less successful for AAAA usually has a broken");
+	println!("  or slow IPv6 path to authoritative servers; clients that ask for both often wait on the slower one.");
+}
+
+/// Print resolvers flagged as possibly filtering or censoring: NXDOMAIN
+/// answers for real domains above `UNEXPECTED_NXDOMAIN_FLAG_PCT`.
+///
+/// Only prints when at least one resolver is flagged.
+pub fn print_unexpected_nxdomain(results: &[ResolverRecord]) {
+	let flagged: Vec<(&ResolverRecord, usize, f64)> = results.iter()
+		.filter(|r| r.possibly_censoring())
+		.filter_map(|r| r.unexpected_nxdomain().map(|(count, pct)| (r, count, pct)))
+		.collect();
+	if flagged.is_empty() {
+		return;
+	}
+	println!("\nUnexpected NXDOMAIN (possibly filtering or censoring)");
+	println!("-----------------------------------------------------");
+	println!("  {:<28} {:>9} {:>7}", "Resolver", "NXDOMAIN", "Rate");
+	for (r, count, pct) in flagged {
+		println!("  {:<28} {:>9} {:>6.1}%", r.resolver.label, count, pct);
+	}
+	println!("  These names exist, so an NXDOMAIN answer for more than {:.0}% of them points to a",
+		crate::stats::UNEXPECTED_NXDOMAIN_FLAG_PCT);
+	println!("  blocklist, government censorship, or a broken forwarder. The Errors column shows the counts.");
+}
+
+/// Print timeouts split into late arrivals and never-answered queries,
+/// with the duplicate and after-grace answers seen per resolver.
+///
+/// Only prints when at least one record caught a late answer during the
+/// --late-grace-ms window, a duplicate, or an answer after the grace
+/// period. Late answers still count as timeouts in the score.
+pub fn print_late_arrivals(results: &[ResolverRecord]) {
+	let late: Vec<&ResolverRecord> = results.iter()
+		.filter(|r| r.benchmark.as_ref().is_some_and(|bm| {
+			bm.late_arrivals > 0 || bm.duplicate_responses > 0 || bm.after_grace_responses > 0
+		}))
+		.collect();
+	if late.is_empty() {
+		return;
+	}
+	println!("\nLate and Duplicate Responses");
+	println!("----------------------------");
+	println!("  {:<28} {:>9} {:>6} {:>10} {:>10} {:>6} {:>6}",
+		"Resolver", "Timeouts", "Late", "Never", "Late p50", "After", "Dup");
+	for r in late {
+		let bm = match &r.benchmark { Some(bm) => bm, None => continue };
+		let p50_str = bm.late_p50_ms
+			.map(|ms| format!("{:.1} ms", ms))
+			.unwrap_or_else(|| "-".to_string());
+		println!("  {:<28} {:>9} {:>6} {:>10} {:>10} {:>6} {:>6}",
+			r.resolver.label, bm.scored_timeouts, bm.late_arrivals,
+			bm.scored_timeouts - bm.late_arrivals, p50_str,
+			bm.after_grace_responses, bm.duplicate_responses);
+	}
+	println!("  Late: timeouts answered during the grace period; After: answered later still (--udp-pool only);");
+	println!("  Dup: extra copies of an answer. Late answers point to slow upstream recursion, never-answered");
+	println!("  ones to packet loss, and duplicates to a broken middlebox or path.");
+}
+
+/// Print resolvers whose latency drifted between round 1 and the last round.
+///
+/// Only prints when at least one resolver's first and last rounds differ by
+/// more than their uncertainty bands (see `crate::stats::round_drift`).
+pub fn print_round_drift(results: &[ResolverRecord]) {
+	let drifted: Vec<(&ResolverRecord, crate::stats::RoundDrift)> = results.iter()
+		.filter_map(|r| r.benchmark.as_ref()
+			.and_then(|bm| crate::stats::round_drift(&bm.raw))
+			.filter(|d| d.drifted())
+			.map(|d| (r, d)))
+		.collect();
+	if drifted.is_empty() {
+		return;
+	}
+	println!("\nRound Drift (round 1 vs last round, scored-set p50)");
+	println!("----------------------------------------------------");
+	for (r, drift) in &drifted {
+		let per_round: Vec<String> = drift.round_p50_ms.iter()
+			.map(|p| p.map(|ms| format!("{:.1}", ms)).unwrap_or_else(|| "-".to_string()))
+			.collect();
+		let trend = if drift.shift_ms > 0.0 { "slower" } else { "faster" };
+		println!("  {:<28} {:+.1} ms ({}; band {:.1} ms)  rounds: {}",
+			r.resolver.label, drift.shift_ms, trend, drift.band_ms, per_round.join(" / "));
+	}
+	println!("  Slowing down points to rate limiting; speeding up to caches warming during the run.");
+}
+
+/// Width of the longest histogram bar in characters
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+
+/// Print an ASCII latency histogram per resolver (--histogram).
+///
+/// Buckets are the power-of-two ranges from `crate::stats::HISTOGRAM_BOUNDS_MS`
+/// over all scored latencies; empty leading and trailing buckets are
+/// trimmed. Several separated peaks usually mean anycast or load-balanced
+/// backends answering at different distances.
+pub fn print_histograms(results: &[ResolverRecord]) {
+	let labels = crate::stats::histogram_bucket_labels();
+	println!("\nLatency Histograms (ms, scored sets)");
+	println!("------------------------------------");
 	for r in results {
 		let bm = match &r.benchmark { Some(bm) => bm, None => continue };
-		// Look for a matching -v6 suffix entry
-		let base_label = r.resolver.label.trim_end_matches("-v6");
-		if base_label == r.resolver.label {
-			// This is the IPv4 entry; look for the v6 pair
-			let v6_label = format!("{}-v6", r.resolver.label);
-			if let Some(v6) = results.iter().find(|x| x.resolver.label == v6_label) {
-				let v6_bm = match &v6.benchmark { Some(bm) => bm, None => continue };
-				if !pairs_printed {
-					println!("\nIPv4 vs IPv6 Comparison");
-					println!("----------------------");
-					pairs_printed = true;
+		let counts = crate::stats::latency_histogram(&bm.raw.scored_latencies_ms());
+		let first = match counts.iter().position(|&c| c > 0) {
+			Some(i) => i,
+			None => continue,
+		};
+		let last = counts.iter().rposition(|&c| c > 0).unwrap_or(first);
+		let max = counts[first..=last].iter().copied().max().unwrap_or(1);
+		println!("  {}", r.resolver.label);
+		for i in first..=last {
+			let bar_len = (counts[i] * HISTOGRAM_BAR_WIDTH).div_ceil(max);
+			println!("    {:>6} | {:<width$} {}", labels[i], "#".repeat(bar_len), counts[i],
+				width = HISTOGRAM_BAR_WIDTH);
+		}
+	}
+}
+
+/// Print latency distribution charts (--chart) on one log-scale axis.
+///
+/// Box plots take one row per resolver; ECDF curves take ECDF_HEIGHT rows
+/// each. Both use all scored latencies, so a long tail or a second mode
+/// shows up next to the p50 in the results table.
+pub fn print_charts(results: &[ResolverRecord], kind: crate::cli::ChartKind) {
+	use crate::chart::{box_row, ecdf_rows, LogScale, CHART_WIDTH, ECDF_HEIGHT};
+	let samples: Vec<(&ResolverRecord, Vec<f64>)> = results.iter()
+		.filter_map(|r| {
+			let mut latencies = r.benchmark.as_ref()?.raw.scored_latencies_ms();
+			latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+			(!latencies.is_empty()).then_some((r, latencies))
+		})
+		.collect();
+	let all: Vec<Vec<f64>> = samples.iter().map(|(_, l)| l.clone()).collect();
+	let Some(scale) = LogScale::fit(&all, CHART_WIDTH) else { return };
+	let (axis_line, axis_labels) = scale.axis();
+	match kind {
+		crate::cli::ChartKind::Box => {
+			println!("\nLatency Box Plots (ms, log scale, scored sets)");
+			println!("----------------------------------------------");
+			for (r, latencies) in &samples {
+				if let Some(row) = box_row(latencies, &scale) {
+					let p50 = crate::stats::percentile(latencies, 50.0).unwrap_or_default();
+					println!("  {:<28} {} {:.1} ms", r.resolver.label, row, p50);
 				}
-				let diff_pct = if bm.overall_score > 0.0 {
-					((v6_bm.overall_score - bm.overall_score) / bm.overall_score) * 100.0
-				} else {
-					0.0
-				};
-				let direction = if diff_pct > 0.0 { "slower" } else { "faster" };
-				// Use first category p50 for the comparison display
-				if let Some(ref cat) = first_cat_name {
-					let v4_p50 = bm.categories.get(cat).map(|s| s.p50_ms).unwrap_or(0.0);
-					let v6_p50 = v6_bm.categories.get(cat).map(|s| s.p50_ms).unwrap_or(0.0);
-					println!("  {} IPv4: {:.1} ms vs IPv6: {:.1} ms ({:.0}% {})",
-						base_label, v4_p50, v6_p50, diff_pct.abs(), direction);
+			}
+			println!("  {:<28} {}", "", axis_line);
+			println!("  {:<28} {}", "", axis_labels);
+			println!("  Whiskers p5-p95, shaded box p25-p75, full block the median.");
+		}
+		crate::cli::ChartKind::Ecdf => {
+			println!("\nLatency ECDF (share of answers at or below each latency, ms, log scale)");
+			println!("-----------------------------------------------------------------------");
+			for (r, latencies) in &samples {
+				let Some(rows) = ecdf_rows(latencies, &scale, ECDF_HEIGHT) else { continue };
+				println!("  {} ({} answers)", r.resolver.label, latencies.len());
+				for (i, row) in rows.iter().enumerate() {
+					let y = match i { 0 => "100%", _ if i == ECDF_HEIGHT - 1 => "  0%", _ => "" };
+					println!("  {:>6} |{}", y, row);
 				}
 			}
+			println!("  {:>6}  {}", "", axis_line);
+			println!("  {:>6}  {}", "", axis_labels);
+			println!("  A steep rise means consistent latency; a step means a second mode, such as cache misses.");
 		}
 	}
 }
 
+/// Results columns that are always text in JSON, even when they look numeric
+const TEXT_COLUMNS: [&str; 15] = [
+	"rank", "resolver", "ip_address", "transport", "hostname", "ptr_name", "provider", "as_name", "country", "tie_group",
+	"nsid", "id_server", "hostname_bind", "discovery_reason", "cert_spki",
+];
+
 /// Write benchmark results to a CSV file.
-pub fn write_csv(path: &str, results: &[ResolverRecord]) -> Result<()> {
+///
+/// The run manifest goes first as `# key: value` comment lines.
+pub fn write_csv(path: &str, results: &[ResolverRecord], manifest: &crate::manifest::RunManifest) -> Result<()> {
+	let (header, rows) = result_rows(results);
+	let mut file = std::fs::File::create(path)?;
+	file.write_all(manifest.csv_comment_lines().as_bytes())?;
+	let mut writer = csv::Writer::from_writer(file);
+	writer.write_record(&header)?;
+	for row in &rows {
+		writer.write_record(row)?;
+	}
+	writer.flush()?;
+	crate::info!("\nResults written to: {}", path);
+	Ok(())
+}
+
+/// Columns an --append export starts with
+pub const APPEND_COLUMNS: [&str; 3] = ["run_id", "timestamp", "network_label"];
+
+/// Run metadata written on every --append row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunLabel {
+	/// Start time plus a random suffix, e.g. 20260101T120000Z-3fa2
+	pub run_id: String,
+	/// Start time, ISO 8601 UTC
+	pub timestamp: String,
+	pub network_label: String,
+}
+
+impl RunLabel {
+	pub fn new(started_secs: u64, network_label: &str) -> Self {
+		let timestamp = crate::telemetry::format_timestamp_iso(started_secs);
+		let compact: String = timestamp.chars().filter(|c| *c != '-' && *c != ':').collect();
+		RunLabel {
+			run_id: format!("{}-{:04x}", compact, rand::random::<u16>()),
+			timestamp,
+			network_label: network_label.to_string(),
+		}
+	}
+}
+
+/// Result rows with the --append metadata columns in front.
+fn labeled_rows(results: &[ResolverRecord], run: &RunLabel) -> (Vec<String>, Vec<Vec<String>>) {
+	let (header, rows) = result_rows(results);
+	let meta = [run.run_id.clone(), run.timestamp.clone(), run.network_label.clone()];
+	let header = APPEND_COLUMNS.iter().map(|c| c.to_string()).chain(header).collect();
+	let rows = rows.into_iter().map(|row| meta.iter().cloned().chain(row).collect()).collect();
+	(header, rows)
+}
+
+/// Add this run's rows to an --append CSV, creating it if needed.
+///
+/// There are no manifest comment lines, so the file opens cleanly in a
+/// spreadsheet. When this run's columns differ from the file's (another
+/// domain set, a probe flag added), the file is rewritten with the union
+/// of both headers and earlier rows get empty cells in the new columns.
+pub fn append_csv(path: &str, results: &[ResolverRecord], run: &RunLabel) -> Result<()> {
+	let (header, rows) = labeled_rows(results, run);
+	let created = append_rows(path, &header, &rows)?;
+	match created {
+		true => crate::info!("\nResults written to: {} (new file, run {})", path, run.run_id),
+		false => crate::info!("\nResults appended to: {} (run {})", path, run.run_id),
+	}
+	Ok(())
+}
+
+/// Append rows under `header` to an --append CSV; true when the file was created.
+fn append_rows(path: &str, header: &[String], rows: &[Vec<String>]) -> Result<bool> {
+	let existing = match std::fs::read_to_string(path) {
+		Ok(text) if !text.trim().is_empty() => Some(text),
+		Ok(_) => None,
+		Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+		Err(e) => return Err(anyhow::anyhow!("reading {}: {}", path, e)),
+	};
+	let Some(text) = existing else {
+		let mut writer = csv::Writer::from_path(path)?;
+		writer.write_record(header)?;
+		for row in rows {
+			writer.write_record(row)?;
+		}
+		writer.flush()?;
+		return Ok(true);
+	};
+
+	let mut reader = csv::ReaderBuilder::new().comment(Some(b'#')).from_reader(text.as_bytes());
+	let old_header: Vec<String> = reader.headers()?.iter().map(String::from).collect();
+	if !old_header.starts_with(&APPEND_COLUMNS.map(String::from)) {
+		anyhow::bail!("{} was not written with --append (it has no run_id column); choose another --output file", path);
+	}
+	let mut merged = old_header.clone();
+	for col in header {
+		if !merged.contains(col) {
+			merged.push(col.clone());
+		}
+	}
+	// Each row in the file's column order, empty where it has no value
+	let place = |cols: &[String], row: &[String]| -> Vec<String> {
+		merged.iter().map(|m| cols.iter().position(|c| c == m).map(|i| row[i].clone()).unwrap_or_default()).collect()
+	};
+	if merged == old_header {
+		let file = std::fs::OpenOptions::new().append(true).open(path)?;
+		let mut writer = csv::WriterBuilder::new().has_headers(false).from_writer(file);
+		for row in rows {
+			writer.write_record(place(header, row))?;
+		}
+		writer.flush()?;
+	} else {
+		let tmp = format!("{}.tmp", path);
+		let mut writer = csv::Writer::from_path(&tmp)?;
+		writer.write_record(&merged)?;
+		for record in reader.records() {
+			let record: Vec<String> = record?.iter().map(String::from).collect();
+			writer.write_record(place(&old_header, &record))?;
+		}
+		for row in rows {
+			writer.write_record(place(header, row))?;
+		}
+		writer.flush()?;
+		drop(writer);
+		std::fs::rename(&tmp, path)?;
+	}
+	Ok(false)
+}
+
+/// Write benchmark results to stdout for piping (--stdout).
+///
+/// CSV has the same manifest comment lines and columns as -o. JSON is an
+/// object with the run `manifest` and a `results` array holding one object
+/// per resolver keyed by those column names: empty cells become null,
+/// numeric cells numbers, and true/false booleans.
+pub fn write_results_stdout(results: &[ResolverRecord], format: crate::cli::OutputFormat, manifest: &crate::manifest::RunManifest) -> Result<()> {
+	let (header, rows) = result_rows(results);
+	write_rows_stdout(&header, &rows, format, manifest)
+}
+
+/// Rows to stdout as CSV with manifest comment lines, or as a JSON object
+/// with "manifest" and "results".
+fn write_rows_stdout(header: &[String], rows: &[Vec<String>], format: crate::cli::OutputFormat, manifest: &crate::manifest::RunManifest) -> Result<()> {
+	let stdout = std::io::stdout();
+	match format {
+		crate::cli::OutputFormat::Csv => {
+			let mut out = stdout.lock();
+			out.write_all(manifest.csv_comment_lines().as_bytes())?;
+			let mut writer = csv::Writer::from_writer(out);
+			writer.write_record(header)?;
+			for row in rows {
+				writer.write_record(row)?;
+			}
+			writer.flush()?;
+		}
+		crate::cli::OutputFormat::Json => {
+			let mut out = stdout.lock();
+			writeln!(out, "{{\"manifest\":{},\"results\":{}}}", manifest.to_json(), results_json(header, rows))?;
+		}
+	}
+	Ok(())
+}
+
+/// Results rows as a JSON array of objects keyed by column name.
+fn results_json(header: &[String], rows: &[Vec<String>]) -> String {
+	let objects: Vec<String> = rows.iter()
+		.map(|row| {
+			let fields: Vec<String> = header.iter().zip(row)
+				.map(|(column, value)| format!("\"{}\":{}", crate::telemetry::json_escape(column), json_value(column, value)))
+				.collect();
+			format!("{{{}}}", fields.join(","))
+		})
+		.collect();
+	format!("[{}]", objects.join(","))
+}
+
+/// One CSV cell as a JSON value.
+fn json_value(column: &str, value: &str) -> String {
+	if value.is_empty() {
+		return "null".to_string();
+	}
+	if TEXT_COLUMNS.contains(&column) {
+		return format!("\"{}\"", crate::telemetry::json_escape(value));
+	}
+	match value {
+		"true" | "false" => value.to_string(),
+		_ if value.parse::<f64>().is_ok_and(f64::is_finite) => value.to_string(),
+		_ => format!("\"{}\"", crate::telemetry::json_escape(value)),
+	}
+}
+
+/// Header and rows of the results CSV, one row per benchmarked resolver.
+fn result_rows(results: &[ResolverRecord]) -> (Vec<String>, Vec<Vec<String>>) {
 	let category_names = result_category_names(results);
-	let mut writer = csv::Writer::from_path(path)?;
+	let mut rows = Vec::new();
 
 	// Build header dynamically
 	let mut header: Vec<String> = vec![
-		"rank".to_string(), "resolver".to_string(), "ip_address".to_string(),
-		"transport".to_string(), "overall_score".to_string(),
+		"rank".to_string(), "resolver".to_string(), "ip_address".to_string(), "port".to_string(),
+		"transport".to_string(), "hostname".to_string(), "overall_score".to_string(), "stability_cv".to_string(),
+		"round_drift_ms".to_string(),
 	];
-	// Add 8 columns per category (p50, p95, mean, stddev, success, timeout, total, score)
+	// Add 20 columns per category (p50, p95, p99, min, max, iqr, mean, stddev, cv,
+	// success, timeout, total, servfail, refused, nxdomain, other errors, network
+	// errors, parse errors, txid mismatches, score)
 	for cat in &category_names {
 		header.push(format!("{}_p50_ms", cat));
 		header.push(format!("{}_p95_ms", cat));
+		header.push(format!("{}_p99_ms", cat));
+		header.push(format!("{}_min_ms", cat));
+		header.push(format!("{}_max_ms", cat));
+		header.push(format!("{}_iqr_ms", cat));
 		header.push(format!("{}_mean_ms", cat));
 		header.push(format!("{}_stddev_ms", cat));
+		header.push(format!("{}_cv", cat));
 		header.push(format!("{}_success", cat));
 		header.push(format!("{}_timeout", cat));
 		header.push(format!("{}_total", cat));
+		header.push(format!("{}_servfail", cat));
+		header.push(format!("{}_refused", cat));
+		header.push(format!("{}_nxdomain", cat));
+		header.push(format!("{}_other_errors", cat));
+		header.push(format!("{}_network_errors", cat));
+		header.push(format!("{}_parse_errors", cat));
+		header.push(format!("{}_txid_mismatches", cat));
 		header.push(format!("{}_score", cat));
 	}
+	for qt in QUERY_TYPES {
+		let qt = qt.to_lowercase();
+		header.push(format!("{}_p50_ms", qt));
+		header.push(format!("{}_p95_ms", qt));
+		header.push(format!("{}_success_rate", qt));
+		header.push(format!("{}_timeout", qt));
+		header.push(format!("{}_total", qt));
+	}
 	header.extend_from_slice(&[
-		"success_rate".to_string(), "intercepts_nxdomain".to_string(),
+		"success_rate".to_string(), "first_attempt_rate".to_string(),
+		"retry_success_rate".to_string(), "loss_pct".to_string(),
+		"traceroute_hops".to_string(), "traceroute_reached".to_string(), "last_hop_rtt_ms".to_string(),
+		"tls_version".to_string(), "tls_connect_ms".to_string(), "tls_handshake_ms".to_string(),
+		"tls_resumed_handshake_ms".to_string(), "tls_resumption".to_string(), "tls_early_data".to_string(),
+		"cert_spki".to_string(), "cert_spki_match".to_string(), "cert_name_match".to_string(),
+		"doh_http1_p50_ms".to_string(), "doh_http2_p50_ms".to_string(),
+		"atlas_p50_ms".to_string(), "atlas_p90_ms".to_string(), "atlas_probes".to_string(), "atlas_measurements".to_string(),
+		"late_arrivals".to_string(), "late_p50_ms".to_string(),
+		"duplicate_responses".to_string(), "after_grace_responses".to_string(), "unresponsive".to_string(), "unreachable".to_string(),
+		"unexpected_nxdomain".to_string(), "possibly_censoring".to_string(),
+		"cache_hit_rate".to_string(), "warm_cached".to_string(), "warm_excluded".to_string(),
+		"nxdomain_repeat_speedup_ms".to_string(),
+		"intercepts_nxdomain".to_string(),
 		"validates_dnssec".to_string(), "rebinding_protection".to_string(),
-		"ptr_name".to_string(), "tie_group".to_string(),
+		"answers_known_record".to_string(),
+	]);
+	header.extend(CAPABILITY_PROBE_COLUMNS.iter().map(|c| c.to_string()));
+	header.extend_from_slice(&[
+		"ptr_name".to_string(), "provider".to_string(),
+		"asn".to_string(), "as_name".to_string(), "country".to_string(),
+		"tie_group".to_string(),
+		"nsid".to_string(), "id_server".to_string(), "hostname_bind".to_string(),
+		"pop_changes".to_string(), "answer_deviations".to_string(),
+		"open_resolver_signals".to_string(),
 		// Discovery stage columns
 		"discovery_latency_ms".to_string(), "discovery_reason".to_string(),
 		// Characterization stage columns
@@ -481,7 +1546,9 @@ pub fn write_csv(path: &str, results: &[ResolverRecord]) -> Result<()> {
 		"qual_score".to_string(), "qual_p50_ms".to_string(),
 		"qual_p95_ms".to_string(), "qual_timeout_rate".to_string(),
 	]);
-	writer.write_record(&header)?;
+	// Latency histogram bucket counts over scored sets
+	header.extend(crate::stats::HISTOGRAM_BOUNDS_MS.iter().map(|b| format!("hist_le_{}ms", b)));
+	header.push(format!("hist_gt_{}ms", crate::stats::HISTOGRAM_BOUNDS_MS[crate::stats::HISTOGRAM_BOUNDS_MS.len() - 1]));
 
 	for r in results {
 		let bm = match &r.benchmark { Some(bm) => bm, None => continue };
@@ -494,8 +1561,12 @@ pub fn write_csv(path: &str, results: &[ResolverRecord]) -> Result<()> {
 			rank_str,
 			r.resolver.label.clone(),
 			r.resolver.addr.ip().to_string(),
+			r.resolver.addr.port().to_string(),
 			r.resolver.transport.to_string(),
+			r.resolver.hostname.clone().unwrap_or_default(),
 			format!("{:.2}", bm.overall_score),
+			bm.stability_cv.map(|cv| format!("{:.3}", cv)).unwrap_or_default(),
+			crate::stats::round_drift(&bm.raw).map(|d| format!("{:.2}", d.shift_ms)).unwrap_or_default(),
 		];
 
 		// Category columns
@@ -504,23 +1575,47 @@ pub fn write_csv(path: &str, results: &[ResolverRecord]) -> Result<()> {
 				row.extend_from_slice(&[
 					format!("{:.2}", cs.p50_ms),
 					format!("{:.2}", cs.p95_ms),
+					format!("{:.2}", cs.p99_ms),
+					format!("{:.2}", cs.min_ms),
+					format!("{:.2}", cs.max_ms),
+					format!("{:.2}", cs.iqr_ms),
 					format!("{:.2}", cs.mean_ms),
 					format!("{:.2}", cs.stddev_ms),
+					format!("{:.3}", cs.cv),
 					cs.success_count.to_string(),
 					cs.timeout_count.to_string(),
 					cs.total_count.to_string(),
+					cs.rcodes.servfail.to_string(),
+					cs.rcodes.refused.to_string(),
+					cs.rcodes.nxdomain.to_string(),
+					cs.rcodes.other.to_string(),
+					cs.failures.network_error.to_string(),
+					cs.failures.parse_error.to_string(),
+					cs.failures.txid_mismatch.to_string(),
 					format!("{:.2}", cs.score),
 				]);
 			} else {
 				// Empty columns for missing category
-				row.extend_from_slice(&[
-					String::new(), String::new(), String::new(), String::new(),
-					String::new(), String::new(), String::new(), String::new(),
-				]);
+				row.extend(std::iter::repeat_n(String::new(), 20));
+			}
+		}
+		for qt in QUERY_TYPES {
+			match bm.query_types.get(qt).filter(|s| s.total_count > 0) {
+				Some(ts) => row.extend([
+					format!("{:.2}", ts.p50_ms),
+					format!("{:.2}", ts.p95_ms),
+					format!("{:.1}", ts.success_count as f64 * 100.0 / ts.total_count as f64),
+					ts.timeout_count.to_string(),
+					ts.total_count.to_string(),
+				]),
+				None => row.extend(std::iter::repeat_n(String::new(), 5)),
 			}
 		}
 
-		let intercepts_str = if r.intercepts_nxdomain() { "true" } else { "false" };
+		let intercepts_str = match &r.characterization {
+			Some(c) if c.reachable => intercepts_text(c),
+			_ => "false".to_string(),
+		};
 		let dnssec_csv = match r.validates_dnssec() {
 			Some(true) => "true", Some(false) => "false", None => "",
 		};
@@ -530,11 +1625,62 @@ pub fn write_csv(path: &str, results: &[ResolverRecord]) -> Result<()> {
 		let ptr_str = r.resolver.ptr_name.clone().unwrap_or_default();
 		let tie_str = bm.tie_group.clone().unwrap_or_default();
 		row.push(format!("{:.1}", bm.success_rate));
-		row.push(intercepts_str.to_string());
+		row.push(format!("{:.1}", bm.first_attempt_rate));
+		row.push(format!("{:.1}", bm.retry_success_rate));
+		row.push(r.loss_probe.as_ref().map(|lp| format!("{:.1}", lp.loss_pct)).unwrap_or_default());
+		row.push(r.traceroute.as_ref().map(|t| t.hops.to_string()).unwrap_or_default());
+		row.push(r.traceroute.as_ref().map(|t| t.reached.to_string()).unwrap_or_default());
+		row.push(r.traceroute.as_ref().map(|t| format!("{:.2}", t.last_hop_rtt_ms)).unwrap_or_default());
+		row.push(r.tls_setup.as_ref().map(|t| t.version.clone()).unwrap_or_default());
+		row.push(r.tls_setup.as_ref().map(|t| format!("{:.2}", t.connect_ms)).unwrap_or_default());
+		row.push(r.tls_setup.as_ref().map(|t| format!("{:.2}", t.handshake_ms)).unwrap_or_default());
+		row.push(r.tls_setup.as_ref().and_then(|t| t.resumed_handshake_ms).map(|ms| format!("{:.2}", ms)).unwrap_or_default());
+		row.push(r.tls_setup.as_ref().map(|t| t.resumed.to_string()).unwrap_or_default());
+		row.push(r.tls_setup.as_ref().map(|t| t.early_data.to_string()).unwrap_or_default());
+		let cert = r.cert_pin.as_ref();
+		row.push(cert.map(|c| c.spki.clone()).unwrap_or_default());
+		row.push(cert.and_then(|c| c.spki_match).map(|m| m.to_string()).unwrap_or_default());
+		row.push(cert.and_then(|c| c.name_match).map(|m| m.to_string()).unwrap_or_default());
+		for version in crate::cli::DohHttpVersion::ALL {
+			let p50 = r.doh_http.iter().find(|d| d.version == version).and_then(|d| d.p50_ms);
+			row.push(p50.map(|ms| format!("{:.2}", ms)).unwrap_or_default());
+		}
+		let atlas = r.atlas.as_ref();
+		row.push(atlas.and_then(|a| a.p50_ms).map(|ms| format!("{:.2}", ms)).unwrap_or_default());
+		row.push(atlas.and_then(|a| a.p90_ms).map(|ms| format!("{:.2}", ms)).unwrap_or_default());
+		row.push(atlas.map(|a| a.probes.to_string()).unwrap_or_default());
+		row.push(atlas.map(|a| a.measurement_ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(" ")).unwrap_or_default());
+		row.push(bm.late_arrivals.to_string());
+		row.push(bm.late_p50_ms.map(|ms| format!("{:.2}", ms)).unwrap_or_default());
+		row.push(bm.duplicate_responses.to_string());
+		row.push(bm.after_grace_responses.to_string());
+		row.push(bm.unresponsive.to_string());
+		row.push(bm.unreachable.to_string());
+		row.push(r.unexpected_nxdomain().map(|(count, _)| count.to_string()).unwrap_or_default());
+		row.push(r.possibly_censoring().to_string());
+		row.push(bm.cache_hit_rate.map(|p| format!("{:.1}", p)).unwrap_or_default());
+		row.push(r.warm_probe.as_ref().map(|w| w.cached.len().to_string()).unwrap_or_default());
+		row.push(r.warm_probe.as_ref().map(|w| w.excluded.len().to_string()).unwrap_or_default());
+		row.push(bm.negative_cache_speedup_ms.map(|ms| format!("{:.2}", ms)).unwrap_or_default());
+		row.push(intercepts_str);
 		row.push(dnssec_csv.to_string());
 		row.push(rebind_csv.to_string());
+		row.push(r.answers_known_record().map(|b| b.to_string()).unwrap_or_default());
+		row.extend(capability_probe_cells(r.characterization.as_ref()));
 		row.push(ptr_str);
+		row.push(r.resolver.provider_key().to_string());
+		let asn = r.asn.clone().unwrap_or_default();
+		row.push(r.asn.as_ref().map(|a| a.asn.to_string()).unwrap_or_default());
+		row.push(asn.name);
+		row.push(asn.country);
 		row.push(tie_str);
+		let identity = r.identity.clone().unwrap_or_default();
+		row.push(identity.nsid.unwrap_or_default());
+		row.push(identity.id_server.unwrap_or_default());
+		row.push(identity.hostname_bind.unwrap_or_default());
+		row.push(if r.identity_rounds.is_empty() { String::new() } else { r.pop_changes().to_string() });
+		row.push(r.consistency.as_ref().map(|c| c.deviations.len().to_string()).unwrap_or_default());
+		row.push(r.misuse.as_ref().map(|m| m.signals.join("; ")).unwrap_or_default());
 
 		// Discovery stage fields
 		let disc_latency = r.discovery.as_ref()
@@ -585,15 +1731,334 @@ pub fn write_csv(path: &str, results: &[ResolverRecord]) -> Result<()> {
 		row.push(qual_p50);
 		row.push(qual_p95);
 		row.push(qual_timeout);
+		row.extend(crate::stats::latency_histogram(&bm.raw.scored_latencies_ms())
+			.iter().map(|c| c.to_string()));
 
-		writer.write_record(&row)?;
+		rows.push(row);
 	}
+	(header, rows)
+}
+
+/// Yes/No/- text for an optional probe outcome.
+fn probe_text(value: Option<bool>) -> &'static str {
+	match value {
+		Some(true) => "Yes",
+		Some(false) => "No",
+		None => "-",
+	}
+}
+
+/// EDNS cell text: advertised UDP payload size, "No" without EDNS, "-" if unknown.
+fn edns_text(size: Option<u16>) -> String {
+	match size {
+		Some(0) => "No".to_string(),
+		Some(n) => n.to_string(),
+		None => "-".to_string(),
+	}
+}
+
+/// Effective UDP limit cell: largest buffer size that got the full answer.
+fn udp_limit_text(buffer: Option<&crate::record::EdnsBufferResult>) -> String {
+	match buffer {
+		Some(b) => b.effective_limit().map(|n| n.to_string()).unwrap_or_else(|| "None".to_string()),
+		None => "-".to_string(),
+	}
+}
+
+/// Fallback cell: "Clean" when oversized answers truncate and TCP works.
+fn fallback_text(buffer: Option<&crate::record::EdnsBufferResult>) -> &'static str {
+	match buffer {
+		Some(b) if b.clean_fallback() => "Clean",
+		Some(b) if b.fragment_drops() => "Drops",
+		Some(b) if !b.tcp_ok => "No TCP",
+		Some(_) => "Silent",
+		None => "-",
+	}
+}
 
+/// Large response cell: the worst outcome across the large answers.
+fn large_response_cell(result: Option<&crate::record::LargeResponseResult>) -> Cell {
+	match result.map(|r| r.handling()) {
+		Some("clean") => Cell::new("OK").fg(Color::Green),
+		Some("udp_lost") => Cell::new("UDP lost").fg(Color::Yellow),
+		Some("no_tcp") => Cell::new("No TCP").fg(Color::Red),
+		Some(_) => Cell::new("Fails").fg(Color::Red),
+		None => Cell::new("-").fg(Color::DarkGrey),
+	}
+}
+
+/// RD=0 cell: what a non-recursive query gets back.
+fn rd_zero_cell(behavior: Option<crate::record::RdZeroBehavior>) -> Cell {
+	use crate::record::RdZeroBehavior;
+	match behavior {
+		Some(RdZeroBehavior::Refused) => Cell::new("Refuses").fg(Color::Green),
+		Some(RdZeroBehavior::NoAnswer) => Cell::new("No answer").fg(Color::Green),
+		Some(RdZeroBehavior::FromCache) => Cell::new("Cache").fg(Color::Yellow),
+		Some(RdZeroBehavior::Recurses) => Cell::new("Recurses").fg(Color::Yellow),
+		None => Cell::new("-").fg(Color::DarkGrey),
+	}
+}
+
+/// HTTPS RR probe cell: green when the ECH config survives, red when stripped.
+fn https_rr_cell(behavior: Option<crate::record::HttpsRrBehavior>) -> Cell {
+	use crate::record::HttpsRrBehavior;
+	match behavior {
+		Some(HttpsRrBehavior::Intact) => Cell::new("Intact").fg(Color::Green),
+		Some(HttpsRrBehavior::NoEch) => Cell::new("No ECH").fg(Color::Yellow),
+		Some(HttpsRrBehavior::Stripped) => Cell::new("Stripped").fg(Color::Red),
+		None => Cell::new("-").fg(Color::DarkGrey),
+	}
+}
+
+/// TTL probe cell: the returned TTL next to a rewrite, so the clamp value shows.
+fn ttl_rewrite_cell(result: Option<&crate::record::TtlRewriteResult>) -> Cell {
+	let Some(result) = result else { return Cell::new("-").fg(Color::DarkGrey) };
+	let mut found = Vec::new();
+	if let (true, Some(c)) = (result.raises_min(), result.short) {
+		found.push(format!("Min {}s", c.returned));
+	}
+	if let (true, Some(c)) = (result.caps_max(), result.long) {
+		found.push(format!("Max {}s", c.returned));
+	}
+	if let (true, Some(c)) = (result.extends_max(), result.long) {
+		found.push(format!("Extends {}s", c.returned));
+	}
+	if found.is_empty() {
+		Cell::new("OK").fg(Color::Green)
+	} else {
+		Cell::new(found.join(", ")).fg(Color::Yellow)
+	}
+}
+
+/// Coalescing cell: the verdict with the spread between first and last answer.
+fn coalescing_cell(result: Option<&crate::record::CoalescingResult>) -> Cell {
+	match result.and_then(|r| Some((r.coalesces()?, r.spread_ms()?))) {
+		Some((true, spread)) => Cell::new(format!("Yes ({:.1} ms)", spread)).fg(Color::Green),
+		Some((false, spread)) => Cell::new(format!("No ({:.1} ms)", spread)).fg(Color::Yellow),
+		None => Cell::new("-").fg(Color::DarkGrey),
+	}
+}
+
+/// Print the capability matrix from the `characterize` subcommand.
+///
+/// One row per resolver with the outcome of every probe; unreachable
+/// resolvers are listed with empty probe cells.
+pub fn print_capability_matrix(results: &[ResolverRecord]) {
+	let mut table = Table::new();
+	table.load_preset(UTF8_FULL);
+	table.set_content_arrangement(ContentArrangement::Dynamic);
+	table.set_header(vec![
+		"Resolver", "IP Address", "Proto", "Reachable", "Latency", "NXDOMAIN",
+		"DNSSEC", "Rebind", "Root NS", "Filtering", "EDNS", "Cookie", "0x20", "UDP Limit", "TC/TCP", "Large Resp", "RD=0", "HTTPS RR", "Padding", "DNS64", "TTL", "Coalesce", "Consistent", "Identity",
+	]);
+	for r in results {
+		let c = match &r.characterization { Some(c) => c, None => continue };
+		let mut row = vec![
+			Cell::new(&r.resolver.label),
+			Cell::new(r.resolver.addr.ip().to_string()),
+			Cell::new(r.resolver.transport.to_string()),
+		];
+		if !c.reachable {
+			row.push(Cell::new("No").fg(Color::Red));
+			row.extend(std::iter::repeat_n("-", 20).map(|s| Cell::new(s).fg(Color::DarkGrey)));
+			table.add_row(row);
+			continue;
+		}
+		row.push(Cell::new("Yes").fg(Color::Green));
+		row.push(match c.latency_ms {
+			Some(ms) => Cell::new(format!("{:.1} ms", ms)).fg(latency_color(ms)),
+			None => Cell::new("-").fg(Color::DarkGrey),
+		});
+		row.push(match c.intercepts_nxdomain {
+			Some(true) => Cell::new("Intercepts").fg(Color::Red),
+			Some(false) => Cell::new("OK").fg(Color::Green),
+			None => Cell::new("Inconclusive").fg(Color::Yellow),
+		});
+		row.push(Cell::new(probe_text(c.validates_dnssec)));
+		row.push(Cell::new(probe_text(c.rebinding_protection)));
+		row.push(match c.answers_known_record {
+			Some(true) => Cell::new("OK").fg(Color::Green),
+			Some(false) => Cell::new("Wrong").fg(Color::Red),
+			None => Cell::new("-").fg(Color::DarkGrey),
+		});
+		row.push(Cell::new(probe_text(c.filtering)));
+		row.push(Cell::new(edns_text(c.edns_udp_size)));
+		row.push(Cell::new(probe_text(c.server_cookie)));
+		row.push(Cell::new(probe_text(c.preserves_case)));
+		row.push(Cell::new(udp_limit_text(c.edns_buffer.as_ref())));
+		row.push(match fallback_text(c.edns_buffer.as_ref()) {
+			"Clean" => Cell::new("Clean").fg(Color::Green),
+			"-" => Cell::new("-").fg(Color::DarkGrey),
+			other => Cell::new(other).fg(Color::Yellow),
+		});
+		row.push(large_response_cell(c.large_responses.as_ref()));
+		row.push(rd_zero_cell(c.rd_zero));
+		row.push(https_rr_cell(c.https_rr));
+		row.push(match c.pads_responses {
+			Some(true) => Cell::new("Yes").fg(Color::Green),
+			Some(false) => Cell::new("No").fg(Color::Yellow),
+			None => Cell::new("-").fg(Color::DarkGrey),
+		});
+		row.push(match c.dns64 {
+			Some(crate::record::Dns64Behavior::Synthesizes(prefix, len)) => Cell::new(format!("{}/{}", prefix, len)).fg(Color::Cyan),
+			Some(crate::record::Dns64Behavior::Off) => Cell::new("Off"),
+			None => Cell::new("-").fg(Color::DarkGrey),
+		});
+		row.push(ttl_rewrite_cell(c.ttl_rewrite.as_ref()));
+		row.push(coalescing_cell(c.coalescing.as_ref()));
+		row.push(match &r.consistency {
+			Some(cr) if cr.deviations.is_empty() => Cell::new("Yes").fg(Color::Green),
+			Some(_) => Cell::new("No").fg(Color::Red),
+			None => Cell::new("-").fg(Color::DarkGrey),
+		});
+		row.push(Cell::new(r.identity.as_ref().and_then(|id| id.best()).unwrap_or("-")));
+		table.add_row(row);
+	}
+	println!("\nCapability Matrix");
+	println!("=================\n");
+	println!("{table}");
+	println!("\nRoot NS: returned the known root NS set (Wrong = captive portal or hijack).");
+	println!("Filtering: blocks {}. EDNS: advertised UDP payload size. Cookie: returns an RFC 7873 server cookie.",
+		crate::dns::FILTER_PROBE_DOMAINS.join(" or "));
+	println!("0x20: echoes a mixed-case query name with its exact case. Identity: NSID, else id.server, else hostname.bind.");
+	println!("UDP Limit: largest of {:?} bytes that returned the signed root DNSKEY set without truncation.",
+		crate::dns::EDNS_BUFFER_SIZES);
+	println!("TC/TCP: Clean = oversized answers truncate and TCP answers; Drops = a larger buffer went unanswered (lost fragments);");
+	println!("        No TCP = the TCP query failed; Silent = no reply even at the smallest size.");
+	println!("Large Resp: {} at a {}-byte buffer, then over TCP. OK = full answer over UDP or after a clean TC/TCP fallback;",
+		crate::dns::LARGE_RESPONSE_PROBES.iter().map(|(_, name, rtype)| format!("{} {}", if *name == "." { "root" } else { name }, rtype)).collect::<Vec<_>>().join(" and "),
+		crate::dns::EDNS_BUFFER_SIZES[1]);
+	println!("      UDP lost = no UDP reply but TCP answers (clients stall first); No TCP = truncated and TCP failed; Fails = no answer.");
+	println!("RD=0: reply to queries without recursion desired. Cache = answers cached names (others' lookups can be snooped);");
+	println!("      Recurses = resolves a fresh name anyway; Refuses and No answer serve nothing.");
+	println!("HTTPS RR: the HTTPS record of {} with its ech parameter (needed for Encrypted Client Hello).",
+		crate::dns::HTTPS_RR_PROBE_DOMAIN);
+	println!("      No ECH = the record came back without ech; Stripped = no HTTPS record at all.");
+	println!("Padding: DoT and DoH only; a padded query got a padded reply (RFC 8467), hiding the answer size.");
+	println!("DNS64: NAT64 prefix of AAAA records synthesized for {}, which has only A records (for IPv6-only networks).",
+		crate::dns::DNS64_PROBE_DOMAIN);
+	println!("TTL: TTLs returned for {} (short) and {} (about 41 days) against the authoritative ones.",
+		crate::dns::TTL_PROBE_SHORT, crate::dns::TTL_PROBE_LONG);
+	println!("      Min = a short TTL came back raised; Max = a long TTL came back at or under {} days; Extends = above the authoritative TTL.",
+		crate::dns::TTL_CAP_LIMIT / 86_400);
+	println!("Coalesce: {} identical queries for a fresh name sent at once were answered together (one upstream lookup);",
+		crate::dns::COALESCE_QUERIES);
+	println!("      the time from first to last answer is shown. No = answers spread out, each copy resolved on its own.");
+	println!("Consistent: answers for fixed-address names match the other resolvers (details below).");
+}
+
+/// Columns of the capability probes that only `characterize` runs, in the
+/// capability matrix and (empty unless probed) the results export
+const CAPABILITY_PROBE_COLUMNS: [&str; 24] = [
+	"filtering", "edns_udp_size", "server_cookie", "preserves_case",
+	"udp_payload_limit", "largest_udp_response", "fragment_drops", "tcp_fallback_ok", "clean_fallback",
+	"large_response", "large_dnskey", "large_txt", "rd0_behavior", "https_rr", "pads_responses", "dns64",
+	"ttl_short_auth", "ttl_short_returned", "ttl_long_auth", "ttl_long_returned", "ttl_rewrite",
+	"coalesces", "coalesce_answered", "coalesce_spread_ms",
+];
+
+/// NXDOMAIN interception as exported: true, false, or inconclusive.
+fn intercepts_text(c: &crate::record::CharacterizationResult) -> String {
+	c.intercepts_nxdomain.map(|b| b.to_string()).unwrap_or_else(|| "inconclusive".to_string())
+}
+
+/// Cells for CAPABILITY_PROBE_COLUMNS; empty for probes that did not run.
+fn capability_probe_cells(c: Option<&crate::record::CharacterizationResult>) -> Vec<String> {
+	let Some(c) = c else { return vec![String::new(); CAPABILITY_PROBE_COLUMNS.len()] };
+	let opt_bool = |v: Option<bool>| v.map(|b| b.to_string()).unwrap_or_default();
+	let ttl_field = |v: Option<u32>| v.map(|n| n.to_string()).unwrap_or_default();
+	let buffer = c.edns_buffer.as_ref();
+	let large = c.large_responses.as_ref();
+	let ttl = c.ttl_rewrite.as_ref();
+	let coalescing = c.coalescing.as_ref();
+	vec![
+		opt_bool(c.filtering),
+		c.edns_udp_size.map(|n| n.to_string()).unwrap_or_default(),
+		opt_bool(c.server_cookie),
+		opt_bool(c.preserves_case),
+		buffer.and_then(|b| b.effective_limit()).map(|n| n.to_string()).unwrap_or_default(),
+		buffer.and_then(|b| b.largest_response()).map(|n| n.to_string()).unwrap_or_default(),
+		opt_bool(buffer.map(|b| b.fragment_drops())),
+		opt_bool(buffer.map(|b| b.tcp_ok)),
+		opt_bool(buffer.map(|b| b.clean_fallback())),
+		large.map(|l| l.handling().to_string()).unwrap_or_default(),
+		large.and_then(|l| l.outcome("dnskey")).map(|o| o.csv_text()).unwrap_or_default(),
+		large.and_then(|l| l.outcome("txt")).map(|o| o.csv_text()).unwrap_or_default(),
+		c.rd_zero.map(|b| b.as_str().to_string()).unwrap_or_default(),
+		c.https_rr.map(|b| b.as_str().to_string()).unwrap_or_default(),
+		opt_bool(c.pads_responses),
+		c.dns64.map(|b| b.csv_text()).unwrap_or_default(),
+		ttl_field(ttl.and_then(|t| t.short).map(|c| c.authoritative)),
+		ttl_field(ttl.and_then(|t| t.short).map(|c| c.returned)),
+		ttl_field(ttl.and_then(|t| t.long).map(|c| c.authoritative)),
+		ttl_field(ttl.and_then(|t| t.long).map(|c| c.returned)),
+		ttl.map(|t| t.csv_text()).unwrap_or_default(),
+		opt_bool(coalescing.and_then(|r| r.coalesces())),
+		coalescing.map(|r| format!("{}/{}", r.latencies_ms.len(), r.sent)).unwrap_or_default(),
+		coalescing.and_then(|r| r.spread_ms()).map(|ms| format!("{:.2}", ms)).unwrap_or_default(),
+	]
+}
+
+/// Header and rows of the capability matrix, one row per characterized resolver.
+fn capability_rows(results: &[ResolverRecord]) -> (Vec<String>, Vec<Vec<String>>) {
+	let mut header: Vec<String> = [
+		"resolver", "ip_address", "transport", "reachable", "latency_ms", "intercepts_nxdomain",
+		"validates_dnssec", "rebinding_protection", "answers_known_record",
+	].iter().map(|c| c.to_string()).collect();
+	header.extend(CAPABILITY_PROBE_COLUMNS.iter().map(|c| c.to_string()));
+	header.extend(["consistent_answers", "deviating_names", "nsid", "id_server", "hostname_bind"].iter().map(|c| c.to_string()));
+	let opt_bool = |v: Option<bool>| v.map(|b| b.to_string()).unwrap_or_default();
+	let mut rows = Vec::new();
+	for r in results {
+		let c = match &r.characterization { Some(c) => c, None => continue };
+		let probed = |v: String| if c.reachable { v } else { String::new() };
+		let identity = r.identity.clone().unwrap_or_default();
+		let mut row = vec![
+			r.resolver.label.clone(),
+			r.resolver.addr.ip().to_string(),
+			r.resolver.transport.to_string(),
+			c.reachable.to_string(),
+			c.latency_ms.map(|ms| format!("{:.2}", ms)).unwrap_or_default(),
+			probed(intercepts_text(c)),
+			opt_bool(c.validates_dnssec),
+			opt_bool(c.rebinding_protection),
+			opt_bool(c.answers_known_record),
+		];
+		row.extend(capability_probe_cells(Some(c)));
+		row.extend([
+			opt_bool(r.consistency.as_ref().map(|cr| cr.deviations.is_empty())),
+			r.consistency.as_ref()
+				.map(|cr| cr.deviations.iter().map(|d| d.domain.as_str()).collect::<Vec<_>>().join(";"))
+				.unwrap_or_default(),
+			identity.nsid.unwrap_or_default(),
+			identity.id_server.unwrap_or_default(),
+			identity.hostname_bind.unwrap_or_default(),
+		]);
+		rows.push(row);
+	}
+	(header, rows)
+}
+
+/// Write the capability matrix from `characterize` to a CSV file.
+pub fn write_capability_csv(path: &str, results: &[ResolverRecord]) -> Result<()> {
+	let (header, rows) = capability_rows(results);
+	let mut writer = csv::Writer::from_path(path)?;
+	writer.write_record(&header)?;
+	for row in &rows {
+		writer.write_record(row)?;
+	}
 	writer.flush()?;
-	println!("\nResults written to: {}", path);
+	crate::info!("\nCapability matrix written to: {}", path);
 	Ok(())
 }
 
+/// Write the capability matrix from `characterize` to stdout (--stdout) in
+/// the same CSV or JSON layout as benchmark results, manifest included.
+pub fn write_capability_stdout(results: &[ResolverRecord], format: crate::cli::OutputFormat, manifest: &crate::manifest::RunManifest) -> Result<()> {
+	let (header, rows) = capability_rows(results);
+	write_rows_stdout(&header, &rows, format, manifest)
+}
+
 /// Save surviving resolver addresses to a file (one per line, IP  # Label).
 pub fn write_resolver_list(path: &str, results: &[ResolverRecord]) -> Result<()> {
 	let mut file = std::fs::File::create(path)?;
@@ -602,6 +2067,143 @@ pub fn write_resolver_list(path: &str, results: &[ResolverRecord]) -> Result<()>
 		// Write in resolver file format: address  # Label
 		writeln!(file, "{}  # {}", r.resolver.addr.ip(), r.resolver.label)?;
 	}
-	println!("\nResolver list written to: {}", path);
+	crate::info!("\nResolver list written to: {}", path);
 	Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_join_phrases_and_pick_reasons() {
+		let words = |list: &[&str]| list.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+		assert_eq!(join_phrases(&words(&["a"])), "a");
+		assert_eq!(join_phrases(&words(&["a", "b"])), "a and b");
+		assert_eq!(join_phrases(&words(&["a", "b", "c"])), "a, b, and c");
+
+		let resolver = crate::transport::Resolver::new("9.9.9.9:53".parse().unwrap(), crate::transport::DnsTransport::Udp);
+		let mut rec = ResolverRecord::new(resolver);
+		rec.characterization = Some(crate::record::CharacterizationResult {
+			reachable: true,
+			attempts_used: 1,
+			successes: 1,
+			latency_ms: Some(5.0),
+			intercepts_nxdomain: Some(false),
+			rebinding_protection: None,
+			validates_dnssec: Some(true),
+			answers_known_record: None,
+			filtering: None,
+			edns_udp_size: None,
+			server_cookie: None,
+			preserves_case: None,
+			edns_buffer: None,
+			large_responses: None,
+			rd_zero: None,
+			https_rr: None,
+			pads_responses: None,
+			dns64: None,
+			ttl_rewrite: None,
+			coalescing: None,
+		});
+		// No benchmark: only characterization reasons
+		assert_eq!(pick_reasons(&rec, Some(3.0)), vec!["does not intercept NXDOMAIN", "validates DNSSEC"]);
+	}
+
+	#[test]
+	fn test_results_json_types_cells() {
+		let header: Vec<String> = ["rank", "resolver", "overall_score", "validates_dnssec", "nsid", "loss_pct"]
+			.iter().map(|c| c.to_string()).collect();
+		let row: Vec<String> = ["1-2", "9.9.9.9", "12.50", "true", "42", ""]
+			.iter().map(|c| c.to_string()).collect();
+		let json = results_json(&header, &[row]);
+		let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+		let obj = &value[0];
+		assert_eq!(obj["rank"], "1-2");
+		assert_eq!(obj["resolver"], "9.9.9.9");
+		assert_eq!(obj["overall_score"], 12.5);
+		assert_eq!(obj["validates_dnssec"], true);
+		// Text columns stay strings even when numeric
+		assert_eq!(obj["nsid"], "42");
+		assert!(obj["loss_pct"].is_null());
+	}
+
+	#[test]
+	fn test_result_rows_query_types_and_capabilities() {
+		let resolver = crate::transport::Resolver::new("9.9.9.9:5353".parse().unwrap(), crate::transport::DnsTransport::Udp);
+		let mut rec = ResolverRecord::new(resolver);
+		let a = crate::stats::SetStats { p50_ms: 8.0, p95_ms: 20.0, success_count: 95, timeout_count: 5, total_count: 100, ..Default::default() };
+		rec.benchmark = Some(crate::record::BenchmarkResult {
+			query_types: BTreeMap::from([("A".to_string(), a)]),
+			overall_score: 10.0,
+			success_rate: 95.0,
+			first_attempt_rate: 95.0,
+			scored_timeouts: 5,
+			rank: 1,
+			..Default::default()
+		});
+		rec.characterization = Some(crate::record::CharacterizationResult {
+			reachable: true,
+			attempts_used: 1,
+			successes: 1,
+			latency_ms: Some(5.0),
+			intercepts_nxdomain: None,
+			rebinding_protection: None,
+			validates_dnssec: None,
+			answers_known_record: None,
+			filtering: Some(true),
+			edns_udp_size: Some(1232),
+			server_cookie: None,
+			preserves_case: None,
+			edns_buffer: None,
+			large_responses: None,
+			rd_zero: None,
+			https_rr: None,
+			pads_responses: None,
+			dns64: None,
+			ttl_rewrite: None,
+			coalescing: Some(crate::record::CoalescingResult { sent: 8, latencies_ms: vec![20.0; 8] }),
+		});
+		let (header, rows) = result_rows(std::slice::from_ref(&rec));
+		assert_eq!(header.len(), rows[0].len());
+		let cell = |column: &str| rows[0][header.iter().position(|c| c == column).unwrap()].as_str();
+		assert_eq!((cell("ip_address"), cell("port"), cell("transport")), ("9.9.9.9", "5353", "UDP"));
+		assert_eq!((cell("a_p50_ms"), cell("a_success_rate"), cell("a_timeout")), ("8.00", "95.0", "5"));
+		// No AAAA queries: empty columns, not zeros
+		assert_eq!((cell("aaaa_p50_ms"), cell("aaaa_total")), ("", ""));
+		// Capability probes are exported as in the capability matrix
+		assert_eq!((cell("intercepts_nxdomain"), cell("filtering"), cell("edns_udp_size")), ("inconclusive", "true", "1232"));
+		assert_eq!((cell("coalesce_answered"), cell("dns64")), ("8/8", ""));
+		let (cap_header, cap_rows) = capability_rows(&[rec]);
+		assert_eq!(cap_header.len(), cap_rows[0].len());
+		let cap_cell = |column: &str| cap_rows[0][cap_header.iter().position(|c| c == column).unwrap()].as_str();
+		for column in CAPABILITY_PROBE_COLUMNS {
+			assert_eq!(cap_cell(column), cell(column), "{}", column);
+		}
+	}
+
+	#[test]
+	fn test_append_rows_unions_headers() {
+		let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+		let path = std::env::temp_dir().join(format!("dns_benchmark_append_{}.csv", std::process::id()));
+		let path = path.to_str().unwrap();
+		let _ = std::fs::remove_file(path);
+		let first = strings(&["run_id", "timestamp", "network_label", "resolver", "cached_p50_ms"]);
+		assert!(append_rows(path, &first, &[strings(&["r1", "t1", "home", "Quad9", "12.0"])]).unwrap());
+		// Same columns in another order: appended as is
+		let reordered = strings(&["run_id", "timestamp", "network_label", "cached_p50_ms", "resolver"]);
+		assert!(!append_rows(path, &reordered, &[strings(&["r2", "t2", "home", "11.0", "Quad9"])]).unwrap());
+		// A new column: the file is rewritten with it
+		let wider = strings(&["run_id", "timestamp", "network_label", "resolver", "tld_p50_ms"]);
+		assert!(!append_rows(path, &wider, &[strings(&["r3", "t3", "office", "Quad9", "30.0"])]).unwrap());
+		let text = std::fs::read_to_string(path).unwrap();
+		std::fs::remove_file(path).unwrap();
+		assert_eq!(text, "run_id,timestamp,network_label,resolver,cached_p50_ms,tld_p50_ms\n\
+			r1,t1,home,Quad9,12.0,\nr2,t2,home,Quad9,11.0,\nr3,t3,office,Quad9,,30.0\n");
+
+		let plain = std::env::temp_dir().join(format!("dns_benchmark_plain_{}.csv", std::process::id()));
+		std::fs::write(&plain, "rank,resolver\n1,Quad9\n").unwrap();
+		assert!(append_rows(plain.to_str().unwrap(), &first, &[]).is_err());
+		std::fs::remove_file(&plain).unwrap();
+	}
+}
@@ -0,0 +1,271 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// Payload carried by every echo request
+const ECHO_PAYLOAD: &[u8] = b"dnsbench";
+
+//============================================
+/// Open an ICMP socket for `ip`'s family: an unprivileged ping socket when
+/// net.ipv4.ping_group_range allows it and `raw_only` is false, else a raw
+/// socket (root or CAP_NET_RAW). Returns the socket and whether it is raw.
+#[cfg(target_os = "linux")]
+fn open_icmp_socket(v6: bool, raw_only: bool) -> Option<(std::net::UdpSocket, bool)> {
+	use std::os::fd::FromRawFd;
+	let (domain, proto) = if v6 {
+		(libc::AF_INET6, libc::IPPROTO_ICMPV6)
+	} else {
+		(libc::AF_INET, libc::IPPROTO_ICMP)
+	};
+	for (kind, raw) in [(libc::SOCK_DGRAM, false), (libc::SOCK_RAW, true)] {
+		if raw_only && !raw {
+			continue;
+		}
+		// SAFETY: plain socket(2) call; the new fd is owned by the returned socket
+		let fd = unsafe { libc::socket(domain, kind | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC, proto) };
+		if fd >= 0 {
+			return Some((unsafe { std::net::UdpSocket::from_raw_fd(fd) }, raw));
+		}
+	}
+	None
+}
+
+/// ICMP sockets are only opened on Linux.
+#[cfg(not(target_os = "linux"))]
+fn open_icmp_socket(_v6: bool, _raw_only: bool) -> Option<(std::net::UdpSocket, bool)> {
+	None
+}
+
+/// Set the outgoing TTL (IPv4) or hop limit (IPv6) of an ICMP socket.
+#[cfg(target_os = "linux")]
+fn set_hop_limit(socket: &tokio::net::UdpSocket, v6: bool, hops: u8) -> bool {
+	use std::os::fd::AsRawFd;
+	let value = hops as libc::c_int;
+	let (level, name) = if v6 {
+		(libc::IPPROTO_IPV6, libc::IPV6_UNICAST_HOPS)
+	} else {
+		(libc::IPPROTO_IP, libc::IP_TTL)
+	};
+	// SAFETY: value outlives the call and its size is passed alongside
+	let rc = unsafe {
+		libc::setsockopt(socket.as_raw_fd(), level, name,
+			&value as *const libc::c_int as *const libc::c_void, std::mem::size_of::<libc::c_int>() as libc::socklen_t)
+	};
+	rc == 0
+}
+
+/// Hop limits are only set on Linux, where the ICMP sockets exist.
+#[cfg(not(target_os = "linux"))]
+fn set_hop_limit(_socket: &tokio::net::UdpSocket, _v6: bool, _hops: u8) -> bool {
+	false
+}
+
+/// Whether this process can send ICMP echo requests over IPv4.
+pub fn icmp_available() -> bool {
+	open_icmp_socket(false, false).is_some()
+}
+
+/// Whether this process can open a raw ICMP socket, which traceroute needs.
+pub fn raw_icmp_available() -> bool {
+	open_icmp_socket(false, true).is_some()
+}
+
+/// RFC 1071 Internet checksum.
+fn checksum(data: &[u8]) -> u16 {
+	let mut sum: u32 = data.chunks(2)
+		.map(|c| u16::from_be_bytes([c[0], c.get(1).copied().unwrap_or(0)]) as u32)
+		.sum();
+	while sum >> 16 != 0 {
+		sum = (sum & 0xffff) + (sum >> 16);
+	}
+	!(sum as u16)
+}
+
+/// Build an echo request. The kernel fills in the ICMPv6 checksum, which
+/// covers a pseudo-header the socket does not see.
+fn echo_request(v6: bool, ident: u16, seq: u16) -> Vec<u8> {
+	let mut packet = vec![if v6 { 128 } else { 8 }, 0, 0, 0];
+	packet.extend_from_slice(&ident.to_be_bytes());
+	packet.extend_from_slice(&seq.to_be_bytes());
+	packet.extend_from_slice(ECHO_PAYLOAD);
+	if !v6 {
+		let sum = checksum(&packet);
+		packet[2..4].copy_from_slice(&sum.to_be_bytes());
+	}
+	packet
+}
+
+/// Whether `packet` is the echo reply to sequence `seq`. Raw IPv4 sockets
+/// deliver the IP header first and see every reply on the host, so the
+/// identifier is checked; ping sockets rewrite it and filter by themselves.
+fn is_echo_reply(packet: &[u8], v6: bool, raw: bool, ident: u16, seq: u16) -> bool {
+	let icmp = if raw && !v6 {
+		let header_len = packet.first().map_or(0, |b| (b & 0x0f) as usize * 4);
+		packet.get(header_len..).unwrap_or_default()
+	} else {
+		packet
+	};
+	if icmp.len() < 8 || icmp[0] != if v6 { 129 } else { 0 } {
+		return false;
+	}
+	let reply_ident = u16::from_be_bytes([icmp[4], icmp[5]]);
+	let reply_seq = u16::from_be_bytes([icmp[6], icmp[7]]);
+	reply_seq == seq && (!raw || reply_ident == ident)
+}
+
+/// What an ICMP packet on a raw socket says about echo request (`ident`, seq):
+/// the sequence number and whether it came from the target (echo reply)
+/// or a router on the way (time exceeded, quoting our request).
+fn trace_response(packet: &[u8], v6: bool, ident: u16) -> Option<(u16, bool)> {
+	let icmp = if v6 {
+		packet
+	} else {
+		let header_len = packet.first().map_or(0, |b| (b & 0x0f) as usize * 4);
+		packet.get(header_len..)?
+	};
+	let (reply_type, exceeded_type) = if v6 { (129, 3) } else { (0, 11) };
+	let echo = match *icmp.first()? {
+		t if t == reply_type => icmp,
+		t if t == exceeded_type => {
+			// 8-byte ICMP header, then the quoted IP header and our echo header
+			let quoted = icmp.get(8..)?;
+			let quoted_header_len = if v6 { 40 } else { quoted.first().map_or(0, |b| (b & 0x0f) as usize * 4) };
+			quoted.get(quoted_header_len..)?
+		}
+		_ => return None,
+	};
+	if echo.len() < 8 || u16::from_be_bytes([echo[4], echo[5]]) != ident {
+		return None;
+	}
+	Some((u16::from_be_bytes([echo[6], echo[7]]), icmp[0] == reply_type))
+}
+
+//============================================
+/// Path to `ip` found by TTL-limited echo requests, one per hop limit from
+/// 1 to `max_hops`, all sent at once and collected for `timeout`. Needs a
+/// raw ICMP socket (root or CAP_NET_RAW): ping sockets do not deliver the
+/// routers' time-exceeded messages. None without one.
+pub async fn trace_path(ip: IpAddr, max_hops: u8, timeout: Duration) -> Option<crate::record::TracerouteResult> {
+	let v6 = ip.is_ipv6();
+	let (std_socket, _) = open_icmp_socket(v6, true)?;
+	let socket = tokio::net::UdpSocket::from_std(std_socket).ok()?;
+	let target = SocketAddr::new(ip, 0);
+	let ident: u16 = rand::random();
+	let mut sent_at: Vec<Option<Instant>> = vec![None; max_hops as usize + 1];
+	for hops in 1..=max_hops {
+		if set_hop_limit(&socket, v6, hops) && socket.send_to(&echo_request(v6, ident, hops as u16), target).await.is_ok() {
+			sent_at[hops as usize] = Some(Instant::now());
+		}
+	}
+	// Fewest hops the echo reply needed, else the furthest router heard from
+	let mut reached: Option<(u8, f64)> = None;
+	let mut furthest: Option<(u8, f64)> = None;
+	let mut buf = [0u8; 1500];
+	let deadline = tokio::time::Instant::now() + timeout;
+	while let Ok(Ok((len, _))) = tokio::time::timeout_at(deadline, socket.recv_from(&mut buf)).await {
+		let Some((seq, is_reply)) = trace_response(&buf[..len], v6, ident) else { continue };
+		let Some(Some(t0)) = sent_at.get(seq as usize) else { continue };
+		let hop = (seq as u8, t0.elapsed().as_secs_f64() * 1000.0);
+		if is_reply {
+			if reached.is_none_or(|(h, _)| hop.0 < h) {
+				reached = Some(hop);
+			}
+		} else if furthest.is_none_or(|(h, _)| hop.0 > h) {
+			furthest = Some(hop);
+		}
+		// Every router below the target has had its chance once the reply is in
+		if reached.is_some_and(|(h, _)| furthest.is_some_and(|(f, _)| f + 1 == h) || h == 1) {
+			break;
+		}
+	}
+	let (hops, last_hop_rtt_ms) = reached.or(furthest)?;
+	Some(crate::record::TracerouteResult { hops, reached: reached.is_some(), last_hop_rtt_ms })
+}
+
+//============================================
+/// Median ICMP echo round trip to `ip` in ms over `attempts` pings, each
+/// waiting up to `timeout`. None if ICMP sockets are unavailable or no
+/// reply came back (many resolvers and firewalls drop ICMP).
+pub async fn ping_rtt(ip: IpAddr, attempts: usize, timeout: Duration) -> Option<f64> {
+	let v6 = ip.is_ipv6();
+	let (std_socket, raw) = open_icmp_socket(v6, false)?;
+	let socket = tokio::net::UdpSocket::from_std(std_socket).ok()?;
+	let target = SocketAddr::new(ip, 0);
+	let ident: u16 = rand::random();
+	let mut rtts: Vec<f64> = Vec::with_capacity(attempts);
+	let mut buf = [0u8; 1500];
+	for seq in 0..attempts as u16 {
+		let t0 = Instant::now();
+		if socket.send_to(&echo_request(v6, ident, seq), target).await.is_err() {
+			continue;
+		}
+		let reply = tokio::time::timeout(timeout, async {
+			loop {
+				match socket.recv_from(&mut buf).await {
+					Ok((len, from)) if from.ip() == ip && is_echo_reply(&buf[..len], v6, raw, ident, seq) => return true,
+					Ok(_) => continue,
+					Err(_) => return false,
+				}
+			}
+		}).await;
+		if reply == Ok(true) {
+			rtts.push(t0.elapsed().as_secs_f64() * 1000.0);
+		}
+	}
+	rtts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	crate::stats::percentile(&rtts, 50.0)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_echo_request_checksum() {
+		let packet = echo_request(false, 0x1234, 7);
+		assert_eq!(&packet[..2], &[8, 0]);
+		// A packet with a correct checksum sums to zero
+		assert_eq!(checksum(&packet), 0);
+		assert_eq!(&packet[4..8], &[0x12, 0x34, 0, 7]);
+		assert_eq!(echo_request(true, 1, 2)[0], 128);
+	}
+
+	#[test]
+	fn test_is_echo_reply() {
+		let mut reply = echo_request(false, 0x1234, 7);
+		reply[0] = 0;
+		assert!(is_echo_reply(&reply, false, false, 0x9999, 7));
+		assert!(!is_echo_reply(&reply, false, false, 0x1234, 8));
+		// Raw IPv4: 20-byte IP header first, identifier must match
+		let mut with_header = vec![0x45];
+		with_header.extend_from_slice(&[0; 19]);
+		with_header.extend_from_slice(&reply);
+		assert!(is_echo_reply(&with_header, false, true, 0x1234, 7));
+		assert!(!is_echo_reply(&with_header, false, true, 0x9999, 7));
+		// Our own request looped back is not a reply
+		assert!(!is_echo_reply(&echo_request(false, 0x1234, 7), false, false, 0x1234, 7));
+	}
+
+	#[test]
+	fn test_trace_response() {
+		// Echo reply straight from the target
+		let mut reply = vec![0x45];
+		reply.extend_from_slice(&[0; 19]);
+		let mut echo = echo_request(false, 0x1234, 9);
+		echo[0] = 0;
+		reply.extend_from_slice(&echo);
+		assert_eq!(trace_response(&reply, false, 0x1234), Some((9, true)));
+		assert_eq!(trace_response(&reply, false, 0x4321), None);
+		// Time exceeded from a router, quoting the IP header and our request
+		let mut exceeded = vec![0x45];
+		exceeded.extend_from_slice(&[0; 19]);
+		exceeded.extend_from_slice(&[11, 0, 0, 0, 0, 0, 0, 0, 0x45]);
+		exceeded.extend_from_slice(&[0; 19]);
+		exceeded.extend_from_slice(&echo_request(false, 0x1234, 3)[..8]);
+		assert_eq!(trace_response(&exceeded, false, 0x1234), Some((3, false)));
+		// ICMPv6 time exceeded: no outer header, 40-byte quoted IPv6 header
+		let mut exceeded6 = vec![3, 0, 0, 0, 0, 0, 0, 0];
+		exceeded6.extend_from_slice(&[0; 40]);
+		exceeded6.extend_from_slice(&echo_request(true, 0x1234, 5)[..8]);
+		assert_eq!(trace_response(&exceeded6, true, 0x1234), Some((5, false)));
+	}
+}
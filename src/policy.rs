@@ -0,0 +1,243 @@
+use anyhow::Result;
+
+use crate::record::ResolverRecord;
+use crate::telemetry::json_escape;
+use crate::transport::DnsTransport;
+
+/// Policy document format version; bump on incompatible changes
+pub const POLICY_VERSION: u32 = 1;
+/// Resolvers answering fewer scored queries than this (percent) are excluded
+pub const POLICY_MIN_SUCCESS_RATE: f64 = 95.0;
+/// Fallback resolvers listed after primary and secondary
+pub const POLICY_FALLBACK_COUNT: usize = 2;
+
+/// One recommended resolver in the policy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyEntry {
+	pub label: String,
+	/// IP:port the resolver was benchmarked at
+	pub address: String,
+	pub transport: String,
+	/// TLS hostname (DoT) or URL (DoH); None for UDP
+	pub endpoint: Option<String>,
+	pub score_ms: f64,
+	pub success_rate: f64,
+	pub validates_dnssec: Option<bool>,
+}
+
+/// A benchmarked resolver left out of the recommendation, with the rule that excluded it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exclusion {
+	pub label: String,
+	pub reason: &'static str,
+}
+
+/// Final recommendation as primary, secondary, and fallback resolvers.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Policy {
+	pub primary: Option<PolicyEntry>,
+	pub secondary: Option<PolicyEntry>,
+	pub fallback: Vec<PolicyEntry>,
+	pub excluded: Vec<Exclusion>,
+}
+
+//============================================
+/// Exclusion rule a record breaks, if any.
+fn exclusion_reason(record: &ResolverRecord) -> Option<&'static str> {
+	let bm = record.benchmark.as_ref()?;
+	if record.intercepts_nxdomain() {
+		return Some("intercepts_nxdomain");
+	}
+	if record.answers_known_record() == Some(false) {
+		return Some("wrong_known_record");
+	}
+	if bm.success_rate < POLICY_MIN_SUCCESS_RATE {
+		return Some("success_rate_below");
+	}
+	None
+}
+
+/// Build a policy entry from a benchmarked record.
+fn policy_entry(record: &ResolverRecord) -> Option<PolicyEntry> {
+	let bm = record.benchmark.as_ref()?;
+	let endpoint = match &record.resolver.transport {
+		DnsTransport::Udp => None,
+		DnsTransport::Dot { hostname } => Some(hostname.clone()),
+		DnsTransport::Doh { url, .. } => Some(url.clone()),
+	};
+	Some(PolicyEntry {
+		label: record.resolver.label.clone(),
+		address: record.resolver.addr.to_string(),
+		transport: record.resolver.transport.to_string(),
+		endpoint,
+		score_ms: bm.overall_score,
+		success_rate: bm.success_rate,
+		validates_dnssec: record.validates_dnssec(),
+	})
+}
+
+//============================================
+/// Build the recommendation policy from final benchmark records.
+///
+/// Eligible resolvers are ordered by overall score, ignoring display pinning
+/// and --rank-by, so the policy always recommends on measured performance.
+pub fn build_policy(records: &[ResolverRecord]) -> Policy {
+	let mut policy = Policy::default();
+	for r in records.iter().filter(|r| r.benchmark.is_some()) {
+		if let Some(reason) = exclusion_reason(r) {
+			policy.excluded.push(Exclusion { label: r.resolver.label.clone(), reason });
+		}
+	}
+	let mut entries = eligible_by_score(records).into_iter().filter_map(policy_entry);
+	policy.primary = entries.next();
+	policy.secondary = entries.next();
+	policy.fallback = entries.take(POLICY_FALLBACK_COUNT).collect();
+	policy
+}
+
+/// Benchmarked records that pass every exclusion rule, best score first.
+pub fn eligible_by_score(records: &[ResolverRecord]) -> Vec<&ResolverRecord> {
+	let mut eligible: Vec<&ResolverRecord> = records.iter()
+		.filter(|r| r.benchmark.is_some() && exclusion_reason(r).is_none())
+		.collect();
+	eligible.sort_by(|a, b| {
+		let score = |r: &ResolverRecord| r.benchmark.as_ref().map(|bm| bm.overall_score).unwrap_or(f64::INFINITY);
+		score(a).partial_cmp(&score(b)).unwrap_or(std::cmp::Ordering::Equal)
+	});
+	eligible
+}
+
+//============================================
+/// JSON for one policy entry, or null.
+fn entry_json(entry: Option<&PolicyEntry>) -> String {
+	let e = match entry {
+		Some(e) => e,
+		None => return "null".to_string(),
+	};
+	let endpoint = e.endpoint.as_ref()
+		.map(|s| format!("\"{}\"", json_escape(s)))
+		.unwrap_or_else(|| "null".to_string());
+	let dnssec = e.validates_dnssec
+		.map(|v| v.to_string())
+		.unwrap_or_else(|| "null".to_string());
+	format!(
+		r#"{{"label":"{}","address":"{}","transport":"{}","endpoint":{},"score_ms":{:.2},"success_rate":{:.1},"validates_dnssec":{}}}"#,
+		json_escape(&e.label), e.address, e.transport, endpoint, e.score_ms, e.success_rate, dnssec,
+	)
+}
+
+/// Render the policy as a JSON document.
+///
+/// `conditions` lists the exclusion rules that were applied, so consumers
+/// can tell why a resolver is missing without re-deriving the thresholds.
+pub fn policy_json(policy: &Policy, generated_secs: u64) -> String {
+	let fallback: Vec<String> = policy.fallback.iter().map(|e| entry_json(Some(e))).collect();
+	let excluded: Vec<String> = policy.excluded.iter()
+		.map(|x| format!(r#"{{"label":"{}","reason":"{}"}}"#, json_escape(&x.label), x.reason))
+		.collect();
+	format!(
+		concat!(
+			"{{\n",
+			"  \"version\": {},\n",
+			"  \"generated\": \"{}\",\n",
+			"  \"conditions\": [{{\"exclude_if\":\"intercepts_nxdomain\"}},{{\"exclude_if\":\"wrong_known_record\"}},{{\"exclude_if\":\"success_rate_below\",\"percent\":{:.1}}}],\n",
+			"  \"primary\": {},\n",
+			"  \"secondary\": {},\n",
+			"  \"fallback\": [{}],\n",
+			"  \"excluded\": [{}]\n",
+			"}}\n",
+		),
+		POLICY_VERSION,
+		crate::telemetry::format_timestamp_iso(generated_secs),
+		POLICY_MIN_SUCCESS_RATE,
+		entry_json(policy.primary.as_ref()),
+		entry_json(policy.secondary.as_ref()),
+		fallback.join(","),
+		excluded.join(","),
+	)
+}
+
+/// Write the recommendation policy (--policy) as JSON.
+pub fn write_policy(path: &str, records: &[ResolverRecord]) -> Result<()> {
+	let now = std::time::SystemTime::now()
+		.duration_since(std::time::SystemTime::UNIX_EPOCH)
+		.map(|d| d.as_secs())
+		.unwrap_or(0);
+	std::fs::write(path, policy_json(&build_policy(records), now))?;
+	crate::info!("Recommendation policy written to {}", path);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn record(label: &str, score: f64, success_rate: f64, intercepts: bool) -> ResolverRecord {
+		use crate::transport::Resolver;
+		let mut resolver = Resolver::new("192.0.2.1:53".parse().unwrap(), DnsTransport::Udp);
+		resolver.label = label.to_string();
+		let mut rec = ResolverRecord::new(resolver);
+		rec.benchmark = Some(crate::record::BenchmarkResult {
+			overall_score: score,
+			success_rate,
+			first_attempt_rate: success_rate,
+			..Default::default()
+		});
+		rec.characterization = Some(crate::record::CharacterizationResult {
+			reachable: true,
+			attempts_used: 1,
+			successes: 1,
+			latency_ms: Some(score),
+			intercepts_nxdomain: Some(intercepts),
+			rebinding_protection: None,
+			validates_dnssec: None,
+			answers_known_record: None,
+			filtering: None,
+			edns_udp_size: None,
+			server_cookie: None,
+			preserves_case: None,
+			edns_buffer: None,
+			large_responses: None,
+			rd_zero: None,
+			https_rr: None,
+			pads_responses: None,
+			dns64: None,
+			ttl_rewrite: None,
+			coalescing: None,
+		});
+		rec
+	}
+
+	#[test]
+	fn test_build_policy_orders_by_score_and_excludes() {
+		let mut records = vec![
+			record("isp", 5.0, 100.0, true),
+			record("slow", 40.0, 100.0, false),
+			record("fast", 10.0, 100.0, false),
+			record("flaky", 8.0, 80.0, false),
+			record("mid", 20.0, 99.0, false),
+			record("spare", 30.0, 99.0, false),
+			record("portal", 2.0, 100.0, false),
+		];
+		records[6].characterization.as_mut().unwrap().answers_known_record = Some(false);
+		let policy = build_policy(&records);
+		assert_eq!(policy.primary.unwrap().label, "fast");
+		assert_eq!(policy.secondary.unwrap().label, "mid");
+		let fallback: Vec<&str> = policy.fallback.iter().map(|e| e.label.as_str()).collect();
+		assert_eq!(fallback, vec!["spare", "slow"]);
+		assert_eq!(policy.excluded, vec![
+			Exclusion { label: "isp".to_string(), reason: "intercepts_nxdomain" },
+			Exclusion { label: "flaky".to_string(), reason: "success_rate_below" },
+			Exclusion { label: "portal".to_string(), reason: "wrong_known_record" },
+		]);
+	}
+
+	#[test]
+	fn test_policy_json_is_valid() {
+		let policy = build_policy(&[record("fast", 10.0, 100.0, false)]);
+		let value: serde_json::Value = serde_json::from_str(&policy_json(&policy, 0)).unwrap();
+		assert_eq!(value["version"], POLICY_VERSION);
+		assert_eq!(value["primary"]["address"], "192.0.2.1:53");
+		assert!(value["secondary"].is_null());
+	}
+}
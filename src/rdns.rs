@@ -14,7 +14,7 @@ pub async fn resolve_ptr_names(
 	records: &mut [ResolverRecord],
 	timeout: Duration,
 ) {
-	println!("Resolving PTR records ({} resolvers)...", records.len());
+	crate::info!("Resolving PTR records ({} resolvers)...", records.len());
 
 	let total = records.len();
 	let completed = Arc::new(AtomicUsize::new(0));
@@ -48,14 +48,14 @@ pub async fn resolve_ptr_names(
 				records[idx].resolver.ptr_name = ptr_name;
 			}
 			Err(e) => {
-				eprintln!("Warning: PTR lookup task failed: {}", e);
+				crate::warn!("PTR lookup task failed: {}", e);
 			}
 		}
 	}
 	stop_progress_monitor(monitor, "PTR lookup", total, start);
-	println!("  {} resolved, {} no PTR record", resolved_count, total - resolved_count);
+	crate::info!("  {} resolved, {} no PTR record", resolved_count, total - resolved_count);
 
-	println!();
+	crate::info!();
 }
 
 //============================================
@@ -97,12 +97,7 @@ async fn lookup_ptr(ip: IpAddr, timeout: Duration) -> Option<String> {
 	// Query system resolver (use first nameserver from /etc/resolv.conf)
 	let system_resolver = get_system_resolver()?;
 
-	let bind_addr = if system_resolver.is_ipv4() {
-		"0.0.0.0:0"
-	} else {
-		"[::]:0"
-	};
-	let socket = match tokio::net::UdpSocket::bind(bind_addr).await {
+	let socket = match crate::source::bind_udp(system_resolver.is_ipv6()).await {
 		Ok(s) => s,
 		Err(_) => return None,
 	};
@@ -21,12 +21,327 @@ pub struct CharacterizationResult {
 	pub successes: u32,
 	/// Median latency in milliseconds; None if unreachable
 	pub latency_ms: Option<f64>,
-	/// Whether the resolver intercepts NXDOMAIN responses
-	pub intercepts_nxdomain: bool,
+	/// Whether the resolver intercepts NXDOMAIN responses, by majority of the
+	/// NXDOMAIN probes; None when inconclusive (conflicting or no replies)
+	pub intercepts_nxdomain: Option<bool>,
 	/// Whether the resolver protects against DNS rebinding attacks
 	pub rebinding_protection: Option<bool>,
 	/// Whether the resolver validates DNSSEC signatures
 	pub validates_dnssec: Option<bool>,
+	/// Whether the resolver returned the known root NS set; Some(false) points
+	/// to a captive portal or hijacking middlebox
+	pub answers_known_record: Option<bool>,
+	/// Whether the resolver blocks ad or malware domains; probed only by `characterize`
+	pub filtering: Option<bool>,
+	/// EDNS UDP payload size the resolver advertises, Some(0) without EDNS;
+	/// probed only by `characterize`
+	pub edns_udp_size: Option<u16>,
+	/// Whether the resolver returns an RFC 7873 server cookie; probed only by `characterize`
+	pub server_cookie: Option<bool>,
+	/// Whether the reply echoes a mixed-case QNAME exactly (0x20 encoding);
+	/// probed only by `characterize`
+	pub preserves_case: Option<bool>,
+	/// Large-response probe across EDNS buffer sizes; probed only by `characterize`
+	pub edns_buffer: Option<EdnsBufferResult>,
+	/// How large answers (signed DNSKEY, big TXT sets) come through at the
+	/// Flag Day buffer size; probed only by `characterize`
+	pub large_responses: Option<LargeResponseResult>,
+	/// How the resolver treats queries with recursion-desired cleared;
+	/// probed only by `characterize`
+	pub rd_zero: Option<RdZeroBehavior>,
+	/// Whether an HTTPS record with an ECH config comes back intact;
+	/// probed only by `characterize`
+	pub https_rr: Option<HttpsRrBehavior>,
+	/// Whether replies to a padded query carry EDNS padding (RFC 8467);
+	/// probed only by `characterize`, over DoT and DoH only
+	pub pads_responses: Option<bool>,
+	/// Whether AAAA answers are synthesized for IPv4-only names (DNS64);
+	/// probed only by `characterize`
+	pub dns64: Option<Dns64Behavior>,
+	/// Returned TTLs against authoritative ones, to catch min/max TTL
+	/// clamping; probed only by `characterize`
+	pub ttl_rewrite: Option<TtlRewriteResult>,
+	/// Answer times of identical simultaneous queries for a fresh name, to
+	/// tell whether duplicates are coalesced; probed only by `characterize`
+	pub coalescing: Option<CoalescingResult>,
+}
+
+/// Reply to queries sent with RD=0 (recursion not desired).
+///
+/// A resolver that answers such queries from its cache lets anyone learn
+/// which names its other users looked up recently (cache snooping).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RdZeroBehavior {
+	/// REFUSED or SERVFAIL: non-recursive queries are not served
+	Refused,
+	/// Answers names it has cached, nothing else: open to cache snooping
+	FromCache,
+	/// Empty answer or referral, even for a popular name
+	NoAnswer,
+	/// Resolves a never-seen name anyway, ignoring RD=0
+	Recurses,
+}
+
+impl RdZeroBehavior {
+	/// Short lowercase form for CSV output.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			RdZeroBehavior::Refused => "refused",
+			RdZeroBehavior::FromCache => "from_cache",
+			RdZeroBehavior::NoAnswer => "no_answer",
+			RdZeroBehavior::Recurses => "recurses",
+		}
+	}
+}
+
+/// Reply to an HTTPS (type 65) query for a name whose record carries an
+/// ECH config (`crate::dns::HTTPS_RR_PROBE_DOMAIN`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HttpsRrBehavior {
+	/// HTTPS record with its `ech` parameter: ECH can work
+	Intact,
+	/// HTTPS record without `ech`: the parameter was removed
+	NoEch,
+	/// No HTTPS record at all (empty answer or an error)
+	Stripped,
+}
+
+impl HttpsRrBehavior {
+	/// Short lowercase form for CSV output.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			HttpsRrBehavior::Intact => "intact",
+			HttpsRrBehavior::NoEch => "no_ech",
+			HttpsRrBehavior::Stripped => "stripped",
+		}
+	}
+}
+
+/// Reply to an AAAA query for `ipv4only.arpa`, a name with A records only
+/// (RFC 7050). A DNS64 resolver (RFC 6147) synthesizes AAAA records by
+/// embedding the IPv4 address in its NAT64 prefix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dns64Behavior {
+	/// No synthesized AAAA: a plain resolver
+	Off,
+	/// Synthesized AAAA under this NAT64 prefix and length (RFC 6052),
+	/// usually the well-known 64:ff9b::/96
+	Synthesizes(std::net::Ipv6Addr, u8),
+}
+
+impl Dns64Behavior {
+	/// CSV form: `off`, or the prefix such as `64:ff9b::/96`.
+	pub fn csv_text(self) -> String {
+		match self {
+			Dns64Behavior::Off => "off".to_string(),
+			Dns64Behavior::Synthesizes(prefix, len) => format!("{}/{}", prefix, len),
+		}
+	}
+}
+
+/// Outcome of one large query at one advertised EDNS buffer size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BufferOutcome {
+	/// Full answer over UDP, with its size in bytes
+	Complete(usize),
+	/// Reply with the TC bit set: the answer did not fit
+	Truncated,
+	/// No reply before the timeout, typically dropped IP fragments
+	NoAnswer,
+}
+
+/// Result of the EDNS buffer size and fragmentation probe.
+///
+/// The same large query is sent once per advertised buffer size. A resolver
+/// that handles big answers well truncates (TC) whatever does not fit and
+/// then answers over TCP; silence at a large size means fragments are lost
+/// somewhere on the path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EdnsBufferResult {
+	/// Outcome per advertised buffer size, smallest first
+	pub sizes: Vec<(u16, BufferOutcome)>,
+	/// Whether the same query got a full answer over TCP
+	pub tcp_ok: bool,
+}
+
+impl EdnsBufferResult {
+	/// Largest advertised size that returned the full answer over UDP.
+	pub fn effective_limit(&self) -> Option<u16> {
+		self.sizes.iter()
+			.filter(|(_, o)| matches!(o, BufferOutcome::Complete(_)))
+			.map(|(size, _)| *size)
+			.max()
+	}
+
+	/// Largest UDP answer received, in bytes.
+	pub fn largest_response(&self) -> Option<usize> {
+		self.sizes.iter()
+			.filter_map(|(_, o)| match o {
+				BufferOutcome::Complete(bytes) => Some(*bytes),
+				_ => None,
+			})
+			.max()
+	}
+
+	/// Whether a larger buffer went unanswered after a smaller one got a reply.
+	pub fn fragment_drops(&self) -> bool {
+		let first_reply = self.sizes.iter().position(|(_, o)| *o != BufferOutcome::NoAnswer);
+		first_reply.is_some_and(|i| self.sizes[i..].iter().any(|(_, o)| *o == BufferOutcome::NoAnswer))
+	}
+
+	/// Whether oversized answers come back truncated (never silently dropped)
+	/// and the full answer is available over TCP.
+	pub fn clean_fallback(&self) -> bool {
+		self.tcp_ok && self.sizes.iter().all(|(_, o)| *o != BufferOutcome::NoAnswer)
+	}
+}
+
+/// How one large answer reached the client.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LargeResponseOutcome {
+	/// Full answer over UDP, with its size in bytes
+	Udp(usize),
+	/// Truncated (TC) over UDP, then the full answer over TCP, with its size
+	TcpFallback(usize),
+	/// No UDP reply at all, but TCP answers: clients wait out a timeout first
+	UdpLost,
+	/// Truncated over UDP and the TCP query failed: the answer is unreachable
+	NoTcp,
+	/// An error or empty answer, or no reply over either transport
+	Failed,
+}
+
+impl LargeResponseOutcome {
+	/// Whether the client ends up with the answer without waiting out a timeout.
+	pub fn is_clean(self) -> bool {
+		matches!(self, LargeResponseOutcome::Udp(_) | LargeResponseOutcome::TcpFallback(_))
+	}
+
+	/// Short lowercase form for CSV output, with the size when answered:
+	/// `udp:1180`, `tcp:3042`, `udp_lost`, `no_tcp`, `failed`.
+	pub fn csv_text(self) -> String {
+		match self {
+			LargeResponseOutcome::Udp(bytes) => format!("udp:{}", bytes),
+			LargeResponseOutcome::TcpFallback(bytes) => format!("tcp:{}", bytes),
+			LargeResponseOutcome::UdpLost => "udp_lost".to_string(),
+			LargeResponseOutcome::NoTcp => "no_tcp".to_string(),
+			LargeResponseOutcome::Failed => "failed".to_string(),
+		}
+	}
+}
+
+/// Result of the large response probe: the signed root DNSKEY set and a
+/// large TXT set, each asked for over UDP at the Flag Day buffer size with
+/// TCP as the fallback (`crate::dns::LARGE_RESPONSE_PROBES`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct LargeResponseResult {
+	/// Outcome per probe, labeled as in `crate::dns::LARGE_RESPONSE_PROBES`
+	pub probes: Vec<(&'static str, LargeResponseOutcome)>,
+}
+
+impl LargeResponseResult {
+	/// Outcome of one probe by label.
+	pub fn outcome(&self, label: &str) -> Option<LargeResponseOutcome> {
+		self.probes.iter().find(|(l, _)| *l == label).map(|(_, o)| *o)
+	}
+
+	/// Overall handling for CSV output, the worst probe outcome: `clean`
+	/// when every answer arrived over UDP or after a clean TC/TCP fallback,
+	/// else `udp_lost`, `no_tcp`, or `fails`.
+	pub fn handling(&self) -> &'static str {
+		let any = |o: LargeResponseOutcome| self.probes.iter().any(|(_, p)| *p == o);
+		if any(LargeResponseOutcome::Failed) {
+			"fails"
+		} else if any(LargeResponseOutcome::NoTcp) {
+			"no_tcp"
+		} else if any(LargeResponseOutcome::UdpLost) {
+			"udp_lost"
+		} else {
+			"clean"
+		}
+	}
+}
+
+/// A TTL as published by the zone's name server and as the resolver returned it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TtlCheck {
+	pub authoritative: u32,
+	pub returned: u32,
+}
+
+/// Result of the TTL rewriting probe: one name with a short authoritative
+/// TTL and one with a very long one (`crate::dns::TTL_PROBE_SHORT`,
+/// `crate::dns::TTL_PROBE_LONG`).
+///
+/// Cache age only ever lowers a TTL, so a short-TTL answer above the
+/// authoritative value means a minimum TTL is enforced. The long name's
+/// TTL is over a month; an answer at or below `crate::dns::TTL_CAP_LIMIT`
+/// would take weeks of cache age, so it means a maximum TTL.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TtlRewriteResult {
+	pub short: Option<TtlCheck>,
+	pub long: Option<TtlCheck>,
+}
+
+impl TtlRewriteResult {
+	/// Short TTLs come back raised (a minimum TTL is enforced).
+	pub fn raises_min(&self) -> bool {
+		self.short.is_some_and(|c| c.returned > c.authoritative)
+	}
+
+	/// Long TTLs come back cut down (a maximum TTL is enforced).
+	pub fn caps_max(&self) -> bool {
+		self.long.is_some_and(|c| c.returned <= crate::dns::TTL_CAP_LIMIT && c.authoritative > crate::dns::TTL_CAP_LIMIT)
+	}
+
+	/// Long TTLs come back above the authoritative value.
+	pub fn extends_max(&self) -> bool {
+		self.long.is_some_and(|c| c.returned > c.authoritative)
+	}
+
+	/// Rewrites found, for CSV output: `none`, or `raises_min`, `caps_max`,
+	/// and `extends_max` joined with `;`.
+	pub fn csv_text(&self) -> String {
+		let found: Vec<&str> = [(self.raises_min(), "raises_min"), (self.caps_max(), "caps_max"), (self.extends_max(), "extends_max")]
+			.into_iter()
+			.filter_map(|(hit, name)| hit.then_some(name))
+			.collect();
+		if found.is_empty() { "none".to_string() } else { found.join(";") }
+	}
+}
+
+/// Result of the duplicate query coalescing probe: `crate::dns::COALESCE_QUERIES`
+/// identical queries for a name no resolver has cached, sent at once.
+///
+/// A resolver that coalesces duplicates sends one upstream query and
+/// answers every copy from it, so the answers arrive together. One that
+/// resolves each copy on its own sends several upstream queries, whose
+/// answers come back spread out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoalescingResult {
+	/// Queries sent
+	pub sent: usize,
+	/// Answer time of each answered query (ms), sorted ascending
+	pub latencies_ms: Vec<f64>,
+}
+
+impl CoalescingResult {
+	/// Time between the first and the last answer (ms); None with fewer than two.
+	pub fn spread_ms(&self) -> Option<f64> {
+		match (self.latencies_ms.first(), self.latencies_ms.last()) {
+			(Some(first), Some(last)) if self.latencies_ms.len() >= 2 => Some(last - first),
+			_ => None,
+		}
+	}
+
+	/// Whether the answers arrived together: a spread within
+	/// `crate::dns::COALESCE_SPREAD_MS` or a tenth of the median answer time,
+	/// whichever is larger. None with fewer than two answers.
+	pub fn coalesces(&self) -> Option<bool> {
+		let spread = self.spread_ms()?;
+		let median = crate::stats::percentile(&self.latencies_ms, 50.0)?;
+		Some(spread <= crate::dns::COALESCE_SPREAD_MS.max(median / 10.0))
+	}
 }
 
 /// Result of the qualification scoring stage for a single resolver.
@@ -43,18 +358,328 @@ pub struct QualificationResult {
 }
 
 /// Result of the full benchmark stage for a single resolver.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct BenchmarkResult {
 	/// Per-category statistics (e.g. "cached", "tld", "dotcom")
 	pub categories: BTreeMap<String, SetStats>,
+	/// Scored sets pooled and split by query type ("A", "AAAA"), to show a
+	/// resolver that is fast for A but slow or broken for AAAA
+	pub query_types: BTreeMap<String, SetStats>,
 	pub overall_score: f64,
 	pub success_rate: f64,
+	/// Percent of scored queries answered on the first UDP send
+	pub first_attempt_rate: f64,
+	/// Percent of scored queries answered only after a UDP retransmission;
+	/// a high value points at packet loss rather than a slow resolver
+	pub retry_success_rate: f64,
+	/// Percent of answers whose TTL was decremented (looked cached); None without TTLs
+	pub cache_hit_rate: Option<f64>,
+	/// NXDOMAIN set p50 of the first round minus p50 of repeat rounds (ms);
+	/// positive means repeated NXDOMAIN lookups got faster (negative caching)
+	pub negative_cache_speedup_ms: Option<f64>,
+	/// Mean coefficient of variation across scored categories (lower is more
+	/// consistent); None without at least two successes in some category
+	pub stability_cv: Option<f64>,
+	/// Timed-out queries in scored sets
+	pub scored_timeouts: usize,
+	/// Of those, how many were answered during the late grace period
+	/// (--late-grace-ms); the rest never got an answer
+	pub late_arrivals: usize,
+	/// Median arrival time of late answers in ms, from the first send
+	pub late_p50_ms: Option<f64>,
+	/// Extra copies of answers received, a sign of a duplicating middlebox
+	/// or path
+	pub duplicate_responses: usize,
+	/// Answers that arrived after their query had given up, grace period
+	/// included; only the shared sockets of --udp-pool see these
+	pub after_grace_responses: usize,
+	/// Abandoned after its first queries in a round all timed out; the
+	/// rest of that round was never sent and later rounds skip it
+	pub unresponsive: bool,
+	/// Of the unresponsive, abandoned because the network reported the
+	/// resolver unreachable (ICMP port or host unreachable)
+	pub unreachable: bool,
+	/// Raw per-set, per-round data behind the statistics above
+	pub raw: RawAggregates,
 	// NOTE: rank and tie_group are comparison outputs, not intrinsic measurements.
 	// Pragmatic compromise; a later cleanup could split ranking metadata out.
 	pub rank: usize,
 	pub tie_group: Option<String>,
 }
 
+/// Raw query counters and latencies for one resolver, one set, one round.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoundAggregate {
+	/// Latencies of successful queries in milliseconds, in completion order
+	pub latencies_ms: Vec<f64>,
+	/// Queries answered successfully (NXDOMAIN counts as success in the NXDOMAIN set)
+	pub success: usize,
+	/// Successful queries that needed at least one UDP retransmission
+	pub retried: usize,
+	/// Queries that timed out
+	pub timeout: usize,
+	/// Queries sent, including failures
+	pub total: usize,
+	/// Error answers by response code
+	pub rcodes: crate::stats::RcodeCounts,
+	/// Unanswered queries by cause, besides plain timeouts
+	pub failures: crate::stats::FailureCounts,
+}
+
+/// Intermediate benchmark data for one resolver, kept so library users can
+/// compute custom metrics (other percentiles, per-round drift, loss patterns)
+/// without re-running queries.
+///
+/// `sets` maps set name (e.g. "cached", "nxdomain") to one entry per round,
+/// indexed from round 1 at position 0. Rounds after a resolver was sidelined
+/// hold empty aggregates.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawAggregates {
+	pub sets: BTreeMap<String, Vec<RoundAggregate>>,
+}
+
+//============================================
+impl RawAggregates {
+	/// All successful latencies for a set across rounds, in round order.
+	pub fn set_latencies_ms(&self, set_name: &str) -> Vec<f64> {
+		self.sets.get(set_name)
+			.map(|rounds| rounds.iter().flat_map(|r| r.latencies_ms.iter().copied()).collect())
+			.unwrap_or_default()
+	}
+
+	/// All successful latencies across scored sets (every set except NXDOMAIN).
+	pub fn scored_latencies_ms(&self) -> Vec<f64> {
+		self.sets.iter()
+			.filter(|(name, _)| name.as_str() != crate::domains::NXDOMAIN_SET)
+			.flat_map(|(_, rounds)| rounds.iter().flat_map(|r| r.latencies_ms.iter().copied()))
+			.collect()
+	}
+
+	/// Aggregate for one set and round (1-based); None if absent.
+	pub fn round(&self, set_name: &str, round: u32) -> Option<&RoundAggregate> {
+		let idx = (round as usize).checked_sub(1)?;
+		self.sets.get(set_name)?.get(idx)
+	}
+
+	/// Number of rounds recorded (the most of any set).
+	pub fn round_count(&self) -> usize {
+		self.sets.values().map(Vec::len).max().unwrap_or(0)
+	}
+
+	/// Successful latencies across scored sets for one round (1-based).
+	pub fn round_scored_latencies_ms(&self, round: u32) -> Vec<f64> {
+		self.sets.keys()
+			.filter(|name| name.as_str() != crate::domains::NXDOMAIN_SET)
+			.filter_map(|name| self.round(name, round))
+			.flat_map(|r| r.latencies_ms.iter().copied())
+			.collect()
+	}
+}
+
+/// Result of the optional network-path probe for a single resolver.
+///
+/// Diagnostic only: not part of the score. TCP connect time approximates
+/// the network round trip; the remainder of the DNS latency is attributed
+/// to resolver processing.
+#[derive(Debug, Clone)]
+pub struct PathProbeResult {
+	/// Median ICMP echo round trip in milliseconds; None if ICMP is unavailable or dropped
+	pub ping_ms: Option<f64>,
+	/// Median TCP connect time in milliseconds; None if every connect failed
+	pub tcp_connect_ms: Option<f64>,
+	/// DNS p50 used for comparison (cached set when available)
+	pub dns_p50_ms: f64,
+	/// dns_p50_ms minus the ping round trip (else the TCP connect time),
+	/// clamped at zero; None without either baseline
+	pub dns_overhead_ms: Option<f64>,
+}
+
+/// Network path length to a resolver from TTL-limited pings (--traceroute).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TracerouteResult {
+	/// Hops to the resolver, or to the furthest router that answered when
+	/// the resolver itself did not
+	pub hops: u8,
+	/// Whether the resolver answered, so `hops` is the full path length
+	pub reached: bool,
+	/// Round trip to that last hop in milliseconds
+	pub last_hop_rtt_ms: f64,
+}
+
+/// Identifiers a resolver reports about the server that answered.
+///
+/// Anycast resolvers share one address across many sites; these names show
+/// which site (PoP) answered, which often explains latency differences.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdentityResult {
+	/// EDNS name server identifier (RFC 5001)
+	pub nsid: Option<String>,
+	/// CHAOS TXT `id.server` (RFC 4892)
+	pub id_server: Option<String>,
+	/// CHAOS TXT `hostname.bind` (BIND convention)
+	pub hostname_bind: Option<String>,
+}
+
+impl IdentityResult {
+	/// The most specific identifier found: NSID, then id.server, then hostname.bind.
+	pub fn best(&self) -> Option<&str> {
+		self.nsid.as_deref()
+			.or(self.id_server.as_deref())
+			.or(self.hostname_bind.as_deref())
+	}
+}
+
+/// One name whose answer disagreed with the other resolvers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deviation {
+	pub domain: String,
+	/// Addresses this resolver returned (empty for NXDOMAIN or no A records)
+	pub answer: Vec<std::net::Ipv4Addr>,
+	/// Addresses most resolvers returned
+	pub consensus: Vec<std::net::Ipv4Addr>,
+}
+
+/// Result of the answer consistency check (--consistency-check).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConsistencyResult {
+	/// Names this resolver answered that had a consensus to compare with
+	pub checked: usize,
+	/// Names answered differently from the consensus
+	pub deviations: Vec<Deviation>,
+}
+
+/// Result of the optional packet-loss probe for a single resolver.
+///
+/// Reported separately from the score: the benchmark folds lost packets
+/// into its timeout penalty, while this isolates the loss fraction.
+#[derive(Debug, Clone)]
+pub struct LossProbeResult {
+	/// Identical queries sent in the burst
+	pub sent: u32,
+	/// Queries that got any response (including error rcodes)
+	pub answered: u32,
+	/// Estimated loss percentage: (sent - answered) / sent * 100
+	pub loss_pct: f64,
+}
+
+/// TLS connection setup to a DoT or DoH resolver, timed apart from the
+/// per-query latency: one fresh connection, then a second one offering the
+/// first connection's session.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsSetupResult {
+	/// Negotiated protocol version, e.g. "TLSv1.3"
+	pub version: String,
+	/// TCP connect time of the fresh connection (ms)
+	pub connect_ms: f64,
+	/// Full TLS handshake time, after the TCP connect (ms)
+	pub handshake_ms: f64,
+	/// Handshake time of the second connection (ms); None if it failed
+	pub resumed_handshake_ms: Option<f64>,
+	/// Whether the second connection resumed the session
+	pub resumed: bool,
+	/// Whether the session ticket allows 0-RTT early data
+	pub early_data: bool,
+}
+
+/// Certificate presented by a DoT or DoH resolver, checked against the
+/// resolver file's `spki=` and `tls_name=` pins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertPinResult {
+	/// SHA-256 of the leaf certificate's SubjectPublicKeyInfo, base64
+	pub spki: String,
+	/// Whether `spki` is one of the pins; None when no pin was given
+	pub spki_match: Option<bool>,
+	/// Whether the certificate chains to a web PKI root and is valid for
+	/// the `tls_name=` name; None when no name was given
+	pub name_match: Option<bool>,
+	/// Why the name check failed, as rustls reports it
+	pub name_error: Option<String>,
+}
+
+impl CertPinResult {
+	/// Whether every given pin matched.
+	pub fn matches(&self) -> bool {
+		self.spki_match != Some(false) && self.name_match != Some(false)
+	}
+}
+
+/// Median DoH latency over one HTTP version (--doh-http-compare).
+#[derive(Debug, Clone, PartialEq)]
+pub struct DohHttpResult {
+	pub version: crate::cli::DohHttpVersion,
+	/// Timed queries that got an answer (of DOH_HTTP_PROBE_QUERIES)
+	pub answered: usize,
+	/// Median latency of the answered queries (ms); None if none answered
+	pub p50_ms: Option<f64>,
+}
+
+/// Latency to a resolver from RIPE Atlas probes around the world
+/// (--ripe-atlas-key), to contrast with the local measurement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AtlasResult {
+	/// One-off Atlas measurement IDs, one per domain
+	pub measurement_ids: Vec<u64>,
+	/// Distinct probes that reported a result
+	pub probes: usize,
+	/// Probe results with an answer, and all probe results
+	pub answered: usize,
+	pub results: usize,
+	/// Median and 90th percentile response time of the answers (ms)
+	pub p50_ms: Option<f64>,
+	pub p90_ms: Option<f64>,
+}
+
+/// Per-resolver classification of the warm-set domains from the pre-benchmark
+/// double-query probe (see `bench::run_warm_probe`).
+#[derive(Debug, Clone, Default)]
+pub struct WarmProbeResult {
+	/// First answer carried a decremented TTL: already cached before probing
+	pub cached: Vec<String>,
+	/// First answer carried the full TTL and the second a lower one: fetched
+	/// fresh, so excluded from this resolver's warm set
+	pub excluded: Vec<String>,
+	/// No answer, or TTLs that do not decrement: kept in the warm set
+	pub undetermined: Vec<String>,
+}
+
+/// Open-resolver misuse signals for one public UDP resolver (see `crate::misuse`).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MisuseResult {
+	/// Signals found, e.g. "version.bind: dnsmasq-2.45" or "no EDNS"
+	pub signals: Vec<String>,
+}
+
+impl MisuseResult {
+	/// Whether enough signals point to a home router or compromised device.
+	pub fn suspect(&self) -> bool {
+		self.signals.len() >= crate::misuse::MISUSE_MIN_SIGNALS
+	}
+}
+
+/// Network and country of a resolver address, from an offline ASN table (--asn-db).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AsnInfo {
+	pub asn: u32,
+	/// ISO country code the range is registered in; empty if unknown
+	pub country: String,
+	/// AS name, e.g. "CLOUDFLARENET"
+	pub name: String,
+}
+
+impl AsnInfo {
+	/// Short display form: "AS13335 CLOUDFLARENET (US)".
+	pub fn display(&self) -> String {
+		let mut text = format!("AS{}", self.asn);
+		if !self.name.is_empty() {
+			text = format!("{} {}", text, self.name);
+		}
+		if !self.country.is_empty() {
+			text = format!("{} ({})", text, self.country);
+		}
+		text
+	}
+}
+
 /// Per-run accumulator for a single resolver.
 /// Each pipeline stage writes its result onto the record.
 #[derive(Debug, Clone)]
@@ -64,6 +689,32 @@ pub struct ResolverRecord {
 	pub characterization: Option<CharacterizationResult>,
 	pub qualification: Option<QualificationResult>,
 	pub benchmark: Option<BenchmarkResult>,
+	pub path_probe: Option<PathProbeResult>,
+	/// Hop count and last-hop round trip (--traceroute)
+	pub traceroute: Option<TracerouteResult>,
+	/// Packet-loss probe result (--loss-probe, UDP resolvers only)
+	pub loss_probe: Option<LossProbeResult>,
+	/// TLS setup and session-resumption timing (DoT and DoH resolvers)
+	pub tls_setup: Option<TlsSetupResult>,
+	/// Certificate pin check (DoT and DoH resolvers with `spki=` or `tls_name=` tags)
+	pub cert_pin: Option<CertPinResult>,
+	/// Latency per HTTP version (--doh-http-compare, DoH resolvers only)
+	pub doh_http: Vec<DohHttpResult>,
+	/// Latency from RIPE Atlas probes (--ripe-atlas-key, public UDP resolvers)
+	pub atlas: Option<AtlasResult>,
+	/// Warm-set pre-probe classification
+	pub warm_probe: Option<WarmProbeResult>,
+	/// Server identity (--identity-probe and `characterize`, UDP)
+	pub identity: Option<IdentityResult>,
+	/// Answer consistency against the other resolvers (--consistency-check and `characterize`)
+	pub consistency: Option<ConsistencyResult>,
+	/// Open-resolver misuse signals (large lists and --exclude-suspect-open)
+	pub misuse: Option<MisuseResult>,
+	/// AS and country of the address (--asn-db)
+	pub asn: Option<AsnInfo>,
+	/// Best identifier seen after each benchmark round (--identity-probe);
+	/// None where the probe got no answer
+	pub identity_rounds: Vec<Option<String>>,
 }
 
 //============================================
@@ -76,25 +727,176 @@ impl ResolverRecord {
 			characterization: None,
 			qualification: None,
 			benchmark: None,
+			path_probe: None,
+			traceroute: None,
+			loss_probe: None,
+			tls_setup: None,
+			cert_pin: None,
+			doh_http: Vec::new(),
+			atlas: None,
+			warm_probe: None,
+			identity: None,
+			consistency: None,
+			misuse: None,
+			asn: None,
+			identity_rounds: Vec::new(),
 		}
 	}
 
 	/// Whether this resolver intercepts NXDOMAIN (from characterization).
 	pub fn intercepts_nxdomain(&self) -> bool {
 		self.characterization.as_ref()
-			.map(|c| c.intercepts_nxdomain)
+			.and_then(|c| c.intercepts_nxdomain)
 			.unwrap_or(false)
 	}
 
+	/// Whether the NXDOMAIN interception check ran but reached no majority.
+	pub fn nxdomain_inconclusive(&self) -> bool {
+		self.characterization.as_ref()
+			.is_some_and(|c| c.reachable && c.intercepts_nxdomain.is_none())
+	}
+
 	/// Whether this resolver has DNS rebinding protection (from characterization).
 	pub fn rebinding_protection(&self) -> Option<bool> {
 		self.characterization.as_ref()
 			.and_then(|c| c.rebinding_protection)
 	}
 
+	/// Consecutive rounds answered by the same site: (identifier, rounds).
+	///
+	/// Rounds where the probe got no answer are skipped, so a lost probe
+	/// never counts as a site change.
+	pub fn identity_runs(&self) -> Vec<(&str, usize)> {
+		let mut runs: Vec<(&str, usize)> = Vec::new();
+		for id in self.identity_rounds.iter().flatten() {
+			match runs.last_mut() {
+				Some((last, count)) if *last == id.as_str() => *count += 1,
+				_ => runs.push((id.as_str(), 1)),
+			}
+		}
+		runs
+	}
+
+	/// Times the answering anycast site changed during the benchmark.
+	pub fn pop_changes(&self) -> usize {
+		self.identity_runs().len().saturating_sub(1)
+	}
+
+	/// Whether this resolver returned the known root NS set (from characterization).
+	pub fn answers_known_record(&self) -> Option<bool> {
+		self.characterization.as_ref()
+			.and_then(|c| c.answers_known_record)
+	}
+
 	/// Whether this resolver validates DNSSEC (from characterization).
 	pub fn validates_dnssec(&self) -> Option<bool> {
 		self.characterization.as_ref()
 			.and_then(|c| c.validates_dnssec)
 	}
+
+	/// NXDOMAIN answers for real domains during the benchmark, as (count,
+	/// percent of real-domain queries); None without benchmark queries.
+	pub fn unexpected_nxdomain(&self) -> Option<(usize, f64)> {
+		let bm = self.benchmark.as_ref()?;
+		let (nxdomain, total) = crate::stats::unexpected_nxdomain(&bm.categories);
+		(total > 0).then(|| (nxdomain, nxdomain as f64 * 100.0 / total as f64))
+	}
+
+	/// Whether this resolver answered NXDOMAIN for more real domains than
+	/// `UNEXPECTED_NXDOMAIN_FLAG_PCT`, a sign of filtering or censorship.
+	pub fn possibly_censoring(&self) -> bool {
+		self.unexpected_nxdomain()
+			.is_some_and(|(_, pct)| pct > crate::stats::UNEXPECTED_NXDOMAIN_FLAG_PCT)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_raw_aggregates_round_lookup() {
+		let mut raw = RawAggregates::default();
+		raw.sets.insert("cached".to_string(), vec![
+			RoundAggregate { latencies_ms: vec![10.0, 12.0], success: 2, total: 2, ..Default::default() },
+			RoundAggregate { latencies_ms: vec![11.0], success: 1, timeout: 1, total: 2, ..Default::default() },
+		]);
+		assert_eq!(raw.round("cached", 2).unwrap().timeout, 1);
+		assert!(raw.round("cached", 0).is_none());
+		assert!(raw.round("cached", 3).is_none());
+		assert_eq!(raw.set_latencies_ms("cached"), vec![10.0, 12.0, 11.0]);
+		assert!(raw.set_latencies_ms("tld").is_empty());
+		assert_eq!(raw.round_count(), 2);
+		assert_eq!(raw.round_scored_latencies_ms(2), vec![11.0]);
+	}
+
+	#[test]
+	fn test_edns_buffer_result_summary() {
+		let result = EdnsBufferResult {
+			sizes: vec![
+				(512, BufferOutcome::Truncated),
+				(1232, BufferOutcome::Complete(1180)),
+				(1452, BufferOutcome::Complete(1180)),
+				(4096, BufferOutcome::NoAnswer),
+			],
+			tcp_ok: true,
+		};
+		assert_eq!(result.effective_limit(), Some(1452));
+		assert_eq!(result.largest_response(), Some(1180));
+		assert!(result.fragment_drops());
+		assert!(!result.clean_fallback());
+
+		let clean = EdnsBufferResult {
+			sizes: vec![(512, BufferOutcome::Truncated), (1232, BufferOutcome::Complete(1180))],
+			tcp_ok: true,
+		};
+		assert!(!clean.fragment_drops());
+		assert!(clean.clean_fallback());
+
+		let silent = EdnsBufferResult { sizes: vec![(512, BufferOutcome::NoAnswer)], tcp_ok: false };
+		assert_eq!(silent.effective_limit(), None);
+		assert!(!silent.fragment_drops());
+	}
+
+	#[test]
+	fn test_large_response_handling() {
+		use LargeResponseOutcome::*;
+		let result = |probes: Vec<(&'static str, LargeResponseOutcome)>| LargeResponseResult { probes };
+		let clean = result(vec![("dnskey", Udp(1180)), ("txt", TcpFallback(3042))]);
+		assert_eq!(clean.handling(), "clean");
+		assert_eq!(clean.outcome("txt"), Some(TcpFallback(3042)));
+		assert_eq!(clean.outcome("any"), None);
+		assert_eq!(result(vec![("dnskey", UdpLost), ("txt", Udp(900))]).handling(), "udp_lost");
+		assert_eq!(result(vec![("dnskey", NoTcp), ("txt", UdpLost)]).handling(), "no_tcp");
+		assert_eq!(result(vec![("dnskey", NoTcp), ("txt", Failed)]).handling(), "fails");
+		assert_eq!(TcpFallback(3042).csv_text(), "tcp:3042");
+		assert!(!UdpLost.is_clean() && Udp(512).is_clean());
+	}
+
+	#[test]
+	fn test_coalescing_verdict() {
+		let result = |latencies_ms: Vec<f64>| CoalescingResult { sent: 8, latencies_ms };
+		let together = result(vec![40.1, 40.3, 40.4, 41.0]);
+		assert_eq!(together.coalesces(), Some(true));
+		assert!((together.spread_ms().unwrap() - 0.9).abs() < 1e-9);
+		// 6 ms apart on a 100 ms median is still within a tenth of it
+		assert_eq!(result(vec![98.0, 100.0, 104.0]).coalesces(), Some(true));
+		assert_eq!(result(vec![21.0, 24.5, 38.0, 52.0]).coalesces(), Some(false));
+		assert_eq!(result(vec![30.0]).coalesces(), None);
+		assert_eq!(result(vec![]).spread_ms(), None);
+	}
+
+	#[test]
+	fn test_identity_runs_and_pop_changes() {
+		let resolver = crate::transport::Resolver::new(
+			"1.1.1.1:53".parse().unwrap(), crate::transport::DnsTransport::Udp);
+		let mut rec = ResolverRecord::new(resolver);
+		let id = |s: &str| Some(s.to_string());
+		rec.identity_rounds = vec![id("fra"), None, id("fra"), id("ams"), id("fra")];
+		assert_eq!(rec.identity_runs(), vec![("fra", 2), ("ams", 1), ("fra", 1)]);
+		assert_eq!(rec.pop_changes(), 2);
+
+		rec.identity_rounds = vec![id("fra"), None, id("fra")];
+		assert_eq!(rec.pop_changes(), 0);
+	}
 }
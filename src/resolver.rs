@@ -1,21 +1,39 @@
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 
-use crate::transport::{DnsTransport, Resolver};
+use crate::transport::{DnsTransport, DohMethod, DohOptions, QueryType, Resolver};
+
+/// Server that resolves resolver hostnames (--bootstrap); None uses the
+/// system resolver
+static BOOTSTRAP: RwLock<Option<IpAddr>> = RwLock::new(None);
+
+/// Address family picked for resolver hostnames (--ipv4-only, --ipv6-only,
+/// --dual-stack)
+static BOOTSTRAP_FAMILY: RwLock<BootstrapFamily> = RwLock::new(BootstrapFamily::PreferIpv4);
+
+/// How long a --bootstrap server gets to answer each lookup, both
+/// record types together
+const BOOTSTRAP_TIMEOUT: Duration = Duration::from_secs(2);
 
 /// Parse a resolver address string into a Resolver.
 ///
 /// Supports formats:
 ///   "1.1.1.1"                           -- UDP, default port 53
 ///   "1.1.1.1:53"                        -- UDP with explicit port
+///   "dns.quad9.net"                     -- UDP, hostname resolved at startup
 ///   "2606:4700::1111"                   -- UDP, bare IPv6, default port 53
 ///   "[2606:4700::1111]:53"              -- UDP, bracketed IPv6 with port
 ///   "tls://1.1.1.1"                     -- DoT, default port 853
 ///   "tls://1.1.1.1:853"                -- DoT with explicit port
+///   "tls://dns.quad9.net"              -- DoT, hostname resolved at startup
 ///   "tls://dns.google/8.8.8.8"         -- DoT with SNI hostname
 ///   "https://1.1.1.1/dns-query"        -- DoH
 ///   "https://dns.google/dns-query"     -- DoH with hostname
+///   "https://1.1.1.1/dns-query method=GET header=X-Key:abc"  -- DoH with options
+///   "sdns://..."                        -- DNS stamp (plain, DoH, or DoT)
 pub fn parse_resolver(input: &str) -> Result<Resolver> {
 	let trimmed = input.trim();
 	if trimmed.is_empty() {
@@ -29,14 +47,35 @@ pub fn parse_resolver(input: &str) -> Result<Resolver> {
 	if trimmed.starts_with("tls://") {
 		return parse_dot_resolver(trimmed);
 	}
+	if trimmed.starts_with("sdns://") {
+		return parse_stamp_resolver(trimmed);
+	}
 
-	// Plain UDP resolver
-	let addr = parse_socket_addr(trimmed, 53)?;
-	Ok(Resolver::new(addr, DnsTransport::Udp))
+	// Plain UDP resolver: an IP address, or a hostname to bootstrap
+	match (parse_socket_addr(trimmed, 53), split_hostname(trimmed, 53)) {
+		(Ok(addr), _) => Ok(Resolver::new(addr, DnsTransport::Udp)),
+		(Err(_), Some((host, port))) => {
+			let mut r = Resolver::new(bootstrap_lookup(host, port)?, DnsTransport::Udp);
+			r.label = host.to_string();
+			r.hostname = Some(host.to_string());
+			Ok(r)
+		}
+		(Err(e), None) => Err(e),
+	}
 }
 
 /// Parse a DoH resolver URL like "https://1.1.1.1/dns-query"
-fn parse_doh_resolver(url: &str) -> Result<Resolver> {
+///
+/// The URL may be followed by whitespace-separated request options:
+///   method=GET|POST         -- HTTP method (default POST)
+///   header=Name:Value       -- extra HTTP header (repeatable)
+///   accept=TYPE             -- shorthand for header=Accept:TYPE
+///   content-type=TYPE       -- shorthand for header=Content-Type:TYPE
+fn parse_doh_resolver(input: &str) -> Result<Resolver> {
+	let mut tokens = input.split_whitespace();
+	let url = tokens.next().unwrap_or(input);
+	let options = parse_doh_options(tokens)?;
+
 	// Strip scheme to extract host and path
 	let after_scheme = &url["https://".len()..];
 
@@ -47,73 +86,321 @@ fn parse_doh_resolver(url: &str) -> Result<Resolver> {
 	};
 
 	// Parse the host as an IP address for the addr field
-	let addr = parse_host_to_addr(host_port, 443)?;
-	let mut r = Resolver::new(addr, DnsTransport::Doh { url: url.to_string() });
+	let (addr, hostname) = parse_host_to_addr(host_port, 443)?;
+	let mut r = Resolver::new(addr, DnsTransport::Doh { url: url.to_string(), options });
 	r.label = host_port.to_string();
+	r.hostname = hostname;
 	Ok(r)
 }
 
+/// Parse DoH request options (key=value tokens following the URL).
+fn parse_doh_options<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<DohOptions> {
+	let mut options = DohOptions::default();
+	for token in tokens {
+		let (key, value) = token.split_once('=')
+			.ok_or_else(|| anyhow!("invalid DoH option '{}': expected key=value", token))?;
+		match key.to_ascii_lowercase().as_str() {
+			"method" => {
+				options.method = match value.to_ascii_uppercase().as_str() {
+					"GET" => DohMethod::Get,
+					"POST" => DohMethod::Post,
+					_ => return Err(anyhow!("invalid DoH method '{}': expected GET or POST", value)),
+				};
+			}
+			"header" => {
+				let (name, header_value) = value.split_once(':')
+					.filter(|(name, _)| !name.is_empty())
+					.ok_or_else(|| anyhow!("invalid DoH header '{}': expected Name:Value", value))?;
+				push_doh_header(&mut options, name, header_value)?;
+			}
+			"accept" => push_doh_header(&mut options, "Accept", value)?,
+			"content-type" => push_doh_header(&mut options, "Content-Type", value)?,
+			_ => return Err(anyhow!("unknown DoH option '{}'", key)),
+		}
+	}
+	Ok(options)
+}
+
+/// Add a DoH request header after checking it is valid HTTP, so a bad one
+/// fails here rather than as a timeout on every query. The JSON API is
+/// refused: responses are parsed as DNS wire format only.
+fn push_doh_header(options: &mut DohOptions, name: &str, value: &str) -> Result<()> {
+	let header_name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+		.map_err(|_| anyhow!("invalid DoH header name '{}'", name))?;
+	reqwest::header::HeaderValue::from_str(value)
+		.map_err(|_| anyhow!("invalid DoH header value '{}' for {}", value, name))?;
+	if header_name == reqwest::header::ACCEPT && value.to_ascii_lowercase().contains("application/dns-json") {
+		bail!("DoH accept={} is not supported: only application/dns-message responses can be parsed", value);
+	}
+	options.headers.push((name.to_string(), value.to_string()));
+	Ok(())
+}
+
 /// Parse a DoT resolver like "tls://1.1.1.1" or "tls://dns.google/8.8.8.8"
 fn parse_dot_resolver(input: &str) -> Result<Resolver> {
 	let after_scheme = &input["tls://".len()..];
 
 	// Check for "hostname/IP" format for SNI + IP separation
-	let (hostname, addr) = if let Some(slash_idx) = after_scheme.find('/') {
+	let (hostname, addr, bootstrapped) = if let Some(slash_idx) = after_scheme.find('/') {
 		let hostname = &after_scheme[..slash_idx];
 		let ip_part = &after_scheme[slash_idx + 1..];
 		let addr = parse_socket_addr(ip_part, 853)?;
-		(hostname.to_string(), addr)
+		(hostname.to_string(), addr, false)
+	} else if let (Err(_), Some((host, port))) = (parse_socket_addr(after_scheme, 853), split_hostname(after_scheme, 853)) {
+		// Just a hostname: it is the SNI name, and the address is looked up
+		(host.to_string(), bootstrap_lookup(host, port)?, true)
 	} else {
 		// Just an IP address, use IP as both addr and hostname
 		let addr = parse_socket_addr(after_scheme, 853)?;
 		let hostname = after_scheme.split(':').next().unwrap_or(after_scheme);
-		(hostname.to_string(), addr)
+		(hostname.to_string(), addr, false)
 	};
 
 	let mut r = Resolver::new(addr, DnsTransport::Dot { hostname: hostname.clone() });
+	r.hostname = bootstrapped.then(|| hostname.clone());
 	r.label = hostname;
 	Ok(r)
 }
 
+/// Parse a DNS stamp (as exported by dnscrypt-proxy) into a Resolver.
+///
+/// Plain, DoH, and DoT stamps are supported. When the stamp carries no
+/// address, the hostname is resolved to get one.
+fn parse_stamp_resolver(input: &str) -> Result<Resolver> {
+	match crate::stamp::decode_stamp(input)? {
+		crate::stamp::DnsStamp::Plain { addr } => {
+			let addr = parse_socket_addr(&addr, 53)?;
+			Ok(Resolver::new(addr, DnsTransport::Udp))
+		}
+		crate::stamp::DnsStamp::Doh { addr, hostname, path } => {
+			let (host, addr, bootstrapped) = stamp_server_addr(&addr, &hostname, 443)?;
+			// The URL is what reqwest connects to, so it must carry the same port
+			let authority = match addr.port() {
+				443 => host.clone(),
+				port => format!("{}:{}", host, port),
+			};
+			let url = format!("https://{}{}", authority, path);
+			let mut r = Resolver::new(addr, DnsTransport::Doh { url, options: DohOptions::default() });
+			r.label = host;
+			r.hostname = bootstrapped;
+			Ok(r)
+		}
+		crate::stamp::DnsStamp::Dot { addr, hostname } => {
+			let (host, addr, bootstrapped) = stamp_server_addr(&addr, &hostname, 853)?;
+			let mut r = Resolver::new(addr, DnsTransport::Dot { hostname: host.clone() });
+			r.label = host;
+			r.hostname = bootstrapped;
+			Ok(r)
+		}
+	}
+}
+
+/// Server address for a DoH or DoT stamp, with the stamp hostname minus
+/// any port (the SNI and label). The port is the one on `addr` when given,
+/// else the one on the hostname, else the transport default; an empty
+/// `addr` bootstraps the hostname.
+fn stamp_server_addr(addr: &str, hostname: &str, default_port: u16) -> Result<(String, SocketAddr, Option<String>)> {
+	let (host, port) = match hostname.rsplit_once(':') {
+		Some((host, port)) if !host.contains(':') => match port.parse::<u16>() {
+			Ok(port) => (host, port),
+			Err(_) => bail!("invalid port in stamp hostname '{}'", hostname),
+		},
+		_ => (hostname, default_port),
+	};
+	let (addr, bootstrapped) = if addr.is_empty() {
+		parse_host_to_addr(host, port)?
+	} else {
+		(parse_socket_addr(addr, port)?, None)
+	};
+	Ok((host.to_string(), addr, bootstrapped))
+}
+
 /// Parse a host:port string to a SocketAddr, supporting IPv4, IPv6, and hostnames.
 ///
-/// For hostnames that cannot be parsed as IPs, attempts DNS resolution.
-fn parse_host_to_addr(host_port: &str, default_port: u16) -> Result<SocketAddr> {
+/// A hostname is resolved with `bootstrap_lookup` and returned alongside
+/// the address; an IP address comes back with None.
+fn parse_host_to_addr(host_port: &str, default_port: u16) -> Result<(SocketAddr, Option<String>)> {
 	// Handle bracketed IPv6: [::1]:443
 	if host_port.starts_with('[') {
 		let addr: SocketAddr = host_port.parse()
 			.map_err(|e| anyhow!("invalid bracketed address '{}': {}", host_port, e))?;
-		return Ok(addr);
+		return Ok((addr, None));
 	}
 
 	// Try as IP:port or plain IP
 	if let Ok(addr) = host_port.parse::<SocketAddr>() {
-		return Ok(addr);
+		return Ok((addr, None));
 	}
 	if let Ok(ip) = host_port.parse::<std::net::IpAddr>() {
-		return Ok(SocketAddr::new(ip, default_port));
+		return Ok((SocketAddr::new(ip, default_port), None));
 	}
 
-	// Split off port if present (hostname:port)
-	let (host, port) = if let Some(colon_idx) = host_port.rfind(':') {
-		let port_str = &host_port[colon_idx + 1..];
-		if let Ok(port) = port_str.parse::<u16>() {
-			(&host_port[..colon_idx], port)
+	let (host, port) = split_hostname(host_port, default_port)
+		.ok_or_else(|| anyhow!("invalid resolver host '{}'", host_port))?;
+	Ok((bootstrap_lookup(host, port)?, Some(host.to_string())))
+}
+
+/// Split "host" or "host:port" into a hostname and port. None unless the
+/// host is a dotted DNS name with a letter in it, so a mistyped IP address
+/// is reported as such rather than looked up.
+fn split_hostname(host_port: &str, default_port: u16) -> Option<(&str, u16)> {
+	let (host, port) = match host_port.rsplit_once(':') {
+		Some((host, port)) => (host, port.parse::<u16>().ok()?),
+		None => (host_port, default_port),
+	};
+	let valid = host.contains('.')
+		&& host.bytes().any(|b| b.is_ascii_alphabetic())
+		&& host.split('.').all(|label| label.len() <= 63)
+		&& host.trim_end_matches('.').split('.').all(|label| !label.is_empty()
+			&& !label.starts_with('-') && label.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-'));
+	valid.then_some((host, port))
+}
+
+//============================================
+/// Set the server that resolves resolver hostnames (--bootstrap); None
+/// uses the system resolver.
+pub fn set_bootstrap(server: Option<IpAddr>) {
+	if let Ok(mut guard) = BOOTSTRAP.write() {
+		*guard = server;
+	}
+}
+
+/// Current bootstrap server, if one was set.
+pub fn bootstrap() -> Option<IpAddr> {
+	BOOTSTRAP.read().ok().and_then(|g| *g)
+}
+
+/// Address family a resolver hostname resolves to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BootstrapFamily {
+	/// Either family, IPv4 when both answer (the default)
+	PreferIpv4,
+	/// Either family, IPv6 when both answer (--dual-stack)
+	PreferIpv6,
+	/// IPv4 addresses only (--ipv4-only)
+	Ipv4Only,
+	/// IPv6 addresses only (--ipv6-only)
+	Ipv6Only,
+}
+
+impl BootstrapFamily {
+	/// Family that matches the resolver family filter flags.
+	pub fn from_filter(ipv4_only: bool, ipv6_only: bool, dual_stack: bool) -> Self {
+		if ipv6_only {
+			BootstrapFamily::Ipv6Only
+		} else if ipv4_only {
+			BootstrapFamily::Ipv4Only
+		} else if dual_stack {
+			BootstrapFamily::PreferIpv6
 		} else {
-			(host_port, default_port)
+			BootstrapFamily::PreferIpv4
+		}
+	}
+
+	/// Record types to ask a bootstrap server for, in order.
+	fn query_types(self) -> &'static [QueryType] {
+		match self {
+			BootstrapFamily::PreferIpv4 => &[QueryType::A, QueryType::AAAA],
+			BootstrapFamily::PreferIpv6 => &[QueryType::AAAA, QueryType::A],
+			BootstrapFamily::Ipv4Only => &[QueryType::A],
+			BootstrapFamily::Ipv6Only => &[QueryType::AAAA],
+		}
+	}
+
+	/// Address to use out of a lookup's answers, None when none fits.
+	fn pick(self, ips: &[IpAddr]) -> Option<IpAddr> {
+		let v4 = ips.iter().find(|ip| ip.is_ipv4());
+		let v6 = ips.iter().find(|ip| ip.is_ipv6());
+		match self {
+			BootstrapFamily::PreferIpv4 => v4.or(v6),
+			BootstrapFamily::PreferIpv6 => v6.or(v4),
+			BootstrapFamily::Ipv4Only => v4,
+			BootstrapFamily::Ipv6Only => v6,
+		}
+		.copied()
+	}
+}
+
+/// Set the address family for resolver hostnames from the family filter.
+pub fn set_bootstrap_family(family: BootstrapFamily) {
+	if let Ok(mut guard) = BOOTSTRAP_FAMILY.write() {
+		*guard = family;
+	}
+}
+
+/// Current address family for resolver hostnames.
+fn bootstrap_family() -> BootstrapFamily {
+	BOOTSTRAP_FAMILY.read().map(|g| *g).unwrap_or(BootstrapFamily::PreferIpv4)
+}
+
+/// Resolve a resolver hostname to the address to benchmark: through the
+/// --bootstrap server when set, else the system resolver. The family
+/// filter picks the address family; IPv4 is preferred without one.
+fn bootstrap_lookup(host: &str, port: u16) -> Result<SocketAddr> {
+	let family = bootstrap_family();
+	let (ips, via) = match bootstrap() {
+		Some(server) => (query_bootstrap(SocketAddr::new(server, 53), host, family)?, server.to_string()),
+		None => {
+			use std::net::ToSocketAddrs;
+			let ips: Vec<IpAddr> = (host, port).to_socket_addrs()
+				.map_err(|e| anyhow!("cannot resolve hostname '{}': {}", host, e))?
+				.map(|a| a.ip())
+				.collect();
+			(ips, "system resolver".to_string())
 		}
-	} else {
-		(host_port, default_port)
 	};
+	let ip = family.pick(&ips).ok_or_else(|| match family {
+		BootstrapFamily::Ipv4Only => anyhow!("no IPv4 address found for hostname '{}' (--ipv4-only)", host),
+		BootstrapFamily::Ipv6Only => anyhow!("no IPv6 address found for hostname '{}' (--ipv6-only)", host),
+		_ => anyhow!("no addresses found for hostname '{}'", host),
+	})?;
+	crate::verbose!("Bootstrap: {} -> {} (via {})", host, ip, via);
+	Ok(SocketAddr::new(ip, port))
+}
 
-	// Try DNS resolution for hostnames
-	use std::net::ToSocketAddrs;
-	let addr_str = format!("{}:{}", host, port);
-	let addr = addr_str.to_socket_addrs()
-		.map_err(|e| anyhow!("cannot resolve hostname '{}': {}", host, e))?
-		.next()
-		.ok_or_else(|| anyhow!("no addresses found for hostname '{}'", host))?;
-	Ok(addr)
+/// Address records of `host` from `server` over UDP: the family's first
+/// record type, then the next if there are none. The whole lookup shares
+/// one BOOTSTRAP_TIMEOUT deadline.
+fn query_bootstrap(server: SocketAddr, host: &str, family: BootstrapFamily) -> Result<Vec<IpAddr>> {
+	use hickory_proto::op::{Message, ResponseCode};
+	use hickory_proto::rr::RData;
+	let local: SocketAddr = if server.is_ipv6() { "[::]:0".parse()? } else { "0.0.0.0:0".parse()? };
+	let socket = std::net::UdpSocket::bind(local)?;
+	socket.connect(server)?;
+	let deadline = Instant::now() + BOOTSTRAP_TIMEOUT;
+	for &query_type in family.query_types() {
+		let txid: u16 = rand::random();
+		socket.send(&crate::dns::build_query(host, query_type, txid, false)?)?;
+		let mut buf = [0u8; 1232];
+		// Skip stray replies with another transaction ID until the deadline
+		let reply = loop {
+			let remaining = deadline.saturating_duration_since(Instant::now());
+			if remaining.is_zero() {
+				bail!("bootstrap server {} did not answer for '{}' within {:?}", server.ip(), host, BOOTSTRAP_TIMEOUT);
+			}
+			socket.set_read_timeout(Some(remaining))?;
+			let len = socket.recv(&mut buf)
+				.map_err(|e| anyhow!("bootstrap server {} did not answer for '{}': {}", server.ip(), host, e))?;
+			match Message::from_vec(&buf[..len]) {
+				Ok(reply) if reply.id() == txid => break reply,
+				_ => continue,
+			}
+		};
+		if reply.response_code() != ResponseCode::NoError {
+			bail!("bootstrap server {} answered {} for '{}'", server.ip(), reply.response_code(), host);
+		}
+		let ips: Vec<IpAddr> = reply.answers().iter()
+			.filter_map(|r| match r.data() {
+				RData::A(a) => Some(IpAddr::V4(a.0)),
+				RData::AAAA(aaaa) => Some(IpAddr::V6(aaaa.0)),
+				_ => None,
+			})
+			.collect();
+		if !ips.is_empty() {
+			return Ok(ips);
+		}
+	}
+	Ok(Vec::new())
 }
 
 /// Parse a plain socket address string with a default port.
@@ -144,7 +431,10 @@ fn parse_socket_addr(input: &str, default_port: u16) -> Result<SocketAddr> {
 /// Parse a resolver line that may contain an inline comment label.
 ///
 /// Format: "IP_ADDRESS  # Label" or "https://url  # Label"
-/// The label after '#' becomes the resolver's display name.
+/// The label after '#' becomes the resolver's display name. Tags after a
+/// '|' in the comment set optional fields: "1.1.1.1  # Cloudflare | provider=cloudflare".
+/// DoT and DoH lines also take certificate pins, `spki=BASE64` (repeatable)
+/// and `tls_name=NAME`.
 fn parse_resolver_line(line: &str) -> Result<Resolver> {
 	let trimmed = line.trim();
 
@@ -154,15 +444,42 @@ fn parse_resolver_line(line: &str) -> Result<Resolver> {
 	let mut config = parse_resolver(addr_part)?;
 
 	// Use the inline comment as the label if present
-	if let Some(label) = label_part {
+	if let Some(comment) = label_part {
+		let (label, tags) = comment.split_once('|').unwrap_or((comment, ""));
+		let label = label.trim();
 		if !label.is_empty() {
 			config.label = label.to_string();
 		}
+		for tag in tags.split_whitespace() {
+			match tag.split_once('=') {
+				Some(("provider", name)) if !name.is_empty() => config.provider = Some(name.to_string()),
+				Some((key @ ("spki" | "tls_name"), _)) if config.transport == DnsTransport::Udp => {
+					return Err(anyhow!("resolver tag '{}=' needs a DoT or DoH resolver", key));
+				}
+				Some(("spki", pin)) => config.spki_pins.push(parse_spki_pin(pin)?),
+				Some(("tls_name", name)) if !name.is_empty() => config.tls_name = Some(name.to_string()),
+				_ => return Err(anyhow!(
+					"unknown resolver tag '{}' (expected provider=NAME, spki=BASE64, or tls_name=NAME)", tag
+				)),
+			}
+		}
 	}
 
 	Ok(config)
 }
 
+/// Check an `spki=` pin: base64 of a 32-byte SHA-256 digest, as printed by
+/// `openssl x509 -pubkey | openssl pkey -pubin -outform der | openssl dgst -sha256 -binary | base64`.
+fn parse_spki_pin(pin: &str) -> Result<String> {
+	use base64::Engine;
+	let digest = base64::engine::general_purpose::STANDARD.decode(pin)
+		.map_err(|e| anyhow!("invalid spki pin '{}': {}", pin, e))?;
+	if digest.len() != 32 {
+		return Err(anyhow!("invalid spki pin '{}': {} bytes, expected a 32-byte SHA-256 digest", pin, digest.len()));
+	}
+	Ok(pin.to_string())
+}
+
 /// Parse a resolver line, detecting transport scheme before splitting label.
 ///
 /// For DoH/DoT URLs, the '#' inside URLs must not be treated as a comment
@@ -171,7 +488,8 @@ fn split_addr_label(line: &str) -> (&str, Option<&str>) {
 	let trimmed = line.trim();
 
 	// For scheme-prefixed URLs, find '#' that comes after the URL
-	if trimmed.starts_with("https://") || trimmed.starts_with("tls://") {
+	if trimmed.starts_with("https://") || trimmed.starts_with("tls://")
+		|| trimmed.starts_with("sdns://") {
 		// Find the first '#' that has whitespace before it (indicating a comment)
 		if let Some(idx) = trimmed.find(" #").or_else(|| trimmed.find("\t#")) {
 			let addr = trimmed[..idx].trim();
@@ -210,34 +528,290 @@ pub fn read_resolver_file(path: &str) -> Result<Vec<Resolver>> {
 	Ok(resolvers)
 }
 
-/// Read system resolvers from /etc/resolv.conf (Unix only).
+/// PowerShell command that prints one configured DNS server per line (Windows)
+#[cfg(windows)]
+const WINDOWS_DNS_COMMAND: &str =
+	"Get-DnsClientServerAddress | Select-Object -ExpandProperty ServerAddresses";
+
+/// Read the system's configured DNS servers.
 ///
-/// Returns an empty vec on non-Unix platforms or if the file cannot be read.
-pub fn system_resolvers() -> Vec<Resolver> {
-	let content = match std::fs::read_to_string("/etc/resolv.conf") {
-		Ok(c) => c,
-		Err(_) => return Vec::new(),
-	};
-	let mut resolvers = Vec::new();
-	for line in content.lines() {
+/// Linux and other Unix read /etc/resolv.conf. macOS asks `scutil --dns`
+/// first, since resolv.conf there is a compatibility copy that misses
+/// per-interface and VPN resolvers. Windows asks PowerShell
+/// (Get-DnsClientServerAddress, backed by GetAdaptersAddresses). Returns an
+/// empty vec when no source can be read.
+///
+/// When the list points at the systemd-resolved stub (127.0.0.53), the stub
+/// is replaced by the upstream servers it forwards to, so the benchmark
+/// measures the real resolvers; `include_stub` keeps the stub as its own
+/// entry too, to compare it against them.
+pub fn system_resolvers(include_stub: bool) -> Vec<Resolver> {
+	let addresses = expand_resolved_stub(system_resolver_addresses(), resolved_upstreams, include_stub);
+	let mut resolvers: Vec<Resolver> = Vec::new();
+	for address in addresses {
+		let mut resolver = match parse_resolver(&address) {
+			Ok(r) => r,
+			// Scoped link-local addresses (fe80::1%en0) cannot be dialed portably
+			Err(_) => continue,
+		};
+		if resolvers.iter().any(|r| r.addr == resolver.addr) {
+			continue;
+		}
+		resolver.is_system = true;
+		// System resolvers are always UDP
+		resolver.transport = DnsTransport::Udp;
+		// Recompute class now that is_system is set
+		resolver.class = crate::transport::resolver_class(&resolver);
+		resolvers.push(resolver);
+	}
+	resolvers
+}
+
+/// Configured DNS server addresses from the platform's source, in order.
+fn system_resolver_addresses() -> Vec<String> {
+	#[cfg(windows)]
+	{
+		let output = command_output("powershell", &["-NoProfile", "-NonInteractive", "-Command", WINDOWS_DNS_COMMAND]);
+		return output.map(|text| parse_address_lines(&text)).unwrap_or_default();
+	}
+	#[cfg(target_os = "macos")]
+	{
+		let scoped = command_output("scutil", &["--dns"])
+			.map(|text| parse_scutil_dns(&text))
+			.unwrap_or_default();
+		if !scoped.is_empty() {
+			return scoped;
+		}
+	}
+	#[cfg(not(windows))]
+	{
+		std::fs::read_to_string("/etc/resolv.conf")
+			.map(|text| parse_resolv_conf(&text))
+			.unwrap_or_default()
+	}
+}
+
+/// Addresses systemd-resolved listens on as a local stub resolver
+const RESOLVED_STUB_ADDRESSES: [&str; 2] = ["127.0.0.53", "127.0.0.54"];
+
+/// Replace systemd-resolved stub addresses with its upstream servers.
+///
+/// `upstreams` is only called when a stub is present. If it finds nothing
+/// (resolved not running, no links configured) the stub is kept, since it
+/// is still the resolver the system actually uses.
+fn expand_resolved_stub(
+	addresses: Vec<String>,
+	upstreams: impl FnOnce() -> Vec<String>,
+	include_stub: bool,
+) -> Vec<String> {
+	let is_stub = |a: &String| RESOLVED_STUB_ADDRESSES.contains(&a.as_str());
+	if !addresses.iter().any(is_stub) {
+		return addresses;
+	}
+	let upstream = upstreams();
+	if upstream.is_empty() {
+		return addresses;
+	}
+	let mut expanded: Vec<String> = addresses.into_iter()
+		.filter(|a| include_stub || !is_stub(a))
+		.collect();
+	for address in upstream {
+		if !expanded.contains(&address) {
+			expanded.push(address);
+		}
+	}
+	expanded
+}
+
+/// Upstream servers behind systemd-resolved (Linux only).
+///
+/// Asks `resolvectl status` (global and per-link servers), falling back to
+/// /run/systemd/resolve/resolv.conf, which resolved keeps in sync with its
+/// upstreams.
+fn resolved_upstreams() -> Vec<String> {
+	#[cfg(target_os = "linux")]
+	{
+		let servers = command_output("resolvectl", &["status", "--no-pager"])
+			.map(|text| parse_resolvectl_status(&text))
+			.unwrap_or_default();
+		if !servers.is_empty() {
+			return servers;
+		}
+		std::fs::read_to_string("/run/systemd/resolve/resolv.conf")
+			.map(|text| parse_resolv_conf(&text))
+			.unwrap_or_default()
+	}
+	#[cfg(not(target_os = "linux"))]
+	{
+		Vec::new()
+	}
+}
+
+/// Run a command and return its stdout when it exits successfully.
+#[cfg(any(windows, target_os = "macos", target_os = "linux"))]
+pub(crate) fn command_output(program: &str, args: &[&str]) -> Option<String> {
+	let output = std::process::Command::new(program).args(args).output().ok()?;
+	if !output.status.success() {
+		return None;
+	}
+	Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+//============================================
+/// `nameserver` addresses from resolv.conf text.
+#[cfg(any(not(windows), test))]
+fn parse_resolv_conf(text: &str) -> Vec<String> {
+	text.lines()
+		.filter_map(|line| {
+			let mut parts = line.split_whitespace();
+			(parts.next() == Some("nameserver")).then(|| parts.next()).flatten()
+		})
+		.map(str::to_string)
+		.collect()
+}
+
+/// `nameserver[N] : ADDRESS` entries from `scutil --dns`, deduplicated.
+///
+/// scutil lists every resolver configuration, including per-domain ones
+/// (VPN split DNS, mDNS); all of their servers are system resolvers.
+#[cfg(any(target_os = "macos", test))]
+fn parse_scutil_dns(text: &str) -> Vec<String> {
+	let mut addresses: Vec<String> = Vec::new();
+	for line in text.lines() {
 		let trimmed = line.trim();
-		if !trimmed.starts_with("nameserver") {
+		if !trimmed.starts_with("nameserver[") {
 			continue;
 		}
-		// Extract the address after "nameserver"
-		let parts: Vec<&str> = trimmed.split_whitespace().collect();
-		if parts.len() >= 2 {
-			if let Ok(mut resolver) = parse_resolver(parts[1]) {
-				resolver.is_system = true;
-				// System resolvers are always UDP
-				resolver.transport = DnsTransport::Udp;
-				// Recompute class now that is_system is set
-				resolver.class = crate::transport::resolver_class(&resolver);
-				resolvers.push(resolver);
+		if let Some((_, address)) = trimmed.split_once(':') {
+			let address = address.trim().to_string();
+			if !address.is_empty() && !addresses.contains(&address) {
+				addresses.push(address);
 			}
 		}
 	}
-	resolvers
+	addresses
+}
+
+/// `DNS Servers:` entries from `resolvectl status`, deduplicated.
+///
+/// A server list may wrap onto indented continuation lines. DoT servers
+/// carry a `#hostname` suffix, which is dropped; fallback servers are not
+/// in use and are skipped.
+#[cfg(any(target_os = "linux", test))]
+fn parse_resolvectl_status(text: &str) -> Vec<String> {
+	let mut addresses: Vec<String> = Vec::new();
+	let mut in_servers = false;
+	for line in text.lines() {
+		let values = match line.split_once(": ") {
+			Some((key, values)) => {
+				in_servers = key.trim() == "DNS Servers";
+				values
+			}
+			// Continuation lines have no key; a blank line ends a section
+			None if line.trim().is_empty() => {
+				in_servers = false;
+				continue;
+			}
+			None => line,
+		};
+		if !in_servers {
+			continue;
+		}
+		for server in values.split_whitespace() {
+			let address = server.split('#').next().unwrap_or(server).to_string();
+			if !addresses.contains(&address) {
+				addresses.push(address);
+			}
+		}
+	}
+	addresses
+}
+
+/// One address per line (PowerShell output), skipping blanks and the
+/// fec0:0:0:ffff::N placeholders Windows lists for unconfigured IPv6.
+#[cfg(any(windows, test))]
+fn parse_address_lines(text: &str) -> Vec<String> {
+	let mut addresses: Vec<String> = Vec::new();
+	for line in text.lines().map(str::trim) {
+		if line.is_empty() || line.starts_with("fec0:0:0:ffff::") || addresses.iter().any(|a| a == line) {
+			continue;
+		}
+		addresses.push(line.to_string());
+	}
+	addresses
+}
+
+//============================================
+/// The default gateway as a resolver candidate (`--gateway-resolver`).
+///
+/// Home routers run a DNS forwarder on their LAN address, and DHCP usually
+/// hands that address out, so it is what most clients really query. The
+/// gateway is read from the IPv4 routing table: /proc/net/route on Linux,
+/// `route -n get default` on macOS, Get-NetRoute on Windows. Returns None
+/// when no default route is found.
+pub fn gateway_resolver() -> Option<Resolver> {
+	let gateway = default_gateway()?;
+	let mut resolver = Resolver::new(SocketAddr::new(IpAddr::V4(gateway), 53), DnsTransport::Udp);
+	resolver.label = "Gateway".to_string();
+	Some(resolver)
+}
+
+/// The IPv4 default gateway from the platform's routing table.
+fn default_gateway() -> Option<Ipv4Addr> {
+	#[cfg(target_os = "linux")]
+	{
+		parse_proc_net_route(&std::fs::read_to_string("/proc/net/route").ok()?)
+	}
+	#[cfg(target_os = "macos")]
+	{
+		parse_route_get(&command_output("route", &["-n", "get", "default"])?)
+	}
+	#[cfg(windows)]
+	{
+		let command = "Get-NetRoute -DestinationPrefix 0.0.0.0/0 | Sort-Object RouteMetric | Select-Object -ExpandProperty NextHop";
+		let output = command_output("powershell", &["-NoProfile", "-NonInteractive", "-Command", command])?;
+		parse_address_lines(&output).iter().find_map(|a| a.parse::<Ipv4Addr>().ok())
+	}
+	#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+	{
+		None
+	}
+}
+
+/// Lowest-metric default route gateway from /proc/net/route.
+///
+/// Addresses are little-endian hex (`0101A8C0` is 192.168.1.1); a route
+/// uses a gateway when its flags include RTF_GATEWAY (0x2).
+#[cfg(any(target_os = "linux", test))]
+fn parse_proc_net_route(text: &str) -> Option<Ipv4Addr> {
+	const RTF_GATEWAY: u32 = 0x2;
+	let mut best: Option<(u32, Ipv4Addr)> = None;
+	for line in text.lines().skip(1) {
+		let fields: Vec<&str> = line.split_whitespace().collect();
+		if fields.len() < 7 || fields[1] != "00000000" {
+			continue;
+		}
+		let (Ok(gateway), Ok(flags), Ok(metric)) = (
+			u32::from_str_radix(fields[2], 16),
+			u32::from_str_radix(fields[3], 16),
+			fields[6].parse::<u32>(),
+		) else { continue };
+		if gateway == 0 || flags & RTF_GATEWAY == 0 {
+			continue;
+		}
+		if best.is_none_or(|(m, _)| metric < m) {
+			best = Some((metric, Ipv4Addr::from(gateway.to_le_bytes())));
+		}
+	}
+	best.map(|(_, ip)| ip)
+}
+
+/// The `gateway:` line of `route -n get default` (macOS).
+#[cfg(any(target_os = "macos", test))]
+fn parse_route_get(text: &str) -> Option<Ipv4Addr> {
+	text.lines()
+		.filter_map(|line| line.trim().strip_prefix("gateway:"))
+		.find_map(|value| value.trim().parse().ok())
 }
 
 /// Try to find a resolver file by name in resolvers/ dir, CWD, or exe dir.
@@ -309,6 +883,55 @@ pub fn default_dot_resolvers() -> Vec<Resolver> {
 	find_resolver_file("resolvers_dot.txt").unwrap_or_default()
 }
 
+//============================================
+/// Label with the "-v6" marker removed, so a provider's IPv4 and IPv6
+/// entries share one key ("Cloudflare-v6-secondary" -> "Cloudflare-secondary").
+pub fn dual_stack_key(label: &str) -> String {
+	label.split('-')
+		.filter(|part| !part.eq_ignore_ascii_case("v6"))
+		.collect::<Vec<_>>()
+		.join("-")
+}
+
+/// Provider name: the label up to the first dash ("Cloudflare-Malware" -> "Cloudflare").
+pub fn provider_name(label: &str) -> &str {
+	label.split('-').next().unwrap_or(label)
+}
+
+/// Add the other-family partner of each UDP resolver from `builtin`
+/// (--dual-stack).
+///
+/// A resolver is matched to a built-in entry by IP address; unlabeled
+/// resolvers (label is just the address) take the built-in label so the
+/// pair can be found again in the report. Returns how many were added.
+pub fn add_dual_stack_partners(resolvers: &mut Vec<Resolver>, builtin: &[Resolver]) -> usize {
+	let mut partners = Vec::new();
+	for r in resolvers.iter_mut().filter(|r| r.transport == DnsTransport::Udp) {
+		let known = match builtin.iter().find(|b| b.addr.ip() == r.addr.ip()) {
+			Some(b) => b,
+			None => continue,
+		};
+		if r.label == r.addr.ip().to_string() || r.label == r.addr.to_string() {
+			r.label = known.label.clone();
+		}
+		let key = dual_stack_key(&known.label);
+		let partner = builtin.iter().find(|b| {
+			b.addr.is_ipv6() != known.addr.is_ipv6() && dual_stack_key(&b.label) == key
+		});
+		if let Some(p) = partner {
+			partners.push(p.clone());
+		}
+	}
+	let mut added = 0;
+	for p in partners {
+		if !resolvers.iter().any(|r| r.addr.ip() == p.addr.ip()) {
+			resolvers.push(p);
+			added += 1;
+		}
+	}
+	added
+}
+
 /// Return the global scan list from scan_global.txt (~63K worldwide public resolvers).
 pub fn scan_global_resolvers() -> Vec<Resolver> {
 	find_resolver_file("scan_global.txt").unwrap_or_default()
@@ -333,7 +956,7 @@ pub async fn download_global_list() -> Result<String> {
 		.map_err(|e| anyhow!("Failed to write {}: {}", path, e))?;
 
 	let line_count = body.lines().filter(|l| !l.trim().is_empty()).count();
-	println!("  Downloaded {} nameservers to {}", line_count, path);
+	crate::info!("  Downloaded {} nameservers to {}", line_count, path);
 	Ok(path)
 }
 
@@ -342,7 +965,7 @@ pub async fn download_global_list() -> Result<String> {
 /// Filters out resolvers with reliability < 0.5.
 pub async fn download_exhaustive_csv() -> Result<Vec<Resolver>> {
 	let url = "https://public-dns.info/nameservers.csv";
-	println!("Downloading nameserver CSV from {}...", url);
+	crate::info!("Downloading nameserver CSV from {}...", url);
 	let response = reqwest::get(url).await
 		.map_err(|e| anyhow!("Failed to download nameservers CSV: {}", e))?;
 	let body = response.text().await
@@ -416,21 +1039,169 @@ pub async fn download_exhaustive_csv() -> Result<Vec<Resolver>> {
 	let ipv6_count = resolvers.iter().filter(|r| r.addr.is_ipv6()).count();
 	let ipv4_pct = if resolvers.is_empty() { 0.0 } else { 100.0 * ipv4_count as f64 / resolvers.len() as f64 };
 	let ipv6_pct = if resolvers.is_empty() { 0.0 } else { 100.0 * ipv6_count as f64 / resolvers.len() as f64 };
-	println!(
+	crate::info!(
 		"  Parsed {} resolvers from CSV (filtered reliability >= 0.5)",
 		resolvers.len(),
 	);
-	println!(
+	crate::info!(
 		"  IPv4: {} ({:.1}%), IPv6: {} ({:.1}%)",
 		ipv4_count, ipv4_pct, ipv6_count, ipv6_pct,
 	);
 	Ok(resolvers)
 }
 
+//============================================
+/// Entries removed from the combined resolver list by `clean_resolver_list`.
+#[derive(Debug, Default, PartialEq)]
+pub struct ListCleanup {
+	/// Entries whose address, port, and transport were already in the list
+	pub duplicates: Vec<String>,
+	/// Entries that cannot be a resolver, with the reason
+	pub invalid: Vec<(String, &'static str)>,
+}
+
+/// Why an address cannot be a resolver: unspecified, multicast, broadcast,
+/// reserved, or a documentation range (RFC 5737, RFC 3849), or port 0.
+pub fn invalid_target_reason(addr: &SocketAddr) -> Option<&'static str> {
+	if addr.port() == 0 {
+		return Some("port 0");
+	}
+	match addr.ip() {
+		ip if ip.is_unspecified() => Some("unspecified address"),
+		ip if ip.is_multicast() => Some("multicast address"),
+		IpAddr::V4(v4) if v4.is_broadcast() => Some("broadcast address"),
+		IpAddr::V4(v4) if v4.is_documentation() => Some("documentation range"),
+		IpAddr::V4(v4) if v4.octets()[0] >= 240 => Some("reserved range"),
+		IpAddr::V6(v6) if v6.segments()[0] == 0x2001 && v6.segments()[1] == 0x0db8 => Some("documentation range"),
+		_ => None,
+	}
+}
+
+/// Remove invalid targets and repeated resolvers, keeping the first entry
+/// for each `Resolver::key`.
+///
+/// Results and sidelining are keyed by address, port, and transport, so
+/// the same IP on another port or transport is a separate resolver; only
+/// an exact repeat would merge into the first one's results.
+pub fn clean_resolver_list(resolvers: &mut Vec<Resolver>) -> ListCleanup {
+	let mut cleanup = ListCleanup::default();
+	let mut seen = std::collections::HashSet::new();
+	resolvers.retain(|r| {
+		let entry = format!("{} {}", r.transport, r.addr);
+		if let Some(reason) = invalid_target_reason(&r.addr) {
+			cleanup.invalid.push((entry, reason));
+			return false;
+		}
+		if !seen.insert(r.key()) {
+			cleanup.duplicates.push(entry);
+			return false;
+		}
+		true
+	});
+	cleanup
+}
+
+/// Up to five entries, then a count of the rest.
+fn preview_list(entries: &[String]) -> String {
+	let shown = entries.iter().take(5).cloned().collect::<Vec<_>>().join(", ");
+	match entries.len() {
+		n if n > 5 => format!("{}, and {} more", shown, n - 5),
+		_ => shown,
+	}
+}
+
+/// Print what `clean_resolver_list` removed, if anything.
+///
+/// The built-in lists overlap by design, so without user-supplied
+/// resolvers the duplicate line only shows with -v.
+pub fn print_list_cleanup(cleanup: &ListCleanup, user_specified: bool) {
+	if !cleanup.duplicates.is_empty() {
+		let line = format!("Removed {} duplicate resolver(s), one entry per address, port, and transport: {}",
+			cleanup.duplicates.len(), preview_list(&cleanup.duplicates));
+		if user_specified {
+			crate::info!("{}", line);
+		} else {
+			crate::verbose!("{}", line);
+		}
+	}
+	if !cleanup.invalid.is_empty() {
+		let entries: Vec<String> = cleanup.invalid.iter().map(|(e, reason)| format!("{} ({})", e, reason)).collect();
+		crate::info!("Removed {} invalid resolver address(es): {}", entries.len(), preview_list(&entries));
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use super::*;
 
+	#[test]
+	fn test_parse_system_resolver_sources() {
+		let resolv = "# generated\nnameserver 192.168.1.1\nsearch lan\nnameserver 2001:db8::53\n";
+		assert_eq!(parse_resolv_conf(resolv), vec!["192.168.1.1", "2001:db8::53"]);
+
+		let scutil = "DNS configuration\n\nresolver #1\n  search domain[0] : lan\n  nameserver[0] : 192.168.1.1\n  nameserver[1] : fe80::1%en0\n\nresolver #2\n  domain   : corp.example\n  nameserver[0] : 10.8.0.1\n  nameserver[1] : 192.168.1.1\n";
+		assert_eq!(parse_scutil_dns(scutil), vec!["192.168.1.1", "fe80::1%en0", "10.8.0.1"]);
+
+		let powershell = "192.168.1.1\r\n1.1.1.1\r\nfec0:0:0:ffff::1\r\n\r\n192.168.1.1\r\n";
+		assert_eq!(parse_address_lines(powershell), vec!["192.168.1.1", "1.1.1.1"]);
+	}
+
+	#[test]
+	fn test_resolved_stub_expands_to_upstreams() {
+		let status = "Global\n         Protocols: +LLMNR -mDNS -DNSOverTLS\n  resolv.conf mode: stub\nCurrent DNS Server: 1.1.1.1#cloudflare-dns.com\n       DNS Servers: 1.1.1.1#cloudflare-dns.com 9.9.9.9\n                    2620:fe::fe\nFallback DNS Servers: 8.8.8.8\n\nLink 2 (eth0)\n    Current Scopes: DNS\n       DNS Servers: 192.168.1.1 9.9.9.9\n        DNS Domain: lan\n";
+		let upstreams = parse_resolvectl_status(status);
+		assert_eq!(upstreams, vec!["1.1.1.1", "9.9.9.9", "2620:fe::fe", "192.168.1.1"]);
+
+		let stub = vec!["127.0.0.53".to_string()];
+		let expanded = expand_resolved_stub(stub.clone(), || upstreams.clone(), false);
+		assert_eq!(expanded, upstreams);
+		let with_stub = expand_resolved_stub(stub.clone(), || upstreams.clone(), true);
+		assert_eq!(with_stub[0], "127.0.0.53");
+		assert_eq!(with_stub.len(), upstreams.len() + 1);
+		// No upstreams found: keep the stub rather than dropping the system resolver
+		assert_eq!(expand_resolved_stub(stub.clone(), Vec::new, false), stub);
+		// Without a stub, upstream discovery is never run
+		let plain = vec!["192.168.1.1".to_string()];
+		assert_eq!(expand_resolved_stub(plain.clone(), || panic!("not called"), false), plain);
+	}
+
+	#[test]
+	fn test_parse_default_gateway() {
+		let route = "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+			wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0\n\
+			eth0\t00000000\t0100000A\t0003\t0\t0\t100\t00000000\t0\t0\t0\n\
+			eth0\t0000000A\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0\n";
+		assert_eq!(parse_proc_net_route(route), Some(Ipv4Addr::new(10, 0, 0, 1)));
+		assert_eq!(parse_proc_net_route("Iface\tDestination\n"), None);
+
+		let route_get = "   route to: default\ndestination: default\n       mask: default\n    gateway: 192.168.1.1\n  interface: en0\n";
+		assert_eq!(parse_route_get(route_get), Some(Ipv4Addr::new(192, 168, 1, 1)));
+	}
+
+	#[test]
+	fn test_dual_stack_key_and_provider() {
+		assert_eq!(dual_stack_key("Cloudflare-v6-secondary"), "Cloudflare-secondary");
+		assert_eq!(dual_stack_key("Google-v6"), "Google");
+		assert_eq!(dual_stack_key("Quad9"), "Quad9");
+		assert_eq!(provider_name("Cloudflare-Malware-v6"), "Cloudflare");
+	}
+
+	#[test]
+	fn test_add_dual_stack_partners() {
+		let labeled = |line: &str| parse_resolver_line(line).unwrap();
+		let builtin = vec![
+			labeled("1.1.1.1  # Cloudflare"),
+			labeled("[2606:4700:4700::1111]:53  # Cloudflare-v6"),
+			labeled("8.8.8.8  # Google"),
+		];
+		let mut resolvers = vec![parse_resolver("1.1.1.1").unwrap(), parse_resolver("8.8.8.8").unwrap()];
+		assert_eq!(add_dual_stack_partners(&mut resolvers, &builtin), 1);
+		assert_eq!(resolvers[0].label, "Cloudflare");
+		assert_eq!(resolvers[2].addr.ip().to_string(), "2606:4700:4700::1111");
+		// Already present partners are not added twice
+		assert_eq!(add_dual_stack_partners(&mut resolvers, &builtin), 0);
+	}
+
 	#[test]
 	fn test_ipv4_no_port() {
 		let r = parse_resolver("1.1.1.1").unwrap();
@@ -463,6 +1234,74 @@ mod tests {
 		assert!(r.is_err());
 	}
 
+	#[test]
+	fn test_split_hostname() {
+		assert_eq!(split_hostname("dns.quad9.net", 53), Some(("dns.quad9.net", 53)));
+		assert_eq!(split_hostname("dns.quad9.net:8853", 853), Some(("dns.quad9.net", 8853)));
+		// Mistyped IPs and single labels are not looked up
+		assert_eq!(split_hostname("1.1.1.300", 53), None);
+		assert_eq!(split_hostname("not-an-ip", 53), None);
+		assert_eq!(split_hostname("bad..name.net", 53), None);
+		assert_eq!(split_hostname("dns.quad9.net:port", 53), None);
+	}
+
+	#[test]
+	fn test_query_bootstrap_reads_addresses() {
+		use hickory_proto::op::{Message, MessageType};
+		use hickory_proto::rr::{rdata::A, RData, Record};
+		// Local server that answers every query with one A record
+		let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+		let addr = server.local_addr().unwrap();
+		std::thread::spawn(move || {
+			let mut buf = [0u8; 512];
+			let (len, src) = server.recv_from(&mut buf).unwrap();
+			let mut reply = Message::from_vec(&buf[..len]).unwrap();
+			let name = reply.queries()[0].name().clone();
+			reply.set_message_type(MessageType::Response);
+			reply.add_answer(Record::from_rdata(name, 60, RData::A(A::new(192, 0, 2, 7))));
+			server.send_to(&reply.to_vec().unwrap(), src).unwrap();
+		});
+		let ips = query_bootstrap(addr, "dns.example.net", BootstrapFamily::PreferIpv4).unwrap();
+		assert_eq!(ips, vec!["192.0.2.7".parse::<IpAddr>().unwrap()]);
+	}
+
+	#[test]
+	fn test_bootstrap_family_from_filter() {
+		let ips: Vec<IpAddr> = vec!["2001:db8::1".parse().unwrap(), "192.0.2.1".parse().unwrap()];
+		let v4_only: Vec<IpAddr> = vec!["192.0.2.1".parse().unwrap()];
+		let family = BootstrapFamily::from_filter(false, false, false);
+		assert_eq!(family.pick(&ips), Some(ips[1]));
+		let family = BootstrapFamily::from_filter(false, false, true);
+		assert_eq!(family.query_types(), &[QueryType::AAAA, QueryType::A]);
+		assert_eq!(family.pick(&ips), Some(ips[0]));
+		assert_eq!(family.pick(&v4_only), Some(v4_only[0]));
+		let family = BootstrapFamily::from_filter(false, true, false);
+		assert_eq!(family.query_types(), &[QueryType::AAAA]);
+		assert_eq!(family.pick(&v4_only), None);
+		assert_eq!(BootstrapFamily::from_filter(true, false, false).pick(&ips), Some(ips[1]));
+	}
+
+	#[test]
+	fn test_query_bootstrap_stray_replies_cannot_extend_deadline() {
+		let server = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+		let addr = server.local_addr().unwrap();
+		std::thread::spawn(move || {
+			let mut buf = [0u8; 512];
+			let (len, src) = server.recv_from(&mut buf).unwrap();
+			// Keep answering with the wrong transaction ID, well past the timeout
+			buf[0] ^= 0xff;
+			buf[2] |= 0x80;
+			for _ in 0..40 {
+				let _ = server.send_to(&buf[..len], src);
+				std::thread::sleep(Duration::from_millis(100));
+			}
+		});
+		let start = Instant::now();
+		let err = query_bootstrap(addr, "dns.example", BootstrapFamily::Ipv4Only).unwrap_err();
+		assert!(err.to_string().contains("did not answer"), "{}", err);
+		assert!(start.elapsed() < BOOTSTRAP_TIMEOUT + Duration::from_millis(500));
+	}
+
 	#[test]
 	fn test_defaults_non_empty() {
 		let defaults = default_resolvers();
@@ -516,9 +1355,162 @@ mod tests {
 		assert!(matches!(r.transport, DnsTransport::Doh { .. }));
 	}
 
+	#[test]
+	fn test_doh_options_parsed() {
+		let r = parse_resolver_line(
+			"https://1.1.1.1/dns-query method=GET header=X-Api-Key:a:b accept=application/dns-message  # Corp",
+		).unwrap();
+		assert_eq!(r.label, "Corp");
+		match r.transport {
+			DnsTransport::Doh { url, options } => {
+				assert_eq!(url, "https://1.1.1.1/dns-query");
+				assert_eq!(options.method, DohMethod::Get);
+				assert_eq!(options.headers, vec![
+					("X-Api-Key".to_string(), "a:b".to_string()),
+					("Accept".to_string(), "application/dns-message".to_string()),
+				]);
+			}
+			other => panic!("expected DoH, got {}", other),
+		}
+	}
+
+	#[test]
+	fn test_doh_options_default_post() {
+		let r = parse_resolver("https://1.1.1.1/dns-query").unwrap();
+		match r.transport {
+			DnsTransport::Doh { options, .. } => assert_eq!(options, DohOptions::default()),
+			other => panic!("expected DoH, got {}", other),
+		}
+	}
+
+	#[test]
+	fn test_doh_options_invalid() {
+		assert!(parse_resolver("https://1.1.1.1/dns-query method=PUT").is_err());
+		assert!(parse_resolver("https://1.1.1.1/dns-query header=NoColon").is_err());
+		assert!(parse_resolver("https://1.1.1.1/dns-query bogus=1").is_err());
+		// Invalid HTTP header names and values fail at parse time, not as timeouts
+		assert!(parse_resolver("https://1.1.1.1/dns-query header=X(Bad):1").is_err());
+		assert!(parse_resolver("https://1.1.1.1/dns-query header=X-Key:a\u{7f}b").is_err());
+		// Responses are parsed as wire format only
+		assert!(parse_resolver("https://1.1.1.1/dns-query accept=application/dns-json").is_err());
+		assert!(parse_resolver("https://1.1.1.1/dns-query header=accept:application/dns-json").is_err());
+	}
+
+	#[test]
+	fn test_stamp_plain_resolver() {
+		let r = parse_resolver("sdns://AAcAAAAAAAAABzguOC44Ljg").unwrap();
+		assert_eq!(r.addr.ip().to_string(), "8.8.8.8");
+		assert_eq!(r.addr.port(), 53);
+		assert!(matches!(r.transport, DnsTransport::Udp));
+	}
+
+	#[test]
+	fn test_stamp_doh_resolver_with_label() {
+		let r = parse_resolver_line(
+			"sdns://AgcAAAAAAAAABzEuMS4xLjEAEmNsb3VkZmxhcmUtZG5zLmNvbQovZG5zLXF1ZXJ5  # Cloudflare",
+		).unwrap();
+		assert_eq!(r.label, "Cloudflare");
+		assert_eq!(r.addr.ip().to_string(), "1.1.1.1");
+		assert_eq!(r.addr.port(), 443);
+		match r.transport {
+			DnsTransport::Doh { url, .. } => assert_eq!(url, "https://cloudflare-dns.com/dns-query"),
+			other => panic!("expected DoH, got {}", other),
+		}
+	}
+
+	#[test]
+	fn test_stamp_dot_resolver() {
+		let r = parse_resolver("sdns://AwcAAAAAAAAABzkuOS45LjkADWRucy5xdWFkOS5uZXQ").unwrap();
+		assert_eq!(r.addr.port(), 853);
+		assert_eq!(r.label, "dns.quad9.net");
+		assert!(matches!(r.transport, DnsTransport::Dot { .. }));
+	}
+
+	/// Encode a DoH (0x02) or DoT (0x03) stamp with no hashes.
+	fn encode_stamp(proto: u8, addr: &str, hostname: &str, path: Option<&str>) -> String {
+		use base64::Engine;
+		let mut bytes = vec![proto, 0, 0, 0, 0, 0, 0, 0, 0];
+		for field in [Some(addr), None, Some(hostname), path] {
+			let field = field.unwrap_or("");
+			bytes.push(field.len() as u8);
+			bytes.extend_from_slice(field.as_bytes());
+		}
+		if path.is_none() {
+			bytes.pop();
+		}
+		format!("sdns://{}", base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(bytes))
+	}
+
+	#[test]
+	fn test_stamp_hostname_port_used_everywhere() {
+		// Port only on the hostname: the address, URL, and SNI all take 8443
+		let r = parse_resolver(&encode_stamp(2, "192.0.2.1", "doh.example:8443", Some("/dns-query"))).unwrap();
+		assert_eq!(r.addr, "192.0.2.1:8443".parse().unwrap());
+		assert_eq!(r.label, "doh.example");
+		match &r.transport {
+			DnsTransport::Doh { url, .. } => assert_eq!(url, "https://doh.example:8443/dns-query"),
+			other => panic!("expected DoH, got {}", other),
+		}
+		// Empty addr: the address comes from the hostname, port included
+		let r = parse_resolver(&encode_stamp(2, "", "192.0.2.1:8443", Some("/dns-query"))).unwrap();
+		assert_eq!(r.addr, "192.0.2.1:8443".parse().unwrap());
+		assert!(matches!(&r.transport, DnsTransport::Doh { url, .. } if url == "https://192.0.2.1:8443/dns-query"));
+		// An explicit addr port wins and the URL follows it
+		let r = parse_resolver(&encode_stamp(2, "192.0.2.1:9443", "doh.example", Some("/q"))).unwrap();
+		assert!(matches!(&r.transport, DnsTransport::Doh { url, .. } if url == "https://doh.example:9443/q"));
+
+		let r = parse_resolver(&encode_stamp(3, "192.0.2.1", "dot.example:8443", None)).unwrap();
+		assert_eq!(r.addr.port(), 8443);
+		assert_eq!(r.label, "dot.example");
+		assert!(matches!(&r.transport, DnsTransport::Dot { hostname } if hostname == "dot.example"));
+
+		assert!(parse_resolver(&encode_stamp(3, "192.0.2.1", "dot.example:x", None)).is_err());
+	}
+
 	#[test]
 	fn test_udp_transport_default() {
 		let r = parse_resolver("8.8.8.8").unwrap();
 		assert!(matches!(r.transport, DnsTransport::Udp));
 	}
+
+	#[test]
+	fn test_clean_resolver_list() {
+		let mut list: Vec<Resolver> = ["1.1.1.1", "tls://cloudflare-dns.com/1.1.1.1", "0.0.0.0", "224.0.0.251",
+			"192.0.2.53", "[2001:db8::1]:53", "1.1.1.1:5353", "9.9.9.9", "1.1.1.1:53", "tls://1.1.1.1", "127.0.0.1:5399"]
+			.iter().map(|a| parse_resolver(a).unwrap()).collect();
+		let cleanup = clean_resolver_list(&mut list);
+		// The same IP on another port or transport is a separate resolver
+		let kept: Vec<String> = list.iter().map(|r| r.key()).collect();
+		assert_eq!(kept, vec!["1.1.1.1:53", "tls://1.1.1.1:853", "1.1.1.1:5353", "9.9.9.9:53", "127.0.0.1:5399"]);
+		assert_eq!(cleanup.duplicates, vec!["UDP 1.1.1.1:53", "DoT 1.1.1.1:853"]);
+		let reasons: Vec<&str> = cleanup.invalid.iter().map(|(_, r)| *r).collect();
+		assert_eq!(reasons, vec!["unspecified address", "multicast address", "documentation range", "documentation range"]);
+	}
+
+	#[test]
+	fn test_parse_resolver_line_provider_tag() {
+		let r = parse_resolver_line("1.1.1.1  # Cloudflare | provider=cloudflare").unwrap();
+		assert_eq!(r.label, "Cloudflare");
+		assert_eq!(r.provider.as_deref(), Some("cloudflare"));
+		assert_eq!(r.provider_key(), "cloudflare");
+		let r = parse_resolver_line("tls://dns.google/8.8.8.8  # Google-DoT").unwrap();
+		assert_eq!(r.provider, None);
+		assert_eq!(r.provider_key(), "Google");
+		assert!(parse_resolver_line("9.9.9.9  # Quad9 | vendor=quad9").is_err());
+	}
+
+	#[test]
+	fn test_parse_resolver_line_pin_tags() {
+		let pin = "jQJTbIh0grw0/1TkHSumWb+Fs0Ggogr621gT3PvPKG0=";
+		let line = format!("tls://dns.google/8.8.8.8  # Google-DoT | spki={} spki={} tls_name=dns.google", pin, pin);
+		let r = parse_resolver_line(&line).unwrap();
+		assert_eq!(r.spki_pins, vec![pin, pin]);
+		assert_eq!(r.tls_name.as_deref(), Some("dns.google"));
+		// Pins only mean something on an encrypted transport
+		assert!(parse_resolver_line(&format!("8.8.8.8  # Google | spki={}", pin)).is_err());
+		assert!(parse_resolver_line("8.8.8.8  # Google | tls_name=dns.google").is_err());
+		// Not base64, and base64 of the wrong length
+		assert!(parse_resolver_line("tls://dns.google/8.8.8.8  # G | spki=not*base64").is_err());
+		assert!(parse_resolver_line("tls://dns.google/8.8.8.8  # G | spki=AAAA").is_err());
+	}
 }
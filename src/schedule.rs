@@ -0,0 +1,144 @@
+use anyhow::{anyhow, bail, Result};
+
+/// Minutes searched for the next matching time (a leap year and a day)
+const MAX_SEARCH_MINUTES: u64 = 367 * 24 * 60;
+
+/// A five-field cron expression (minute hour day-of-month month day-of-week),
+/// evaluated in UTC like every other timestamp the tool prints.
+///
+/// Each field takes `*`, a number, a range `a-b`, a step `*/n` or `a-b/n`,
+/// or a comma-separated list of those. Day-of-week runs 0-6 from Sunday
+/// (7 is also Sunday). As in cron, when both day fields are restricted a
+/// time matches if either one does.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+	minutes: Vec<bool>,
+	hours: Vec<bool>,
+	days: Vec<bool>,
+	months: Vec<bool>,
+	weekdays: Vec<bool>,
+	days_restricted: bool,
+	weekdays_restricted: bool,
+}
+
+//============================================
+/// Parse one field into a table of allowed values indexed from 0 up to `max`.
+fn parse_field(text: &str, name: &str, min: u32, max: u32) -> Result<Vec<bool>> {
+	let mut allowed = vec![false; max as usize + 1];
+	for part in text.split(',') {
+		let (range, step) = match part.split_once('/') {
+			Some((range, step)) => {
+				let step: u32 = step.parse().map_err(|_| anyhow!("bad step '{}' in {} field", step, name))?;
+				if step == 0 {
+					bail!("step must be at least 1 in {} field", name);
+				}
+				(range, step)
+			}
+			None => (part, 1),
+		};
+		let number = |s: &str| s.parse::<u32>().map_err(|_| anyhow!("bad value '{}' in {} field", s, name));
+		let (start, end) = match range {
+			"*" => (min, max),
+			_ => match range.split_once('-') {
+				Some((a, b)) => (number(a)?, number(b)?),
+				// "5/15" means 5 through the end in steps of 15
+				None if step > 1 => (number(range)?, max),
+				None => (number(range)?, number(range)?),
+			},
+		};
+		if start < min || end > max || start > end {
+			bail!("{} field '{}' is outside {}-{}", name, part, min, max);
+		}
+		for value in (start..=end).step_by(step as usize) {
+			allowed[value as usize] = true;
+		}
+	}
+	Ok(allowed)
+}
+
+//============================================
+impl CronSchedule {
+	/// Parse an expression such as "0 */6 * * *".
+	pub fn parse(expr: &str) -> Result<Self> {
+		let fields: Vec<&str> = expr.split_whitespace().collect();
+		let [minute, hour, day, month, weekday] = fields[..] else {
+			bail!("schedule '{}' needs 5 fields: minute hour day-of-month month day-of-week", expr);
+		};
+		let mut weekdays = parse_field(weekday, "day-of-week", 0, 7)?;
+		// 7 is Sunday too
+		if weekdays[7] {
+			weekdays[0] = true;
+		}
+		weekdays.truncate(7);
+		Ok(CronSchedule {
+			minutes: parse_field(minute, "minute", 0, 59)?,
+			hours: parse_field(hour, "hour", 0, 23)?,
+			days: parse_field(day, "day-of-month", 1, 31)?,
+			months: parse_field(month, "month", 1, 12)?,
+			weekdays,
+			days_restricted: day != "*",
+			weekdays_restricted: weekday != "*",
+		})
+	}
+
+	/// Whether the minute starting at `secs` (Unix seconds, UTC) matches.
+	pub fn matches(&self, secs: u64) -> bool {
+		let (_, month, day) = crate::telemetry::days_to_ymd(secs / 86400);
+		let minute = (secs % 3600) / 60;
+		let hour = (secs % 86400) / 3600;
+		// 1970-01-01 was a Thursday
+		let weekday = (secs / 86400 + 4) % 7;
+		let day_ok = self.days[day as usize];
+		let weekday_ok = self.weekdays[weekday as usize];
+		let day_match = match (self.days_restricted, self.weekdays_restricted) {
+			(true, true) => day_ok || weekday_ok,
+			_ => day_ok && weekday_ok,
+		};
+		self.minutes[minute as usize] && self.hours[hour as usize] && self.months[month as usize] && day_match
+	}
+
+	/// Start of the first matching minute strictly after `secs`; None if
+	/// nothing matches within a year (e.g. "0 0 31 2 *").
+	pub fn next_after(&self, secs: u64) -> Option<u64> {
+		let first = (secs / 60 + 1) * 60;
+		(0..MAX_SEARCH_MINUTES)
+			.map(|i| first + i * 60)
+			.find(|&t| self.matches(t))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	/// 2024-03-15 (a Friday) 10:17:30 UTC
+	const FRI_10_17: u64 = 1_710_497_850;
+
+	#[test]
+	fn test_every_six_hours() {
+		let s = CronSchedule::parse("0 */6 * * *").unwrap();
+		// Next is 12:00 the same day
+		assert_eq!(s.next_after(FRI_10_17), Some(1_710_504_000));
+		assert!(s.matches(1_710_504_000));
+		assert!(!s.matches(1_710_504_060));
+	}
+
+	#[test]
+	fn test_weekday_lists_and_day_or_rule() {
+		// Weekdays at 09:30: the next one after Friday 10:17 is Monday
+		let s = CronSchedule::parse("30 9 * * 1-5").unwrap();
+		assert_eq!(s.next_after(FRI_10_17), Some(1_710_754_200));
+		// Either the 1st of the month or a Sunday (7 = Sunday)
+		let s = CronSchedule::parse("0 0 1 * 7").unwrap();
+		assert_eq!(s.next_after(FRI_10_17), Some(1_710_633_600));
+	}
+
+	#[test]
+	fn test_parse_rejects_bad_expressions() {
+		assert!(CronSchedule::parse("0 */6 * *").is_err());
+		assert!(CronSchedule::parse("60 * * * *").is_err());
+		assert!(CronSchedule::parse("*/0 * * * *").is_err());
+		assert!(CronSchedule::parse("0 5-2 * * *").is_err());
+		assert!(CronSchedule::parse("0 0 31 2 *").unwrap().next_after(FRI_10_17).is_none());
+	}
+}
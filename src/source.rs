@@ -0,0 +1,137 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::RwLock;
+
+use anyhow::{bail, Result};
+use tokio::net::{TcpSocket, TcpStream, UdpSocket};
+
+/// Where outgoing queries leave the host (--source-ip, --interface), so a
+/// multihomed host can benchmark resolvers over one uplink.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceBinding {
+	/// Local address every socket binds to
+	pub ip: Option<IpAddr>,
+	/// Network interface every socket is bound to (SO_BINDTODEVICE, Linux only)
+	pub interface: Option<String>,
+}
+
+impl SourceBinding {
+	/// No binding: the OS picks the address and route.
+	pub const NONE: SourceBinding = SourceBinding { ip: None, interface: None };
+
+	/// Local address for a socket of the given family: the source IP, or
+	/// the unspecified address when none is set. Fails when the source IP
+	/// is of the other family.
+	fn local_addr(&self, ipv6: bool) -> io::Result<SocketAddr> {
+		match self.ip {
+			Some(ip) if ip.is_ipv6() != ipv6 => Err(io::Error::new(io::ErrorKind::AddrNotAvailable,
+				format!("source address {} cannot reach an IPv{} address", ip, if ipv6 { 6 } else { 4 }))),
+			Some(ip) => Ok(SocketAddr::new(ip, 0)),
+			None if ipv6 => Ok(SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), 0)),
+			None => Ok(SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0)),
+		}
+	}
+}
+
+static SOURCE: RwLock<SourceBinding> = RwLock::new(SourceBinding::NONE);
+
+//============================================
+/// Set the process-wide source binding.
+pub fn set_source(binding: SourceBinding) {
+	if let Ok(mut guard) = SOURCE.write() {
+		*guard = binding;
+	}
+}
+
+/// Current process-wide source binding.
+pub fn source() -> SourceBinding {
+	SOURCE.read().map(|g| g.clone()).unwrap_or_default()
+}
+
+/// Check that the binding can be used before any query is sent: the
+/// address must be local and the interface must exist and be usable.
+pub async fn check(binding: &SourceBinding) -> Result<()> {
+	#[cfg(not(target_os = "linux"))]
+	if binding.interface.is_some() {
+		bail!("--interface is only supported on Linux; use --source-ip with the interface's address");
+	}
+	let ipv6 = binding.ip.is_some_and(|ip| ip.is_ipv6());
+	let socket = match UdpSocket::bind(binding.local_addr(ipv6)?).await {
+		Ok(socket) => socket,
+		Err(e) => bail!("--source-ip {}: {}", binding.ip.map(|ip| ip.to_string()).unwrap_or_default(), e),
+	};
+	#[cfg(target_os = "linux")]
+	if let Some(name) = &binding.interface {
+		if let Err(e) = socket.bind_device(Some(name.as_bytes())) {
+			bail!("--interface {}: {} (binding to an interface may need root or CAP_NET_RAW)", name, e);
+		}
+	}
+	drop(socket);
+	Ok(())
+}
+
+//============================================
+/// UDP socket for queries to an address of the given family, bound per the
+/// source binding.
+pub async fn bind_udp(ipv6: bool) -> io::Result<UdpSocket> {
+	let binding = source();
+	let socket = UdpSocket::bind(binding.local_addr(ipv6)?).await?;
+	#[cfg(target_os = "linux")]
+	if let Some(name) = &binding.interface {
+		socket.bind_device(Some(name.as_bytes()))?;
+	}
+	Ok(socket)
+}
+
+/// TCP connection to `remote`, from the source address and interface when set.
+pub async fn connect_tcp(remote: SocketAddr) -> io::Result<TcpStream> {
+	let binding = source();
+	if binding == SourceBinding::NONE {
+		return TcpStream::connect(remote).await;
+	}
+	let socket = if remote.is_ipv6() { TcpSocket::new_v6()? } else { TcpSocket::new_v4()? };
+	#[cfg(target_os = "linux")]
+	if let Some(name) = &binding.interface {
+		socket.bind_device(Some(name.as_bytes()))?;
+	}
+	if binding.ip.is_some() {
+		socket.bind(binding.local_addr(remote.is_ipv6())?)?;
+	}
+	socket.connect(remote).await
+}
+
+/// Apply the source binding to a DoH client.
+pub fn apply_to_client(builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+	let binding = source();
+	let builder = builder.local_address(binding.ip);
+	#[cfg(target_os = "linux")]
+	if let Some(name) = &binding.interface {
+		return builder.interface(name);
+	}
+	builder
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_local_addr_matches_family() {
+		let none = SourceBinding::NONE;
+		assert_eq!(none.local_addr(false).unwrap(), "0.0.0.0:0".parse().unwrap());
+		assert_eq!(none.local_addr(true).unwrap(), "[::]:0".parse().unwrap());
+		let v4 = SourceBinding { ip: Some("192.0.2.10".parse().unwrap()), interface: None };
+		assert_eq!(v4.local_addr(false).unwrap(), "192.0.2.10:0".parse().unwrap());
+		// An IPv4 source cannot send to an IPv6 resolver
+		assert_eq!(v4.local_addr(true).unwrap_err().kind(), io::ErrorKind::AddrNotAvailable);
+	}
+
+	#[tokio::test]
+	async fn test_check_rejects_foreign_address() {
+		let loopback = SourceBinding { ip: Some("127.0.0.1".parse().unwrap()), interface: None };
+		assert!(check(&loopback).await.is_ok());
+		// TEST-NET-1 is never assigned to a local interface
+		let foreign = SourceBinding { ip: Some("192.0.2.10".parse().unwrap()), interface: None };
+		assert!(check(&foreign).await.is_err());
+	}
+}
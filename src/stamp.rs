@@ -0,0 +1,188 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+
+/// Stamp protocol identifiers (first byte of the decoded stamp).
+const STAMP_PLAIN: u8 = 0x00;
+const STAMP_DNSCRYPT: u8 = 0x01;
+const STAMP_DOH: u8 = 0x02;
+const STAMP_DOT: u8 = 0x03;
+const STAMP_DOQ: u8 = 0x04;
+const STAMP_ODOH: u8 = 0x05;
+
+/// Decoded DNS stamp fields needed to build a Resolver.
+///
+/// Only the transports this tool can benchmark are represented; DNSCrypt,
+/// DoQ, ODoH, and relay stamps are rejected by `decode_stamp`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsStamp {
+	/// Plain DNS (Do53): address with optional port
+	Plain { addr: String },
+	/// DNS over HTTPS: optional address, hostname[:port], and URL path
+	Doh { addr: String, hostname: String, path: String },
+	/// DNS over TLS: optional address and hostname[:port]
+	Dot { addr: String, hostname: String },
+}
+
+/// Cursor over the decoded stamp bytes.
+struct StampReader<'a> {
+	bytes: &'a [u8],
+	pos: usize,
+}
+
+//============================================
+impl<'a> StampReader<'a> {
+	fn new(bytes: &'a [u8]) -> Self {
+		StampReader { bytes, pos: 0 }
+	}
+
+	/// Read a single byte.
+	fn byte(&mut self) -> Result<u8> {
+		let b = *self.bytes.get(self.pos)
+			.ok_or_else(|| anyhow!("DNS stamp truncated"))?;
+		self.pos += 1;
+		Ok(b)
+	}
+
+	/// Skip a fixed number of bytes.
+	fn skip(&mut self, n: usize) -> Result<()> {
+		if self.pos + n > self.bytes.len() {
+			return Err(anyhow!("DNS stamp truncated"));
+		}
+		self.pos += n;
+		Ok(())
+	}
+
+	/// Read a length-prefixed (LP) string.
+	fn lp_string(&mut self) -> Result<String> {
+		let len = self.byte()? as usize;
+		let start = self.pos;
+		self.skip(len)?;
+		String::from_utf8(self.bytes[start..start + len].to_vec())
+			.map_err(|_| anyhow!("DNS stamp contains a non-UTF-8 string"))
+	}
+
+	/// Skip a variable-length set of LP items (VLP), as used for cert hashes.
+	/// The high bit of each length byte marks that another item follows.
+	fn skip_vlp(&mut self) -> Result<()> {
+		loop {
+			let len_byte = self.byte()?;
+			self.skip((len_byte & 0x7f) as usize)?;
+			if len_byte & 0x80 == 0 {
+				return Ok(());
+			}
+		}
+	}
+}
+
+/// Decode an "sdns://" DNS stamp into its transport-specific fields.
+///
+/// Format reference: https://dnscrypt.info/stamps-specifications
+pub fn decode_stamp(input: &str) -> Result<DnsStamp> {
+	let encoded = input.trim().strip_prefix("sdns://")
+		.ok_or_else(|| anyhow!("DNS stamp must start with sdns://"))?;
+	let bytes = URL_SAFE_NO_PAD.decode(encoded.trim_end_matches('='))
+		.map_err(|e| anyhow!("invalid DNS stamp encoding '{}': {}", input, e))?;
+
+	let mut reader = StampReader::new(&bytes);
+	let protocol = reader.byte()?;
+	match protocol {
+		STAMP_PLAIN => {
+			// props (8 bytes), LP(addr)
+			reader.skip(8)?;
+			let addr = reader.lp_string()?;
+			if addr.is_empty() {
+				return Err(anyhow!("plain DNS stamp has no address"));
+			}
+			Ok(DnsStamp::Plain { addr })
+		}
+		STAMP_DOH => {
+			// props, LP(addr), VLP(hashes), LP(hostname), LP(path), [VLP(bootstrap)]
+			reader.skip(8)?;
+			let addr = reader.lp_string()?;
+			reader.skip_vlp()?;
+			let hostname = reader.lp_string()?;
+			let path = reader.lp_string()?;
+			if hostname.is_empty() {
+				return Err(anyhow!("DoH stamp has no hostname"));
+			}
+			Ok(DnsStamp::Doh { addr, hostname, path })
+		}
+		STAMP_DOT => {
+			// props, LP(addr), VLP(hashes), LP(hostname), [VLP(bootstrap)]
+			reader.skip(8)?;
+			let addr = reader.lp_string()?;
+			reader.skip_vlp()?;
+			let hostname = reader.lp_string()?;
+			if hostname.is_empty() {
+				return Err(anyhow!("DoT stamp has no hostname"));
+			}
+			Ok(DnsStamp::Dot { addr, hostname })
+		}
+		STAMP_DNSCRYPT => Err(anyhow!("DNSCrypt stamps are not supported")),
+		STAMP_DOQ => Err(anyhow!("DNS-over-QUIC stamps are not supported")),
+		STAMP_ODOH => Err(anyhow!("Oblivious DoH stamps are not supported")),
+		other => Err(anyhow!("unsupported DNS stamp protocol 0x{:02x}", other)),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_decode_plain_stamp() {
+		let stamp = decode_stamp("sdns://AAcAAAAAAAAABzguOC44Ljg").unwrap();
+		assert_eq!(stamp, DnsStamp::Plain { addr: "8.8.8.8".to_string() });
+	}
+
+	#[test]
+	fn test_decode_plain_ipv6_stamp() {
+		let stamp = decode_stamp("sdns://AAcAAAAAAAAAFlsyNjA2OjQ3MDA6OjExMTFdOjUzNTM").unwrap();
+		assert_eq!(stamp, DnsStamp::Plain { addr: "[2606:4700::1111]:5353".to_string() });
+	}
+
+	#[test]
+	fn test_decode_doh_stamp() {
+		let stamp = decode_stamp(
+			"sdns://AgcAAAAAAAAABzEuMS4xLjEAEmNsb3VkZmxhcmUtZG5zLmNvbQovZG5zLXF1ZXJ5",
+		).unwrap();
+		assert_eq!(stamp, DnsStamp::Doh {
+			addr: "1.1.1.1".to_string(),
+			hostname: "cloudflare-dns.com".to_string(),
+			path: "/dns-query".to_string(),
+		});
+	}
+
+	#[test]
+	fn test_decode_doh_stamp_with_hashes() {
+		let stamp = decode_stamp(
+			"sdns://AgcAAAAAAAAADDEuMS4xLjE6ODQ0M6Crq6urq6urq6urq6urq6urq6urq6urq6urq6urq6urqyCrq6urq6urq6urq6urq6urq6urq6urq6urq6urq6urqwtkb2guZXhhbXBsZQIvcQ",
+		).unwrap();
+		assert_eq!(stamp, DnsStamp::Doh {
+			addr: "1.1.1.1:8443".to_string(),
+			hostname: "doh.example".to_string(),
+			path: "/q".to_string(),
+		});
+	}
+
+	#[test]
+	fn test_decode_dot_stamp() {
+		let stamp = decode_stamp("sdns://AwcAAAAAAAAABzkuOS45LjkADWRucy5xdWFkOS5uZXQ").unwrap();
+		assert_eq!(stamp, DnsStamp::Dot {
+			addr: "9.9.9.9".to_string(),
+			hostname: "dns.quad9.net".to_string(),
+		});
+	}
+
+	#[test]
+	fn test_dnscrypt_stamp_rejected() {
+		let err = decode_stamp("sdns://AQcAAAAAAAAABzEuMi4zLjQ").unwrap_err();
+		assert!(err.to_string().contains("DNSCrypt"));
+	}
+
+	#[test]
+	fn test_truncated_stamp_rejected() {
+		assert!(decode_stamp("sdns://AAcAAAAA").is_err());
+	}
+}
@@ -8,6 +8,8 @@ pub enum SortMode {
 	Category(String),
 	/// Sort alphabetically by resolver name
 	Name,
+	/// Sort by latency consistency (lowest coefficient of variation first)
+	Stability,
 }
 
 /// Parse a sort mode string, returning Score, Name, or Category(name).
@@ -15,20 +17,142 @@ pub fn parse_sort_mode(s: &str) -> SortMode {
 	match s {
 		"score" => SortMode::Score,
 		"name" => SortMode::Name,
+		"stability" => SortMode::Stability,
 		other => SortMode::Category(other.to_string()),
 	}
 }
 
+/// Error response codes received in a set. NOERROR answers and timeouts
+/// are not counted; nor is NXDOMAIN in the NXDOMAIN set, where it is the
+/// expected answer.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RcodeCounts {
+	pub servfail: usize,
+	pub refused: usize,
+	pub nxdomain: usize,
+	/// Any other error code (FORMERR, NOTIMP, ...)
+	pub other: usize,
+}
+
+impl RcodeCounts {
+	/// Count one answer's response code.
+	pub fn record(&mut self, rcode: hickory_proto::op::ResponseCode) {
+		use hickory_proto::op::ResponseCode;
+		match rcode {
+			ResponseCode::NoError => {}
+			ResponseCode::ServFail => self.servfail += 1,
+			ResponseCode::Refused => self.refused += 1,
+			ResponseCode::NXDomain => self.nxdomain += 1,
+			_ => self.other += 1,
+		}
+	}
+
+	/// Add another set's or round's counts.
+	pub fn add(&mut self, other: &RcodeCounts) {
+		self.servfail += other.servfail;
+		self.refused += other.refused;
+		self.nxdomain += other.nxdomain;
+		self.other += other.other;
+	}
+
+	/// All error answers counted.
+	pub fn total(&self) -> usize {
+		self.servfail + self.refused + self.nxdomain + self.other
+	}
+
+	/// Nonzero codes as short text, e.g. "SERVFAIL 12, REFUSED 1"; empty without errors.
+	pub fn summary(&self) -> String {
+		[("SERVFAIL", self.servfail), ("REFUSED", self.refused), ("NXDOMAIN", self.nxdomain), ("other", self.other)]
+			.iter()
+			.filter(|(_, n)| *n > 0)
+			.map(|(name, n)| format!("{} {}", name, n))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+}
+
+/// Causes of unanswered queries other than a plain timeout. Each of these
+/// also counts in the set's timeouts, so scoring is unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FailureCounts {
+	/// Socket or connection errors (refused, unreachable, reset, ...)
+	pub network_error: usize,
+	/// Only unparseable replies arrived
+	pub parse_error: usize,
+	/// Only replies with the wrong transaction ID arrived
+	pub txid_mismatch: usize,
+}
+
+impl FailureCounts {
+	/// Count one query's outcome; answers and plain timeouts are ignored.
+	pub fn record(&mut self, outcome: &crate::transport::QueryOutcome) {
+		use crate::transport::QueryOutcome;
+		match outcome {
+			QueryOutcome::NetworkError(_) => self.network_error += 1,
+			QueryOutcome::ParseError => self.parse_error += 1,
+			QueryOutcome::TxidMismatchExhausted => self.txid_mismatch += 1,
+			QueryOutcome::Ok | QueryOutcome::Timeout | QueryOutcome::Rcode(_) => {}
+		}
+	}
+
+	/// Add another set's or round's counts.
+	pub fn add(&mut self, other: &FailureCounts) {
+		self.network_error += other.network_error;
+		self.parse_error += other.parse_error;
+		self.txid_mismatch += other.txid_mismatch;
+	}
+
+	/// All failures counted.
+	pub fn total(&self) -> usize {
+		self.network_error + self.parse_error + self.txid_mismatch
+	}
+
+	/// Nonzero causes as short text, e.g. "network error 3"; empty without failures.
+	pub fn summary(&self) -> String {
+		[("network error", self.network_error), ("parse error", self.parse_error), ("txid mismatch", self.txid_mismatch)]
+			.iter()
+			.filter(|(_, n)| *n > 0)
+			.map(|(name, n)| format!("{} {}", name, n))
+			.collect::<Vec<_>>()
+			.join(", ")
+	}
+}
+
+/// Percent of real-domain queries answered NXDOMAIN above which a resolver
+/// is flagged as possibly filtering or censoring
+pub const UNEXPECTED_NXDOMAIN_FLAG_PCT: f64 = 5.0;
+
+/// NXDOMAIN answers for names that exist, and the queries sent for them.
+///
+/// Every set but the NXDOMAIN set holds real domains (a --cold-zone must
+/// answer a wildcard), so NXDOMAIN there means blocking or broken forwarding.
+pub fn unexpected_nxdomain(categories: &std::collections::BTreeMap<String, SetStats>) -> (usize, usize) {
+	categories.iter()
+		.filter(|(name, _)| name.as_str() != crate::domains::NXDOMAIN_SET)
+		.fold((0, 0), |(nx, total), (_, s)| (nx + s.rcodes.nxdomain, total + s.total_count))
+}
+
 /// Statistics for a set of queries (e.g. cached, uncached, tld, dotcom)
 #[derive(Debug, Clone, Default)]
 pub struct SetStats {
 	pub p50_ms: f64,
 	pub p95_ms: f64,
+	pub p99_ms: f64,
+	pub min_ms: f64,
+	pub max_ms: f64,
+	/// Interquartile range (p75 - p25), a jitter measure robust to outliers
+	pub iqr_ms: f64,
 	pub mean_ms: f64,
 	pub stddev_ms: f64,
+	/// Coefficient of variation (stddev / mean); lower is more stable
+	pub cv: f64,
 	pub success_count: usize,
 	pub timeout_count: usize,
 	pub total_count: usize,
+	/// Error answers by response code
+	pub rcodes: RcodeCounts,
+	/// Unanswered queries by cause, besides plain timeouts
+	pub failures: FailureCounts,
 	pub score: f64,
 }
 
@@ -74,25 +198,118 @@ pub fn stddev(values: &[f64]) -> Option<f64> {
 	Some(variance.sqrt())
 }
 
+/// Weights in the set score formula.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreWeights {
+	/// Multiplier on the tail spread (p95 - p50)
+	pub tail_weight: f64,
+	/// Multiplier on the timeout penalty (the full timeout)
+	pub timeout_scale: f64,
+	/// Milliseconds charged per answered query with an error rcode (SERVFAIL, REFUSED, ...)
+	pub failure_penalty_ms: f64,
+	/// Milliseconds added to the overall score of a resolver that intercepts NXDOMAIN
+	pub interception_penalty_ms: f64,
+	/// Milliseconds taken off the overall score of a DNSSEC-validating resolver
+	pub dnssec_bonus_ms: f64,
+	/// Milliseconds added to the overall score of a filtering resolver
+	pub filtering_penalty_ms: f64,
+	/// Weight of the warm (cached) set in the overall score; other sets weigh 1
+	pub warm_weight: f64,
+	/// Weight of the cold (uncached) set in the overall score
+	pub cold_weight: f64,
+	/// Weight of the AAAA query-type score, blended in as one more set; 0 leaves it out
+	pub aaaa_weight: f64,
+}
+
+impl ScoreWeights {
+	/// The weights used for ranking: latency only, no characterization terms.
+	pub const DEFAULT: ScoreWeights = ScoreWeights {
+		tail_weight: 0.5,
+		timeout_scale: 1.0,
+		failure_penalty_ms: 1000.0,
+		interception_penalty_ms: 0.0,
+		dnssec_bonus_ms: 0.0,
+		filtering_penalty_ms: 0.0,
+		warm_weight: 1.0,
+		cold_weight: 1.0,
+		aaaa_weight: 0.0,
+	};
+
+	/// Starting weights for a --profile; DEFAULT without one.
+	///
+	/// Browsing counts the warm set three times, since page loads mostly
+	/// hit popular cached names. Gaming weighs the tail spread and timeouts
+	/// more heavily, because one slow lookup stalls a match. Server counts
+	/// the cold set three times and adds the AAAA score, for crawlers and
+	/// mail servers that resolve uncached names over dual-stack.
+	pub fn for_profile(profile: Option<crate::cli::Profile>) -> ScoreWeights {
+		use crate::cli::Profile;
+		match profile {
+			None => ScoreWeights::DEFAULT,
+			Some(Profile::Browsing) => ScoreWeights { warm_weight: 3.0, ..ScoreWeights::DEFAULT },
+			Some(Profile::Gaming) => ScoreWeights { tail_weight: 1.5, timeout_scale: 2.0, ..ScoreWeights::DEFAULT },
+			Some(Profile::Server) => ScoreWeights { cold_weight: 3.0, aaaa_weight: 1.0, ..ScoreWeights::DEFAULT },
+		}
+	}
+
+	/// Weight of a domain set in the overall score.
+	pub fn set_weight(&self, set: &str) -> f64 {
+		match set {
+			"cached" => self.warm_weight,
+			crate::domains::COLD_SET => self.cold_weight,
+			_ => 1.0,
+		}
+	}
+
+	/// Overall score adjustment (ms) from characterization results.
+	///
+	/// Unknown probe results (None) add nothing, so a resolver is never
+	/// penalized for a probe that got no answer.
+	pub fn characterization_adjustment(&self, characterization: Option<&crate::record::CharacterizationResult>) -> f64 {
+		let Some(c) = characterization else { return 0.0 };
+		let mut adjustment = 0.0;
+		if c.intercepts_nxdomain == Some(true) {
+			adjustment += self.interception_penalty_ms;
+		}
+		if c.validates_dnssec == Some(true) {
+			adjustment -= self.dnssec_bonus_ms;
+		}
+		if c.filtering == Some(true) {
+			adjustment += self.filtering_penalty_ms;
+		}
+		adjustment
+	}
+}
+
 /// Calculate a set score that balances median latency, tail latency, and reliability.
 ///
-/// Formula: p50 + 0.5 * (p95 - p50) + penalty_ms * timeout_rate
+/// Formula: p50 + 0.5 * (p95 - p50) + penalty_ms * timeout_rate + 1000 * failure_rate
 ///
 /// - p50: baseline latency (median)
 /// - 0.5 * (p95 - p50): half-weighted tail penalty to penalize inconsistent resolvers
 /// - penalty_ms * timeout_rate: reliability penalty using full timeout as the cost
+/// - 1000 * failure_rate: penalty for fast error answers (SERVFAIL, REFUSED, ...),
+///   which cost a client a fallback query but no timeout wait
 pub fn set_score(stats: &SetStats, timeout_penalty_ms: f64) -> f64 {
+	weighted_set_score(stats, timeout_penalty_ms, &ScoreWeights::DEFAULT)
+}
+
+/// Set score with explicit weights; `set_score` uses ScoreWeights::DEFAULT.
+pub fn weighted_set_score(stats: &SetStats, timeout_penalty_ms: f64, weights: &ScoreWeights) -> f64 {
 	// Dead resolvers (no successful queries) get infinite score so they sort last
 	if stats.success_count == 0 {
 		return f64::INFINITY;
 	}
-	let timeout_rate = if stats.total_count > 0 {
-		stats.timeout_count as f64 / stats.total_count as f64
+	let (timeout_rate, failure_rate) = if stats.total_count > 0 {
+		let failures = stats.total_count.saturating_sub(stats.success_count + stats.timeout_count);
+		(stats.timeout_count as f64 / stats.total_count as f64, failures as f64 / stats.total_count as f64)
 	} else {
-		0.0
+		(0.0, 0.0)
 	};
-	// Composite: median + half the tail spread + timeout penalty
-	stats.p50_ms + 0.5 * (stats.p95_ms - stats.p50_ms) + timeout_penalty_ms * timeout_rate
+	// Composite: median + weighted tail spread + timeout penalty + failure penalty
+	stats.p50_ms + weights.tail_weight * (stats.p95_ms - stats.p50_ms)
+		+ weights.timeout_scale * timeout_penalty_ms * timeout_rate
+		+ weights.failure_penalty_ms * failure_rate
 }
 
 /// Compute SetStats from a slice of latencies (in milliseconds) and counts.
@@ -102,29 +319,279 @@ pub fn compute_set_stats(
 	timeout_count: usize,
 	total_count: usize,
 	timeout_penalty_ms: f64,
+	weights: &ScoreWeights,
 ) -> SetStats {
 	let mut sorted = latencies_ms.to_vec();
 	sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
 
 	let p50 = percentile(&sorted, 50.0).unwrap_or(0.0);
 	let p95 = percentile(&sorted, 95.0).unwrap_or(0.0);
+	let p99 = percentile(&sorted, 99.0).unwrap_or(0.0);
+	let p25 = percentile(&sorted, 25.0).unwrap_or(0.0);
+	let p75 = percentile(&sorted, 75.0).unwrap_or(0.0);
 	let avg = mean(&sorted).unwrap_or(0.0);
 	let sd = stddev(&sorted).unwrap_or(0.0);
 
 	let mut stats = SetStats {
 		p50_ms: p50,
 		p95_ms: p95,
+		p99_ms: p99,
+		min_ms: sorted.first().copied().unwrap_or(0.0),
+		max_ms: sorted.last().copied().unwrap_or(0.0),
+		iqr_ms: p75 - p25,
 		mean_ms: avg,
 		stddev_ms: sd,
+		cv: if avg > 0.0 { sd / avg } else { 0.0 },
 		success_count,
 		timeout_count,
 		total_count,
+		rcodes: RcodeCounts::default(),
+		failures: FailureCounts::default(),
 		score: 0.0,
 	};
-	stats.score = set_score(&stats, timeout_penalty_ms);
+	stats.score = weighted_set_score(&stats, timeout_penalty_ms, weights);
 	stats
 }
 
+/// Overall stability: mean coefficient of variation across scored categories.
+///
+/// Categories with fewer than two successes are skipped (no spread to measure),
+/// as is the NXDOMAIN set. Returns None when no category qualifies.
+pub fn overall_stability(categories: &std::collections::BTreeMap<String, SetStats>) -> Option<f64> {
+	let cvs: Vec<f64> = categories.iter()
+		.filter(|(name, s)| name.as_str() != crate::domains::NXDOMAIN_SET && s.success_count >= 2)
+		.map(|(_, s)| s.cv)
+		.collect();
+	mean(&cvs)
+}
+
+//============================================
+/// Measurements pooled over every address of one provider (--by-provider).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderStats {
+	pub name: String,
+	/// Member labels in result order
+	pub members: Vec<String>,
+	/// Overall score from the pooled per-set latencies and counts
+	pub score: f64,
+	/// Scored queries answered, percent
+	pub success_rate: f64,
+	/// p50 of all pooled scored latencies
+	pub p50_ms: f64,
+}
+
+/// Group benchmarked resolvers by provider and score each group as if its
+/// addresses were one resolver: per-set latencies and counts are pooled,
+/// then scored the same way as a single resolver. Best score first.
+pub fn provider_stats(
+	records: &[crate::record::ResolverRecord],
+	timeout_penalty_ms: f64,
+	weights: &ScoreWeights,
+) -> Vec<ProviderStats> {
+	use std::collections::BTreeMap;
+	// Provider -> (members, set -> pooled round aggregate)
+	let mut groups: Vec<(String, Vec<String>, BTreeMap<String, crate::record::RoundAggregate>)> = Vec::new();
+	for r in records {
+		let Some(bm) = &r.benchmark else { continue };
+		let key = r.resolver.provider_key();
+		let idx = match groups.iter().position(|(name, _, _)| name == key) {
+			Some(i) => i,
+			None => {
+				groups.push((key.to_string(), Vec::new(), BTreeMap::new()));
+				groups.len() - 1
+			}
+		};
+		let (_, members, sets) = &mut groups[idx];
+		members.push(r.resolver.label.clone());
+		for (set, rounds) in bm.raw.sets.iter().filter(|(name, _)| name.as_str() != crate::domains::NXDOMAIN_SET) {
+			let pooled = sets.entry(set.clone()).or_default();
+			for round in rounds {
+				pooled.latencies_ms.extend_from_slice(&round.latencies_ms);
+				pooled.success += round.success;
+				pooled.timeout += round.timeout;
+				pooled.total += round.total;
+				pooled.rcodes.add(&round.rcodes);
+				pooled.failures.add(&round.failures);
+			}
+		}
+	}
+	let mut providers: Vec<ProviderStats> = groups.into_iter()
+		.map(|(name, members, sets)| {
+			let set_stats: BTreeMap<String, SetStats> = sets.iter()
+				.map(|(set, agg)| {
+					let stats = compute_set_stats(&agg.latencies_ms, agg.success, agg.timeout, agg.total, timeout_penalty_ms, weights);
+					(set.clone(), SetStats { rcodes: agg.rcodes, failures: agg.failures, ..stats })
+				})
+				.collect();
+			let (success, total) = sets.values().fold((0, 0), |(s, t), agg| (s + agg.success, t + agg.total));
+			let mut all: Vec<f64> = sets.values().flat_map(|agg| agg.latencies_ms.iter().copied()).collect();
+			all.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+			ProviderStats {
+				name,
+				members,
+				// Pooled rounds keep no per-type split, so the AAAA term is left out
+				score: rescore(&set_stats, &BTreeMap::new(), timeout_penalty_ms, weights),
+				success_rate: if total > 0 { 100.0 * success as f64 / total as f64 } else { 0.0 },
+				p50_ms: percentile(&all, 50.0).unwrap_or(0.0),
+			}
+		})
+		.collect();
+	providers.sort_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal));
+	providers
+}
+
+/// Perturbations tried by the score sensitivity analysis, as multipliers
+/// on the configured (tail weight, timeout scale)
+pub const SENSITIVITY_SCENARIOS: [(&str, f64, f64); 4] = [
+	("tail weight -50%", 0.5, 1.0),
+	("tail weight +50%", 1.5, 1.0),
+	("timeout penalty x0.5", 1.0, 0.5),
+	("timeout penalty x2", 1.0, 2.0),
+];
+/// Ranking positions compared by the sensitivity analysis
+pub const SENSITIVITY_TOP_N: usize = 3;
+
+/// Top of the ranking under one set of scoring weights.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityScenario {
+	pub name: &'static str,
+	/// Labels of the best SENSITIVITY_TOP_N resolvers, best first
+	pub top: Vec<String>,
+}
+
+//============================================
+/// Overall score from per-set stats with the given weights: the weighted
+/// mean set score over scored sets with data, plus the AAAA query-type
+/// score when `aaaa_weight` is above 0. Sets of weight 0 are left out.
+/// The benchmark scores resolvers with this same function.
+pub fn rescore(
+	categories: &std::collections::BTreeMap<String, SetStats>,
+	query_types: &std::collections::BTreeMap<String, SetStats>,
+	timeout_penalty_ms: f64,
+	weights: &ScoreWeights,
+) -> f64 {
+	let aaaa = query_types.get("AAAA").map(|s| ("AAAA", s, weights.aaaa_weight));
+	let weighted: Vec<(f64, f64)> = categories.iter()
+		.filter(|(name, _)| name.as_str() != crate::domains::NXDOMAIN_SET)
+		.map(|(name, s)| (name.as_str(), s, weights.set_weight(name)))
+		.chain(aaaa)
+		.filter(|(_, s, weight)| s.total_count > 0 && *weight > 0.0)
+		.map(|(_, s, weight)| (weighted_set_score(s, timeout_penalty_ms, weights), weight))
+		.collect();
+	if weighted.is_empty() {
+		return f64::INFINITY;
+	}
+	let total_weight: f64 = weighted.iter().map(|(_, w)| w).sum();
+	weighted.iter().map(|(score, w)| score * w).sum::<f64>() / total_weight
+}
+
+/// Labels of the best SENSITIVITY_TOP_N resolvers under `weights`.
+fn top_by_weights(records: &[crate::record::ResolverRecord], timeout_penalty_ms: f64, weights: &ScoreWeights) -> Vec<String> {
+	let mut scored: Vec<(&str, f64)> = records.iter()
+		.filter_map(|r| r.benchmark.as_ref()
+			.map(|bm| (r.resolver.label.as_str(), rescore(&bm.categories, &bm.query_types, timeout_penalty_ms, weights)
+				+ weights.characterization_adjustment(r.characterization.as_ref()))))
+		.collect();
+	scored.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+	scored.into_iter().take(SENSITIVITY_TOP_N).map(|(label, _)| label.to_string()).collect()
+}
+
+/// Rank the top resolvers under `base` weights and each perturbed scenario.
+///
+/// The first entry is the baseline ("current weights"). Ordering is by
+/// score alone, ignoring --rank-by and system resolver pinning.
+pub fn score_sensitivity(
+	records: &[crate::record::ResolverRecord],
+	timeout_penalty_ms: f64,
+	base: &ScoreWeights,
+) -> Vec<SensitivityScenario> {
+	std::iter::once(("current weights", 1.0, 1.0))
+		.chain(SENSITIVITY_SCENARIOS)
+		.map(|(name, tail_mult, timeout_mult)| {
+			let weights = ScoreWeights {
+				tail_weight: base.tail_weight * tail_mult,
+				timeout_scale: base.timeout_scale * timeout_mult,
+				..*base
+			};
+			SensitivityScenario { name, top: top_by_weights(records, timeout_penalty_ms, &weights) }
+		})
+		.collect()
+}
+
+//============================================
+/// Latency shift between the first and last benchmark rounds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundDrift {
+	/// Scored-set p50 per round, from round 1; None for a round without answers
+	pub round_p50_ms: Vec<Option<f64>>,
+	/// Last-round p50 minus first-round p50
+	pub shift_ms: f64,
+	/// Sum of the first and last rounds' uncertainty bands (see compute_uncertainty)
+	pub band_ms: f64,
+}
+
+impl RoundDrift {
+	/// Whether the first and last rounds differ by more than their uncertainty bands.
+	pub fn drifted(&self) -> bool {
+		self.shift_ms.abs() > self.band_ms
+	}
+}
+
+/// Compare scored-set latencies of round 1 and the last round.
+///
+/// A rising p50 points to rate limiting kicking in; a falling one to caches
+/// warming during the run. None with fewer than two rounds or when the
+/// first or last round has no answers.
+pub fn round_drift(raw: &crate::record::RawAggregates) -> Option<RoundDrift> {
+	let rounds = raw.round_count();
+	if rounds < 2 {
+		return None;
+	}
+	let sorted_round = |round: usize| {
+		let mut latencies = raw.round_scored_latencies_ms(round as u32);
+		latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+		latencies
+	};
+	let round_p50_ms: Vec<Option<f64>> = (1..=rounds)
+		.map(|round| percentile(&sorted_round(round), 50.0))
+		.collect();
+	let first = sorted_round(1);
+	let last = sorted_round(rounds);
+	let shift_ms = percentile(&last, 50.0)? - percentile(&first, 50.0)?;
+	let band_ms = compute_uncertainty(&first) + compute_uncertainty(&last);
+	Some(RoundDrift { round_p50_ms, shift_ms, band_ms })
+}
+
+/// Upper bounds (ms) of latency histogram buckets: powers of two, as in
+/// HdrHistogram's magnitude buckets; a final bucket catches anything slower
+pub const HISTOGRAM_BOUNDS_MS: [f64; 12] = [
+	1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0,
+];
+
+/// Count latencies into HISTOGRAM_BOUNDS_MS buckets.
+///
+/// Returns one count per bound (latency <= bound, above the previous bound)
+/// plus a final overflow count.
+pub fn latency_histogram(latencies_ms: &[f64]) -> Vec<usize> {
+	let mut counts = vec![0; HISTOGRAM_BOUNDS_MS.len() + 1];
+	for &ms in latencies_ms {
+		let idx = HISTOGRAM_BOUNDS_MS.iter()
+			.position(|&bound| ms <= bound)
+			.unwrap_or(HISTOGRAM_BOUNDS_MS.len());
+		counts[idx] += 1;
+	}
+	counts
+}
+
+/// Short labels for latency_histogram buckets ("<=1", ..., ">2048").
+pub fn histogram_bucket_labels() -> Vec<String> {
+	let mut labels: Vec<String> = HISTOGRAM_BOUNDS_MS.iter()
+		.map(|b| format!("<={}", b))
+		.collect();
+	labels.push(format!(">{}", HISTOGRAM_BOUNDS_MS[HISTOGRAM_BOUNDS_MS.len() - 1]));
+	labels
+}
+
 /// Compute the uncertainty of a score using MAD (median absolute deviation).
 ///
 /// Uses the scale factor 1.4826 for consistency with normal distribution.
@@ -151,55 +618,173 @@ pub fn compute_uncertainty(latencies_ms: &[f64]) -> f64 {
 	1.4826 * mad
 }
 
-/// Detect ties among ranked resolver records based on overlapping uncertainty bands.
+/// Two-sided significance level for pairwise resolver comparisons
+pub const SIGNIFICANCE_ALPHA: f64 = 0.05;
+/// Minimum latency samples per resolver for the Mann-Whitney normal approximation
+pub const MIN_SIGNIFICANCE_SAMPLES: usize = 8;
+
+/// Result of a two-sided Mann-Whitney U test.
+#[derive(Debug, Clone, Copy)]
+pub struct MannWhitney {
+	/// U statistic of the first sample
+	pub u: f64,
+	/// Normal-approximation z score (tie- and continuity-corrected)
+	pub z: f64,
+	/// Two-sided p-value
+	pub p_value: f64,
+}
+
+//============================================
+/// Complementary error function (Numerical Recipes erfcc, fractional error < 1.2e-7).
+fn erfc(x: f64) -> f64 {
+	let z = x.abs();
+	let t = 1.0 / (1.0 + 0.5 * z);
+	let poly = -z * z - 1.26551223 + t * (1.00002368 + t * (0.37409196 + t * (0.09678418
+		+ t * (-0.18628806 + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587
+		+ t * (-0.82215223 + t * 0.17087277))))))));
+	let r = t * poly.exp();
+	if x >= 0.0 { r } else { 2.0 - r }
+}
+
+//============================================
+/// Two-sided Mann-Whitney U test on two latency samples.
 ///
-/// For consecutive pairs: if |score_a - score_b| < uncertainty_a + uncertainty_b,
-/// they are tied. Groups tied resolvers and assigns shared rank labels.
-pub fn detect_ties_on_records(records: &mut [crate::record::ResolverRecord], uncertainties: &[f64]) {
-	if records.len() < 2 || uncertainties.len() != records.len() {
-		return;
+/// Non-parametric: compares whole distributions by rank, so it is robust to
+/// the long right tail typical of DNS latency. Uses the normal approximation
+/// with tie correction, so both samples need MIN_SIGNIFICANCE_SAMPLES values.
+/// Returns None for smaller samples.
+pub fn mann_whitney_u(a: &[f64], b: &[f64]) -> Option<MannWhitney> {
+	if a.len() < MIN_SIGNIFICANCE_SAMPLES || b.len() < MIN_SIGNIFICANCE_SAMPLES {
+		return None;
 	}
+	let n1 = a.len() as f64;
+	let n2 = b.len() as f64;
+	let n = n1 + n2;
 
-	let n = records.len();
-	let mut group_id: Vec<usize> = (0..n).collect();
-
-	// Check consecutive pairs for overlap
-	for i in 0..(n - 1) {
-		let score_a = records[i].benchmark.as_ref().map(|b| b.overall_score).unwrap_or(f64::INFINITY);
-		let score_b = records[i + 1].benchmark.as_ref().map(|b| b.overall_score).unwrap_or(f64::INFINITY);
-		let diff = (score_a - score_b).abs();
-		let threshold = uncertainties[i] + uncertainties[i + 1];
-
-		if diff < threshold {
-			let target = group_id[i];
-			let source = group_id[i + 1];
-			for g in group_id.iter_mut() {
-				if *g == source {
-					*g = target;
-				}
-			}
+	// Pool both samples (true = from a) and sort by value
+	let mut pooled: Vec<(f64, bool)> = a.iter().map(|&v| (v, true))
+		.chain(b.iter().map(|&v| (v, false)))
+		.collect();
+	pooled.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap_or(std::cmp::Ordering::Equal));
+
+	// Average ranks over tied runs; accumulate sum(t^3 - t) for the variance correction
+	let mut rank_sum_a = 0.0;
+	let mut tie_term = 0.0;
+	let mut i = 0;
+	while i < pooled.len() {
+		let mut j = i + 1;
+		while j < pooled.len() && pooled[j].0 == pooled[i].0 {
+			j += 1;
 		}
+		let avg_rank = (i + 1 + j) as f64 / 2.0;
+		let from_a = pooled[i..j].iter().filter(|p| p.1).count();
+		rank_sum_a += avg_rank * from_a as f64;
+		let t = (j - i) as f64;
+		tie_term += t * t * t - t;
+		i = j;
 	}
 
-	// Convert group IDs to rank labels
-	let mut i = 0;
-	while i < n {
-		let gid = group_id[i];
-		let members: Vec<usize> = (0..n).filter(|&j| group_id[j] == gid).collect();
-
-		if members.len() > 1 {
-			let first_rank = members[0] + 1;
-			let last_rank = members[members.len() - 1] + 1;
-			let label = format!("{}-{}", first_rank, last_rank);
-			for &m in &members {
-				if let Some(ref mut bm) = records[m].benchmark {
+	let u = rank_sum_a - n1 * (n1 + 1.0) / 2.0;
+	let mean_u = n1 * n2 / 2.0;
+	let variance = n1 * n2 / 12.0 * ((n + 1.0) - tie_term / (n * (n - 1.0)));
+	if variance <= 0.0 {
+		// Every value identical: no evidence of a difference
+		return Some(MannWhitney { u, z: 0.0, p_value: 1.0 });
+	}
+	// Continuity correction toward the mean
+	let diff = u - mean_u;
+	let corrected = (diff.abs() - 0.5).max(0.0) * diff.signum();
+	let z = corrected / variance.sqrt();
+	let p_value = erfc(z.abs() / std::f64::consts::SQRT_2).min(1.0);
+	Some(MannWhitney { u, z, p_value })
+}
+
+/// Holm-Bonferroni adjusted p-values for a family of tests, in input order.
+///
+/// Comparing the adjusted values against SIGNIFICANCE_ALPHA keeps the chance
+/// of any false "significant" verdict in the family at alpha. Tests that did
+/// not run (None) are not counted in the family.
+pub fn holm_adjust(p_values: &[Option<f64>]) -> Vec<Option<f64>> {
+	let mut order: Vec<usize> = (0..p_values.len()).filter(|&i| p_values[i].is_some()).collect();
+	order.sort_by(|&a, &b| p_values[a].partial_cmp(&p_values[b]).unwrap_or(std::cmp::Ordering::Equal));
+	let m = order.len();
+	let mut adjusted = vec![None; p_values.len()];
+	let mut running_max: f64 = 0.0;
+	for (k, &i) in order.iter().enumerate() {
+		let scaled = (p_values[i].unwrap_or(1.0) * (m - k) as f64).min(1.0);
+		// Adjusted values never decrease with the raw p-value
+		running_max = running_max.max(scaled);
+		adjusted[i] = Some(running_max);
+	}
+	adjusted
+}
+
+//============================================
+/// Assign shared rank labels to runs of consecutive tied records.
+///
+/// `tied[i]` says whether records i and i+1 are tied.
+fn assign_tie_groups(records: &mut [crate::record::ResolverRecord], tied: &[bool]) {
+	let n = records.len();
+	let mut start = 0;
+	while start < n {
+		let mut end = start;
+		while end + 1 < n && tied.get(end).copied().unwrap_or(false) {
+			end += 1;
+		}
+		if end > start {
+			let label = format!("{}-{}", start + 1, end + 1);
+			for rec in &mut records[start..=end] {
+				if let Some(ref mut bm) = rec.benchmark {
 					bm.tie_group = Some(label.clone());
 				}
 			}
 		}
+		start = end + 1;
+	}
+}
 
-		i = members.last().map(|&l| l + 1).unwrap_or(i + 1);
+/// Detect ties among ranked resolver records based on overlapping uncertainty bands.
+///
+/// For consecutive pairs: if |score_a - score_b| < uncertainty_a + uncertainty_b,
+/// they are tied. Groups tied resolvers and assigns shared rank labels.
+pub fn detect_ties_on_records(records: &mut [crate::record::ResolverRecord], uncertainties: &[f64]) {
+	if records.len() < 2 || uncertainties.len() != records.len() {
+		return;
 	}
+	let tied: Vec<bool> = (0..records.len() - 1)
+		.map(|i| uncertainty_overlap(records, uncertainties, i))
+		.collect();
+	assign_tie_groups(records, &tied);
+}
+
+/// Whether records i and i+1 have overlapping score uncertainty bands.
+fn uncertainty_overlap(records: &[crate::record::ResolverRecord], uncertainties: &[f64], i: usize) -> bool {
+	let score_a = records[i].benchmark.as_ref().map(|b| b.overall_score).unwrap_or(f64::INFINITY);
+	let score_b = records[i + 1].benchmark.as_ref().map(|b| b.overall_score).unwrap_or(f64::INFINITY);
+	(score_a - score_b).abs() < uncertainties[i] + uncertainties[i + 1]
+}
+
+/// Detect ties among ranked records with a Mann-Whitney U test.
+///
+/// Consecutive records are tied when their latency distributions are not
+/// significantly different at SIGNIFICANCE_ALPHA. Pairs with too few samples
+/// for the test fall back to the uncertainty-band overlap heuristic.
+pub fn detect_ties_significance(
+	records: &mut [crate::record::ResolverRecord],
+	latencies: &[Vec<f64>],
+	uncertainties: &[f64],
+) {
+	let n = records.len();
+	if n < 2 || latencies.len() != n || uncertainties.len() != n {
+		return;
+	}
+	let tied: Vec<bool> = (0..n - 1)
+		.map(|i| match mann_whitney_u(&latencies[i], &latencies[i + 1]) {
+			Some(test) => test.p_value >= SIGNIFICANCE_ALPHA,
+			None => uncertainty_overlap(records, uncertainties, i),
+		})
+		.collect();
+	assign_tie_groups(records, &tied);
 }
 
 /// Rank resolver records by the chosen sort mode, ascending.
@@ -225,7 +810,22 @@ pub fn rank_records(records: &mut [crate::record::ResolverRecord], sort_mode: &S
 			});
 		}
 		SortMode::Name => {
-			records.sort_by(|a, b| a.resolver.label.to_lowercase().cmp(&b.resolver.label.to_lowercase()));
+			records.sort_by_key(|a| a.resolver.label.to_lowercase());
+		}
+		SortMode::Stability => {
+			// Lowest CV first; overall score breaks ties
+			let key = |r: &crate::record::ResolverRecord| {
+				let bm = r.benchmark.as_ref();
+				(
+					bm.and_then(|bm| bm.stability_cv).unwrap_or(f64::INFINITY),
+					bm.map(|bm| bm.overall_score).unwrap_or(f64::INFINITY),
+				)
+			};
+			records.sort_by(|a, b| {
+				let (ca, sa) = key(a);
+				let (cb, sb) = key(b);
+				cmp_f64(ca, cb).then(cmp_f64(sa, sb))
+			});
 		}
 	}
 	// Set rank on each record's benchmark result
@@ -239,7 +839,6 @@ pub fn rank_records(records: &mut [crate::record::ResolverRecord], sort_mode: &S
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use std::collections::BTreeMap;
 
 	#[test]
 	fn test_percentile_basic() {
@@ -287,6 +886,7 @@ mod tests {
 			timeout_count: 0,
 			total_count: 100,
 			score: 0.0,
+			..Default::default()
 		};
 		// score = 20 + 0.5*(50-20) + 5000*0 = 20 + 15 = 35
 		let score = set_score(&stats, 5000.0);
@@ -304,12 +904,30 @@ mod tests {
 			timeout_count: 10,
 			total_count: 100,
 			score: 0.0,
+			..Default::default()
 		};
 		// score = 20 + 0.5*(50-20) + 5000*0.1 = 20 + 15 + 500 = 535
 		let score = set_score(&stats, 5000.0);
 		assert!((score - 535.0).abs() < 0.01);
 	}
 
+	#[test]
+	fn test_set_score_with_error_answers() {
+		// 10 fast SERVFAILs: no timeout penalty, but the failure penalty
+		let stats = SetStats {
+			p50_ms: 5.0,
+			p95_ms: 5.0,
+			success_count: 90,
+			timeout_count: 0,
+			total_count: 100,
+			..Default::default()
+		};
+		// score = 5 + 0 + 0 + 1000*0.1 = 105
+		assert!((set_score(&stats, 2000.0) - 105.0).abs() < 0.01);
+		let lenient = ScoreWeights { failure_penalty_ms: 0.0, ..ScoreWeights::DEFAULT };
+		assert!((weighted_set_score(&stats, 2000.0, &lenient) - 5.0).abs() < 0.01);
+	}
+
 	/// Helper to build a minimal ResolverRecord for testing
 	fn make_test_record(label: &str, overall_score: f64, success_rate: f64) -> crate::record::ResolverRecord {
 		use crate::transport::{Resolver, DnsTransport};
@@ -317,11 +935,10 @@ mod tests {
 		resolver.label = label.to_string();
 		let mut rec = crate::record::ResolverRecord::new(resolver);
 		rec.benchmark = Some(crate::record::BenchmarkResult {
-			categories: BTreeMap::new(),
 			overall_score,
 			success_rate,
-			rank: 0,
-			tie_group: None,
+			first_attempt_rate: success_rate,
+			..Default::default()
 		});
 		rec
 	}
@@ -340,6 +957,130 @@ mod tests {
 		assert_eq!(records[2].resolver.label, "slow");
 	}
 
+	#[test]
+	fn test_score_sensitivity_detects_winner_flip() {
+		// "steady": no tail, some timeouts; "spiky": big tail, no timeouts
+		let set = |p50: f64, p95: f64, timeouts: usize| {
+			let mut s = SetStats { p50_ms: p50, p95_ms: p95, success_count: 100 - timeouts,
+				timeout_count: timeouts, total_count: 100, ..SetStats::default() };
+			s.score = set_score(&s, 1000.0);
+			s
+		};
+		let mut steady = make_test_record("steady", 0.0, 99.0);
+		let mut spiky = make_test_record("spiky", 0.0, 100.0);
+		for (rec, stats) in [(&mut steady, set(20.0, 20.0, 1)), (&mut spiky, set(10.0, 50.0, 0))] {
+			let bm = rec.benchmark.as_mut().unwrap();
+			bm.categories.insert("cached".to_string(), stats);
+			bm.overall_score = rescore(&bm.categories, &bm.query_types, 1000.0, &ScoreWeights::DEFAULT);
+		}
+		// Both score 30 under the default weights, so any perturbation decides
+		let scenarios = score_sensitivity(&[steady, spiky], 1000.0, &ScoreWeights::DEFAULT);
+		assert_eq!(scenarios.len(), SENSITIVITY_SCENARIOS.len() + 1);
+		let top = |name: &str| scenarios.iter().find(|s| s.name == name).unwrap().top[0].clone();
+		// Lighter tail weight favors spiky; doubled timeout penalty also favors spiky
+		assert_eq!(top("tail weight -50%"), "spiky");
+		assert_eq!(top("tail weight +50%"), "steady");
+		assert_eq!(top("timeout penalty x2"), "spiky");
+		assert_eq!(top("timeout penalty x0.5"), "steady");
+	}
+
+	#[test]
+	fn test_profile_set_weights() {
+		use std::collections::BTreeMap;
+		let set = |p50: f64| SetStats { p50_ms: p50, p95_ms: p50, success_count: 10, total_count: 10, ..SetStats::default() };
+		let categories: BTreeMap<String, SetStats> = [("cached", set(10.0)), ("uncached", set(50.0)), ("tld", set(30.0)), ("nxdomain", set(500.0))]
+			.into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+		let types: BTreeMap<String, SetStats> = [("A".to_string(), set(20.0)), ("AAAA".to_string(), set(90.0))].into_iter().collect();
+		let score = |profile| rescore(&categories, &types, 1000.0, &ScoreWeights::for_profile(profile));
+		// Plain mean of the scored sets; NXDOMAIN and query types stay out
+		assert_eq!(score(None), 30.0);
+		// Browsing: (3*10 + 50 + 30) / 5
+		assert_eq!(score(Some(crate::cli::Profile::Browsing)), 22.0);
+		// Server: (10 + 3*50 + 30 + 90) / 6
+		assert!((score(Some(crate::cli::Profile::Server)) - 280.0 / 6.0).abs() < 1e-9);
+		// A zero weight leaves the set out
+		let no_warm = ScoreWeights { warm_weight: 0.0, ..ScoreWeights::DEFAULT };
+		assert_eq!(rescore(&categories, &types, 1000.0, &no_warm), 40.0);
+	}
+
+	#[test]
+	fn test_provider_stats_pools_addresses() {
+		use crate::record::RoundAggregate;
+		let with_round = |label: &str, provider: Option<&str>, latencies: Vec<f64>, timeout: usize| {
+			let mut rec = make_test_record(label, 0.0, 100.0);
+			rec.resolver.provider = provider.map(str::to_string);
+			let total = latencies.len() + timeout;
+			let round = RoundAggregate { success: latencies.len(), timeout, total, latencies_ms: latencies, ..RoundAggregate::default() };
+			rec.benchmark.as_mut().unwrap().raw.sets.insert("cached".to_string(), vec![round]);
+			rec
+		};
+		let records = vec![
+			with_round("Cloudflare", None, vec![10.0; 9], 1),
+			with_round("Cloudflare-DoT", None, vec![30.0; 10], 0),
+			with_round("Quad9 primary", Some("quad9"), vec![15.0; 10], 0),
+			with_round("Quad9 secondary", Some("quad9"), vec![15.0; 10], 0),
+		];
+		let providers = provider_stats(&records, 1000.0, &ScoreWeights::DEFAULT);
+		let names: Vec<&str> = providers.iter().map(|p| p.name.as_str()).collect();
+		assert_eq!(names, ["quad9", "Cloudflare"]);
+		assert_eq!(providers[0].members, ["Quad9 primary", "Quad9 secondary"]);
+		assert_eq!(providers[0].success_rate, 100.0);
+		// Cloudflare pools 19 answers and the one timeout of its first address
+		assert_eq!(providers[1].success_rate, 95.0);
+		assert_eq!(providers[1].p50_ms, 30.0);
+	}
+
+	#[test]
+	fn test_characterization_adjustment() {
+		let weights = ScoreWeights { interception_penalty_ms: 100.0, dnssec_bonus_ms: 5.0, filtering_penalty_ms: 20.0, ..ScoreWeights::DEFAULT };
+		let c = crate::record::CharacterizationResult {
+			reachable: true,
+			attempts_used: 1,
+			successes: 1,
+			latency_ms: Some(10.0),
+			intercepts_nxdomain: Some(true),
+			rebinding_protection: None,
+			validates_dnssec: Some(true),
+			answers_known_record: Some(true),
+			filtering: None,
+			edns_udp_size: None,
+			server_cookie: None,
+			preserves_case: None,
+			edns_buffer: None,
+			large_responses: None,
+			rd_zero: None,
+			https_rr: None,
+			pads_responses: None,
+			dns64: None,
+			ttl_rewrite: None,
+			coalescing: None,
+		};
+		// Unknown filtering adds nothing
+		assert_eq!(weights.characterization_adjustment(Some(&c)), 95.0);
+		assert_eq!(weights.characterization_adjustment(None), 0.0);
+		// The defaults keep ranking latency-only
+		assert_eq!(ScoreWeights::DEFAULT.characterization_adjustment(Some(&c)), 0.0);
+	}
+
+	#[test]
+	fn test_round_drift_flags_shift_beyond_band() {
+		let round = |latencies: &[f64]| crate::record::RoundAggregate {
+			latencies_ms: latencies.to_vec(), success: latencies.len(), total: latencies.len(), ..Default::default()
+		};
+		let mut raw = crate::record::RawAggregates::default();
+		raw.sets.insert("cached".to_string(), vec![round(&[10.0, 11.0, 12.0]), round(&[15.0]), round(&[30.0, 31.0, 32.0])]);
+		let drift = round_drift(&raw).unwrap();
+		assert_eq!(drift.round_p50_ms, vec![Some(11.0), Some(15.0), Some(31.0)]);
+		assert_eq!(drift.shift_ms, 20.0);
+		assert!(drift.drifted());
+		// Noisy rounds: the shift stays inside the bands
+		raw.sets.insert("cached".to_string(), vec![round(&[5.0, 10.0, 40.0]), round(&[8.0, 12.0, 45.0])]);
+		assert!(!round_drift(&raw).unwrap().drifted());
+		// One round is not enough
+		raw.sets.insert("cached".to_string(), vec![round(&[10.0])]);
+		assert_eq!(round_drift(&raw), None);
+	}
+
 	#[test]
 	fn test_compute_uncertainty_basic() {
 		let values = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
@@ -407,4 +1148,144 @@ mod tests {
 		assert_eq!(records[0].resolver.label, "b");
 		assert_eq!(records[1].resolver.label, "a");
 	}
+
+	#[test]
+	fn test_compute_set_stats_spread() {
+		let latencies: Vec<f64> = (1..=100).map(|v| v as f64).collect();
+		let stats = compute_set_stats(&latencies, 100, 0, 100, 2000.0, &ScoreWeights::DEFAULT);
+		assert_eq!(stats.min_ms, 1.0);
+		assert_eq!(stats.max_ms, 100.0);
+		assert_eq!(stats.p99_ms, 99.0);
+		assert_eq!(stats.iqr_ms, 50.0);
+		assert!((stats.cv - stats.stddev_ms / 50.5).abs() < 1e-9);
+	}
+
+	#[test]
+	fn test_sort_by_stability() {
+		// Consistent 20 ms resolver beats one swinging between 5 and 200 ms
+		let mut steady = make_test_record("steady", 30.0, 100.0);
+		steady.benchmark.as_mut().unwrap().stability_cv = Some(0.05);
+		let mut swingy = make_test_record("swingy", 20.0, 100.0);
+		swingy.benchmark.as_mut().unwrap().stability_cv = Some(1.2);
+		let unmeasured = make_test_record("unmeasured", 10.0, 100.0);
+
+		let mut records = vec![swingy, unmeasured, steady];
+		rank_records(&mut records, &SortMode::Stability);
+		let order: Vec<&str> = records.iter().map(|r| r.resolver.label.as_str()).collect();
+		assert_eq!(order, vec!["steady", "swingy", "unmeasured"]);
+	}
+
+	#[test]
+	fn test_mann_whitney_separated_samples() {
+		let fast: Vec<f64> = (0..20).map(|i| 10.0 + i as f64 * 0.1).collect();
+		let slow: Vec<f64> = (0..20).map(|i| 30.0 + i as f64 * 0.1).collect();
+		let test = mann_whitney_u(&fast, &slow).unwrap();
+		// Complete separation: U = 0, |z| ~ 5.4
+		assert_eq!(test.u, 0.0);
+		assert!(test.p_value < 0.001);
+	}
+
+	#[test]
+	fn test_latency_histogram_buckets() {
+		let counts = latency_histogram(&[0.5, 1.0, 1.5, 30.0, 33.0, 5000.0]);
+		assert_eq!(counts.len(), HISTOGRAM_BOUNDS_MS.len() + 1);
+		assert_eq!(counts[0], 2); // <=1
+		assert_eq!(counts[1], 1); // <=2
+		assert_eq!(counts[5], 1); // <=32
+		assert_eq!(counts[6], 1); // <=64
+		assert_eq!(counts[HISTOGRAM_BOUNDS_MS.len()], 1); // overflow
+		assert_eq!(histogram_bucket_labels().last().unwrap(), ">2048");
+	}
+
+	#[test]
+	fn test_mann_whitney_same_distribution() {
+		let a: Vec<f64> = (0..20).map(|i| (i % 5) as f64).collect();
+		let b: Vec<f64> = (0..20).map(|i| ((i + 2) % 5) as f64).collect();
+		let test = mann_whitney_u(&a, &b).unwrap();
+		assert!(test.p_value > 0.9);
+		// All-identical samples carry no evidence of a difference
+		assert_eq!(mann_whitney_u(&[5.0; 10], &[5.0; 10]).unwrap().p_value, 1.0);
+	}
+
+	#[test]
+	fn test_holm_adjust() {
+		let adjusted = holm_adjust(&[Some(0.01), None, Some(0.04), Some(0.03), Some(0.5)]);
+		// Family of 4: 0.01*4, then max(0.03*3, 0.04), max(0.04*2, 0.09), 0.5*1
+		let expected: [Option<f64>; 5] = [Some(0.04), None, Some(0.09), Some(0.09), Some(0.5)];
+		for (a, e) in adjusted.iter().zip(expected) {
+			assert_eq!(a.map(|p| (p * 1e9).round()), e.map(|p| (p * 1e9).round()));
+		}
+		assert_eq!(holm_adjust(&[Some(0.6), Some(0.9)]), vec![Some(1.0), Some(1.0)]);
+	}
+
+	#[test]
+	fn test_mann_whitney_small_sample() {
+		assert!(mann_whitney_u(&[1.0, 2.0], &[3.0; 10]).is_none());
+	}
+
+	#[test]
+	fn test_detect_ties_significance() {
+		let mut records = vec![
+			make_test_record("a", 10.0, 99.0),
+			make_test_record("b", 10.5, 99.0),
+			make_test_record("c", 30.0, 99.0),
+		];
+		rank_records(&mut records, &SortMode::Score);
+		let same: Vec<f64> = (0..20).map(|i| 10.0 + (i % 4) as f64).collect();
+		let slow: Vec<f64> = (0..20).map(|i| 30.0 + (i % 4) as f64).collect();
+		let latencies = vec![same.clone(), same, slow];
+		detect_ties_significance(&mut records, &latencies, &[0.0, 0.0, 0.0]);
+		assert_eq!(records[0].benchmark.as_ref().unwrap().tie_group, Some("1-2".to_string()));
+		assert_eq!(records[1].benchmark.as_ref().unwrap().tie_group, Some("1-2".to_string()));
+		assert_eq!(records[2].benchmark.as_ref().unwrap().tie_group, None);
+	}
+
+	#[test]
+	fn test_unexpected_nxdomain_skips_nxdomain_set() {
+		let set = |total_count, nxdomain| SetStats {
+			total_count,
+			rcodes: RcodeCounts { nxdomain, ..Default::default() },
+			..Default::default()
+		};
+		let categories = std::collections::BTreeMap::from([
+			("cached".to_string(), set(100, 2)),
+			("uncached".to_string(), set(100, 9)),
+			(crate::domains::NXDOMAIN_SET.to_string(), set(50, 0)),
+		]);
+		assert_eq!(unexpected_nxdomain(&categories), (11, 200));
+		assert_eq!(unexpected_nxdomain(&Default::default()), (0, 0));
+	}
+
+	#[test]
+	fn test_rcode_counts() {
+		use hickory_proto::op::ResponseCode;
+		let mut counts = RcodeCounts::default();
+		for rcode in [ResponseCode::NoError, ResponseCode::ServFail, ResponseCode::ServFail, ResponseCode::Refused, ResponseCode::NotImp] {
+			counts.record(rcode);
+		}
+		assert_eq!(counts, RcodeCounts { servfail: 2, refused: 1, nxdomain: 0, other: 1 });
+		assert_eq!(counts.total(), 4);
+		assert_eq!(counts.summary(), "SERVFAIL 2, REFUSED 1, other 1");
+		counts.add(&RcodeCounts { nxdomain: 3, ..Default::default() });
+		assert_eq!(counts.summary(), "SERVFAIL 2, REFUSED 1, NXDOMAIN 3, other 1");
+		assert_eq!(RcodeCounts::default().summary(), "");
+	}
+
+	#[test]
+	fn test_failure_counts() {
+		use crate::transport::QueryOutcome;
+		let mut counts = FailureCounts::default();
+		for outcome in [
+			QueryOutcome::Timeout, QueryOutcome::NetworkError(std::io::ErrorKind::ConnectionRefused),
+			QueryOutcome::NetworkError(std::io::ErrorKind::ConnectionReset), QueryOutcome::ParseError,
+			QueryOutcome::Rcode(hickory_proto::op::ResponseCode::ServFail), QueryOutcome::Ok,
+		] {
+			counts.record(&outcome);
+		}
+		assert_eq!(counts, FailureCounts { network_error: 2, parse_error: 1, txid_mismatch: 0 });
+		counts.add(&FailureCounts { txid_mismatch: 1, ..Default::default() });
+		assert_eq!(counts.total(), 4);
+		assert_eq!(counts.summary(), "network error 2, parse error 1, txid mismatch 1");
+		assert_eq!(FailureCounts::default().summary(), "");
+	}
 }
@@ -0,0 +1,315 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Result};
+use tokio::sync::Semaphore;
+
+use crate::transport::{Resolver, DEFAULT_DNSSEC, DEFAULT_TIMEOUT_MS};
+
+/// Default peak offered rate in queries per second
+pub const DEFAULT_STRESS_TARGET_QPS: u32 = 500;
+/// Highest --target-qps accepted
+pub const STRESS_MAX_TARGET_QPS: u32 = 100_000;
+/// Load steps in the ramp, evenly spaced from target/STEPS up to the target
+const STRESS_STEPS: u32 = 10;
+/// Queries in flight at once, so an overloaded resolver cannot exhaust file
+/// descriptors; a send that finds them all taken is dropped, not delayed
+const STRESS_MAX_INFLIGHT: usize = 1024;
+/// Highest timeout fraction a sustained step may show
+const STRESS_MAX_TIMEOUT_RATE: f64 = 0.01;
+/// Answered rate must reach this fraction of the offered rate to count as sustained
+const STRESS_MIN_ANSWERED_FRACTION: f64 = 0.95;
+/// p50 may grow by this factor over the first step before the knee
+const STRESS_KNEE_INFLATION: f64 = 2.0;
+/// Absolute slack (ms) on the knee limit so sub-millisecond baselines are not over-strict
+const STRESS_KNEE_SLACK_MS: f64 = 5.0;
+/// Stop ramping once a step times out this often; the resolver is saturated
+const STRESS_ABORT_TIMEOUT_RATE: f64 = 0.5;
+
+/// Measurements for one load step.
+#[derive(Debug, Clone)]
+pub struct StepStats {
+	/// Offered rate in queries per second
+	pub offered_qps: u32,
+	pub sent: usize,
+	/// Scheduled sends skipped because STRESS_MAX_INFLIGHT queries were outstanding
+	pub dropped: usize,
+	pub answered: usize,
+	pub timeouts: usize,
+	/// Answered queries per second of step time
+	pub achieved_qps: f64,
+	pub p50_ms: Option<f64>,
+	pub p95_ms: Option<f64>,
+}
+
+impl StepStats {
+	/// Fraction of sent queries that timed out.
+	fn timeout_rate(&self) -> f64 {
+		if self.sent == 0 {
+			return 0.0;
+		}
+		self.timeouts as f64 / self.sent as f64
+	}
+
+	/// Fraction of scheduled sends that were dropped or timed out.
+	fn failure_rate(&self) -> f64 {
+		let scheduled = self.sent + self.dropped;
+		if scheduled == 0 {
+			return 0.0;
+		}
+		(self.timeouts + self.dropped) as f64 / scheduled as f64
+	}
+
+	/// Whether the resolver kept up with the offered rate at this step.
+	pub fn is_sustained(&self) -> bool {
+		self.sent > 0
+			&& self.dropped == 0
+			&& self.timeout_rate() <= STRESS_MAX_TIMEOUT_RATE
+			&& self.achieved_qps >= self.offered_qps as f64 * STRESS_MIN_ANSWERED_FRACTION
+	}
+}
+
+//============================================
+/// Parse a --duration value: seconds with an optional s, m, or h suffix.
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+	let trimmed = value.trim();
+	let (digits, scale) = match trimmed.chars().last() {
+		Some('s') => (&trimmed[..trimmed.len() - 1], 1),
+		Some('m') => (&trimmed[..trimmed.len() - 1], 60),
+		Some('h') => (&trimmed[..trimmed.len() - 1], 3600),
+		_ => (trimmed, 1),
+	};
+	match digits.parse::<u64>() {
+		Ok(n) if n > 0 => Ok(Duration::from_secs(n * scale)),
+		_ => Err(format!("expected a duration like 30s, 2m, or 1h, got '{}'", value)),
+	}
+}
+
+/// Offered rate at each ramp step, evenly spaced up to `target_qps`.
+fn step_rates(target_qps: u32) -> Vec<u32> {
+	let mut rates: Vec<u32> = (1..=STRESS_STEPS)
+		.map(|i| (target_qps as u64 * i as u64 / STRESS_STEPS as u64).max(1) as u32)
+		.collect();
+	rates.dedup();
+	rates
+}
+
+//============================================
+/// Highest answered rate among sustained steps.
+pub fn max_sustained_qps(steps: &[StepStats]) -> Option<f64> {
+	steps.iter()
+		.filter(|s| s.is_sustained())
+		.map(|s| s.achieved_qps)
+		.max_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+}
+
+/// Index of the knee: the first step that is not sustained or whose p50
+/// exceeds the first step's by STRESS_KNEE_INFLATION (plus slack).
+///
+/// None when latency stays flat across the whole ramp.
+pub fn find_knee(steps: &[StepStats]) -> Option<usize> {
+	let baseline = steps.first()?.p50_ms?;
+	let limit = baseline * STRESS_KNEE_INFLATION + STRESS_KNEE_SLACK_MS;
+	steps.iter().position(|s| !s.is_sustained() || s.p50_ms.is_none_or(|p50| p50 > limit))
+}
+
+//============================================
+/// Send queries at `qps` for `length`, open loop, and measure the step.
+///
+/// Sends are paced on a fixed schedule regardless of answers, so a slow
+/// resolver shows up as rising latency and timeouts rather than as a
+/// silently lower send rate. A send due while STRESS_MAX_INFLIGHT queries
+/// are outstanding is counted as dropped instead of waiting for a slot.
+async fn run_step(
+	resolver: &Resolver,
+	domains: &[String],
+	qps: u32,
+	length: Duration,
+	connections: &Arc<crate::bench::ConnectionPool>,
+) -> StepStats {
+	let timeout = Duration::from_millis(DEFAULT_TIMEOUT_MS);
+	let semaphore = Arc::new(Semaphore::new(STRESS_MAX_INFLIGHT));
+	let total = ((qps as f64) * length.as_secs_f64()).round().max(1.0) as usize;
+	let gap = Duration::from_secs_f64(1.0 / qps as f64);
+	let start = Instant::now();
+	let mut handles = Vec::new();
+	let mut dropped = 0;
+	for i in 0..total {
+		tokio::time::sleep_until((start + gap * i as u32).into()).await;
+		let Ok(permit) = semaphore.clone().try_acquire_owned() else {
+			dropped += 1;
+			continue;
+		};
+		let resolver = resolver.clone();
+		let domain = domains[i % domains.len()].clone();
+		let connections = connections.clone();
+		handles.push(tokio::spawn(async move {
+			let _permit = permit;
+			crate::bench::probe_query(&resolver, &domain, timeout, DEFAULT_DNSSEC, &connections).await
+		}));
+	}
+
+	let mut latencies = Vec::new();
+	let mut timeouts = 0;
+	for handle in handles {
+		match handle.await {
+			Ok(result) if !result.outcome.no_answer() => latencies.push(result.latency.as_secs_f64() * 1000.0),
+			_ => timeouts += 1,
+		}
+	}
+	latencies.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+	StepStats {
+		offered_qps: qps,
+		sent: total - dropped,
+		dropped,
+		answered: latencies.len(),
+		timeouts,
+		achieved_qps: latencies.len() as f64 / length.as_secs_f64(),
+		p50_ms: crate::stats::percentile(&latencies, 50.0),
+		p95_ms: crate::stats::percentile(&latencies, 95.0),
+	}
+}
+
+//============================================
+/// Print the latency-vs-load curve, sustained maximum, and knee.
+fn print_stress_report(resolver: &Resolver, steps: &[StepStats]) {
+	let fmt_ms = |v: Option<f64>| v.map(|ms| format!("{:.1} ms", ms)).unwrap_or_else(|| "-".to_string());
+	println!("\nStress results for {}", resolver.label);
+	println!("  {:>8} {:>9} {:>8} {:>8} {:>10} {:>10} {:>9}", "Offered", "Answered", "Timeout", "Dropped", "p50", "p95", "Status");
+	for s in steps {
+		let status = if s.is_sustained() { "ok" } else { "overload" };
+		println!("  {:>8} {:>9.1} {:>7.1}% {:>8} {:>10} {:>10} {:>9}",
+			s.offered_qps, s.achieved_qps, s.timeout_rate() * 100.0, s.dropped,
+			fmt_ms(s.p50_ms), fmt_ms(s.p95_ms), status);
+	}
+	if steps.iter().any(|s| s.dropped > 0) {
+		println!("  Dropped sends found {} queries already in flight; the resolver answers too slowly for the offered rate.",
+			STRESS_MAX_INFLIGHT);
+	}
+	match max_sustained_qps(steps) {
+		Some(qps) => println!("  Max sustained: {:.0} QPS", qps),
+		None => println!("  Max sustained: none (the lowest step already overloaded)"),
+	}
+	match find_knee(steps) {
+		Some(0) => println!("  Knee: below the first step ({} QPS)", steps[0].offered_qps),
+		Some(i) => println!("  Knee: between {} and {} QPS (latency or loss rises sharply above {} QPS)",
+			steps[i - 1].offered_qps, steps[i].offered_qps, steps[i - 1].offered_qps),
+		None => println!("  Knee: not reached; latency stayed flat up to {} QPS",
+			steps.last().map(|s| s.offered_qps).unwrap_or(0)),
+	}
+}
+
+//============================================
+/// Ramp query rate against one resolver and report its capacity.
+///
+/// The duration is split evenly over STRESS_STEPS rate steps from
+/// target/STRESS_STEPS up to `target_qps`. Queries rotate through the
+/// cached set, so the test measures answering capacity rather than
+/// upstream recursion. The ramp stops early once STRESS_ABORT_TIMEOUT_RATE
+/// of a step's scheduled queries time out or are dropped.
+///
+/// Only loopback and private addresses are loaded unless `allow_public` is
+/// set, so a typo cannot point the ramp at someone else's resolver.
+pub async fn run_stress(resolver: &Resolver, duration: Duration, target_qps: u32, allow_public: bool) -> Result<()> {
+	if crate::atlas::publicly_routable(resolver.addr.ip()) && !allow_public {
+		bail!(
+			"{} is a public address; stress only loads loopback and private resolvers unless --allow-public is given",
+			resolver.addr.ip(),
+		);
+	}
+	if target_qps > STRESS_MAX_TARGET_QPS {
+		bail!("--target-qps {} is above the limit of {}", target_qps, STRESS_MAX_TARGET_QPS);
+	}
+	let domains = crate::domains::load_default_query_domains()
+		.remove("cached")
+		.filter(|d| !d.is_empty())
+		.unwrap_or_else(|| vec!["google.com".to_string()]);
+	let connections = Arc::new(crate::bench::build_connection_pool(std::slice::from_ref(resolver), &Default::default()));
+	let rates = step_rates(target_qps);
+	let step_length = duration / rates.len() as u32;
+
+	crate::info!("Stress test: {} ({}) up to {} QPS over {} s in {} steps",
+		resolver.label, resolver.transport, target_qps, duration.as_secs(), rates.len());
+	crate::info!("Only run this against resolvers you operate.");
+
+	let mut steps = Vec::new();
+	for &qps in &rates {
+		let step = run_step(resolver, &domains, qps, step_length, &connections).await;
+		crate::info!("  {:>6} QPS offered: {:.1} answered/s, {} timeouts, {} dropped",
+			qps, step.achieved_qps, step.timeouts, step.dropped);
+		let saturated = step.failure_rate() >= STRESS_ABORT_TIMEOUT_RATE;
+		steps.push(step);
+		if saturated {
+			crate::info!("  Stopping ramp: resolver saturated");
+			break;
+		}
+	}
+	print_stress_report(resolver, &steps);
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn step(offered_qps: u32, achieved_qps: f64, timeouts: usize, p50: f64) -> StepStats {
+		StepStats {
+			offered_qps,
+			sent: 100,
+			dropped: 0,
+			answered: 100 - timeouts,
+			timeouts,
+			achieved_qps,
+			p50_ms: Some(p50),
+			p95_ms: Some(p50 * 2.0),
+		}
+	}
+
+	#[test]
+	fn test_parse_duration() {
+		assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+		assert_eq!(parse_duration("2m"), Ok(Duration::from_secs(120)));
+		assert_eq!(parse_duration("45"), Ok(Duration::from_secs(45)));
+		assert!(parse_duration("0s").is_err());
+		assert!(parse_duration("fast").is_err());
+	}
+
+	#[test]
+	fn test_step_rates_ramp_to_target() {
+		assert_eq!(step_rates(500), vec![50, 100, 150, 200, 250, 300, 350, 400, 450, 500]);
+		// Low targets collapse duplicate steps
+		assert_eq!(step_rates(3), vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn test_knee_and_max_sustained() {
+		let steps = vec![
+			step(100, 100.0, 0, 2.0),
+			step(200, 199.0, 0, 3.0),
+			step(300, 298.0, 1, 8.0),
+			step(400, 310.0, 20, 40.0),
+		];
+		assert_eq!(max_sustained_qps(&steps), Some(298.0));
+		// 8 ms is within 2x2+5 = 9 ms; the overloaded step is the knee
+		assert_eq!(find_knee(&steps), Some(3));
+
+		let flat = vec![step(100, 100.0, 0, 2.0), step(200, 200.0, 0, 2.5)];
+		assert_eq!(find_knee(&flat), None);
+
+		// Drops mean the in-flight cap was reached: not sustained
+		let mut capped = step(2000, 1990.0, 0, 2.0);
+		capped.dropped = 10;
+		assert!(!capped.is_sustained());
+		assert!((capped.failure_rate() - 10.0 / 110.0).abs() < 1e-9);
+	}
+
+	#[tokio::test]
+	async fn test_public_target_needs_override() {
+		let public = crate::resolver::parse_resolver("9.9.9.9").unwrap();
+		let err = run_stress(&public, Duration::from_secs(1), 10, false).await.unwrap_err();
+		assert!(err.to_string().contains("--allow-public"));
+		let local = crate::resolver::parse_resolver("127.0.0.1").unwrap();
+		let err = run_stress(&local, Duration::from_secs(1), STRESS_MAX_TARGET_QPS + 1, false).await.unwrap_err();
+		assert!(err.to_string().contains("limit"));
+	}
+}
@@ -1,7 +1,7 @@
 use std::fs::{File, OpenOptions};
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use std::sync::{Arc, Mutex};
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
 
 /// JSONL telemetry logger for benchmark runs.
 /// Writes one JSON object per line to dns_benchmark.jsonl.
@@ -23,7 +23,12 @@ fn timestamp_iso() -> String {
 	let now = SystemTime::now()
 		.duration_since(SystemTime::UNIX_EPOCH)
 		.unwrap_or_default();
-	let secs = now.as_secs();
+	format_timestamp_iso(now.as_secs())
+}
+
+//============================================
+/// Format seconds since the Unix epoch as an ISO 8601 UTC timestamp.
+pub(crate) fn format_timestamp_iso(secs: u64) -> String {
 	// Simple UTC timestamp without chrono dependency
 	let days = secs / 86400;
 	let time_secs = secs % 86400;
@@ -35,8 +40,31 @@ fn timestamp_iso() -> String {
 	format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, hours, minutes, seconds)
 }
 
+/// Parse an ISO 8601 UTC timestamp in the `format_timestamp_iso` form
+/// (`2026-01-01T12:00:00Z`) to seconds since the Unix epoch.
+pub(crate) fn parse_timestamp_iso(text: &str) -> Option<u64> {
+	let (date, time) = text.trim().strip_suffix('Z')?.split_once('T')?;
+	let fields = |s: &str, sep: char| -> Option<[u64; 3]> {
+		let parts: Vec<u64> = s.split(sep).map(|p| p.parse().ok()).collect::<Option<_>>()?;
+		parts.try_into().ok()
+	};
+	let [year, month, day] = fields(date, '-')?;
+	let [hours, minutes, seconds] = fields(time, ':')?;
+	if year < 1970 || !(1..=12).contains(&month) || hours > 23 || minutes > 59 || seconds > 59 {
+		return None;
+	}
+	let lengths = month_lengths(year);
+	if day == 0 || day > lengths[month as usize - 1] {
+		return None;
+	}
+	let days = (1970..year).map(|y| if is_leap(y) { 366 } else { 365 }).sum::<u64>()
+		+ lengths[..month as usize - 1].iter().sum::<u64>()
+		+ day - 1;
+	Some(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
 //============================================
-fn days_to_ymd(days: u64) -> (u64, u64, u64) {
+pub(crate) fn days_to_ymd(days: u64) -> (u64, u64, u64) {
 	// Convert days since Unix epoch to (year, month, day)
 	let mut y = 1970;
 	let mut remaining = days;
@@ -48,13 +76,8 @@ fn days_to_ymd(days: u64) -> (u64, u64, u64) {
 		remaining -= days_in_year;
 		y += 1;
 	}
-	let month_days: [u64; 12] = if is_leap(y) {
-		[31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-	} else {
-		[31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
-	};
 	let mut m = 0;
-	for md in &month_days {
+	for md in &month_lengths(y) {
 		if remaining < *md {
 			break;
 		}
@@ -64,6 +87,15 @@ fn days_to_ymd(days: u64) -> (u64, u64, u64) {
 	(y, m + 1, remaining + 1)
 }
 
+/// Days in each month of year `y`.
+fn month_lengths(y: u64) -> [u64; 12] {
+	if is_leap(y) {
+		[31, 29, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+	} else {
+		[31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31]
+	}
+}
+
 //============================================
 fn is_leap(y: u64) -> bool {
 	(y.is_multiple_of(4) && !y.is_multiple_of(100)) || y.is_multiple_of(400)
@@ -71,7 +103,7 @@ fn is_leap(y: u64) -> bool {
 
 //============================================
 /// Escape a string for JSON output (handles quotes, backslashes, control chars).
-fn json_escape(s: &str) -> String {
+pub(crate) fn json_escape(s: &str) -> String {
 	let mut out = String::with_capacity(s.len());
 	for c in s.chars() {
 		match c {
@@ -151,6 +183,19 @@ impl TelemetryLog {
 		self.write_line(&line);
 	}
 
+	//============================================
+	/// Log one concurrency level measured by the auto-tuner.
+	pub fn log_autotune_level(&self, level: usize, p50_ms: f64, timeouts: usize,
+		send_errors: usize, rcvbuf_drops: u64, sustainable: bool,
+	) {
+		let ts = timestamp_iso();
+		let line = format!(
+			r#"{{"event":"autotune_level","timestamp":"{}","level":{},"p50_ms":{:.1},"timeouts":{},"send_errors":{},"rcvbuf_drops":{},"sustainable":{}}}"#,
+			ts, level, p50_ms, timeouts, send_errors, rcvbuf_drops, sustainable
+		);
+		self.write_line(&line);
+	}
+
 	//============================================
 	/// Log completion of a benchmark round.
 	pub fn log_round_complete(&self, round: u32, queries: usize, failures: usize) {
@@ -251,4 +296,151 @@ impl TelemetryLog {
 		self.write_line(&line);
 	}
 
+	//============================================
+	/// Log host clock synchronization status.
+	pub fn log_clock(&self, status: &crate::clock::ClockStatus) {
+		let ts = timestamp_iso();
+		let line = format!(r#"{{"event":"clock","timestamp":"{}",{}}}"#, ts, clock_fields_json(status));
+		self.write_line(&line);
+	}
+
+}
+
+/// JSON number or null for an optional value.
+fn json_opt<T: std::fmt::Display>(value: Option<T>) -> String {
+	value.map(|v| v.to_string()).unwrap_or_else(|| "null".to_string())
+}
+
+/// Clock status as JSON object fields (without braces).
+fn clock_fields_json(status: &crate::clock::ClockStatus) -> String {
+	format!(
+		r#""clock_source":"{}","clock_synchronized":{},"ntp_offset_us":{},"ntp_max_error_us":{},"ntp_est_error_us":{}"#,
+		status.source, json_opt(status.synchronized), json_opt(status.offset_us),
+		json_opt(status.max_error_us), json_opt(status.est_error_us),
+	)
+}
+
+/// Per-query raw export (--raw-output), one JSON object per line.
+///
+/// The first line is an `"event":"clock"` header with the run start time
+/// and host clock sync status. Every query line carries its wall-clock send
+/// time plus a monotonic offset from the run start; the offsets are immune
+/// to clock steps during the run, the wall times line up with external logs.
+#[derive(Clone)]
+pub struct RawQueryLog {
+	file: Arc<Mutex<Option<BufWriter<File>>>>,
+	start: Instant,
+}
+
+impl std::fmt::Debug for RawQueryLog {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let enabled = self.file.lock().map(|g| g.is_some()).unwrap_or(false);
+		write!(f, "RawQueryLog(enabled={})", enabled)
+	}
+}
+
+impl RawQueryLog {
+	//============================================
+	/// A logger that writes nothing.
+	pub fn disabled() -> Self {
+		RawQueryLog { file: Arc::new(Mutex::new(None)), start: Instant::now() }
+	}
+
+	//============================================
+	/// Create the raw export file and write the clock header line.
+	pub fn create(path: &str, clock: &crate::clock::ClockStatus) -> std::io::Result<Self> {
+		let mut writer = BufWriter::new(File::create(path)?);
+		let start_wall = SystemTime::now();
+		writeln!(writer,
+			r#"{{"event":"clock","start_time":"{}","start_unix_ms":{},{}}}"#,
+			crate::clock::format_iso_ms(start_wall),
+			crate::clock::since_epoch(start_wall).as_millis(),
+			clock_fields_json(clock),
+		)?;
+		Ok(RawQueryLog { file: Arc::new(Mutex::new(Some(writer))), start: Instant::now() })
+	}
+
+	//============================================
+	/// Whether queries are being recorded.
+	pub fn is_enabled(&self) -> bool {
+		self.file.lock().map(|g| g.is_some()).unwrap_or(false)
+	}
+
+	//============================================
+	/// Record one query sent at `sent_wall` / `sent_mono`.
+	#[allow(clippy::too_many_arguments)]
+	pub fn log_query(&self, sent_wall: SystemTime, sent_mono: Instant, round: u32,
+		resolver: &str, transport: &str, set: &str, domain: &str, query_type: &str,
+		result: &crate::transport::QueryResult,
+	) {
+		let Ok(mut guard) = self.file.lock() else { return };
+		let Some(ref mut f) = *guard else { return };
+		let offset_ms = sent_mono.saturating_duration_since(self.start).as_secs_f64() * 1000.0;
+		let _ = writeln!(f,
+			r#"{{"event":"query","time":"{}","unix_ms":{},"mono_offset_ms":{:.3},"round":{},"resolver":"{}","transport":"{}","set":"{}","domain":"{}","qtype":"{}","latency_ms":{:.3},"outcome":"{}","success":{},"timeout":{},"attempts":{},"ttl":{},"late_ms":{},"duplicates":{}}}"#,
+			crate::clock::format_iso_ms(sent_wall),
+			crate::clock::since_epoch(sent_wall).as_millis(),
+			offset_ms, round, json_escape(resolver), transport, json_escape(set),
+			json_escape(domain), query_type, result.latency.as_secs_f64() * 1000.0,
+			result.outcome, result.outcome.is_ok(), result.outcome.no_answer(), result.attempts, json_opt(result.ttl),
+			json_opt(result.late_latency.map(|d| format!("{:.3}", d.as_secs_f64() * 1000.0))),
+			result.duplicates,
+		);
+	}
+
+	//============================================
+	/// Flush buffered lines to disk.
+	pub fn flush(&self) {
+		if let Ok(mut guard) = self.file.lock() {
+			if let Some(ref mut f) = *guard {
+				let _ = f.flush();
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_timestamp_iso_round_trip() {
+		for secs in [0, 951_782_400, 1_767_268_800, 1_792_137_600 + 86_399] {
+			assert_eq!(parse_timestamp_iso(&format_timestamp_iso(secs)), Some(secs));
+		}
+		assert_eq!(parse_timestamp_iso("2026-01-01T12:00:00Z"), Some(1_767_268_800));
+		assert_eq!(parse_timestamp_iso("2025-02-29T00:00:00Z"), None);
+		assert_eq!(parse_timestamp_iso("2026-01-01 12:00:00"), None);
+	}
+
+	#[test]
+	fn test_raw_query_log_lines_are_json() {
+		let path = std::env::temp_dir().join(format!("raw_log_test_{}.jsonl", std::process::id()));
+		let path_str = path.to_str().unwrap();
+		let clock = crate::clock::ClockStatus { synchronized: Some(true), offset_us: Some(-42), ..Default::default() };
+		let log = RawQueryLog::create(path_str, &clock).unwrap();
+		let result = crate::transport::QueryResult {
+			resolver: "1.1.1.1".to_string(),
+			latency: std::time::Duration::from_millis(12),
+			outcome: crate::transport::QueryOutcome::NetworkError(std::io::ErrorKind::ConnectionRefused),
+			ttl: None,
+			attempts: 1,
+			late_latency: None,
+			duplicates: 0,
+		};
+		log.log_query(SystemTime::now(), Instant::now(), 1, "1.1.1.1", "UDP", "cached",
+			"example.com", "A", &result);
+		log.flush();
+		let text = std::fs::read_to_string(&path).unwrap();
+		std::fs::remove_file(&path).unwrap();
+		let lines: Vec<serde_json::Value> = text.lines()
+			.map(|l| serde_json::from_str(l).unwrap())
+			.collect();
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[0]["ntp_offset_us"], -42);
+		assert_eq!(lines[1]["domain"], "example.com");
+		assert!(lines[1]["ttl"].is_null());
+		assert_eq!(lines[1]["outcome"], "network_error:connection_refused");
+		assert_eq!(lines[1]["timeout"], true);
+	}
 }
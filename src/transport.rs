@@ -16,6 +16,21 @@ pub const DEFAULT_QUERY_AAAA: bool = true;
 pub const DEFAULT_DNSSEC: bool = true;
 pub const DEFAULT_INCLUDE_SYSTEM_RESOLVERS: bool = true;
 pub const DEFAULT_SORT: &str = "score";
+// UDP retransmissions per query (0 = a lost packet is a timeout)
+pub const DEFAULT_UDP_RETRIES: u32 = 0;
+pub const MAX_UDP_RETRIES: u32 = 5;
+// Benchmark queries in flight to any one resolver IP (its pipeline's
+// window), so a burst cannot trip a single resolver's rate limiter
+pub const DEFAULT_PER_RESOLVER_INFLIGHT: usize = 8;
+// Adaptive timeout (--adaptive-timeout): multiple of a resolver's p99, the
+// lowest timeout it may reach, and the answers needed before tightening
+pub const ADAPTIVE_TIMEOUT_FACTOR: f64 = 3.0;
+pub const ADAPTIVE_TIMEOUT_FLOOR_MS: u64 = 100;
+pub const ADAPTIVE_TIMEOUT_MIN_SAMPLES: usize = 20;
+// Consecutive timeouts, with no answer yet in the round, after which a
+// resolver's pipeline stops sending: one full in-flight window. An
+// unreachable error stops it at once.
+pub const DEFAULT_ABANDON_TIMEOUTS: usize = DEFAULT_PER_RESOLVER_INFLIGHT;
 // Level-specific round defaults
 pub const DEFAULT_QUICK_ROUNDS: u32 = 3;
 pub const DEFAULT_MEDIUM_ROUNDS: u32 = 5;
@@ -26,6 +41,10 @@ pub const DEFAULT_MEDIUM_BUDGET: usize = 200;
 // Slow mode: purge ratio and minimum finalist floor
 pub const DEFAULT_SLOW_PURGE_RATIO: f64 = 0.5;
 pub const DEFAULT_SLOW_FINALIST_MIN: usize = 250;
+// Discovery: target survivor count after the final progressive wave
+pub const DEFAULT_DISCOVERY_TOP_N: usize = 50;
+// Discovery: queries per resolver in the second progressive wave
+pub const DEFAULT_DISCOVERY_WAVE2_QUERIES: usize = 5;
 
 /// DNS transport protocol
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -41,9 +60,31 @@ pub enum DnsTransport {
 	Doh {
 		/// Full HTTPS URL (e.g. "https://1.1.1.1/dns-query")
 		url: String,
+		/// Per-resolver HTTP method and header customization
+		options: DohOptions,
 	},
 }
 
+/// HTTP method used for DoH requests (RFC 8484 allows both)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DohMethod {
+	/// POST with the DNS message as the request body (default)
+	#[default]
+	Post,
+	/// GET with the DNS message base64url-encoded in the dns= parameter
+	Get,
+}
+
+/// Per-resolver DoH request customization for gateways that require
+/// a specific method, auth headers, or content negotiation.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DohOptions {
+	pub method: DohMethod,
+	/// Extra HTTP headers; a header named Accept or Content-Type
+	/// replaces the application/dns-message default
+	pub headers: Vec<(String, String)>,
+}
+
 impl fmt::Display for DnsTransport {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
@@ -55,7 +96,8 @@ impl fmt::Display for DnsTransport {
 }
 
 /// Canonical identity and metadata for a single DNS resolver.
-/// IP address is the true key; label is display metadata.
+/// Address, port, and transport are the true key (see `Resolver::key`);
+/// label is display metadata.
 #[derive(Debug, Clone)]
 pub struct Resolver {
 	pub label: String,
@@ -64,10 +106,13 @@ pub struct Resolver {
 	pub transport: DnsTransport,
 	/// Cached resolver classification: "system", "private", or "public"
 	pub class: &'static str,
-	/// Whether this resolver came from the system's /etc/resolv.conf
+	/// Whether this resolver came from the system's DNS configuration
 	pub is_system: bool,
 	/// Reverse DNS (PTR) hostname for the resolver IP
 	pub ptr_name: Option<String>,
+	/// Hostname the address was resolved from at startup ("dns.quad9.net"
+	/// given as a resolver); None when the address was given directly
+	pub hostname: Option<String>,
 	/// Pre-existing DNSSEC validation status from CSV metadata (not from this run)
 	pub declared_dnssec: Option<bool>,
 	/// ISO 2-letter country code from public-dns.info metadata
@@ -76,6 +121,14 @@ pub struct Resolver {
 	pub as_org: Option<String>,
 	/// Reliability score (0.0-1.0) from public-dns.info
 	pub reliability: Option<f64>,
+	/// Provider group from a resolver file `provider=` tag
+	pub provider: Option<String>,
+	/// Expected SHA-256 SPKI pins (base64) from resolver file `spki=` tags,
+	/// DoT and DoH only; any one matching passes
+	pub spki_pins: Vec<String>,
+	/// Expected certificate name from a resolver file `tls_name=` tag; also
+	/// sent as the SNI name in the pin check
+	pub tls_name: Option<String>,
 }
 
 //============================================
@@ -90,14 +143,51 @@ impl Resolver {
 			class: "public",
 			is_system: false,
 			ptr_name: None,
+			hostname: None,
 			declared_dnssec: None,
 			country_code: None,
 			as_org: None,
 			reliability: None,
+			provider: None,
+			spki_pins: Vec::new(),
+			tls_name: None,
 		};
 		r.class = resolver_class(&r);
 		r
 	}
+
+	/// Provider this resolver is grouped under: the `provider=` tag, else
+	/// the label up to the first dash ("Cloudflare-DoT" -> "Cloudflare").
+	pub fn provider_key(&self) -> &str {
+		self.provider.as_deref().unwrap_or_else(|| crate::resolver::provider_name(&self.label))
+	}
+
+	/// Aggregation key: transport, address, and port (see `resolver_key`).
+	pub fn key(&self) -> String {
+		resolver_key(self.addr, &self.transport)
+	}
+}
+
+//============================================
+/// Key that tells resolver endpoints apart, written the way a resolver
+/// is given on the command line: "1.1.1.1:53", "tls://1.1.1.1:853", or the
+/// DoH URL. Two resolvers on one IP but different ports or transports
+/// never merge. Keys hold no spaces, so checkpoint lists can hold them.
+pub fn resolver_key(addr: SocketAddr, transport: &DnsTransport) -> String {
+	match transport {
+		DnsTransport::Udp => addr.to_string(),
+		DnsTransport::Dot { .. } => format!("tls://{}", addr),
+		DnsTransport::Doh { url, .. } => url.clone(),
+	}
+}
+
+/// Port a transport uses when none is given: 53, 853, or 443.
+pub fn default_port(transport: &DnsTransport) -> u16 {
+	match transport {
+		DnsTransport::Udp => 53,
+		DnsTransport::Dot { .. } => 853,
+		DnsTransport::Doh { .. } => 443,
+	}
 }
 
 //============================================
@@ -136,11 +226,138 @@ pub(crate) fn resolver_class(resolver: &Resolver) -> &'static str {
 }
 
 /// DNS query type
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum QueryType {
 	A,
 	#[allow(clippy::upper_case_acronyms)]
 	AAAA,
+	/// HTTPS service binding (type 65, RFC 9460)
+	#[allow(clippy::upper_case_acronyms)]
+	HTTPS,
+}
+
+/// How a single DNS query ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryOutcome {
+	/// NOERROR answer
+	Ok,
+	/// No answer before the timeout, after every retransmission
+	Timeout,
+	/// The socket or connection failed: send or receive error, refused or
+	/// reset connection, failed TLS or HTTP exchange
+	NetworkError(std::io::ErrorKind),
+	/// Replies came back but none was a well-formed answer to the question
+	ParseError,
+	/// Answer with an error response code (NXDOMAIN, SERVFAIL, REFUSED, ...)
+	Rcode(hickory_proto::op::ResponseCode),
+	/// Every reply read carried another query's transaction ID
+	TxidMismatchExhausted,
+}
+
+/// I/O error kinds with their text in exports; any other kind is `other`
+const ERROR_KIND_NAMES: [(std::io::ErrorKind, &str); 16] = {
+	use std::io::ErrorKind::*;
+	[
+		(ConnectionRefused, "connection_refused"), (ConnectionReset, "connection_reset"),
+		(ConnectionAborted, "connection_aborted"), (NotConnected, "not_connected"),
+		(HostUnreachable, "host_unreachable"), (NetworkUnreachable, "network_unreachable"),
+		(NetworkDown, "network_down"), (AddrInUse, "addr_in_use"),
+		(AddrNotAvailable, "addr_not_available"), (BrokenPipe, "broken_pipe"),
+		(TimedOut, "timed_out"), (PermissionDenied, "permission_denied"),
+		(InvalidInput, "invalid_input"), (InvalidData, "invalid_data"),
+		(UnexpectedEof, "unexpected_eof"), (Other, "other"),
+	]
+};
+
+/// Response codes exported by name; others are exported as their number
+const RCODE_NAMES: [(hickory_proto::op::ResponseCode, &str); 6] = {
+	use hickory_proto::op::ResponseCode::*;
+	[(NoError, "NOERROR"), (FormErr, "FORMERR"), (ServFail, "SERVFAIL"), (NXDomain, "NXDOMAIN"), (NotImp, "NOTIMP"), (Refused, "REFUSED")]
+};
+
+impl QueryOutcome {
+	/// Outcome of a parsed answer: Ok for NOERROR, else Rcode.
+	pub fn from_rcode(rcode: hickory_proto::op::ResponseCode) -> QueryOutcome {
+		if rcode == hickory_proto::op::ResponseCode::NoError {
+			QueryOutcome::Ok
+		} else {
+			QueryOutcome::Rcode(rcode)
+		}
+	}
+
+	pub fn is_ok(self) -> bool {
+		self == QueryOutcome::Ok
+	}
+
+	/// The network reported the resolver unreachable (ICMP port or host
+	/// unreachable, surfaced as a socket error) rather than staying silent.
+	/// Windows reports ICMP port unreachable on UDP as a connection reset.
+	pub fn is_unreachable(self) -> bool {
+		use std::io::ErrorKind::*;
+		matches!(self, QueryOutcome::NetworkError(ConnectionRefused | ConnectionReset | HostUnreachable | NetworkUnreachable))
+	}
+
+	/// No answer arrived: a timeout, or a network, parse, or transaction ID
+	/// failure. All of these count as timeouts in scoring.
+	pub fn no_answer(self) -> bool {
+		!matches!(self, QueryOutcome::Ok | QueryOutcome::Rcode(_))
+	}
+
+	/// Response code of the answer; None when there was no answer.
+	pub fn rcode(self) -> Option<hickory_proto::op::ResponseCode> {
+		match self {
+			QueryOutcome::Ok => Some(hickory_proto::op::ResponseCode::NoError),
+			QueryOutcome::Rcode(rcode) => Some(rcode),
+			_ => None,
+		}
+	}
+
+	pub fn is_nxdomain(self) -> bool {
+		self == QueryOutcome::Rcode(hickory_proto::op::ResponseCode::NXDomain)
+	}
+
+	/// Parse the text form written by Display, e.g. `rcode:SERVFAIL`.
+	pub fn parse(text: &str) -> Option<QueryOutcome> {
+		match text.split_once(':') {
+			None => match text {
+				"ok" => Some(QueryOutcome::Ok),
+				"timeout" => Some(QueryOutcome::Timeout),
+				"parse_error" => Some(QueryOutcome::ParseError),
+				"txid_mismatch" => Some(QueryOutcome::TxidMismatchExhausted),
+				_ => None,
+			},
+			Some(("network_error", kind)) => ERROR_KIND_NAMES.iter()
+				.find(|(_, name)| *name == kind)
+				.map(|(kind, _)| QueryOutcome::NetworkError(*kind)),
+			Some(("rcode", code)) => RCODE_NAMES.iter()
+				.find(|(_, name)| *name == code)
+				.map(|(rcode, _)| *rcode)
+				.or_else(|| code.parse::<u16>().ok().map(Into::into))
+				.map(QueryOutcome::Rcode),
+			Some(_) => None,
+		}
+	}
+}
+
+/// Short text for exports: `ok`, `timeout`, `network_error:connection_refused`,
+/// `parse_error`, `rcode:SERVFAIL`, or `txid_mismatch`.
+impl fmt::Display for QueryOutcome {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			QueryOutcome::Ok => write!(f, "ok"),
+			QueryOutcome::Timeout => write!(f, "timeout"),
+			QueryOutcome::NetworkError(kind) => {
+				let name = ERROR_KIND_NAMES.iter().find(|(k, _)| k == kind).map(|(_, name)| *name).unwrap_or("other");
+				write!(f, "network_error:{}", name)
+			}
+			QueryOutcome::ParseError => write!(f, "parse_error"),
+			QueryOutcome::Rcode(rcode) => match RCODE_NAMES.iter().find(|(r, _)| r == rcode) {
+				Some((_, name)) => write!(f, "rcode:{}", name),
+				None => write!(f, "rcode:{}", u16::from(*rcode)),
+			},
+			QueryOutcome::TxidMismatchExhausted => write!(f, "txid_mismatch"),
+		}
+	}
 }
 
 /// Result of a single DNS query
@@ -148,8 +365,17 @@ pub enum QueryType {
 pub struct QueryResult {
 	pub resolver: String,
 	pub latency: Duration,
-	pub success: bool,
-	pub timeout: bool,
+	pub outcome: QueryOutcome,
+	/// Minimum answer TTL in seconds; None if no answers or no response
+	pub ttl: Option<u32>,
+	/// Number of sends made (1 = answered or failed on the first attempt)
+	pub attempts: u32,
+	/// For a timed-out UDP query, when the answer finally arrived during the
+	/// late grace period (measured from the first send); None if it never came
+	pub late_latency: Option<Duration>,
+	/// Extra copies of the answer already received when it was read, as sent
+	/// by broken middleboxes or a retransmission answered twice
+	pub duplicates: u32,
 }
 
 /// Benchmark configuration
@@ -157,12 +383,47 @@ pub struct QueryResult {
 pub struct BenchmarkConfig {
 	pub rounds: u32,
 	pub timeout: Duration,
+	/// UDP retransmissions per query within the timeout (exponential backoff)
+	pub retries: u32,
+	/// Extra listening time after a UDP timeout to record late arrivals (--late-grace-ms)
+	pub late_grace: Duration,
 	pub max_inflight: usize,
+	/// Benchmark queries in flight to one resolver IP
+	pub per_resolver_inflight: usize,
+	/// Benchmark queries per second to one resolver IP; None is unlimited
+	pub per_resolver_qps: Option<u32>,
 	pub inter_query_spacing: Duration,
 	pub query_aaaa: bool,
+	/// Also query HTTPS (type 65) records for every domain (--https-rr)
+	pub query_https: bool,
+	/// Keep DoT and DoH connections open between queries, or open one per
+	/// query (--connection-mode)
+	pub connection_mode: crate::cli::ConnectionMode,
+	/// HTTP version of DoH queries (--doh-http)
+	pub doh_http: crate::cli::DohHttpVersion,
+	/// Proxy for DoT and DoH connections (--proxy); UDP always goes direct
+	pub proxy: Option<crate::proxy::ProxyConfig>,
 	pub seed: Option<u64>,
+	/// Zone under which every uncached-set query gets a fresh random label
+	/// (--cold-zone); None queries the set's names as listed
+	pub cold_zone: Option<String>,
+	/// Tighten each resolver's timeout from its observed p99 after every
+	/// round (--adaptive-timeout); scoring still uses `timeout`
+	pub adaptive_timeout: bool,
 	/// Enable DNSSEC (DO bit) on all queries
 	pub dnssec: bool,
+	/// File the completed rounds are saved to after each round (--checkpoint)
+	pub checkpoint: Option<String>,
+	/// Start from the rounds saved in `checkpoint` (--resume)
+	pub resume: bool,
+	/// Hash of the input resolver list, domain sets, and query settings,
+	/// taken before discovery (see `checkpoint::fingerprint`); saved with
+	/// each checkpoint and checked on --resume
+	pub checkpoint_fingerprint: String,
+	/// Shared UDP sockets for benchmark queries (--udp-pool); None binds a socket per query
+	pub udp_pool: Option<std::sync::Arc<crate::udp_pool::UdpSocketPool>>,
+	/// Per-query raw export (--raw-output); disabled when not requested
+	pub raw_log: crate::telemetry::RawQueryLog,
 	/// Enable discovery prefilter mode
 	pub discover: bool,
 	/// Benchmark level
@@ -171,6 +432,57 @@ pub struct BenchmarkConfig {
 	pub max_resolver_ms: f64,
 	/// Sort mode for ranking results
 	pub sort_mode: SortMode,
+	/// Set score weights (tail spread and timeout penalty) and set weights
+	pub score_weights: crate::stats::ScoreWeights,
+	/// Workload profile the weights started from (--profile)
+	pub profile: Option<crate::cli::Profile>,
+	/// Probe server identity after every benchmark round to catch anycast
+	/// PoP changes (--identity-probe)
+	pub identity_rounds: bool,
 	/// Telemetry logger for JSONL debug output
 	pub telemetry: crate::telemetry::TelemetryLog,
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use hickory_proto::op::ResponseCode;
+
+	#[test]
+	fn test_query_outcome_text_round_trip() {
+		let outcomes = [
+			QueryOutcome::Ok, QueryOutcome::Timeout, QueryOutcome::ParseError, QueryOutcome::TxidMismatchExhausted,
+			QueryOutcome::NetworkError(std::io::ErrorKind::ConnectionRefused),
+			QueryOutcome::Rcode(ResponseCode::ServFail), QueryOutcome::Rcode(ResponseCode::BADCOOKIE),
+		];
+		for outcome in outcomes {
+			assert_eq!(QueryOutcome::parse(&outcome.to_string()), Some(outcome), "{}", outcome);
+		}
+		assert_eq!(QueryOutcome::NetworkError(std::io::ErrorKind::ConnectionRefused).to_string(), "network_error:connection_refused");
+		assert_eq!(QueryOutcome::Rcode(ResponseCode::BADCOOKIE).to_string(), "rcode:23");
+		// Kinds without a name are exported as other
+		assert_eq!(QueryOutcome::NetworkError(std::io::ErrorKind::WouldBlock).to_string(), "network_error:other");
+		assert_eq!(QueryOutcome::parse("success"), None);
+
+		assert_eq!(QueryOutcome::from_rcode(ResponseCode::NoError), QueryOutcome::Ok);
+		assert!(QueryOutcome::from_rcode(ResponseCode::NXDomain).is_nxdomain());
+		assert!(QueryOutcome::ParseError.no_answer() && !QueryOutcome::Rcode(ResponseCode::Refused).no_answer());
+		assert_eq!(QueryOutcome::Timeout.rcode(), None);
+		assert!(QueryOutcome::NetworkError(std::io::ErrorKind::HostUnreachable).is_unreachable());
+		assert!(!QueryOutcome::NetworkError(std::io::ErrorKind::AddrInUse).is_unreachable() && !QueryOutcome::Timeout.is_unreachable());
+	}
+
+	#[test]
+	fn test_resolver_key_includes_port_and_transport() {
+		let plain = Resolver::new("127.0.0.1:53".parse().unwrap(), DnsTransport::Udp);
+		let alt = Resolver::new("127.0.0.1:5353".parse().unwrap(), DnsTransport::Udp);
+		let dot = Resolver::new("127.0.0.1:853".parse().unwrap(), DnsTransport::Dot { hostname: String::new() });
+		assert_eq!(plain.key(), "127.0.0.1:53");
+		assert_eq!(alt.key(), "127.0.0.1:5353");
+		assert_eq!(dot.key(), "tls://127.0.0.1:853");
+		let doh = DnsTransport::Doh { url: "https://[::1]/dns-query".to_string(), options: DohOptions::default() };
+		assert_eq!(resolver_key("[::1]:443".parse().unwrap(), &doh), "https://[::1]/dns-query");
+		assert_eq!(resolver_key("[::1]:53".parse().unwrap(), &DnsTransport::Udp), "[::1]:53");
+		assert_eq!(default_port(&doh), 443);
+	}
+}
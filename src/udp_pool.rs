@@ -0,0 +1,422 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use rand::rngs::StdRng;
+use rand::Rng;
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::dns::{build_query, parse_response, response_matches_question};
+use crate::transport::{QueryOutcome, QueryResult, QueryType};
+
+/// Sockets per address family in the shared pool (--udp-pool)
+pub const DEFAULT_UDP_POOL_SOCKETS: usize = 16;
+/// Random txid picks tried before falling back to a linear scan
+const TXID_RANDOM_TRIES: usize = 64;
+/// Responses buffered per waiting query before extras are dropped
+const WAITER_CAPACITY: usize = 4;
+
+/// Demultiplexing key: resolver address and transaction ID.
+type QueryKey = (SocketAddr, u16);
+
+/// Responses that reached a shared socket after their query had finished.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StrayResponses {
+	/// Answers to a query that had already given up, after its late grace period
+	pub late: usize,
+	/// Further copies of an answer the query had already accepted
+	pub duplicate: usize,
+}
+
+/// Routing state for one shared socket.
+#[derive(Default)]
+struct Routing {
+	/// In-flight queries waiting for a response
+	pending: HashMap<QueryKey, mpsc::Sender<Vec<u8>>>,
+	/// Recently finished keys, held back until the given time so a late
+	/// response is never delivered to a new query that reused the txid;
+	/// the flag records whether the query was answered
+	quarantine: HashMap<QueryKey, (Instant, bool)>,
+	/// Responses that arrived for quarantined keys, per resolver
+	strays: HashMap<SocketAddr, StrayResponses>,
+}
+
+/// One socket shared by many in-flight queries.
+struct PooledSocket {
+	socket: UdpSocket,
+	routing: Mutex<Routing>,
+}
+
+//============================================
+/// Pick a txid for `resolver` that is neither in flight nor quarantined.
+///
+/// Tries random txids first (unpredictable to off-path spoofers), then scans
+/// from a random start; None only when all 65536 are taken.
+fn allocate_txid(routing: &Routing, resolver: SocketAddr, mut roll: impl FnMut() -> u16) -> Option<u16> {
+	let free = |txid: u16| {
+		let key = (resolver, txid);
+		!routing.pending.contains_key(&key) && !routing.quarantine.contains_key(&key)
+	};
+	for _ in 0..TXID_RANDOM_TRIES {
+		let txid = roll();
+		if free(txid) {
+			return Some(txid);
+		}
+	}
+	let start = roll();
+	(0..=u16::MAX).map(|i| start.wrapping_add(i)).find(|&txid| free(txid))
+}
+
+impl PooledSocket {
+	//============================================
+	/// Register a waiter for `resolver` under a fresh txid drawn from `rng`.
+	fn register(&self, resolver: SocketAddr, rng: &mut StdRng) -> Option<(u16, mpsc::Receiver<Vec<u8>>)> {
+		let mut routing = self.routing.lock().ok()?;
+		let now = Instant::now();
+		routing.quarantine.retain(|_, (until, _)| *until > now);
+		let txid = allocate_txid(&routing, resolver, || rng.gen())?;
+		let (tx, rx) = mpsc::channel(WAITER_CAPACITY);
+		routing.pending.insert((resolver, txid), tx);
+		Some((txid, rx))
+	}
+
+	/// Remove a waiter and quarantine its key for `hold`.
+	fn release(&self, key: QueryKey, hold: Duration, answered: bool) {
+		if let Ok(mut routing) = self.routing.lock() {
+			routing.pending.remove(&key);
+			routing.quarantine.insert(key, (Instant::now() + hold, answered));
+		}
+	}
+
+	//============================================
+	/// Receive datagrams forever, routing each to its waiter by (source, txid).
+	async fn receive_loop(self: Arc<Self>) {
+		let mut buf = vec![0u8; 4096];
+		loop {
+			let (len, src) = match self.socket.recv_from(&mut buf).await {
+				Ok(r) => r,
+				Err(_) => continue,
+			};
+			if len < 2 {
+				continue;
+			}
+			let txid = u16::from_be_bytes([buf[0], buf[1]]);
+			let Ok(mut routing) = self.routing.lock() else { continue };
+			if let Some(tx) = routing.pending.get(&(src, txid)) {
+				let _ = tx.try_send(buf[..len].to_vec());
+			} else if let Some(&(until, answered)) = routing.quarantine.get(&(src, txid)) {
+				if until <= Instant::now() {
+					continue;
+				}
+				// Too late for its query: count it instead of dropping it unseen
+				let strays = routing.strays.entry(src).or_default();
+				if answered { strays.duplicate += 1; } else { strays.late += 1; }
+			}
+		}
+	}
+}
+
+/// Releases a registered key when the query finishes, however it exits.
+struct Registration<'a> {
+	socket: &'a PooledSocket,
+	key: QueryKey,
+	hold: Duration,
+	/// Set once an answer is accepted, so later copies count as duplicates
+	answered: bool,
+}
+
+impl Drop for Registration<'_> {
+	fn drop(&mut self) {
+		self.socket.release(self.key, self.hold, self.answered);
+	}
+}
+
+/// Shared UDP sockets that multiplex many queries each.
+///
+/// Binding a socket per query costs a file descriptor and several syscalls
+/// per query; at high concurrency that overhead and the descriptor limit
+/// become the bottleneck. The pool binds a fixed set of sockets per address
+/// family and demultiplexes responses by (resolver, txid), then checks the
+/// question section before accepting an answer.
+pub struct UdpSocketPool {
+	v4: Vec<Arc<PooledSocket>>,
+	v6: Vec<Arc<PooledSocket>>,
+	next: AtomicUsize,
+	receivers: Vec<JoinHandle<()>>,
+}
+
+impl std::fmt::Debug for UdpSocketPool {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "UdpSocketPool(v4={}, v6={})", self.v4.len(), self.v6.len())
+	}
+}
+
+impl Drop for UdpSocketPool {
+	fn drop(&mut self) {
+		for handle in &self.receivers {
+			handle.abort();
+		}
+	}
+}
+
+impl UdpSocketPool {
+	//============================================
+	/// Bind `per_family` sockets for IPv4 and, where available, IPv6.
+	pub async fn bind(per_family: usize) -> Result<Self> {
+		let mut pool = UdpSocketPool { v4: Vec::new(), v6: Vec::new(), next: AtomicUsize::new(0), receivers: Vec::new() };
+		for _ in 0..per_family {
+			// A family may be missing: hosts without IPv6, or a --source-ip of the
+			// other family, just cannot reach those resolvers
+			if let Ok(socket) = crate::source::bind_udp(false).await {
+				pool.v4.push(Arc::new(PooledSocket { socket, routing: Mutex::new(Routing::default()) }));
+			}
+			if let Ok(socket) = crate::source::bind_udp(true).await {
+				pool.v6.push(Arc::new(PooledSocket { socket, routing: Mutex::new(Routing::default()) }));
+			}
+		}
+		if pool.v4.is_empty() && pool.v6.is_empty() {
+			return Err(std::io::Error::new(std::io::ErrorKind::AddrNotAvailable, "no UDP socket could be bound").into());
+		}
+		for sock in pool.v4.iter().chain(pool.v6.iter()) {
+			pool.receivers.push(tokio::spawn(sock.clone().receive_loop()));
+		}
+		Ok(pool)
+	}
+
+	/// Total sockets held by the pool.
+	pub fn socket_count(&self) -> usize {
+		self.v4.len() + self.v6.len()
+	}
+
+	/// Next socket for the resolver's address family, round-robin.
+	fn pick(&self, resolver: SocketAddr) -> Option<&Arc<PooledSocket>> {
+		let family = if resolver.is_ipv4() { &self.v4 } else { &self.v6 };
+		if family.is_empty() {
+			return None;
+		}
+		Some(&family[self.next.fetch_add(1, Ordering::Relaxed) % family.len()])
+	}
+
+	//============================================
+	/// Send one query over a pooled socket and wait for its answer.
+	///
+	/// Same timing and retransmission behavior as the per-query socket path:
+	/// timing starts before the first send and `retries` resends share the
+	/// timeout in doubling windows. The txid is drawn from `rng`.
+	#[allow(clippy::too_many_arguments)]
+	pub async fn query(
+		&self,
+		resolver: SocketAddr,
+		domain: &str,
+		query_type: QueryType,
+		dnssec: bool,
+		timeout: Duration,
+		retries: u32,
+		grace: Duration,
+		rng: &mut StdRng,
+	) -> QueryResult {
+		let failed = |outcome: QueryOutcome, attempts: u32, latency: Duration| QueryResult {
+			resolver: resolver.ip().to_string(),
+			latency,
+			outcome,
+			ttl: None,
+			attempts,
+			late_latency: None,
+			duplicates: 0,
+		};
+		// No socket for the address family, or every transaction ID in use
+		let unavailable = QueryOutcome::NetworkError(std::io::ErrorKind::AddrNotAvailable);
+		let sock = match self.pick(resolver) {
+			Some(s) => s,
+			None => return failed(unavailable, 1, timeout),
+		};
+		let (txid, mut rx) = match sock.register(resolver, rng) {
+			Some(r) => r,
+			None => return failed(unavailable, 1, timeout),
+		};
+		let mut registration = Registration { socket: sock, key: (resolver, txid), hold: timeout + grace, answered: false };
+		let query_bytes = match build_query(domain, query_type, txid, dnssec) {
+			Ok(b) => b,
+			Err(_) => return failed(QueryOutcome::NetworkError(std::io::ErrorKind::InvalidInput), 1, Duration::ZERO),
+		};
+
+		let start = Instant::now();
+		let windows = crate::bench::udp_attempt_windows(timeout, retries);
+		let mut attempt_deadline = start;
+		// A reply to this ID that was not a usable answer to the question
+		let mut parse_errors = false;
+		for (attempt, window) in windows.iter().enumerate() {
+			let attempts = attempt as u32 + 1;
+			if let Err(e) = sock.socket.send_to(&query_bytes, resolver).await {
+				return failed(QueryOutcome::NetworkError(e.kind()), attempts, timeout);
+			}
+			attempt_deadline += *window;
+			loop {
+				// Only the fault-injection hook below mutates the datagram
+				#[cfg_attr(not(any(test, feature = "fault-injection")), allow(unused_mut))]
+				let mut bytes = match tokio::time::timeout_at(attempt_deadline.into(), rx.recv()).await {
+					Ok(Some(bytes)) => bytes,
+					// Window expired (or the pool shut down): next attempt
+					_ => break,
+				};
+				#[cfg(any(test, feature = "fault-injection"))]
+				match crate::fault::next_fault() {
+					crate::fault::Fault::Drop => continue,
+					crate::fault::Fault::Delay(d) => tokio::time::sleep(d).await,
+					crate::fault::Fault::Corrupt => crate::fault::corrupt(&mut bytes, rand::random()),
+					crate::fault::Fault::None => {}
+				}
+				let latency = start.elapsed();
+				if !response_matches_question(&bytes, domain, query_type) {
+					continue;
+				}
+				match parse_response(&bytes, txid, domain, query_type) {
+					Ok(response) => {
+						registration.answered = true;
+						return QueryResult {
+							resolver: resolver.ip().to_string(),
+							latency,
+							outcome: QueryOutcome::from_rcode(response.rcode),
+							ttl: response.min_ttl,
+							attempts,
+							late_latency: None,
+							duplicates: drain_duplicates(&mut rx, txid, domain, query_type),
+						};
+					}
+					Err(_) => parse_errors = true,
+				}
+			}
+		}
+		// Timed out; optionally wait out the grace period for a late answer
+		let latency = start.elapsed();
+		let mut late_latency = None;
+		if !grace.is_zero() {
+			let deadline = start + timeout + grace;
+			while let Ok(Some(bytes)) = tokio::time::timeout_at(deadline.into(), rx.recv()).await {
+				if response_matches_question(&bytes, domain, query_type)
+					&& parse_response(&bytes, txid, domain, query_type).is_ok()
+				{
+					late_latency = Some(start.elapsed());
+					registration.answered = true;
+					break;
+				}
+			}
+		}
+		let duplicates = if late_latency.is_some() { drain_duplicates(&mut rx, txid, domain, query_type) } else { 0 };
+		let outcome = if parse_errors { QueryOutcome::ParseError } else { QueryOutcome::Timeout };
+		QueryResult { late_latency, duplicates, ..failed(outcome, windows.len() as u32, latency) }
+	}
+
+	/// Responses per resolver that arrived after their query finished,
+	/// since the last call; the counts are reset.
+	pub fn take_stray_responses(&self) -> HashMap<SocketAddr, StrayResponses> {
+		let mut total: HashMap<SocketAddr, StrayResponses> = HashMap::new();
+		for sock in self.v4.iter().chain(self.v6.iter()) {
+			let Ok(mut routing) = sock.routing.lock() else { continue };
+			for (addr, strays) in routing.strays.drain() {
+				let entry = total.entry(addr).or_default();
+				entry.late += strays.late;
+				entry.duplicate += strays.duplicate;
+			}
+		}
+		total
+	}
+}
+
+/// Answers to this query already routed to its waiter after the one just read.
+fn drain_duplicates(rx: &mut mpsc::Receiver<Vec<u8>>, txid: u16, domain: &str, query_type: QueryType) -> u32 {
+	let mut duplicates = 0;
+	while let Ok(bytes) = rx.try_recv() {
+		if response_matches_question(&bytes, domain, query_type) && parse_response(&bytes, txid, domain, query_type).is_ok() {
+			duplicates += 1;
+		}
+	}
+	duplicates
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_allocate_txid_skips_pending_and_quarantined() {
+		let resolver: SocketAddr = "192.0.2.1:53".parse().unwrap();
+		let other: SocketAddr = "192.0.2.2:53".parse().unwrap();
+		let mut routing = Routing::default();
+		let (tx, _rx) = mpsc::channel(1);
+		routing.pending.insert((resolver, 7), tx);
+		routing.quarantine.insert((resolver, 8), (Instant::now() + Duration::from_secs(5), true));
+		// Every random roll collides, so the scan from 7 must land on 9
+		assert_eq!(allocate_txid(&routing, resolver, || 7), Some(9));
+		// Keys are per resolver: txid 7 is free for a different one
+		assert_eq!(allocate_txid(&routing, other, || 7), Some(7));
+	}
+
+	#[tokio::test]
+	async fn test_pool_demultiplexes_concurrent_queries() {
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		// Responder that answers in reverse arrival order, so replies interleave
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let addr = server.local_addr().unwrap();
+		tokio::spawn(async move {
+			let mut buf = vec![0u8; 512];
+			let mut batch = Vec::new();
+			while let Ok((len, src)) = server.recv_from(&mut buf).await {
+				let mut reply = buf[..len].to_vec();
+				reply[2] |= 0x80;
+				batch.push((reply, src));
+				if batch.len() == 3 {
+					for (reply, src) in batch.drain(..).rev() {
+						let _ = server.send_to(&reply, src).await;
+					}
+				}
+			}
+		});
+
+		let pool = Arc::new(UdpSocketPool::bind(1).await.unwrap());
+		let mut handles = Vec::new();
+		for (seed, domain) in ["a.example", "b.example", "c.example"].into_iter().enumerate() {
+			let pool = pool.clone();
+			handles.push(tokio::spawn(async move {
+				let mut rng = rand::SeedableRng::seed_from_u64(seed as u64);
+				pool.query(addr, domain, QueryType::A, false, Duration::from_millis(500), 0, Duration::ZERO, &mut rng).await
+			}));
+		}
+		for handle in handles {
+			let result = handle.await.unwrap();
+			assert!(result.outcome.is_ok(), "{:?}", result);
+		}
+	}
+
+	#[tokio::test]
+	async fn test_pool_counts_duplicate_answers() {
+		let _lock = crate::fault::TEST_LOCK.lock().await;
+		// Responder that answers every query twice, like a duplicating middlebox
+		let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+		let addr = server.local_addr().unwrap();
+		tokio::spawn(async move {
+			let mut buf = vec![0u8; 512];
+			while let Ok((len, src)) = server.recv_from(&mut buf).await {
+				let mut reply = buf[..len].to_vec();
+				reply[2] |= 0x80;
+				let _ = server.send_to(&reply, src).await;
+				let _ = server.send_to(&reply, src).await;
+			}
+		});
+
+		let pool = UdpSocketPool::bind(1).await.unwrap();
+		let mut rng = rand::SeedableRng::seed_from_u64(1);
+		let result = pool.query(addr, "a.example", QueryType::A, false, Duration::from_millis(500), 0, Duration::ZERO, &mut rng).await;
+		assert!(result.outcome.is_ok(), "{:?}", result);
+		tokio::time::sleep(Duration::from_millis(100)).await;
+		// The copy is either queued when the answer is read or caught after release
+		let strays = pool.take_stray_responses().get(&addr).copied().unwrap_or_default();
+		assert_eq!((result.duplicates as usize + strays.duplicate, strays.late), (1, 0));
+		assert!(pool.take_stray_responses().is_empty());
+	}
+}
@@ -0,0 +1,245 @@
+use crate::transport::BenchmarkConfig;
+
+/// Shortest useful first UDP attempt window; below this, retransmissions
+/// fire before a typical resolver can answer and every query looks lost
+pub const MIN_UDP_ATTEMPT_MS: u64 = 100;
+/// File descriptors reserved for stdio, telemetry, CSV output, and DoH clients
+pub(crate) const FD_RESERVE: u64 = 32;
+
+/// Configuration combinations that would produce confusing runtime behavior.
+///
+/// Checked once before any query is sent so the run fails fast with a
+/// message that names the flag or constant to change.
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum ConfigError {
+	#[error("--rounds must be at least 1")]
+	ZeroRounds,
+
+	#[error("--resume needs --checkpoint FILE to resume from")]
+	ResumeWithoutCheckpoint,
+
+	#[error("--checkpoint does not support --level slow, whose staged elimination drops resolvers between stages")]
+	CheckpointWithStaged,
+
+	#[error("--retries {retries} leaves a {window_ms} ms first attempt inside the {timeout_ms} ms timeout \
+		(minimum {min_ms} ms); use --retries {max_retries} or fewer", min_ms = MIN_UDP_ATTEMPT_MS)]
+	RetryWindowTooShort {
+		retries: u32,
+		window_ms: u64,
+		timeout_ms: u64,
+		max_retries: u32,
+	},
+
+	#[error("query spacing of {spacing_ms} ms (up to {max_delay_ms} ms with jitter) is not below the \
+		{timeout_ms} ms timeout; lower DEFAULT_SPACING_MS or raise DEFAULT_TIMEOUT_MS in src/transport.rs")]
+	SpacingExceedsTimeout {
+		spacing_ms: u64,
+		max_delay_ms: u64,
+		timeout_ms: u64,
+	},
+
+	#[error("up to {sockets} concurrent sockets are needed but the open file limit is {limit}; \
+		raise it with `ulimit -n {wanted}` or lower DEFAULT_CONCURRENCY in src/transport.rs")]
+	FileLimitTooLow {
+		sockets: u64,
+		limit: u64,
+		wanted: u64,
+	},
+}
+
+//============================================
+/// Validate cross-flag constraints before the benchmark starts.
+///
+/// `open_file_limit` is the soft RLIMIT_NOFILE, or None when it cannot be read.
+pub fn validate_config(
+	config: &BenchmarkConfig,
+	open_file_limit: Option<u64>,
+) -> Result<(), ConfigError> {
+	if config.rounds == 0 {
+		return Err(ConfigError::ZeroRounds);
+	}
+	if config.resume && config.checkpoint.is_none() {
+		return Err(ConfigError::ResumeWithoutCheckpoint);
+	}
+	if config.checkpoint.is_some() && config.level == crate::cli::BenchLevel::Slow {
+		return Err(ConfigError::CheckpointWithStaged);
+	}
+
+	// Retries split the timeout into doubling windows; the first must stay usable
+	let timeout_ms = config.timeout.as_millis() as u64;
+	let window_ms = first_attempt_window_ms(timeout_ms, config.retries);
+	if config.retries > 0 && window_ms < MIN_UDP_ATTEMPT_MS {
+		let max_retries = (0..config.retries)
+			.rev()
+			.find(|&r| first_attempt_window_ms(timeout_ms, r) >= MIN_UDP_ATTEMPT_MS)
+			.unwrap_or(0);
+		return Err(ConfigError::RetryWindowTooShort {
+			retries: config.retries,
+			window_ms,
+			timeout_ms,
+			max_retries,
+		});
+	}
+
+	// Each query task sleeps spacing plus up to 50% jitter while holding a slot
+	let spacing_ms = config.inter_query_spacing.as_millis() as u64;
+	let max_delay_ms = spacing_ms + spacing_ms / 2;
+	if spacing_ms > 0 && max_delay_ms >= timeout_ms {
+		return Err(ConfigError::SpacingExceedsTimeout { spacing_ms, max_delay_ms, timeout_ms });
+	}
+
+	// Discovery raises concurrency to at least DISCOVERY_CONCURRENCY
+	let sockets = crate::bench::DISCOVERY_CONCURRENCY.max(config.max_inflight) as u64;
+	if let Some(limit) = open_file_limit {
+		if sockets + FD_RESERVE > limit {
+			return Err(ConfigError::FileLimitTooLow { sockets, limit, wanted: sockets + FD_RESERVE });
+		}
+	}
+
+	Ok(())
+}
+
+//============================================
+/// Non-fatal notes about the configuration, printed before the run.
+pub fn config_warnings(config: &BenchmarkConfig, resolver_count: usize) -> Vec<String> {
+	let mut warnings = Vec::new();
+	let top_n = crate::transport::DEFAULT_DISCOVERY_TOP_N;
+	if config.discover && resolver_count <= top_n {
+		warnings.push(format!(
+			"discovery keeps the top {} resolvers but only {} are loaded; \
+			waves will only drop unreachable resolvers",
+			top_n, resolver_count,
+		));
+	}
+	warnings
+}
+
+/// First UDP attempt window in ms under the benchmark's retry backoff.
+fn first_attempt_window_ms(timeout_ms: u64, retries: u32) -> u64 {
+	let windows = crate::bench::udp_attempt_windows(std::time::Duration::from_millis(timeout_ms), retries);
+	windows[0].as_millis() as u64
+}
+
+//============================================
+/// Soft open file limit from /proc/self/limits (Linux); None elsewhere.
+pub fn open_file_limit() -> Option<u64> {
+	let text = std::fs::read_to_string("/proc/self/limits").ok()?;
+	parse_open_file_limit(&text)
+}
+
+/// Parse the soft "Max open files" value; None for "unlimited" or a missing row.
+fn parse_open_file_limit(limits: &str) -> Option<u64> {
+	let line = limits.lines().find(|l| l.starts_with("Max open files"))?;
+	line.trim_start_matches("Max open files")
+		.split_whitespace()
+		.next()?
+		.parse()
+		.ok()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	fn test_config() -> BenchmarkConfig {
+		BenchmarkConfig {
+			rounds: 3,
+			timeout: Duration::from_millis(2000),
+			retries: 0,
+			late_grace: Duration::ZERO,
+			max_inflight: 64,
+			per_resolver_inflight: 8,
+			per_resolver_qps: None,
+			inter_query_spacing: Duration::from_millis(25),
+			query_aaaa: true,
+			query_https: false,
+			connection_mode: crate::cli::ConnectionMode::Reuse,
+			doh_http: crate::cli::DohHttpVersion::Http2,
+			proxy: None,
+			seed: None,
+			cold_zone: None,
+			adaptive_timeout: false,
+			dnssec: true,
+			checkpoint: None,
+			resume: false,
+			checkpoint_fingerprint: String::new(),
+			discover: false,
+			level: crate::cli::BenchLevel::Quick,
+			max_resolver_ms: 1000.0,
+			sort_mode: crate::stats::SortMode::Score,
+			score_weights: crate::stats::ScoreWeights::DEFAULT,
+			profile: None,
+			identity_rounds: false,
+			telemetry: crate::telemetry::TelemetryLog::new(false),
+			udp_pool: None,
+			raw_log: crate::telemetry::RawQueryLog::disabled(),
+		}
+	}
+
+	#[test]
+	fn test_default_config_is_valid() {
+		assert_eq!(validate_config(&test_config(), Some(1024)), Ok(()));
+	}
+
+	#[test]
+	fn test_zero_rounds_rejected() {
+		let config = BenchmarkConfig { rounds: 0, ..test_config() };
+		assert_eq!(validate_config(&config, None), Err(ConfigError::ZeroRounds));
+	}
+
+	#[test]
+	fn test_checkpoint_combinations() {
+		let config = BenchmarkConfig { resume: true, ..test_config() };
+		assert_eq!(validate_config(&config, None), Err(ConfigError::ResumeWithoutCheckpoint));
+		let config = BenchmarkConfig { checkpoint: Some("run.ckpt".to_string()), resume: true, ..test_config() };
+		assert_eq!(validate_config(&config, None), Ok(()));
+		let config = BenchmarkConfig {
+			checkpoint: Some("run.ckpt".to_string()),
+			level: crate::cli::BenchLevel::Slow,
+			..test_config()
+		};
+		assert_eq!(validate_config(&config, None), Err(ConfigError::CheckpointWithStaged));
+	}
+
+	#[test]
+	fn test_retry_window_suggests_max_retries() {
+		// 2000 ms over 5 retries: 2000 / 63 = 31 ms first window; 3 retries gives 133 ms
+		let config = BenchmarkConfig { retries: 5, ..test_config() };
+		assert_eq!(validate_config(&config, None), Err(ConfigError::RetryWindowTooShort {
+			retries: 5,
+			window_ms: 31,
+			timeout_ms: 2000,
+			max_retries: 3,
+		}));
+	}
+
+	#[test]
+	fn test_spacing_beyond_timeout_rejected() {
+		let config = BenchmarkConfig {
+			timeout: Duration::from_millis(300),
+			inter_query_spacing: Duration::from_millis(200),
+			..test_config()
+		};
+		assert!(matches!(
+			validate_config(&config, None),
+			Err(ConfigError::SpacingExceedsTimeout { max_delay_ms: 300, .. })
+		));
+	}
+
+	#[test]
+	fn test_low_file_limit_rejected() {
+		let err = validate_config(&test_config(), Some(100)).unwrap_err();
+		assert!(err.to_string().contains("ulimit -n 160"));
+	}
+
+	#[test]
+	fn test_parse_open_file_limit() {
+		let limits = "Limit                     Soft Limit           Hard Limit           Units\n\
+			Max processes             63432                63432                processes\n\
+			Max open files            1024                 524288               files\n";
+		assert_eq!(parse_open_file_limit(limits), Some(1024));
+		let unlimited = "Max open files            unlimited            unlimited            files\n";
+		assert_eq!(parse_open_file_limit(unlimited), None);
+	}
+}